@@ -286,6 +286,25 @@ where
     Ok(())
 }
 
+/// Move a file from one location to another.
+///
+/// This method uses [rename] at first. If [rename] fails because `from` and `to` are on different filesystems,
+/// it will fallback to copying the file and then removing the source file. Other errors are returned as is.
+pub fn move_file<P1, P2>(from: P1, to: P2) -> io::Result<()>
+where
+    P1: AsRef<Path>,
+    P2: AsRef<Path>,
+{
+    match rename(from.as_ref(), to.as_ref()) {
+        Err(e) if is_cross_device_error(&e) => {
+            debug!("Failed to rename across filesystems. Copying instead.");
+            copy(from.as_ref(), to.as_ref())?;
+            fs::remove_file(from.as_ref())
+        }
+        result => result,
+    }
+}
+
 /// Checks raw os error code of `error`.
 ///
 /// Returns true if the code is [`EXDEV`](https://github.com/rust-lang/rust/blob/master/library/std/src/sys/unix/mod.rs#L284) on unix
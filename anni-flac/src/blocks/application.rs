@@ -4,6 +4,13 @@ use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use std::fmt;
 use std::io::{Read, Write};
 
+/// Application ID of RIFF chunks stored by `flac --keep-foreign-metadata`.
+pub const APPLICATION_ID_RIFF: u32 = u32::from_be_bytes(*b"riff");
+/// Application ID of AIFF chunks stored by `flac --keep-foreign-metadata`.
+pub const APPLICATION_ID_AIFF: u32 = u32::from_be_bytes(*b"aiff");
+/// Application ID of Wave64 chunks stored by `flac --keep-foreign-metadata`.
+pub const APPLICATION_ID_W64: u32 = u32::from_be_bytes(*b"w64 ");
+
 pub struct BlockApplication {
     /// Registered application ID.
     /// (Visit the [registration page](https://xiph.org/flac/id.html) to register an ID with FLAC.)
@@ -12,6 +19,25 @@ pub struct BlockApplication {
     pub data: Vec<u8>,
 }
 
+impl BlockApplication {
+    pub fn new(application_id: u32, data: Vec<u8>) -> Self {
+        Self {
+            application_id,
+            data,
+        }
+    }
+
+    /// Application ID as 4 ASCII characters, or `None` if any of them is not printable.
+    pub fn application_id_str(&self) -> Option<String> {
+        let id = self.application_id.to_be_bytes();
+        if id.iter().all(|c| c.is_ascii_graphic() || *c == b' ') {
+            Some(String::from_utf8_lossy(&id).to_string())
+        } else {
+            None
+        }
+    }
+}
+
 impl Decode for BlockApplication {
     fn from_reader<R: Read>(reader: &mut R) -> Result<Self> {
         Ok(BlockApplication {
@@ -51,12 +77,33 @@ impl fmt::Debug for BlockApplication {
         }
         writeln!(
             f,
-            "{prefix}application ID: {:x}",
+            "{prefix}application ID: {:08x}",
             self.application_id,
             prefix = prefix
         )?;
         writeln!(f, "{prefix}data contents:", prefix = prefix)?;
-        // TODO: hexdump
-        writeln!(f, "{prefix}<TODO>", prefix = prefix)
+        hexdump(f, &format!("{prefix}  "), &self.data)
+    }
+}
+
+/// Print `data` in the same format as `metaflac --application-data-format=hexdump`.
+///
+/// Each line contains 16 bytes. Missing bytes of the last line are printed as `00` in hex, and spaces in ASCII.
+fn hexdump(f: &mut fmt::Formatter<'_>, prefix: &str, data: &[u8]) -> fmt::Result {
+    for (i, line) in data.chunks(16).enumerate() {
+        write!(f, "{prefix}{:08X}: ", i * 16)?;
+        for j in 0..16 {
+            write!(f, "{:02X} ", line.get(j).copied().unwrap_or(0))?;
+        }
+        for j in 0..16 {
+            let c = match line.get(j) {
+                Some(&c) if c.is_ascii_graphic() || c == b' ' => c as char,
+                Some(_) => '.',
+                None => ' ',
+            };
+            write!(f, "{c}")?;
+        }
+        writeln!(f)?;
     }
+    Ok(())
 }
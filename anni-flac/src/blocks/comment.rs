@@ -52,6 +52,40 @@ impl BlockVorbisComment {
         self.comments.clear()
     }
 
+    /// Replace all comments with `key` by a single `key=value` comment.
+    ///
+    /// Keys are compared case-insensitively. The new comment takes the place of the first matching comment,
+    /// or is appended if no comment matches.
+    pub fn set(&mut self, key: &str, value: &str) {
+        let comment = UserComment::new(format!("{key}={value}"));
+        match self
+            .comments
+            .iter()
+            .position(|c| c.key_raw().eq_ignore_ascii_case(key))
+        {
+            Some(index) => {
+                self.comments[index] = comment;
+                let mut i = 0;
+                self.comments.retain(|c| {
+                    let keep = i <= index || !c.key_raw().eq_ignore_ascii_case(key);
+                    i += 1;
+                    keep
+                });
+            }
+            None => self.push(comment),
+        }
+    }
+
+    /// Remove all comments with `key`, compared case-insensitively.
+    ///
+    /// Returns the number of removed comments.
+    pub fn remove(&mut self, key: &str) -> usize {
+        let len = self.len();
+        self.comments
+            .retain(|c| !c.key_raw().eq_ignore_ascii_case(key));
+        len - self.len()
+    }
+
     pub fn to_map(&self) -> HashMap<String, &UserComment> {
         let mut map: HashMap<_, _> = Default::default();
         for comment in self.comments.iter() {
@@ -1,3 +1,4 @@
+use crate::error::FlacError;
 use crate::prelude::*;
 use crate::utils::*;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
@@ -34,11 +35,49 @@ pub struct BlockCueSheet {
     pub tracks: Vec<CueSheetTrack>,
 }
 
+impl BlockCueSheet {
+    /// Create a cue sheet with `tracks`, and a lead-out track at `lead_out_offset`.
+    ///
+    /// `lead_out_offset` is usually the total samples of the stream.
+    /// Lead-out track number is 170 for CD-DA, and 255 otherwise.
+    pub fn new(is_cd: bool, mut tracks: Vec<CueSheetTrack>, lead_out_offset: u64) -> Self {
+        tracks.push(CueSheetTrack {
+            track_offset: lead_out_offset,
+            track_number: if is_cd { 170 } else { 255 },
+            isrc: [0; 12],
+            is_audio: true,
+            is_pre_emphasis: false,
+            index_point_number: 0,
+            track_index: Vec::new(),
+        });
+
+        Self {
+            catalog: String::new(),
+            leadin_samples: if is_cd { 88200 } else { 0 },
+            is_cd,
+            track_number: tracks.len() as u8,
+            tracks,
+        }
+    }
+
+    /// Set media catalog number.
+    pub fn with_catalog<S: Into<String>>(mut self, catalog: S) -> Self {
+        self.catalog = catalog.into();
+        self
+    }
+
+    /// Set the number of lead-in samples, which defaults to 2 seconds for CD-DA.
+    pub fn with_leadin_samples(mut self, leadin_samples: u64) -> Self {
+        self.leadin_samples = leadin_samples;
+        self
+    }
+}
+
 impl Decode for BlockCueSheet {
     fn from_reader<R: Read>(reader: &mut R) -> Result<Self> {
-        let catalog_number = take_string(reader, 128)?;
+        let catalog_number = take_string(reader, 128)?.trim_end_matches('\0').to_string();
         let leadin_samples = reader.read_u64::<BigEndian>()?;
-        let is_cd = reader.read_u8()? > 0;
+        let is_cd = (reader.read_u8()? & 0b10000000) > 0;
         skip(reader, 258)?;
         let track_number = reader.read_u8()?;
         let mut tracks = Vec::with_capacity(track_number as usize);
@@ -62,9 +101,12 @@ impl AsyncDecode for BlockCueSheet {
     where
         R: AsyncRead + Unpin + Send,
     {
-        let catalog_number = take_string_async(reader, 128).await?;
+        let catalog_number = take_string_async(reader, 128)
+            .await?
+            .trim_end_matches('\0')
+            .to_string();
         let leadin_samples = reader.read_u64().await?;
-        let is_cd = reader.read_u8().await? > 0;
+        let is_cd = (reader.read_u8().await? & 0b10000000) > 0;
         skip_async(reader, 258).await?;
         let track_number = reader.read_u8().await?;
         let mut tracks = Vec::with_capacity(track_number as usize);
@@ -83,6 +125,15 @@ impl AsyncDecode for BlockCueSheet {
 
 impl Encode for BlockCueSheet {
     fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        if self.catalog.len() > 128 {
+            return Err(FlacError::InvalidCueSheet(
+                "catalog is longer than 128 bytes",
+            ));
+        }
+        if self.tracks.len() != self.track_number as usize {
+            return Err(FlacError::InvalidCueSheet("track number mismatch"));
+        }
+
         let padding = 128 - self.catalog.len();
         writer.write_all(self.catalog.as_bytes())?;
         writer.write_all(&vec![0u8; padding])?;
@@ -171,6 +222,53 @@ pub struct CueSheetTrack {
     pub track_index: Vec<CueSheetTrackIndex>,
 }
 
+impl CueSheetTrack {
+    /// Create an audio track starting at `track_offset`, with a single `INDEX 01` at the beginning of track.
+    pub fn new(track_number: u8, track_offset: u64) -> Self {
+        Self {
+            track_offset,
+            track_number,
+            isrc: [0; 12],
+            is_audio: true,
+            is_pre_emphasis: false,
+            index_point_number: 1,
+            track_index: vec![CueSheetTrackIndex {
+                sample_offset: 0,
+                index_point: 1,
+            }],
+        }
+    }
+
+    /// Set track ISRC.
+    pub fn with_isrc(mut self, isrc: [u8; 12]) -> Self {
+        self.isrc = isrc;
+        self
+    }
+
+    /// Add a pre-gap of `samples` as `INDEX 00`.
+    ///
+    /// Track offset is moved to the beginning of the pre-gap, so `INDEX 01` stays at the same position.
+    /// Returns an error if the pre-gap starts before the beginning of the stream.
+    pub fn with_pregap(mut self, samples: u64) -> Result<Self> {
+        self.track_offset = self
+            .track_offset
+            .checked_sub(samples)
+            .ok_or(FlacError::InvalidCueSheet("pre-gap before stream start"))?;
+        for index in self.track_index.iter_mut() {
+            index.sample_offset += samples;
+        }
+        self.track_index.insert(
+            0,
+            CueSheetTrackIndex {
+                sample_offset: 0,
+                index_point: 0,
+            },
+        );
+        self.index_point_number += 1;
+        Ok(self)
+    }
+}
+
 impl Decode for CueSheetTrack {
     fn from_reader<R: Read>(reader: &mut R) -> Result<Self> {
         let track_offset = reader.read_u64::<BigEndian>()?;
@@ -179,7 +277,7 @@ impl Decode for CueSheetTrack {
         reader.read_exact(&mut isrc)?;
 
         let b = reader.read_u8()?;
-        let is_audio = (b & 0b10000000) > 0;
+        let is_audio = (b & 0b10000000) == 0;
         let is_pre_emphasis = (b & 0b01000000) > 0;
         skip(reader, 13)?;
 
@@ -214,7 +312,7 @@ impl AsyncDecode for CueSheetTrack {
         reader.read_exact(&mut isrc).await?;
 
         let b = reader.read_u8().await?;
-        let is_audio = (b & 0b10000000) > 0;
+        let is_audio = (b & 0b10000000) == 0;
         let is_pre_emphasis = (b & 0b01000000) > 0;
         skip_async(reader, 13).await?;
 
@@ -242,7 +340,11 @@ impl Encode for CueSheetTrack {
         writer.write_u8(self.track_number)?;
         writer.write_all(&self.isrc)?;
 
-        let b = if self.is_audio { 0b10000000 } else { 0 }
+        if self.track_index.len() != self.index_point_number as usize {
+            return Err(FlacError::InvalidCueSheet("index point number mismatch"));
+        }
+
+        let b = if self.is_audio { 0 } else { 0b10000000 }
             + if self.is_pre_emphasis { 0b01000000 } else { 0 };
         writer.write_u8(b)?;
         writer.write_all(&[0; 13])?;
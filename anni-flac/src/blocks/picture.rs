@@ -2,13 +2,13 @@ use crate::error::FlacError;
 use crate::prelude::*;
 use crate::utils::*;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
-use std::borrow::Cow;
 use std::fmt;
 use std::fmt::Display;
 use std::io::{Read, Write};
 use std::path::Path;
 use std::str::FromStr;
 
+#[derive(Clone)]
 pub struct BlockPicture {
     /// <32> The picture type according to the ID3v2 APIC frame
     /// Others are reserved and should not be used.
@@ -164,37 +164,94 @@ impl fmt::Debug for BlockPicture {
 }
 
 impl BlockPicture {
+    /// Create a picture from image file at `file`.
+    ///
+    /// MIME type, dimensions and color depth are detected from content of the file.
     pub fn new<P: AsRef<Path>>(
         file: P,
         picture_type: PictureType,
         description: String,
     ) -> Result<Self> {
-        let img = image::open(file.as_ref())?;
-        let mut data = Vec::new();
-        std::fs::File::open(file.as_ref())?.read_to_end(&mut data)?;
-
-        let mut ext = file.as_ref().extension().unwrap().to_string_lossy();
-        if ext == "jpg" {
-            ext = Cow::Borrowed("jpeg");
-        }
+        let data = std::fs::read(file.as_ref())?;
+        let format = image::guess_format(&data)?;
+        let img = image::load_from_memory_with_format(&data, format)?;
+        let mime_type = match format {
+            image::ImageFormat::Jpeg => "image/jpeg".to_string(),
+            format => format!("image/{}", format.extensions_str()[0]),
+        };
 
         Ok(Self {
             picture_type,
-            mime_type: format!("image/{}", ext),
+            mime_type,
             description,
             width: img.width(),
             height: img.height(),
             depth: img.color().bits_per_pixel() as u32,
-            colors: 0, // TODO: support format with indexed-color support
+            colors: indexed_colors(format, &data),
             data,
         })
     }
 
+    /// Convert the picture to JPEG which fits in `max_size` x `max_size`, keeping its aspect ratio.
+    ///
+    /// Returns `None` if the picture is already a JPEG image within the size.
+    pub fn to_jpeg(&self, max_size: u32) -> Result<Option<Self>> {
+        if self.mime_type == "image/jpeg" && self.width <= max_size && self.height <= max_size {
+            return Ok(None);
+        }
+
+        let mut img = image::load_from_memory(&self.data)?;
+        if img.width() > max_size || img.height() > max_size {
+            img = img.resize(max_size, max_size, image::imageops::FilterType::Lanczos3);
+        }
+        let img = image::DynamicImage::ImageRgb8(img.to_rgb8());
+        let mut data = Vec::new();
+        img.write_to(
+            &mut std::io::Cursor::new(&mut data),
+            image::ImageOutputFormat::Jpeg(90),
+        )?;
+
+        Ok(Some(Self {
+            picture_type: self.picture_type,
+            mime_type: "image/jpeg".to_string(),
+            description: self.description.clone(),
+            width: img.width(),
+            height: img.height(),
+            depth: img.color().bits_per_pixel() as u32,
+            colors: 0,
+            data,
+        }))
+    }
+
     pub fn color_indexed(&self) -> bool {
         self.colors != 0
     }
 }
 
+/// Number of colors in palette of indexed-color images, or 0 for non-indexed images.
+fn indexed_colors(format: image::ImageFormat, data: &[u8]) -> u32 {
+    match format {
+        // global color table flag and size are stored in the logical screen descriptor
+        image::ImageFormat::Gif => match data.get(10) {
+            Some(flags) if flags & 0x80 != 0 => 2 << (flags & 0x07),
+            _ => 0,
+        },
+        // color type 3 in IHDR means indexed-color, and PLTE chunk holds 3 bytes per color
+        image::ImageFormat::Png if data.get(25) == Some(&3) => {
+            let mut offset = 8;
+            while let Some(chunk) = data.get(offset..offset + 8) {
+                let length = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+                if &chunk[4..] == b"PLTE" {
+                    return length / 3;
+                }
+                offset += 12 + length as usize;
+            }
+            0
+        }
+        _ => 0,
+    }
+}
+
 /// The picture type according to the ID3v2 APIC frame:
 /// Others are reserved and should not be used. There may only be one each of picture type 1 and 2 in a file.
 #[repr(u32)]
@@ -13,6 +13,30 @@ pub enum FlacError {
     InvalidSeekTableSize,
     #[error("invalid picture type")]
     InvalidPictureType,
+    #[error("invalid cue sheet: {0}")]
+    InvalidCueSheet(&'static str),
+    #[error("not enough space to write header in place")]
+    InsufficientSpace,
+    #[error("invalid frame sync code")]
+    InvalidSyncCode,
+    #[error("invalid frame header: {0}")]
+    InvalidFrameHeader(&'static str),
+    #[error("invalid subframe: {0}")]
+    InvalidSubFrame(&'static str),
+    #[error("frame header crc mismatch, expected {expected:#04x}, got {actual:#04x}")]
+    FrameHeaderCrcMismatch { expected: u8, actual: u8 },
+    #[error("frame crc mismatch, expected {expected:#06x}, got {actual:#06x}")]
+    FrameCrcMismatch { expected: u16, actual: u16 },
+    #[error("corrupt frame {frame} at byte {offset}, after {samples} valid samples: {source}")]
+    CorruptFrame {
+        /// Index of the corrupt frame
+        frame: usize,
+        /// Byte offset of the corrupt frame
+        offset: u64,
+        /// Number of inter-channel samples decoded before the corrupt frame
+        samples: u64,
+        source: Box<FlacError>,
+    },
     #[error(transparent)]
     InvalidString(#[from] FromUtf8Error),
     #[error(transparent)]
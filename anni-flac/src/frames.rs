@@ -1,3 +1,9 @@
+use crate::blocks::BlockStreamInfo;
+use crate::error::FlacError;
+use crate::prelude::*;
+use crate::utils::BitReader;
+use std::io::Read;
+
 #[derive(Debug)]
 pub enum Frames {
     Parsed(Vec<Frame>),
@@ -90,7 +96,7 @@ pub struct FrameHeader {
     pub crc: u8,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BlockStrategy {
     /// <8-48>:"UTF-8" coded frame number (decoded number is 31 bits)
     Fixed(u32),
@@ -98,7 +104,7 @@ pub enum BlockStrategy {
     Variable(u64),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SampleRate {
     Inherit,
     Rate88200,
@@ -115,8 +121,9 @@ pub enum SampleRate {
     Custom(u64),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ChannelAssignment {
+    /// Number of independent channels
     Independent(u8),
     LeftSide,
     RightSide,
@@ -149,7 +156,7 @@ pub enum SubframeType {
     Fixed(SubFrameFixed),
     LPC(SubFrameLPC),
     /// <n*i> Unencoded subblock; n = frame's bits-per-sample, i = frame's blocksize.
-    Verbatim(Vec<u8>),
+    Verbatim(Vec<i32>),
 }
 
 #[derive(Debug)]
@@ -166,11 +173,13 @@ pub struct SubFrameLPC {
     /// <n> Unencoded warm-up samples (n = frame's bits-per-sample * lpc order).
     pub warm_up: Vec<i32>,
     /// <4> (Quantized linear predictor coefficients' precision in bits)-1 (1111 = invalid).
+    ///
+    /// The precision itself is stored here.
     pub qlp_coeff_prediction: u8,
     /// <5> Quantized linear predictor coefficient shift needed in bits (NOTE: this number is signed two's-complement).
-    pub qlp_shift: u8,
+    pub qlp_shift: i8,
     /// <n> Unencoded predictor coefficients (n = qlp coeff precision * lpc order) (NOTE: the coefficients are signed two's-complement).
-    pub qlp_coeff: Vec<u8>,
+    pub qlp_coeff: Vec<i32>,
     /// Encoded residual
     pub residual: Residual,
 }
@@ -206,7 +215,9 @@ pub struct RicePartition {
     /// - if the partition order is zero, n = frame's blocksize - predictor order
     /// - else if this is not the first partition of the subframe, n = (frame's blocksize / (2^partition order))
     /// - else n = (frame's blocksize / (2^partition order)) - predictor order
-    pub encoded_residual: Vec<u8>,
+    ///
+    /// Residuals are stored decoded.
+    pub residual: Vec<i32>,
 }
 
 #[derive(Debug)]
@@ -223,3 +234,522 @@ pub enum RiceParameter {
     /// n is stored
     Escape(u8),
 }
+
+impl Frames {
+    /// Parse all frames until the end of `reader`.
+    ///
+    /// `reader` must be positioned at the first frame, which is right after the metadata blocks.
+    ///
+    /// Returns [FlacError::CorruptFrame] if any frame is invalid. Use [FrameReader] to keep frames before it.
+    pub fn parse<R: Read>(reader: &mut R, stream_info: &BlockStreamInfo) -> Result<Frames> {
+        let frames = FrameReader::new(reader, stream_info).collect::<Result<_>>()?;
+        Ok(Frames::Parsed(frames))
+    }
+}
+
+/// Iterator over audio frames.
+///
+/// Frames are yielded until the end of stream. If a frame is corrupt or truncated,
+/// [FlacError::CorruptFrame] is yielded as the last item, which carries the position of the
+/// corrupt frame and the number of samples decoded before it.
+pub struct FrameReader<R: Read> {
+    reader: BitReader<R>,
+    bits_per_sample: u8,
+    /// Byte offset of the first frame
+    offset: u64,
+    /// Index of the next frame
+    frame: usize,
+    /// Samples in valid frames
+    samples: u64,
+    finished: bool,
+}
+
+impl<R: Read> FrameReader<R> {
+    /// `reader` must be positioned at the first frame, which is right after the metadata blocks.
+    pub fn new(reader: R, stream_info: &BlockStreamInfo) -> Self {
+        Self {
+            reader: BitReader::new(reader),
+            bits_per_sample: stream_info.bits_per_sample,
+            offset: 0,
+            frame: 0,
+            samples: 0,
+            finished: false,
+        }
+    }
+
+    /// Set byte offset of the first frame, so that error offsets are relative to the beginning of file.
+    pub fn with_offset(mut self, offset: u64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Number of inter-channel samples in frames yielded.
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    /// Byte offset of the next frame.
+    pub fn offset(&self) -> u64 {
+        self.offset + self.reader.position()
+    }
+}
+
+impl<R: Read> Iterator for FrameReader<R> {
+    type Item = Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let offset = self.offset();
+        self.reader.reset_crc();
+        let result = match self.reader.is_eof() {
+            Ok(true) => {
+                self.finished = true;
+                return None;
+            }
+            Ok(false) => Frame::parse(&mut self.reader, self.bits_per_sample),
+            Err(e) => Err(e.into()),
+        };
+
+        match result {
+            Ok(frame) => {
+                self.frame += 1;
+                self.samples += frame.header.block_size as u64;
+                Some(Ok(frame))
+            }
+            Err(e) => {
+                self.finished = true;
+                Some(Err(FlacError::CorruptFrame {
+                    frame: self.frame,
+                    offset,
+                    samples: self.samples,
+                    source: Box::new(e),
+                }))
+            }
+        }
+    }
+}
+
+impl Frame {
+    /// Parse a single frame from `reader`.
+    pub fn from_reader<R: Read>(reader: &mut R, stream_info: &BlockStreamInfo) -> Result<Frame> {
+        Frame::parse(&mut BitReader::new(reader), stream_info.bits_per_sample)
+    }
+
+    fn parse<R: Read>(reader: &mut BitReader<R>, stream_bits_per_sample: u8) -> Result<Frame> {
+        let header = FrameHeader::parse(reader)?;
+        let bits_per_sample = header.sample_size.unwrap_or(stream_bits_per_sample) as u32;
+        let block_size = header.block_size as usize;
+
+        let channels = header.channel_assignment.channels();
+        let mut subframes = Vec::with_capacity(channels);
+        for channel in 0..channels {
+            // side channel has one extra bit
+            let bits_per_sample = match (header.channel_assignment, channel) {
+                (ChannelAssignment::LeftSide, 1)
+                | (ChannelAssignment::RightSide, 0)
+                | (ChannelAssignment::MidSide, 1) => bits_per_sample + 1,
+                _ => bits_per_sample,
+            };
+            subframes.push(SubFrame::parse(reader, bits_per_sample, block_size)?);
+        }
+
+        // FRAME_FOOTER
+        reader.align();
+        let actual = reader.crc16();
+        let crc = reader.read_bits(16)? as u16;
+        if crc != actual {
+            return Err(FlacError::FrameCrcMismatch {
+                expected: crc,
+                actual,
+            });
+        }
+
+        Ok(Frame {
+            header,
+            subframes,
+            crc,
+        })
+    }
+
+    /// Decode samples of this frame, one [Vec] per channel.
+    ///
+    /// Inter-channel decorrelation is applied, so the result contains samples of original channels.
+    pub fn decode(&self) -> Vec<Vec<i32>> {
+        let block_size = self.header.block_size as usize;
+        let mut channels: Vec<_> = self
+            .subframes
+            .iter()
+            .map(|subframe| subframe.decode(block_size))
+            .collect();
+
+        match self.header.channel_assignment {
+            ChannelAssignment::LeftSide => {
+                let (left, side) = channels.split_at_mut(1);
+                for (left, side) in left[0].iter().zip(side[0].iter_mut()) {
+                    *side = left.wrapping_sub(*side);
+                }
+            }
+            ChannelAssignment::RightSide => {
+                let (side, right) = channels.split_at_mut(1);
+                for (side, right) in side[0].iter_mut().zip(right[0].iter()) {
+                    *side = side.wrapping_add(*right);
+                }
+            }
+            ChannelAssignment::MidSide => {
+                let (mid, side) = channels.split_at_mut(1);
+                for (mid, side) in mid[0].iter_mut().zip(side[0].iter_mut()) {
+                    let m = ((*mid as i64) << 1) | (*side as i64 & 1);
+                    let s = *side as i64;
+                    *mid = ((m + s) >> 1) as i32;
+                    *side = ((m - s) >> 1) as i32;
+                }
+            }
+            ChannelAssignment::Independent(_) | ChannelAssignment::Reserved(_) => {}
+        }
+        channels
+    }
+}
+
+impl FrameHeader {
+    fn parse<R: Read>(reader: &mut BitReader<R>) -> Result<FrameHeader> {
+        if reader.read_bits(14)? != 0b11111111111110 {
+            return Err(FlacError::InvalidSyncCode);
+        }
+        let reserved = reader.read_bit()?;
+        let variable_block_size = reader.read_bit()?;
+        let block_size_bits = reader.read_bits(4)?;
+        let sample_rate_bits = reader.read_bits(4)?;
+        let channel_assignment = match reader.read_bits(4)? as u8 {
+            n @ 0b0000..=0b0111 => ChannelAssignment::Independent(n + 1),
+            0b1000 => ChannelAssignment::LeftSide,
+            0b1001 => ChannelAssignment::RightSide,
+            0b1010 => ChannelAssignment::MidSide,
+            _ => return Err(FlacError::InvalidFrameHeader("reserved channel assignment")),
+        };
+        let sample_size = match reader.read_bits(3)? {
+            0b000 => None,
+            0b001 => Some(8),
+            0b010 => Some(12),
+            0b100 => Some(16),
+            0b101 => Some(20),
+            0b110 => Some(24),
+            _ => return Err(FlacError::InvalidFrameHeader("reserved sample size")),
+        };
+        if reader.read_bit()? {
+            return Err(FlacError::InvalidFrameHeader("reserved bit is not zero"));
+        }
+
+        let block_strategy = if variable_block_size {
+            BlockStrategy::Variable(read_utf8_number(reader, 7)?)
+        } else {
+            BlockStrategy::Fixed(read_utf8_number(reader, 6)? as u32)
+        };
+
+        let block_size = match block_size_bits {
+            0b0000 => return Err(FlacError::InvalidFrameHeader("reserved block size")),
+            0b0001 => 192,
+            n @ 0b0010..=0b0101 => 576 << (n - 2),
+            0b0110 => reader.read_bits(8)? + 1,
+            0b0111 => reader.read_bits(16)? + 1,
+            n => 256 << (n - 8),
+        };
+        if block_size > u16::MAX as u32 {
+            return Err(FlacError::InvalidFrameHeader("block size too large"));
+        }
+
+        let sample_rate = match sample_rate_bits {
+            0b0000 => SampleRate::Inherit,
+            0b0001 => SampleRate::Rate88200,
+            0b0010 => SampleRate::Rate176400,
+            0b0011 => SampleRate::Rate192000,
+            0b0100 => SampleRate::Rate8000,
+            0b0101 => SampleRate::Rate16000,
+            0b0110 => SampleRate::Rate22050,
+            0b0111 => SampleRate::Rate24000,
+            0b1000 => SampleRate::Rate32000,
+            0b1001 => SampleRate::Rate44100,
+            0b1010 => SampleRate::Rate48000,
+            0b1011 => SampleRate::Rate96000,
+            0b1100 => SampleRate::Custom(reader.read_bits(8)? as u64 * 1000),
+            0b1101 => SampleRate::Custom(reader.read_bits(16)? as u64),
+            0b1110 => SampleRate::Custom(reader.read_bits(16)? as u64 * 10),
+            _ => return Err(FlacError::InvalidFrameHeader("invalid sample rate")),
+        };
+
+        let actual = reader.crc8();
+        let crc = reader.read_bits(8)? as u8;
+        if crc != actual {
+            return Err(FlacError::FrameHeaderCrcMismatch {
+                expected: crc,
+                actual,
+            });
+        }
+
+        Ok(FrameHeader {
+            reserved,
+            block_strategy,
+            block_size: block_size as u16,
+            sample_rate,
+            channel_assignment,
+            sample_size,
+            crc,
+        })
+    }
+}
+
+/// Read "UTF-8" coded number with at most `max_bytes` bytes.
+fn read_utf8_number<R: Read>(reader: &mut BitReader<R>, max_bytes: u32) -> Result<u64> {
+    let first = reader.read_bits(8)?;
+    let bytes = (first << 24).leading_ones();
+    let mut value = match bytes {
+        0 => return Ok(first as u64),
+        1 => return Err(FlacError::InvalidFrameHeader("invalid utf-8 coded number")),
+        n if n > max_bytes => {
+            return Err(FlacError::InvalidFrameHeader(
+                "utf-8 coded number too large",
+            ))
+        }
+        n => (first & (0xff >> (n + 1))) as u64,
+    };
+    for _ in 1..bytes {
+        let byte = reader.read_bits(8)?;
+        if byte & 0b11000000 != 0b10000000 {
+            return Err(FlacError::InvalidFrameHeader("invalid utf-8 coded number"));
+        }
+        value = (value << 6) | (byte & 0b00111111) as u64;
+    }
+    Ok(value)
+}
+
+impl ChannelAssignment {
+    /// Number of channels, which is also the number of subframes in a frame.
+    pub fn channels(&self) -> usize {
+        match self {
+            ChannelAssignment::Independent(n) => *n as usize,
+            ChannelAssignment::LeftSide
+            | ChannelAssignment::RightSide
+            | ChannelAssignment::MidSide => 2,
+            ChannelAssignment::Reserved(_) => 0,
+        }
+    }
+}
+
+impl SubFrame {
+    fn parse<R: Read>(
+        reader: &mut BitReader<R>,
+        bits_per_sample: u32,
+        block_size: usize,
+    ) -> Result<SubFrame> {
+        if reader.read_bit()? {
+            return Err(FlacError::InvalidSubFrame("padding bit is not zero"));
+        }
+        let subframe_type = reader.read_bits(6)?;
+        let wasted_bits = if reader.read_bit()? {
+            reader.read_unary()? + 1
+        } else {
+            0
+        };
+        if wasted_bits >= bits_per_sample {
+            return Err(FlacError::InvalidSubFrame("too many wasted bits"));
+        }
+        let bits_per_sample = bits_per_sample - wasted_bits;
+        if bits_per_sample > 32 {
+            return Err(FlacError::InvalidSubFrame("unsupported bits per sample"));
+        }
+
+        let content = match subframe_type {
+            0b000000 => SubframeType::Constant(reader.read_signed(bits_per_sample)?),
+            0b000001 => SubframeType::Verbatim(
+                (0..block_size)
+                    .map(|_| reader.read_signed(bits_per_sample))
+                    .collect::<std::io::Result<_>>()?,
+            ),
+            n @ 0b001000..=0b001100 => {
+                let order = (n & 0b111) as usize;
+                if order > block_size {
+                    return Err(FlacError::InvalidSubFrame("predictor order too large"));
+                }
+                let warm_up = (0..order)
+                    .map(|_| reader.read_signed(bits_per_sample))
+                    .collect::<std::io::Result<_>>()?;
+                SubframeType::Fixed(SubFrameFixed {
+                    warm_up,
+                    residual: Residual::parse(reader, block_size, order)?,
+                })
+            }
+            n @ 0b100000..=0b111111 => {
+                let order = (n & 0b11111) as usize + 1;
+                if order > block_size {
+                    return Err(FlacError::InvalidSubFrame("predictor order too large"));
+                }
+                let warm_up = (0..order)
+                    .map(|_| reader.read_signed(bits_per_sample))
+                    .collect::<std::io::Result<_>>()?;
+                let precision = reader.read_bits(4)? as u8;
+                if precision == 0b1111 {
+                    return Err(FlacError::InvalidSubFrame("invalid qlp coeff precision"));
+                }
+                let precision = precision + 1;
+                let qlp_shift = reader.read_signed(5)? as i8;
+                if qlp_shift < 0 {
+                    return Err(FlacError::InvalidSubFrame("negative qlp shift"));
+                }
+                let qlp_coeff = (0..order)
+                    .map(|_| reader.read_signed(precision as u32))
+                    .collect::<std::io::Result<_>>()?;
+                SubframeType::LPC(SubFrameLPC {
+                    warm_up,
+                    qlp_coeff_prediction: precision,
+                    qlp_shift,
+                    qlp_coeff,
+                    residual: Residual::parse(reader, block_size, order)?,
+                })
+            }
+            _ => return Err(FlacError::InvalidSubFrame("reserved subframe type")),
+        };
+
+        Ok(SubFrame {
+            content,
+            wasted_bits,
+        })
+    }
+
+    /// Decode samples of this subframe.
+    pub fn decode(&self, block_size: usize) -> Vec<i32> {
+        let mut samples = match &self.content {
+            SubframeType::Constant(value) => vec![*value; block_size],
+            SubframeType::Verbatim(samples) => samples.clone(),
+            SubframeType::Fixed(fixed) => {
+                const COEFFICIENTS: [&[i64]; 5] =
+                    [&[], &[1], &[2, -1], &[3, -3, 1], &[4, -6, 4, -1]];
+                let coefficients = COEFFICIENTS[fixed.warm_up.len()];
+                restore_signal(&fixed.warm_up, &fixed.residual, block_size, |history| {
+                    coefficients
+                        .iter()
+                        .zip(history.iter().rev())
+                        .map(|(c, s)| c * *s as i64)
+                        .sum()
+                })
+            }
+            SubframeType::LPC(lpc) => {
+                restore_signal(&lpc.warm_up, &lpc.residual, block_size, |history| {
+                    let prediction: i64 = lpc
+                        .qlp_coeff
+                        .iter()
+                        .zip(history.iter().rev())
+                        .map(|(c, s)| *c as i64 * *s as i64)
+                        .sum();
+                    prediction >> lpc.qlp_shift
+                })
+            }
+        };
+
+        if self.wasted_bits > 0 {
+            for sample in samples.iter_mut() {
+                *sample <<= self.wasted_bits;
+            }
+        }
+        samples
+    }
+}
+
+/// Restore signal from warm-up samples and residuals.
+///
+/// `predict` receives the previous `warm_up.len()` samples, from the oldest to the latest.
+fn restore_signal<F>(
+    warm_up: &[i32],
+    residual: &Residual,
+    block_size: usize,
+    predict: F,
+) -> Vec<i32>
+where
+    F: Fn(&[i32]) -> i64,
+{
+    let order = warm_up.len();
+    let mut samples = Vec::with_capacity(block_size);
+    samples.extend_from_slice(warm_up);
+    for residual in residual.residuals() {
+        let prediction = predict(&samples[samples.len() - order..]);
+        samples.push((prediction + residual as i64) as i32);
+    }
+    samples
+}
+
+impl Residual {
+    fn parse<R: Read>(
+        reader: &mut BitReader<R>,
+        block_size: usize,
+        predictor_order: usize,
+    ) -> Result<Residual> {
+        let (parameter_bits, rice2) = match reader.read_bits(2)? {
+            0b00 => (4, false),
+            0b01 => (5, true),
+            _ => {
+                return Err(FlacError::InvalidSubFrame(
+                    "reserved residual coding method",
+                ))
+            }
+        };
+        let escape = (1 << parameter_bits) - 1;
+
+        let order = reader.read_bits(4)? as u8;
+        let partitions = 1usize << order;
+        if block_size & (partitions - 1) != 0 || block_size / partitions < predictor_order {
+            return Err(FlacError::InvalidSubFrame("invalid partition order"));
+        }
+
+        let mut partitons = Vec::with_capacity(partitions);
+        for i in 0..partitions {
+            let samples = if i == 0 {
+                block_size / partitions - predictor_order
+            } else {
+                block_size / partitions
+            };
+
+            let parameter = reader.read_bits(parameter_bits)?;
+            let (parameter, residual) = if parameter == escape {
+                let bits = reader.read_bits(5)?;
+                let residual = (0..samples)
+                    .map(|_| reader.read_signed(bits))
+                    .collect::<std::io::Result<_>>()?;
+                (RiceParameter::Escape(bits as u8), residual)
+            } else {
+                let residual = (0..samples)
+                    .map(|_| {
+                        let quotient = reader.read_unary()? as u64;
+                        let remainder = reader.read_bits(parameter)? as u64;
+                        let value = (quotient << parameter) | remainder;
+                        // zigzag decode
+                        Ok(((value >> 1) as i64 ^ -((value & 1) as i64)) as i32)
+                    })
+                    .collect::<std::io::Result<_>>()?;
+                (RiceParameter::Parameter(parameter as u8), residual)
+            };
+            partitons.push(RicePartition {
+                parameter,
+                residual,
+            });
+        }
+
+        let rice = ResidualCodingMethodPartitionedRice { order, partitons };
+        Ok(if rice2 {
+            Residual::Rice2(rice)
+        } else {
+            Residual::Rice(rice)
+        })
+    }
+
+    /// Iterate over residuals of all partitions.
+    pub fn residuals(&self) -> impl Iterator<Item = i32> + '_ {
+        let partitions = match self {
+            Residual::Rice(rice) | Residual::Rice2(rice) => rice.partitons.as_slice(),
+            Residual::Reserved(_) => &[],
+        };
+        partitions
+            .iter()
+            .flat_map(|partition| partition.residual.iter().copied())
+    }
+}
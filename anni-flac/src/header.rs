@@ -1,13 +1,53 @@
 use crate::blocks::*;
 use crate::error::FlacError;
+use crate::frames::{FrameReader, Frames};
 use crate::prelude::*;
 use crate::utils::*;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use md5::{Digest, Md5};
 use std::fmt;
 use std::fs::{File, OpenOptions};
-use std::io::{Read, Seek, SeekFrom, Write};
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 
+/// Strategy used by [FlacHeader::save_with] when saving to the original file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SaveStrategy {
+    /// Overwrite header in place if it fits into the original header space,
+    /// otherwise write a new file to a temporary path and rename it to the original file.
+    #[default]
+    Auto,
+    /// Always write a new file to a temporary path and rename it to the original file.
+    ///
+    /// Audio data is never modified in place, which is safer on copy-on-write or network filesystems.
+    AlwaysTemp,
+    /// Only overwrite header in place.
+    ///
+    /// Returns [FlacError::InsufficientSpace] if the header does not fit into the original header space.
+    InPlaceIfFits,
+}
+
+/// Problems fixed by [FlacHeader::repair].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RepairReport {
+    /// Multiple padding blocks were merged, or padding block was moved to the end of header.
+    pub padding_merged: bool,
+    /// `is_last` flag of some blocks were wrong.
+    pub is_last_fixed: bool,
+    /// Seek table did not match audio frames and was rebuilt.
+    pub seek_table_rebuilt: bool,
+}
+
+impl RepairReport {
+    /// Whether the header was healthy, so nothing was changed.
+    pub fn is_healthy(&self) -> bool {
+        !self.padding_merged && !self.is_last_fixed && !self.seek_table_rebuilt
+    }
+}
+
+/// Interval of seek points in seconds when rebuilding seek table, which is the same as the reference encoder.
+const SEEK_POINT_INTERVAL: u64 = 10;
+
 pub struct FlacHeader {
     pub blocks: Vec<MetadataBlock>,
     pub path: PathBuf,
@@ -46,6 +86,14 @@ impl FlacHeader {
         })
     }
 
+    /// Read metadata blocks only, from a forward-only stream.
+    ///
+    /// Reading stops right after the last metadata block, so audio frames are never read.
+    /// This is useful to read tags, cover or duration from remote files without downloading the whole file.
+    pub fn parse_metadata_only<R: Read>(reader: &mut R) -> Result<Vec<MetadataBlock>> {
+        Ok(Self::parse(reader, PathBuf::new())?.blocks)
+    }
+
     #[cfg(feature = "async")]
     pub async fn parse_async<R>(reader: &mut R, path: PathBuf) -> Result<FlacHeader>
     where
@@ -87,6 +135,57 @@ impl FlacHeader {
         Ok(header)
     }
 
+    /// Parse header without trusting `is_last` flags.
+    ///
+    /// The end of header is detected by the sync code of the first frame instead,
+    /// so headers with wrong `is_last` flags can be read and repaired.
+    pub fn parse_lenient<R: Read + Seek>(reader: &mut R, path: PathBuf) -> Result<FlacHeader> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != b"fLaC" {
+            return Err(FlacError::InvalidMagicNumber);
+        }
+
+        let stream_info = MetadataBlock::from_reader(reader)?;
+        match stream_info.data {
+            MetadataBlockData::StreamInfo(_) => {}
+            _ => return Err(FlacError::InvalidFirstBlock),
+        }
+
+        let mut blocks = vec![stream_info];
+        let mut frame_offset = 4 + 4 + 34;
+        loop {
+            let mut next = [0u8; 2];
+            match reader.read_exact(&mut next) {
+                Ok(()) => {}
+                // no audio frame
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e.into()),
+            }
+            reader.seek(SeekFrom::Current(-2))?;
+            // frame sync code, with fixed or variable blocksize
+            if next[0] == 0xff && next[1] & 0b11111110 == 0b11111000 {
+                break;
+            }
+
+            let block = MetadataBlock::from_reader(reader)?;
+            frame_offset += 4 + block.length;
+            blocks.push(block);
+        }
+
+        Ok(FlacHeader {
+            blocks,
+            path,
+            frame_offset,
+        })
+    }
+
+    /// Open file with [FlacHeader::parse_lenient].
+    pub fn from_file_lenient<P: AsRef<Path>>(filename: P) -> Result<FlacHeader> {
+        let mut file = BufReader::new(File::open(filename.as_ref())?);
+        Self::parse_lenient(&mut file, filename.as_ref().to_path_buf())
+    }
+
     pub fn stream_info(&self) -> &BlockStreamInfo {
         let block = self.blocks.get(0).unwrap();
         match &block.data {
@@ -95,6 +194,68 @@ impl FlacHeader {
         }
     }
 
+    /// Compute MD5 signature of decoded audio samples of the file at [FlacHeader::path].
+    ///
+    /// Samples are interleaved and hashed in little-endian, using the minimal bytes to hold `bits_per_sample`.
+    pub fn compute_md5(&self) -> Result<[u8; 16]> {
+        let bytes_per_sample = (self.stream_info().bits_per_sample as usize).div_ceil(8);
+        let mut md5 = Md5::new();
+        let mut buf = Vec::new();
+        for frame in self.frame_reader()? {
+            let frame = frame?;
+            let channels = frame.decode();
+            buf.clear();
+            for i in 0..frame.header.block_size as usize {
+                for channel in channels.iter() {
+                    buf.extend_from_slice(&channel[i].to_le_bytes()[..bytes_per_sample]);
+                }
+            }
+            md5.update(&buf);
+        }
+        Ok(md5.finalize().into())
+    }
+
+    /// Check whether MD5 signature in [BlockStreamInfo] matches decoded audio samples.
+    ///
+    /// Unset signature (all zeros) never matches. Corrupt frames are returned as error.
+    pub fn verify_md5(&self) -> Result<bool> {
+        Ok(self.compute_md5()? == self.stream_info().md5_signature)
+    }
+
+    /// Compute and store MD5 signature if it is unset (all zeros).
+    ///
+    /// Returns whether the signature was updated. Changes are not written until [FlacHeader::save] is called.
+    pub fn fix_md5(&mut self) -> Result<bool> {
+        if self.stream_info().md5_signature != [0; 16] {
+            return Ok(false);
+        }
+
+        let md5 = self.compute_md5()?;
+        match &mut self.blocks[0].data {
+            MetadataBlockData::StreamInfo(info) => info.md5_signature = md5,
+            _ => panic!("First block is not stream info!"),
+        }
+        Ok(true)
+    }
+
+    /// Parse audio frames of the file at [FlacHeader::path].
+    ///
+    /// Frames are read from the original file, so header modifications which are not saved
+    /// do not affect the result.
+    pub fn frames(&self) -> Result<Frames> {
+        let frames = self.frame_reader()?.collect::<Result<_>>()?;
+        Ok(Frames::Parsed(frames))
+    }
+
+    /// Iterate over audio frames of the file at [FlacHeader::path].
+    ///
+    /// Error offsets are relative to the beginning of file.
+    pub fn frame_reader(&self) -> Result<FrameReader<BufReader<File>>> {
+        let mut file = BufReader::new(File::open(&self.path)?);
+        file.seek(SeekFrom::Start(self.frame_offset as u64))?;
+        Ok(FrameReader::new(file, self.stream_info()).with_offset(self.frame_offset as u64))
+    }
+
     fn block_of(&self, id: u8) -> Option<&MetadataBlock> {
         self.blocks
             .iter()
@@ -135,6 +296,94 @@ impl FlacHeader {
             .unwrap()
     }
 
+    /// Get data of the first APPLICATION block with `application_id`.
+    pub fn application(&self, application_id: u32) -> Option<&[u8]> {
+        self.blocks.iter().find_map(|block| match &block.data {
+            MetadataBlockData::Application(a) if a.application_id == application_id => {
+                Some(a.data.as_slice())
+            }
+            _ => None,
+        })
+    }
+
+    /// Set data of the first APPLICATION block with `application_id`.
+    ///
+    /// If such block does not exist, a new block would be appended to header.
+    pub fn set_application(&mut self, application_id: u32, data: Vec<u8>) {
+        let block = self
+            .blocks
+            .iter_mut()
+            .find_map(|block| match &mut block.data {
+                MetadataBlockData::Application(a) if a.application_id == application_id => Some(a),
+                _ => None,
+            });
+        match block {
+            Some(block) => block.data = data,
+            None => self
+                .blocks
+                .push(MetadataBlock::new(MetadataBlockData::Application(
+                    BlockApplication::new(application_id, data),
+                ))),
+        }
+    }
+
+    /// Get all PICTURE blocks.
+    pub fn pictures(&self) -> impl Iterator<Item = &BlockPicture> {
+        self.blocks.iter().filter_map(|block| match &block.data {
+            MetadataBlockData::Picture(p) => Some(p),
+            _ => None,
+        })
+    }
+
+    /// Get the first PICTURE block of front cover.
+    pub fn front_cover(&self) -> Option<&BlockPicture> {
+        self.pictures()
+            .find(|p| p.picture_type == PictureType::CoverFront)
+    }
+
+    /// Replace all PICTURE blocks with `cover` as the only front cover.
+    ///
+    /// The new block takes the place of the first PICTURE block, or is appended to header if there's none.
+    pub fn set_front_cover(&mut self, mut cover: BlockPicture) {
+        cover.picture_type = PictureType::CoverFront;
+        let index = self
+            .blocks
+            .iter()
+            .position(|block| matches!(block.data, MetadataBlockData::Picture(_)));
+        self.strip_pictures();
+
+        let block = MetadataBlock::new(MetadataBlockData::Picture(cover));
+        match index {
+            Some(index) => self.blocks.insert(index, block),
+            None => self.blocks.push(block),
+        }
+    }
+
+    /// Remove all PICTURE blocks except the first front cover, and returns the number of removed blocks.
+    pub fn retain_front_cover(&mut self) -> usize {
+        let count = self.blocks.len();
+        let mut found = false;
+        self.blocks.retain(|block| match &block.data {
+            MetadataBlockData::Picture(p)
+                if p.picture_type == PictureType::CoverFront && !found =>
+            {
+                found = true;
+                true
+            }
+            MetadataBlockData::Picture(_) => false,
+            _ => true,
+        });
+        count - self.blocks.len()
+    }
+
+    /// Remove all PICTURE blocks, and returns the number of removed blocks.
+    pub fn strip_pictures(&mut self) -> usize {
+        let count = self.blocks.len();
+        self.blocks
+            .retain(|block| !matches!(block.data, MetadataBlockData::Picture(_)));
+        count - self.blocks.len()
+    }
+
     fn frame_offset_now(&self) -> usize {
         let mut frame_offset_now = 4;
         for block in self.blocks.iter() {
@@ -143,102 +392,217 @@ impl FlacHeader {
         frame_offset_now
     }
 
+    /// Save header to `output`, or to the original file if `output` is `None`.
+    ///
+    /// This is the same as [FlacHeader::save_with] with [SaveStrategy::Auto].
     pub fn save<P: AsRef<Path>>(&mut self, output: Option<P>) -> Result<()> {
-        let input_path = self.path.to_path_buf();
-        let output_path = match output {
-            Some(p) => p.as_ref().to_path_buf(),
-            None => input_path.clone(),
-        };
+        self.save_with(output, SaveStrategy::Auto)
+    }
 
-        self.format();
-        if input_path != output_path {
-            // save to another file
-            let mut file = File::create(output_path)?;
-
-            // write magic number
-            file.write_all(b"fLaC")?;
-            // write header blocks
-            for block in self.blocks.iter() {
-                block.write_to(&mut file)?;
+    /// Save header with given [SaveStrategy].
+    ///
+    /// `strategy` only takes effect when saving to the original file.
+    /// Saving to another path always writes a complete new file.
+    pub fn save_with<P: AsRef<Path>>(
+        &mut self,
+        output: Option<P>,
+        strategy: SaveStrategy,
+    ) -> Result<()> {
+        match self.prepare_save(output.as_ref().map(|p| p.as_ref()), strategy)? {
+            SavePlan::NewFile(output) => self.write_file(&output),
+            SavePlan::InPlace => {
+                // write back to input directly
+                // so we only need to write header blocks to override the original header
+                let mut file = OpenOptions::new().write(true).open(&self.path)?;
+                file.write_all(&self.header_bytes()?)?;
+                Ok(())
             }
-            // write frames
-            let mut file_input = File::open(input_path)?;
-            file_input.seek(SeekFrom::Start(self.frame_offset as u64))?;
-            std::io::copy(&mut file_input, &mut file)?;
-        } else {
-            // recalculate frame offset after header modify
-            let frame_offset_now = self.frame_offset_now();
-            log::debug!(
-                "frame_offset_now = {}, flac.frame_offset = {}",
-                frame_offset_now,
-                self.frame_offset
-            );
+            SavePlan::Replace => self.replace_file(),
+        }
+    }
 
-            let need_new_file = frame_offset_now > self.frame_offset || {
-                // if header is smaller than / the same size as previous header
-                // means we do not need more space
-                // just need to write all data to the header
-                let space_to_add = self.frame_offset - frame_offset_now;
-
-                // try to get last block for padding
-                let last = self.blocks.last_mut().unwrap();
-                if let MetadataBlockData::Padding(size) = &mut last.data {
-                    // padding block exists, modify padding size directly
-                    last.length += space_to_add;
-                    log::debug!(
-                        "padding.size_original = {}, adjusted to {}",
-                        size,
-                        last.length
-                    );
-                    *size = last.length;
-                    false
-                } else if space_to_add >= 4 {
-                    // padding block does not exist, add a new padding block
-                    let space_to_add = space_to_add - 4;
-                    // make the last block not last
-                    self.blocks.last_mut().unwrap().is_last = false;
-                    // add padding block
-                    self.blocks.push(MetadataBlock {
-                        is_last: true,
-                        length: space_to_add,
-                        data: MetadataBlockData::Padding(space_to_add),
-                    });
-                    false
-                } else {
-                    // a new padding block needs at least 4 bytes
-                    // so if the space left is less than 4 bytes
-                    // padding block can not be created
-                    // we handle this situation as frame_offset_now > frame_offset_old
-                    true
+    /// Save header to `output` asynchronously, or to the original file if `output` is `None`.
+    ///
+    /// This is the same as [FlacHeader::save_with_async] with [SaveStrategy::Auto].
+    #[cfg(feature = "async")]
+    pub async fn save_async<P: AsRef<Path>>(&mut self, output: Option<P>) -> Result<()> {
+        self.save_with_async(output, SaveStrategy::Auto).await
+    }
+
+    /// Save header with given [SaveStrategy] asynchronously.
+    ///
+    /// This is the async version of [FlacHeader::save_with]. When a new file is needed,
+    /// it is written to a unique temporary path next to the original file and renamed to the original file
+    /// after all data is written, so concurrent saves never share temporary files.
+    /// Temporary files are removed on error.
+    #[cfg(feature = "async")]
+    pub async fn save_with_async<P: AsRef<Path>>(
+        &mut self,
+        output: Option<P>,
+        strategy: SaveStrategy,
+    ) -> Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        match self.prepare_save(output.as_ref().map(|p| p.as_ref()), strategy)? {
+            SavePlan::NewFile(output) => self.write_file_async(&output).await,
+            SavePlan::InPlace => {
+                let header = self.header_bytes()?;
+                let mut file = tokio::fs::OpenOptions::new()
+                    .write(true)
+                    .open(&self.path)
+                    .await?;
+                file.write_all(&header).await?;
+                file.flush().await?;
+                Ok(())
+            }
+            SavePlan::Replace => {
+                let output_new_path = unique_temp_path(&self.path, "anni");
+                if let Err(e) = self.write_file_async(&output_new_path).await {
+                    let _ = tokio::fs::remove_file(&output_new_path).await;
+                    return Err(e);
                 }
-            };
-            if need_new_file {
-                // write to filename.anni
-                let output_new_path = output_path.with_extension("anni");
-                self.save(Some(output_new_path.as_path()))?;
-
-                let original_backup_path = output_path.with_extension("anni.bak");
-                // move original file to filename.anni.bak
-                std::fs::rename(&output_path, &original_backup_path)?;
-                // move new file to original file
-                std::fs::rename(output_new_path, output_path)?;
-                // remove backup of original file
-                std::fs::remove_file(original_backup_path)?;
-            } else {
-                // write back to input directly
-                // so we only need to write header blocks to override the original header
-                let mut file = OpenOptions::new().write(true).open(input_path)?;
-                // skip magic number b"fLaC"
-                file.seek(SeekFrom::Start(4))?;
-                // write header blocks
-                for block in self.blocks.iter() {
-                    block.write_to(&mut file)?;
+                // rename replaces the original file atomically
+                if let Err(e) = tokio::fs::rename(&output_new_path, &self.path).await {
+                    let _ = tokio::fs::remove_file(&output_new_path).await;
+                    return Err(e.into());
                 }
+                // frames are placed right after the new header now
+                self.frame_offset = self.frame_offset_now();
+                Ok(())
             }
         }
+    }
+
+    /// Format header, and decide how it should be written by `strategy`.
+    fn prepare_save(&mut self, output: Option<&Path>, strategy: SaveStrategy) -> Result<SavePlan> {
+        self.format();
+        match output {
+            Some(output) if output != self.path => Ok(SavePlan::NewFile(output.to_path_buf())),
+            _ => match strategy {
+                SaveStrategy::Auto if self.fit_in_place() => Ok(SavePlan::InPlace),
+                SaveStrategy::Auto | SaveStrategy::AlwaysTemp => Ok(SavePlan::Replace),
+                SaveStrategy::InPlaceIfFits if self.fit_in_place() => Ok(SavePlan::InPlace),
+                SaveStrategy::InPlaceIfFits => Err(FlacError::InsufficientSpace),
+            },
+        }
+    }
+
+    /// Write a new file to a temporary path, and rename it to the original file.
+    ///
+    /// The temporary file is removed on error, so the original file is never half written.
+    fn replace_file(&mut self) -> Result<()> {
+        let output_new_path = unique_temp_path(&self.path, "anni");
+        if let Err(e) = self.write_file(&output_new_path) {
+            let _ = std::fs::remove_file(&output_new_path);
+            return Err(e);
+        }
+        // rename replaces the original file atomically
+        if let Err(e) = std::fs::rename(&output_new_path, &self.path) {
+            let _ = std::fs::remove_file(&output_new_path);
+            return Err(e.into());
+        }
+        // frames are placed right after the new header now
+        self.frame_offset = self.frame_offset_now();
+        Ok(())
+    }
+
+    /// Write a complete file with current header and frames of the original file to `output`.
+    fn write_file(&self, output: &Path) -> Result<()> {
+        let mut file = File::create(output)?;
+        file.write_all(&self.header_bytes()?)?;
+
+        // write frames
+        let mut file_input = File::open(&self.path)?;
+        file_input.seek(SeekFrom::Start(self.frame_offset as u64))?;
+        std::io::copy(&mut file_input, &mut file)?;
+        Ok(())
+    }
+
+    /// Write a complete file with current header and frames of the original file to `output`.
+    #[cfg(feature = "async")]
+    async fn write_file_async(&self, output: &Path) -> Result<()> {
+        use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+        let header = self.header_bytes()?;
+        let mut file = tokio::fs::File::create(output).await?;
+        file.write_all(&header).await?;
+
+        // write frames
+        let mut file_input = tokio::fs::File::open(&self.path).await?;
+        file_input
+            .seek(SeekFrom::Start(self.frame_offset as u64))
+            .await?;
+        tokio::io::copy(&mut file_input, &mut file).await?;
+        // tokio writes files in background, make sure all data is written before renaming
+        file.flush().await?;
         Ok(())
     }
 
+    /// Magic number and all header blocks.
+    fn header_bytes(&self) -> Result<Vec<u8>> {
+        let mut header = Vec::with_capacity(self.frame_offset_now());
+        header.extend_from_slice(b"fLaC");
+        for block in self.blocks.iter() {
+            block.write_to(&mut header)?;
+        }
+        Ok(header)
+    }
+
+    /// Try to fit current header into the space of original header.
+    ///
+    /// Padding block would be resized or inserted to fill the remaining space.
+    /// Returns `false` if a new file is needed.
+    fn fit_in_place(&mut self) -> bool {
+        // recalculate frame offset after header modify
+        let frame_offset_now = self.frame_offset_now();
+        log::debug!(
+            "frame_offset_now = {}, flac.frame_offset = {}",
+            frame_offset_now,
+            self.frame_offset
+        );
+
+        if frame_offset_now > self.frame_offset {
+            return false;
+        }
+
+        // if header is smaller than / the same size as previous header
+        // means we do not need more space
+        // just need to write all data to the header
+        let space_to_add = self.frame_offset - frame_offset_now;
+
+        // try to get last block for padding
+        let last = self.blocks.last_mut().unwrap();
+        if let MetadataBlockData::Padding(size) = &mut last.data {
+            // padding block exists, modify padding size directly
+            last.length += space_to_add;
+            log::debug!(
+                "padding.size_original = {}, adjusted to {}",
+                size,
+                last.length
+            );
+            *size = last.length;
+            true
+        } else if space_to_add >= 4 {
+            // padding block does not exist, add a new padding block
+            let space_to_add = space_to_add - 4;
+            // make the last block not last
+            self.blocks.last_mut().unwrap().is_last = false;
+            // add padding block
+            self.blocks.push(MetadataBlock {
+                is_last: true,
+                length: space_to_add,
+                data: MetadataBlockData::Padding(space_to_add),
+            });
+            true
+        } else {
+            // a new padding block needs at least 4 bytes
+            // so if the space left is less than 4 bytes
+            // padding block can not be created
+            // we handle this situation as frame_offset_now > frame_offset_old
+            false
+        }
+    }
+
     // TODO: make this method private
     pub fn format(&mut self) {
         // recalculate frame offset after header modify
@@ -295,6 +659,129 @@ impl FlacHeader {
         self.fix_is_last()
     }
 
+    /// Normalize header blocks, and save the file atomically if anything was changed.
+    ///
+    /// - multiple padding blocks are merged into one at the end of header
+    /// - `is_last` flags are fixed
+    /// - seek table is rebuilt if it does not match audio frames
+    ///
+    /// Audio frames are never modified. Use [FlacHeader::from_file_lenient] to open files with wrong `is_last` flags.
+    pub fn repair(&mut self) -> Result<RepairReport> {
+        let last = self.blocks.len() - 1;
+        let paddings: Vec<_> = self
+            .blocks
+            .iter()
+            .enumerate()
+            .filter(|(_, block)| matches!(block.data, MetadataBlockData::Padding(_)))
+            .map(|(index, _)| index)
+            .collect();
+
+        let mut report = RepairReport {
+            padding_merged: paddings.len() > 1 || paddings.first().is_some_and(|i| *i != last),
+            is_last_fixed: self
+                .blocks
+                .iter()
+                .enumerate()
+                .any(|(index, block)| block.is_last != (index == last)),
+            seek_table_rebuilt: false,
+        };
+
+        if let Some(seek_points) = self.rebuild_seek_table()? {
+            let block = self.block_of_mut(3).unwrap();
+            block.data = MetadataBlockData::SeekTable(BlockSeekTable { seek_points });
+            report.seek_table_rebuilt = true;
+        }
+
+        if !report.is_healthy() {
+            self.merge_padding();
+            self.fix_is_last();
+            self.replace_file()?;
+        }
+        Ok(report)
+    }
+
+    /// Merge all padding blocks into one at the end of header.
+    ///
+    /// Headers of removed padding blocks are added to the merged block, so header size is kept.
+    fn merge_padding(&mut self) {
+        let mut padding_size: Option<usize> = None;
+        self.blocks.retain(|block| match &block.data {
+            MetadataBlockData::Padding(size) => {
+                padding_size = Some(padding_size.map_or(*size, |s| s + 4 + size));
+                false
+            }
+            _ => true,
+        });
+        if let Some(size) = padding_size {
+            self.blocks.push(MetadataBlock {
+                is_last: true,
+                length: size,
+                data: MetadataBlockData::Padding(size),
+            });
+        }
+    }
+
+    /// Check seek table against audio frames.
+    ///
+    /// Returns the rebuilt seek points if seek table exists and does not match audio frames.
+    fn rebuild_seek_table(&self) -> Result<Option<Vec<SeekPoint>>> {
+        let seek_table = match self.block_of(3).map(|b| &b.data) {
+            Some(MetadataBlockData::SeekTable(table)) => table,
+            _ => return Ok(None),
+        };
+
+        // sample number, offset and samples of all frames
+        let mut frames = Vec::new();
+        let mut reader = self.frame_reader()?;
+        loop {
+            let sample_number = reader.samples();
+            let stream_offset = reader.offset() - self.frame_offset as u64;
+            match reader.next() {
+                Some(frame) => frames.push(SeekPoint {
+                    sample_number,
+                    stream_offset,
+                    frame_samples: frame?.header.block_size,
+                }),
+                None => break,
+            }
+        }
+
+        let mut previous = None;
+        let mut placeholder = false;
+        let is_valid = seek_table.seek_points.iter().all(|point| {
+            if point.is_placeholder() {
+                placeholder = true;
+                return true;
+            }
+            // placeholders must be at the end, and points must be sorted and unique
+            if placeholder || previous.is_some_and(|p| p >= point.sample_number) {
+                return false;
+            }
+            previous = Some(point.sample_number);
+            frames
+                .binary_search_by_key(&point.sample_number, |f| f.sample_number)
+                .is_ok_and(|i| {
+                    frames[i].stream_offset == point.stream_offset
+                        && frames[i].frame_samples == point.frame_samples
+                })
+        });
+        if is_valid {
+            return Ok(None);
+        }
+
+        let interval = self.stream_info().sample_rate as u64 * SEEK_POINT_INTERVAL;
+        let mut seek_points: Vec<SeekPoint> = Vec::new();
+        let mut target = 0;
+        for frame in frames {
+            // use the first frame which contains target sample
+            if frame.sample_number + frame.frame_samples as u64 > target {
+                target = (frame.sample_number / interval + 1) * interval;
+                seek_points.push(frame);
+            }
+        }
+        Ok(Some(seek_points))
+    }
+
     fn fix_is_last(&mut self) {
         let last = self.blocks.len() - 1;
         for (index, block) in self.blocks.iter_mut().enumerate() {
@@ -303,6 +790,25 @@ impl FlacHeader {
     }
 }
 
+/// How [FlacHeader::save_with] writes the header.
+enum SavePlan {
+    /// Write a complete new file to another path.
+    NewFile(PathBuf),
+    /// Overwrite header of the original file.
+    InPlace,
+    /// Write a new file to a temporary path, and rename it to the original file.
+    Replace,
+}
+
+/// Generate a temporary path next to `path` which is unique in current process.
+fn unique_temp_path(path: &Path, extension: &str) -> PathBuf {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+    path.with_extension(format!("{}.{}.{extension}", std::process::id(), id))
+}
+
 pub struct MetadataBlock {
     /// Whether the block is the last block in header.
     ///
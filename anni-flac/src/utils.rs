@@ -66,3 +66,180 @@ pub(crate) async fn read_u24_async<R: AsyncRead + Unpin>(reader: &mut R) -> std:
     reader.read_exact(&mut buf).await?;
     Ok(byteorder::BigEndian::read_u24(&buf))
 }
+
+const fn crc8_table() -> [u8; 256] {
+    let mut table = [0u8; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u8;
+        let mut j = 0;
+        while j < 8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+            j += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+const fn crc16_table() -> [u16; 256] {
+    let mut table = [0u16; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u16) << 8;
+        let mut j = 0;
+        while j < 8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+            j += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// CRC-8, polynomial = x^8 + x^2 + x^1 + x^0
+const CRC8_TABLE: [u8; 256] = crc8_table();
+/// CRC-16, polynomial = x^16 + x^15 + x^2 + x^0
+const CRC16_TABLE: [u16; 256] = crc16_table();
+
+/// Big-endian bit reader used by frame parsing.
+///
+/// CRC-8 and CRC-16 of all bytes read are updated on the fly.
+/// Bytes are read from the underlying reader only when needed,
+/// so the reader is positioned right after the last byte consumed.
+pub(crate) struct BitReader<R: Read> {
+    reader: R,
+    /// Unconsumed bits are stored in the lowest `bits` bits
+    buffer: u64,
+    bits: u32,
+    /// Number of bytes read from `reader`
+    position: u64,
+    crc8: u8,
+    crc16: u16,
+}
+
+impl<R: Read> BitReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: 0,
+            bits: 0,
+            position: 0,
+            crc8: 0,
+            crc16: 0,
+        }
+    }
+
+    /// Number of bytes read from the underlying reader.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    fn refill(&mut self) -> std::io::Result<()> {
+        let mut byte = [0u8; 1];
+        self.reader.read_exact(&mut byte)?;
+        let byte = byte[0];
+        self.position += 1;
+        self.crc8 = CRC8_TABLE[(self.crc8 ^ byte) as usize];
+        self.crc16 = (self.crc16 << 8) ^ CRC16_TABLE[((self.crc16 >> 8) as u8 ^ byte) as usize];
+        self.buffer = (self.buffer << 8) | byte as u64;
+        self.bits += 8;
+        Ok(())
+    }
+
+    /// Reset CRC states. Must be called at byte boundary.
+    pub fn reset_crc(&mut self) {
+        debug_assert_eq!(self.bits, 0);
+        self.crc8 = 0;
+        self.crc16 = 0;
+    }
+
+    /// CRC-8 of bytes read since last [BitReader::reset_crc]
+    pub fn crc8(&self) -> u8 {
+        self.crc8
+    }
+
+    /// CRC-16 of bytes read since last [BitReader::reset_crc]
+    pub fn crc16(&self) -> u16 {
+        self.crc16
+    }
+
+    /// Whether the underlying reader reaches its end. Must be called at byte boundary.
+    pub fn is_eof(&mut self) -> std::io::Result<bool> {
+        if self.bits > 0 {
+            return Ok(false);
+        }
+        match self.refill() {
+            Ok(()) => Ok(false),
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(true),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Read `n` bits as unsigned integer, `n` must be no more than 32.
+    pub fn read_bits(&mut self, n: u32) -> std::io::Result<u32> {
+        debug_assert!(n <= 32);
+        if n == 0 {
+            return Ok(0);
+        }
+        while self.bits < n {
+            self.refill()?;
+        }
+        self.bits -= n;
+        let value = (self.buffer >> self.bits) & ((1u64 << n) - 1);
+        self.buffer &= (1u64 << self.bits) - 1;
+        Ok(value as u32)
+    }
+
+    pub fn read_bit(&mut self) -> std::io::Result<bool> {
+        Ok(self.read_bits(1)? == 1)
+    }
+
+    /// Read `n` bits as two's-complement signed integer, `n` must be no more than 32.
+    pub fn read_signed(&mut self, n: u32) -> std::io::Result<i32> {
+        if n == 0 {
+            return Ok(0);
+        }
+        let value = self.read_bits(n)?;
+        // sign extend
+        Ok(((value << (32 - n)) as i32) >> (32 - n))
+    }
+
+    /// Read unary coded integer, which is the number of `0`s before the first `1`.
+    pub fn read_unary(&mut self) -> std::io::Result<u32> {
+        let mut count = 0;
+        loop {
+            if self.bits == 0 {
+                self.refill()?;
+            }
+            if self.buffer == 0 {
+                count += self.bits;
+                self.bits = 0;
+                continue;
+            }
+
+            // position of the highest `1`
+            let position = 63 - self.buffer.leading_zeros();
+            count += self.bits - 1 - position;
+            self.bits = position;
+            self.buffer &= (1u64 << self.bits) - 1;
+            return Ok(count);
+        }
+    }
+
+    /// Skip bits until byte boundary.
+    pub fn align(&mut self) {
+        self.bits = 0;
+        self.buffer = 0;
+    }
+}
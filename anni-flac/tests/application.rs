@@ -1,5 +1,6 @@
+use anni_flac::blocks::{BlockApplication, APPLICATION_ID_RIFF};
 use anni_flac::prelude::{Decode, Encode};
-use anni_flac::{MetadataBlock, MetadataBlockData};
+use anni_flac::{FlacHeader, MetadataBlock, MetadataBlockData};
 use std::io::Cursor;
 
 #[test]
@@ -24,3 +25,48 @@ fn block_application_encode_decode() {
         _ => false,
     });
 }
+
+#[test]
+fn block_application_hexdump() {
+    let block = BlockApplication::new(
+        APPLICATION_ID_RIFF,
+        b"RIFF\x24\x00\x00\x00WAVEfmt \x10".to_vec(),
+    );
+    assert_eq!(block.application_id_str().as_deref(), Some("riff"));
+    assert_eq!(
+        format!("{:?}", block),
+        concat!(
+            "application ID: 72696666\n",
+            "data contents:\n",
+            "  00000000: 52 49 46 46 24 00 00 00 57 41 56 45 66 6D 74 20 RIFF$...WAVEfmt \n",
+            "  00000010: 10 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 .               \n",
+        )
+    );
+}
+
+#[test]
+fn header_application() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("application.flac");
+    std::fs::copy("../assets/1s.flac", &path).unwrap();
+
+    let id = u32::from_be_bytes(*b"TEST");
+    let mut flac = FlacHeader::from_file(&path).unwrap();
+    assert!(flac.application(id).is_none());
+    flac.set_application(id, vec![1, 2, 3, 4]);
+    flac.save::<String>(None).unwrap();
+
+    let mut flac = FlacHeader::from_file(&path).unwrap();
+    assert_eq!(flac.application(id), Some(&[1, 2, 3, 4][..]));
+    assert!(flac.application(APPLICATION_ID_RIFF).is_none());
+
+    // existing block is replaced
+    flac.set_application(id, vec![5, 6]);
+    assert_eq!(flac.application(id), Some(&[5, 6][..]));
+    let count = flac
+        .blocks
+        .iter()
+        .filter(|b| matches!(b.data, MetadataBlockData::Application(_)))
+        .count();
+    assert_eq!(count, 1);
+}
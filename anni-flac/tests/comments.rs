@@ -51,3 +51,44 @@ fn user_comment_encode_decode() {
     let parsed = common::encode_and_decode(&comment);
     assert_eq!(format!("{:?}", parsed), format!("{:?}", comment));
 }
+
+fn comment_entries(comment: &BlockVorbisComment) -> Vec<String> {
+    comment.comments.iter().map(|c| c.entry()).collect()
+}
+
+#[test]
+fn vorbis_comment_set() {
+    let mut comment = BlockVorbisComment {
+        vendor_string: "Project Anni".to_string(),
+        comments: vec![
+            UserComment::new("ARTIST=a".to_string()),
+            UserComment::new("TITLE=t".to_string()),
+            UserComment::new("artist=b".to_string()),
+        ],
+    };
+
+    comment.set("Artist", "c");
+    assert_eq!(comment_entries(&comment), ["Artist=c", "TITLE=t"]);
+
+    comment.set("ALBUM", "d");
+    assert_eq!(
+        comment_entries(&comment),
+        ["Artist=c", "TITLE=t", "ALBUM=d"]
+    );
+}
+
+#[test]
+fn vorbis_comment_remove() {
+    let mut comment = BlockVorbisComment {
+        vendor_string: "Project Anni".to_string(),
+        comments: vec![
+            UserComment::new("ARTIST=a".to_string()),
+            UserComment::new("TITLE=t".to_string()),
+            UserComment::new("artist=b".to_string()),
+        ],
+    };
+
+    assert_eq!(comment.remove("Artist"), 2);
+    assert_eq!(comment_entries(&comment), ["TITLE=t"]);
+    assert_eq!(comment.remove("ARTIST"), 0);
+}
@@ -0,0 +1,164 @@
+use anni_flac::blocks::{BlockCueSheet, CueSheetTrack};
+use anni_flac::prelude::{Decode, Encode};
+use anni_flac::{FlacHeader, MetadataBlock, MetadataBlockData};
+use std::io::Cursor;
+
+mod common;
+
+fn cue_sheet() -> BlockCueSheet {
+    BlockCueSheet::new(
+        true,
+        vec![
+            CueSheetTrack::new(1, 0).with_isrc(*b"JPXX02100001"),
+            CueSheetTrack::new(2, 588 * 1000)
+                .with_pregap(588 * 75)
+                .unwrap(),
+            CueSheetTrack::new(3, 588 * 3000),
+        ],
+        588 * 5000,
+    )
+    .with_catalog("4988000000000")
+}
+
+fn encode(block: &MetadataBlock) -> Vec<u8> {
+    let mut buf = Vec::new();
+    block.write_to(&mut buf).expect("Failed to write to buf");
+    buf
+}
+
+#[test]
+fn block_cue_sheet_new() {
+    let cue = cue_sheet();
+    assert_eq!(cue.leadin_samples, 88200);
+    assert_eq!(cue.track_number, 4);
+
+    // lead-out track
+    let lead_out = &cue.tracks[3];
+    assert_eq!(lead_out.track_number, 170);
+    assert_eq!(lead_out.track_offset, 588 * 5000);
+    assert!(lead_out.track_index.is_empty());
+
+    // INDEX 01 does not move after pre-gap was added
+    let track = &cue.tracks[1];
+    assert_eq!(track.track_offset, 588 * 925);
+    assert_eq!(track.index_point_number, 2);
+    assert_eq!(track.track_index[0].index_point, 0);
+    assert_eq!(track.track_index[0].sample_offset, 0);
+    assert_eq!(track.track_index[1].index_point, 1);
+    assert_eq!(track.track_index[1].sample_offset, 588 * 75);
+}
+
+#[test]
+fn block_cue_sheet_pregap_underflow() {
+    assert!(CueSheetTrack::new(1, 588).with_pregap(588 * 2).is_err());
+    assert!(CueSheetTrack::new(1, 588).with_pregap(588).is_ok());
+}
+
+#[test]
+fn block_cue_sheet_encode_decode() {
+    let block = MetadataBlock::new(MetadataBlockData::CueSheet(cue_sheet()));
+    let encoded = encode(&block);
+    // block header + data, 3 tracks with 4 indexes and a lead-out track
+    assert_eq!(encoded.len(), 4 + block.data.len());
+    assert_eq!(block.data.len(), 396 + 4 * 36 + 4 * 12);
+
+    let decoded = MetadataBlock::from_reader(&mut Cursor::new(&encoded)).unwrap();
+    let cue = match &decoded.data {
+        MetadataBlockData::CueSheet(cue) => cue,
+        _ => panic!("Invalid block."),
+    };
+    assert_eq!(cue.catalog, "4988000000000");
+    assert!(cue.is_cd);
+    assert_eq!(cue.tracks.len(), 4);
+    assert!(cue.tracks[0].is_audio);
+    assert_eq!(&cue.tracks[0].isrc, b"JPXX02100001");
+
+    assert_eq!(encode(&decoded), encoded);
+}
+
+#[test]
+fn block_cue_sheet_decode_encode() {
+    // non-CD cue sheet with a data track and a lead-out track
+    let mut data = vec![5, 0, 0, 0];
+    data.extend_from_slice(&[0; 128]);
+    data.extend_from_slice(&0u64.to_be_bytes());
+    data.extend_from_slice(&[0; 259]);
+    data.push(2);
+    // track 1
+    data.extend_from_slice(&0u64.to_be_bytes());
+    data.push(1);
+    data.extend_from_slice(&[0; 12]);
+    data.push(0b11000000);
+    data.extend_from_slice(&[0; 13]);
+    data.push(1);
+    data.extend_from_slice(&0u64.to_be_bytes());
+    data.extend_from_slice(&[1, 0, 0, 0]);
+    // lead-out
+    data.extend_from_slice(&44100u64.to_be_bytes());
+    data.push(255);
+    data.extend_from_slice(&[0; 12]);
+    data.push(0);
+    data.extend_from_slice(&[0; 13]);
+    data.push(0);
+    let length = (data.len() - 4) as u32;
+    data[1..4].copy_from_slice(&length.to_be_bytes()[1..]);
+
+    let block = MetadataBlock::from_reader(&mut Cursor::new(&data)).unwrap();
+    match &block.data {
+        MetadataBlockData::CueSheet(cue) => {
+            assert!(!cue.is_cd);
+            assert_eq!(cue.catalog, "");
+            assert!(!cue.tracks[0].is_audio);
+            assert!(cue.tracks[0].is_pre_emphasis);
+            assert!(cue.tracks[1].is_audio);
+        }
+        _ => panic!("Invalid block."),
+    }
+    assert_eq!(block.data.len(), length as usize);
+    assert_eq!(encode(&block), data);
+}
+
+#[test]
+fn block_cue_sheet_catalog_too_long() {
+    let block = MetadataBlock::new(MetadataBlockData::CueSheet(
+        cue_sheet().with_catalog("0".repeat(129)),
+    ));
+    assert!(block.write_to(&mut Vec::new()).is_err());
+}
+
+#[test]
+fn block_cue_sheet_save() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cue.flac");
+    std::fs::copy("../assets/1s.flac", &path).unwrap();
+
+    let mut flac = FlacHeader::from_file(&path).unwrap();
+    let total_samples = flac.stream_info().total_samples;
+    flac.blocks
+        .push(MetadataBlock::new(MetadataBlockData::CueSheet(
+            BlockCueSheet::new(
+                false,
+                vec![
+                    CueSheetTrack::new(1, 0),
+                    CueSheetTrack::new(2, total_samples / 2),
+                ],
+                total_samples,
+            ),
+        )));
+    flac.save::<String>(None).unwrap();
+
+    let flac = FlacHeader::from_file(&path).unwrap();
+    let cue = flac
+        .blocks
+        .iter()
+        .find_map(|b| match &b.data {
+            MetadataBlockData::CueSheet(cue) => Some(cue),
+            _ => None,
+        })
+        .expect("Cue sheet not found.");
+    assert_eq!(cue.tracks[1].track_offset, total_samples / 2);
+    assert_eq!(cue.tracks[2].track_number, 255);
+    assert_eq!(cue.tracks[2].track_offset, total_samples);
+    // audio frames are kept untouched
+    assert!(flac.frames().is_ok());
+}
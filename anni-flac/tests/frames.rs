@@ -0,0 +1,307 @@
+use anni_flac::blocks::BlockStreamInfo;
+use anni_flac::error::FlacError;
+use anni_flac::frames::{
+    BlockStrategy, ChannelAssignment, Frame, FrameReader, Frames, SampleRate, SubframeType,
+};
+use md5::{Digest, Md5};
+use std::io::Cursor;
+
+mod common;
+
+fn parse_1s_frames() -> Vec<Frame> {
+    match common::parse_1s_audio().frames().unwrap() {
+        Frames::Parsed(frames) => frames,
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn test_1s_first_frame() {
+    let frames = parse_1s_frames();
+    let header = &frames[0].header;
+    assert_eq!(header.block_strategy, BlockStrategy::Fixed(0));
+    assert_eq!(header.block_size, 4608);
+    assert_eq!(header.channel_assignment, ChannelAssignment::Independent(1));
+    assert_eq!(header.sample_rate, SampleRate::Rate44100);
+    assert_eq!(header.sample_size, Some(16));
+    assert_eq!(frames[0].subframes.len(), 1);
+}
+
+#[test]
+fn test_1s_frames() {
+    let frames = parse_1s_frames();
+    assert_eq!(frames.len(), 10);
+    for (i, frame) in frames.iter().enumerate() {
+        assert_eq!(frame.header.block_strategy, BlockStrategy::Fixed(i as u32));
+    }
+
+    // only the last block can be shorter
+    let total: usize = frames.iter().map(|f| f.header.block_size as usize).sum();
+    assert_eq!(frames[9].header.block_size, 2628);
+    assert_eq!(total, 44100);
+}
+
+#[test]
+fn test_1s_decode_md5() {
+    let header = common::parse_1s_audio();
+    let frames = parse_1s_frames();
+
+    let mut md5 = Md5::new();
+    for frame in frames.iter() {
+        let channels = frame.decode();
+        for i in 0..frame.header.block_size as usize {
+            for channel in channels.iter() {
+                md5.update((channel[i] as i16).to_le_bytes());
+            }
+        }
+    }
+    assert_eq!(
+        md5.finalize().as_slice(),
+        &header.stream_info().md5_signature
+    );
+}
+
+/// Byte offsets of all frames in `1s.flac`, and the end of file.
+fn frame_offsets_1s() -> Vec<u64> {
+    let header = common::parse_1s_audio();
+    let mut reader = header.frame_reader().unwrap();
+    let mut offsets = vec![reader.offset()];
+    while let Some(frame) = reader.next() {
+        frame.unwrap();
+        offsets.push(reader.offset());
+    }
+    offsets
+}
+
+#[test]
+fn test_truncated_frame() {
+    let offsets = frame_offsets_1s();
+    assert_eq!(offsets.len(), 11);
+
+    // truncate in the middle of the 4th frame
+    let data = std::fs::read("../assets/1s.flac").unwrap();
+    let end = (offsets[3] + offsets[4]) / 2;
+    let header = common::parse_1s_audio();
+    let mut data = Cursor::new(&data[..end as usize]);
+    data.set_position(offsets[0]);
+    let mut reader = FrameReader::new(data, header.stream_info()).with_offset(offsets[0]);
+
+    for _ in 0..3 {
+        assert!(reader.next().unwrap().is_ok());
+    }
+    match reader.next() {
+        Some(Err(FlacError::CorruptFrame {
+            frame,
+            offset,
+            samples,
+            source,
+        })) => {
+            assert_eq!(frame, 3);
+            assert_eq!(offset, offsets[3]);
+            assert_eq!(samples, 3 * 4608);
+            assert!(matches!(*source, FlacError::IO(_)));
+        }
+        _ => panic!("Corrupt frame is not reported."),
+    }
+    assert!(reader.next().is_none());
+    assert_eq!(reader.samples(), 3 * 4608);
+}
+
+#[test]
+fn test_corrupt_frame() {
+    let offsets = frame_offsets_1s();
+    let mut data = std::fs::read("../assets/1s.flac").unwrap();
+    // flip a bit in the footer crc of the 6th frame
+    data[offsets[6] as usize - 1] ^= 0x01;
+
+    let header = common::parse_1s_audio();
+    let mut data = Cursor::new(data);
+    data.set_position(offsets[0]);
+    let reader = FrameReader::new(data, header.stream_info()).with_offset(offsets[0]);
+    let results: Vec<_> = reader.collect();
+    assert_eq!(results.len(), 6);
+    assert!(results[..5].iter().all(|r| r.is_ok()));
+    match &results[5] {
+        Err(FlacError::CorruptFrame {
+            frame,
+            offset,
+            samples,
+            source,
+        }) => {
+            assert_eq!(*frame, 5);
+            assert_eq!(*offset, offsets[5]);
+            assert_eq!(*samples, 5 * 4608);
+            assert!(matches!(**source, FlacError::FrameCrcMismatch { .. }));
+        }
+        _ => panic!("Corrupt frame is not reported."),
+    }
+}
+
+/// Minimal bit writer to build frames by hand.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, n: u32) {
+        for i in (0..n).rev() {
+            if self.bits & 7 == 0 {
+                self.bytes.push(0);
+            }
+            let bit = ((value >> i) & 1) as u8;
+            *self.bytes.last_mut().unwrap() |= bit << (7 - self.bits % 8);
+            self.bits += 1;
+        }
+    }
+
+    fn write_crc8(&mut self) {
+        let mut crc = 0u8;
+        for byte in self.bytes.iter() {
+            crc ^= byte;
+            for _ in 0..8 {
+                crc = if crc & 0x80 != 0 {
+                    (crc << 1) ^ 0x07
+                } else {
+                    crc << 1
+                };
+            }
+        }
+        self.write(crc as u32, 8);
+    }
+
+    fn write_crc16(&mut self) {
+        let mut crc = 0u16;
+        for byte in self.bytes.iter() {
+            crc ^= (*byte as u16) << 8;
+            for _ in 0..8 {
+                crc = if crc & 0x8000 != 0 {
+                    (crc << 1) ^ 0x8005
+                } else {
+                    crc << 1
+                };
+            }
+        }
+        self.write(crc as u32, 16);
+    }
+}
+
+fn stream_info() -> BlockStreamInfo {
+    BlockStreamInfo {
+        min_block_size: 16,
+        max_block_size: 4096,
+        min_frame_size: 0,
+        max_frame_size: 0,
+        sample_rate: 44100,
+        channels: 2,
+        bits_per_sample: 16,
+        total_samples: 0,
+        md5_signature: [0; 16],
+    }
+}
+
+/// A variable-blocksize mid/side stereo frame with 16 samples, starting at sample 1000.
+///
+/// Mid channel is constant, side channel is verbatim.
+fn mid_side_frame() -> Vec<u8> {
+    let mut writer = BitWriter::default();
+    // sync code, reserved bit and variable blocksize
+    writer.write(0b11111111111110, 14);
+    writer.write(0, 1);
+    writer.write(1, 1);
+    // 8-bit blocksize at the end of header, 44.1kHz, mid/side, 16 bits
+    writer.write(0b0110, 4);
+    writer.write(0b1001, 4);
+    writer.write(0b1010, 4);
+    writer.write(0b100, 3);
+    writer.write(0, 1);
+    // sample number 1000 in 2 bytes "UTF-8"
+    writer.write(0b11001111, 8);
+    writer.write(0b10101000, 8);
+    // blocksize - 1
+    writer.write(15, 8);
+    writer.write_crc8();
+
+    // mid: constant 100
+    writer.write(0, 1);
+    writer.write(0b000000, 6);
+    writer.write(0, 1);
+    writer.write(100, 16);
+    // side: verbatim, 17 bits
+    writer.write(0, 1);
+    writer.write(0b000001, 6);
+    writer.write(0, 1);
+    for i in 0..16i32 {
+        writer.write((i * 2 - 16) as u32 & 0x1ffff, 17);
+    }
+
+    // zero padding to byte boundary
+    let padding = (8 - writer.bits % 8) % 8;
+    writer.write(0, padding);
+    writer.write_crc16();
+    writer.bytes
+}
+
+#[test]
+fn test_mid_side_frame() {
+    let frame = Frame::from_reader(&mut Cursor::new(mid_side_frame()), &stream_info()).unwrap();
+    assert_eq!(frame.header.block_strategy, BlockStrategy::Variable(1000));
+    assert_eq!(frame.header.block_size, 16);
+    assert_eq!(frame.header.channel_assignment, ChannelAssignment::MidSide);
+    assert!(matches!(
+        frame.subframes[0].content,
+        SubframeType::Constant(100)
+    ));
+    assert!(matches!(
+        frame.subframes[1].content,
+        SubframeType::Verbatim(_)
+    ));
+
+    let channels = frame.decode();
+    for i in 0..16 {
+        let side = i * 2 - 16;
+        assert_eq!(channels[0][i as usize], 100 + side / 2);
+        assert_eq!(channels[1][i as usize], 100 - side / 2);
+    }
+}
+
+#[test]
+fn test_multiple_frames() {
+    let mut data = mid_side_frame();
+    data.extend(mid_side_frame());
+    let frames = Frames::parse(&mut Cursor::new(data), &stream_info()).unwrap();
+    match frames {
+        Frames::Parsed(frames) => assert_eq!(frames.len(), 2),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn test_invalid_sync_code() {
+    let mut data = mid_side_frame();
+    data[0] = 0xfe;
+    let result = Frame::from_reader(&mut Cursor::new(data), &stream_info());
+    assert!(matches!(result, Err(FlacError::InvalidSyncCode)));
+}
+
+#[test]
+fn test_header_crc_mismatch() {
+    let mut data = mid_side_frame();
+    // change sample rate to 48kHz
+    data[2] = 0b01101010;
+    let result = Frame::from_reader(&mut Cursor::new(data), &stream_info());
+    assert!(matches!(
+        result,
+        Err(FlacError::FrameHeaderCrcMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_frame_crc_mismatch() {
+    let mut data = mid_side_frame();
+    let last = data.len() - 3;
+    data[last] ^= 0x01;
+    let result = Frame::from_reader(&mut Cursor::new(data), &stream_info());
+    assert!(matches!(result, Err(FlacError::FrameCrcMismatch { .. })));
+}
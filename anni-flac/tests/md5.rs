@@ -0,0 +1,58 @@
+use anni_flac::{FlacHeader, MetadataBlockData};
+
+mod common;
+
+#[test]
+fn test_verify_md5() {
+    assert!(common::parse_1s_audio().verify_md5().unwrap());
+    assert!(FlacHeader::from_file("../assets/1s-full.flac")
+        .unwrap()
+        .verify_md5()
+        .unwrap());
+}
+
+#[test]
+fn test_fix_md5() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("md5.flac");
+    std::fs::copy("../assets/1s.flac", &path).unwrap();
+
+    let mut flac = FlacHeader::from_file(&path).unwrap();
+    let md5 = flac.stream_info().md5_signature;
+    // signature is already set
+    assert!(!flac.fix_md5().unwrap());
+
+    match &mut flac.blocks[0].data {
+        MetadataBlockData::StreamInfo(info) => info.md5_signature = [0; 16],
+        _ => unreachable!(),
+    }
+    flac.save::<String>(None).unwrap();
+
+    let mut flac = FlacHeader::from_file(&path).unwrap();
+    assert!(!flac.verify_md5().unwrap());
+    assert!(flac.fix_md5().unwrap());
+    assert_eq!(flac.stream_info().md5_signature, md5);
+    flac.save::<String>(None).unwrap();
+
+    let flac = FlacHeader::from_file(&path).unwrap();
+    assert!(flac.verify_md5().unwrap());
+}
+
+#[test]
+fn test_verify_md5_mismatch() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("mismatch.flac");
+    std::fs::copy("../assets/1s.flac", &path).unwrap();
+
+    let mut flac = FlacHeader::from_file(&path).unwrap();
+    match &mut flac.blocks[0].data {
+        MetadataBlockData::StreamInfo(info) => info.md5_signature[0] ^= 0xff,
+        _ => unreachable!(),
+    }
+    flac.save::<String>(None).unwrap();
+
+    let mut flac = FlacHeader::from_file(&path).unwrap();
+    assert!(!flac.verify_md5().unwrap());
+    // only unset signature would be fixed
+    assert!(!flac.fix_md5().unwrap());
+}
@@ -0,0 +1,34 @@
+use anni_flac::error::FlacError;
+use anni_flac::{FlacHeader, MetadataBlockData};
+use std::io::Cursor;
+
+#[test]
+fn test_parse_metadata_only() {
+    let header = FlacHeader::from_file("../assets/1s-full.flac").unwrap();
+    let frame_offset = header.frame_reader().unwrap().offset() as usize;
+
+    // truncated right after the header, so any read of frames fails
+    let data = std::fs::read("../assets/1s-full.flac").unwrap();
+    let mut reader = Cursor::new(&data[..frame_offset]);
+    let blocks = FlacHeader::parse_metadata_only(&mut reader).unwrap();
+    assert_eq!(reader.position() as usize, frame_offset);
+
+    assert_eq!(blocks.len(), header.blocks.len());
+    assert!(matches!(blocks[0].data, MetadataBlockData::StreamInfo(_)));
+    assert!(blocks
+        .iter()
+        .any(|block| matches!(block.data, MetadataBlockData::Comment(_))));
+    assert!(blocks
+        .iter()
+        .any(|block| matches!(block.data, MetadataBlockData::Picture(_))));
+}
+
+#[test]
+fn test_parse_metadata_only_truncated_header() {
+    let data = std::fs::read("../assets/1s-full.flac").unwrap();
+    let mut reader = Cursor::new(&data[..100]);
+    assert!(matches!(
+        FlacHeader::parse_metadata_only(&mut reader),
+        Err(FlacError::IO(_))
+    ));
+}
@@ -0,0 +1,146 @@
+use anni_flac::blocks::{BlockPicture, PictureType};
+use anni_flac::{FlacHeader, MetadataBlock, MetadataBlockData};
+
+mod common;
+
+const COVER_PATH: &str = "../assets/1s-cover.png";
+
+fn cover() -> Vec<u8> {
+    std::fs::read(COVER_PATH).unwrap()
+}
+
+fn picture(picture_type: PictureType, description: &str) -> BlockPicture {
+    BlockPicture::new(COVER_PATH, picture_type, description.to_string()).unwrap()
+}
+
+#[test]
+fn test_picture_new() {
+    let picture = picture(PictureType::Other, "");
+    assert_eq!(picture.mime_type, "image/png");
+    assert_eq!(picture.width, 640);
+    assert_eq!(picture.height, 480);
+    assert_eq!(picture.depth, 24);
+    assert!(!picture.color_indexed());
+
+    // 1x1 gif with a global color table of 2 colors
+    let gif = b"GIF89a\x01\x00\x01\x00\x80\x00\x00\xff\xff\xff\x00\x00\x00!\xf9\x04\x01\x00\x00\x00\x00,\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02D\x01\x00;";
+    let dir = tempfile::tempdir().unwrap();
+    // format is detected from content instead of extension
+    let path = dir.path().join("cover.jpg");
+    std::fs::write(&path, gif).unwrap();
+    let picture = BlockPicture::new(&path, PictureType::Other, String::new()).unwrap();
+    assert_eq!(picture.mime_type, "image/gif");
+    assert_eq!((picture.width, picture.height), (1, 1));
+    assert_eq!(picture.colors, 2);
+}
+
+#[test]
+fn test_extract_then_set_cover() {
+    let data = common::parse_full_1s_audio()
+        .front_cover()
+        .expect("Front cover not found")
+        .data
+        .clone();
+    assert_eq!(data, cover());
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cover.flac");
+    std::fs::copy("../assets/1s.flac", &path).unwrap();
+
+    let mut header = FlacHeader::from_file(&path).unwrap();
+    assert!(header.front_cover().is_none());
+    header.set_front_cover(picture(PictureType::Other, ""));
+    header.save::<String>(None).unwrap();
+
+    let header = FlacHeader::from_file(&path).unwrap();
+    assert_eq!(header.pictures().count(), 1);
+    let cover = header.front_cover().expect("Front cover not found");
+    assert_eq!(cover.data, data);
+    assert_eq!((cover.width, cover.height), (640, 480));
+    assert!(header.verify_md5().unwrap());
+}
+
+#[test]
+fn test_set_cover_replaces_pictures() {
+    let mut header = common::parse_full_1s_audio();
+    let index = header
+        .blocks
+        .iter()
+        .position(|block| matches!(block.data, MetadataBlockData::Picture(_)))
+        .unwrap();
+    for picture_type in [PictureType::CoverBack, PictureType::CoverFront] {
+        header
+            .blocks
+            .push(MetadataBlock::new(MetadataBlockData::Picture(picture(
+                picture_type,
+                "",
+            ))));
+    }
+    assert_eq!(header.pictures().count(), 3);
+
+    header.set_front_cover(picture(PictureType::Media, "new"));
+    assert_eq!(header.pictures().count(), 1);
+    assert!(matches!(
+        &header.blocks[index].data,
+        MetadataBlockData::Picture(p) if p.picture_type == PictureType::CoverFront && p.description == "new"
+    ));
+}
+
+#[test]
+fn test_strip_pictures() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("strip.flac");
+    std::fs::copy("../assets/1s-full.flac", &path).unwrap();
+
+    let mut header = FlacHeader::from_file(&path).unwrap();
+    assert_eq!(header.strip_pictures(), 1);
+    assert_eq!(header.strip_pictures(), 0);
+    header.save::<String>(None).unwrap();
+
+    let header = FlacHeader::from_file(&path).unwrap();
+    assert_eq!(header.pictures().count(), 0);
+    assert!(header.front_cover().is_none());
+    assert!(header.comments().is_some());
+    assert!(header.verify_md5().unwrap());
+}
+
+#[test]
+fn test_retain_front_cover() {
+    let mut header = common::parse_full_1s_audio();
+    for (picture_type, description) in [
+        (PictureType::CoverBack, "back"),
+        (PictureType::CoverFront, "duplicated"),
+    ] {
+        header
+            .blocks
+            .push(MetadataBlock::new(MetadataBlockData::Picture(picture(
+                picture_type,
+                description,
+            ))));
+    }
+    assert_eq!(header.pictures().count(), 3);
+
+    assert_eq!(header.retain_front_cover(), 2);
+    assert_eq!(header.retain_front_cover(), 0);
+    assert_eq!(header.pictures().count(), 1);
+    let front_cover = header.front_cover().expect("Front cover not found");
+    assert_ne!(front_cover.description, "duplicated");
+    assert!(header.comments().is_some());
+}
+
+#[test]
+fn test_picture_to_jpeg() {
+    let png = picture(PictureType::CoverFront, "cover");
+    let jpeg = png.to_jpeg(320).unwrap().expect("Picture is not converted");
+    assert_eq!(jpeg.mime_type, "image/jpeg");
+    assert_eq!((jpeg.width, jpeg.height), (320, 240));
+    assert_eq!(jpeg.picture_type, PictureType::CoverFront);
+    assert_eq!(jpeg.description, "cover");
+    assert!(jpeg.data.starts_with(&[0xff, 0xd8, 0xff]));
+
+    // JPEG within the size is kept
+    assert!(jpeg.to_jpeg(320).unwrap().is_none());
+    // other formats are converted even if they are small enough
+    let converted = png.to_jpeg(1500).unwrap().unwrap();
+    assert_eq!((converted.width, converted.height), (640, 480));
+}
@@ -0,0 +1,110 @@
+use anni_flac::blocks::{BlockSeekTable, SeekPoint};
+use anni_flac::{FlacHeader, MetadataBlock, MetadataBlockData};
+
+mod common;
+
+/// Build a file with two padding blocks and wrong `is_last` flags from `1s.flac`.
+fn broken_file(path: &std::path::Path) {
+    let header = common::parse_1s_audio();
+    let offset = header.frame_reader().unwrap().offset() as usize;
+    let data = std::fs::read("../assets/1s.flac").unwrap();
+
+    let mut output = b"fLaC".to_vec();
+    // stream info, copied from 1s.flac with is_last cleared
+    output.push(0x00);
+    output.extend_from_slice(&data[5..8 + 34]);
+    // padding with is_last set, but it is not the last block
+    output.extend_from_slice(&[0x81, 0, 0, 100]);
+    output.extend_from_slice(&[0; 100]);
+    // the last padding with is_last cleared
+    output.extend_from_slice(&[0x01, 0, 0, 50]);
+    output.extend_from_slice(&[0; 50]);
+    // audio frames
+    output.extend_from_slice(&data[offset..]);
+    std::fs::write(path, output).unwrap();
+}
+
+#[test]
+fn test_repair_padding_and_is_last() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("broken.flac");
+    broken_file(&path);
+
+    // strict parser stops at the wrong is_last flag
+    let flac = FlacHeader::from_file(&path).unwrap();
+    assert_eq!(flac.blocks.len(), 2);
+
+    let mut flac = FlacHeader::from_file_lenient(&path).unwrap();
+    assert_eq!(flac.blocks.len(), 3);
+    let report = flac.repair().unwrap();
+    assert!(report.padding_merged);
+    assert!(report.is_last_fixed);
+    assert!(!report.seek_table_rebuilt);
+
+    let flac = FlacHeader::from_file(&path).unwrap();
+    assert_eq!(flac.blocks.len(), 2);
+    assert!(matches!(
+        flac.blocks[0].data,
+        MetadataBlockData::StreamInfo(_)
+    ));
+    assert!(matches!(
+        flac.blocks[1].data,
+        MetadataBlockData::Padding(154)
+    ));
+
+    let data = std::fs::read(&path).unwrap();
+    assert_eq!(data[4], 0x00);
+    assert_eq!(data[8 + 34], 0x81);
+    // audio frames are kept untouched
+    assert_eq!(flac.stream_info().total_samples, 44100);
+    assert!(flac.frames().is_ok());
+
+    // repair a healthy file is a no-op
+    let mut flac = FlacHeader::from_file_lenient(&path).unwrap();
+    assert!(flac.repair().unwrap().is_healthy());
+    assert_eq!(std::fs::read(&path).unwrap(), data);
+}
+
+#[test]
+fn test_repair_seek_table() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("seek.flac");
+    std::fs::copy("../assets/1s.flac", &path).unwrap();
+
+    // a seek point pointing to the middle of a frame
+    let mut flac = FlacHeader::from_file(&path).unwrap();
+    flac.blocks.insert(
+        1,
+        MetadataBlock::new(MetadataBlockData::SeekTable(BlockSeekTable {
+            seek_points: vec![SeekPoint {
+                sample_number: 100,
+                stream_offset: 1,
+                frame_samples: 4608,
+            }],
+        })),
+    );
+    flac.save::<String>(None).unwrap();
+
+    let mut flac = FlacHeader::from_file_lenient(&path).unwrap();
+    let report = flac.repair().unwrap();
+    assert!(report.seek_table_rebuilt);
+    assert!(!report.padding_merged);
+
+    let flac = FlacHeader::from_file(&path).unwrap();
+    let seek_table = flac
+        .blocks
+        .iter()
+        .find_map(|b| match &b.data {
+            MetadataBlockData::SeekTable(table) => Some(table),
+            _ => None,
+        })
+        .expect("Seek table not found.");
+    // only one point in a 1s file
+    assert_eq!(seek_table.seek_points.len(), 1);
+    assert_eq!(seek_table.seek_points[0].sample_number, 0);
+    assert_eq!(seek_table.seek_points[0].stream_offset, 0);
+    assert_eq!(seek_table.seek_points[0].frame_samples, 4608);
+
+    let mut flac = FlacHeader::from_file_lenient(&path).unwrap();
+    assert!(flac.repair().unwrap().is_healthy());
+}
@@ -1,7 +1,11 @@
 use anni_flac::blocks::{
     BlockPicture, BlockSeekTable, PictureType, SeekPoint, UserComment, UserCommentExt,
 };
-use anni_flac::{MetadataBlock, MetadataBlockData};
+use anni_flac::error::FlacError;
+use anni_flac::{FlacHeader, MetadataBlock, MetadataBlockData, SaveStrategy};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 
 mod common;
 
@@ -47,3 +51,114 @@ fn test_save() {
     header.save(Some(file)).unwrap();
     //TODO: assert(file == 1s-full)
 }
+
+/// Copy `1s.flac` to a temporary directory for saving in place.
+fn copy_1s_audio(dir: &Path) -> PathBuf {
+    let path = dir.join("1s.flac");
+    std::fs::copy("../assets/1s.flac", &path).unwrap();
+    path
+}
+
+/// Read header and audio frames of a flac file.
+fn read_flac(path: &Path) -> (FlacHeader, Vec<u8>) {
+    let mut file = File::open(path).unwrap();
+    let header = FlacHeader::parse(&mut file, path.to_path_buf()).unwrap();
+    let mut frames = Vec::new();
+    file.read_to_end(&mut frames).unwrap();
+    (header, frames)
+}
+
+fn save_title(path: &Path, title: &str, strategy: SaveStrategy) -> anni_flac::prelude::Result<()> {
+    let mut header = FlacHeader::from_file(path).unwrap();
+    let comments = header.comments_mut();
+    comments.clear();
+    comments.push(UserComment::title(title));
+    header.save_with(None::<&Path>, strategy)
+}
+
+fn assert_saved(path: &Path, title: &str, frames: &[u8]) {
+    let (header, frames_now) = read_flac(path);
+    assert_eq!(header.comments().unwrap().to_map()["TITLE"].value(), title);
+    assert_eq!(frames_now, frames);
+    assert!(!path.with_extension("anni").exists());
+    assert!(!path.with_extension("anni.bak").exists());
+}
+
+#[test]
+fn test_save_auto_in_place() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = copy_1s_audio(dir.path());
+    let (_, frames) = read_flac(&path);
+    let size = std::fs::metadata(&path).unwrap().len();
+
+    save_title(&path, "Short", SaveStrategy::Auto).unwrap();
+    assert_saved(&path, "Short", &frames);
+    // header fits into padding, so file size should not change
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), size);
+}
+
+#[test]
+fn test_save_auto_fallback() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = copy_1s_audio(dir.path());
+    let (_, frames) = read_flac(&path);
+    let size = std::fs::metadata(&path).unwrap().len();
+
+    let title = "Long".repeat(4096);
+    save_title(&path, &title, SaveStrategy::Auto).unwrap();
+    assert_saved(&path, &title, &frames);
+    assert!(std::fs::metadata(&path).unwrap().len() > size);
+}
+
+#[test]
+fn test_save_always_temp() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = copy_1s_audio(dir.path());
+    let (_, frames) = read_flac(&path);
+
+    save_title(&path, "Short", SaveStrategy::AlwaysTemp).unwrap();
+    assert_saved(&path, "Short", &frames);
+
+    let title = "Long".repeat(4096);
+    save_title(&path, &title, SaveStrategy::AlwaysTemp).unwrap();
+    assert_saved(&path, &title, &frames);
+}
+
+#[test]
+fn test_save_in_place_if_fits() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = copy_1s_audio(dir.path());
+    let (_, frames) = read_flac(&path);
+    let size = std::fs::metadata(&path).unwrap().len();
+
+    save_title(&path, "Short", SaveStrategy::InPlaceIfFits).unwrap();
+    assert_saved(&path, "Short", &frames);
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), size);
+
+    // header does not fit, file should be left untouched
+    let original = std::fs::read(&path).unwrap();
+    let result = save_title(&path, &"Long".repeat(4096), SaveStrategy::InPlaceIfFits);
+    assert!(matches!(result, Err(FlacError::InsufficientSpace)));
+    assert_eq!(std::fs::read(&path).unwrap(), original);
+}
+
+#[test]
+fn test_save_twice_after_fallback() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = copy_1s_audio(dir.path());
+    let (_, frames) = read_flac(&path);
+
+    let mut header = FlacHeader::from_file(&path).unwrap();
+    let title = "Long".repeat(4096);
+    header.comments_mut().push(UserComment::title(&title));
+    header.save(None::<&Path>).unwrap();
+    header.comments_mut().push(UserComment::album("TestAlbum"));
+    header.save(None::<&Path>).unwrap();
+
+    let (header, frames_now) = read_flac(&path);
+    assert_eq!(
+        header.comments().unwrap().to_map()["ALBUM"].value(),
+        "TestAlbum"
+    );
+    assert_eq!(frames_now, frames);
+}
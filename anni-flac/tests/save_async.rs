@@ -0,0 +1,87 @@
+#![cfg(feature = "async")]
+
+use anni_flac::blocks::UserComment;
+use anni_flac::error::FlacError;
+use anni_flac::{FlacHeader, SaveStrategy};
+use std::path::PathBuf;
+
+#[tokio::test]
+async fn test_save_async_concurrently() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut tasks = Vec::new();
+    for i in 0..8 {
+        let path = dir.path().join(format!("{i}.flac"));
+        std::fs::copy("../assets/1s-full.flac", &path).unwrap();
+
+        let mut flac = FlacHeader::from_file(&path).unwrap();
+        // odd files need more space than the original header
+        let value = "a".repeat(if i & 1 == 1 { 65536 } else { 8 });
+        flac.comments_mut()
+            .push(UserComment::new(format!("INDEX={i}{value}")));
+        tasks.push(tokio::spawn(async move {
+            flac.save_async::<PathBuf>(None).await.unwrap();
+            flac
+        }));
+    }
+
+    for (i, task) in tasks.into_iter().enumerate() {
+        let saved = task.await.unwrap();
+        let flac = FlacHeader::from_file(&saved.path).unwrap();
+        let comment = flac
+            .comments()
+            .unwrap()
+            .comments
+            .iter()
+            .find(|c| c.key() == "INDEX")
+            .unwrap();
+        assert!(comment.value().starts_with(&i.to_string()));
+        // audio frames are kept untouched
+        assert!(flac.verify_md5().unwrap());
+    }
+
+    // no temporary file is left
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 8);
+}
+
+#[tokio::test]
+async fn test_save_async_to_another_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("output.flac");
+
+    let mut flac = FlacHeader::from_file("../assets/1s.flac").unwrap();
+    flac.comments_mut()
+        .push(UserComment::new("TITLE=async".to_string()));
+    flac.save_async(Some(&output)).await.unwrap();
+
+    let flac = FlacHeader::from_file(&output).unwrap();
+    let comments = &flac.comments().unwrap().comments;
+    assert!(comments
+        .iter()
+        .any(|c| c.key() == "TITLE" && c.value() == "async"));
+    assert!(flac.verify_md5().unwrap());
+}
+
+#[tokio::test]
+async fn test_save_async_with_strategy() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("strategy.flac");
+    std::fs::copy("../assets/1s-full.flac", &path).unwrap();
+    let original = std::fs::read(&path).unwrap();
+
+    let mut flac = FlacHeader::from_file(&path).unwrap();
+    flac.comments_mut()
+        .push(UserComment::new(format!("TITLE={}", "a".repeat(65536))));
+    let result = flac
+        .save_with_async::<PathBuf>(None, SaveStrategy::InPlaceIfFits)
+        .await;
+    assert!(matches!(result, Err(FlacError::InsufficientSpace)));
+    // file is not touched
+    assert_eq!(std::fs::read(&path).unwrap(), original);
+
+    flac.save_with_async::<PathBuf>(None, SaveStrategy::AlwaysTemp)
+        .await
+        .unwrap();
+    let flac = FlacHeader::from_file(&path).unwrap();
+    assert!(flac.verify_md5().unwrap());
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+}
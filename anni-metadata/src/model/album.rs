@@ -74,7 +74,7 @@ impl Album {
 
     /// Only album title uses edition parameter.
     pub fn full_title(&self) -> Cow<str> {
-        if let Some(edition) = &self.info.edition {
+        if let Some(edition) = self.edition() {
             Cow::Owned(format!("{}【{edition}】", self.info.title))
         } else {
             Cow::Borrowed(&self.info.title)
@@ -85,8 +85,9 @@ impl Album {
         self.info.title.as_ref()
     }
 
+    /// Edition of the album. Empty edition is treated as no edition.
     pub fn edition(&self) -> Option<&str> {
-        self.info.edition.as_deref()
+        self.info.edition.as_deref().filter(|e| !e.is_empty())
     }
 
     pub fn artist(&self) -> &str {
@@ -105,6 +106,16 @@ impl Album {
         self.info.catalog.as_ref()
     }
 
+    /// Album ReplayGain in dB
+    pub fn gain(&self) -> Option<f64> {
+        self.info.gain
+    }
+
+    /// Album ReplayGain peak
+    pub fn peak(&self) -> Option<f64> {
+        self.info.peak
+    }
+
     pub fn tags<'me, 'tag>(&'me self) -> Vec<&'me TagRef<'tag>>
     where
         'tag: 'me,
@@ -238,6 +249,14 @@ pub struct AlbumInfo {
     pub album_type: TrackType,
     /// Album catalog
     pub catalog: String,
+    /// Album ReplayGain in dB
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gain: Option<f64>,
+    /// Album ReplayGain peak
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peak: Option<f64>,
     /// Album tags
     #[serde(default)]
     // TODO: use IndexSet
@@ -255,6 +274,8 @@ impl Default for AlbumInfo {
             release_date: AnniDate::new(2021, 1, 1),
             album_type: TrackType::Normal,
             catalog: "@TEMP".to_string(),
+            gain: None,
+            peak: None,
             tags: Default::default(),
         }
     }
@@ -331,6 +352,13 @@ impl DiscInfo {
             disc_type,
         }
     }
+
+    /// Disc artist, or album artist if the disc has no artist of its own.
+    ///
+    /// An empty artist is treated the same as a missing one.
+    pub fn resolved_artist<'a>(&'a self, album: &'a AlbumInfo) -> &'a str {
+        own_artist(&self.artist).unwrap_or(album.artist.as_str())
+    }
 }
 
 #[derive(Clone)]
@@ -353,15 +381,12 @@ impl<'album> DiscRef<'album> {
     }
 
     pub fn artist(&self) -> &str {
-        self.disc
-            .artist
-            .as_deref()
-            .unwrap_or(self.album.artist.as_str())
+        self.disc.resolved_artist(self.album)
     }
 
-    /// Get raw disc artist without inherit
+    /// Get raw disc artist without inherit, `None` if the disc has no artist of its own
     pub fn artist_raw(&self) -> Option<&str> {
-        self.disc.artist.as_deref()
+        own_artist(&self.disc.artist)
     }
 
     pub fn artists(&self) -> Option<&HashMap<String, String>> {
@@ -415,10 +440,7 @@ impl<'album> DiscRefMut<'album> {
     }
 
     pub fn artist(&self) -> &str {
-        self.disc
-            .artist
-            .as_deref()
-            .unwrap_or(self.album.artist.as_str())
+        self.disc.resolved_artist(self.album)
     }
 
     pub fn catalog(&self) -> &str {
@@ -515,6 +537,14 @@ pub struct Track {
     #[serde(rename = "type")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub track_type: Option<TrackType>,
+    /// Track ReplayGain in dB
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gain: Option<f64>,
+    /// Track ReplayGain peak
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peak: Option<f64>,
     /// Track tags
     #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
@@ -534,6 +564,8 @@ impl Track {
             artist,
             artists,
             track_type,
+            gain: None,
+            peak: None,
             tags,
         }
     }
@@ -541,6 +573,18 @@ impl Track {
     pub fn empty() -> Self {
         Track::new(String::new(), None, None, None, Default::default())
     }
+
+    /// Track artist, falling back to disc artist and then album artist.
+    ///
+    /// An empty artist is treated the same as a missing one at each level.
+    pub fn resolved_artist<'a>(&'a self, disc: &'a DiscInfo, album: &'a AlbumInfo) -> &'a str {
+        own_artist(&self.artist).unwrap_or_else(|| disc.resolved_artist(album))
+    }
+}
+
+/// Artist set on an album, disc or track itself, which is inherited if absent or empty.
+fn own_artist(artist: &Option<String>) -> Option<&str> {
+    artist.as_deref().filter(|artist| !artist.is_empty())
 }
 
 #[derive(Clone)]
@@ -559,12 +603,7 @@ where
     }
 
     pub fn artist(&self) -> &'disc str {
-        self.track.artist.as_deref().unwrap_or_else(|| {
-            self.disc
-                .artist
-                .as_deref()
-                .unwrap_or(self.album.artist.as_str())
-        })
+        self.track.resolved_artist(self.disc, self.album)
     }
 
     pub fn artists(&self) -> Option<&'disc HashMap<String, String>> {
@@ -583,6 +622,16 @@ where
         })
     }
 
+    /// Track ReplayGain in dB
+    pub fn gain(&self) -> Option<f64> {
+        self.track.gain
+    }
+
+    /// Track ReplayGain peak
+    pub fn peak(&self) -> Option<f64> {
+        self.track.peak
+    }
+
     pub fn tags_iter<'me, 'tag>(&'me self) -> impl Iterator<Item = &'me TagRef<'tag>>
     where
         'tag: 'me,
@@ -652,15 +701,8 @@ where
 
     pub fn set_artist(&mut self, artist: Option<String>) {
         if let Some(artist) = artist {
-            let artist_str = artist.as_str();
-            let current_artist_str = self.track.artist.as_deref().unwrap_or_else(|| {
-                self.disc
-                    .artist
-                    .as_deref()
-                    .unwrap_or(self.album.artist.as_str())
-            });
-
-            if artist_str == current_artist_str {
+            // compare with the inherited artist, so that an artist equal to it is not stored
+            if artist.is_empty() || artist == self.disc.resolved_artist(self.album) {
                 self.track.artist = None;
             } else {
                 self.track.artist = Some(artist);
@@ -733,3 +775,113 @@ impl TrackType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Album;
+    use std::str::FromStr;
+
+    fn album() -> Album {
+        Album::from_str(
+            r#"[album]
+album_id = "7b5e2f3a-6c1d-4e8f-9a2b-3c4d5e6f7a01"
+title = "Title"
+artist = "Album Artist"
+date = 2999-12-31
+type = "normal"
+catalog = "TEST-0001"
+
+[[discs]]
+catalog = "TEST-0001"
+
+[[discs.tracks]]
+title = "Album artist"
+
+[[discs.tracks]]
+title = "Own artist"
+artist = "Track Artist"
+
+[[discs.tracks]]
+title = "Empty artist"
+artist = ""
+
+[[discs]]
+catalog = "TEST-0002"
+artist = "Disc Artist"
+
+[[discs.tracks]]
+title = "Disc artist"
+
+[[discs.tracks]]
+title = "Own artist"
+artist = "Track Artist"
+
+[[discs.tracks]]
+title = "Empty artist"
+artist = ""
+
+[[discs]]
+catalog = "TEST-0003"
+artist = ""
+
+[[discs.tracks]]
+title = "Album artist"
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_resolved_artist() {
+        let album = album();
+        let artists: Vec<Vec<_>> = album
+            .iter()
+            .map(|disc| {
+                disc.iter()
+                    .map(|track| track.artist().to_string())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            artists,
+            vec![
+                vec!["Album Artist", "Track Artist", "Album Artist"],
+                vec!["Disc Artist", "Track Artist", "Disc Artist"],
+                vec!["Album Artist"],
+            ]
+        );
+
+        let discs: Vec<_> = album.iter().collect();
+        assert_eq!(discs[0].artist(), "Album Artist");
+        assert_eq!(discs[1].artist(), "Disc Artist");
+        assert_eq!(discs[2].artist(), "Album Artist");
+        assert_eq!(discs[1].artist_raw(), Some("Disc Artist"));
+        assert_eq!(discs[2].artist_raw(), None);
+
+        let disc = &album.discs[1];
+        assert_eq!(
+            disc.tracks[0].resolved_artist(disc, &album.info),
+            "Disc Artist"
+        );
+    }
+
+    #[test]
+    fn test_set_artist() {
+        let mut album = album();
+        let mut disc = album.iter_mut().nth(1).unwrap();
+        let mut tracks = disc.iter_mut();
+
+        // artist equal to the inherited one is not stored
+        let mut track = tracks.next().unwrap();
+        track.set_artist(Some("Disc Artist".to_string()));
+        assert_eq!(track.artist, None);
+        track.set_artist(Some(String::new()));
+        assert_eq!(track.artist, None);
+
+        // setting own artist again keeps it
+        let mut track = tracks.next().unwrap();
+        track.set_artist(Some("Track Artist".to_string()));
+        assert_eq!(track.artist.as_deref(), Some("Track Artist"));
+        assert_eq!(track.artist(), "Track Artist");
+    }
+}
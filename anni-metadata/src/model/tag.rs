@@ -76,6 +76,7 @@ impl<'a> TagRef<'a> {
         Tag {
             inner: self.full_clone(),
             names: Default::default(),
+            aliases: Vec::new(),
             parents,
             children: Vec::new(),
         }
@@ -189,7 +190,7 @@ impl From<TagRef<'static>> for TagString {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq)]
 #[serde(deny_unknown_fields)]
 pub struct Tag {
     #[serde(flatten)]
@@ -197,6 +198,10 @@ pub struct Tag {
     /// Tag localized name
     #[serde(default)]
     names: HashMap<String, String>,
+    /// Other spellings of tag name
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    aliases: Vec<String>,
     /// Tag parents
     #[serde(default)]
     #[serde(rename = "included-by")]
@@ -297,6 +302,10 @@ impl Tag {
         self.inner.tag_type()
     }
 
+    pub fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+
     pub fn parents<'me, 'tag>(&'me self) -> impl Iterator<Item = &'me TagRef<'tag>>
     where
         'tag: 'me,
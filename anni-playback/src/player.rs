@@ -19,7 +19,9 @@ use anni_common::models::TrackIdentifier;
 
 use crate::{
     sources::cached_http::{
-        cache::CacheStore, provider::ProviderProxy, CachedAnnilSource, OpenTrackError,
+        cache::{CacheCleanReport, CacheStore},
+        provider::ProviderProxy,
+        CachedAnnilSource, OpenTrackError,
     },
     types::PlayerEvent,
     Controls, Decoder,
@@ -92,6 +94,13 @@ impl AnniPlayer {
         *provider = TypedPriorityProvider::new(vec![]);
     }
 
+    /// Removes partial or orphaned entries in the cache directory.
+    ///
+    /// See [`CacheStore::verify_and_clean`] for details.
+    pub fn clean_cache(&self) -> std::io::Result<CacheCleanReport> {
+        self.cache_store.verify_and_clean()
+    }
+
     pub fn open(
         &self,
         track: TrackIdentifier,
@@ -120,6 +129,30 @@ impl AnniPlayer {
         Ok(())
     }
 
+    /// Downloads `track` into cache in background, so that it can be opened instantly later.
+    ///
+    /// This is usually called for the upcoming track to make the transition gapless.
+    pub fn prefetch(
+        &self,
+        track: TrackIdentifier,
+        quality: AudioQuality,
+        opus: bool,
+    ) -> Result<(), OpenTrackError> {
+        log::debug!("prefetching track: {track}");
+
+        let provider = self.provider.read().unwrap();
+        CachedAnnilSource::prefetch(
+            track,
+            quality,
+            &self.cache_store,
+            self.client.clone(),
+            &provider,
+            opus,
+        )?;
+
+        Ok(())
+    }
+
     pub fn open_and_play(
         &self,
         track: TrackIdentifier,
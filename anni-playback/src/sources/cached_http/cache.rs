@@ -3,6 +3,10 @@ use std::{
     fs::{self, File},
     io::{self, ErrorKind},
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Condvar, Mutex,
+    },
 };
 
 use crate::CODEC_REGISTRY;
@@ -16,18 +20,30 @@ use symphonia::{
     default::get_probe,
 };
 
+use super::OpenTrackError;
 use anni_common::models::RawTrackIdentifier;
 use symphonia_core::io::MediaSource;
 use thiserror::Error;
 
+/// Max number of tracks being prefetched at the same time.
+pub const MAX_PREFETCH: usize = 2;
+
 #[derive(Debug, Clone)]
 pub struct CacheStore {
     base: PathBuf,
+    /// Tracks being downloaded, shared by all sources of the same track
+    downloads: Arc<Mutex<HashMap<PathBuf, Arc<Download>>>>,
+    /// Number of tracks being prefetched
+    prefetching: Arc<AtomicUsize>,
 }
 
 impl CacheStore {
     pub fn new(base: PathBuf) -> Self {
-        Self { base }
+        Self {
+            base,
+            downloads: Default::default(),
+            prefetching: Default::default(),
+        }
     }
 
     /// Returns the path to given `track`
@@ -47,22 +63,31 @@ impl CacheStore {
 
     /// Attempts to open a cache file corresponding to `track` and validates it.
     ///
-    /// On success, returns a `Result<File, (File, File)>`.
-    /// If the cache exists and is valid, opens it in read mode and returns an `Ok(_)`.
+    /// If the track is being downloaded, opens the cache file in read mode and returns
+    /// [`CacheEntry::Downloading`] to follow the download.
+    /// If the cache exists and is valid, opens it in read mode and returns [`CacheEntry::Cached`].
     /// Otherwise, creates or truncates a cache file, opens it in read mode as a `reader`
-    /// and append mode as a `writer`, and returns an `Err((reader, writer))`
+    /// and append mode as a `writer`, and returns [`CacheEntry::Created`].
     ///
     /// On error, an [`Error`](std::io::Error) is returned.
-    pub fn acquire(&self, track: RawTrackIdentifier) -> io::Result<Result<File, (File, File)>> {
+    pub fn acquire(&self, track: RawTrackIdentifier) -> io::Result<CacheEntry> {
         let path = self.loaction_of(track.copied());
 
+        let mut downloads = self.downloads.lock().unwrap();
+        if let Some(download) = downloads.get(&path) {
+            return Ok(CacheEntry::Downloading(
+                File::open(&path)?,
+                Arc::clone(download),
+            ));
+        }
+
         if path.exists() {
             let content_length = self.acquire_info::<u64>(track.copied(), "content-length")?;
             let f = File::open(&path)?;
 
             if content_length == Some(f.metadata()?.len()) || validate_audio(&path).unwrap_or(false)
             {
-                return Ok(Ok(f));
+                return Ok(CacheEntry::Cached(f));
             }
 
             log::warn!("cache of {track} exists but is invalid");
@@ -77,18 +102,42 @@ impl CacheStore {
             .open(&path)?; // truncate the file first to clear incorrect data
 
         let reader = File::options().read(true).open(&path)?;
-        let writer = File::options().append(true).open(path)?;
+        let writer = File::options().append(true).open(&path)?;
+
+        let download = Arc::new(Download::new());
+        downloads.insert(path.clone(), Arc::clone(&download));
+
+        Ok(CacheEntry::Created {
+            reader,
+            writer,
+            guard: DownloadGuard {
+                store: self.clone(),
+                path,
+                download,
+            },
+        })
+    }
 
-        Ok(Err((reader, writer)))
+    /// Reserves a slot for prefetching.
+    ///
+    /// Returns `None` if [`MAX_PREFETCH`] tracks are being prefetched.
+    pub fn try_prefetch(&self) -> Option<PrefetchPermit> {
+        self.prefetching
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                (n < MAX_PREFETCH).then_some(n + 1)
+            })
+            .ok()
+            .map(|_| PrefetchPermit(Arc::clone(&self.prefetching)))
     }
 
     pub fn add(&self, path: &Path, track: RawTrackIdentifier) -> io::Result<()> {
-        let location = self.loaction_of(track);
+        let location = self.loaction_of(track.copied());
 
         if location.exists() {
             Err(ErrorKind::AlreadyExists.into())
         } else if validate_audio(path).unwrap_or(false) {
-            fs::copy(path, location).map(|_| {})
+            let content_length = fs::copy(path, location)?;
+            self.store_info(track, "content-length", content_length)
         } else {
             Err(io::Error::new(ErrorKind::Other, "invalid cache"))
         }
@@ -144,6 +193,242 @@ impl CacheStore {
     }
 }
 
+/// Result of [`CacheStore::acquire`].
+pub enum CacheEntry {
+    /// The cache is complete.
+    Cached(File),
+    /// The track is being downloaded by another source.
+    Downloading(File, Arc<Download>),
+    /// The cache is created or truncated, and should be downloaded by the caller.
+    ///
+    /// The download is finished when `guard` is dropped.
+    Created {
+        reader: File,
+        writer: File,
+        guard: DownloadGuard,
+    },
+}
+
+/// Progress of a track being downloaded into cache.
+///
+/// Readers can wait for new bytes with [`Download::wait_for`], which sleeps until the writer
+/// makes progress or finishes.
+#[derive(Debug)]
+pub struct Download {
+    state: Mutex<DownloadState>,
+    cond: Condvar,
+}
+
+#[derive(Debug)]
+struct DownloadState {
+    buf_len: usize,
+    is_buffering: bool,
+    /// Reason of failure if the download finished early
+    error: Option<String>,
+}
+
+impl Download {
+    fn new() -> Self {
+        Self {
+            state: Mutex::new(DownloadState {
+                buf_len: 0,
+                is_buffering: true,
+                error: None,
+            }),
+            cond: Condvar::new(),
+        }
+    }
+
+    /// Creates a download that has already finished with `buf_len` bytes.
+    pub fn finished(buf_len: usize) -> Self {
+        Self {
+            state: Mutex::new(DownloadState {
+                buf_len,
+                is_buffering: false,
+                error: None,
+            }),
+            cond: Condvar::new(),
+        }
+    }
+
+    /// Number of bytes written to cache.
+    pub fn buf_len(&self) -> usize {
+        self.state.lock().unwrap().buf_len
+    }
+
+    pub fn is_buffering(&self) -> bool {
+        self.state.lock().unwrap().is_buffering
+    }
+
+    /// Records that `n` more bytes were written to cache, and wakes up waiting readers.
+    pub fn advance(&self, n: usize) {
+        self.state.lock().unwrap().buf_len += n;
+        self.cond.notify_all();
+    }
+
+    /// Marks the download as finished, and wakes up waiting readers.
+    fn finish(&self) {
+        self.state.lock().unwrap().is_buffering = false;
+        self.cond.notify_all();
+    }
+
+    /// Marks the download as failed, so that readers get an error instead of a clean EOF.
+    pub fn fail(&self, error: String) {
+        let mut state = self.state.lock().unwrap();
+        state.error = Some(error);
+        state.is_buffering = false;
+        drop(state);
+        self.cond.notify_all();
+    }
+
+    /// Blocks until more than `pos` bytes are written or the download finishes.
+    ///
+    /// Returns the number of bytes written to cache.
+    /// If the download failed and no more than `pos` bytes were written, returns an error.
+    pub fn wait_for(&self, pos: usize) -> io::Result<usize> {
+        let state = self
+            .cond
+            .wait_while(self.state.lock().unwrap(), |state| {
+                state.buf_len <= pos && state.is_buffering
+            })
+            .unwrap();
+        match &state.error {
+            Some(error) if state.buf_len <= pos => Err(io::Error::new(
+                ErrorKind::Other,
+                OpenTrackError::DownloadFailed(error.clone()),
+            )),
+            _ => Ok(state.buf_len),
+        }
+    }
+}
+
+/// Marks a download as finished and unregisters it from [`CacheStore`] on drop.
+pub struct DownloadGuard {
+    store: CacheStore,
+    path: PathBuf,
+    download: Arc<Download>,
+}
+
+impl DownloadGuard {
+    pub fn download(&self) -> &Arc<Download> {
+        &self.download
+    }
+}
+
+impl Drop for DownloadGuard {
+    fn drop(&mut self) {
+        self.store.downloads.lock().unwrap().remove(&self.path);
+        self.download.finish();
+    }
+}
+
+/// A slot reserved by [`CacheStore::try_prefetch`], released on drop.
+pub struct PrefetchPermit(Arc<AtomicUsize>);
+
+impl Drop for PrefetchPermit {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Summary of [`CacheStore::verify_and_clean`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheCleanReport {
+    /// Number of removed files.
+    pub removed_files: usize,
+    /// Total size of removed files in bytes.
+    pub reclaimed_bytes: u64,
+}
+
+impl CacheCleanReport {
+    fn remove(&mut self, path: &Path) -> io::Result<()> {
+        let len = fs::metadata(path)?.len();
+        fs::remove_file(path)?;
+
+        self.removed_files += 1;
+        self.reclaimed_bytes += len;
+        Ok(())
+    }
+}
+
+impl CacheStore {
+    /// Removes partial or orphaned entries in the cache directory.
+    ///
+    /// The following files are removed:
+    /// - files with unknown extensions, such as temporary files left by crashes
+    /// - info files without cached audio
+    /// - cached audio without info, or whose size does not match `content-length` in info
+    ///
+    /// Empty album directories are removed as well.
+    /// This method should be called before any track is opened, e.g. on player startup.
+    pub fn verify_and_clean(&self) -> io::Result<CacheCleanReport> {
+        let mut report = CacheCleanReport::default();
+
+        let albums = match fs::read_dir(&self.base) {
+            Ok(albums) => albums,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(report),
+            Err(e) => return Err(e),
+        };
+
+        for album in albums {
+            let album = album?.path();
+            if !album.is_dir() {
+                continue;
+            }
+
+            for entry in fs::read_dir(&album)? {
+                let path = entry?.path();
+                // the file may have been removed with its audio
+                if !path.is_file() {
+                    continue;
+                }
+
+                match path.extension() {
+                    // cached audio
+                    None => {
+                        if !is_complete_audio(&path) {
+                            log::warn!("removing partial cache {}", path.display());
+                            report.remove(&path)?;
+
+                            let info = path.with_extension("info");
+                            if info.is_file() {
+                                report.remove(&info)?;
+                            }
+                        }
+                    }
+                    Some(ext) if ext == "info" => {
+                        if !path.with_extension("").is_file() {
+                            log::warn!("removing orphaned cache info {}", path.display());
+                            report.remove(&path)?;
+                        }
+                    }
+                    Some(_) => {
+                        log::warn!("removing unknown cache file {}", path.display());
+                        report.remove(&path)?;
+                    }
+                }
+            }
+
+            if fs::read_dir(&album)?.next().is_none() {
+                fs::remove_dir(&album)?;
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Whether the size of cached audio at `path` matches `content-length` in its info.
+fn is_complete_audio(path: &Path) -> bool {
+    let content_length = File::open(path.with_extension("info"))
+        .ok()
+        .and_then(|f| read_info(&f).ok())
+        .and_then(|mut info| info.remove("content-length"))
+        .and_then(|v| v.as_u64());
+
+    content_length.is_some_and(|len| fs::metadata(path).is_ok_and(|m| m.len() == len))
+}
+
 fn read_info(f: &File) -> serde_json::Result<HashMap<String, Value>> {
     serde_json::from_reader(f)
 }
@@ -196,3 +481,53 @@ pub enum ValidationError {
     #[error("Validation is not supported on the source")]
     Unsupported,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Download;
+    use std::sync::{mpsc, Arc};
+    use std::thread;
+    use std::time::Duration;
+
+    const TIMEOUT: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn test_wait_for_blocks_until_progress_or_eof() {
+        let download = Arc::new(Download::new());
+        download.advance(16);
+
+        let (sender, receiver) = mpsc::channel();
+        let reader = thread::spawn({
+            let download = Arc::clone(&download);
+            move || {
+                // read slightly ahead of the writer
+                sender.send(download.wait_for(16).unwrap()).unwrap();
+                sender.send(download.wait_for(32).unwrap()).unwrap();
+            }
+        });
+
+        // the reader is sleeping, instead of returning early
+        assert!(receiver.recv_timeout(TIMEOUT).is_err());
+        download.advance(16);
+        assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(32));
+
+        // no more bytes, until eof wakes the reader up
+        assert!(receiver.recv_timeout(TIMEOUT).is_err());
+        download.finish();
+        assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(32));
+        assert!(!download.is_buffering());
+
+        reader.join().unwrap();
+    }
+
+    #[test]
+    fn test_failed_download_is_not_eof() {
+        let download = Download::new();
+        download.advance(16);
+        download.fail("connection reset".to_string());
+
+        // written bytes can still be read
+        assert_eq!(download.wait_for(8).unwrap(), 16);
+        assert!(download.wait_for(16).is_err());
+    }
+}
@@ -3,35 +3,33 @@ pub mod provider;
 
 use std::{
     fs::File,
-    hint::spin_loop,
-    io::{ErrorKind, Read, Seek, Write},
-    sync::{
-        atomic::{AtomicBool, AtomicUsize, Ordering},
-        Arc,
-    },
-    thread,
+    io::{self, ErrorKind, Read, Seek, Write},
+    sync::{atomic::AtomicBool, Arc},
+    thread::{self, JoinHandle},
 };
 
 use anni_common::models::TrackIdentifier;
 use anni_provider::providers::TypedPriorityProvider;
-use reqwest::{blocking::Client, Url};
+use reqwest::{blocking::Client, header::RANGE, StatusCode, Url};
 use thiserror::Error;
 
 use crate::types::MediaSource;
 use provider::{AudioQuality, ProviderProxy};
 
-use cache::CacheStore;
+use cache::{CacheEntry, CacheStore, Download, DownloadGuard, PrefetchPermit};
 
 use super::AnniSource;
 
 const BUF_SIZE: usize = 1024 * 64; // 64k
 
+/// Max attempts to download a track, including the first one.
+const MAX_ATTEMPTS: usize = 3;
+
 pub struct CachedHttpSource {
     identifier: TrackIdentifier,
     cache: File,
-    buf_len: Arc<AtomicUsize>,
+    download: Arc<Download>,
     pos: usize,
-    is_buffering: Arc<AtomicBool>,
     #[allow(unused)]
     buffer_signal: Arc<AtomicBool>,
     duration: Option<u64>,
@@ -47,112 +45,188 @@ impl CachedHttpSource {
         client: Client,
         buffer_signal: Arc<AtomicBool>,
     ) -> Result<Self, OpenTrackError> {
-        let (reader, writer) = match cache_store.acquire(identifier.inner.copied())? {
-            Ok(cache) => {
+        Self::open(identifier, url, cache_store, client, buffer_signal, None)
+            .map(|(source, _)| source)
+    }
+
+    /// Opens the source like [`CachedHttpSource::new`], and returns the handle of download thread if a download is started.
+    ///
+    /// `permit` is held by the download thread until it finishes.
+    fn open(
+        identifier: TrackIdentifier,
+        url: impl FnOnce() -> Option<(Url, Option<u64>, Option<u64>)>,
+        cache_store: &CacheStore,
+        client: Client,
+        buffer_signal: Arc<AtomicBool>,
+        permit: Option<PrefetchPermit>,
+    ) -> Result<(Self, Option<JoinHandle<()>>), OpenTrackError> {
+        let (reader, mut writer, guard) = match cache_store.acquire(identifier.inner.copied())? {
+            CacheEntry::Cached(cache) => {
                 let buf_len = cache.metadata()?.len() as usize;
 
-                return Ok(Self {
-                    identifier,
-                    cache,
-                    buf_len: Arc::new(AtomicUsize::new(buf_len)),
-                    pos: 0,
-                    is_buffering: Arc::new(AtomicBool::new(false)),
-                    buffer_signal,
-                    duration: None,
-                    content_length: Some(buf_len as u64),
-                });
+                return Ok((
+                    Self {
+                        identifier,
+                        cache,
+                        download: Arc::new(Download::finished(buf_len)),
+                        pos: 0,
+                        buffer_signal,
+                        duration: None,
+                        content_length: Some(buf_len as u64),
+                    },
+                    None,
+                ));
+            }
+            CacheEntry::Downloading(cache, download) => {
+                log::debug!("{identifier} is being downloaded, reading from it");
+                let content_length =
+                    cache_store.acquire_info(identifier.inner.copied(), "content-length")?;
+
+                return Ok((
+                    Self {
+                        identifier,
+                        cache,
+                        download,
+                        pos: 0,
+                        buffer_signal,
+                        duration: None,
+                        content_length,
+                    },
+                    None,
+                ));
             }
-            Err(cache) => cache,
+            CacheEntry::Created {
+                reader,
+                writer,
+                guard,
+            } => (reader, writer, guard),
         };
 
-        let buf_len = Arc::new(AtomicUsize::new(0));
-        let is_buffering = Arc::new(AtomicBool::new(true));
+        let download = Arc::clone(guard.download());
 
         let (url, duration, content_length) = url().ok_or(OpenTrackError::NoAvailableAnnil)?;
 
         log::debug!("got duration {duration:?}");
 
-        thread::spawn({
-            let mut cache = writer;
-            let buf_len = Arc::clone(&buf_len);
-            let mut buf = [0; BUF_SIZE];
-            let is_buffering = Arc::clone(&is_buffering);
+        let handle = thread::spawn({
             let identifier = identifier.clone();
 
             move || {
-                let mut response = match client.get(url).send() {
-                    Ok(r) => r,
+                // released when download finishes
+                let _permit = permit;
+
+                match download_to(&client, url, &mut writer, &guard, content_length) {
+                    Ok(()) => log::info!("{identifier} reached eof"),
                     Err(e) => {
-                        log::error!("failed to send request: {e}");
-                        is_buffering.store(false, Ordering::Release);
-                        return;
+                        log::error!("failed to download {identifier}: {e}");
+                        guard.download().fail(e.to_string());
                     }
-                };
-
-                loop {
-                    match response.read(&mut buf) {
-                        Ok(0) => {
-                            log::info!("{identifier} reached eof");
-                            break;
-                        }
-                        Ok(n) => {
-                            if let Err(e) = cache.write_all(&buf[..n]) {
-                                log::error!("{e}");
-                                break;
-                            }
-
-                            let _ = cache.flush();
-                            buf_len.fetch_add(n, Ordering::AcqRel);
-
-                            log::trace!("wrote {n} bytes to {identifier}");
-                        }
-                        Err(e) if e.kind() == ErrorKind::Interrupted => {}
-                        Err(e) => {
-                            log::error!("{e}");
-                            break;
-                        }
+                }
+            }
+        });
+
+        Ok((
+            Self {
+                identifier,
+                cache: reader,
+                download,
+                pos: 0,
+                buffer_signal,
+                duration,
+                content_length,
+            },
+            Some(handle),
+        ))
+    }
+}
+
+/// Downloads `url` into `writer`.
+///
+/// If the connection breaks, the download is resumed from written bytes with a `Range` request,
+/// until [`MAX_ATTEMPTS`] is reached.
+fn download_to(
+    client: &Client,
+    url: Url,
+    writer: &mut File,
+    guard: &DownloadGuard,
+    content_length: Option<u64>,
+) -> anyhow::Result<()> {
+    let mut buf = [0; BUF_SIZE];
+    let mut written = 0;
+    let mut attempt = 1;
+
+    loop {
+        let error: anyhow::Error = 'attempt: {
+            let mut request = client.get(url.clone());
+            if written > 0 {
+                request = request.header(RANGE, format!("bytes={written}-"));
+            }
+
+            let mut response = match request.send().and_then(|r| r.error_for_status()) {
+                Ok(r) => r,
+                Err(e) => break 'attempt e.into(),
+            };
+
+            // skip written bytes if range is not supported by server
+            if written > 0 && response.status() != StatusCode::PARTIAL_CONTENT {
+                if let Err(e) = io::copy(&mut (&mut response).take(written), &mut io::sink()) {
+                    break 'attempt e.into();
+                }
+            }
+
+            loop {
+                match response.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        // failed to write to cache, retrying does not help
+                        writer.write_all(&buf[..n])?;
+                        let _ = writer.flush();
+                        written += n as u64;
+                        guard.download().advance(n);
+
+                        log::trace!("wrote {n} bytes");
                     }
+                    Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                    Err(e) => break 'attempt e.into(),
                 }
+            }
 
-                is_buffering.store(false, Ordering::Release);
+            match content_length {
+                Some(len) if written < len => {
+                    break 'attempt anyhow::anyhow!(
+                        "connection closed after {written} of {len} bytes"
+                    )
+                }
+                _ => return Ok(()),
             }
-        });
+        };
 
-        Ok(Self {
-            identifier,
-            cache: reader,
-            buf_len,
-            pos: 0,
-            is_buffering,
-            buffer_signal,
-            duration,
-            content_length,
-        })
+        if attempt >= MAX_ATTEMPTS {
+            return Err(error);
+        }
+        log::warn!(
+            "download interrupted at {written} bytes, retrying ({attempt}/{MAX_ATTEMPTS}): {error}"
+        );
+        attempt += 1;
     }
 }
 
 impl Read for CachedHttpSource {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        // A naive spin loop that waits until we have more data to read.
-        loop {
-            let is_buffering = self.is_buffering.load(Ordering::Acquire);
-            let buf_len = self.buf_len.load(Ordering::Acquire);
-            let has_buf = buf_len > self.pos;
-
-            if has_buf {
-                let n = <File as Read>::by_ref(&mut self.cache)
-                    .take((buf_len - self.pos) as u64)
-                    .read(buf)?; // ensure not exceeding the buffer
-
-                log::trace!("read {n} bytes from {}", self.identifier);
-
-                self.pos += n;
-                break Ok(n);
-            } else if !is_buffering {
-                break Ok(0);
-            } else {
-                spin_loop();
-            }
+        // Sleep until we have more data to read, or the download finishes.
+        let buf_len = self.download.wait_for(self.pos)?;
+
+        if buf_len > self.pos {
+            let n = <File as Read>::by_ref(&mut self.cache)
+                .take((buf_len - self.pos) as u64)
+                .read(buf)?; // ensure not exceeding the buffer
+
+            log::trace!("read {n} bytes from {}", self.identifier);
+
+            self.pos += n;
+            Ok(n)
+        } else {
+            Ok(0)
         }
     }
 }
@@ -167,7 +241,7 @@ impl Seek for CachedHttpSource {
 
 impl MediaSource for CachedHttpSource {
     fn is_seekable(&self) -> bool {
-        !self.is_buffering.load(Ordering::Acquire)
+        !self.download.is_buffering()
     }
 
     fn byte_len(&self) -> Option<u64> {
@@ -194,12 +268,50 @@ impl CachedAnnilSource {
         buffer_signal: Arc<AtomicBool>,
         opus: bool,
     ) -> Result<Self, OpenTrackError> {
-        let cloned_track = track.clone();
+        let url = url_of(track.clone(), quality, cache_store, provider, opus);
+
+        CachedHttpSource::new(track, url, cache_store, client, buffer_signal).map(Self)
+    }
 
-        let mut source = provider
+    /// Starts downloading `track` into `cache_store` in background, so that it can be opened from cache later.
+    ///
+    /// Returns the handle of download thread, or `None` if the track is already cached or being downloaded,
+    /// or [`MAX_PREFETCH`](cache::MAX_PREFETCH) tracks are being prefetched.
+    pub fn prefetch(
+        track: TrackIdentifier,
+        quality: AudioQuality,
+        cache_store: &CacheStore,
+        client: Client,
+        provider: &TypedPriorityProvider<ProviderProxy>,
+        opus: bool,
+    ) -> Result<Option<JoinHandle<()>>, OpenTrackError> {
+        let Some(permit) = cache_store.try_prefetch() else {
+            log::debug!("too many tracks are being prefetched, skipping {track}");
+            return Ok(None);
+        };
+
+        let url = url_of(track.clone(), quality, cache_store, provider, opus);
+        let buffer_signal = Arc::new(AtomicBool::new(true));
+        let (_, handle) =
+            CachedHttpSource::open(track, url, cache_store, client, buffer_signal, Some(permit))?;
+
+        Ok(handle)
+    }
+}
+
+/// Returns a function that finds an available url of `track` from `provider`.
+fn url_of<'a>(
+    track: TrackIdentifier,
+    quality: AudioQuality,
+    cache_store: &'a CacheStore,
+    provider: &'a TypedPriorityProvider<ProviderProxy>,
+    opus: bool,
+) -> impl FnOnce() -> Option<(Url, Option<u64>, Option<u64>)> + 'a {
+    move || {
+        provider
             .providers()
             .filter_map(|p| {
-                p.head(cloned_track.inner.copied(), quality, opus)
+                p.head(track.inner.copied(), quality, opus)
                     .and_then(|r| r.error_for_status())
                     .inspect_err(|e| log::warn!("{e}"))
                     .ok()
@@ -210,15 +322,14 @@ impl CachedAnnilSource {
                 let duration = parse_header("X-Duration-Seconds").and_then(|v| v.parse().ok());
                 if let Some(content_length) = r.content_length() {
                     let _ = cache_store.store_info(
-                        cloned_track.inner.copied(),
+                        track.inner.copied(),
                         "content-length",
                         content_length,
                     );
                 }
                 (url.clone(), duration, r.content_length())
-            });
-
-        CachedHttpSource::new(track, || source.next(), cache_store, client, buffer_signal).map(Self)
+            })
+            .next()
     }
 }
 
@@ -254,6 +365,8 @@ impl AnniSource for CachedAnnilSource {
 pub enum OpenTrackError {
     #[error("No available annil")]
     NoAvailableAnnil,
+    #[error("Download failed: {0}")]
+    DownloadFailed(String),
     #[error("Io Error: {0}")]
     Io(#[from] std::io::Error),
 }
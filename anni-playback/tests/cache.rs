@@ -0,0 +1,55 @@
+use std::{fs, num::NonZeroU8};
+
+use anni_common::models::RawTrackIdentifier;
+use anni_playback::sources::cached_http::cache::CacheStore;
+
+const ALBUM_ID: &str = "4f4e4b1a-0c5b-4b7a-9d1e-5f2a3c6b7d8e";
+
+fn track(track_id: u8) -> RawTrackIdentifier<'static> {
+    RawTrackIdentifier::new(
+        ALBUM_ID,
+        NonZeroU8::new(1).unwrap(),
+        NonZeroU8::new(track_id).unwrap(),
+    )
+}
+
+#[test]
+fn test_verify_and_clean() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let store = CacheStore::new(dir.path().to_path_buf());
+    let album = dir.path().join(ALBUM_ID);
+    fs::create_dir_all(&album)?;
+
+    // valid entry
+    fs::write(store.loaction_of(track(1)), [0; 16])?;
+    store.store_info(track(1), "content-length", 16)?;
+    // partial download
+    fs::write(store.loaction_of(track(2)), [0; 8])?;
+    store.store_info(track(2), "content-length", 16)?;
+    // data without info
+    fs::write(store.loaction_of(track(3)), [0; 4])?;
+    // info without data
+    store.store_info(track(4), "content-length", 16)?;
+    // orphaned temp file
+    fs::write(album.join("1_5.tmp"), [0; 2])?;
+    // empty album directory
+    fs::create_dir_all(dir.path().join("empty"))?;
+
+    let report = store.verify_and_clean()?;
+    assert_eq!(report.removed_files, 5);
+    // 8 + 4 + 2 bytes of data, and 2 info files
+    assert!(report.reclaimed_bytes > 14);
+
+    let mut files: Vec<_> = fs::read_dir(&album)?
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    files.sort();
+    assert_eq!(files, ["1_1", "1_1.info"]);
+    assert!(!dir.path().join("empty").exists());
+
+    // nothing to clean
+    let report = store.verify_and_clean()?;
+    assert_eq!(report.removed_files, 0);
+    assert_eq!(report.reclaimed_bytes, 0);
+    Ok(())
+}
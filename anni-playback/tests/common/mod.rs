@@ -0,0 +1,72 @@
+//! Mock HTTP server for tests of cached http sources.
+#![allow(dead_code)]
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+/// `Range` headers of requests served by [serve], in order.
+pub type Requests = Arc<Mutex<Vec<Option<String>>>>;
+
+/// Serves `body` for every request, with `Range: bytes={start}-` supported.
+///
+/// At most `limit` bytes are sent per response before the connection is closed.
+/// Returns the url and requests served.
+pub fn serve(body: Vec<u8>, limit: usize) -> (String, Requests) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Requests::default();
+
+    thread::spawn({
+        let requests = Arc::clone(&requests);
+        move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(&mut stream);
+                let mut range = None;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((key, value)) = line.split_once(": ") {
+                        if key.eq_ignore_ascii_case("range") {
+                            range = Some(value.to_string());
+                        }
+                    }
+                }
+
+                let start = range
+                    .as_deref()
+                    .and_then(|r| r.strip_prefix("bytes="))
+                    .and_then(|r| r.strip_suffix('-'))
+                    .map_or(0, |start| start.parse().unwrap());
+                let status = if range.is_some() {
+                    format!(
+                        "206 Partial Content\r\nContent-Range: bytes {start}-{}/{}",
+                        body.len() - 1,
+                        body.len()
+                    )
+                } else {
+                    "200 OK".to_string()
+                };
+                requests.lock().unwrap().push(range);
+
+                let rest = &body[start..];
+                let head = format!(
+                    "HTTP/1.1 {status}\r\nContent-Length: {}\r\nX-Duration-Seconds: 1\r\nConnection: close\r\n\r\n",
+                    rest.len()
+                );
+                let _ = stream.write_all(head.as_bytes());
+                let _ = stream.write_all(&rest[..rest.len().min(limit)]);
+            }
+        }
+    });
+
+    (url, requests)
+}
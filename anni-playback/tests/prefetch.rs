@@ -0,0 +1,96 @@
+use std::{
+    io::Read,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use anni_common::models::TrackIdentifier;
+use anni_playback::{
+    player::TypedPriorityProvider,
+    sources::cached_http::{
+        cache::CacheStore,
+        provider::{AudioQuality, ProviderProxy},
+        CachedAnnilSource,
+    },
+    types::MediaSource,
+};
+use reqwest::blocking::Client;
+
+mod common;
+
+const TRACK: &str = "4f4e4b1a-0c5b-4b7a-9d1e-5f2a3c6b7d8e/1/1";
+
+#[test]
+fn test_prefetch_then_open_from_cache() {
+    let body: Vec<u8> = (0..=255).cycle().take(256 * 1024).collect();
+    let (url, requests) = common::serve(body.clone(), usize::MAX);
+
+    let dir = tempfile::tempdir().unwrap();
+    let store = CacheStore::new(dir.path().to_path_buf());
+    let client = Client::new();
+    let provider = TypedPriorityProvider::new(vec![(
+        0,
+        ProviderProxy::new(url, String::new(), client.clone()),
+    )]);
+    let track: TrackIdentifier = TRACK.parse().unwrap();
+
+    let handle = CachedAnnilSource::prefetch(
+        track.clone(),
+        AudioQuality::Lossless,
+        &store,
+        client.clone(),
+        &provider,
+        false,
+    )
+    .unwrap()
+    .expect("download should be started");
+    handle.join().unwrap();
+    let served = requests.lock().unwrap().len();
+
+    let mut source = CachedAnnilSource::new(
+        track.clone(),
+        AudioQuality::Lossless,
+        &store,
+        client.clone(),
+        &provider,
+        Arc::new(AtomicBool::new(true)),
+        false,
+    )
+    .unwrap();
+    assert!(source.is_seekable());
+    assert_eq!(source.byte_len(), Some(body.len() as u64));
+
+    let mut data = Vec::new();
+    source.read_to_end(&mut data).unwrap();
+    assert_eq!(data, body);
+    // served from cache without any request
+    assert_eq!(requests.lock().unwrap().len(), served);
+
+    // nothing to prefetch for a cached track
+    let handle = CachedAnnilSource::prefetch(
+        track,
+        AudioQuality::Lossless,
+        &store,
+        client,
+        &provider,
+        false,
+    )
+    .unwrap();
+    assert!(handle.is_none());
+}
+
+#[test]
+fn test_prefetch_limit() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = CacheStore::new(dir.path().to_path_buf());
+
+    let permits: Vec<_> = std::iter::from_fn(|| store.try_prefetch())
+        .take(16)
+        .collect();
+    assert_eq!(
+        permits.len(),
+        anni_playback::sources::cached_http::cache::MAX_PREFETCH
+    );
+
+    drop(permits);
+    assert!(store.try_prefetch().is_some());
+}
@@ -0,0 +1,62 @@
+use std::{
+    io::Read,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use anni_common::models::TrackIdentifier;
+use anni_playback::sources::cached_http::{cache::CacheStore, CachedHttpSource, OpenTrackError};
+use reqwest::{blocking::Client, Url};
+
+mod common;
+
+const TRACK: &str = "4f4e4b1a-0c5b-4b7a-9d1e-5f2a3c6b7d8e/1/1";
+
+/// Serves `body`, but sends at most `limit` bytes per response before closing the connection.
+fn serve(body: Vec<u8>, limit: usize) -> (Url, common::Requests) {
+    let (url, requests) = common::serve(body, limit);
+    (Url::parse(&format!("{url}/track")).unwrap(), requests)
+}
+
+fn open(url: Url, len: usize) -> (CachedHttpSource, tempfile::TempDir) {
+    let dir = tempfile::tempdir().unwrap();
+    let store = CacheStore::new(dir.path().to_path_buf());
+    let source = CachedHttpSource::new(
+        TRACK.parse::<TrackIdentifier>().unwrap(),
+        || Some((url, None, Some(len as u64))),
+        &store,
+        Client::new(),
+        Arc::new(AtomicBool::new(true)),
+    )
+    .unwrap();
+    (source, dir)
+}
+
+#[test]
+fn test_resume_truncated_download() {
+    let body: Vec<u8> = (0..=255).cycle().take(100_000).collect();
+    let (url, ranges) = serve(body.clone(), 60_000);
+
+    let (mut source, _dir) = open(url, body.len());
+    let mut data = Vec::new();
+    source.read_to_end(&mut data).unwrap();
+    assert_eq!(data, body);
+
+    assert_eq!(
+        *ranges.lock().unwrap(),
+        [None, Some("bytes=60000-".to_string())]
+    );
+}
+
+#[test]
+fn test_give_up_after_max_attempts() {
+    let body: Vec<u8> = (0..=255).cycle().take(100_000).collect();
+    let (url, ranges) = serve(body.clone(), 10_000);
+
+    let (mut source, _dir) = open(url, body.len());
+    let mut data = Vec::new();
+    let error = source.read_to_end(&mut data).unwrap_err();
+    // all downloaded bytes are read before the error
+    assert_eq!(data, body[..30_000]);
+    assert!(error.get_ref().is_some_and(|e| e.is::<OpenTrackError>()));
+    assert_eq!(ranges.lock().unwrap().len(), 3);
+}
@@ -1,19 +1,25 @@
-use crate::{AnniProvider, AudioInfo, AudioResourceReader, ProviderError, Range, ResourceReader};
+use crate::{
+    AnniProvider, AudioInfo, AudioResourceReader, ProviderError, Range, ReloadReport,
+    ResourceReader,
+};
 use anni_common::models::{RawTrackIdentifier, TrackIdentifier};
 use async_trait::async_trait;
+use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
 use lru::LruCache;
 use parking_lot::RwLock;
+use sha2::{Digest, Sha256};
 use std::borrow::{Borrow, Cow};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
 use std::num::NonZeroU8;
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
 use tokio::fs::File;
-use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt, ReadBuf};
 use tokio::sync::Mutex;
 use tokio::time::Duration;
 
@@ -88,19 +94,37 @@ where
             .await
     }
 
+    /// Read from the inner provider directly. Cached copy is neither used nor updated.
+    async fn get_audio_no_cache(
+        &self,
+        album_id: &str,
+        disc_id: NonZeroU8,
+        track_id: NonZeroU8,
+        range: Range,
+    ) -> Result<AudioResourceReader, ProviderError> {
+        self.inner
+            .get_audio_no_cache(album_id, disc_id, track_id, range)
+            .await
+    }
+
     async fn get_cover(
         &self,
         album_id: &str,
         disc_id: Option<NonZeroU8>,
     ) -> Result<ResourceReader, ProviderError> {
-        // TODO: cache cover
-        self.inner.get_cover(album_id, disc_id).await
+        self.pool
+            .fetch_cover(album_id, disc_id, self.inner.get_cover(album_id, disc_id))
+            .await
     }
 
-    async fn reload(&mut self) -> Result<(), ProviderError> {
+    async fn reload(&self) -> Result<(), ProviderError> {
         // reload the inner provider
         self.inner.reload().await
     }
+
+    async fn reload_report(&self) -> Result<ReloadReport, ProviderError> {
+        self.inner.reload_report().await
+    }
 }
 
 pub struct CachePool {
@@ -108,10 +132,35 @@ pub struct CachePool {
     root: PathBuf,
     /// Maximum space used by cache
     max_size: Option<usize>,
-    cache: DashMap<TrackIdentifier, Arc<CacheItem>>,
+    cache: Arc<DashMap<TrackIdentifier, Arc<CacheItem>>>,
     // https://github.com/xacrimon/dashmap/issues/189
     // TODO: Use LFU instead of LRU
-    last_used: Mutex<LruCache<TrackIdentifier, Arc<Mutex<u8>>>>,
+    last_used: Arc<Mutex<LruCache<TrackIdentifier, Arc<Mutex<u8>>>>>,
+    /// Number of items removed to free space
+    evictions: AtomicUsize,
+    /// Cached covers, keyed by album id and disc id. Covers are evicted only if no track can be evicted
+    covers: DashMap<(String, Option<NonZeroU8>), Arc<CacheItem>>,
+    /// Content-addressed storage shared by identical items, if deduplication is enabled
+    blobs: Option<Arc<BlobStore>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CacheConfig {
+    /// Maximum space used by cache
+    pub max_size: Option<usize>,
+    /// Store items with identical content only once, keyed by their hash
+    pub dedup: bool,
+}
+
+/// Snapshot of [CachePool] usage
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Total size of cached items in bytes
+    pub used_bytes: usize,
+    /// Number of cached items
+    pub item_count: usize,
+    /// Number of items evicted since the pool was created
+    pub evictions: usize,
 }
 
 impl CachePool {
@@ -119,93 +168,293 @@ impl CachePool {
     where
         P: AsRef<Path>,
     {
+        Self::with_config(
+            root,
+            CacheConfig {
+                max_size,
+                dedup: false,
+            },
+        )
+    }
+
+    pub fn with_config<P>(root: P, config: CacheConfig) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        let root = PathBuf::from(root.as_ref());
+        let blobs = config
+            .dedup
+            .then(|| Arc::new(BlobStore::new(root.join("blobs"))));
         Self {
-            root: PathBuf::from(root.as_ref()),
-            max_size,
+            root,
+            max_size: config.max_size,
             cache: Default::default(),
-            last_used: Mutex::new(LruCache::unbounded()),
+            last_used: Arc::new(Mutex::new(LruCache::unbounded())),
+            evictions: AtomicUsize::new(0),
+            covers: Default::default(),
+            blobs,
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            used_bytes: self.space_used(),
+            item_count: self.cache.len(),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Remove all cached items.
+    pub async fn purge(&self) {
+        let mut last_used = self.last_used.lock().await;
+        last_used.clear();
+        self.cache.retain(|_, item| {
+            item.set_cached(false);
+            false
+        });
+        self.covers.retain(|_, item| {
+            item.set_cached(false);
+            false
+        });
+    }
+
+    /// Read cover of an album or a disc from cache, or from `on_miss` if it's not cached yet.
+    ///
+    /// Covers are read fully before being cached. Identical covers share one blob if deduplication is enabled.
+    pub async fn fetch_cover(
+        &self,
+        album_id: &str,
+        disc_id: Option<NonZeroU8>,
+        on_miss: impl Future<Output = Result<ResourceReader, ProviderError>>,
+    ) -> Result<ResourceReader, ProviderError> {
+        let key = (album_id.to_string(), disc_id);
+        let cached = self.covers.get(&key).map(|item| item.path());
+        if let Some(path) = cached {
+            return Ok(Box::pin(File::open(path).await?));
+        }
+
+        let mut data = Vec::new();
+        on_miss.await?.read_to_end(&mut data).await?;
+        // the cover might have been cached by another request in the meantime
+        if let Entry::Vacant(entry) = self.covers.entry(key) {
+            entry.insert(self.store_cover(album_id, disc_id, &data)?);
         }
+        Ok(Box::pin(std::io::Cursor::new(data)))
+    }
+
+    fn store_cover(
+        &self,
+        album_id: &str,
+        disc_id: Option<NonZeroU8>,
+        data: &[u8],
+    ) -> std::io::Result<Arc<CacheItem>> {
+        let info = AudioInfo {
+            extension: String::new(),
+            size: data.len(),
+            duration: 0,
+        };
+        let item = match &self.blobs {
+            Some(blobs) => {
+                let hash = hex::encode(Sha256::digest(data));
+                let path = blobs.insert_data(&hash, data)?;
+                let item = CacheItem::new(path, info, true, Some(blobs.clone()));
+                *item.blob.write() = Some(hash);
+                item
+            }
+            None => {
+                let mut path = self.root.join(album_id);
+                std::fs::create_dir_all(&path)?;
+                path.push(match disc_id {
+                    Some(disc_id) => format!("cover_{disc_id}"),
+                    None => "cover".to_string(),
+                });
+                std::fs::write(&path, data)?;
+                CacheItem::new(path, info, true, None)
+            }
+        };
+        Ok(Arc::new(item))
     }
 
-    async fn fetch_audio(
+    /// Read audio of a track from cache, or from `on_miss` if it's not cached yet.
+    ///
+    /// Output of `on_miss` is written to cache in background, and `range` is applied on the cached audio.
+    pub async fn fetch_audio(
         &self,
         album_id: &str,
         disc_id: NonZeroU8,
         track_id: NonZeroU8,
         range: Range,
         on_miss: impl Future<Output = Result<AudioResourceReader, ProviderError>>,
+    ) -> Result<AudioResourceReader, ProviderError> {
+        self.fetch(album_id, disc_id, track_id, range, async {
+            on_miss.await.map(CacheSource::Reader)
+        })
+        .await
+    }
+
+    /// Read audio of a track from cache, or from the file written by `on_miss` if it's not cached yet.
+    ///
+    /// `on_miss` returns path and info of a complete file, which is moved into cache as is.
+    /// It should be on the same file system as the cache, so that it can be renamed.
+    /// Nothing is cached if `on_miss` fails.
+    pub async fn fetch_audio_file(
+        &self,
+        album_id: &str,
+        disc_id: NonZeroU8,
+        track_id: NonZeroU8,
+        range: Range,
+        on_miss: impl Future<Output = Result<(PathBuf, AudioInfo), ProviderError>>,
+    ) -> Result<AudioResourceReader, ProviderError> {
+        self.fetch(album_id, disc_id, track_id, range, async {
+            on_miss
+                .await
+                .map(|(path, info)| CacheSource::File(path, info))
+        })
+        .await
+    }
+
+    async fn fetch(
+        &self,
+        album_id: &str,
+        disc_id: NonZeroU8,
+        track_id: NonZeroU8,
+        range: Range,
+        on_miss: impl Future<Output = Result<CacheSource, ProviderError>>,
     ) -> Result<AudioResourceReader, ProviderError> {
         let key = RawTrackIdentifier::new(album_id, disc_id, track_id);
         let item = if !self.has_cache(album_id, disc_id, track_id).await {
             // on miss, set state to cached first
             let mutex = Arc::new(Mutex::new(0));
             let handle = mutex.clone().lock_owned().await;
-            // TODO: The following procedure may fail, but the entry would still be added to `last_used`
-            //       We should remove it from `last_used` and fail all correspoding requests.
             self.last_used.lock().await.put(key.to_owned(), mutex);
 
-            // get data, return directly if it's a partial request
-            let result = on_miss.await?;
-
-            // prepare for new item
-            let mut path = self.root.join(key.album_id.as_ref());
-            tokio::fs::create_dir_all(&path).await?;
-            path.push(format!("{}_{}", key.disc_id.get(), key.track_id.get()));
-            let mut file = File::create(&path).await?;
-
-            let AudioResourceReader {
-                info, mut reader, ..
-            } = result;
-            let item = Arc::new(CacheItem::new(path, info, false));
-
-            // remove old item if space is full
-            if let Some(max_size) = self.max_size {
-                if self.space_used() > max_size {
-                    // get the first item of BTreeMap
-                    let mut write = self.last_used.lock().await;
-                    let key = write.pop_lru().unwrap();
-                    // remove it from cache map
-                    // drop would do the removal
-                    self.remove(&key.0.borrow()).await;
+            let item = match self.insert(&key, on_miss).await {
+                Ok(item) => item,
+                Err(e) => {
+                    // requests waiting for this item would find it missing
+                    self.last_used.lock().await.pop(&key);
+                    return Err(e);
                 }
-            }
-
-            // write to map
-            self.cache.insert(key.to_owned(), item.clone());
+            };
             // item is set to cached, release lock
             drop(handle);
-
-            // cache
-            let item_spawn = item.clone();
-            tokio::spawn(async move {
-                let actual_size = tokio::io::copy(&mut reader, &mut file).await.unwrap() as usize;
-                if item_spawn.size() != actual_size {
-                    // TODO: should not happen, throw error here
-                    item_spawn.set_size(actual_size);
-                }
-                item_spawn.set_cached(true);
-            });
             item
         } else {
             // resource requested, but not added to cache map yet
             if !self.cache.contains_key(&key) {
                 // await cache mutex
-                let mutex = {
-                    let mut map = self.last_used.lock().await;
-                    map.get(&key).unwrap().clone()
-                };
-                let _ = mutex.lock().await;
+                let mutex = self.last_used.lock().await.get(&key).cloned();
+                if let Some(mutex) = mutex {
+                    let _ = mutex.lock().await;
+                }
             }
             // update last_used time
-            self.last_used.lock().await.get(&key).unwrap();
-            self.cache.get(&key).unwrap().clone()
+            self.last_used.lock().await.get(&key);
+            // the item is missing if `on_miss` of the first request failed
+            self.cache
+                .get(&key)
+                .map(|item| item.clone())
+                .ok_or(ProviderError::FileNotFound)?
         };
 
         Ok(item
-            .to_audio_resource_reader(File::open(&item.path).await?, range)
+            .to_audio_resource_reader(File::open(item.path()).await?, range)
             .await)
     }
 
+    /// Add item of `key` from `on_miss` to cache map.
+    async fn insert(
+        &self,
+        key: &RawTrackIdentifier<'_>,
+        on_miss: impl Future<Output = Result<CacheSource, ProviderError>>,
+    ) -> Result<Arc<CacheItem>, ProviderError> {
+        // get data, return directly if it's a partial request
+        let source = on_miss.await?;
+
+        // prepare for new item
+        let mut path = self.root.join(key.album_id.as_ref());
+        tokio::fs::create_dir_all(&path).await?;
+        path.push(format!("{}_{}", key.disc_id.get(), key.track_id.get()));
+
+        let (item, reader) = match source {
+            CacheSource::Reader(AudioResourceReader { info, reader, .. }) => {
+                let file = File::create(&path).await?;
+                let item = CacheItem::new(path, info, false, self.blobs.clone());
+                (Arc::new(item), Some((file, reader)))
+            }
+            CacheSource::File(source, info) => {
+                tokio::fs::rename(&source, &path).await?;
+                let item = CacheItem::new(path, info, true, self.blobs.clone());
+                if self.blobs.is_some() {
+                    let hash = hex::encode(Sha256::digest(tokio::fs::read(item.path()).await?));
+                    item.move_to_blob(hash)?;
+                }
+                (Arc::new(item), None)
+            }
+        };
+
+        // remove old items until the new item fits
+        if let Some(max_size) = self.max_size {
+            while self.space_used() + item.size() > max_size {
+                // get the least recently used item, which is never the new one
+                let lru = {
+                    let mut last_used = self.last_used.lock().await;
+                    match last_used.peek_lru() {
+                        Some((k, _)) if k.inner != *key => last_used.pop_lru(),
+                        _ => None,
+                    }
+                };
+                let Some((lru, _)) = lru else {
+                    if self.evict_cover() {
+                        continue;
+                    }
+                    break;
+                };
+                // remove it from cache map
+                // drop would do the removal
+                self.remove(&lru.borrow()).await;
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        // write to map
+        self.cache.insert(key.to_owned(), item.clone());
+
+        // cache
+        if let Some((file, reader)) = reader {
+            let key = key.to_owned();
+            let item_spawn = item.clone();
+            let cache = self.cache.clone();
+            let last_used = self.last_used.clone();
+            tokio::spawn(async move {
+                match write_cache(file, reader, &item_spawn).await {
+                    Ok(()) => item_spawn.set_cached(true),
+                    Err(e) => {
+                        log::error!("Failed to cache {}: {}", item_spawn.path().display(), e);
+                        // partial file is removed when the item is dropped,
+                        // and the next request fetches from upstream again
+                        cache.remove_if(&key, |_, item| Arc::ptr_eq(item, &item_spawn));
+                        last_used.lock().await.pop(&key);
+                        item_spawn.set_failed();
+                    }
+                }
+            });
+        }
+        Ok(item)
+    }
+
+    /// Remove any cached cover, returns `false` if there's none.
+    fn evict_cover(&self) -> bool {
+        let Some(key) = self.covers.iter().next().map(|item| item.key().clone()) else {
+            return false;
+        };
+        if let Some((_, item)) = self.covers.remove(&key) {
+            item.set_cached(false);
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+        true
+    }
+
     async fn remove<'a>(&self, key: &RawTrackIdentifier<'a>) {
         self.cache.remove(key).map(|r| r.1.set_cached(false));
         self.last_used.lock().await.pop(key);
@@ -240,36 +489,184 @@ impl CachePool {
     }
 
     fn space_used(&self) -> usize {
-        self.cache
+        // items stored in blobs are counted once per blob
+        let unshared = |item: &Arc<CacheItem>| match *item.blob.read() {
+            Some(_) => 0,
+            None => item.size(),
+        };
+        let items = self
+            .cache
             .iter()
-            .map(|i| i.size())
-            .reduce(|a, b| a + b)
-            .unwrap_or(0)
+            .map(|i| unshared(i.value()))
+            .sum::<usize>()
+            + self
+                .covers
+                .iter()
+                .map(|i| unshared(i.value()))
+                .sum::<usize>();
+        items + self.blobs.as_ref().map_or(0, |b| b.space_used())
+    }
+}
+
+/// Write audio from `reader` to `file` of `item`, and move it to blob store if deduplication is enabled.
+async fn write_cache(
+    mut file: File,
+    mut reader: ResourceReader,
+    item: &CacheItem,
+) -> std::io::Result<()> {
+    let mut hasher = item.blobs.is_some().then(Sha256::new);
+    let mut buf = vec![0; 64 * 1024];
+    let mut actual_size = 0;
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        file.write_all(&buf[..n]).await?;
+        if let Some(hasher) = &mut hasher {
+            hasher.update(&buf[..n]);
+        }
+        actual_size += n;
+    }
+    file.flush().await?;
+    drop(file);
+
+    if item.size() != actual_size {
+        // TODO: should not happen, throw error here
+        item.set_size(actual_size);
+    }
+    if let Some(hasher) = hasher {
+        let hash = hex::encode(hasher.finalize());
+        if let Err(e) = item.move_to_blob(hash) {
+            log::error!("Failed to move cache to blob: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Source of an audio item to be cached.
+enum CacheSource {
+    /// Audio which is written to cache while being read
+    Reader(AudioResourceReader),
+    /// Complete file of audio, which is moved into cache
+    File(PathBuf, AudioInfo),
+}
+
+/// Reference counted blobs named by content hash
+struct BlobStore {
+    root: PathBuf,
+    /// hash -> (reference count, size)
+    blobs: parking_lot::Mutex<HashMap<String, (usize, usize)>>,
+}
+
+impl BlobStore {
+    fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            blobs: Default::default(),
+        }
+    }
+
+    /// Move file at `path` into store, or remove it if a blob with the same `hash` exists.
+    ///
+    /// Returns path of the blob.
+    fn insert(&self, path: &Path, hash: &str, size: usize) -> std::io::Result<PathBuf> {
+        let blob = self.root.join(hash);
+        let mut blobs = self.blobs.lock();
+        match blobs.get_mut(hash) {
+            Some((refs, _)) => {
+                std::fs::remove_file(path)?;
+                *refs += 1;
+            }
+            None => {
+                std::fs::create_dir_all(&self.root)?;
+                std::fs::rename(path, &blob)?;
+                blobs.insert(hash.to_string(), (1, size));
+            }
+        }
+        Ok(blob)
+    }
+
+    /// Write `data` to store, or reference the existing blob with the same `hash`.
+    ///
+    /// Returns path of the blob.
+    fn insert_data(&self, hash: &str, data: &[u8]) -> std::io::Result<PathBuf> {
+        let blob = self.root.join(hash);
+        let mut blobs = self.blobs.lock();
+        match blobs.get_mut(hash) {
+            Some((refs, _)) => *refs += 1,
+            None => {
+                std::fs::create_dir_all(&self.root)?;
+                std::fs::write(&blob, data)?;
+                blobs.insert(hash.to_string(), (1, data.len()));
+            }
+        }
+        Ok(blob)
+    }
+
+    /// Drop a reference to blob, and remove it if it's no longer referenced
+    fn release(&self, hash: &str) {
+        let mut blobs = self.blobs.lock();
+        if let Some((refs, _)) = blobs.get_mut(hash) {
+            *refs -= 1;
+            if *refs == 0 {
+                blobs.remove(hash);
+                if let Err(e) = std::fs::remove_file(self.root.join(hash)) {
+                    log::error!("Failed to remove blob {}: {}", hash, e);
+                }
+            }
+        }
+    }
+
+    fn space_used(&self) -> usize {
+        self.blobs.lock().values().map(|(_, size)| size).sum()
     }
 }
 
 struct CacheItem {
     ext: String,
-    path: PathBuf,
+    path: RwLock<PathBuf>,
     size: RwLock<usize>,
     duration: u64,
     cached: RwLock<bool>,
+    /// Whether writing to cache failed, so the file would never be complete
+    failed: RwLock<bool>,
+    blobs: Option<Arc<BlobStore>>,
+    /// Hash of the blob this item is stored in
+    blob: RwLock<Option<String>>,
 }
 
 impl CacheItem {
-    fn new(path: PathBuf, info: AudioInfo, cached: bool) -> Self {
+    fn new(path: PathBuf, info: AudioInfo, cached: bool, blobs: Option<Arc<BlobStore>>) -> Self {
         let AudioInfo {
             extension: ext,
             duration,
             size,
         } = info;
         CacheItem {
-            path,
+            path: RwLock::new(path),
             ext,
             size: RwLock::new(size),
             duration,
             cached: RwLock::new(cached),
+            failed: RwLock::new(false),
+            blobs,
+            blob: RwLock::new(None),
+        }
+    }
+
+    fn path(&self) -> PathBuf {
+        self.path.read().clone()
+    }
+
+    /// Move cached file to blob store, sharing it with items of the same content.
+    fn move_to_blob(&self, hash: String) -> std::io::Result<()> {
+        if let Some(blobs) = &self.blobs {
+            let mut path = self.path.write();
+            *path = blobs.insert(&path, &hash, self.size())?;
+            *self.blob.write() = Some(hash);
         }
+        Ok(())
     }
 
     fn size(&self) -> usize {
@@ -287,6 +684,14 @@ impl CacheItem {
     fn set_cached(&self, cached: bool) {
         *self.cached.write() = cached
     }
+
+    fn failed(&self) -> bool {
+        *self.failed.read()
+    }
+
+    fn set_failed(&self) {
+        *self.failed.write() = true
+    }
 }
 
 #[async_trait::async_trait]
@@ -337,8 +742,14 @@ impl Drop for CacheItem {
         // a. file not fully cached and program reaches program termination
         // b. manually set cached to false
         if !self.cached() {
-            if let Err(e) = std::fs::remove_file(&self.path) {
-                log::error!("Failed to drop CacheItem: {}", e);
+            match (&self.blobs, self.blob.get_mut()) {
+                // blob might be shared by other items
+                (Some(blobs), Some(hash)) => blobs.release(hash),
+                _ => {
+                    if let Err(e) = std::fs::remove_file(self.path.get_mut()) {
+                        log::error!("Failed to drop CacheItem: {}", e);
+                    }
+                }
             }
         }
     }
@@ -393,6 +804,11 @@ impl AsyncRead for CacheItemReader {
                                 // EOF
                                 Poll::Ready(Ok(()))
                             }
+                        } else if self.item.failed() {
+                            Poll::Ready(Err(std::io::Error::new(
+                                std::io::ErrorKind::UnexpectedEof,
+                                "failed to cache audio",
+                            )))
                         } else {
                             // not done, wait for more data
                             // set up timer to wait
@@ -412,3 +828,336 @@ impl AsyncRead for CacheItemReader {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{CacheConfig, CachePool, CacheProvider, CacheStats};
+    use crate::{AnniProvider, AudioInfo, AudioResourceReader, Range, ResourceReader};
+    use async_trait::async_trait;
+    use std::borrow::Cow;
+    use std::collections::HashSet;
+    use std::num::NonZeroU8;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+    use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
+
+    const ALBUM_ID: &str = "3e5ff166-f800-4433-a413-6cfa3c2b3cdd";
+    const AUDIO: &[u8] = b"fLaC audio content";
+    const COVER: &[u8] = b"JFIF cover content";
+
+    /// Provider which counts how many times audio or cover is requested.
+    struct CountingProvider(Arc<AtomicUsize>);
+
+    #[async_trait]
+    impl AnniProvider for CountingProvider {
+        async fn albums(&self) -> crate::Result<HashSet<Cow<str>>> {
+            Ok(HashSet::from([Cow::Borrowed(ALBUM_ID)]))
+        }
+
+        async fn get_audio(
+            &self,
+            _album_id: &str,
+            _disc_id: NonZeroU8,
+            _track_id: NonZeroU8,
+            range: Range,
+        ) -> crate::Result<AudioResourceReader> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(AudioResourceReader {
+                info: AudioInfo {
+                    extension: "flac".to_string(),
+                    size: AUDIO.len(),
+                    duration: 1000,
+                },
+                range,
+                reader: Box::pin(AUDIO),
+            })
+        }
+
+        async fn get_cover(
+            &self,
+            _album_id: &str,
+            _disc_id: Option<NonZeroU8>,
+        ) -> crate::Result<ResourceReader> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(Box::pin(COVER))
+        }
+
+        async fn reload(&self) -> crate::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Provider whose first audio fails after a few bytes.
+    struct FlakyProvider(Arc<AtomicUsize>);
+
+    /// Reader which always fails.
+    struct BrokenReader;
+
+    impl AsyncRead for BrokenReader {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &mut ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Err(std::io::Error::other("disk is gone")))
+        }
+    }
+
+    #[async_trait]
+    impl AnniProvider for FlakyProvider {
+        async fn albums(&self) -> crate::Result<HashSet<Cow<str>>> {
+            Ok(HashSet::from([Cow::Borrowed(ALBUM_ID)]))
+        }
+
+        async fn get_audio(
+            &self,
+            _album_id: &str,
+            _disc_id: NonZeroU8,
+            _track_id: NonZeroU8,
+            range: Range,
+        ) -> crate::Result<AudioResourceReader> {
+            let reader: ResourceReader = match self.0.fetch_add(1, Ordering::SeqCst) {
+                0 => Box::pin(AUDIO[..4].chain(BrokenReader)),
+                _ => Box::pin(AUDIO),
+            };
+            Ok(AudioResourceReader {
+                info: AudioInfo {
+                    extension: "flac".to_string(),
+                    size: AUDIO.len(),
+                    duration: 1000,
+                },
+                range,
+                reader,
+            })
+        }
+
+        async fn get_cover(
+            &self,
+            _album_id: &str,
+            _disc_id: Option<NonZeroU8>,
+        ) -> crate::Result<ResourceReader> {
+            Ok(Box::pin(COVER))
+        }
+
+        async fn reload(&self) -> crate::Result<()> {
+            Ok(())
+        }
+    }
+
+    async fn read_all(audio: AudioResourceReader) -> Vec<u8> {
+        let mut reader = audio.reader;
+        let mut result = Vec::new();
+        reader.read_to_end(&mut result).await.unwrap();
+        result
+    }
+
+    #[tokio::test]
+    async fn test_no_cache_reads_inner_provider() {
+        let root = tempfile::tempdir().unwrap();
+        let hits = Arc::new(AtomicUsize::new(0));
+        let provider = CacheProvider::new(
+            CountingProvider(hits.clone()),
+            Arc::new(CachePool::new(root.path(), None)),
+        );
+        let one = NonZeroU8::new(1).unwrap();
+
+        let audio = provider.get_audio(ALBUM_ID, one, one, Range::FULL).await;
+        assert_eq!(read_all(audio.unwrap()).await, AUDIO);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        // cached copy exists now
+        let audio = provider.get_audio(ALBUM_ID, one, one, Range::FULL).await;
+        assert_eq!(read_all(audio.unwrap()).await, AUDIO);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        let audio = provider
+            .get_audio_no_cache(ALBUM_ID, one, one, Range::FULL)
+            .await;
+        assert_eq!(read_all(audio.unwrap()).await, AUDIO);
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_evict_past_max_size() {
+        let root = tempfile::tempdir().unwrap();
+        let max_size = AUDIO.len() * 2;
+        let pool = Arc::new(CachePool::new(root.path(), Some(max_size)));
+        let hits = Arc::new(AtomicUsize::new(0));
+        // providers sharing the same pool
+        let a = CacheProvider::new(CountingProvider(hits.clone()), pool.clone());
+        let b = CacheProvider::new(CountingProvider(hits.clone()), pool.clone());
+        let one = NonZeroU8::new(1).unwrap();
+
+        for track in 1..=4 {
+            let provider = if track % 2 == 0 { &a } else { &b };
+            let track = NonZeroU8::new(track).unwrap();
+            let audio = provider.get_audio(ALBUM_ID, one, track, Range::FULL).await;
+            assert_eq!(read_all(audio.unwrap()).await, AUDIO);
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 4);
+
+        let stats = pool.stats();
+        assert_eq!(stats.evictions, 2);
+        assert_eq!(stats.item_count, 2);
+        assert!(stats.used_bytes <= max_size);
+
+        // least recently used tracks are evicted
+        let four = NonZeroU8::new(4).unwrap();
+        let audio = a.get_audio(ALBUM_ID, one, four, Range::FULL).await;
+        assert_eq!(read_all(audio.unwrap()).await, AUDIO);
+        assert_eq!(hits.load(Ordering::SeqCst), 4);
+
+        pool.purge().await;
+        assert_eq!(
+            pool.stats(),
+            CacheStats {
+                used_bytes: 0,
+                item_count: 0,
+                evictions: 2,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dedup_identical_audio() {
+        let root = tempfile::tempdir().unwrap();
+        let max_size = AUDIO.len() * 2;
+        let pool = Arc::new(CachePool::with_config(
+            root.path(),
+            CacheConfig {
+                max_size: Some(max_size),
+                dedup: true,
+            },
+        ));
+        let provider = CacheProvider::new(CountingProvider(Default::default()), pool.clone());
+        let one = NonZeroU8::new(1).unwrap();
+        let blobs = || {
+            std::fs::read_dir(root.path().join("blobs"))
+                .unwrap()
+                .count()
+        };
+
+        for track in 1..=3 {
+            let track = NonZeroU8::new(track).unwrap();
+            let audio = provider.get_audio(ALBUM_ID, one, track, Range::FULL).await;
+            assert_eq!(read_all(audio.unwrap()).await, AUDIO);
+        }
+
+        // identical tracks share one blob, so no eviction happened
+        assert_eq!(blobs(), 1);
+        assert_eq!(
+            pool.stats(),
+            CacheStats {
+                used_bytes: AUDIO.len(),
+                item_count: 3,
+                evictions: 0,
+            }
+        );
+
+        // blob is kept until the last reference is removed
+        for track in 1..=2 {
+            let track = NonZeroU8::new(track).unwrap();
+            provider.invalidate(ALBUM_ID, one, track).await;
+            assert_eq!(blobs(), 1);
+        }
+        let three = NonZeroU8::new(3).unwrap();
+        let audio = provider.get_audio(ALBUM_ID, one, three, Range::FULL).await;
+        assert_eq!(read_all(audio.unwrap()).await, AUDIO);
+
+        provider.invalidate(ALBUM_ID, one, three).await;
+        assert_eq!(blobs(), 0);
+        assert_eq!(pool.stats().used_bytes, 0);
+    }
+
+    #[tokio::test]
+    async fn test_dedup_identical_covers() {
+        let root = tempfile::tempdir().unwrap();
+        let pool = Arc::new(CachePool::with_config(
+            root.path(),
+            CacheConfig {
+                max_size: None,
+                dedup: true,
+            },
+        ));
+        let hits = Arc::new(AtomicUsize::new(0));
+        let provider = CacheProvider::new(CountingProvider(hits.clone()), pool.clone());
+        let one = NonZeroU8::new(1);
+        let blobs = || {
+            std::fs::read_dir(root.path().join("blobs"))
+                .unwrap()
+                .count()
+        };
+
+        // album cover and disc cover are identical
+        for disc_id in [None, one, None, one] {
+            let mut cover = Vec::new();
+            let mut reader = provider.get_cover(ALBUM_ID, disc_id).await.unwrap();
+            reader.read_to_end(&mut cover).await.unwrap();
+            assert_eq!(cover, COVER);
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+        assert_eq!(blobs(), 1);
+        assert_eq!(pool.stats().used_bytes, COVER.len());
+
+        pool.purge().await;
+        assert_eq!(blobs(), 0);
+        assert_eq!(pool.stats().used_bytes, 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_cache_is_discarded() {
+        let root = tempfile::tempdir().unwrap();
+        let pool = Arc::new(CachePool::new(root.path(), None));
+        let hits = Arc::new(AtomicUsize::new(0));
+        let provider = CacheProvider::new(FlakyProvider(hits.clone()), pool.clone());
+        let one = NonZeroU8::new(1).unwrap();
+
+        // reading from upstream fails while caching
+        let audio = provider.get_audio(ALBUM_ID, one, one, Range::FULL).await;
+        let mut result = Vec::new();
+        assert!(audio
+            .unwrap()
+            .reader
+            .read_to_end(&mut result)
+            .await
+            .is_err());
+        assert_eq!(pool.stats().item_count, 0);
+        assert!(!root.path().join(ALBUM_ID).join("1_1").exists());
+
+        // the next request fetches from upstream again
+        let audio = provider.get_audio(ALBUM_ID, one, one, Range::FULL).await;
+        assert_eq!(read_all(audio.unwrap()).await, AUDIO);
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+        let audio = provider.get_audio(ALBUM_ID, one, one, Range::FULL).await;
+        assert_eq!(read_all(audio.unwrap()).await, AUDIO);
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_evict_covers() {
+        let root = tempfile::tempdir().unwrap();
+        let pool = Arc::new(CachePool::new(root.path(), Some(AUDIO.len())));
+        let hits = Arc::new(AtomicUsize::new(0));
+        let provider = CacheProvider::new(CountingProvider(hits.clone()), pool.clone());
+        let one = NonZeroU8::new(1).unwrap();
+
+        let mut cover = Vec::new();
+        let mut reader = provider.get_cover(ALBUM_ID, None).await.unwrap();
+        reader.read_to_end(&mut cover).await.unwrap();
+        assert_eq!(pool.stats().used_bytes, COVER.len());
+
+        // cover is evicted to make room for audio
+        let audio = provider.get_audio(ALBUM_ID, one, one, Range::FULL).await;
+        assert_eq!(read_all(audio.unwrap()).await, AUDIO);
+        let stats = pool.stats();
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.used_bytes, AUDIO.len());
+        assert!(!root.path().join(ALBUM_ID).join("cover").exists());
+
+        let mut reader = provider.get_cover(ALBUM_ID, None).await.unwrap();
+        reader.read_to_end(&mut cover).await.unwrap();
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+}
@@ -31,6 +31,44 @@ pub struct AudioResourceReader {
     pub reader: ResourceReader,
 }
 
+/// Summary of album changes after a provider reload.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReloadReport {
+    /// Number of albums which were not available before reload
+    pub added: usize,
+    /// Number of albums which are no longer available after reload
+    pub removed: usize,
+}
+
+impl ReloadReport {
+    /// Compute report by comparing album ids before and after reload
+    pub fn diff<'a, I>(old: &HashSet<String>, new: I) -> Self
+    where
+        I: IntoIterator<Item = &'a String>,
+    {
+        let mut added = 0;
+        let mut kept = 0;
+        for album_id in new {
+            if old.contains(album_id) {
+                kept += 1;
+            } else {
+                added += 1;
+            }
+        }
+        Self {
+            added,
+            removed: old.len() - kept,
+        }
+    }
+}
+
+impl std::ops::AddAssign for ReloadReport {
+    fn add_assign(&mut self, rhs: Self) {
+        self.added += rhs.added;
+        self.removed += rhs.removed;
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct Range {
     pub start: u64,
@@ -169,12 +207,35 @@ pub trait AnniProvider: Sync {
         range: Range,
     ) -> Result<AudioResourceReader>;
 
+    /// Returns a reader like [AnniProvider::get_audio], but skips cached copy and reads from the underlying storage.
+    ///
+    /// Providers without a cache layer do not need to override this method.
+    async fn get_audio_no_cache(
+        &self,
+        album_id: &str,
+        disc_id: NonZeroU8,
+        track_id: NonZeroU8,
+        range: Range,
+    ) -> Result<AudioResourceReader> {
+        self.get_audio(album_id, disc_id, track_id, range).await
+    }
+
     /// Returns a cover of corresponding album
     async fn get_cover(&self, album_id: &str, disc_id: Option<NonZeroU8>)
         -> Result<ResourceReader>;
 
-    /// Reloads the provider for new albums
-    async fn reload(&mut self) -> Result<()>;
+    /// Reloads the provider for new albums.
+    ///
+    /// Providers keep their state behind interior mutability, so reloading does not block concurrent reads.
+    async fn reload(&self) -> Result<()>;
+
+    /// Reloads the provider like [AnniProvider::reload], and returns how many albums were added or removed.
+    ///
+    /// Providers which can not compute the difference report zeros.
+    async fn reload_report(&self) -> Result<ReloadReport> {
+        self.reload().await?;
+        Ok(ReloadReport::default())
+    }
 }
 
 #[async_trait]
@@ -210,6 +271,18 @@ impl AnniProvider for Box<dyn AnniProvider + Send + Sync> {
             .await
     }
 
+    async fn get_audio_no_cache(
+        &self,
+        album_id: &str,
+        disc_id: NonZeroU8,
+        track_id: NonZeroU8,
+        range: Range,
+    ) -> Result<AudioResourceReader> {
+        self.as_ref()
+            .get_audio_no_cache(album_id, disc_id, track_id, range)
+            .await
+    }
+
     async fn get_cover(
         &self,
         album_id: &str,
@@ -218,8 +291,12 @@ impl AnniProvider for Box<dyn AnniProvider + Send + Sync> {
         self.as_ref().get_cover(album_id, disc_id).await
     }
 
-    async fn reload(&mut self) -> Result<()> {
-        self.as_mut().reload().await
+    async fn reload(&self) -> Result<()> {
+        self.as_ref().reload().await
+    }
+
+    async fn reload_report(&self) -> Result<ReloadReport> {
+        self.as_ref().reload_report().await
     }
 }
 
@@ -246,11 +323,23 @@ pub trait FileSystemProvider: Sync {
     /// Get audio info: (extension ,size)
     async fn get_audio_info(&self, path: &PathBuf) -> Result<(String, usize)>;
 
+    /// Get audio duration in milliseconds, which is used if the requested range does not contain STREAMINFO.
+    ///
+    /// Returns `None` if reading the header separately is expensive, and duration would be 0 in that case.
+    async fn get_audio_duration(&self, _path: &PathBuf) -> Result<Option<u64>> {
+        Ok(None)
+    }
+
     // TODO: move this method to a sub trait
     async fn get_audio_file(&self, path: &PathBuf, range: Range) -> Result<AudioResourceReader> {
         let reader = self.get_file(path, range).await?;
         let metadata = self.get_audio_info(path).await?;
-        let (duration, reader) = crate::utils::read_duration(reader, range).await?;
+        let (mut duration, reader) = crate::utils::read_duration(reader, range).await?;
+        if !range.contains_flac_header() {
+            if let Some(d) = self.get_audio_duration(path).await? {
+                duration = d;
+            }
+        }
         Ok(AudioResourceReader {
             info: AudioInfo {
                 extension: metadata.0,
@@ -267,7 +356,7 @@ pub trait FileSystemProvider: Sync {
     }
 
     /// Reload
-    async fn reload(&mut self) -> Result<()>;
+    async fn reload(&self) -> Result<()>;
 }
 
 #[derive(Debug, Error)]
@@ -297,6 +386,14 @@ pub enum ProviderError {
     #[error(transparent)]
     RequestError(#[from] reqwest::Error),
 
+    #[cfg(feature = "s3")]
+    #[error("s3 error: {0}")]
+    S3Error(String),
+
+    #[cfg(feature = "quick-xml")]
+    #[error(transparent)]
+    XmlError(#[from] quick_xml::Error),
+
     #[error(transparent)]
     FlacError(#[from] anni_flac::error::FlacError),
 
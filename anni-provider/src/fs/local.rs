@@ -68,7 +68,11 @@ impl FileSystemProvider for LocalFileSystemProvider {
         Ok((extension, size as usize))
     }
 
-    async fn reload(&mut self) -> crate::Result<()> {
+    async fn get_audio_duration(&self, path: &PathBuf) -> crate::Result<Option<u64>> {
+        Ok(Some(crate::utils::read_file_duration(path).await?))
+    }
+
+    async fn reload(&self) -> crate::Result<()> {
         Ok(())
     }
 }
@@ -1,2 +1,6 @@
 mod local;
+#[cfg(feature = "s3")]
+mod s3;
 pub use local::LocalFileSystemProvider;
+#[cfg(feature = "s3")]
+pub use s3::{S3Client, S3FileSystemProvider, S3Listing};
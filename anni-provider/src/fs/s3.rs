@@ -0,0 +1,196 @@
+use crate::{FileEntry, FileSystemProvider, ProviderError, Range, ResourceReader};
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use tokio_stream::{self as stream, Stream};
+
+/// Objects and sub folders directly under a prefix
+#[derive(Default)]
+pub struct S3Listing {
+    /// Common prefixes ending with `/`
+    pub prefixes: Vec<String>,
+    /// Object keys
+    pub keys: Vec<String>,
+}
+
+/// Minimal set of S3 operations used by [S3FileSystemProvider].
+#[async_trait]
+pub trait S3Client: Send + Sync {
+    /// List objects and common prefixes under `prefix`, delimited by `/`
+    async fn list(&self, bucket: &str, prefix: &str) -> crate::Result<S3Listing>;
+
+    /// Get object content. `range` is a http `Range` header value.
+    async fn get_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        range: Option<String>,
+    ) -> crate::Result<ResourceReader>;
+
+    /// Get object size
+    async fn content_length(&self, bucket: &str, key: &str) -> crate::Result<u64>;
+}
+
+#[async_trait]
+impl S3Client for aws_sdk_s3::Client {
+    async fn list(&self, bucket: &str, prefix: &str) -> crate::Result<S3Listing> {
+        let mut listing = S3Listing::default();
+        let mut pages = self
+            .list_objects_v2()
+            .bucket(bucket)
+            .prefix(prefix)
+            .delimiter("/")
+            .into_paginator()
+            .send();
+        while let Some(page) = pages.next().await {
+            let page = page.map_err(|e| ProviderError::S3Error(e.to_string()))?;
+            listing.prefixes.extend(
+                page.common_prefixes()
+                    .iter()
+                    .filter_map(|p| p.prefix().map(str::to_string)),
+            );
+            listing.keys.extend(
+                page.contents()
+                    .iter()
+                    .filter_map(|o| o.key().map(str::to_string)),
+            );
+        }
+        Ok(listing)
+    }
+
+    async fn get_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        range: Option<String>,
+    ) -> crate::Result<ResourceReader> {
+        let resp = self
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .set_range(range)
+            .send()
+            .await
+            .map_err(|e| match e.into_service_error() {
+                e if e.is_no_such_key() => ProviderError::FileNotFound,
+                e => ProviderError::S3Error(e.to_string()),
+            })?;
+        Ok(Box::pin(resp.body.into_async_read()))
+    }
+
+    async fn content_length(&self, bucket: &str, key: &str) -> crate::Result<u64> {
+        let resp = self
+            .head_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| match e.into_service_error() {
+                e if e.is_not_found() => ProviderError::FileNotFound,
+                e => ProviderError::S3Error(e.to_string()),
+            })?;
+        Ok(resp.content_length().unwrap_or_default() as u64)
+    }
+}
+
+/// `S3FileSystemProvider` reads files from a S3 bucket, treating `/` in object keys as folders.
+///
+/// Paths are object keys relative to the bucket root, so an empty path refers to the bucket itself.
+pub struct S3FileSystemProvider {
+    client: Box<dyn S3Client>,
+    bucket: String,
+}
+
+impl S3FileSystemProvider {
+    pub fn new(client: impl S3Client + 'static, bucket: String) -> Self {
+        Self {
+            client: Box::new(client),
+            bucket,
+        }
+    }
+}
+
+/// Convert a path to object key, which always uses `/` as separator
+fn to_key(path: &Path) -> String {
+    path.iter()
+        .map(|c| c.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Convert a path to a prefix which lists its children
+fn to_prefix(path: &Path) -> String {
+    let key = to_key(path);
+    if key.is_empty() {
+        key
+    } else {
+        format!("{key}/")
+    }
+}
+
+#[async_trait]
+impl FileSystemProvider for S3FileSystemProvider {
+    async fn children(
+        &self,
+        path: &PathBuf,
+    ) -> crate::Result<Pin<Box<dyn Stream<Item = FileEntry> + Send>>> {
+        let prefix = to_prefix(path);
+        let listing = self.client.list(&self.bucket, &prefix).await?;
+        Ok(Box::pin(stream::iter(
+            listing.prefixes.into_iter().filter_map(move |p| {
+                let name = p.strip_prefix(&prefix)?.trim_end_matches('/').to_string();
+                Some(FileEntry {
+                    path: path_of(&p),
+                    name,
+                })
+            }),
+        )))
+    }
+
+    async fn get_file_entry_by_prefix(
+        &self,
+        parent: &PathBuf,
+        prefix: &str,
+    ) -> crate::Result<FileEntry> {
+        let parent = to_prefix(parent);
+        let listing = self
+            .client
+            .list(&self.bucket, &format!("{parent}{prefix}"))
+            .await?;
+        listing
+            .keys
+            .into_iter()
+            .next()
+            .map(|key| FileEntry {
+                name: key[parent.len()..].to_string(),
+                path: path_of(&key),
+            })
+            .ok_or(ProviderError::FileNotFound)
+    }
+
+    async fn get_file(&self, path: &PathBuf, range: Range) -> crate::Result<ResourceReader> {
+        self.client
+            .get_object(&self.bucket, &to_key(path), range.to_range_header())
+            .await
+    }
+
+    async fn get_audio_info(&self, path: &PathBuf) -> crate::Result<(String, usize)> {
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let size = self
+            .client
+            .content_length(&self.bucket, &to_key(path))
+            .await?;
+        Ok((extension, size as usize))
+    }
+
+    async fn reload(&self) -> crate::Result<()> {
+        Ok(())
+    }
+}
+
+fn path_of(key: &str) -> PathBuf {
+    key.trim_end_matches('/').split('/').collect()
+}
@@ -1,11 +1,11 @@
 use crate::{
     AnniProvider, AudioResourceReader, FileEntry, FileSystemProvider, ProviderError, Range,
-    ResourceReader, Result,
+    ReloadReport, ResourceReader, Result,
 };
 use anni_repo::db::RepoDatabaseRead;
 use anni_repo::library::{AlbumFolderInfo, DiscFolderInfo};
 use async_trait::async_trait;
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::num::NonZeroU8;
@@ -18,8 +18,8 @@ pub struct CommonConventionProvider {
     fs: Box<dyn FileSystemProvider + Send + Sync>,
     repo: Mutex<RepoDatabaseRead>,
 
-    pub albums: HashMap<String, FileEntry>,
-    pub discs: HashMap<String, Vec<FileEntry>>,
+    pub albums: RwLock<HashMap<String, FileEntry>>,
+    pub discs: RwLock<HashMap<String, Vec<FileEntry>>>,
 }
 
 impl CommonConventionProvider {
@@ -28,13 +28,13 @@ impl CommonConventionProvider {
         repo: RepoDatabaseRead,
         fs: Box<dyn FileSystemProvider + Send + Sync>,
     ) -> Result<Self> {
-        let mut me = Self {
+        let me = Self {
             root,
             fs,
             repo: Mutex::new(repo),
 
-            albums: HashMap::new(),
-            discs: HashMap::new(),
+            albums: Default::default(),
+            discs: Default::default(),
         };
         me.reload().await?;
         Ok(me)
@@ -46,8 +46,9 @@ impl AnniProvider for CommonConventionProvider {
     async fn albums(&self) -> Result<HashSet<Cow<str>>> {
         Ok(self
             .albums
+            .read()
             .keys()
-            .map(|s| Cow::Borrowed(s.as_str()))
+            .map(|s| Cow::Owned(s.clone()))
             .collect())
     }
 
@@ -75,7 +76,9 @@ impl AnniProvider for CommonConventionProvider {
             Some(disc_id) => self.get_disc(album_id, disc_id)?,
             _ => self
                 .albums
+                .read()
                 .get(album_id)
+                .cloned()
                 .ok_or(ProviderError::FileNotFound)?,
         };
         self.fs
@@ -83,42 +86,59 @@ impl AnniProvider for CommonConventionProvider {
             .await
     }
 
-    async fn reload(&mut self) -> Result<()> {
+    async fn reload(&self) -> Result<()> {
         self.fs.reload().await?;
         self.repo.lock().reload()?;
         self.reload_albums().await?;
         Ok(())
     }
+
+    async fn reload_report(&self) -> Result<ReloadReport> {
+        let old: HashSet<String> = self.albums.read().keys().cloned().collect();
+        self.reload().await?;
+        Ok(ReloadReport::diff(&old, self.albums.read().keys()))
+    }
 }
 
 impl CommonConventionProvider {
-    pub fn get_disc(&self, album_id: &str, disc_id: NonZeroU8) -> Result<&FileEntry> {
-        if let Some(album) = self.albums.get(album_id) {
-            if let Some(folders) = self.discs.get(album_id) {
+    pub fn get_disc(&self, album_id: &str, disc_id: NonZeroU8) -> Result<FileEntry> {
+        if let Some(album) = self.albums.read().get(album_id) {
+            if let Some(folders) = self.discs.read().get(album_id) {
                 folders
                     .get((disc_id.get() - 1) as usize)
+                    .cloned()
                     .ok_or(ProviderError::FileNotFound)
             } else {
-                Ok(album)
+                Ok(album.clone())
             }
         } else {
             Err(ProviderError::FileNotFound)
         }
     }
 
-    pub async fn reload_albums(&mut self) -> Result<()> {
-        self.albums.clear();
-        self.discs.clear();
+    pub async fn reload_albums(&self) -> Result<()> {
+        let mut albums = HashMap::new();
+        let mut discs = HashMap::new();
 
         let mut to_visit = vec![self.root.clone()];
         while let Some(dir) = to_visit.pop() {
-            self.walk_dir_impl(dir, &mut to_visit).await?;
+            self.walk_dir_impl(dir, &mut to_visit, &mut albums, &mut discs)
+                .await?;
         }
 
+        // replace albums at once, so that requests during reload still see the old ones
+        *self.albums.write() = albums;
+        *self.discs.write() = discs;
         Ok(())
     }
 
-    async fn walk_dir_impl(&mut self, dir: PathBuf, to_visit: &mut Vec<PathBuf>) -> Result<()> {
+    async fn walk_dir_impl(
+        &self,
+        dir: PathBuf,
+        to_visit: &mut Vec<PathBuf>,
+        albums: &mut HashMap<String, FileEntry>,
+        discs: &mut HashMap<String, Vec<FileEntry>>,
+    ) -> Result<()> {
         log::debug!("Walking dir: {}", dir.display());
         let mut dir = self.fs.children(&dir).await?;
         while let Some(entry) = dir.next().await {
@@ -142,10 +162,10 @@ impl CommonConventionProvider {
                     Some(album_id) => {
                         if disc_count > 1 {
                             // look for inner discs
-                            let discs = self.walk_discs(&entry.path, disc_count).await?;
-                            self.discs.insert(album_id.to_string(), discs);
+                            let album_discs = self.walk_discs(&entry.path, disc_count).await?;
+                            discs.insert(album_id.to_string(), album_discs);
                         }
-                        self.albums.insert(album_id.to_string(), entry);
+                        albums.insert(album_id.to_string(), entry);
                     }
                     None => {
                         log::warn!("Album ID not found for {}, ignoring...", catalog);
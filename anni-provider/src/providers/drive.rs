@@ -20,8 +20,10 @@ use anni_repo::db::RepoDatabaseRead;
 use anni_repo::library::{AlbumFolderInfo, DiscFolderInfo};
 use dashmap::DashMap;
 use futures::TryStreamExt;
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
+use std::future::Future;
 use std::str::FromStr;
+use std::time::Duration;
 use tokio::sync::Semaphore;
 
 pub enum DriveAuth {
@@ -109,13 +111,78 @@ impl From<PathBuf> for TokenStorage {
 pub struct DriveProviderSettings {
     pub corpora: String,
     pub drive_id: Option<String>,
+    /// Max attempts of a request, including the first one
+    pub max_attempts: u32,
+    /// Base delay of exponential backoff between attempts
+    pub retry_delay: Duration,
 }
 
 impl DriveProviderSettings {
     pub fn new(corpora: String, drive_id: Option<String>) -> Self {
-        Self { corpora, drive_id }
+        Self {
+            corpora,
+            drive_id,
+            max_attempts: 5,
+            retry_delay: Duration::from_millis(500),
+        }
     }
 }
+
+/// Whether the request may succeed if retried, according to
+/// https://developers.google.com/drive/api/guides/handle-errors
+fn is_retryable(error: &anni_google_drive3::Error) -> bool {
+    use anni_google_drive3::Error;
+
+    let retryable_status = |status: u64| status == 429 || (500..600).contains(&status);
+    match error {
+        Error::HttpError(_) => true,
+        Error::Failure(response) => retryable_status(response.status().as_u16() as u64),
+        Error::BadRequest(value) => {
+            let error = &value["error"];
+            match error["code"].as_u64() {
+                Some(403) => error["errors"].as_array().map_or(false, |errors| {
+                    errors.iter().any(|e| {
+                        matches!(
+                            e["reason"].as_str(),
+                            Some("rateLimitExceeded" | "userRateLimitExceeded" | "quotaExceeded")
+                        )
+                    })
+                }),
+                Some(status) => retryable_status(status),
+                None => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Call `f` until it succeeds, returns a non-retryable error, or `max_attempts` is reached.
+///
+/// The delay before the n-th retry is randomly picked between `[delay * 2^(n-1) / 2, delay * 2^(n-1)]`.
+async fn retry<T, F, Fut>(
+    max_attempts: u32,
+    delay: Duration,
+    mut f: F,
+) -> Result<T, anni_google_drive3::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, anni_google_drive3::Error>>,
+{
+    let mut attempt = 1;
+    loop {
+        match f().await {
+            Err(e) if attempt < max_attempts && is_retryable(&e) => {
+                let backoff = delay.saturating_mul(1 << (attempt - 1).min(16));
+                let backoff = backoff / 2 + (backoff / 2).mul_f64(rand::random());
+                log::warn!("Drive request failed, retrying after {backoff:?} ({attempt}/{max_attempts}): {e}");
+                tokio::time::sleep(backoff).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
 pub struct DriveClient {
     hub: Box<DriveHub<HttpsConnector<HttpConnector>>>,
     settings: DriveProviderSettings,
@@ -157,6 +224,15 @@ impl DriveClient {
         })
     }
 
+    /// Call Drive API with retry. Semaphore permit should be held by caller across all attempts.
+    async fn retry<T, F, Fut>(&self, f: F) -> Result<T, ProviderError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, anni_google_drive3::Error>>,
+    {
+        Ok(retry(self.settings.max_attempts, self.settings.retry_delay, f).await?)
+    }
+
     fn prepare_list(&self) -> FileListCall<HttpsConnector<HttpConnector>> {
         let result = self
             .hub
@@ -174,10 +250,15 @@ impl DriveClient {
 
     async fn list_folder(&self, parent_id: &str) -> Result<FileList, ProviderError> {
         let permit = self.semaphore.acquire().await.unwrap();
-        let (_, list) = self.prepare_list()
-            .q(&format!("mimeType = 'application/vnd.google-apps.folder' and trashed = false and '{parent_id}' in parents"))
-            .param("fields", "nextPageToken, files(id,name)")
-            .doit().await?;
+        let q = format!("mimeType = 'application/vnd.google-apps.folder' and trashed = false and '{parent_id}' in parents");
+        let (_, list) = self
+            .retry(|| {
+                self.prepare_list()
+                    .q(&q)
+                    .param("fields", "nextPageToken, files(id,name)")
+                    .doit()
+            })
+            .await?;
         drop(permit);
         Ok(list)
     }
@@ -189,14 +270,16 @@ impl DriveClient {
     ) -> Result<(ResourceReader, Range), ProviderError> {
         let permit = self.semaphore.acquire().await.unwrap();
         let (resp, _) = self
-            .hub
-            .files()
-            .get(file_id)
-            .supports_all_drives(true)
-            .acknowledge_abuse(true)
-            .param("alt", "media")
-            .range(range.to_range_header())
-            .doit()
+            .retry(|| {
+                self.hub
+                    .files()
+                    .get(file_id)
+                    .supports_all_drives(true)
+                    .acknowledge_abuse(true)
+                    .param("alt", "media")
+                    .range(range.to_range_header())
+                    .doit()
+            })
             .await?;
         drop(permit);
         let content_range = resp
@@ -224,10 +307,15 @@ impl DriveClient {
         }
 
         let permit = self.semaphore.acquire().await.unwrap();
-        let (_, list) = self.prepare_list()
-            .q(&format!("trashed = false and mimeType = 'image/jpeg' and name = 'cover.jpg' and '{}' in parents", parent_id))
-            .param("fields", "nextPageToken, files(id,name)")
-            .doit().await?;
+        let q = format!("trashed = false and mimeType = 'image/jpeg' and name = 'cover.jpg' and '{}' in parents", parent_id);
+        let (_, list) = self
+            .retry(|| {
+                self.prepare_list()
+                    .q(&q)
+                    .param("fields", "nextPageToken, files(id,name)")
+                    .doit()
+            })
+            .await?;
         drop(permit);
 
         let files = list.files.unwrap();
@@ -242,7 +330,7 @@ pub struct DriveProvider {
     /// Google Drive API Client
     client: DriveClient,
     /// HashMap mapping album_id and folder_id
-    folders: HashMap<String, String>,
+    folders: RwLock<HashMap<String, String>>,
     /// Cache for mapping album_id and its discs if multiple discs exists
     /// All albums with multiple discs must be in this map
     /// If the value is None, it means the album is not cached
@@ -268,7 +356,7 @@ impl DriveProvider {
         repo: Option<RepoDatabaseRead>,
         token_storage: impl Into<TokenStorage>,
     ) -> Result<Self, ProviderError> {
-        let mut this = Self {
+        let this = Self {
             client: DriveClient::new(auth, settings, token_storage).await?,
             folders: Default::default(),
             discs: Default::default(),
@@ -281,12 +369,16 @@ impl DriveProvider {
         Ok(this)
     }
 
+    fn folder_id(&self, album_id: &str) -> Option<String> {
+        self.folders.read().get(album_id).cloned()
+    }
+
     async fn cache_discs(&self, album_id: &str) -> Result<(), ProviderError> {
-        if self.folders.contains_key(album_id)
-            && self.discs.contains_key(album_id)
-            && self.discs.get(album_id).unwrap().is_none()
-        {
-            let list = self.client.list_folder(&self.folders[album_id]).await?;
+        let Some(folder_id) = self.folder_id(album_id) else {
+            return Ok(());
+        };
+        if matches!(self.discs.get(album_id).as_deref(), Some(None)) {
+            let list = self.client.list_folder(&folder_id).await?;
             let mut discs: Vec<_> = list
                 .files
                 .unwrap()
@@ -313,21 +405,22 @@ impl DriveProvider {
         Ok(())
     }
 
-    fn get_parent_folder(&self, album_id: &str, disc_id: Option<NonZeroU8>) -> Cow<str> {
-        match disc_id {
-            Some(disc_id) => {
-                if self.discs.contains_key(album_id) {
-                    Cow::Owned(
-                        self.discs.get(album_id).unwrap().as_deref().unwrap()
-                            [(disc_id.get() - 1) as usize]
-                            .clone(),
-                    )
-                } else {
-                    Cow::Borrowed(&self.folders[album_id])
-                }
+    fn get_parent_folder(
+        &self,
+        album_id: &str,
+        disc_id: Option<NonZeroU8>,
+    ) -> Result<String, ProviderError> {
+        if let Some(disc_id) = disc_id {
+            if let Some(discs) = self.discs.get(album_id) {
+                // discs may be uncached again by a concurrent reload
+                let discs = discs.as_deref().ok_or(ProviderError::FileNotFound)?;
+                return discs
+                    .get((disc_id.get() - 1) as usize)
+                    .cloned()
+                    .ok_or(ProviderError::FileNotFound);
             }
-            None => Cow::Borrowed(&self.folders[album_id]),
         }
+        self.folder_id(album_id).ok_or(ProviderError::FileNotFound)
     }
 }
 
@@ -336,8 +429,9 @@ impl AnniProvider for DriveProvider {
     async fn albums(&self) -> Result<HashSet<Cow<str>>, ProviderError> {
         Ok(self
             .folders
+            .read()
             .keys()
-            .map(|a| Cow::Borrowed(a.as_str()))
+            .map(|a| Cow::Owned(a.clone()))
             .collect())
     }
 
@@ -349,7 +443,7 @@ impl AnniProvider for DriveProvider {
         range: Range,
     ) -> Result<AudioResourceReader, ProviderError> {
         // catalog not found
-        if !self.folders.contains_key(album_id) {
+        if !self.folders.read().contains_key(album_id) {
             return Err(ProviderError::FileNotFound);
         }
 
@@ -357,7 +451,7 @@ impl AnniProvider for DriveProvider {
         if !self.files.contains_key(&key) {
             // get folder_id
             self.cache_discs(album_id).await?;
-            let folder_id = self.get_parent_folder(album_id, Some(disc_id));
+            let folder_id = self.get_parent_folder(album_id, Some(disc_id))?;
 
             // get audio file id
             let permit = self.client.semaphore.acquire().await.unwrap();
@@ -368,10 +462,13 @@ impl AnniProvider for DriveProvider {
             };
             let (_, list) = self
                 .client
-                .prepare_list()
-                .q(&q)
-                .param("fields", "nextPageToken, files(id,name,fileExtension,size)")
-                .doit()
+                .retry(|| {
+                    self.client
+                        .prepare_list()
+                        .q(&q)
+                        .param("fields", "nextPageToken, files(id,name,fileExtension,size)")
+                        .doit()
+                })
                 .await?;
             drop(permit);
 
@@ -410,7 +507,11 @@ impl AnniProvider for DriveProvider {
             Some(id) => {
                 let file_id = id.value().to_string();
                 drop(id); // drop lock immediately
-                let metadata = self.audios.get(&file_id).unwrap().value().clone(); // drop lock inline
+                let metadata = match self.audios.get(&file_id) {
+                    Some(metadata) => metadata.value().clone(), // drop lock inline
+                    // cache may be cleared by a concurrent reload
+                    None => return Err(ProviderError::FileNotFound),
+                };
 
                 let (reader, range) = self.client.get_file(&file_id, &range).await?;
                 let (duration, reader) = read_duration(reader, range).await?;
@@ -434,7 +535,7 @@ impl AnniProvider for DriveProvider {
         disc_id: Option<NonZeroU8>,
     ) -> Result<ResourceReader, ProviderError> {
         // album_id not found
-        if !self.folders.contains_key(album_id) ||
+        if !self.folders.read().contains_key(album_id) ||
             // disc not found
             (disc_id.is_some() && disc_id != NonZeroU8::new(1) && !self.discs.contains_key(album_id))
         {
@@ -450,7 +551,7 @@ impl AnniProvider for DriveProvider {
             None => {
                 // get folder_id
                 self.cache_discs(album_id).await?;
-                let folder_id = self.get_parent_folder(album_id, disc_id);
+                let folder_id = self.get_parent_folder(album_id, disc_id)?;
 
                 // get cover file id
                 self.client.get_cover_id_in(&folder_id).await?
@@ -460,11 +561,9 @@ impl AnniProvider for DriveProvider {
         Ok(self.client.get_file(&id, &Range::FULL).await?.0)
     }
 
-    async fn reload(&mut self) -> Result<(), ProviderError> {
-        self.folders.clear();
-        self.discs.clear();
-        self.files.clear();
-        self.audios.clear();
+    async fn reload(&self) -> Result<(), ProviderError> {
+        let mut folders = HashMap::new();
+        let mut discs = HashSet::new();
 
         if let Some(repo) = &mut *self.repo.lock() {
             repo.reload()?;
@@ -475,16 +574,19 @@ impl AnniProvider for DriveProvider {
             let permit = self.client.semaphore.acquire().await.unwrap();
             let (_, list) = self
                 .client
-                .prepare_list()
-                .page_token(&page_token)
-                .q(if self.strict {
-                    "mimeType = 'application/vnd.google-apps.folder' and name != '0' and name != '1' and name != '2' and name != '3' and name != '4' and name != '5' and name != '6' and name != '7' and name != '8' and name != '9' and trashed = false"
-                } else {
-                    "mimeType = 'application/vnd.google-apps.folder' and trashed = false"
+                .retry(|| {
+                    self.client
+                        .prepare_list()
+                        .page_token(&page_token)
+                        .q(if self.strict {
+                            "mimeType = 'application/vnd.google-apps.folder' and name != '0' and name != '1' and name != '2' and name != '3' and name != '4' and name != '5' and name != '6' and name != '7' and name != '8' and name != '9' and trashed = false"
+                        } else {
+                            "mimeType = 'application/vnd.google-apps.folder' and trashed = false"
+                        })
+                        .param("fields", "nextPageToken, files(id,name)")
+                        .page_size(1000)
+                        .doit()
                 })
-                .param("fields", "nextPageToken, files(id,name)")
-                .page_size(1000)
-                .doit()
                 .await?;
             drop(permit);
             for file in list.files.unwrap() {
@@ -493,8 +595,8 @@ impl AnniProvider for DriveProvider {
                     if name.len() != 36 {
                         continue;
                     }
-                    self.folders.insert(name.to_string(), file.id.unwrap());
-                    self.discs.insert(name, None);
+                    folders.insert(name.to_string(), file.id.unwrap());
+                    discs.insert(name);
                 } else {
                     if let Ok(AlbumFolderInfo {
                         release_date,
@@ -513,9 +615,9 @@ impl AnniProvider for DriveProvider {
                         )?;
                         match album_id {
                             Some(album_id) => {
-                                self.folders.insert(album_id.to_string(), file.id.unwrap());
+                                folders.insert(album_id.to_string(), file.id.unwrap());
                                 if disc_count > 1 {
-                                    self.discs.insert(album_id.to_string(), None);
+                                    discs.insert(album_id.to_string());
                                 }
                             }
                             None => {
@@ -531,6 +633,77 @@ impl AnniProvider for DriveProvider {
                 page_token = list.next_page_token.unwrap();
             }
         }
+
+        // replace albums at once, so that requests during reload still see the old ones
+        self.files.clear();
+        self.audios.clear();
+        self.discs.retain(|album_id, _| discs.contains(album_id));
+        for album_id in discs {
+            self.discs.insert(album_id, None);
+        }
+        *self.folders.write() = folders;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::retry;
+    use anni_google_drive3::{hyper, Error};
+    use std::time::Duration;
+
+    fn failure(status: u16) -> Error {
+        Error::Failure(
+            hyper::Response::builder()
+                .status(status)
+                .body(hyper::Body::empty())
+                .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_retry_transient_errors() {
+        let mut calls = 0;
+        let result = retry(5, Duration::from_millis(1), || {
+            calls += 1;
+            let result = match calls {
+                1 => Err(failure(503)),
+                2 => Err(Error::BadRequest(serde_json::json!({
+                    "error": {
+                        "code": 403,
+                        "errors": [{ "reason": "userRateLimitExceeded" }]
+                    }
+                }))),
+                _ => Ok(calls),
+            };
+            async move { result }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(calls, 3);
+    }
+
+    #[tokio::test]
+    async fn test_do_not_retry_not_found() {
+        let mut calls = 0;
+        let result: Result<(), _> = retry(5, Duration::from_millis(1), || {
+            calls += 1;
+            async { Err(failure(404)) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[tokio::test]
+    async fn test_give_up_after_max_attempts() {
+        let mut calls = 0;
+        let result: Result<(), _> = retry(3, Duration::from_millis(1), || {
+            calls += 1;
+            async { Err(failure(500)) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls, 3);
+    }
+}
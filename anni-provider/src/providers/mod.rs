@@ -8,8 +8,12 @@ pub use no_cache::NoCacheStrictLocalProvider;
 pub use priority::{PriorityProvider, TypedPriorityProvider};
 #[cfg(feature = "proxy")]
 pub use proxy::ProxyBackend;
+#[cfg(feature = "s3")]
+pub use s3::S3Provider;
 #[cfg(feature = "strict")]
 pub use strict::CommonStrictProvider;
+#[cfg(feature = "webdav")]
+pub use webdav::WebDavProvider;
 
 #[cfg(feature = "convention")]
 mod convention;
@@ -21,5 +25,9 @@ mod no_cache;
 mod priority;
 #[cfg(feature = "proxy")]
 mod proxy;
+#[cfg(feature = "s3")]
+mod s3;
 #[cfg(feature = "strict")]
 mod strict;
+#[cfg(feature = "webdav")]
+pub mod webdav;
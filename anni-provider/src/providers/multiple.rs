@@ -1,4 +1,7 @@
-use crate::{AnniProvider, AudioInfo, AudioResourceReader, ProviderError, Range, ResourceReader};
+use crate::{
+    AnniProvider, AudioInfo, AudioResourceReader, ProviderError, Range, ReloadReport,
+    ResourceReader,
+};
 use async_trait::async_trait;
 use std::borrow::Cow;
 use std::collections::HashSet;
@@ -64,6 +67,24 @@ impl AnniProvider for MultipleProviders {
         Err(ProviderError::FileNotFound)
     }
 
+    async fn get_audio_no_cache(
+        &self,
+        album_id: &str,
+        disc_id: NonZeroU8,
+        track_id: NonZeroU8,
+        range: Range,
+    ) -> crate::Result<AudioResourceReader> {
+        for provider in self.0.iter() {
+            if provider.has_album(album_id).await {
+                return provider
+                    .get_audio_no_cache(album_id, disc_id, track_id, range)
+                    .await;
+            }
+        }
+
+        Err(ProviderError::FileNotFound)
+    }
+
     async fn get_cover(
         &self,
         album_id: &str,
@@ -78,9 +99,9 @@ impl AnniProvider for MultipleProviders {
         Err(ProviderError::FileNotFound)
     }
 
-    async fn reload(&mut self) -> crate::Result<()> {
+    async fn reload(&self) -> crate::Result<()> {
         let mut error = Ok(());
-        for provider in self.0.iter_mut() {
+        for provider in self.0.iter() {
             if let (Ok(()), Err(e)) = (&error, provider.reload().await) {
                 error = Err(e);
             }
@@ -88,4 +109,21 @@ impl AnniProvider for MultipleProviders {
 
         error
     }
+
+    async fn reload_report(&self) -> crate::Result<ReloadReport> {
+        let mut report = ReloadReport::default();
+        let mut error = Ok(());
+        for provider in self.0.iter() {
+            match provider.reload_report().await {
+                Ok(r) => report += r,
+                Err(e) => {
+                    if error.is_ok() {
+                        error = Err(e);
+                    }
+                }
+            }
+        }
+
+        error.map(|_| report)
+    }
 }
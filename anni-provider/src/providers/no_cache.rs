@@ -74,14 +74,18 @@ impl AnniProvider for NoCacheStrictLocalProvider {
             return Err(crate::ProviderError::FileNotFound);
         }
 
-        let mut file = tokio::fs::File::open(audio).await?;
+        let mut file = tokio::fs::File::open(&audio).await?;
         let metadata = file.metadata().await?;
         let file_size = metadata.len();
 
         file.seek(SeekFrom::Start(range.start)).await?;
         let file = file.take(range.length_limit(file_size));
         let reader = Box::pin(file);
-        let (duration, reader) = crate::utils::read_duration(reader, range).await?;
+        let (mut duration, reader) = crate::utils::read_duration(reader, range).await?;
+        if !range.contains_flac_header() {
+            // STREAMINFO is not in range, read it from the file directly
+            duration = crate::utils::read_file_duration(&audio).await?;
+        }
 
         Ok(AudioResourceReader {
             info: AudioInfo {
@@ -117,7 +121,7 @@ impl AnniProvider for NoCacheStrictLocalProvider {
         Ok(Box::pin(file))
     }
 
-    async fn reload(&mut self) -> crate::Result<()> {
+    async fn reload(&self) -> crate::Result<()> {
         Ok(())
     }
 }
@@ -2,7 +2,9 @@ use std::{borrow::Cow, collections::HashSet, num::NonZeroU8};
 
 use async_trait::async_trait;
 
-use crate::{AnniProvider, AudioResourceReader, ProviderError, Range, ResourceReader, Result};
+use crate::{
+    AnniProvider, AudioResourceReader, ProviderError, Range, ReloadReport, ResourceReader, Result,
+};
 
 pub type PriorityProvider = TypedPriorityProvider<Box<dyn AnniProvider + Send + Sync>>;
 
@@ -78,6 +80,25 @@ impl<P: AnniProvider + Send + Sync> AnniProvider for TypedPriorityProvider<P> {
         Err(ProviderError::FileNotFound)
     }
 
+    async fn get_audio_no_cache(
+        &self,
+        album_id: &str,
+        disc_id: NonZeroU8,
+        track_id: NonZeroU8,
+        range: Range,
+    ) -> Result<AudioResourceReader> {
+        for (_, provider) in self.0.iter() {
+            if let Ok(reader) = provider
+                .get_audio_no_cache(album_id, disc_id, track_id, range)
+                .await
+            {
+                return Ok(reader);
+            }
+        }
+
+        Err(ProviderError::FileNotFound)
+    }
+
     async fn get_cover(
         &self,
         album_id: &str,
@@ -95,15 +116,37 @@ impl<P: AnniProvider + Send + Sync> AnniProvider for TypedPriorityProvider<P> {
     /// Attempts to reload all providers.
     ///
     /// If multiple providers errors, the last error will be returned.
-    async fn reload(&mut self) -> Result<()> {
+    async fn reload(&self) -> Result<()> {
         let mut error = None;
 
-        for (_, provider) in self.0.iter_mut() {
+        for (_, provider) in self.0.iter() {
             error.replace(provider.reload().await);
         }
 
         error.unwrap_or(Ok(()))
     }
+
+    /// Attempts to reload all providers, and sums up their reports.
+    ///
+    /// If multiple providers errors, the last error will be returned.
+    async fn reload_report(&self) -> Result<ReloadReport> {
+        let mut report = ReloadReport::default();
+        let mut error = None;
+
+        for (_, provider) in self.0.iter() {
+            match provider.reload_report().await {
+                Ok(r) => report += r,
+                Err(e) => {
+                    error.replace(e);
+                }
+            }
+        }
+
+        match error {
+            Some(e) => Err(e),
+            None => Ok(report),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -124,7 +124,7 @@ impl AnniProvider for ProxyBackend {
         Ok(Box::pin(body))
     }
 
-    async fn reload(&mut self) -> Result<(), ProviderError> {
+    async fn reload(&self) -> Result<(), ProviderError> {
         // proxy provider does not need to be reloaded
         Ok(())
     }
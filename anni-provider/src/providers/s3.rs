@@ -0,0 +1,222 @@
+use crate::fs::{S3Client, S3FileSystemProvider};
+use crate::providers::CommonStrictProvider;
+use crate::{AnniProvider, AudioResourceReader, Range, ReloadReport, ResourceReader, Result};
+use async_trait::async_trait;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::num::NonZeroU8;
+use std::path::PathBuf;
+
+/// `S3Provider` serves albums stored in a S3-compatible bucket.
+///
+/// Albums can be organized in strict layout like [CommonStrictProvider],
+/// or in convention layout like [CommonConventionProvider](crate::providers::CommonConventionProvider).
+pub struct S3Provider(Box<dyn AnniProvider + Send + Sync>);
+
+impl S3Provider {
+    /// Create a provider whose albums are keyed by album id under `root`, with `layer` hash layers.
+    pub async fn strict(
+        client: impl S3Client + 'static,
+        bucket: String,
+        root: PathBuf,
+        layer: usize,
+    ) -> Result<Self> {
+        let fs = S3FileSystemProvider::new(client, bucket);
+        let provider = CommonStrictProvider::new(root, layer, Box::new(fs)).await?;
+        Ok(Self(Box::new(provider)))
+    }
+
+    /// Create a provider whose album folders under `root` are named by convention,
+    /// and matched with albums in metadata repository.
+    #[cfg(feature = "convention")]
+    pub async fn convention(
+        client: impl S3Client + 'static,
+        bucket: String,
+        root: PathBuf,
+        repo: crate::RepoDatabaseRead,
+    ) -> Result<Self> {
+        use crate::providers::CommonConventionProvider;
+
+        let fs = S3FileSystemProvider::new(client, bucket);
+        let provider = CommonConventionProvider::new(root, repo, Box::new(fs)).await?;
+        Ok(Self(Box::new(provider)))
+    }
+}
+
+#[async_trait]
+impl AnniProvider for S3Provider {
+    async fn albums(&self) -> Result<HashSet<Cow<str>>> {
+        self.0.albums().await
+    }
+
+    async fn get_audio(
+        &self,
+        album_id: &str,
+        disc_id: NonZeroU8,
+        track_id: NonZeroU8,
+        range: Range,
+    ) -> Result<AudioResourceReader> {
+        self.0.get_audio(album_id, disc_id, track_id, range).await
+    }
+
+    async fn get_cover(
+        &self,
+        album_id: &str,
+        disc_id: Option<NonZeroU8>,
+    ) -> Result<ResourceReader> {
+        self.0.get_cover(album_id, disc_id).await
+    }
+
+    async fn reload(&self) -> Result<()> {
+        self.0.reload().await
+    }
+
+    async fn reload_report(&self) -> Result<ReloadReport> {
+        self.0.reload_report().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::S3Provider;
+    use crate::fs::{S3Client, S3Listing};
+    use crate::{AnniProvider, ProviderError, Range, ResourceReader};
+    use parking_lot::Mutex;
+    use std::collections::BTreeMap;
+    use std::num::NonZeroU8;
+    use std::sync::Arc;
+    use tokio::io::AsyncReadExt;
+
+    const ALBUM_ID: &str = "c0e2ad3e-6b1a-4b0f-9a6a-2f5b6d4b8d1a";
+
+    /// In-memory bucket which records `Range` headers it received
+    #[derive(Default)]
+    struct MockS3 {
+        objects: BTreeMap<String, Vec<u8>>,
+        ranges: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl S3Client for MockS3 {
+        async fn list(&self, _bucket: &str, prefix: &str) -> crate::Result<S3Listing> {
+            let mut listing = S3Listing::default();
+            for key in self.objects.keys().filter(|k| k.starts_with(prefix)) {
+                match key[prefix.len()..].find('/') {
+                    Some(i) => {
+                        let p = key[..prefix.len() + i + 1].to_string();
+                        if !listing.prefixes.contains(&p) {
+                            listing.prefixes.push(p);
+                        }
+                    }
+                    None => listing.keys.push(key.clone()),
+                }
+            }
+            Ok(listing)
+        }
+
+        async fn get_object(
+            &self,
+            _bucket: &str,
+            key: &str,
+            range: Option<String>,
+        ) -> crate::Result<ResourceReader> {
+            let data = self.objects.get(key).ok_or(ProviderError::FileNotFound)?;
+            let data = match &range {
+                Some(range) => {
+                    let (start, end) = range["bytes=".len()..].split_once('-').unwrap();
+                    let start = start.parse().unwrap();
+                    let end = end.parse::<usize>().map_or(data.len(), |end| end + 1);
+                    data[start..end.min(data.len())].to_vec()
+                }
+                None => data.clone(),
+            };
+            self.ranges.lock().push(range);
+            Ok(Box::pin(std::io::Cursor::new(data)))
+        }
+
+        async fn content_length(&self, _bucket: &str, key: &str) -> crate::Result<u64> {
+            self.objects
+                .get(key)
+                .map(|data| data.len() as u64)
+                .ok_or(ProviderError::FileNotFound)
+        }
+    }
+
+    /// A flac file with only STREAMINFO block: 44100Hz, 2 channels, 16 bits, 10 seconds
+    fn flac() -> Vec<u8> {
+        let mut data = b"fLaC".to_vec();
+        data.extend([0x80, 0, 0, 34]);
+        data.extend([0x10, 0x00, 0x10, 0x00, 0, 0, 0, 0, 0, 0]);
+        let info: u64 = (44100 << 44) | (1 << 41) | (15 << 36) | 441000;
+        data.extend(info.to_be_bytes());
+        data.extend([0; 16]);
+        data.extend([0xff; 64]);
+        data
+    }
+
+    fn mock() -> MockS3 {
+        let album = format!("library/c0/e2/{ALBUM_ID}");
+        let mut s3 = MockS3::default();
+        s3.objects
+            .insert(format!("{album}/cover.jpg"), b"album".to_vec());
+        s3.objects
+            .insert(format!("{album}/1/cover.jpg"), b"disc".to_vec());
+        s3.objects.insert(format!("{album}/1/1.flac"), flac());
+        s3.objects
+            .insert("library/c0/e2/not-an-album/1/1.flac".to_string(), flac());
+        s3
+    }
+
+    async fn read_all(mut reader: ResourceReader) -> Vec<u8> {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).await.unwrap();
+        buf
+    }
+
+    #[tokio::test]
+    async fn test_strict_albums_and_cover() {
+        let provider = S3Provider::strict(mock(), "bucket".to_string(), "library".into(), 2)
+            .await
+            .unwrap();
+
+        let albums = provider.albums().await.unwrap();
+        assert_eq!(albums.len(), 1);
+        assert!(albums.contains(ALBUM_ID));
+
+        let cover = provider.get_cover(ALBUM_ID, None).await.unwrap();
+        assert_eq!(read_all(cover).await, b"album");
+        let cover = provider
+            .get_cover(ALBUM_ID, NonZeroU8::new(1))
+            .await
+            .unwrap();
+        assert_eq!(read_all(cover).await, b"disc");
+    }
+
+    #[tokio::test]
+    async fn test_strict_audio_range() {
+        let s3 = mock();
+        let ranges = s3.ranges.clone();
+        let provider = S3Provider::strict(s3, "bucket".to_string(), "library".into(), 2)
+            .await
+            .unwrap();
+        let one = NonZeroU8::new(1).unwrap();
+
+        let audio = provider
+            .get_audio(ALBUM_ID, one, one, Range::FULL)
+            .await
+            .unwrap();
+        assert_eq!(audio.info.extension, "flac");
+        assert_eq!(audio.info.size, flac().len());
+        assert_eq!(audio.info.duration, 10000);
+        assert_eq!(read_all(audio.reader).await, flac());
+
+        let audio = provider
+            .get_audio(ALBUM_ID, one, one, Range::new(42, Some(49)))
+            .await
+            .unwrap();
+        assert_eq!(audio.info.duration, 0);
+        assert_eq!(read_all(audio.reader).await, vec![0xff; 8]);
+
+        assert_eq!(*ranges.lock(), vec![None, Some("bytes=42-49".to_string())]);
+    }
+}
@@ -1,9 +1,10 @@
 use crate::{
     AnniProvider, AudioResourceReader, FileEntry, FileSystemProvider, ProviderError, Range,
-    ResourceReader, Result,
+    ReloadReport, ResourceReader, Result,
 };
 use async_trait::async_trait;
 use futures::StreamExt;
+use parking_lot::RwLock;
 use std::borrow::Cow;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::num::NonZeroU8;
@@ -14,7 +15,7 @@ pub struct CommonStrictProvider {
     root: PathBuf,
     layer: usize,
     fs: Box<dyn FileSystemProvider + Send + Sync>,
-    folders: HashMap<String, FileEntry>,
+    folders: RwLock<HashMap<String, FileEntry>>,
 }
 
 impl CommonStrictProvider {
@@ -23,11 +24,11 @@ impl CommonStrictProvider {
         layer: usize,
         fs: Box<dyn FileSystemProvider + Send + Sync>,
     ) -> Result<Self> {
-        let mut me = Self {
+        let me = Self {
             root,
             layer,
             fs,
-            folders: HashMap::new(),
+            folders: Default::default(),
         };
         me.reload().await?;
         Ok(me)
@@ -39,8 +40,9 @@ impl AnniProvider for CommonStrictProvider {
     async fn albums(&self) -> Result<HashSet<Cow<str>>> {
         Ok(self
             .folders
+            .read()
             .keys()
-            .map(|c| Cow::Borrowed(c.as_str()))
+            .map(|c| Cow::Owned(c.clone()))
             .collect())
     }
 
@@ -74,10 +76,7 @@ impl AnniProvider for CommonStrictProvider {
                 self.fs.get_file(&cover.path, Range::FULL).await
             }
             None => {
-                let album = self
-                    .folders
-                    .get(album_id)
-                    .ok_or(ProviderError::FileNotFound)?;
+                let album = self.get_album(album_id)?;
                 let cover = self
                     .fs
                     .get_file_entry_by_prefix(&album.path, "cover.jpg")
@@ -87,19 +86,22 @@ impl AnniProvider for CommonStrictProvider {
         }
     }
 
-    async fn reload(&mut self) -> Result<()> {
+    async fn reload(&self) -> Result<()> {
         self.fs.reload().await?;
         self.reload_albums().await?;
         Ok(())
     }
+
+    async fn reload_report(&self) -> Result<ReloadReport> {
+        let old: HashSet<String> = self.folders.read().keys().cloned().collect();
+        self.reload().await?;
+        Ok(ReloadReport::diff(&old, self.folders.read().keys()))
+    }
 }
 
 impl CommonStrictProvider {
     pub async fn get_disc(&self, album_id: &str, disc_id: NonZeroU8) -> Result<FileEntry> {
-        let folder = self
-            .folders
-            .get(album_id)
-            .ok_or(ProviderError::FileNotFound)?;
+        let folder = self.get_album(album_id)?;
         let mut folders = self.fs.children(&folder.path).await?;
         while let Some(folder) = folders.next().await {
             if folder.name == format!("{disc_id}") {
@@ -109,8 +111,16 @@ impl CommonStrictProvider {
         Err(ProviderError::FileNotFound)
     }
 
-    pub async fn reload_albums(&mut self) -> Result<()> {
-        self.folders.clear();
+    fn get_album(&self, album_id: &str) -> Result<FileEntry> {
+        self.folders
+            .read()
+            .get(album_id)
+            .cloned()
+            .ok_or(ProviderError::FileNotFound)
+    }
+
+    pub async fn reload_albums(&self) -> Result<()> {
+        let mut folders = HashMap::new();
 
         let mut vis = VecDeque::from([(self.root.clone(), 0)]);
         while let Some((ref path, layer)) = vis.pop_front() {
@@ -120,7 +130,7 @@ impl CommonStrictProvider {
                 while let Some(entry) = reader.next().await {
                     match Uuid::parse_str(&entry.name) {
                         Ok(album_id) => {
-                            self.folders.insert(album_id.to_string(), entry);
+                            folders.insert(album_id.to_string(), entry);
                         }
                         _ => log::warn!("Unexpected dir: {path:?}"),
                     }
@@ -132,6 +142,68 @@ impl CommonStrictProvider {
             }
         }
 
+        // replace albums at once, so that requests during reload still see the old ones
+        *self.folders.write() = folders;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::CommonStrictProvider;
+    use crate::fs::LocalFileSystemProvider;
+    use crate::{AnniProvider, Range, ReloadReport};
+    use std::num::NonZeroU8;
+
+    const ALBUM_ID: &str = "c0e2ad3e-6b1a-4b0f-9a6a-2f5b6d4b8d1a";
+    const NEW_ALBUM_ID: &str = "5f0b5a5e-4a0e-4f3d-8c7b-1d6f2a9e3b40";
+
+    #[tokio::test]
+    async fn test_reload_report_added() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("c0/e2").join(ALBUM_ID)).unwrap();
+
+        let provider = CommonStrictProvider::new(
+            root.path().to_path_buf(),
+            2,
+            Box::new(LocalFileSystemProvider),
+        )
+        .await
+        .unwrap();
+        assert_eq!(provider.albums().await.unwrap().len(), 1);
+
+        std::fs::create_dir_all(root.path().join("5f/0b").join(NEW_ALBUM_ID)).unwrap();
+        let report = provider.reload_report().await.unwrap();
+        assert_eq!(
+            report,
+            ReloadReport {
+                added: 1,
+                removed: 0
+            }
+        );
+        assert!(provider.has_album(NEW_ALBUM_ID).await);
+    }
+
+    #[tokio::test]
+    async fn test_duration_without_header_in_range() {
+        let root = tempfile::tempdir().unwrap();
+        let disc = root.path().join("c0/e2").join(ALBUM_ID).join("1");
+        std::fs::create_dir_all(&disc).unwrap();
+        std::fs::copy("../assets/1s.flac", disc.join("1.flac")).unwrap();
+
+        let provider = CommonStrictProvider::new(
+            root.path().to_path_buf(),
+            2,
+            Box::new(LocalFileSystemProvider),
+        )
+        .await
+        .unwrap();
+        let one = NonZeroU8::new(1).unwrap();
+
+        let audio = provider
+            .get_audio(ALBUM_ID, one, one, Range::new(100, Some(199)))
+            .await
+            .unwrap();
+        assert_eq!(audio.info.duration, 1000);
+    }
+}
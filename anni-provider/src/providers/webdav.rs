@@ -0,0 +1,471 @@
+use crate::common::content_range_to_range;
+use crate::utils::read_duration;
+use crate::{
+    AnniProvider, AudioInfo, AudioResourceReader, ProviderError, Range, ReloadReport,
+    ResourceReader,
+};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use parking_lot::RwLock;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use reqwest::{Method, RequestBuilder, StatusCode, Url};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::num::NonZeroU8;
+use uuid::Uuid;
+
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:"><d:prop><d:resourcetype/></d:prop></d:propfind>"#;
+
+pub enum WebDavAuth {
+    None,
+    Basic { username: String, password: String },
+    Bearer(String),
+}
+
+pub struct WebDavProviderSettings {
+    /// Url of the library root folder
+    pub url: String,
+    pub auth: WebDavAuth,
+    /// Hash layers of strict layout
+    pub layer: usize,
+}
+
+/// `WebDavProvider` serves albums in strict layout from a WebDAV share.
+///
+/// Album folders are enumerated with `PROPFIND` on reload, while audio and cover files are
+/// fetched by path directly.
+pub struct WebDavProvider {
+    client: reqwest::Client,
+    settings: WebDavProviderSettings,
+    root: Url,
+    /// album_id <-> folder url
+    albums: RwLock<HashMap<String, Url>>,
+}
+
+impl WebDavProvider {
+    pub async fn new(settings: WebDavProviderSettings) -> Result<Self, ProviderError> {
+        let mut root = Url::parse(&settings.url).map_err(|_| ProviderError::InvalidPath)?;
+        if !root.path().ends_with('/') {
+            root.set_path(&format!("{}/", root.path()));
+        }
+
+        let this = Self {
+            client: reqwest::Client::new(),
+            settings,
+            root,
+            albums: Default::default(),
+        };
+        this.reload().await?;
+        Ok(this)
+    }
+
+    fn request(&self, method: Method, url: Url) -> RequestBuilder {
+        let request = self.client.request(method, url);
+        match &self.settings.auth {
+            WebDavAuth::None => request,
+            WebDavAuth::Basic { username, password } => {
+                request.basic_auth(username, Some(password))
+            }
+            WebDavAuth::Bearer(token) => request.bearer_auth(token),
+        }
+    }
+
+    /// List sub folders of `url`, returns their names and urls
+    async fn list_folders(&self, url: &Url) -> Result<Vec<(String, Url)>, ProviderError> {
+        let body = self
+            .request(Method::from_bytes(b"PROPFIND").unwrap(), url.clone())
+            .header("Depth", "1")
+            .header("Content-Type", "application/xml")
+            .body(PROPFIND_BODY)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        let mut folders = Vec::new();
+        for href in parse_collections(&body)? {
+            let mut folder = url.join(&href).map_err(|_| ProviderError::InvalidPath)?;
+            if !folder.path().ends_with('/') {
+                folder.set_path(&format!("{}/", folder.path()));
+            }
+            // the requested folder itself is also included in the response
+            if folder.path() == url.path() {
+                continue;
+            }
+            let name = folder
+                .path_segments()
+                .and_then(|mut segments| segments.rfind(|s| !s.is_empty()))
+                .map(|name| name.to_string());
+            if let Some(name) = name {
+                folders.push((name, folder));
+            }
+        }
+        Ok(folders)
+    }
+
+    /// Get file with a ranged `GET`, returns the reader, actual range and file size
+    async fn get_file(
+        &self,
+        url: Url,
+        range: &Range,
+    ) -> Result<(ResourceReader, Range, usize), ProviderError> {
+        let mut request = self.request(Method::GET, url);
+        if let Some(range) = range.to_range_header() {
+            request = request.header("Range", range);
+        }
+        let response = request.send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(ProviderError::FileNotFound);
+        }
+        let response = response.error_for_status()?;
+
+        let content_range = response
+            .headers()
+            .get("Content-Range")
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+        let range = content_range_to_range(content_range.as_deref());
+        let size = match range.total {
+            Some(total) => total as usize,
+            None => response.content_length().unwrap_or_default() as usize,
+        };
+
+        let body = response
+            .bytes_stream()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))
+            .into_async_read();
+        let body = tokio_util::compat::FuturesAsyncReadCompatExt::compat(body);
+        Ok((Box::pin(body), range, size))
+    }
+
+    fn file_url(&self, album_id: &str, path: &str) -> Result<Url, ProviderError> {
+        self.albums
+            .read()
+            .get(album_id)
+            .ok_or(ProviderError::FileNotFound)?
+            .join(path)
+            .map_err(|_| ProviderError::InvalidPath)
+    }
+}
+
+#[async_trait]
+impl AnniProvider for WebDavProvider {
+    async fn albums(&self) -> Result<HashSet<Cow<str>>, ProviderError> {
+        Ok(self
+            .albums
+            .read()
+            .keys()
+            .map(|a| Cow::Owned(a.clone()))
+            .collect())
+    }
+
+    async fn get_audio(
+        &self,
+        album_id: &str,
+        disc_id: NonZeroU8,
+        track_id: NonZeroU8,
+        range: Range,
+    ) -> Result<AudioResourceReader, ProviderError> {
+        let url = self.file_url(album_id, &format!("{disc_id}/{track_id}.flac"))?;
+        let (reader, range, size) = self.get_file(url, &range).await?;
+        let (duration, reader) = read_duration(reader, range).await?;
+        Ok(AudioResourceReader {
+            info: AudioInfo {
+                extension: "flac".to_string(),
+                size,
+                duration,
+            },
+            range,
+            reader,
+        })
+    }
+
+    async fn get_cover(
+        &self,
+        album_id: &str,
+        disc_id: Option<NonZeroU8>,
+    ) -> Result<ResourceReader, ProviderError> {
+        let path = match disc_id {
+            Some(disc_id) => format!("{disc_id}/cover.jpg"),
+            None => "cover.jpg".to_string(),
+        };
+        let url = self.file_url(album_id, &path)?;
+        Ok(self.get_file(url, &Range::FULL).await?.0)
+    }
+
+    async fn reload(&self) -> Result<(), ProviderError> {
+        let mut albums = HashMap::new();
+
+        let mut vis = VecDeque::from([(self.root.clone(), 0)]);
+        while let Some((url, layer)) = vis.pop_front() {
+            log::debug!("Walking dir: {url}");
+            for (name, folder) in self.list_folders(&url).await? {
+                if layer == self.settings.layer {
+                    match Uuid::parse_str(&name) {
+                        Ok(album_id) => {
+                            albums.insert(album_id.to_string(), folder);
+                        }
+                        _ => log::warn!("Unexpected dir: {folder}"),
+                    }
+                } else {
+                    vis.push_back((folder, layer + 1));
+                }
+            }
+        }
+
+        *self.albums.write() = albums;
+        Ok(())
+    }
+
+    async fn reload_report(&self) -> Result<ReloadReport, ProviderError> {
+        let old: HashSet<String> = self.albums.read().keys().cloned().collect();
+        self.reload().await?;
+        Ok(ReloadReport::diff(&old, self.albums.read().keys()))
+    }
+}
+
+/// Extract `href` of collections from a `PROPFIND` multistatus response
+fn parse_collections(xml: &str) -> Result<Vec<String>, ProviderError> {
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(true);
+
+    let mut result = Vec::new();
+    let mut href = None;
+    let mut in_href = false;
+    let mut is_collection = false;
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"response" => {
+                    href = None;
+                    is_collection = false;
+                }
+                b"href" => in_href = true,
+                b"collection" => is_collection = true,
+                _ => {}
+            },
+            Event::Empty(e) if e.local_name().as_ref() == b"collection" => is_collection = true,
+            Event::Text(text) if in_href => href = Some(text.unescape()?.into_owned()),
+            Event::End(e) => match e.local_name().as_ref() {
+                b"href" => in_href = false,
+                b"response" => {
+                    if let (true, Some(href)) = (is_collection, href.take()) {
+                        result.push(href);
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_collections, WebDavAuth, WebDavProvider, WebDavProviderSettings};
+    use crate::{AnniProvider, Range};
+    use std::collections::HashMap;
+    use std::num::NonZeroU8;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    const ALBUM_ID: &str = "c0e2ad3e-6b1a-4b0f-9a6a-2f5b6d4b8d1a";
+
+    fn multistatus(folder: &str, children: &[&str]) -> String {
+        let mut body = String::from(r#"<?xml version="1.0"?><d:multistatus xmlns:d="DAV:">"#);
+        for href in std::iter::once(&folder).chain(children) {
+            let resource_type = if href.ends_with('/') {
+                "<d:collection/>"
+            } else {
+                ""
+            };
+            body += &format!(
+                "<d:response><d:href>{href}</d:href><d:propstat><d:prop>\
+                 <d:resourcetype>{resource_type}</d:resourcetype>\
+                 </d:prop></d:propstat></d:response>"
+            );
+        }
+        body + "</d:multistatus>"
+    }
+
+    /// Minimal WebDAV server, which records `Range` headers of `GET` requests
+    async fn serve(files: HashMap<String, Vec<u8>>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let recorded = ranges.clone();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut stream = BufReader::new(stream);
+
+                let mut request_line = String::new();
+                stream.read_line(&mut request_line).await.unwrap();
+                let mut parts = request_line.split_whitespace();
+                let method = parts.next().unwrap().to_string();
+                let path = parts.next().unwrap().to_string();
+
+                let mut headers = HashMap::new();
+                loop {
+                    let mut line = String::new();
+                    stream.read_line(&mut line).await.unwrap();
+                    match line.trim_end().split_once(": ") {
+                        Some((key, value)) => {
+                            headers.insert(key.to_lowercase(), value.to_string());
+                        }
+                        None => break,
+                    }
+                }
+                let length = headers
+                    .get("content-length")
+                    .map_or(0, |l| l.parse().unwrap());
+                let mut body = vec![0; length];
+                stream.read_exact(&mut body).await.unwrap();
+
+                let (status, extra, body) = if headers.get("authorization").map(String::as_str)
+                    != Some("Bearer token")
+                {
+                    ("401 Unauthorized", String::new(), Vec::new())
+                } else if method == "PROPFIND" {
+                    let prefix = path.clone();
+                    let mut children: Vec<String> = files
+                        .keys()
+                        .filter_map(|p| {
+                            let rest = p.strip_prefix(&prefix)?;
+                            Some(match rest.find('/') {
+                                Some(i) => format!("{prefix}{}", &rest[..=i]),
+                                None => p.clone(),
+                            })
+                        })
+                        .collect();
+                    children.sort();
+                    children.dedup();
+                    let children: Vec<_> = children.iter().map(String::as_str).collect();
+                    let body = multistatus(&path, &children);
+                    ("207 Multi-Status", String::new(), body.into_bytes())
+                } else {
+                    match files.get(&path) {
+                        Some(data) => match headers.get("range") {
+                            Some(range) => {
+                                recorded.lock().unwrap().push(range.clone());
+                                let (start, end) = range["bytes=".len()..].split_once('-').unwrap();
+                                let start: usize = start.parse().unwrap();
+                                let end = end
+                                    .parse::<usize>()
+                                    .map_or(data.len() - 1, |e| e.min(data.len() - 1));
+                                (
+                                    "206 Partial Content",
+                                    format!(
+                                        "Content-Range: bytes {start}-{end}/{}\r\n",
+                                        data.len()
+                                    ),
+                                    data[start..=end].to_vec(),
+                                )
+                            }
+                            None => ("200 OK", String::new(), data.clone()),
+                        },
+                        None => ("404 Not Found", String::new(), Vec::new()),
+                    }
+                };
+
+                let stream = stream.get_mut();
+                let head = format!(
+                    "HTTP/1.1 {status}\r\n{extra}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                );
+                stream.write_all(head.as_bytes()).await.unwrap();
+                stream.write_all(&body).await.unwrap();
+                stream.shutdown().await.unwrap();
+            }
+        });
+        (format!("http://{addr}/dav"), ranges)
+    }
+
+    fn files() -> HashMap<String, Vec<u8>> {
+        let album = format!("/dav/c0/e2/{ALBUM_ID}");
+        HashMap::from([
+            (format!("{album}/cover.jpg"), b"album".to_vec()),
+            (format!("{album}/1/1.flac"), (0..=255).collect()),
+            ("/dav/c0/e2/not-an-album/1/1.flac".to_string(), Vec::new()),
+        ])
+    }
+
+    async fn provider(url: String) -> WebDavProvider {
+        WebDavProvider::new(WebDavProviderSettings {
+            url,
+            auth: WebDavAuth::Bearer("token".to_string()),
+            layer: 2,
+        })
+        .await
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_collections() {
+        let xml = multistatus("/dav/", &["/dav/a/", "/dav/b.txt", "/dav/c%20d/"]);
+        assert_eq!(
+            parse_collections(&xml).unwrap(),
+            vec!["/dav/", "/dav/a/", "/dav/c%20d/"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_enumerate_albums() {
+        let (url, _) = serve(files()).await;
+        let provider = provider(url).await;
+
+        let albums = provider.albums().await.unwrap();
+        assert_eq!(albums.len(), 1);
+        assert!(albums.contains(ALBUM_ID));
+
+        let mut cover = Vec::new();
+        provider
+            .get_cover(ALBUM_ID, None)
+            .await
+            .unwrap()
+            .read_to_end(&mut cover)
+            .await
+            .unwrap();
+        assert_eq!(cover, b"album");
+    }
+
+    #[tokio::test]
+    async fn test_ranged_audio() {
+        let (url, ranges) = serve(files()).await;
+        let provider = provider(url).await;
+        let one = NonZeroU8::new(1).unwrap();
+
+        let mut audio = provider
+            .get_audio(ALBUM_ID, one, one, Range::new(100, Some(103)))
+            .await
+            .unwrap();
+        assert_eq!(audio.info.size, 256);
+        assert_eq!(audio.info.duration, 0);
+        assert_eq!(audio.range.start, 100);
+        assert_eq!(audio.range.end, Some(103));
+        assert_eq!(audio.range.total, Some(256));
+
+        let mut data = Vec::new();
+        audio.reader.read_to_end(&mut data).await.unwrap();
+        assert_eq!(data, vec![100, 101, 102, 103]);
+        assert_eq!(*ranges.lock().unwrap(), vec!["bytes=100-103"]);
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized() {
+        let (url, _) = serve(files()).await;
+        let result = WebDavProvider::new(WebDavProviderSettings {
+            url,
+            auth: WebDavAuth::None,
+            layer: 2,
+        })
+        .await;
+        assert!(result.is_err());
+    }
+}
@@ -2,6 +2,7 @@ use crate::{Range, ResourceReader};
 use anni_flac::blocks::BlockStreamInfo;
 use anni_flac::prelude::{AsyncDecode, Encode, Result};
 use std::io::Cursor;
+use std::path::Path;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
 
 async fn read_header<R>(mut reader: R) -> Result<(BlockStreamInfo, ResourceReader)>
@@ -30,6 +31,17 @@ pub(crate) async fn read_duration(
     }
 
     let (info, reader) = read_header(reader).await?;
-    let duration = info.total_samples * 1000 / info.sample_rate as u64;
-    Ok((duration, Box::pin(reader)))
+    Ok((duration_of(&info), Box::pin(reader)))
+}
+
+/// Read duration of a local flac file from its STREAMINFO block.
+pub(crate) async fn read_file_duration(path: &Path) -> Result<u64> {
+    let file = tokio::fs::File::open(path).await?;
+    let (info, _) = read_header(file).await?;
+    Ok(duration_of(&info))
+}
+
+/// Duration of audio in milliseconds
+fn duration_of(info: &BlockStreamInfo) -> u64 {
+    info.total_samples * 1000 / info.sample_rate as u64
 }
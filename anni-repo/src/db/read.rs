@@ -48,22 +48,35 @@ impl RepoDatabaseRead {
         release_date: &AnniDate,
         disc_count: u8,
         album_title: &str,
-        _edition: Option<&str>,
+        edition: Option<&str>,
     ) -> RepoResult<Option<Uuid>> {
-        log::trace!("Catalog: {catalog}, Title: {album_title}, Release date: {release_date}, Discs: {disc_count}");
+        // empty edition is the same as no edition
+        let edition = edition.filter(|e| !e.is_empty());
+        log::trace!("Catalog: {catalog}, Title: {album_title}, Edition: {edition:?}, Release date: {release_date}, Discs: {disc_count}");
         let mut stmt = self.conn.prepare(
-            "SELECT album_id, title FROM repo_album
+            "SELECT album_id, title, edition FROM repo_album
   WHERE catalog = ? AND release_date = ? AND disc_count = ?;",
         )?;
         let albums_iter = stmt.query_map(
             params![catalog, release_date.to_string(), disc_count],
-            |row| Ok((row.get(0)?, row.get(1)?)),
+            |row| {
+                let edition: Option<String> = row.get(2)?;
+                Ok((row.get(0)?, row.get(1)?, edition.filter(|e| !e.is_empty())))
+            },
         )?;
-        let mut albums: Vec<(Uuid, String)> = Vec::new();
+        let mut albums: Vec<(Uuid, String, Option<String>)> = Vec::new();
         for album in albums_iter {
             albums.push(album?);
         }
 
+        if edition.is_some() {
+            // edition must agree if provided
+            albums.retain(|(_, _, e)| e.as_deref() == edition);
+        } else if albums.iter().any(|(_, _, e)| e.is_none()) {
+            // prefer albums without edition, but still match albums with edition for folders without it
+            albums.retain(|(_, _, e)| e.is_none());
+        }
+
         if albums.is_empty() {
             Ok(None)
         } else if albums.len() == 1 {
@@ -71,7 +84,7 @@ impl RepoDatabaseRead {
         } else {
             let filtered: Vec<_> = albums
                 .iter()
-                .filter(|(_, title)| title == album_title)
+                .filter(|(_, title, _)| title == album_title)
                 .collect();
             if filtered.is_empty() {
                 Ok(None)
@@ -85,6 +98,32 @@ impl RepoDatabaseRead {
         }
     }
 
+    /// Find albums released between `from` and `to`, both inclusive, ordered by release date.
+    ///
+    /// Missing month or day is treated as the whole year or month, so an album released in `2020`
+    /// matches any range that overlaps with 2020, and range `2020..=2020-06` covers `2020-01-01..=2020-06-30`.
+    pub fn albums_by_date_range(&self, from: &AnniDate, to: &AnniDate) -> RepoResult<Vec<Uuid>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT album_id FROM repo_album
+  WHERE release_year BETWEEN ? AND ?
+    AND release_year * 10000 + COALESCE(release_month, 12) * 100 + COALESCE(release_day, 31) >= ?
+    AND release_year * 10000 + COALESCE(release_month, 0) * 100 + COALESCE(release_day, 0) <= ?
+  ORDER BY release_year, release_month, release_day, catalog;",
+        )?;
+        let albums = stmt
+            .query_map(
+                params![
+                    from.year(),
+                    to.year(),
+                    date_key(from, false),
+                    date_key(to, true)
+                ],
+                |row| row.get(0),
+            )?
+            .collect::<Result<_, _>>()?;
+        Ok(albums)
+    }
+
     #[doc(hidden)]
     pub fn query_optional<P, T>(&self, sql: &str, params: P) -> RepoResult<Option<T>>
     where
@@ -127,12 +166,14 @@ impl RepoDatabaseRead {
         let album_info = AlbumInfo {
             album_id,
             title: album_row.title,
-            edition: album_row.edition,
+            edition: album_row.edition.filter(|e| !e.is_empty()),
             artist: album_row.artist,
             artists: None,
             release_date: AnniDate::from_str(&album_row.release_date)?,
             album_type: TrackType::from_str(&album_row.album_type)?,
             catalog: album_row.catalog,
+            gain: album_row.gain,
+            peak: album_row.peak,
             tags: album_tags,
         };
 
@@ -154,14 +195,16 @@ impl RepoDatabaseRead {
             for track in tracks_row {
                 let track_tags =
                     self.get_item_tags(album_id, Some(disc.disc_id), Some(track.track_id))?;
-                let track = Track::new(
+                let mut track_info = Track::new(
                     track.title,
                     Some(track.artist),
                     None,
                     Some(TrackType::from_str(&track.track_type)?),
                     track_tags,
                 );
-                tracks.push(track);
+                track_info.gain = track.gain;
+                track_info.peak = track.peak;
+                tracks.push(track_info);
             }
 
             let disc = Disc::new(disc_info, tracks);
@@ -172,6 +215,21 @@ impl RepoDatabaseRead {
         Ok(Some(album))
     }
 
+    /// Iterate over all albums in the database.
+    ///
+    /// Albums are read lazily, one at a time, so the whole repository is never held in memory.
+    pub fn albums_iter(&self) -> RepoResult<impl Iterator<Item = RepoResult<Album>> + '_> {
+        let album_ids: Vec<rows::AlbumIdRow> =
+            self.query_list("SELECT album_id FROM repo_album", ())?;
+        Ok(album_ids
+            .into_iter()
+            .filter_map(move |row| self.read_album(row.album_id.0).transpose()))
+    }
+
+    pub fn get_albums(&self) -> RepoResult<Vec<rows::AlbumRow>> {
+        self.query_list("SELECT * FROM repo_album", ())
+    }
+
     pub fn get_album(&self, album_id: Uuid) -> RepoResult<Option<rows::AlbumRow>> {
         self.query_optional("SELECT * FROM repo_album WHERE album_id = ?", [album_id])
     }
@@ -338,6 +396,20 @@ SELECT * FROM repo_album WHERE album_id IN (
     }
 }
 
+/// Encode date as `yyyymmdd`, filling missing month or day with the start or end of the period.
+fn date_key(date: &AnniDate, end: bool) -> u32 {
+    // month and day are 0 if absent
+    let month = date.month().filter(|month| *month != 0);
+    let day = month.and(date.day().filter(|day| *day != 0));
+    let (month, day) = match (month, day) {
+        (Some(month), Some(day)) => (month, day),
+        (Some(month), None) => (month, if end { 31 } else { 0 }),
+        (None, _) if end => (12, 31),
+        (None, _) => (0, 0),
+    };
+    date.year() as u32 * 10000 + month as u32 * 100 + day as u32
+}
+
 #[cfg(target_arch = "wasm32")]
 mod wasm {
     use super::rows::wasm::*;
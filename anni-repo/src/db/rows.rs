@@ -23,6 +23,11 @@ impl Serialize for UuidRow {
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct AlbumIdRow {
+    pub album_id: UuidRow,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AlbumRow {
     pub album_id: UuidRow,
@@ -33,6 +38,8 @@ pub struct AlbumRow {
     pub release_date: String,
     #[serde(rename(serialize = "type"))]
     pub album_type: String,
+    pub gain: Option<f64>,
+    pub peak: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -55,6 +62,8 @@ pub struct TrackRow {
     pub artist: String,
     #[serde(rename(serialize = "type"))]
     pub track_type: String,
+    pub gain: Option<f64>,
+    pub peak: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -83,6 +92,8 @@ interface AlbumRow {
     artist: string;
     release_date: string;
     type: TrackType;
+    gain?: number;
+    peak?: number;
 }
 
 type AlbumRowArray = AlbumRow[];
@@ -105,6 +116,8 @@ interface TrackRow {
     title: string;
     artist: string;
     type: TrackType;
+    gain?: number;
+    peak?: number;
 }
 
 type TrackRowArray = TrackRow[];
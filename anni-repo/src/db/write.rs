@@ -1,9 +1,11 @@
 use crate::db::DB_VERSION;
 use crate::prelude::RepoResult;
-use anni_metadata::model::{Album, Tag, TagType};
+use crate::TagAliases;
+use anni_metadata::model::{Album, Tag, TagRef, TagType};
 use rusqlite::{params, Connection};
 use std::collections::HashMap;
 use std::path::Path;
+use uuid::Uuid;
 
 pub struct RepoDatabaseWrite {
     conn: Connection,
@@ -19,6 +21,26 @@ impl RepoDatabaseWrite {
         Ok(me)
     }
 
+    /// Open an existing database for in-place updates.
+    ///
+    /// Unlike [RepoDatabaseWrite::create], journal is kept so that an interrupted update can be rolled back.
+    pub fn open(path: impl AsRef<Path>) -> RepoResult<Self> {
+        let conn = Connection::open(path)?;
+        let me = Self { conn };
+        me.create_tables()?;
+        Ok(me)
+    }
+
+    pub fn begin(&self) -> RepoResult<()> {
+        self.conn.execute_batch("BEGIN")?;
+        Ok(())
+    }
+
+    pub fn commit(&self) -> RepoResult<()> {
+        self.conn.execute_batch("COMMIT")?;
+        Ok(())
+    }
+
     fn create_tables(&self) -> RepoResult<()> {
         self.conn.execute_batch(r#"
 BEGIN;
@@ -30,8 +52,13 @@ CREATE TABLE IF NOT EXISTS "repo_album" (
   "catalog"        TEXT NOT NULL,
   "artist"         TEXT NOT NULL,
   "release_date"   TEXT NOT NULL,
+  "release_year"   INTEGER NOT NULL,
+  "release_month"  INTEGER,
+  "release_day"    INTEGER,
   "disc_count"     INTEGER NOT NULL,
-  "album_type"     TEXT NOT NULL DEFAULT 'normal' CHECK("album_type" IN ('normal', 'instrumental', 'absolute', 'drama', 'radio', 'vocal'))
+  "album_type"     TEXT NOT NULL DEFAULT 'normal' CHECK("album_type" IN ('normal', 'instrumental', 'absolute', 'drama', 'radio', 'vocal')),
+  "gain"           REAL,
+  "peak"           REAL
 );
 
 CREATE TABLE IF NOT EXISTS "repo_disc" (
@@ -53,6 +80,8 @@ CREATE TABLE IF NOT EXISTS "repo_track" (
   "title"        TEXT NOT NULL,
   "artist"       TEXT NOT NULL,
   "track_type"   TEXT NOT NULL DEFAULT 'normal' CHECK("track_type" IN ('normal', 'instrumental', 'absolute', 'drama', 'radio', 'vocal')),
+  "gain"         REAL,
+  "peak"         REAL,
   UNIQUE("album_id","disc_id","track_id"),
   FOREIGN KEY("album_id", "disc_id") REFERENCES "repo_disc"("album_id", "disc_id")
 );
@@ -92,6 +121,11 @@ CREATE TABLE IF NOT EXISTS "repo_info" (
   "value"  TEXT
 );
 
+CREATE TABLE IF NOT EXISTS "repo_album_hash" (
+  "album_id"  BLOB NOT NULL UNIQUE,
+  "hash"      BLOB NOT NULL
+);
+
 COMMIT;"#)?;
         Ok(())
     }
@@ -105,6 +139,12 @@ CREATE UNIQUE INDEX "repo_album_index" ON "repo_album" (
   "album_id"
 );
 
+CREATE INDEX IF NOT EXISTS "repo_album_date_index" ON "repo_album" (
+  "release_year",
+  "release_month",
+  "release_day"
+);
+
 CREATE UNIQUE INDEX IF NOT EXISTS "repo_disc_index" ON "repo_disc" (
   "album_id",
   "disc_id"
@@ -129,21 +169,33 @@ COMMIT;
         Ok(())
     }
 
-    pub fn add_album(&self, album: &Album) -> RepoResult<()> {
+    /// Add `album` with its discs, tracks and tags.
+    ///
+    /// Tags referenced by alias are resolved to their canonical tags with `aliases`.
+    pub fn add_album(&self, album: &Album, aliases: &TagAliases) -> RepoResult<()> {
         let album_id = album.album_id();
+        let release_date = album.release_date();
+        // month and day are 0 if absent
+        let release_month = release_date.month().filter(|month| *month != 0);
+        let release_day = release_month.and(release_date.day().filter(|day| *day != 0));
 
         // add album info
         self.conn.execute(
-            "INSERT INTO repo_album (album_id, title, edition, catalog, artist, release_date, disc_count, album_type) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            "INSERT INTO repo_album (album_id, title, edition, catalog, artist, release_date, release_year, release_month, release_day, disc_count, album_type, gain, peak) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             params![
                 album_id,
                 album.title_raw(),
                 album.edition(),
                 album.catalog(),
                 album.artist(),
-                album.release_date().to_string(),
+                release_date.to_string(),
+                release_date.year(),
+                release_month,
+                release_day,
                 album.discs_len(),
                 album.track_type().as_ref(),
+                album.gain(),
+                album.peak(),
             ],
         )?;
 
@@ -153,7 +205,7 @@ COMMIT;
                 "INSERT INTO repo_tag_detail (album_id, tag_id) SELECT ?, tag_id FROM repo_tag WHERE name = ?",
                 params![
                     album_id,
-                    tag.name(),
+                    canonical(aliases, tag).name(),
                 ],
             )?;
         }
@@ -182,7 +234,7 @@ COMMIT;
                     params![
                         album_id,
                         disc_id,
-                        tag.name(),
+                        canonical(aliases, tag).name(),
                     ],
                 )?;
             }
@@ -192,7 +244,7 @@ COMMIT;
 
                 // add track info
                 self.conn.execute(
-                    "INSERT INTO repo_track (album_id, disc_id, track_id, title, artist, track_type) VALUES (?, ?, ?, ?, ?, ?)",
+                    "INSERT INTO repo_track (album_id, disc_id, track_id, title, artist, track_type, gain, peak) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
                     params![
                         album_id,
                         disc_id,
@@ -200,6 +252,8 @@ COMMIT;
                         track.title(),
                         track.artist(),
                         track.track_type().as_ref(),
+                        track.gain(),
+                        track.peak(),
                     ],
                 )?;
 
@@ -211,7 +265,7 @@ COMMIT;
                             album_id,
                             disc_id,
                             track_id,
-                            tag.name(),
+                            canonical(aliases, tag).name(),
                         ],
                     )?;
                 }
@@ -220,6 +274,42 @@ COMMIT;
         Ok(())
     }
 
+    /// Remove an album with its discs, tracks and tags.
+    pub fn remove_album(&self, album_id: Uuid) -> RepoResult<()> {
+        for table in [
+            "repo_tag_detail",
+            "repo_track",
+            "repo_disc",
+            "repo_album",
+            "repo_album_hash",
+        ] {
+            self.conn.execute(
+                &format!("DELETE FROM {table} WHERE album_id = ?"),
+                [album_id],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Hashes of album files the database was built from.
+    pub fn album_hashes(&self) -> RepoResult<HashMap<Uuid, Vec<u8>>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT album_id, hash FROM repo_album_hash")?;
+        let hashes = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        Ok(hashes)
+    }
+
+    pub fn set_album_hash(&self, album_id: Uuid, hash: &[u8]) -> RepoResult<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO repo_album_hash (album_id, hash) VALUES (?, ?)",
+            params![album_id, hash],
+        )?;
+        Ok(())
+    }
+
     fn add_tag(&self, name: &str, tag_type: &TagType) -> RepoResult<i32> {
         self.conn.execute(
             "INSERT INTO repo_tag (name, tag_type) VALUES (?, ?)",
@@ -252,7 +342,14 @@ COMMIT;
         Ok(())
     }
 
-    pub fn add_tags<'tag>(&self, tags: impl Iterator<Item = &'tag Tag>) -> RepoResult<()> {
+    /// Add `tags` and their relations.
+    ///
+    /// Parents referenced by alias are resolved to their canonical tags with `aliases`.
+    pub fn add_tags<'tag>(
+        &self,
+        tags: impl Iterator<Item = &'tag Tag>,
+        aliases: &TagAliases,
+    ) -> RepoResult<()> {
         let mut tag_id = HashMap::new();
         let mut relation_deferred = HashMap::new();
 
@@ -280,20 +377,31 @@ COMMIT;
 
         for (child_id, parents) in relation_deferred {
             for parent in parents {
-                self.add_parent(child_id, tag_id[parent])?;
+                self.add_parent(child_id, tag_id[canonical(aliases, parent)])?;
             }
         }
         Ok(())
     }
 
-    fn add_info(&self, key: &str, value: &str) -> RepoResult<()> {
+    pub fn add_info(&self, key: &str, value: &str) -> RepoResult<()> {
         self.conn.execute(
-            "INSERT INTO repo_info (key, value) VALUES (?, ?)",
+            "INSERT OR REPLACE INTO repo_info (key, value) VALUES (?, ?)",
             [key, value],
         )?;
         Ok(())
     }
 
+    pub fn info(&self, key: &str) -> RepoResult<Option<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT value FROM repo_info WHERE key = ?")?;
+        let value = stmt
+            .query_map([key], |row| row.get(0))?
+            .next()
+            .transpose()?;
+        Ok(value)
+    }
+
     pub fn write_info(
         &self,
         repo_name: &str,
@@ -317,3 +425,11 @@ COMMIT;
         Ok(())
     }
 }
+
+/// Get the canonical tag of `tag` if it is an alias, or `tag` itself.
+fn canonical<'a>(aliases: &'a TagAliases, tag: &'a TagRef<'a>) -> &'a TagRef<'a> {
+    aliases
+        .get(tag.name())
+        .and_then(|aliases| aliases.get(tag.tag_type()))
+        .unwrap_or(tag)
+}
@@ -0,0 +1,107 @@
+use crate::OwnedRepositoryManager;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use uuid::Uuid;
+
+/// How track titles and artists are normalized before comparison
+#[derive(Debug, Clone)]
+pub struct DedupeOptions {
+    /// Compare titles and artists case-insensitively
+    pub ignore_case: bool,
+    /// Trim titles and artists, and treat consecutive whitespaces as a single space
+    pub collapse_whitespace: bool,
+}
+
+impl Default for DedupeOptions {
+    fn default() -> Self {
+        Self {
+            ignore_case: true,
+            collapse_whitespace: true,
+        }
+    }
+}
+
+impl DedupeOptions {
+    fn normalize(&self, input: &str) -> String {
+        let output = if self.collapse_whitespace {
+            input.split_whitespace().collect::<Vec<_>>().join(" ")
+        } else {
+            input.to_string()
+        };
+        if self.ignore_case {
+            output.to_lowercase()
+        } else {
+            output
+        }
+    }
+}
+
+/// Tracks with the same normalized title and artist, found in more than one album
+#[derive(Serialize, Debug, PartialEq)]
+pub struct DuplicateTracks {
+    /// Normalized title
+    pub title: String,
+    /// Normalized artist
+    pub artist: String,
+    /// Sorted by album id, disc id and track id
+    pub tracks: Vec<DuplicateTrack>,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DuplicateTrack {
+    pub album_id: Uuid,
+    /// Starts from 1
+    pub disc_id: u32,
+    /// Starts from 1
+    pub track_id: u32,
+}
+
+impl DuplicateTracks {
+    /// Ids of albums containing the duplicated track, without repetition
+    pub fn album_ids(&self) -> Vec<Uuid> {
+        let mut ids: Vec<_> = self.tracks.iter().map(|track| track.album_id).collect();
+        ids.dedup();
+        ids
+    }
+}
+
+// Metadata repository does not record track durations, so only title and artist are compared.
+pub(crate) fn find_duplicate_tracks(
+    manager: &OwnedRepositoryManager,
+    options: &DedupeOptions,
+) -> Vec<DuplicateTracks> {
+    let mut groups: BTreeMap<(String, String), Vec<DuplicateTrack>> = BTreeMap::new();
+    for album in manager.albums_iter() {
+        for (disc_id, disc) in album.iter().enumerate() {
+            for (track_id, track) in disc.iter().enumerate() {
+                let key = (
+                    options.normalize(track.title()),
+                    options.normalize(track.artist()),
+                );
+                groups.entry(key).or_default().push(DuplicateTrack {
+                    album_id: album.album_id(),
+                    disc_id: disc_id as u32 + 1,
+                    track_id: track_id as u32 + 1,
+                });
+            }
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter_map(|((title, artist), mut tracks)| {
+            // tracks repeated in the same album are not duplicated imports
+            let albums: HashSet<_> = tracks.iter().map(|track| track.album_id).collect();
+            if albums.len() < 2 {
+                return None;
+            }
+
+            tracks.sort();
+            Some(DuplicateTracks {
+                title,
+                artist,
+                tracks,
+            })
+        })
+        .collect()
+}
@@ -29,9 +29,19 @@ pub enum Error {
     #[error("duplicated tag: {0}")]
     RepoTagDuplicated(TagRef<'static>),
 
+    #[error("tag alias {alias} is used by both {first} and {second}")]
+    RepoTagAliasConflict {
+        alias: TagRef<'static>,
+        first: TagRef<'static>,
+        second: TagRef<'static>,
+    },
+
     #[error("repo is locked by another instance")]
     RepoInUse,
 
+    #[error("repo is in memory, and has no files on disk")]
+    RepoInMemory,
+
     #[error("invalid track type: {0}")]
     InvalidTrackType(String),
 
@@ -56,6 +66,10 @@ pub enum Error {
     #[error(transparent)]
     GitError(#[from] git2::Error),
 
+    #[cfg(feature = "search")]
+    #[error(transparent)]
+    SearchError(#[from] tantivy::TantivyError),
+
     #[error("multiple errors detected: {0:#?}")]
     MultipleErrors(Vec<Error>),
 }
@@ -90,3 +104,18 @@ pub enum AlbumApplyError {
     #[error(transparent)]
     FlacParseError(#[from] anni_flac::error::FlacError),
 }
+
+#[cfg(any(feature = "flac", feature = "audio-tags"))]
+#[derive(thiserror::Error, Debug)]
+pub enum AudioTagsError {
+    #[cfg(feature = "flac")]
+    #[error(transparent)]
+    FlacError(#[from] anni_flac::error::FlacError),
+
+    #[cfg(feature = "audio-tags")]
+    #[error(transparent)]
+    LoftyError(#[from] lofty::error::LoftyError),
+
+    #[error("unsupported audio file: {0}")]
+    UnsupportedFormat(PathBuf),
+}
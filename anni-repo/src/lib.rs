@@ -1,10 +1,15 @@
+pub mod dedupe;
 pub mod error;
 pub mod library;
 mod manager;
+pub mod migrate;
 pub mod models;
-
-#[cfg(feature = "search")]
 pub mod search;
+pub mod stats;
+mod tagging;
+
+#[cfg(any(feature = "flac", feature = "audio-tags"))]
+pub mod tags;
 
 pub mod prelude {
     pub use crate::error::Error;
@@ -16,7 +21,7 @@ pub mod prelude {
 pub mod db;
 pub(crate) mod utils;
 
-pub use manager::{OwnedRepositoryManager, RepositoryManager};
+pub use manager::{OwnedRepositoryManager, RepositoryManager, TagAliases};
 
 #[cfg(feature = "git")]
 pub use utils::git::setup_git2;
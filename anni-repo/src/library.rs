@@ -71,6 +71,28 @@ impl FromStr for AlbumFolderInfo {
     }
 }
 
+/// Format album folder name in convention layout, e.g. `[220302][SMCL-753] 彩色硝子【Edition】 [2 Discs]`.
+///
+/// Disc count is omitted if there's only one disc.
+impl std::fmt::Display for AlbumFolderInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}][{}] {}",
+            self.release_date.to_short_string(),
+            self.catalog.replace('/', "／"),
+            self.title.replace('/', "／")
+        )?;
+        if let Some(edition) = self.edition.as_deref().filter(|e| !e.is_empty()) {
+            write!(f, "【{}】", edition.replace('/', "／"))?;
+        }
+        if self.disc_count > 1 {
+            write!(f, " [{} Discs]", self.disc_count)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct DiscFolderInfo {
     pub info: DiscInfo,
@@ -102,6 +124,23 @@ impl FromStr for DiscFolderInfo {
     }
 }
 
+/// Format disc folder name in convention layout, e.g. `[SMCL-753] 彩色硝子 [Disc 1]`.
+impl std::fmt::Display for DiscFolderInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}] {} [Disc {}]",
+            self.info.catalog.replace('/', "／"),
+            self.info
+                .title
+                .as_deref()
+                .unwrap_or_default()
+                .replace('/', "／"),
+            self.disc_id
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use anni_metadata::model::{AnniDate, DiscInfo};
@@ -185,6 +224,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_album_info_to_string() {
+        for name in [
+            "[220302][SMCL-753] 彩色硝子",
+            "[220302][SMCL-753] 彩色硝子【Edition】",
+            "[200102][CATA-001] TITLE [2 Discs]",
+        ] {
+            assert_eq!(AlbumFolderInfo::from_str(name).unwrap().to_string(), name);
+        }
+
+        let info = AlbumFolderInfo {
+            release_date: AnniDate::from_parts("2020", "01", "02").unwrap(),
+            catalog: "CATA-001".to_string(),
+            title: "A/B".to_string(),
+            edition: None,
+            disc_count: 1,
+        };
+        assert_eq!(info.to_string(), "[200102][CATA-001] A／B");
+    }
+
     #[test]
     fn test_disc_info() -> Result<(), Box<dyn std::error::Error>> {
         assert_eq!(
@@ -203,4 +262,24 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_disc_info_to_string() -> Result<(), Box<dyn std::error::Error>> {
+        let name = "[CATA-001] TITLE [Disc 1]";
+        assert_eq!(DiscFolderInfo::from_str(name)?.to_string(), name);
+
+        let info = DiscFolderInfo {
+            info: DiscInfo::new(
+                "CATA/001".to_string(),
+                Some("A/B".to_string()),
+                None,
+                None,
+                None,
+                vec![],
+            ),
+            disc_id: 2,
+        };
+        assert_eq!(info.to_string(), "[CATA／001] A／B [Disc 2]");
+        Ok(())
+    }
 }
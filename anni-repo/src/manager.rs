@@ -1,7 +1,10 @@
+use crate::dedupe::{DedupeOptions, DuplicateTracks};
 use crate::prelude::*;
+use crate::stats::RepoStats;
 use anni_common::fs;
 use anni_metadata::model::{Album, Tag, TagRef, TagType, Tags};
 use indexmap::IndexSet;
+use rayon::prelude::*;
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
@@ -9,7 +12,8 @@ use uuid::Uuid;
 
 /// A simple repository visitor. Can perform simple operations on the repository.
 pub struct RepositoryManager {
-    root: PathBuf,
+    /// Root of repository on disk, `None` for in-memory repositories
+    root: Option<PathBuf>,
     repo: Repository,
 }
 
@@ -24,11 +28,19 @@ impl RepositoryManager {
         crate::utils::git::setup_git2_internal();
 
         Ok(Self {
-            root: root.as_ref().to_owned(),
+            root: Some(root.as_ref().to_owned()),
             repo: Repository::from_str(&fs::read_to_string(repo)?)?,
         })
     }
 
+    /// Create a repository manager which is not backed by a directory on disk.
+    ///
+    /// Methods reading or writing files under repository root fail with [Error::RepoInMemory].
+    /// Use [OwnedRepositoryManager::from_memory] to load albums and tags.
+    pub fn from_memory(repo: Repository) -> Self {
+        Self { root: None, repo }
+    }
+
     #[cfg(feature = "git")]
     pub fn clone<P>(url: &str, root: P) -> RepoResult<Self>
     where
@@ -49,6 +61,29 @@ impl RepositoryManager {
         Self::new(root.as_ref())
     }
 
+    /// Stage metadata files at `paths` and commit them with `message`.
+    ///
+    /// `paths` are relative to repository root, or start with it, like paths returned by [Self::album_paths].
+    /// Other changes in the worktree, staged or not, are not committed.
+    ///
+    /// Returns `false` without doing anything if repository root is not in a git worktree,
+    /// or if there is nothing to commit.
+    #[cfg(feature = "git")]
+    pub fn commit_changes<P>(&self, paths: &[P], message: &str) -> RepoResult<bool>
+    where
+        P: AsRef<Path>,
+    {
+        crate::utils::git::commit(self.disk_root()?, paths, message)
+    }
+
+    /// Push `branch` to `origin`.
+    ///
+    /// Returns `false` without doing anything if repository root is not in a git worktree.
+    #[cfg(feature = "git")]
+    pub fn push(&self, branch: &str) -> RepoResult<bool> {
+        Ok(crate::utils::git::push(self.disk_root()?, branch)?)
+    }
+
     pub fn name(&self) -> &str {
         self.repo.name()
     }
@@ -57,61 +92,84 @@ impl RepositoryManager {
         self.repo.edition()
     }
 
+    pub fn catalog_pattern(&self) -> &str {
+        self.repo.catalog_pattern()
+    }
+
     // Get all album roots.
-    fn album_roots(&self) -> Vec<PathBuf> {
-        self.repo
+    fn album_roots(&self) -> RepoResult<Vec<PathBuf>> {
+        let root = self.disk_root()?;
+        Ok(self
+            .repo
             .albums()
             .iter()
-            .map(|album| self.root.join(album))
-            .collect()
+            .map(|album| root.join(album))
+            .collect())
     }
 
-    fn default_album_root(&self) -> PathBuf {
-        self.root.join(
+    fn default_album_root(&self) -> RepoResult<PathBuf> {
+        Ok(self.disk_root()?.join(
             self.repo
                 .albums()
                 .get(0)
                 .map_or_else(|| "album", String::as_str),
-        )
+        ))
     }
 
     /// Get all album paths.
-    /// TODO: use iterator
     pub fn all_album_paths(&self) -> RepoResult<Vec<PathBuf>> {
-        let mut paths = Vec::new();
-        for root in self.album_roots() {
-            let files = fs::read_dir(root)?;
-            for file in files {
-                let file = file?;
-                let path = file.path();
-                if path.is_file() {
-                    if let Some(ext) = path.extension() {
-                        if ext == "toml" {
-                            paths.push(path);
-                        }
-                    }
-                } else if path.is_dir() {
-                    let mut index = 0;
-                    let catalog = file.file_name();
-                    loop {
-                        let path = path.join(&catalog).with_extension(format!("{index}.toml"));
-                        if path.exists() {
-                            paths.push(path);
-                            index += 1;
-                        } else {
-                            break;
-                        }
+        self.all_album_paths_iter().collect()
+    }
+
+    /// Iterate over all album paths lazily.
+    ///
+    /// Both `{catalog}.toml` and `{catalog}/{catalog}.{index}.toml` are yielded.
+    pub fn all_album_paths_iter(&self) -> impl Iterator<Item = RepoResult<PathBuf>> {
+        let (roots, error) = match self.album_roots() {
+            Ok(roots) => (roots, None),
+            Err(e) => (Vec::new(), Some(Err(e))),
+        };
+        error.into_iter().chain(
+            roots
+                .into_iter()
+                .map(fs::read_dir)
+                .flat_map(|files| {
+                    let (files, error) = match files {
+                        Ok(files) => (Some(files), None),
+                        Err(e) => (None, Some(Err(e))),
+                    };
+                    files.into_iter().flatten().chain(error)
+                })
+                .flat_map(|file| -> Box<dyn Iterator<Item = RepoResult<PathBuf>>> {
+                    let file = match file {
+                        Ok(file) => file,
+                        Err(e) => return Box::new(std::iter::once(Err(e.into()))),
+                    };
+                    let path = file.path();
+                    if path.is_file() {
+                        let is_toml = path.extension().is_some_and(|ext| ext == "toml");
+                        Box::new(is_toml.then_some(Ok(path)).into_iter())
+                    } else if path.is_dir() {
+                        let catalog = file.file_name();
+                        Box::new(
+                            (0..)
+                                .map(move |index| {
+                                    path.join(&catalog).with_extension(format!("{index}.toml"))
+                                })
+                                .take_while(|path| path.exists())
+                                .map(Ok),
+                        )
+                    } else {
+                        Box::new(std::iter::empty())
                     }
-                }
-            }
-        }
-        Ok(paths)
+                }),
+        )
     }
 
     /// Get album paths with given catalog.
     pub fn album_paths(&self, catalog: &str) -> RepoResult<Vec<PathBuf>> {
         let mut paths = Vec::new();
-        for root in self.album_roots() {
+        for root in self.album_roots()? {
             let file = root.join(format!("{catalog}.toml"));
             if file.exists() {
                 // toml exists
@@ -159,10 +217,31 @@ impl RepositoryManager {
             .collect())
     }
 
+    /// Find path of album with given `album_id`.
+    ///
+    /// Album id is not a part of file name, so albums with `catalog` are checked first before scanning the whole repository.
+    pub fn find_album_path(&self, album_id: &Uuid, catalog: &str) -> RepoResult<Option<PathBuf>> {
+        let is_album = |path: &PathBuf| {
+            self.load_album(path)
+                .is_ok_and(|album| album.album_id() == *album_id)
+        };
+
+        if let Some(path) = self.album_paths(catalog)?.into_iter().find(is_album) {
+            return Ok(Some(path));
+        }
+        for path in self.all_album_paths_iter() {
+            let path = path?;
+            if is_album(&path) {
+                return Ok(Some(path));
+            }
+        }
+        Ok(None)
+    }
+
     /// Add new album to the repository.
     pub fn add_album(&self, mut album: Album, allow_duplicate: bool) -> RepoResult<()> {
         let catalog = album.catalog();
-        let folder = self.default_album_root().join(catalog);
+        let folder = self.default_album_root()?.join(catalog);
         let file = folder.with_extension("toml");
 
         if folder.exists() {
@@ -199,11 +278,20 @@ impl RepositoryManager {
         OwnedRepositoryManager::new(self)
     }
 
+    /// Root of repository, which is empty if the repository is in memory.
     pub fn root(&self) -> &Path {
-        self.root.as_path()
+        self.root.as_deref().unwrap_or(Path::new(""))
+    }
+
+    /// Root of repository on disk, or [Error::RepoInMemory] if the repository is in memory.
+    pub(crate) fn disk_root(&self) -> RepoResult<&Path> {
+        self.root.as_deref().ok_or(Error::RepoInMemory)
     }
 }
 
+/// Canonical tags of aliases, indexed by alias name and tag type.
+pub type TagAliases = HashMap<String, HashMap<TagType, TagRef<'static>>>;
+
 /// A repository manager which own full copy of a repo.
 ///
 /// This is helpful when you need to perform a full-repo operation,
@@ -213,6 +301,8 @@ pub struct OwnedRepositoryManager {
 
     /// All available tags.
     tags: HashMap<String, HashMap<TagType, Tag>>,
+    /// Alias name -> Tag type -> Canonical tag
+    tag_aliases: TagAliases,
     /// Parent to child tag relation
     tags_relation: HashMap<TagRef<'static>, IndexSet<TagRef<'static>>>,
     /// Tag -> File
@@ -223,35 +313,65 @@ pub struct OwnedRepositoryManager {
     albums: HashMap<Uuid, Album>,
     /// AlbumID -> Album Path
     album_path: HashMap<Uuid, PathBuf>,
+
+    /// Lock file created by this manager, `None` for in-memory repositories
+    lock_file: Option<PathBuf>,
 }
 
 impl OwnedRepositoryManager {
     pub fn new(repo: RepositoryManager) -> RepoResult<Self> {
-        let mut repo = Self {
-            repo,
-            tags: Default::default(),
-            tags_relation: Default::default(),
-            tag_path: Default::default(),
-            album_tags: Default::default(),
-            albums: Default::default(),
-            album_path: Default::default(),
-        };
+        let mut repo = Self::empty(repo);
 
         // create lock file so that other anni repository managers can not visit the repo
-        let lock_file = repo.lock_file();
+        let lock_file = repo.repo.disk_root()?.join(".repo_lock");
         if lock_file.exists() {
             return Err(Error::RepoInUse);
         }
 
-        fs::write(lock_file, "")?;
+        fs::write(&lock_file, "")?;
+        repo.lock_file = Some(lock_file);
         repo.load_tags()?;
         repo.load_albums()?;
 
         Ok(repo)
     }
 
-    fn lock_file(&self) -> PathBuf {
-        self.repo.root().join(".repo_lock")
+    /// Create a repository manager from albums and tags in memory, without reading or writing any file.
+    ///
+    /// Paths of album files and tag files are relative to repository root.
+    /// Albums and tags are validated the same way as those loaded from disk.
+    pub fn from_memory(
+        repo: Repository,
+        albums: Vec<(PathBuf, Album)>,
+        tags: Vec<(PathBuf, Vec<Tag>)>,
+    ) -> RepoResult<Self> {
+        let mut repo = Self::empty(RepositoryManager::from_memory(repo));
+        repo.index_tags(tags)?;
+
+        let albums = albums
+            .into_iter()
+            .map(|(path, mut album)| {
+                album.resolve_tags(&repo.tags)?;
+                Ok((path, album))
+            })
+            .collect::<RepoResult<Vec<_>>>()?;
+        repo.index_albums(albums)?;
+
+        Ok(repo)
+    }
+
+    fn empty(repo: RepositoryManager) -> Self {
+        Self {
+            repo,
+            tags: Default::default(),
+            tag_aliases: Default::default(),
+            tags_relation: Default::default(),
+            tag_path: Default::default(),
+            album_tags: Default::default(),
+            albums: Default::default(),
+            album_path: Default::default(),
+            lock_file: None,
+        }
     }
 
     pub fn album(&self, album_id: &Uuid) -> Option<&Album> {
@@ -270,12 +390,21 @@ impl OwnedRepositoryManager {
         self.albums.values()
     }
 
+    /// Get tag by its name or alias.
     pub fn tag(&self, tag: &TagRef<'_>) -> Option<&Tag> {
+        let tag = self.resolve_alias(tag).unwrap_or(tag);
         self.tags
             .get(tag.name())
             .and_then(|tags| tags.get(tag.tag_type()))
     }
 
+    /// Get the canonical tag if `tag` is an alias.
+    pub fn resolve_alias(&self, tag: &TagRef<'_>) -> Option<&TagRef<'static>> {
+        self.tag_aliases
+            .get(tag.name())
+            .and_then(|aliases| aliases.get(tag.tag_type()))
+    }
+
     pub fn tags_iter(&self) -> impl Iterator<Item = &Tag> {
         self.tags.values().flat_map(|m| m.values())
     }
@@ -297,6 +426,7 @@ impl OwnedRepositoryManager {
     where
         'tag: 'me,
     {
+        let tag = self.resolve_alias(tag).unwrap_or(tag);
         self.album_tags.get(tag)
     }
 
@@ -329,18 +459,47 @@ impl OwnedRepositoryManager {
         }
     }
 
+    /// Register aliases of loaded tags.
+    ///
+    /// An alias must not be the name of another tag, or an alias of another tag with the same type.
+    fn load_tag_aliases(&mut self) -> RepoResult<()> {
+        let mut aliases = TagAliases::new();
+        for tag in self.tags_iter() {
+            for alias in tag.aliases() {
+                if alias == tag.name() {
+                    continue;
+                }
+
+                let alias_ref = TagRef::new(alias.clone(), tag.tag_type().clone());
+                let previous = match self.tag(&alias_ref) {
+                    Some(other) => Some(other.get_owned_ref()),
+                    None => aliases
+                        .entry(alias.clone())
+                        .or_default()
+                        .insert(tag.tag_type().clone(), tag.get_owned_ref()),
+                };
+                if let Some(first) = previous {
+                    return Err(Error::RepoTagAliasConflict {
+                        alias: alias_ref,
+                        first,
+                        second: tag.get_owned_ref(),
+                    });
+                }
+            }
+        }
+
+        self.tag_aliases = aliases;
+        Ok(())
+    }
+
     /// Load tags into self.tags.
     fn load_tags(&mut self) -> RepoResult<()> {
         // filter out toml files
-        let tags_path =
-            fs::PathWalker::new(self.repo.root.join("tag"), true, false, Default::default())
-                .filter(|p| p.extension().map(|e| e == "toml").unwrap_or(false));
+        let root = self.repo.disk_root()?.to_path_buf();
+        let tags_path = fs::PathWalker::new(root.join("tag"), true, false, Default::default())
+            .filter(|p| p.extension().map(|e| e == "toml").unwrap_or(false));
 
-        // clear tags
-        self.tags.clear();
-        self.tags_relation.clear();
-
-        // iterate over tag files
+        let mut tag_files = Vec::new();
         for tag_file in tags_path {
             let text = fs::read_to_string(&tag_file)?;
             let tags = toml::from_str::<Tags>(&text)
@@ -350,11 +509,26 @@ impl OwnedRepositoryManager {
                     err: e,
                 })?
                 .into_inner();
-            let relative_path = pathdiff::diff_paths(&tag_file, &self.repo.root).unwrap();
+            let relative_path = pathdiff::diff_paths(&tag_file, &root).unwrap();
+            tag_files.push((relative_path, tags));
+        }
+
+        self.index_tags(tag_files)
+    }
 
+    /// Index tags of each tag file, and check that all related tags are defined.
+    fn index_tags(&mut self, tag_files: Vec<(PathBuf, Vec<Tag>)>) -> RepoResult<()> {
+        // clear tags
+        self.tags.clear();
+        self.tag_aliases.clear();
+        self.tags_relation.clear();
+
+        // iterate over tag files
+        let mut relations = Vec::new();
+        for (relative_path, tags) in tag_files {
             for tag in tags {
                 for parent in tag.parents() {
-                    self.add_tag_relation(parent.clone(), tag.get_owned_ref());
+                    relations.push((parent.clone(), tag.get_owned_ref()));
                 }
 
                 // add children to set
@@ -362,13 +536,20 @@ impl OwnedRepositoryManager {
                     let parent = tag.get_owned_ref();
                     let full = child.clone().into_full(vec![parent.into()]);
                     self.add_tag(full, relative_path.clone())?;
-                    self.add_tag_relation(tag.get_owned_ref(), child.clone());
+                    relations.push((tag.get_owned_ref(), child.clone()));
                 }
 
                 self.add_tag(tag, relative_path.clone())?;
             }
         }
 
+        // relations are added after aliases are loaded, as parents may be referenced by alias
+        self.load_tag_aliases()?;
+        for (parent, child) in relations {
+            let parent = self.resolve_alias(&parent).cloned().unwrap_or(parent);
+            self.add_tag_relation(parent, child);
+        }
+
         // check tag relationship
         let all_tags: HashSet<_> = self.tags_iter().map(Tag::as_ref).collect();
         let mut rel_tags: HashSet<_> = self.tags_relation.keys().collect();
@@ -384,13 +565,29 @@ impl OwnedRepositoryManager {
     }
 
     fn load_albums(&mut self) -> RepoResult<()> {
+        // parse album files in parallel, then index them in order
+        let root = self.repo.disk_root()?;
+        let albums = self
+            .repo
+            .all_album_paths()?
+            .into_par_iter()
+            .map(|path| {
+                let mut album = self.repo.load_album(&path)?;
+                album.resolve_tags(&self.tags)?;
+                let relative_path = pathdiff::diff_paths(&path, root).unwrap();
+                Ok((relative_path, album))
+            })
+            .collect::<RepoResult<Vec<_>>>()?;
+
+        self.index_albums(albums)
+    }
+
+    /// Index albums by id and tags. Paths of albums are relative to repository root.
+    fn index_albums(&mut self, albums: Vec<(PathBuf, Album)>) -> RepoResult<()> {
         self.album_tags.clear();
 
         let mut problems = vec![];
-        for path in self.repo.all_album_paths()? {
-            let mut album = self.repo.load_album(&path)?;
-            album.resolve_tags(&self.tags)?;
-
+        for (path, album) in albums {
             let album_id = album.album_id();
             let catalog = album.catalog();
             let tags = album.tags();
@@ -410,10 +607,12 @@ impl OwnedRepositoryManager {
                         problems.push(Error::RepoTagsUndefined(vec![tag_ref.clone()]));
                     }
 
-                    if !self.album_tags.contains_key(tag_ref) {
+                    // albums tagged by alias are indexed by the canonical tag
+                    let tag_ref = self.resolve_alias(tag_ref).unwrap_or(tag_ref).clone();
+                    if !self.album_tags.contains_key(&tag_ref) {
                         self.album_tags.insert(tag_ref.clone(), vec![]);
                     }
-                    self.album_tags.get_mut(tag_ref).unwrap().push(album_id);
+                    self.album_tags.get_mut(&tag_ref).unwrap().push(album_id);
                 }
             }
             if let Some(album_with_same_id) = self.albums.insert(album_id, album) {
@@ -423,10 +622,7 @@ impl OwnedRepositoryManager {
                 );
                 problems.push(Error::RepoDuplicatedAlbumId(album_id.to_string()));
             }
-            self.album_path.insert(
-                album_id,
-                pathdiff::diff_paths(&path, &self.repo.root).unwrap(),
-            );
+            self.album_path.insert(album_id, path);
         }
 
         if problems.is_empty() {
@@ -436,96 +632,200 @@ impl OwnedRepositoryManager {
         }
     }
 
+    /// Find a loop in tag relations.
+    ///
+    /// Returns tags in the loop, starting and ending with the same tag.
     pub fn check_tags_loop<'me, 'tag>(&'me self) -> Option<Vec<&'me TagRef<'tag>>>
     where
         'me: 'tag,
     {
-        fn dfs<'tag, 'func>(
-            tag: &'tag TagRef<'tag>,
-            tags_relation: &'tag HashMap<TagRef<'static>, IndexSet<TagRef<'static>>>,
-            current: &'func mut HashMap<&'tag TagRef<'tag>, bool>,
-            visited: &'func mut HashMap<&'tag TagRef<'tag>, bool>,
-            mut path: Vec<&'tag TagRef<'tag>>,
-        ) -> (bool, Vec<&'tag TagRef<'tag>>) {
-            visited.insert(tag, true);
-            current.insert(tag, true);
-            path.push(tag);
-
-            if let Some(children) = tags_relation.get(tag) {
-                for child in children {
-                    if let Some(true) = current.get(child) {
+        self.find_tag_loops(true).pop()
+    }
+
+    /// Find all loops in tag relations, each starting and ending with the same tag.
+    ///
+    /// A loop is reported once for each relation which closes it during a depth-first traversal,
+    /// so disjoint loops are all reported. Loops are rotated to start from their smallest tag and sorted.
+    pub fn all_tag_loops<'me, 'tag>(&'me self) -> Vec<Vec<&'me TagRef<'tag>>>
+    where
+        'me: 'tag,
+    {
+        self.find_tag_loops(false)
+    }
+
+    // Iterative depth-first traversal, so that deep tag graphs do not overflow the stack
+    fn find_tag_loops(&self, first_only: bool) -> Vec<Vec<&TagRef<'static>>> {
+        enum State {
+            /// Tag is on the current traversal path
+            Visiting,
+            /// All children of the tag have been visited
+            Visited,
+        }
+
+        let children = |tag: &TagRef<'static>| self.tags_relation.get(tag).into_iter().flatten();
+
+        let mut roots: Vec<&TagRef<'static>> = self.tags_iter().map(|t| t.as_ref()).collect();
+        roots.sort_by_cached_key(|tag| tag.to_string());
+
+        let mut states: HashMap<&TagRef<'static>, State> = HashMap::new();
+        let mut loops = Vec::new();
+        'roots: for root in roots {
+            if states.contains_key(root) {
+                continue;
+            }
+
+            states.insert(root, State::Visiting);
+            let mut stack = vec![(root, children(root))];
+            while let Some((tag, iter)) = stack.last_mut() {
+                let tag = *tag;
+                let Some(child) = iter.next() else {
+                    states.insert(tag, State::Visited);
+                    stack.pop();
+                    continue;
+                };
+
+                match states.get(child) {
+                    Some(State::Visiting) => {
+                        // the loop starts from where child is on the path
+                        let start = stack.iter().position(|(t, _)| *t == child).unwrap();
+                        let mut path: Vec<_> = stack[start..].iter().map(|(t, _)| *t).collect();
                         path.push(child);
-                        return (true, path);
-                    }
-                    // if !visited[child]
-                    if !visited.get(child).map_or(false, |x| *x) {
-                        let (loop_detected, loop_path) =
-                            dfs(child, tags_relation, current, visited, path);
-                        if loop_detected {
-                            return (true, loop_path);
-                        } else {
-                            path = loop_path;
+                        loops.push(path);
+                        if first_only {
+                            break 'roots;
                         }
                     }
+                    Some(State::Visited) => {}
+                    None => {
+                        states.insert(child, State::Visiting);
+                        stack.push((child, children(child)));
+                    }
                 }
             }
+        }
 
-            current.insert(tag, false);
+        for path in loops.iter_mut() {
             path.pop();
-            (false, path)
+            let (start, _) = path
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, tag)| tag.to_string())
+                .unwrap();
+            path.rotate_left(start);
+            path.push(path[0]);
         }
+        loops.sort_by_cached_key(|path| path.iter().map(|t| t.to_string()).collect::<Vec<_>>());
+        loops
+    }
 
-        let mut visited: HashMap<&TagRef, bool> = Default::default();
-        let mut current: HashMap<&TagRef, bool> = Default::default();
-        let tags: Vec<_> = self.tags_iter().map(|t| t.as_ref()).collect();
-        for tag in tags.into_iter() {
-            // if !visited[tag]
-            if !visited.get(&tag).map_or(false, |x| *x) {
-                let (loop_detected, path) = dfs(
-                    tag,
-                    &self.tags_relation,
-                    &mut current,
-                    &mut visited,
-                    Default::default(),
-                );
-                if loop_detected {
-                    return Some(path);
-                }
-            }
-        }
+    #[cfg(feature = "db-write")]
+    pub fn to_database<P>(&self, database_path: P) -> RepoResult<()>
+    where
+        P: AsRef<Path>,
+    {
+        // prevent concurrent builds from writing to the same database
+        let _lock = BuildLock::new(database_path.as_ref())?;
 
-        None
+        self.write_database(database_path.as_ref(), &self.tags_hash()?)
     }
 
+    /// Update an existing database in place, rewriting only albums whose files have changed since last build.
+    ///
+    /// Albums are compared by hashes of their files. The database is rebuilt with [Self::to_database]
+    /// if it does not exist, was created by another version, or tags have changed.
+    ///
+    /// Returns ids of albums added, updated or removed.
     #[cfg(feature = "db-write")]
-    pub fn to_database<P>(&self, database_path: P) -> RepoResult<()>
+    pub fn to_database_incremental<P>(&self, database_path: P) -> RepoResult<Vec<Uuid>>
     where
         P: AsRef<Path>,
     {
-        use std::time::{SystemTime, UNIX_EPOCH};
+        let database_path = database_path.as_ref();
+        let _lock = BuildLock::new(database_path)?;
+
+        let tags_hash = self.tags_hash()?;
+        if database_path.exists() {
+            let db = crate::db::RepoDatabaseWrite::open(database_path)?;
+            if db.info("db_version")?.as_deref() == Some(crate::db::DB_VERSION)
+                && db.info("tags_hash")?.as_deref() == Some(tags_hash.as_str())
+            {
+                let changed = self.update_database(&db)?;
+                Self::write_last_modified(database_path)?;
+                return Ok(changed);
+            }
+            log::info!("Database is outdated, rebuilding");
+        }
 
+        self.write_database(database_path, &tags_hash)?;
+        let mut albums: Vec<_> = self.albums.keys().copied().collect();
+        albums.sort();
+        Ok(albums)
+    }
+
+    #[cfg(feature = "db-write")]
+    fn write_database(&self, database_path: &Path, tags_hash: &str) -> RepoResult<()> {
         // remove database first
-        let _ = std::fs::remove_file(database_path.as_ref());
+        let _ = std::fs::remove_file(database_path);
 
-        let db = crate::db::RepoDatabaseWrite::create(database_path.as_ref())?;
+        let db = crate::db::RepoDatabaseWrite::create(database_path)?;
         // TODO: get url / ref from repo
         db.write_info(self.repo.name(), self.repo.edition(), "", "")?;
+        db.add_info("tags_hash", tags_hash)?;
 
         // Write all tags
         let tags = self.tags_iter();
-        db.add_tags(tags)?;
+        db.add_tags(tags, &self.tag_aliases)?;
 
         // Write all albums
         for album in self.albums_iter() {
-            db.add_album(album)?;
+            db.add_album(album, &self.tag_aliases)?;
+            db.set_album_hash(album.album_id(), &self.album_hash(&album.album_id())?)?;
         }
 
         // Create Index
         db.create_index()?;
 
+        Self::write_last_modified(database_path)
+    }
+
+    /// Rewrite changed albums in `db`, and returns their ids.
+    #[cfg(feature = "db-write")]
+    fn update_database(&self, db: &crate::db::RepoDatabaseWrite) -> RepoResult<Vec<Uuid>> {
+        db.begin()?;
+
+        let mut hashes = db.album_hashes()?;
+        let mut changed = Vec::new();
+        for album in self.albums_iter() {
+            let album_id = album.album_id();
+            let hash = self.album_hash(&album_id)?;
+            if hashes.remove(&album_id).as_ref() != Some(&hash) {
+                db.remove_album(album_id)?;
+                db.add_album(album, &self.tag_aliases)?;
+                db.set_album_hash(album_id, &hash)?;
+                changed.push(album_id);
+            }
+        }
+
+        // album files which no longer exist
+        for album_id in hashes.into_keys() {
+            db.remove_album(album_id)?;
+            changed.push(album_id);
+        }
+
+        db.write_info(self.repo.name(), self.repo.edition(), "", "")?;
+        db.commit()?;
+
+        changed.sort();
+        Ok(changed)
+    }
+
+    #[cfg(feature = "db-write")]
+    fn write_last_modified(database_path: &Path) -> RepoResult<()> {
+        use std::time::{SystemTime, UNIX_EPOCH};
+
         // Creation time
         fs::write(
-            database_path.as_ref().with_file_name("repo.json"),
+            database_path.with_file_name("repo.json"),
             format!(
                 "{{\"last_modified\": {}}}",
                 SystemTime::now()
@@ -537,40 +837,150 @@ impl OwnedRepositoryManager {
         Ok(())
     }
 
+    /// Hash of the file where album is defined
+    #[cfg(feature = "db-write")]
+    fn album_hash(&self, album_id: &Uuid) -> RepoResult<Vec<u8>> {
+        use sha2::{Digest, Sha256};
+
+        let path = self.repo.disk_root()?.join(&self.album_path[album_id]);
+        Ok(Sha256::digest(fs::read(path)?).to_vec())
+    }
+
+    /// Hash of all tag files, in hex
+    #[cfg(feature = "db-write")]
+    fn tags_hash(&self) -> RepoResult<String> {
+        use sha2::{Digest, Sha256};
+
+        let mut files: Vec<_> = self.tag_path.values().collect();
+        files.sort();
+        files.dedup();
+
+        let mut hasher = Sha256::new();
+        for file in files {
+            hasher.update(file.to_string_lossy().as_bytes());
+            hasher.update(fs::read(self.repo.disk_root()?.join(file))?);
+        }
+        Ok(hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect())
+    }
+
+    /// Collect statistics of albums and tags in the repository.
+    pub fn stats(&self) -> RepoStats {
+        RepoStats::new(self)
+    }
+
+    /// Find tracks which appear in more than one album, with title and artist normalized by `options`.
+    pub fn find_duplicate_tracks(&self, options: &DedupeOptions) -> Vec<DuplicateTracks> {
+        crate::dedupe::find_duplicate_tracks(self, options)
+    }
+
+    /// Export albums to `albums.json` and tags to `tags.json` under `out_dir`.
+    #[cfg(feature = "json")]
+    pub fn to_json<P>(&self, out_dir: P) -> RepoResult<()>
+    where
+        P: AsRef<Path>,
+    {
+        use crate::models::{JsonAlbum, JsonTagRelation, JsonTags};
+        use std::io::BufWriter;
+
+        let out_dir = out_dir.as_ref();
+        fs::create_dir_all(out_dir)?;
+
+        // prevent concurrent builds from writing to the same directory
+        let _lock = BuildLock::new(out_dir)?;
+
+        fn write_json<T: serde::Serialize>(path: PathBuf, value: &T) -> RepoResult<()> {
+            let writer = BufWriter::new(fs::File::create(path)?);
+            serde_json::to_writer(writer, value).map_err(std::io::Error::from)?;
+            Ok(())
+        }
+
+        // sort albums and tags to keep output stable
+        let mut albums: Vec<_> = self.albums_iter().collect();
+        albums.sort_by_key(|album| album.album_id);
+        let albums: Vec<_> = albums
+            .into_iter()
+            .map(|album| JsonAlbum::from(album.clone()))
+            .collect();
+        write_json(out_dir.join("albums.json"), &albums)?;
+
+        let mut tags: Vec<_> = self.tags_iter().collect();
+        tags.sort_by(|a, b| {
+            (a.name(), a.tag_type().as_ref()).cmp(&(b.name(), b.tag_type().as_ref()))
+        });
+        let relations = tags
+            .iter()
+            .filter_map(|&tag| {
+                let parent: &TagRef = tag.as_ref();
+                let children = self.tags_relation.get(parent)?;
+                Some(JsonTagRelation {
+                    parent: parent.clone(),
+                    children: children.iter().cloned().collect(),
+                })
+            })
+            .collect();
+        let tags = JsonTags {
+            tags: tags.into_iter().cloned().collect(),
+            relations,
+        };
+        write_json(out_dir.join("tags.json"), &tags)?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "search")]
+    pub fn build_search_index<P>(&self, path: P) -> RepoResult<()>
+    where
+        P: AsRef<Path>,
+    {
+        self.build_search_index_with_tokenizer(path, &Default::default())
+    }
+
     #[cfg(feature = "search")]
-    pub fn build_search_index<P>(&self, path: P)
+    pub fn build_search_index_with_tokenizer<P>(
+        &self,
+        path: P,
+        tokenizer: &crate::search::TokenizerConfig,
+    ) -> RepoResult<()>
     where
         P: AsRef<Path>,
     {
         use crate::search::RepositorySearchManager;
 
-        let searcher = RepositorySearchManager::create(path).unwrap();
-        let mut index_writer = searcher.index.writer(100_000_000).unwrap();
+        // prevent concurrent builds from writing to the same index
+        let _lock = BuildLock::new(path.as_ref())?;
+
+        let searcher = RepositorySearchManager::create_with_tokenizer(path, tokenizer)?;
+        let mut index_writer = searcher.index.writer(100_000_000)?;
 
         for album in self.albums_iter() {
             for (disc_id_v, disc) in album.iter().enumerate() {
                 let disc_id_v = disc_id_v + 1;
                 for (track_id_v, track) in disc.iter().enumerate() {
                     let track_id_v = track_id_v + 1;
-                    index_writer
-                        .add_document(searcher.build_document(
-                            track.title(),
-                            track.artist(),
-                            &album.album_id,
-                            disc_id_v as i64,
-                            track_id_v as i64,
-                        ))
-                        .unwrap();
+                    index_writer.add_document(searcher.build_document(
+                        track.title(),
+                        track.artist(),
+                        &album.album_id,
+                        disc_id_v as i64,
+                        track_id_v as i64,
+                    ))?;
                 }
             }
         }
-        index_writer.commit().unwrap();
+        index_writer.commit()?;
+        Ok(())
     }
 }
 
 impl Drop for OwnedRepositoryManager {
     fn drop(&mut self) {
-        let lock_file = self.lock_file();
+        let Some(lock_file) = &self.lock_file else {
+            return;
+        };
         // it should exist. If it does not exist, then something wrong happened
         // TODO: add detection for this case
         if lock_file.exists() {
@@ -578,3 +988,40 @@ impl Drop for OwnedRepositoryManager {
         }
     }
 }
+
+/// Lock file guarding database or search index builds at a path.
+///
+/// The lock file `<path>.lock` is locked by OS while the lock is held, so lock files left by crashed
+/// builds are not treated as locked. Lock files are kept after builds, as removing them would let
+/// another build lock a file which is no longer at that path.
+#[cfg(any(feature = "db-write", feature = "json", feature = "search"))]
+struct BuildLock(fs::File);
+
+#[cfg(any(feature = "db-write", feature = "json", feature = "search"))]
+impl BuildLock {
+    fn new(path: &Path) -> RepoResult<Self> {
+        use fs4::FileExt;
+
+        let mut lock_file = path.as_os_str().to_owned();
+        lock_file.push(".lock");
+
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(PathBuf::from(lock_file))?;
+        if file.try_lock_exclusive().is_err() {
+            return Err(Error::RepoInUse);
+        }
+        Ok(Self(file))
+    }
+}
+
+#[cfg(any(feature = "db-write", feature = "json", feature = "search"))]
+impl Drop for BuildLock {
+    fn drop(&mut self) {
+        use fs4::FileExt;
+
+        let _ = self.0.unlock();
+    }
+}
@@ -0,0 +1,238 @@
+//! Data migrations of metadata repository files.
+//!
+//! Migrations edit album files in place with `toml_edit`, so formatting and comments are kept.
+//! All migrations are idempotent: running a migration again on a migrated repository changes nothing.
+
+use crate::prelude::*;
+use crate::RepositoryManager;
+use anni_common::fs;
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+use toml_edit::{Document, Item, Table, Value};
+
+/// Field required by the search indexer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchField {
+    AlbumCatalog,
+    AlbumEdition,
+    /// Catalog of disc, 1-based
+    DiscCatalog(usize),
+}
+
+impl Display for SearchField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SearchField::AlbumCatalog => write!(f, "album.catalog"),
+            SearchField::AlbumEdition => write!(f, "album.edition"),
+            SearchField::DiscCatalog(index) => write!(f, "discs[{index}].catalog"),
+        }
+    }
+}
+
+/// Reason why a field could not be normalized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnresolvedReason {
+    /// Field does not exist
+    Missing,
+    /// Field is not a string
+    InvalidType,
+    /// Field is empty after normalization
+    Empty,
+    /// Field is a placeholder, like `@TEMP`
+    Placeholder(String),
+    /// Album file is not a valid toml file
+    InvalidToml(String),
+}
+
+impl Display for UnresolvedReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnresolvedReason::Missing => write!(f, "missing"),
+            UnresolvedReason::InvalidType => write!(f, "not a string"),
+            UnresolvedReason::Empty => write!(f, "empty"),
+            UnresolvedReason::Placeholder(placeholder) => write!(f, "placeholder {placeholder}"),
+            UnresolvedReason::InvalidToml(err) => write!(f, "invalid toml: {err}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnresolvedField {
+    pub path: PathBuf,
+    /// `None` if the whole album file could not be read
+    pub field: Option<SearchField>,
+    pub reason: UnresolvedReason,
+}
+
+#[derive(Debug, Default)]
+pub struct SearchFieldsReport {
+    /// Album files which were migrated, or would be migrated in dry-run mode
+    pub migrated: Vec<PathBuf>,
+    /// Fields which could not be normalized and need manual fix
+    pub unresolved: Vec<UnresolvedField>,
+}
+
+/// Normalize a catalog for indexing.
+///
+/// Full-width characters are converted to their ASCII forms, dash variants are replaced
+/// with `-` and whitespaces are trimmed and collapsed.
+pub fn normalize_catalog(catalog: &str) -> Result<String, UnresolvedReason> {
+    let mut result = String::with_capacity(catalog.len());
+    for c in catalog.chars() {
+        let c = match c {
+            // full-width ASCII variants
+            '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0).unwrap(),
+            // dash variants
+            '\u{2010}'..='\u{2015}' | '\u{2212}' | '\u{ff0d}' => '-',
+            c if c.is_whitespace() => ' ',
+            c => c,
+        };
+        if c == ' ' && (result.is_empty() || result.ends_with(' ')) {
+            continue;
+        }
+        result.push(c);
+    }
+    let result = result.trim_end().to_string();
+
+    if result.is_empty() {
+        Err(UnresolvedReason::Empty)
+    } else if result.starts_with('@') {
+        Err(UnresolvedReason::Placeholder(result))
+    } else {
+        Ok(result)
+    }
+}
+
+/// Replace string value in `item` and keep its decoration.
+fn set_str(item: &mut Item, new: &str) {
+    if let Some(value) = item.as_value_mut() {
+        let decor = value.decor().clone();
+        *value = Value::from(new);
+        *value.decor_mut() = decor;
+    }
+}
+
+/// Normalize catalog in `table`, returns whether it was changed.
+fn migrate_catalog(table: &mut Table, field: SearchField) -> Result<bool, UnresolvedReason> {
+    let item = table.get_mut("catalog").ok_or(UnresolvedReason::Missing)?;
+    let catalog = item.as_str().ok_or(UnresolvedReason::InvalidType)?;
+    let normalized = normalize_catalog(catalog)?;
+    if normalized == catalog {
+        return Ok(false);
+    }
+
+    log::debug!("Normalized {field}: {catalog} -> {normalized}");
+    set_str(item, &normalized);
+    Ok(true)
+}
+
+/// Trim edition in `table`, and remove it if empty. Returns whether it was changed.
+fn migrate_edition(table: &mut Table) -> Result<bool, UnresolvedReason> {
+    let Some(item) = table.get_mut("edition") else {
+        // edition is optional
+        return Ok(false);
+    };
+    let edition = item.as_str().ok_or(UnresolvedReason::InvalidType)?;
+    let trimmed = edition.trim();
+    if trimmed == edition {
+        Ok(false)
+    } else if trimmed.is_empty() {
+        table.remove("edition");
+        Ok(true)
+    } else {
+        let trimmed = trimmed.to_string();
+        set_str(item, &trimmed);
+        Ok(true)
+    }
+}
+
+impl RepositoryManager {
+    /// Make sure every album has the fields used by search index normalized.
+    ///
+    /// Album files are not written if `dry_run` is true.
+    pub fn migrate_search_fields(&self, dry_run: bool) -> RepoResult<SearchFieldsReport> {
+        let mut report = SearchFieldsReport::default();
+
+        for path in self.all_album_paths()? {
+            let input = fs::read_to_string(&path)?;
+            let mut document = match input.parse::<Document>() {
+                Ok(document) => document,
+                Err(err) => {
+                    report.unresolved.push(UnresolvedField {
+                        path,
+                        field: None,
+                        reason: UnresolvedReason::InvalidToml(err.to_string()),
+                    });
+                    continue;
+                }
+            };
+
+            let mut changed = false;
+            let mut record = |field, result: Result<bool, UnresolvedReason>| match result {
+                Ok(c) => changed |= c,
+                Err(reason) => report.unresolved.push(UnresolvedField {
+                    path: path.clone(),
+                    field: Some(field),
+                    reason,
+                }),
+            };
+
+            match document.get_mut("album").and_then(Item::as_table_mut) {
+                Some(album) => {
+                    record(
+                        SearchField::AlbumCatalog,
+                        migrate_catalog(album, SearchField::AlbumCatalog),
+                    );
+                    record(SearchField::AlbumEdition, migrate_edition(album));
+                }
+                None => record(SearchField::AlbumCatalog, Err(UnresolvedReason::Missing)),
+            }
+
+            if let Some(discs) = document
+                .get_mut("discs")
+                .and_then(Item::as_array_of_tables_mut)
+            {
+                for (index, disc) in discs.iter_mut().enumerate() {
+                    let field = SearchField::DiscCatalog(index + 1);
+                    record(field, migrate_catalog(disc, field));
+                }
+            }
+
+            if changed {
+                if !dry_run {
+                    std::fs::write(&path, document.to_string())?;
+                }
+                report.migrated.push(path);
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_catalog, UnresolvedReason};
+
+    #[test]
+    fn test_normalize_catalog() {
+        assert_eq!(normalize_catalog("LACA-1234").unwrap(), "LACA-1234");
+        assert_eq!(
+            normalize_catalog("ＬＡＣＡ－１２３４").unwrap(),
+            "LACA-1234"
+        );
+        assert_eq!(
+            normalize_catalog("  LACA\u{2013}1234 ").unwrap(),
+            "LACA-1234"
+        );
+        assert_eq!(
+            normalize_catalog("KSLA 0001  ~ 2").unwrap(),
+            "KSLA 0001 ~ 2"
+        );
+        assert_eq!(normalize_catalog("   "), Err(UnresolvedReason::Empty));
+        assert_eq!(
+            normalize_catalog("@TEMP"),
+            Err(UnresolvedReason::Placeholder("@TEMP".to_string()))
+        );
+    }
+}
@@ -70,11 +70,40 @@ pub trait ApplyMetadata {
     where
         P: AsRef<std::path::Path>;
 
+    /// Compute changes [ApplyMetadata::apply_strict] would make, without writing any file.
+    fn strict_changes<P>(
+        &self,
+        directory: P,
+        detailed: bool,
+    ) -> Result<Vec<TrackChange>, crate::error::AlbumApplyError>
+    where
+        P: AsRef<std::path::Path>;
+
     fn apply_convention<P>(&self, directory: P) -> Result<(), crate::error::AlbumApplyError>
     where
         P: AsRef<std::path::Path>;
 }
 
+/// Changes to a track file made by [ApplyMetadata::apply_strict].
+#[cfg(feature = "apply")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackChange {
+    /// Path of the flac file.
+    pub path: std::path::PathBuf,
+    /// Vorbis comments to write, or `None` if current comments are kept.
+    pub comments: Option<Vec<String>>,
+    /// Whether embedded pictures are replaced or removed.
+    pub picture: bool,
+}
+
+#[cfg(feature = "apply")]
+impl TrackChange {
+    /// Returns `true` if the file is left untouched.
+    pub fn is_empty(&self) -> bool {
+        self.comments.is_none() && !self.picture
+    }
+}
+
 #[cfg(feature = "apply")]
 impl ApplyMetadata for Album {
     /// Apply album metadata to a directory formatted with strict album format.
@@ -90,142 +119,28 @@ impl ApplyMetadata for Album {
     where
         P: AsRef<std::path::Path>,
     {
-        use crate::error::AlbumApplyError;
-        use anni_common::fs;
-        use anni_flac::{
-            blocks::{BlockPicture, PictureType, UserComment, UserCommentExt},
-            FlacHeader, MetadataBlock, MetadataBlockData,
-        };
-
-        // check disc name
-        let mut discs = fs::read_dir(directory.as_ref())?
-            .filter_map(|entry| entry.ok())
-            .filter_map(|entry| {
-                entry
-                    .metadata()
-                    .ok()
-                    .and_then(|meta| if meta.is_dir() { Some(entry) } else { None })
-            })
-            .filter_map(|entry| {
-                entry
-                    .path()
-                    .file_name()
-                    .and_then(|f| f.to_str().map(|s| s.to_string()))
-            })
-            .collect::<Vec<_>>();
-        alphanumeric_sort::sort_str_slice(&mut discs);
-
-        if self.discs_len() != discs.len() {
-            return Err(AlbumApplyError::DiscMismatch {
-                path: directory.as_ref().to_path_buf(),
-                expected: self.discs_len(),
-                actual: discs.len(),
-            });
-        }
-
-        let album_cover_path = directory.as_ref().join("cover.jpg");
-        if !album_cover_path.exists() {
-            return Err(AlbumApplyError::MissingCover(album_cover_path));
-        }
-
-        for (index, disc_id) in discs.iter().enumerate() {
-            let disc_path = directory.as_ref().join(disc_id);
-            if disc_id != &(index + 1).to_string() {
-                return Err(AlbumApplyError::InvalidDiscFolder(disc_path));
-            }
-
-            let disc_cover_path = disc_path.join("cover.jpg");
-            if !disc_cover_path.exists() {
-                return Err(AlbumApplyError::MissingCover(disc_cover_path));
-            }
-        }
-
-        let disc_total = discs.len();
-
-        for ((disc_id, disc), disc_name) in self.iter().enumerate().zip(discs) {
-            let disc_num = disc_id + 1;
-            let disc_dir = directory.as_ref().join(disc_name);
-
-            let mut files = fs::get_ext_files(&disc_dir, "flac", false)?;
-            alphanumeric_sort::sort_path_slice(&mut files);
-            let tracks = disc.iter();
-            let track_total = disc.tracks_len();
-
-            if files.len() != track_total {
-                return Err(AlbumApplyError::TrackMismatch {
-                    path: disc_dir,
-                    expected: track_total,
-                    actual: files.len(),
-                });
-            }
-
-            for (track_num, (file, track)) in files.iter().zip(tracks).enumerate() {
-                let track_num = track_num + 1;
-
-                let mut flac = FlacHeader::from_file(file)?;
-                let comments = flac.comments();
-                let meta = format!(
-                    r#"TITLE={title}
-    ALBUM={album}
-    ARTIST={artist}
-    DATE={release_date}
-    TRACKNUMBER={track_number}
-    TRACKTOTAL={track_total}
-    DISCNUMBER={disc_number}
-    DISCTOTAL={disc_total}
-    "#,
-                    title = track.title(),
-                    album = disc.title(),
-                    artist = track.artist(),
-                    release_date = self.release_date(),
-                    track_number = track_num,
-                    disc_number = disc_num,
-                );
-
-                // let mut modified = false;
-                // no comment block exist, or comments is not correct
-                // TODO: the comparison is not accurate if `detailed` = false
-                if comments.is_none() || comments.unwrap().to_string() != meta {
-                    let comments = flac.comments_mut();
-                    comments.clear();
-
-                    if detailed {
-                        comments.push(UserComment::title(track.title()));
-                        comments.push(UserComment::album(disc.title()));
-                        comments.push(UserComment::artist(track.artist()));
-                        comments.push(UserComment::date(self.release_date()));
-                    }
-                    comments.push(UserComment::track_number(track_num));
-                    comments.push(UserComment::track_total(track_total));
-                    comments.push(UserComment::disc_number(disc_num));
-                    comments.push(UserComment::disc_total(disc_total));
-                    // modified = true;
-                }
-
-                if detailed {
-                    // TODO: do not modify flac file if embed cover is the same as the one in folder
-                    let cover_path = file.with_file_name("cover.jpg");
-                    let picture =
-                        BlockPicture::new(cover_path, PictureType::CoverFront, String::new())?;
-                    flac.blocks
-                        .retain(|block| !matches!(block.data, MetadataBlockData::Picture(_)));
-                    flac.blocks
-                        .push(MetadataBlock::new(MetadataBlockData::Picture(picture)));
-                    // modified = true;
-                } else {
-                    // remove cover block
-                    flac.blocks
-                        .retain(|block| !matches!(block.data, MetadataBlockData::Picture(_)));
-                }
-
-                // if modified {
+        for (mut flac, change) in prepare_strict(self, directory.as_ref(), detailed)? {
+            if !change.is_empty() {
                 flac.save::<String>(None)?;
-                // }
             }
         }
         Ok(())
     }
 
+    fn strict_changes<P>(
+        &self,
+        directory: P,
+        detailed: bool,
+    ) -> Result<Vec<TrackChange>, crate::error::AlbumApplyError>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        Ok(prepare_strict(self, directory.as_ref(), detailed)?
+            .into_iter()
+            .map(|(_, change)| change)
+            .collect())
+    }
+
     /// Apply album metadata to a directory formatted with **convention album format**.
     ///
     /// This function applies metadata only. Cover is not checked
@@ -295,6 +210,10 @@ DISCTOTAL={disc_total}
                     disc_number = disc_num,
                     disc_total = disc_total,
                 );
+                let replay_gain = replay_gain_entries(self, &track);
+                let meta = replay_gain
+                    .iter()
+                    .fold(meta, |meta, entry| format!("{meta}{entry}\n"));
                 // no comment block exist, or comments is not correct
                 if comments.is_none() || comments.unwrap().to_string() != meta {
                     let comments = flac.comments_mut();
@@ -307,6 +226,9 @@ DISCTOTAL={disc_total}
                     comments.push(UserComment::track_total(track_total));
                     comments.push(UserComment::disc_number(disc_num));
                     comments.push(UserComment::disc_total(disc_total));
+                    for entry in replay_gain {
+                        comments.push(UserComment::new(entry));
+                    }
                     flac.save::<String>(None)?;
                 }
             }
@@ -315,42 +237,227 @@ DISCTOTAL={disc_total}
     }
 }
 
+/// Modify headers of tracks in `directory` as strict album format in memory.
+///
+/// Returns modified headers with the changes made to them.
+#[cfg(feature = "apply")]
+fn prepare_strict(
+    album: &Album,
+    directory: &std::path::Path,
+    detailed: bool,
+) -> Result<Vec<(anni_flac::FlacHeader, TrackChange)>, crate::error::AlbumApplyError> {
+    use crate::error::AlbumApplyError;
+    use anni_common::fs;
+    use anni_flac::{
+        blocks::{BlockPicture, PictureType, UserComment, UserCommentExt},
+        FlacHeader, MetadataBlock, MetadataBlockData,
+    };
+
+    // check disc name
+    let mut discs = fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            entry
+                .metadata()
+                .ok()
+                .and_then(|meta| if meta.is_dir() { Some(entry) } else { None })
+        })
+        .filter_map(|entry| {
+            entry
+                .path()
+                .file_name()
+                .and_then(|f| f.to_str().map(|s| s.to_string()))
+        })
+        .collect::<Vec<_>>();
+    alphanumeric_sort::sort_str_slice(&mut discs);
+
+    if album.discs_len() != discs.len() {
+        return Err(AlbumApplyError::DiscMismatch {
+            path: directory.to_path_buf(),
+            expected: album.discs_len(),
+            actual: discs.len(),
+        });
+    }
+
+    let album_cover_path = directory.join("cover.jpg");
+    if !album_cover_path.exists() {
+        return Err(AlbumApplyError::MissingCover(album_cover_path));
+    }
+
+    for (index, disc_id) in discs.iter().enumerate() {
+        let disc_path = directory.join(disc_id);
+        if disc_id != &(index + 1).to_string() {
+            return Err(AlbumApplyError::InvalidDiscFolder(disc_path));
+        }
+
+        let disc_cover_path = disc_path.join("cover.jpg");
+        if !disc_cover_path.exists() {
+            return Err(AlbumApplyError::MissingCover(disc_cover_path));
+        }
+    }
+
+    let disc_total = discs.len();
+    let mut result = Vec::new();
+
+    for ((disc_id, disc), disc_name) in album.iter().enumerate().zip(discs) {
+        let disc_num = disc_id + 1;
+        let disc_dir = directory.join(disc_name);
+
+        let mut files = fs::get_ext_files(&disc_dir, "flac", false)?;
+        alphanumeric_sort::sort_path_slice(&mut files);
+        let tracks = disc.iter();
+        let track_total = disc.tracks_len();
+
+        if files.len() != track_total {
+            return Err(AlbumApplyError::TrackMismatch {
+                path: disc_dir,
+                expected: track_total,
+                actual: files.len(),
+            });
+        }
+
+        for (track_num, (file, track)) in files.iter().zip(tracks).enumerate() {
+            let track_num = track_num + 1;
+
+            let mut flac = FlacHeader::from_file(file)?;
+
+            let mut comments = Vec::new();
+            if detailed {
+                comments.push(UserComment::title(track.title()));
+                comments.push(UserComment::album(disc.title()));
+                comments.push(UserComment::artist(track.artist()));
+                comments.push(UserComment::date(album.release_date()));
+            }
+            comments.push(UserComment::track_number(track_num));
+            comments.push(UserComment::track_total(track_total));
+            comments.push(UserComment::disc_number(disc_num));
+            comments.push(UserComment::disc_total(disc_total));
+            for entry in replay_gain_entries(album, &track) {
+                comments.push(UserComment::new(entry));
+            }
+            let comments: Vec<String> = comments.iter().map(UserComment::entry).collect();
+
+            // no comment block exist, or comments is not correct
+            let comments = match flac.comments() {
+                Some(current)
+                    if current
+                        .comments
+                        .iter()
+                        .map(UserComment::entry)
+                        .eq(comments.iter().cloned()) =>
+                {
+                    None
+                }
+                _ => {
+                    let block = flac.comments_mut();
+                    block.clear();
+                    for entry in comments.iter() {
+                        block.push(UserComment::new(entry.clone()));
+                    }
+                    Some(comments)
+                }
+            };
+
+            let has_picture = flac
+                .blocks
+                .iter()
+                .any(|block| matches!(block.data, MetadataBlockData::Picture(_)));
+            flac.blocks
+                .retain(|block| !matches!(block.data, MetadataBlockData::Picture(_)));
+            if detailed {
+                // TODO: do not modify flac file if embed cover is the same as the one in folder
+                let cover_path = file.with_file_name("cover.jpg");
+                let picture =
+                    BlockPicture::new(cover_path, PictureType::CoverFront, String::new())?;
+                flac.blocks
+                    .push(MetadataBlock::new(MetadataBlockData::Picture(picture)));
+            }
+
+            let change = TrackChange {
+                path: file.clone(),
+                comments,
+                picture: detailed || has_picture,
+            };
+            result.push((flac, change));
+        }
+    }
+    Ok(result)
+}
+
+/// ReplayGain comment entries of a track, only values stored in repository are included.
+#[cfg(feature = "apply")]
+fn replay_gain_entries(album: &Album, track: &anni_metadata::model::TrackRef) -> Vec<String> {
+    let mut entries = Vec::new();
+    if let Some(gain) = track.gain() {
+        entries.push(format!("REPLAYGAIN_TRACK_GAIN={gain:.2} dB"));
+    }
+    if let Some(peak) = track.peak() {
+        entries.push(format!("REPLAYGAIN_TRACK_PEAK={peak:.6}"));
+    }
+    if let Some(gain) = album.gain() {
+        entries.push(format!("REPLAYGAIN_ALBUM_GAIN={gain:.2} dB"));
+    }
+    if let Some(peak) = album.peak() {
+        entries.push(format!("REPLAYGAIN_ALBUM_PEAK={peak:.6}"));
+    }
+    entries
+}
+
 pub struct RepoTrack(pub Track);
 
-#[cfg(feature = "flac")]
-impl From<anni_flac::FlacHeader> for RepoTrack {
-    fn from(stream: anni_flac::FlacHeader) -> Self {
+#[cfg(any(feature = "flac", feature = "audio-tags"))]
+impl RepoTrack {
+    /// Create a track from tags of an audio file.
+    ///
+    /// If the title is missing, file name without track number is used instead.
+    pub fn from_tags<T>(tags: &T) -> Self
+    where
+        T: crate::tags::AudioTags + ?Sized,
+    {
         use regex::Regex;
 
-        match stream.comments() {
-            Some(comment) => {
-                let map = comment.to_map();
-                let title = map
-                    .get("TITLE")
-                    .map(|v| v.value().to_owned())
-                    .or_else(|| {
-                        // use filename as default track name
-                        let reg = Regex::new(r#"^\d{2,3}(?:\s?[.-]\s?|\s)(.+)$"#).unwrap();
-                        let input = stream.path.file_stem().and_then(|s| s.to_str())?;
-                        let filename = reg
-                            .captures(input)
-                            .and_then(|c| c.get(1))
-                            .map(|r| r.as_str().to_string())
-                            .unwrap_or_else(|| input.to_string());
-                        Some(filename)
-                    })
-                    .unwrap_or_default();
-                // auto audio type for instrumental, drama and radio
-                let track_type = TrackType::guess(&title);
-                RepoTrack(Track::new(
-                    title,
-                    map.get("ARTIST").map(|v| v.value().to_string()),
-                    None,
-                    track_type,
-                    Default::default(),
-                ))
-            }
-            None => RepoTrack(Track::empty()),
+        if !tags.has_tags() {
+            return RepoTrack(Track::empty());
         }
+
+        let title = tags
+            .title()
+            .map(|title| title.into_owned())
+            .or_else(|| {
+                // use filename as default track name
+                let reg = Regex::new(r#"^\d{2,3}(?:\s?[.-]\s?|\s)(.+)$"#).unwrap();
+                let input = tags.path().file_stem().and_then(|s| s.to_str())?;
+                let filename = reg
+                    .captures(input)
+                    .and_then(|c| c.get(1))
+                    .map(|r| r.as_str().to_string())
+                    .unwrap_or_else(|| input.to_string());
+                Some(filename)
+            })
+            .unwrap_or_default();
+        // auto audio type for instrumental, drama and radio
+        let track_type = TrackType::guess(&title);
+        let mut track = Track::new(
+            title,
+            // empty artist is inherited from disc or album
+            tags.artist()
+                .filter(|artist| !artist.is_empty())
+                .map(|artist| artist.into_owned()),
+            None,
+            track_type,
+            Default::default(),
+        );
+        // keep ReplayGain values computed by external scanners
+        let gain = tags.replay_gain();
+        track.gain = gain.track_gain;
+        track.peak = gain.track_peak;
+        RepoTrack(track)
+    }
+}
+
+#[cfg(feature = "flac")]
+impl From<anni_flac::FlacHeader> for RepoTrack {
+    fn from(stream: anni_flac::FlacHeader) -> Self {
+        RepoTrack::from_tags(&stream)
     }
 }
@@ -1,7 +1,7 @@
 use std::{collections::HashMap, str::FromStr};
 
 use crate::error::Error;
-use anni_metadata::model::{Album, AlbumInfo, AnniDate, Disc, TagString, TrackType};
+use anni_metadata::model::{Album, AlbumInfo, AnniDate, Disc, Tag, TagRef, TagString, TrackType};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -43,6 +43,14 @@ pub struct JsonAlbumInfo {
     pub album_type: TrackType,
     /// Album catalog
     pub catalog: String,
+    /// Album ReplayGain in dB
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gain: Option<f64>,
+    /// Album ReplayGain peak
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peak: Option<f64>,
     /// Album tags
     #[serde(default)]
     pub tags: Vec<TagString>,
@@ -73,6 +81,8 @@ impl From<Album> for JsonAlbum {
             release_date,
             album_type,
             catalog,
+            gain,
+            peak,
             tags,
         } = album.info;
         JsonAlbum {
@@ -85,6 +95,8 @@ impl From<Album> for JsonAlbum {
                 release_date: release_date.to_string(),
                 album_type,
                 catalog,
+                gain,
+                peak,
                 tags,
             },
             discs: album.discs,
@@ -105,6 +117,8 @@ impl TryFrom<JsonAlbum> for Album {
             release_date,
             album_type,
             catalog,
+            gain,
+            peak,
             tags,
         } = album.info;
         Ok(Album {
@@ -117,6 +131,8 @@ impl TryFrom<JsonAlbum> for Album {
                 release_date: AnniDate::from_str(&release_date)?,
                 album_type,
                 catalog,
+                gain,
+                peak,
                 tags,
             },
             discs: album.discs,
@@ -124,6 +140,22 @@ impl TryFrom<JsonAlbum> for Album {
     }
 }
 
+/// All tags in repository and their relations
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct JsonTags {
+    pub tags: Vec<Tag>,
+    /// Parent to children relations, including those declared by children with `included-by`
+    pub relations: Vec<JsonTagRelation>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct JsonTagRelation {
+    pub parent: TagRef<'static>,
+    pub children: Vec<TagRef<'static>>,
+}
+
 mod test {
     #[test]
     fn test_json_album_serialize_deserialize() {
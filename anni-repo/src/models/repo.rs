@@ -13,12 +13,19 @@ struct RepositoryInner {
     edition: String,
     #[serde(default = "default_albums")]
     albums: Vec<String>,
+    /// Pattern of album and disc catalogs, used by lint
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    catalog_pattern: Option<String>,
 }
 
 fn default_albums() -> Vec<String> {
     vec!["album".into()]
 }
 
+/// Default pattern of catalogs, e.g. `LACA-15001` or `LACA-15001~2`
+pub const DEFAULT_CATALOG_PATTERN: &str = r"^[A-Z]{2,5}-\d{3,5}(~\d+)?$";
+
 impl FromStr for Repository {
     type Err = Error;
 
@@ -50,4 +57,11 @@ impl Repository {
     pub fn albums(&self) -> &[String] {
         self.repo.albums.as_ref()
     }
+
+    pub fn catalog_pattern(&self) -> &str {
+        self.repo
+            .catalog_pattern
+            .as_deref()
+            .unwrap_or(DEFAULT_CATALOG_PATTERN)
+    }
 }
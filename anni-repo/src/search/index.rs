@@ -1,7 +1,10 @@
 use std::path::Path;
 
 use lindera_core::mode::Mode;
-use lindera_dictionary::{load_dictionary_from_config, DictionaryConfig, DictionaryKind};
+use lindera_dictionary::{
+    load_dictionary_from_config, load_user_dictionary_from_config, DictionaryConfig,
+    DictionaryKind, UserDictionaryConfig,
+};
 use lindera_tantivy::tokenizer::LinderaTokenizer;
 use tantivy::{
     doc,
@@ -13,6 +16,7 @@ use tantivy::{
 pub use tantivy;
 use uuid::Uuid;
 
+use super::TokenizerConfig;
 use crate::prelude::TrackIdentifier;
 
 pub struct RepositorySearchManager {
@@ -22,38 +26,74 @@ pub struct RepositorySearchManager {
 
 impl RepositorySearchManager {
     pub fn create<P>(directory_path: P) -> Result<Self, TantivyError>
+    where
+        P: AsRef<Path>,
+    {
+        Self::create_with_tokenizer(directory_path, &TokenizerConfig::default())
+    }
+
+    pub fn create_with_tokenizer<P>(
+        directory_path: P,
+        tokenizer: &TokenizerConfig,
+    ) -> Result<Self, TantivyError>
     where
         P: AsRef<Path>,
     {
         let (schema, fields) = SearchFields::new();
         let index = Index::create_in_dir(directory_path, schema)?;
         let me = Self { index, fields };
-        me.register_tokenizers();
+        me.register_tokenizers(tokenizer)?;
         Ok(me)
     }
 
     pub fn open<P>(directory_path: P) -> Result<Self, TantivyError>
+    where
+        P: AsRef<Path>,
+    {
+        Self::open_with_tokenizer(directory_path, &TokenizerConfig::default())
+    }
+
+    /// Open an existing index.
+    ///
+    /// `tokenizer` should be the same as the one used when creating the index,
+    /// otherwise queries may be tokenized differently from indexed documents.
+    pub fn open_with_tokenizer<P>(
+        directory_path: P,
+        tokenizer: &TokenizerConfig,
+    ) -> Result<Self, TantivyError>
     where
         P: AsRef<Path>,
     {
         let index = Index::open_in_dir(directory_path)?;
         let fields = SearchFields::from_index(&index);
         let me = Self { index, fields };
-        me.register_tokenizers();
+        me.register_tokenizers(tokenizer)?;
         Ok(me)
     }
 
-    fn register_tokenizers(&self) {
+    fn register_tokenizers(&self, config: &TokenizerConfig) -> Result<(), TantivyError> {
         let dictionary_config = DictionaryConfig {
             kind: Some(DictionaryKind::IPADIC),
             path: None,
         };
-        let dictionary = load_dictionary_from_config(dictionary_config).unwrap();
+        let dictionary = load_dictionary_from_config(dictionary_config)
+            .map_err(|e| TantivyError::InvalidArgument(e.to_string()))?;
+        let user_dictionary = match &config.user_dictionary {
+            Some(path) => Some(
+                load_user_dictionary_from_config(UserDictionaryConfig {
+                    kind: Some(DictionaryKind::IPADIC),
+                    path: path.clone(),
+                })
+                .map_err(|e| TantivyError::InvalidArgument(e.to_string()))?,
+            ),
+            None => None,
+        };
 
         self.index.tokenizers().register(
             "lang_ja",
-            LinderaTokenizer::new(dictionary, None, Mode::Normal),
+            LinderaTokenizer::new(dictionary, user_dictionary, Mode::Normal),
         );
+        Ok(())
     }
 
     pub fn build_document(
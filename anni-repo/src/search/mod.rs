@@ -0,0 +1,24 @@
+//! Search index of metadata repository.
+//!
+//! [TokenizerConfig] is always available, so that servers with their own index can share the same configuration.
+//! Index related items require feature `search`.
+
+use serde::Deserialize;
+use std::path::PathBuf;
+
+#[cfg(feature = "search")]
+mod index;
+#[cfg(feature = "search")]
+pub use index::*;
+
+/// Dictionary used by the tokenizer of search index.
+///
+/// The bundled IPADIC is always used as system dictionary.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TokenizerConfig {
+    /// Path of IPADIC user dictionary in csv or compiled format.
+    ///
+    /// Terms in user dictionary would not be split by tokenizer, which is useful for artist or series names.
+    pub user_dictionary: Option<PathBuf>,
+}
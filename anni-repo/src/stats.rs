@@ -0,0 +1,118 @@
+use crate::OwnedRepositoryManager;
+use anni_metadata::model::{AnniDate, TagRef};
+use serde::Serialize;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// Overview of a metadata repository
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct RepoStats {
+    pub albums: usize,
+    pub discs: usize,
+    pub tracks: usize,
+    /// Number of distinct artists of albums, discs and tracks
+    pub artists: usize,
+    pub tags: usize,
+    /// Number of albums tagged by each tag, most used first
+    pub tag_usage: Vec<TagUsage>,
+    /// Albums without any tag, sorted by album id
+    pub untagged_albums: Vec<Uuid>,
+    /// Albums without cover, sorted by album id
+    ///
+    /// Covers are stored in library instead of metadata repository,
+    /// so this is `None` unless checked by [RepoStats::check_covers].
+    pub albums_missing_cover: Option<Vec<Uuid>>,
+    /// Release date of the earliest album
+    pub earliest_release: Option<String>,
+    /// Release date of the latest album
+    pub latest_release: Option<String>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct TagUsage {
+    pub tag: String,
+    pub albums: usize,
+}
+
+impl RepoStats {
+    pub fn new(manager: &OwnedRepositoryManager) -> Self {
+        let mut stats = RepoStats::default();
+        let mut artists = HashSet::new();
+        let mut dates = Vec::new();
+
+        for album in manager.albums_iter() {
+            stats.albums += 1;
+            artists.insert(album.artist());
+            for disc in album.iter() {
+                stats.discs += 1;
+                artists.insert(disc.artist());
+                for track in disc.iter() {
+                    stats.tracks += 1;
+                    artists.insert(track.artist());
+                }
+            }
+
+            if album.tags().is_empty() {
+                stats.untagged_albums.push(album.album_id());
+            }
+
+            let date = album.release_date();
+            if date.year() != 0 {
+                dates.push(date);
+            }
+        }
+        stats.artists = artists.len();
+        stats.untagged_albums.sort();
+
+        dates.sort_by_key(|date| date_key(date));
+        stats.earliest_release = dates.first().map(|date| format_date(date));
+        stats.latest_release = dates.last().map(|date| format_date(date));
+
+        for tag in manager.tags_iter() {
+            let tag: &TagRef = tag.as_ref();
+            stats.tags += 1;
+            stats.tag_usage.push(TagUsage {
+                tag: tag.to_string(),
+                albums: manager
+                    .albums_tagged_by(tag)
+                    .map_or(0, |albums| albums.len()),
+            });
+        }
+        stats
+            .tag_usage
+            .sort_by(|a, b| b.albums.cmp(&a.albums).then_with(|| a.tag.cmp(&b.tag)));
+
+        stats
+    }
+
+    /// Check covers of all albums in `manager` by `has_cover`, and record those without cover.
+    pub fn check_covers<F>(&mut self, manager: &OwnedRepositoryManager, has_cover: F)
+    where
+        F: Fn(Uuid) -> bool,
+    {
+        let mut albums: Vec<_> = manager
+            .albums_iter()
+            .map(|album| album.album_id())
+            .filter(|album_id| !has_cover(*album_id))
+            .collect();
+        albums.sort();
+        self.albums_missing_cover = Some(albums);
+    }
+}
+
+// month and day are 0 if absent
+fn date_key(date: &AnniDate) -> (u16, u8, u8) {
+    (
+        date.year(),
+        date.month().unwrap_or(0),
+        date.day().unwrap_or(0),
+    )
+}
+
+fn format_date(date: &AnniDate) -> String {
+    match date_key(date) {
+        (year, 0, _) => year.to_string(),
+        (year, month, 0) => format!("{year}-{month:02}"),
+        (year, month, day) => format!("{year}-{month:02}-{day:02}"),
+    }
+}
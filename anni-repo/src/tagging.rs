@@ -0,0 +1,131 @@
+//! Bulk editing of album tags.
+//!
+//! Album files are edited in place with `toml_edit`, so formatting and comments are kept.
+
+use crate::prelude::*;
+use crate::OwnedRepositoryManager;
+use anni_common::fs;
+use anni_metadata::model::{TagRef, TagType};
+use std::path::PathBuf;
+use toml_edit::{Array, Document, Item, Value};
+
+impl OwnedRepositoryManager {
+    /// Add `tag` to albums whose catalog starts with `catalog_prefix`.
+    ///
+    /// The tag must be defined in the repository, so that no orphan tag is created.
+    /// Returns paths of changed album files. Albums which already have the tag are not changed.
+    pub fn add_album_tag(
+        &self,
+        tag: &TagRef<'_>,
+        catalog_prefix: &str,
+    ) -> RepoResult<Vec<PathBuf>> {
+        let tag = match self.tag(tag) {
+            Some(tag) => tag.get_owned_ref(),
+            None => return Err(Error::RepoTagsUndefined(vec![tag.full_clone()])),
+        };
+
+        self.edit_album_tags(catalog_prefix, |tags| {
+            if tags
+                .iter()
+                .filter_map(Value::as_str)
+                .any(|t| self.is_same_tag(t, &tag))
+            {
+                return false;
+            }
+            tags.push(tag.to_string());
+            true
+        })
+    }
+
+    /// Remove `tag` from albums whose catalog starts with `catalog_prefix`.
+    ///
+    /// Tags are matched by their canonical names, so aliases of `tag` are removed, too.
+    /// Returns paths of changed album files.
+    pub fn remove_album_tag(
+        &self,
+        tag: &TagRef<'_>,
+        catalog_prefix: &str,
+    ) -> RepoResult<Vec<PathBuf>> {
+        let tag = self.resolve_alias(tag).unwrap_or(tag);
+
+        self.edit_album_tags(catalog_prefix, |tags| {
+            let mut changed = false;
+            let mut index = 0;
+            while index < tags.len() {
+                let matched = tags
+                    .get(index)
+                    .and_then(Value::as_str)
+                    .map_or(false, |t| self.is_same_tag(t, tag));
+                if !matched {
+                    index += 1;
+                    continue;
+                }
+
+                let removed = tags.remove(index);
+                // keep the leading whitespace of array
+                if index == 0 {
+                    if let (Some(first), Some(prefix)) =
+                        (tags.get_mut(0), removed.decor().prefix().cloned())
+                    {
+                        first.decor_mut().set_prefix(prefix);
+                    }
+                }
+                changed = true;
+            }
+            changed
+        })
+    }
+
+    /// Whether tag written as `tag` in album file refers to `target`.
+    fn is_same_tag(&self, tag: &str, target: &TagRef<'_>) -> bool {
+        let tag = TagRef::from_cow_str(tag);
+        let tag = self.resolve_alias(&tag).unwrap_or(&tag);
+        tag.name() == target.name()
+            && (tag.tag_type() == target.tag_type() || tag.tag_type() == &TagType::Unknown)
+    }
+
+    /// Apply `edit` to `album.tags` of albums whose catalog starts with `catalog_prefix`,
+    /// and write albums back if `edit` returns true.
+    fn edit_album_tags<F>(&self, catalog_prefix: &str, mut edit: F) -> RepoResult<Vec<PathBuf>>
+    where
+        F: FnMut(&mut Array) -> bool,
+    {
+        let root = self.repo.disk_root()?;
+        let mut paths: Vec<_> = self
+            .albums_iter()
+            .filter(|album| album.catalog().starts_with(catalog_prefix))
+            .filter_map(|album| self.album_path(&album.album_id()))
+            .map(|path| root.join(path))
+            .collect();
+        paths.sort();
+
+        let mut changed = Vec::new();
+        for path in paths {
+            let input = fs::read_to_string(&path)?;
+            let invalid = || Error::RepoAlbumLoadError {
+                album: path.display().to_string(),
+            };
+            let mut document = input.parse::<Document>().map_err(|_| invalid())?;
+            let album = document
+                .get_mut("album")
+                .and_then(Item::as_table_mut)
+                .ok_or_else(invalid)?;
+            let tags = album
+                .entry("tags")
+                .or_insert(toml_edit::value(Array::new()))
+                .as_array_mut()
+                .ok_or_else(invalid)?;
+            if !edit(tags) {
+                continue;
+            }
+            if tags.is_empty() {
+                album.remove("tags");
+            }
+
+            std::fs::write(&path, document.to_string())?;
+            changed.push(path);
+        }
+
+        Ok(changed)
+    }
+}
@@ -0,0 +1,182 @@
+//! Read basic tags from audio files.
+//!
+//! FLAC files are read by `anni-flac` with feature `flac`.
+//! Opus, Ogg Vorbis and MP4 files are read by `lofty` with feature `audio-tags`.
+
+use crate::error::AudioTagsError;
+use std::borrow::Cow;
+use std::path::Path;
+#[cfg(feature = "audio-tags")]
+use std::path::PathBuf;
+
+/// Basic tags of an audio file, used to create tracks in metadata repository.
+pub trait AudioTags {
+    /// Path of the audio file.
+    fn path(&self) -> &Path;
+
+    /// Whether the file has a tag block at all.
+    fn has_tags(&self) -> bool;
+
+    /// Title of the track.
+    fn title(&self) -> Option<Cow<str>>;
+
+    /// Artist of the track.
+    fn artist(&self) -> Option<Cow<str>>;
+
+    /// ReplayGain values written by a ReplayGain scanner, if any.
+    fn replay_gain(&self) -> ReplayGain;
+}
+
+/// ReplayGain values read from `REPLAYGAIN_*` tags.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ReplayGain {
+    pub track_gain: Option<f64>,
+    pub track_peak: Option<f64>,
+    pub album_gain: Option<f64>,
+    pub album_peak: Option<f64>,
+}
+
+impl ReplayGain {
+    /// Parse a ReplayGain tag value like `-8.21 dB` or `0.988525`.
+    pub fn parse_value(value: &str) -> Option<f64> {
+        let value = value.trim();
+        let value = match value.len().checked_sub(2) {
+            Some(i) if value.is_char_boundary(i) && value[i..].eq_ignore_ascii_case("dB") => {
+                &value[..i]
+            }
+            _ => value,
+        };
+        value.trim().parse().ok()
+    }
+}
+
+#[cfg(feature = "flac")]
+impl AudioTags for anni_flac::FlacHeader {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn has_tags(&self) -> bool {
+        self.comments().is_some()
+    }
+
+    fn title(&self) -> Option<Cow<str>> {
+        self.comments()?
+            .to_map()
+            .get("TITLE")
+            .map(|v| Cow::Owned(v.value().to_string()))
+    }
+
+    fn artist(&self) -> Option<Cow<str>> {
+        self.comments()?
+            .to_map()
+            .get("ARTIST")
+            .map(|v| Cow::Owned(v.value().to_string()))
+    }
+
+    fn replay_gain(&self) -> ReplayGain {
+        let Some(comments) = self.comments() else {
+            return ReplayGain::default();
+        };
+        let map = comments.to_map();
+        let get = |key: &str| {
+            map.get(key)
+                .and_then(|v| ReplayGain::parse_value(v.value()))
+        };
+        ReplayGain {
+            track_gain: get("REPLAYGAIN_TRACK_GAIN"),
+            track_peak: get("REPLAYGAIN_TRACK_PEAK"),
+            album_gain: get("REPLAYGAIN_ALBUM_GAIN"),
+            album_peak: get("REPLAYGAIN_ALBUM_PEAK"),
+        }
+    }
+}
+
+/// Tags of Opus, Ogg Vorbis or MP4 files.
+#[cfg(feature = "audio-tags")]
+pub struct LoftyTags {
+    path: PathBuf,
+    tag: Option<lofty::tag::Tag>,
+}
+
+#[cfg(feature = "audio-tags")]
+impl LoftyTags {
+    pub fn from_file<P>(path: P) -> Result<Self, lofty::error::LoftyError>
+    where
+        P: AsRef<Path>,
+    {
+        use lofty::file::TaggedFileExt;
+
+        let file = lofty::read_from_path(path.as_ref())?;
+        let tag = file.primary_tag().or_else(|| file.first_tag()).cloned();
+        Ok(Self {
+            path: path.as_ref().to_path_buf(),
+            tag,
+        })
+    }
+}
+
+#[cfg(feature = "audio-tags")]
+impl AudioTags for LoftyTags {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn has_tags(&self) -> bool {
+        self.tag.is_some()
+    }
+
+    fn title(&self) -> Option<Cow<str>> {
+        use lofty::tag::Accessor;
+        self.tag.as_ref()?.title()
+    }
+
+    fn artist(&self) -> Option<Cow<str>> {
+        use lofty::tag::Accessor;
+        self.tag.as_ref()?.artist()
+    }
+
+    fn replay_gain(&self) -> ReplayGain {
+        use lofty::tag::ItemKey;
+
+        let Some(tag) = self.tag.as_ref() else {
+            return ReplayGain::default();
+        };
+        let get = |key: &ItemKey| tag.get_string(key).and_then(ReplayGain::parse_value);
+        ReplayGain {
+            track_gain: get(&ItemKey::ReplayGainTrackGain),
+            track_peak: get(&ItemKey::ReplayGainTrackPeak),
+            album_gain: get(&ItemKey::ReplayGainAlbumGain),
+            album_peak: get(&ItemKey::ReplayGainAlbumPeak),
+        }
+    }
+}
+
+/// Extensions of audio files supported by [read_audio_tags], in order of preference.
+pub fn audio_extensions() -> &'static [&'static str] {
+    #[cfg(all(feature = "flac", feature = "audio-tags"))]
+    return &["flac", "opus", "ogg", "m4a"];
+    #[cfg(all(feature = "flac", not(feature = "audio-tags")))]
+    return &["flac"];
+    #[cfg(all(not(feature = "flac"), feature = "audio-tags"))]
+    return &["opus", "ogg", "m4a"];
+}
+
+/// Read tags of audio file at `path`, choosing the reader by file extension.
+pub fn read_audio_tags<P>(path: P) -> Result<Box<dyn AudioTags>, AudioTagsError>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+    match extension.as_deref() {
+        #[cfg(feature = "flac")]
+        Some("flac") => Ok(Box::new(anni_flac::FlacHeader::from_file(path)?)),
+        #[cfg(feature = "audio-tags")]
+        Some("opus" | "ogg" | "m4a") => Ok(Box::new(LoftyTags::from_file(path)?)),
+        _ => Err(AudioTagsError::UnsupportedFormat(path.to_path_buf())),
+    }
+}
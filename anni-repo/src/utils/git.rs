@@ -1,3 +1,4 @@
+use crate::prelude::RepoResult;
 use git2::Repository;
 use std::io::{self, Write};
 use std::path::Path;
@@ -185,3 +186,115 @@ pub(crate) fn pull<P: AsRef<Path>>(root: P, remote_branch: &str) -> Result<(), g
     let fetch_commit = do_fetch(&repo, &[remote_branch], &mut remote)?;
     do_merge(&repo, remote_branch, fetch_commit)
 }
+
+/// Open the git repository containing `root`, or `None` if `root` is not in a git worktree.
+fn open_worktree(root: &Path) -> Result<Option<Repository>, git2::Error> {
+    match Repository::discover(root) {
+        Ok(repo) if repo.is_bare() => Ok(None),
+        Ok(repo) => Ok(Some(repo)),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Commit `paths` in repository at `root` with `message`.
+///
+/// Only `paths` are staged and committed, so other changes in the worktree or the index are left untouched.
+/// `paths` are either relative to `root`, or start with `root`. Removed files are removed from the index.
+pub(crate) fn commit<P, Q>(root: P, paths: &[Q], message: &str) -> RepoResult<bool>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let Some(repo) = open_worktree(root.as_ref())? else {
+        return Ok(false);
+    };
+
+    let workdir = repo.workdir().unwrap().canonicalize()?;
+    let canonical_root = root.as_ref().canonicalize()?;
+    let mut relative_paths = Vec::with_capacity(paths.len());
+    for path in paths {
+        let path = path.as_ref();
+        let path = path.strip_prefix(root.as_ref()).unwrap_or(path);
+        let path = canonical_root.join(path);
+        let relative = path.strip_prefix(&workdir).map_err(|_| {
+            git2::Error::from_str(&format!("{} is outside of worktree", path.display()))
+        })?;
+        relative_paths.push(relative.to_path_buf());
+    }
+
+    let parent = match repo.head() {
+        Ok(head) => Some(head.peel_to_commit()?),
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    // stage paths in the index of worktree
+    let mut index = repo.index()?;
+    for path in relative_paths.iter() {
+        if workdir.join(path).exists() {
+            index.add_path(path)?;
+        } else {
+            index.remove_path(path)?;
+        }
+    }
+    index.write()?;
+
+    // the committed tree is the parent tree with only paths updated
+    let mut staging = git2::Index::new()?;
+    if let Some(parent) = &parent {
+        staging.read_tree(&parent.tree()?)?;
+    }
+    for path in relative_paths.iter() {
+        match index.get_path(path, 0) {
+            Some(entry) => staging.add(&entry)?,
+            None => staging.remove_path(path)?,
+        }
+    }
+    let tree = repo.find_tree(staging.write_tree_to(&repo)?)?;
+
+    if parent
+        .as_ref()
+        .map_or(tree.is_empty(), |parent| parent.tree_id() == tree.id())
+    {
+        // nothing to commit
+        return Ok(false);
+    }
+
+    let signature = repo.signature()?;
+    let parents: Vec<_> = parent.iter().collect();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents,
+    )?;
+    Ok(true)
+}
+
+pub(crate) fn push<P: AsRef<Path>>(root: P, branch: &str) -> Result<bool, git2::Error> {
+    let Some(repo) = open_worktree(root.as_ref())? else {
+        return Ok(false);
+    };
+    let mut remote = repo.find_remote("origin")?;
+    let config = repo.config()?;
+
+    let mut cb = git2::RemoteCallbacks::new();
+    cb.credentials(|url, username, _| git2::Cred::credential_helper(&config, url, username));
+    cb.push_update_reference(|reference, status| match status {
+        Some(message) => Err(git2::Error::from_str(&format!(
+            "Failed to push {reference}: {message}"
+        ))),
+        None => Ok(()),
+    });
+
+    let mut po = git2::PushOptions::new();
+    po.remote_callbacks(cb);
+    remote.push(
+        &[format!("refs/heads/{branch}:refs/heads/{branch}")],
+        Some(&mut po),
+    )?;
+    Ok(true)
+}
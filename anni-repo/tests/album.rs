@@ -72,3 +72,38 @@ fn test_deserialize_album() {
         }
     }
 }
+
+#[test]
+fn test_deserialize_album_gain() {
+    let album = Album::from_str(include_str!("fixtures/test-album-gain.toml"))
+        .expect("Failed to parse album toml.");
+    assert_eq!(album.gain(), Some(-7.5));
+    assert_eq!(album.peak(), Some(0.988831));
+
+    let disc = album.iter().next().unwrap();
+    let tracks = disc.iter().collect::<Vec<_>>();
+    assert_eq!(tracks[0].gain(), Some(-8.21));
+    assert_eq!(tracks[0].peak(), Some(0.977142));
+    assert_eq!(tracks[1].gain(), None);
+    assert_eq!(tracks[1].peak(), None);
+
+    // gain fields are kept after serialization
+    let serialized = toml::to_string_pretty(&album).unwrap();
+    let album = Album::from_str(&serialized).unwrap();
+    assert_eq!(album.gain(), Some(-7.5));
+    let disc = album.iter().next().unwrap();
+    assert_eq!(disc.iter().next().unwrap().gain(), Some(-8.21));
+}
+
+#[test]
+fn test_deserialize_album_without_gain() {
+    let album = album_from_str();
+    assert_eq!(album.gain(), None);
+    assert_eq!(album.peak(), None);
+    for disc in album.iter() {
+        for track in disc.iter() {
+            assert_eq!(track.gain(), None);
+            assert_eq!(track.peak(), None);
+        }
+    }
+}
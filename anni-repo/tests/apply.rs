@@ -0,0 +1,59 @@
+#![cfg(feature = "apply")]
+
+use anni_flac::FlacHeader;
+use anni_metadata::model::Album;
+use anni_repo::models::ApplyMetadata;
+use std::str::FromStr;
+
+#[test]
+fn test_apply_replay_gain() {
+    let album = Album::from_str(include_str!("fixtures/test-album-gain.toml"))
+        .expect("Failed to parse album toml.");
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::copy("../assets/1s.flac", dir.path().join("01.flac")).unwrap();
+    std::fs::copy("../assets/1s.flac", dir.path().join("02.flac")).unwrap();
+    album.apply_convention(dir.path()).unwrap();
+
+    let header = FlacHeader::from_file(dir.path().join("01.flac")).unwrap();
+    let comments = header.comments().unwrap().to_map();
+    assert_eq!(comments["REPLAYGAIN_TRACK_GAIN"].value(), "-8.21 dB");
+    assert_eq!(comments["REPLAYGAIN_TRACK_PEAK"].value(), "0.977142");
+    assert_eq!(comments["REPLAYGAIN_ALBUM_GAIN"].value(), "-7.50 dB");
+    assert_eq!(comments["REPLAYGAIN_ALBUM_PEAK"].value(), "0.988831");
+
+    // track without stored gain only gets album gain
+    let header = FlacHeader::from_file(dir.path().join("02.flac")).unwrap();
+    let comments = header.comments().unwrap().to_map();
+    assert!(!comments.contains_key("REPLAYGAIN_TRACK_GAIN"));
+    assert!(!comments.contains_key("REPLAYGAIN_TRACK_PEAK"));
+    assert_eq!(comments["REPLAYGAIN_ALBUM_GAIN"].value(), "-7.50 dB");
+}
+
+#[test]
+fn test_strict_changes() {
+    let album = Album::from_str(include_str!("fixtures/test-album-gain.toml"))
+        .expect("Failed to parse album toml.");
+
+    let dir = tempfile::tempdir().unwrap();
+    let disc = dir.path().join("1");
+    std::fs::create_dir_all(&disc).unwrap();
+    std::fs::write(dir.path().join("cover.jpg"), "cover").unwrap();
+    std::fs::write(disc.join("cover.jpg"), "cover").unwrap();
+    std::fs::copy("../assets/1s.flac", disc.join("01.flac")).unwrap();
+    std::fs::copy("../assets/1s.flac", disc.join("02.flac")).unwrap();
+
+    // nothing is written when computing changes
+    let changes = album.strict_changes(dir.path(), false).unwrap();
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[0].path, disc.join("01.flac"));
+    let comments = changes[0].comments.as_ref().unwrap();
+    assert!(comments.contains(&"TRACKNUMBER=1".to_string()));
+    assert!(comments.contains(&"REPLAYGAIN_TRACK_GAIN=-8.21 dB".to_string()));
+    assert_eq!(album.strict_changes(dir.path(), false).unwrap(), changes);
+
+    // applied tracks are left untouched
+    album.apply_strict(dir.path(), false).unwrap();
+    let changes = album.strict_changes(dir.path(), false).unwrap();
+    assert!(changes.iter().all(|change| change.is_empty()));
+}
@@ -0,0 +1,94 @@
+//! Shared fixture of anni-repo tests.
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Create an empty repository at `root`.
+pub fn create_repo(root: &Path) {
+    fs::create_dir_all(root.join("album")).unwrap();
+    fs::create_dir_all(root.join("tag")).unwrap();
+    fs::write(
+        root.join("repo.toml"),
+        r#"[repo]
+name = "Metadata repo test cases"
+edition = "1.0+alpha.1.5.1"
+"#,
+    )
+    .unwrap();
+}
+
+/// TOML of an album tagged by `tags`, which has one disc with `tracks`.
+pub fn album(album_id: &str, title: &str, catalog: &str, tags: &[&str], tracks: &[&str]) -> String {
+    let mut album = format!(
+        r#"[album]
+album_id = "{album_id}"
+title = "{title}"
+artist = "Artist"
+date = 2999-12-31
+type = "normal"
+catalog = "{catalog}"
+"#
+    );
+    if !tags.is_empty() {
+        let tags: Vec<_> = tags.iter().map(|tag| format!(r#""{tag}""#)).collect();
+        album += &format!("tags = [{}]\n", tags.join(", "));
+    }
+    album += &format!(
+        r#"
+[[discs]]
+catalog = "{catalog}"
+"#
+    );
+    for track in tracks {
+        album += &format!(
+            r#"
+[[discs.tracks]]
+title = "{track}"
+type = "normal"
+"#
+        );
+    }
+    album
+}
+
+/// Write `album` to `album/{name}.toml` in repository at `root`.
+pub fn write_album(root: &Path, name: &str, album: &str) {
+    fs::write(root.join("album").join(format!("{name}.toml")), album).unwrap();
+}
+
+/// Id of the `index`-th album in repository created by [generate_repo].
+pub fn generated_album_id(index: usize) -> Uuid {
+    Uuid::from_str(&format!("00000000-0000-4000-8000-{index:012}")).unwrap()
+}
+
+/// The `index`-th album in repository created by [generate_repo], which is tagged by `tag`.
+pub fn generated_album(index: usize, album_id: &str, tag: &str) -> String {
+    let catalog = format!("TEST-{index:04}");
+    album(
+        album_id,
+        &format!("Title {index}"),
+        &catalog,
+        &[tag],
+        &["Track 1"],
+    )
+}
+
+/// Generate a repository with `count` albums, each tagged by one of 10 artists.
+///
+/// The `i`-th album is stored at `album/TEST-{i:04}.toml`.
+pub fn generate_repo(root: &Path, count: usize) {
+    create_repo(root);
+    let tags: String = (0..10)
+        .map(|i| format!("[[tag]]\nname = \"Artist {i}\"\ntype = \"artist\"\n\n"))
+        .collect();
+    fs::write(root.join("tag/default.toml"), tags).unwrap();
+
+    for i in 0..count {
+        let album_id = generated_album_id(i).to_string();
+        let album = generated_album(i, &album_id, &format!("artist:Artist {}", i % 10));
+        write_album(root, &format!("TEST-{i:04}"), &album);
+    }
+}
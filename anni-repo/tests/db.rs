@@ -0,0 +1,207 @@
+#![cfg(feature = "db")]
+
+use anni_metadata::model::AnniDate;
+use anni_repo::{db::RepoDatabaseRead, error::Error, RepositoryManager};
+use fs4::FileExt;
+use std::path::Path;
+use std::str::FromStr;
+use uuid::Uuid;
+
+#[test]
+fn test_match_album_edition() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("repo.db");
+    RepositoryManager::new("tests/repos/editions")
+        .expect("Failed to load metadata repository")
+        .into_owned_manager()
+        .expect("Failed to convert to owned manager")
+        .to_database(&db_path)
+        .expect("Failed to write database");
+
+    let db = RepoDatabaseRead::new(&db_path).expect("Failed to open database");
+    let date = AnniDate::from_str("2999-12-31").unwrap();
+    let plain = Uuid::from_str("7b1d3a8e-52c4-4f0e-9a0c-1a2b3c4d5e01").unwrap();
+    let limited = Uuid::from_str("7b1d3a8e-52c4-4f0e-9a0c-1a2b3c4d5e02").unwrap();
+
+    let match_album = |edition| {
+        db.match_album("TEST-0001", &date, 1, "Title", edition)
+            .unwrap()
+    };
+    assert_eq!(match_album(Some("Limited")), Some(limited));
+    assert_eq!(match_album(None), Some(plain));
+    // empty edition is the same as no edition
+    assert_eq!(match_album(Some("")), Some(plain));
+    assert_eq!(match_album(Some("Other")), None);
+}
+
+#[test]
+fn test_concurrent_database_build() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("repo.db");
+    let lock_path = dir.path().join("repo.db.lock");
+    let manager = RepositoryManager::new("tests/repos/editions")
+        .expect("Failed to load metadata repository")
+        .into_owned_manager()
+        .expect("Failed to convert to owned manager");
+
+    // another build is writing to the database
+    std::fs::write(&db_path, "building").unwrap();
+    let lock = std::fs::File::create(&lock_path).unwrap();
+    lock.lock_exclusive().unwrap();
+    assert!(matches!(
+        manager.to_database(&db_path),
+        Err(Error::RepoInUse)
+    ));
+    assert_eq!(std::fs::read_to_string(&db_path).unwrap(), "building");
+
+    // lock file left by a crashed build is taken over
+    lock.unlock().unwrap();
+    drop(lock);
+    manager
+        .to_database(&db_path)
+        .expect("Failed to write database");
+    RepoDatabaseRead::new(&db_path).expect("Failed to open database");
+
+    // lock is released after the build
+    let lock = std::fs::File::open(&lock_path).unwrap();
+    lock.try_lock_exclusive()
+        .expect("Lock should be released after the build");
+}
+
+/// Copy a repository to a temporary directory so that it can be modified.
+fn copy_repo(from: &Path, to: &Path) {
+    std::fs::create_dir_all(to).unwrap();
+    for entry in std::fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        let target = to.join(entry.file_name());
+        if entry.file_type().unwrap().is_dir() {
+            copy_repo(&entry.path(), &target);
+        } else {
+            std::fs::copy(entry.path(), target).unwrap();
+        }
+    }
+}
+
+#[test]
+fn test_incremental_database_build() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path().join("repo");
+    let db_path = dir.path().join("repo.db");
+    copy_repo(Path::new("tests/repos/editions"), &repo);
+
+    let build = || {
+        RepositoryManager::new(&repo)
+            .expect("Failed to load metadata repository")
+            .into_owned_manager()
+            .expect("Failed to convert to owned manager")
+            .to_database_incremental(&db_path)
+            .expect("Failed to write database")
+    };
+    let title = |album_id| {
+        RepoDatabaseRead::new(&db_path)
+            .unwrap()
+            .read_album(album_id)
+            .unwrap()
+            .map(|album| album.title_raw().to_string())
+    };
+    let plain = Uuid::from_str("7b1d3a8e-52c4-4f0e-9a0c-1a2b3c4d5e01").unwrap();
+    let limited = Uuid::from_str("7b1d3a8e-52c4-4f0e-9a0c-1a2b3c4d5e02").unwrap();
+
+    // database is built from scratch
+    assert_eq!(build(), [plain, limited]);
+    // nothing changed
+    assert_eq!(build(), Vec::<Uuid>::new());
+
+    // only the changed album is rewritten
+    let limited_path = repo.join("album/limited.toml");
+    let text = std::fs::read_to_string(&limited_path).unwrap();
+    std::fs::write(
+        &limited_path,
+        text.replacen("title = \"Title\"", "title = \"New Title\"", 1),
+    )
+    .unwrap();
+    assert_eq!(build(), [limited]);
+    assert_eq!(title(limited).as_deref(), Some("New Title"));
+    assert_eq!(title(plain).as_deref(), Some("Title"));
+
+    // removed album
+    std::fs::remove_file(repo.join("album/plain.toml")).unwrap();
+    assert_eq!(build(), [plain]);
+    assert_eq!(title(plain), None);
+
+    // added album
+    std::fs::write(
+        repo.join("album/plain.toml"),
+        std::fs::read_to_string("tests/repos/editions/album/plain.toml").unwrap(),
+    )
+    .unwrap();
+    assert_eq!(build(), [plain]);
+    assert_eq!(title(plain).as_deref(), Some("Title"));
+    assert_eq!(title(limited).as_deref(), Some("New Title"));
+}
+
+#[test]
+fn test_albums_by_date_range() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("repo.db");
+    RepositoryManager::new("tests/repos/dates")
+        .expect("Failed to load metadata repository")
+        .into_owned_manager()
+        .expect("Failed to convert to owned manager")
+        .to_database(&db_path)
+        .expect("Failed to write database");
+    let db = RepoDatabaseRead::new(&db_path).expect("Failed to open database");
+
+    let album = |i: u128| Uuid::from_u128(0x2c6e1a7d_9b3f_4d2e_8a5c_000000000000 + i);
+    let query = |from: &str, to: &str| {
+        db.albums_by_date_range(
+            &AnniDate::from_str(from).unwrap(),
+            &AnniDate::from_str(to).unwrap(),
+        )
+        .unwrap()
+    };
+
+    // both bounds are inclusive
+    assert_eq!(query("2020-01-01", "2020-12-31"), [album(1), album(2)]);
+    assert_eq!(query("2020-01-02", "2020-12-30"), Vec::<Uuid>::new());
+    // album released in 2019 overlaps with the range
+    assert_eq!(query("2019-12-31", "2020-01-01"), [album(4), album(1)]);
+    // album released in 2021-06
+    assert_eq!(query("2021-06-30", "2021-07-01"), [album(3)]);
+    assert_eq!(query("2021-07-01", "2022-03-14"), Vec::<Uuid>::new());
+    // partial bounds cover the whole period
+    assert_eq!(query("2022", "2022"), [album(5)]);
+    assert_eq!(query("2020-12", "2021-06"), [album(2), album(3)]);
+}
+
+#[test]
+fn test_tag_aliases_in_database() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("repo.db");
+    RepositoryManager::new("tests/repos/tag-aliases")
+        .expect("Failed to load metadata repository")
+        .into_owned_manager()
+        .expect("Failed to load tag aliases")
+        .to_database(&db_path)
+        .expect("Failed to write database");
+
+    let db = RepoDatabaseRead::new(&db_path).expect("Failed to open database");
+    let album = Uuid::from_str("5a1f0c2e-3b4d-4e6f-8a9b-0c1d2e3f4a5b").unwrap();
+    let live = Uuid::from_str("5a1f0c2e-3b4d-4e6f-8a9b-0c1d2e3f4a5c").unwrap();
+    let query = |tag, recursive| {
+        let mut albums: Vec<_> = db
+            .get_albums_by_tag(tag, recursive)
+            .unwrap()
+            .into_iter()
+            .map(|album| album.album_id.0)
+            .collect();
+        albums.sort();
+        albums
+    };
+
+    // album tagged by alias is stored with the canonical tag
+    assert_eq!(query("水樹奈々", false), [album]);
+    assert!(query("Nana Mizuki", false).is_empty());
+    // tag included by alias is a child of the canonical tag
+    assert_eq!(query("水樹奈々", true), [album, live]);
+}
@@ -0,0 +1,105 @@
+use anni_repo::dedupe::{DedupeOptions, DuplicateTrack};
+use anni_repo::RepositoryManager;
+use std::path::Path;
+use uuid::Uuid;
+
+const ALBUM_ID: &str = "1b5e2f3a-6c1d-4e8f-9a2b-3c4d5e6f7a01";
+const REIMPORTED_ALBUM_ID: &str = "1b5e2f3a-6c1d-4e8f-9a2b-3c4d5e6f7a02";
+
+mod common;
+
+fn album(album_id: &str, catalog: &str, tracks: &[&str]) -> String {
+    common::album(album_id, catalog, catalog, &[], tracks)
+}
+
+fn create_repo(root: &Path) {
+    common::create_repo(root);
+    common::write_album(
+        root,
+        "original",
+        &album(ALBUM_ID, "TEST-0001", &["Opening", "Main Theme"]),
+    );
+    // the same disc imported again under another catalog
+    common::write_album(
+        root,
+        "reimported",
+        &album(
+            REIMPORTED_ALBUM_ID,
+            "TEST-0003",
+            &["opening", "Main  Theme"],
+        ),
+    );
+    common::write_album(
+        root,
+        "distinct",
+        &album(
+            "1b5e2f3a-6c1d-4e8f-9a2b-3c4d5e6f7a03",
+            "TEST-0002",
+            // repeated in the same album only
+            &["Ending", "Ending"],
+        ),
+    );
+}
+
+fn track(album_id: &str, track_id: u32) -> DuplicateTrack {
+    DuplicateTrack {
+        album_id: Uuid::parse_str(album_id).unwrap(),
+        disc_id: 1,
+        track_id,
+    }
+}
+
+#[test]
+fn test_find_duplicate_tracks() {
+    let root = tempfile::tempdir().unwrap();
+    create_repo(root.path());
+    let manager = RepositoryManager::new(root.path())
+        .unwrap()
+        .into_owned_manager()
+        .unwrap();
+
+    let duplicates = manager.find_duplicate_tracks(&DedupeOptions::default());
+    assert_eq!(duplicates.len(), 2);
+    assert_eq!(duplicates[0].title, "main theme");
+    assert_eq!(duplicates[0].artist, "artist");
+    assert_eq!(
+        duplicates[0].tracks,
+        vec![track(ALBUM_ID, 2), track(REIMPORTED_ALBUM_ID, 2)]
+    );
+    assert_eq!(duplicates[1].title, "opening");
+    assert_eq!(
+        duplicates[1].tracks,
+        vec![track(ALBUM_ID, 1), track(REIMPORTED_ALBUM_ID, 1)]
+    );
+    assert_eq!(
+        duplicates[1].album_ids(),
+        vec![
+            Uuid::parse_str(ALBUM_ID).unwrap(),
+            Uuid::parse_str(REIMPORTED_ALBUM_ID).unwrap()
+        ]
+    );
+}
+
+#[test]
+fn test_find_duplicate_tracks_strictly() {
+    let root = tempfile::tempdir().unwrap();
+    create_repo(root.path());
+    let manager = RepositoryManager::new(root.path())
+        .unwrap()
+        .into_owned_manager()
+        .unwrap();
+
+    let case_sensitive = DedupeOptions {
+        ignore_case: false,
+        ..Default::default()
+    };
+    let duplicates = manager.find_duplicate_tracks(&case_sensitive);
+    assert_eq!(duplicates.len(), 1);
+    assert_eq!(duplicates[0].title, "Main Theme");
+
+    let exact = DedupeOptions {
+        ignore_case: false,
+        collapse_whitespace: false,
+    };
+    assert!(manager.find_duplicate_tracks(&exact).is_empty());
+}
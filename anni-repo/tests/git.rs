@@ -0,0 +1,118 @@
+#![cfg(feature = "git")]
+
+use anni_metadata::model::Album;
+use anni_repo::RepositoryManager;
+use std::path::Path;
+use std::str::FromStr;
+
+mod common;
+
+fn album() -> Album {
+    Album::from_str(&common::album(
+        "2b5e2f3a-6c1d-4e8f-9a2b-3c4d5e6f7a01",
+        "Committed",
+        "TEST-0001",
+        &[],
+        &["Track 1"],
+    ))
+    .unwrap()
+}
+
+fn init_git(root: &Path) -> git2::Repository {
+    let repo = git2::Repository::init(root).unwrap();
+    let mut config = repo.config().unwrap();
+    config.set_str("user.name", "Anni").unwrap();
+    config.set_str("user.email", "anni@example.com").unwrap();
+    repo
+}
+
+#[test]
+fn test_commit_and_push_changes() {
+    let root = tempfile::tempdir().unwrap();
+    common::create_repo(root.path());
+    let git = init_git(root.path());
+    let remote = tempfile::tempdir().unwrap();
+    let remote_git = git2::Repository::init_bare(remote.path()).unwrap();
+    git.remote("origin", remote.path().to_str().unwrap())
+        .unwrap();
+
+    let manager = RepositoryManager::new(root.path()).unwrap();
+    assert!(manager
+        .commit_changes(&["repo.toml"], "Initial commit")
+        .unwrap());
+    manager.add_album(album(), false).unwrap();
+    let paths = manager.album_paths("TEST-0001").unwrap();
+    assert!(manager
+        .commit_changes(&paths, "Add TEST-0001 Committed")
+        .unwrap());
+
+    let head = git.head().unwrap().peel_to_commit().unwrap();
+    assert_eq!(head.message(), Some("Add TEST-0001 Committed"));
+    assert_eq!(head.parent_count(), 1);
+    let tree = head.tree().unwrap();
+    assert!(tree.get_path(Path::new("repo.toml")).is_ok());
+    assert!(tree.get_path(Path::new("album/TEST-0001.toml")).is_ok());
+
+    // nothing changed
+    assert!(!manager.commit_changes(&paths, "Empty commit").unwrap());
+
+    let branch = git.head().unwrap().shorthand().unwrap().to_string();
+    assert!(manager.push(&branch).unwrap());
+    let pushed = remote_git
+        .find_reference(&format!("refs/heads/{branch}"))
+        .unwrap();
+    assert_eq!(pushed.target(), Some(head.id()));
+}
+
+#[test]
+fn test_commit_outside_git_worktree() {
+    let root = tempfile::tempdir().unwrap();
+    common::create_repo(root.path());
+
+    let manager = RepositoryManager::new(root.path()).unwrap();
+    manager.add_album(album(), false).unwrap();
+    let paths = manager.album_paths("TEST-0001").unwrap();
+    assert!(!manager
+        .commit_changes(&paths, "Add TEST-0001 Committed")
+        .unwrap());
+    assert!(!manager.push("master").unwrap());
+    assert!(root.path().join("album/TEST-0001.toml").exists());
+}
+
+#[test]
+fn test_commit_only_given_paths() {
+    let root = tempfile::tempdir().unwrap();
+    common::create_repo(root.path());
+    let git = init_git(root.path());
+    let manager = RepositoryManager::new(root.path()).unwrap();
+    assert!(manager
+        .commit_changes(&["repo.toml"], "Initial commit")
+        .unwrap());
+
+    // unrelated changes, one of which is staged
+    std::fs::write(root.path().join("repo.toml"), "# modified\n").unwrap();
+    common::write_album(root.path(), "staged", "# staged\n");
+    let mut index = git.index().unwrap();
+    index.add_path(Path::new("album/staged.toml")).unwrap();
+    index.write().unwrap();
+
+    manager.add_album(album(), false).unwrap();
+    let paths = manager.album_paths("TEST-0001").unwrap();
+    assert!(manager
+        .commit_changes(&paths, "Add TEST-0001 Committed")
+        .unwrap());
+
+    let head = git.head().unwrap().peel_to_commit().unwrap();
+    let tree = head.tree().unwrap();
+    assert!(tree.get_path(Path::new("album/TEST-0001.toml")).is_ok());
+    assert!(tree.get_path(Path::new("album/staged.toml")).is_err());
+    let parent = head.parent(0).unwrap().tree().unwrap();
+    assert_eq!(
+        tree.get_path(Path::new("repo.toml")).unwrap().id(),
+        parent.get_path(Path::new("repo.toml")).unwrap().id()
+    );
+
+    // staged changes are kept staged
+    let index = git.index().unwrap();
+    assert!(index.get_path(Path::new("album/staged.toml"), 0).is_some());
+}
@@ -0,0 +1,54 @@
+#![cfg(feature = "json")]
+
+use anni_metadata::model::{Album, TagRef, TagType};
+use anni_repo::{
+    models::{JsonAlbum, JsonTagRelation, JsonTags},
+    RepositoryManager,
+};
+use std::fs::File;
+
+#[test]
+fn test_export_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = RepositoryManager::new("tests/repos/json")
+        .expect("Failed to load metadata repository")
+        .into_owned_manager()
+        .expect("Failed to convert to owned manager");
+    manager
+        .to_json(dir.path())
+        .expect("Failed to export repository");
+
+    let albums: Vec<JsonAlbum> =
+        serde_json::from_reader(File::open(dir.path().join("albums.json")).unwrap()).unwrap();
+    let albums: Vec<Album> = albums
+        .into_iter()
+        .map(|album| Album::try_from(album).unwrap())
+        .collect();
+    assert_eq!(albums.len(), 1);
+    let album = &albums[0];
+    let expected = manager.album(&album.album_id).unwrap();
+    assert_eq!(
+        toml::to_string(album).unwrap(),
+        toml::to_string(expected).unwrap()
+    );
+
+    let JsonTags { tags, relations } =
+        serde_json::from_reader(File::open(dir.path().join("tags.json")).unwrap()).unwrap();
+    assert_eq!(
+        tags.iter().map(|tag| tag.to_string()).collect::<Vec<_>>(),
+        ["group:Group", "artist:Member", "artist:Solo"]
+    );
+    for tag in tags.iter() {
+        assert_eq!(Some(tag), manager.tag(tag.as_ref()));
+    }
+    assert_eq!(
+        relations,
+        [JsonTagRelation {
+            parent: TagRef::new("Group", TagType::Group),
+            children: vec![
+                TagRef::new("Member", TagType::Artist),
+                TagRef::new("Solo", TagType::Artist),
+            ],
+        }]
+    );
+}
@@ -0,0 +1,85 @@
+use anni_repo::migrate::{SearchField, UnresolvedField, UnresolvedReason};
+use anni_repo::RepositoryManager;
+use std::fs;
+use std::path::Path;
+
+mod common;
+
+const NORMAL_ALBUM: &str = r#"[album]
+album_id = "5e7d2f4c-0c6a-4bb1-9e0d-5e5b5f4f7a11"
+title = "Normal"
+artist = "Artist"
+date = 2999-12-31
+type = "normal"
+# full-width catalog
+catalog = "ＬＡＣＡ－１２３４"
+edition = " Limited "
+
+[[discs]]
+catalog = "laca–1234"
+
+[[discs.tracks]]
+title = "Track 1"
+type = "normal"
+"#;
+
+const MISSING_CATALOG_ALBUM: &str = r#"[album]
+album_id = "9b0c1c55-2f7e-4b7b-8a8e-0b7d7f1f9c22"
+title = "Missing"
+artist = "Artist"
+date = 2999-12-31
+type = "normal"
+
+[[discs]]
+catalog = "TEST-0002"
+
+[[discs.tracks]]
+title = "Track 1"
+type = "normal"
+"#;
+
+fn create_repo(root: &Path) {
+    common::create_repo(root);
+    common::write_album(root, "normal", NORMAL_ALBUM);
+    common::write_album(root, "missing", MISSING_CATALOG_ALBUM);
+}
+
+#[test]
+fn test_migrate_search_fields() {
+    let root = tempfile::tempdir().unwrap();
+    let root = root.path();
+    create_repo(root);
+    let normal = root.join("album/normal.toml");
+    let missing = root.join("album/missing.toml");
+
+    let manager = RepositoryManager::new(root).expect("Failed to load metadata repository");
+    let missing_catalog = UnresolvedField {
+        path: missing.clone(),
+        field: Some(SearchField::AlbumCatalog),
+        reason: UnresolvedReason::Missing,
+    };
+
+    // dry run does not touch album files
+    let report = manager.migrate_search_fields(true).unwrap();
+    assert_eq!(report.migrated, vec![normal.clone()]);
+    assert_eq!(report.unresolved, vec![missing_catalog.clone()]);
+    assert_eq!(fs::read_to_string(&normal).unwrap(), NORMAL_ALBUM);
+
+    let report = manager.migrate_search_fields(false).unwrap();
+    assert_eq!(report.migrated, vec![normal.clone()]);
+    assert_eq!(report.unresolved, vec![missing_catalog.clone()]);
+    assert_eq!(
+        fs::read_to_string(&normal).unwrap(),
+        NORMAL_ALBUM
+            .replace("ＬＡＣＡ－１２３４", "LACA-1234")
+            .replace("laca–1234", "laca-1234")
+            .replace("\" Limited \"", "\"Limited\"")
+    );
+    // album with missing catalog is flagged, not filled with an empty catalog
+    assert_eq!(fs::read_to_string(&missing).unwrap(), MISSING_CATALOG_ALBUM);
+
+    // migration is idempotent
+    let report = manager.migrate_search_fields(false).unwrap();
+    assert!(report.migrated.is_empty());
+    assert_eq!(report.unresolved, vec![missing_catalog]);
+}
@@ -1,6 +1,10 @@
-use anni_metadata::model::{TagRef, TagType, TrackType};
-use anni_repo::{error::Error, prelude::*, RepositoryManager};
+use anni_metadata::model::{Album, TagRef, TagType, Tags, TrackType};
+use anni_repo::{error::Error, prelude::*, OwnedRepositoryManager, RepositoryManager};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use uuid::Uuid;
+
+mod common;
 
 fn repo_from_str() -> Repository {
     Repository::from_str(
@@ -24,6 +28,21 @@ albums = ["album"]
     );
 }
 
+#[test]
+fn test_catalog_pattern() {
+    assert_eq!(repo_from_str().catalog_pattern(), DEFAULT_CATALOG_PATTERN);
+
+    let repo = Repository::from_str(
+        r#"[repo]
+name = "Yesterday17's Metadata Repo"
+edition = "1.3"
+catalog_pattern = '^TEST-\d+$'
+"#,
+    )
+    .expect("Failed to parse toml");
+    assert_eq!(repo.catalog_pattern(), r"^TEST-\d+$");
+}
+
 #[test]
 fn test_empty_repository() {
     let manager =
@@ -141,3 +160,336 @@ fn test_repo_album_tags() {
     assert_eq!(tracks[0].artist(), "Artist1");
     assert_eq!(tracks[0].track_type(), &TrackType::Absolute);
 }
+
+#[test]
+fn test_tag_aliases() {
+    let manager = RepositoryManager::new("tests/repos/tag-aliases")
+        .expect("Failed to load metadata repository")
+        .into_owned_manager()
+        .expect("Failed to load tag aliases");
+
+    let canonical = TagRef::new("水樹奈々", TagType::Artist);
+    for name in ["水樹奈々", "Mizuki Nana", "Nana Mizuki"] {
+        let tag = manager
+            .tag(&TagRef::new(name, TagType::Artist))
+            .expect("Tag alias is not resolved");
+        assert_eq!(tag.as_ref(), &canonical);
+    }
+    // aliases are bound to tag type
+    assert!(manager
+        .tag(&TagRef::new("Mizuki Nana", TagType::Group))
+        .is_none());
+
+    // album tagged by alias can be found by both names
+    let album_id = Uuid::from_str("5a1f0c2e-3b4d-4e6f-8a9b-0c1d2e3f4a5b").unwrap();
+    assert_eq!(manager.albums_tagged_by(&canonical), Some(&vec![album_id]));
+    let alias = TagRef::new("Mizuki Nana", TagType::Artist);
+    assert_eq!(manager.albums_tagged_by(&alias), Some(&vec![album_id]));
+
+    // tag included by alias is a child of the canonical tag
+    let live = TagRef::new("NANA MIZUKI LIVE", TagType::Series);
+    assert!(manager.child_tags(&canonical).contains(&live));
+}
+
+#[test]
+fn test_tag_loop() {
+    let manager = RepositoryManager::new("tests/repos/tag-loop")
+        .expect("Failed to load metadata repository")
+        .into_owned_manager()
+        .expect("Failed to load tags");
+
+    let group = |name| TagRef::new(name, TagType::Group);
+    // tags leading to the loop are not included
+    let path = manager.check_tags_loop().expect("Tag loop is not detected");
+    assert_eq!(
+        path,
+        vec![
+            &group("Loop 1"),
+            &group("Loop 2"),
+            &group("Loop 3"),
+            &group("Loop 1"),
+        ]
+    );
+    assert_eq!(manager.all_tag_loops(), vec![path]);
+}
+
+#[test]
+fn test_disjoint_tag_loops() {
+    let manager = RepositoryManager::new("tests/repos/tag-loops")
+        .expect("Failed to load metadata repository")
+        .into_owned_manager()
+        .expect("Failed to load tags");
+
+    let group = |name| TagRef::new(name, TagType::Group);
+    assert_eq!(
+        manager.all_tag_loops(),
+        vec![
+            vec![&group("A1"), &group("A2"), &group("A1")],
+            vec![&group("B1"), &group("B2"), &group("B3"), &group("B1")],
+        ]
+    );
+    assert!(manager.check_tags_loop().is_some());
+}
+
+#[test]
+fn test_tag_alias_conflict() {
+    let result = RepositoryManager::new("tests/repos/tag-alias-conflict")
+        .expect("Failed to load metadata repository")
+        .into_owned_manager();
+    match result {
+        Err(Error::RepoTagAliasConflict {
+            alias,
+            first,
+            second,
+        }) => {
+            assert_eq!(alias, TagRef::new("Shared", TagType::Artist));
+            let mut tags = [first, second];
+            tags.sort_by(|a, b| a.name().cmp(b.name()));
+            assert_eq!(
+                tags,
+                [
+                    TagRef::new("Tag A", TagType::Artist),
+                    TagRef::new("Tag B", TagType::Artist),
+                ]
+            );
+        }
+        Err(e) => panic!("Unexpected error: {e}"),
+        Ok(_) => panic!("Conflicting tag aliases should not be allowed."),
+    }
+}
+
+#[test]
+fn test_repo_stats() {
+    let manager = RepositoryManager::new("tests/repos/stats")
+        .expect("Failed to load metadata repository")
+        .into_owned_manager()
+        .expect("Failed to load repository");
+    let stats = manager.stats();
+
+    assert_eq!(stats.albums, 2);
+    assert_eq!(stats.discs, 3);
+    assert_eq!(stats.tracks, 4);
+    // A, B and C
+    assert_eq!(stats.artists, 3);
+    assert_eq!(stats.tags, 3);
+    assert_eq!(
+        stats
+            .tag_usage
+            .iter()
+            .map(|usage| (usage.tag.as_str(), usage.albums))
+            .collect::<Vec<_>>(),
+        [("artist:B", 1), ("group:G", 1), ("artist:A", 0)]
+    );
+    assert_eq!(
+        stats.untagged_albums,
+        [Uuid::from_str("7d3b9e21-4c5a-4f6e-9b8d-000000000002").unwrap()]
+    );
+    assert_eq!(stats.earliest_release.as_deref(), Some("2019"));
+    assert_eq!(stats.latest_release.as_deref(), Some("2020-01-01"));
+    assert_eq!(stats.albums_missing_cover, None);
+}
+
+#[test]
+fn test_repo_stats_missing_cover() {
+    let manager = RepositoryManager::new("tests/repos/stats")
+        .expect("Failed to load metadata repository")
+        .into_owned_manager()
+        .expect("Failed to load repository");
+    let with_cover = Uuid::from_str("7d3b9e21-4c5a-4f6e-9b8d-000000000001").unwrap();
+    let without_cover = Uuid::from_str("7d3b9e21-4c5a-4f6e-9b8d-000000000002").unwrap();
+
+    let mut stats = manager.stats();
+    stats.check_covers(&manager, |album_id| album_id == with_cover);
+    assert_eq!(stats.albums_missing_cover, Some(vec![without_cover]));
+}
+
+#[test]
+fn test_load_generated_albums() {
+    let dir = tempfile::tempdir().unwrap();
+    common::generate_repo(dir.path(), 1000);
+    let manager = RepositoryManager::new(dir.path())
+        .expect("Failed to load metadata repository")
+        .into_owned_manager()
+        .expect("Failed to load generated repository");
+
+    assert_eq!(manager.albums().len(), 1000);
+    for i in 0..1000 {
+        let album_id = common::generated_album_id(i);
+        let path = Path::new("album").join(format!("TEST-{i:04}.toml"));
+        assert_eq!(manager.album_path(&album_id), Some(path.as_path()));
+
+        let mut expected =
+            Album::from_str(&std::fs::read_to_string(dir.path().join(&path)).unwrap()).unwrap();
+        let mut album = manager.album(&album_id).unwrap().clone();
+        assert_eq!(album.format_to_string(), expected.format_to_string());
+    }
+
+    for artist in 0..10 {
+        let tag = TagRef::new(format!("Artist {artist}"), TagType::Artist);
+        let mut albums = manager.albums_tagged_by(&tag).unwrap().clone();
+        albums.sort();
+        let expected: Vec<_> = (artist..1000)
+            .step_by(10)
+            .map(common::generated_album_id)
+            .collect();
+        assert_eq!(albums, expected);
+    }
+}
+
+#[test]
+fn test_load_generated_albums_problems() {
+    let dir = tempfile::tempdir().unwrap();
+    common::generate_repo(dir.path(), 1000);
+    // duplicated album id
+    let album = common::generated_album(
+        1000,
+        &common::generated_album_id(0).to_string(),
+        "artist:Artist 0",
+    );
+    common::write_album(dir.path(), "TEST-1000", &album);
+    // orphan tags
+    for i in 1001..1003 {
+        let album = common::generated_album(
+            i,
+            &common::generated_album_id(i).to_string(),
+            "artist:Orphan",
+        );
+        common::write_album(dir.path(), &format!("TEST-{i:04}"), &album);
+    }
+
+    let result = RepositoryManager::new(dir.path())
+        .expect("Failed to load metadata repository")
+        .into_owned_manager();
+    match result {
+        Err(Error::MultipleErrors(problems)) => {
+            assert_eq!(problems.len(), 3);
+            let duplicated = problems
+                .iter()
+                .filter(|e| matches!(e, Error::RepoDuplicatedAlbumId(_)))
+                .count();
+            let orphan = problems
+                .iter()
+                .filter(|e| matches!(e, Error::RepoTagsUndefined(_)))
+                .count();
+            assert_eq!((duplicated, orphan), (1, 2));
+        }
+        Err(e) => panic!("Unexpected error: {e}"),
+        Ok(_) => panic!("Problems in albums should be reported."),
+    }
+}
+
+fn memory_repo(albums: Vec<(PathBuf, Album)>) -> RepoResult<OwnedRepositoryManager> {
+    let repo =
+        Repository::from_str("[repo]\nname = \"Memory repo\"\nedition = \"1.0+alpha.1.5.1\"\n")
+            .unwrap();
+    let tags = toml::from_str::<Tags>(
+        r#"[[tag]]
+name = "Loop 1"
+type = "group"
+included-by = ["group:Loop 2"]
+
+[[tag]]
+name = "Loop 2"
+type = "group"
+included-by = ["group:Loop 1"]
+"#,
+    )
+    .unwrap()
+    .into_inner();
+    OwnedRepositoryManager::from_memory(
+        repo,
+        albums,
+        vec![(PathBuf::from("tag/default.toml"), tags)],
+    )
+}
+
+#[test]
+fn test_memory_repo() {
+    let album_id = common::generated_album_id(0);
+    let album = Album::from_str(&common::generated_album(
+        0,
+        &album_id.to_string(),
+        "group:Loop 1",
+    ))
+    .unwrap();
+    let manager = memory_repo(vec![(PathBuf::from("album/TEST-0000.toml"), album)])
+        .expect("Failed to load repository in memory");
+
+    assert_eq!(manager.repo.name(), "Memory repo");
+    assert_eq!(
+        manager.album_path(&album_id),
+        Some(Path::new("album/TEST-0000.toml"))
+    );
+    let loop_1 = TagRef::new("Loop 1", TagType::Group);
+    assert_eq!(manager.albums_tagged_by(&loop_1), Some(&vec![album_id]));
+    assert_eq!(
+        manager.tag_path(&loop_1),
+        Some(&PathBuf::from("tag/default.toml"))
+    );
+
+    let path = manager.check_tags_loop().expect("Tag loop is not detected");
+    assert_eq!(path.len(), 3);
+    assert_eq!(path[0], &loop_1);
+
+    // files are neither read nor written relative to working directory
+    let album = manager.album(&album_id).unwrap().clone();
+    assert!(matches!(
+        manager.repo.add_album(album, false),
+        Err(Error::RepoInMemory)
+    ));
+    assert!(matches!(
+        manager.repo.album_paths("TEST-0000"),
+        Err(Error::RepoInMemory)
+    ));
+    assert!(matches!(
+        manager.add_album_tag(&loop_1, "TEST-"),
+        Err(Error::RepoInMemory)
+    ));
+}
+
+#[test]
+fn test_memory_repo_problems() {
+    let album = |index: usize, tag| {
+        let album_id = common::generated_album_id(index).to_string();
+        let album = Album::from_str(&common::generated_album(index, &album_id, tag)).unwrap();
+        (PathBuf::from(format!("album/TEST-{index:04}.toml")), album)
+    };
+
+    // albums are validated the same way as albums on disk
+    let result = memory_repo(vec![album(0, "group:Loop 1"), album(1, "artist:Orphan")]);
+    match result {
+        Err(Error::MultipleErrors(problems)) => {
+            assert_eq!(problems.len(), 1);
+            assert!(matches!(problems[0], Error::RepoTagsUndefined(_)));
+        }
+        Err(e) => panic!("Unexpected error: {e}"),
+        Ok(_) => panic!("Orphan tags should be reported."),
+    }
+}
+
+#[test]
+fn test_all_album_paths_iter() {
+    let manager = RepositoryManager::new("tests/repos/album-layout")
+        .expect("Failed to load metadata repository");
+
+    let mut paths = manager
+        .all_album_paths_iter()
+        .collect::<RepoResult<Vec<_>>>()
+        .expect("Failed to iterate album paths");
+    paths.sort();
+    let root = Path::new("tests/repos/album-layout/album");
+    assert_eq!(
+        paths,
+        [
+            root.join("TEST-0001.toml"),
+            root.join("TEST-0002/TEST-0002.0.toml"),
+            root.join("TEST-0002/TEST-0002.1.toml"),
+        ]
+    );
+
+    let mut collected = manager
+        .all_album_paths()
+        .expect("Failed to get album paths");
+    collected.sort();
+    assert_eq!(paths, collected);
+}
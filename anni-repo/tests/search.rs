@@ -0,0 +1,82 @@
+#![cfg(feature = "search")]
+
+use anni_repo::error::Error;
+use anni_repo::search::tantivy::collector::TopDocs;
+use anni_repo::search::tantivy::tokenizer::TokenStream;
+use anni_repo::search::{RepositorySearchManager, TokenizerConfig};
+use anni_repo::RepositoryManager;
+use uuid::Uuid;
+
+const TERM: &str = "東京スカイツリー";
+
+fn tokenize(manager: &RepositorySearchManager, text: &str) -> Vec<String> {
+    let mut analyzer = manager.index.tokenizers().get("lang_ja").unwrap();
+    let mut stream = analyzer.token_stream(text);
+    let mut tokens = Vec::new();
+    while stream.advance() {
+        tokens.push(stream.token().text.clone());
+    }
+    tokens
+}
+
+#[test]
+fn test_default_dictionary_splits_term() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = RepositorySearchManager::create(dir.path()).unwrap();
+    assert!(tokenize(&manager, TERM).len() > 1);
+}
+
+#[test]
+fn test_user_dictionary() {
+    let dir = tempfile::tempdir().unwrap();
+    let tokenizer = TokenizerConfig {
+        user_dictionary: Some("tests/fixtures/search/userdic.csv".into()),
+    };
+    let manager = RepositorySearchManager::create_with_tokenizer(dir.path(), &tokenizer).unwrap();
+    assert_eq!(tokenize(&manager, TERM), [TERM]);
+
+    let album_id = Uuid::new_v4();
+    let mut writer = manager.index.writer(15_000_000).unwrap();
+    writer
+        .add_document(manager.build_document(&format!("{TERM}の歌"), "Artist", &album_id, 1, 2))
+        .unwrap();
+    writer
+        .add_document(manager.build_document("東京の空", "Artist", &album_id, 1, 3))
+        .unwrap();
+    writer.commit().unwrap();
+
+    // reopen with the same dictionary
+    drop(manager);
+    let manager = RepositorySearchManager::open_with_tokenizer(dir.path(), &tokenizer).unwrap();
+    let searcher = manager.index.reader().unwrap().searcher();
+    let query = manager.build_query_parser().parse_query(TERM).unwrap();
+    let results = searcher.search(&query, &TopDocs::with_limit(10)).unwrap();
+    assert_eq!(results.len(), 1);
+
+    let track = manager.deserialize_document(searcher.doc(results[0].1).unwrap());
+    assert_eq!(track.album_id, album_id);
+    assert_eq!(track.track_id, 2);
+}
+
+#[test]
+fn test_invalid_user_dictionary() {
+    let dir = tempfile::tempdir().unwrap();
+    let tokenizer = TokenizerConfig {
+        user_dictionary: Some("tests/fixtures/search/not-exist.csv".into()),
+    };
+    assert!(RepositorySearchManager::create_with_tokenizer(dir.path(), &tokenizer).is_err());
+}
+
+#[test]
+fn test_build_with_invalid_user_dictionary() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = RepositoryManager::new("tests/repos/editions")
+        .unwrap()
+        .into_owned_manager()
+        .unwrap();
+    let tokenizer = TokenizerConfig {
+        user_dictionary: Some("tests/fixtures/search/not-exist.csv".into()),
+    };
+    let result = manager.build_search_index_with_tokenizer(dir.path(), &tokenizer);
+    assert!(matches!(result, Err(Error::SearchError(_))));
+}
@@ -0,0 +1,128 @@
+use anni_metadata::model::{TagRef, TagType};
+use anni_repo::prelude::Error;
+use anni_repo::RepositoryManager;
+use std::fs;
+use std::path::Path;
+
+mod common;
+
+fn tagged_album() -> String {
+    common::album(
+        "0b5e2f3a-6c1d-4e8f-9a2b-3c4d5e6f7a01",
+        "Series 1",
+        "SERIES-0001",
+        &["artist:Artist"],
+        &["Track 1"],
+    )
+}
+
+fn untagged_album() -> String {
+    common::album(
+        "0b5e2f3a-6c1d-4e8f-9a2b-3c4d5e6f7a02",
+        "Series 2",
+        "SERIES-0002",
+        &[],
+        &["Track 1"],
+    )
+}
+
+fn other_album() -> String {
+    common::album(
+        "0b5e2f3a-6c1d-4e8f-9a2b-3c4d5e6f7a03",
+        "Other",
+        "OTHER-0001",
+        &["artist:Artist"],
+        &["Track 1"],
+    )
+}
+
+const TAGS: &str = r#"[[tag]]
+name = "Artist"
+type = "artist"
+
+[[tag]]
+name = "Series"
+type = "group"
+"#;
+
+fn create_repo(root: &Path) {
+    common::create_repo(root);
+    common::write_album(root, "series-1", &tagged_album());
+    common::write_album(root, "series-2", &untagged_album());
+    common::write_album(root, "other", &other_album());
+    fs::write(root.join("tag/default.toml"), TAGS).unwrap();
+}
+
+#[test]
+fn test_tag_albums_by_catalog_prefix() {
+    let root = tempfile::tempdir().unwrap();
+    let root = root.path();
+    create_repo(root);
+    let series_1 = root.join("album/series-1.toml");
+    let series_2 = root.join("album/series-2.toml");
+    let other = root.join("album/other.toml");
+    let series = TagRef::new("Series", TagType::Group);
+
+    let manager = RepositoryManager::new(root)
+        .unwrap()
+        .into_owned_manager()
+        .unwrap();
+    let changed = manager.add_album_tag(&series, "SERIES-").unwrap();
+    assert_eq!(changed, vec![series_1.clone(), series_2.clone()]);
+    assert_eq!(
+        fs::read_to_string(&series_1).unwrap(),
+        tagged_album().replace(
+            r#"tags = ["artist:Artist"]"#,
+            r#"tags = ["artist:Artist", "group:Series"]"#
+        )
+    );
+    assert_eq!(
+        fs::read_to_string(&series_2).unwrap(),
+        untagged_album().replace(
+            "catalog = \"SERIES-0002\"\n\n",
+            "catalog = \"SERIES-0002\"\ntags = [\"group:Series\"]\n\n"
+        )
+    );
+    assert_eq!(fs::read_to_string(&other).unwrap(), other_album());
+
+    // tagging again changes nothing
+    assert!(manager
+        .add_album_tag(&series, "SERIES-")
+        .unwrap()
+        .is_empty());
+    drop(manager);
+
+    // repository is still valid, without orphan tags
+    let manager = RepositoryManager::new(root)
+        .unwrap()
+        .into_owned_manager()
+        .unwrap();
+    assert_eq!(manager.albums_tagged_by(&series).unwrap().len(), 2);
+
+    let changed = manager.remove_album_tag(&series, "SERIES-").unwrap();
+    assert_eq!(changed, vec![series_1.clone(), series_2.clone()]);
+    assert_eq!(fs::read_to_string(&series_1).unwrap(), tagged_album());
+    assert_eq!(fs::read_to_string(&series_2).unwrap(), untagged_album());
+}
+
+#[test]
+fn test_refuse_orphan_tag() {
+    let root = tempfile::tempdir().unwrap();
+    let root = root.path();
+    create_repo(root);
+
+    let manager = RepositoryManager::new(root)
+        .unwrap()
+        .into_owned_manager()
+        .unwrap();
+    let result = manager.add_album_tag(&TagRef::new("Missing", TagType::Group), "SERIES-");
+    assert!(matches!(result, Err(Error::RepoTagsUndefined(_))));
+    assert_eq!(
+        fs::read_to_string(root.join("album/series-1.toml")).unwrap(),
+        tagged_album()
+    );
+    assert_eq!(
+        fs::read_to_string(root.join("album/series-2.toml")).unwrap(),
+        untagged_album()
+    );
+}
@@ -0,0 +1,59 @@
+#![cfg(all(feature = "flac", feature = "audio-tags"))]
+
+use anni_repo::prelude::RepoTrack;
+use anni_repo::tags::{audio_extensions, read_audio_tags, ReplayGain};
+
+#[test]
+fn test_read_opus_tags() {
+    let tags = read_audio_tags("tests/fixtures/tags/test.opus").unwrap();
+    assert!(tags.has_tags());
+    assert_eq!(tags.title().as_deref(), Some("Opus Track"));
+    assert_eq!(tags.artist().as_deref(), Some("Opus Artist"));
+
+    let RepoTrack(track) = RepoTrack::from_tags(tags.as_ref());
+    assert_eq!(track.title, "Opus Track");
+    assert_eq!(track.artist.as_deref(), Some("Opus Artist"));
+}
+
+#[test]
+fn test_read_flac_tags() {
+    let tags = read_audio_tags("../assets/1s-full.flac").unwrap();
+    assert_eq!(tags.title().as_deref(), Some("TRACK ONE"));
+    assert_eq!(tags.artist().as_deref(), Some("TestArtist"));
+}
+
+#[test]
+fn test_read_replay_gain() {
+    let tags = read_audio_tags("tests/fixtures/tags/replay-gain.flac").unwrap();
+    assert_eq!(
+        tags.replay_gain(),
+        ReplayGain {
+            track_gain: Some(-8.21),
+            track_peak: Some(0.988525),
+            album_gain: Some(-7.5),
+            album_peak: Some(0.999969),
+        }
+    );
+
+    let RepoTrack(track) = RepoTrack::from_tags(tags.as_ref());
+    assert_eq!(track.gain, Some(-8.21));
+    assert_eq!(track.peak, Some(0.988525));
+
+    // files without ReplayGain tags leave the fields empty
+    let tags = read_audio_tags("../assets/1s-full.flac").unwrap();
+    assert_eq!(tags.replay_gain(), ReplayGain::default());
+}
+
+#[test]
+fn test_parse_replay_gain_value() {
+    assert_eq!(ReplayGain::parse_value("-8.21 dB"), Some(-8.21));
+    assert_eq!(ReplayGain::parse_value("+1.50dB"), Some(1.5));
+    assert_eq!(ReplayGain::parse_value(" 0.988525 "), Some(0.988525));
+    assert_eq!(ReplayGain::parse_value("loud"), None);
+}
+
+#[test]
+fn test_flac_preferred() {
+    assert_eq!(audio_extensions().first(), Some(&"flac"));
+    assert!(read_audio_tags("tests/fixtures/test-album.toml").is_err());
+}
@@ -0,0 +1,281 @@
+use crate::codec::wav::{WavDecoder, WavEncoder};
+use crate::codec::{
+    ApeCommandDecoder, Decoder, Encoder, FlacCommandDecoder, FlacCommandEncoder, TakCommandDecoder,
+    TtaCommandDecoder,
+};
+use crate::cue::cue_breakpoints;
+use crate::error::SplitError;
+use crate::split::{split_with_progress, SplitEvent};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Condvar, Mutex};
+use std::thread;
+
+/// An album to be split by [split_many].
+pub struct SplitJob {
+    /// Audio file to split. Format is detected by its extension, which can be `wav`, `flac`, `ape`, `tak` or `tta`.
+    pub input: PathBuf,
+    /// Content of the cue file.
+    pub cue: String,
+    /// Template of output paths.
+    ///
+    /// `{track}` is replaced with 2-digit track number, and `{title}` is replaced with track title.
+    /// Format is detected by its extension, which can be `wav` or `flac`.
+    pub output: String,
+}
+
+/// Progress reported by [split_many] after a track is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SplitProgress {
+    /// Index of the job in `jobs`.
+    pub job: usize,
+    /// Index of the finished track, starting from 0.
+    pub track: usize,
+    /// Number of tracks in the job.
+    pub tracks: usize,
+}
+
+/// Split multiple albums in parallel.
+///
+/// At most `concurrency` external en/decoder processes are running at the same time,
+/// and at most `concurrency` jobs are running if no external process is needed.
+/// If a job needs more processes than `concurrency`, its input is decoded to a temporary wave file
+/// next to the input before splitting, so that decoder and encoder do not run at the same time.
+/// `progress` is called on the current thread after each track is written.
+///
+/// Returns the result of each job, in the order of `jobs`.
+pub fn split_many(
+    jobs: Vec<SplitJob>,
+    concurrency: usize,
+    progress: impl Fn(SplitProgress),
+) -> Vec<Result<(), SplitError>> {
+    let concurrency = concurrency.max(1);
+    let workers = concurrency.min(jobs.len());
+    let jobs = &Mutex::new(jobs.into_iter().enumerate());
+    let permits = &Permits::new(concurrency);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|s| {
+        let workers: Vec<_> = (0..workers)
+            .map(|_| {
+                let sender = sender.clone();
+                s.spawn(move || {
+                    let mut results = Vec::new();
+                    loop {
+                        let next = jobs.lock().unwrap().next();
+                        let Some((index, job)) = next else {
+                            break;
+                        };
+                        results.push((index, job.split(index, &sender, permits, concurrency)));
+                    }
+                    results
+                })
+            })
+            .collect();
+
+        // receiver stops after all workers exit
+        drop(sender);
+        for p in receiver {
+            progress(p);
+        }
+
+        let mut results: Vec<_> = workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect();
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    })
+}
+
+impl SplitJob {
+    /// Number of external processes running at the same time when splitting.
+    fn processes(&self) -> usize {
+        let decoder = !has_extension(&self.input, "wav");
+        let encoder = !has_extension(&self.output, "wav");
+        decoder as usize + encoder as usize
+    }
+
+    fn split(
+        self,
+        job: usize,
+        sender: &Sender<SplitProgress>,
+        permits: &Permits,
+        concurrency: usize,
+    ) -> Result<(), SplitError> {
+        let processes = self.processes();
+        if processes <= concurrency {
+            let _permit = permits.acquire(processes);
+            return self.split_from(JobDecoder::new(&self.input)?, job, sender);
+        }
+
+        // decoder and encoder can not run at the same time, decode to a temporary file first
+        let decoder = JobDecoder::new(&self.input)?;
+        let directory = match self.input.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let mut decoded = tempfile::NamedTempFile::new_in(directory)?;
+        {
+            let _permit = permits.acquire(1);
+            std::io::copy(&mut decoder.decode()?, decoded.as_file_mut())?;
+        }
+
+        let _permit = permits.acquire(1);
+        self.split_from(JobDecoder::Wav(WavDecoder(decoded.path())), job, sender)
+    }
+
+    fn split_from(
+        &self,
+        input: JobDecoder<'_>,
+        job: usize,
+        sender: &Sender<SplitProgress>,
+    ) -> Result<(), SplitError> {
+        let (breakpoints, cue) = cue_breakpoints(&self.cue)?;
+        let breakpoints: Vec<_> = breakpoints.into_iter().collect();
+        let titles: Vec<_> = cue
+            .files
+            .iter()
+            .flat_map(|file| file.tracks.iter())
+            .map(|track| track.title.first().map_or("", String::as_str))
+            .collect();
+        let tracks = breakpoints.len() + 1;
+
+        split_with_progress(
+            input,
+            |index| {
+                let title = titles.get(index).copied().unwrap_or_default();
+                let output = self
+                    .output
+                    .replace("{track}", &format!("{:02}", index + 1))
+                    .replace("{title}", &title.replace('/', "／"));
+                JobEncoder::new(output)
+            },
+            breakpoints,
+            &mut |event| {
+                if let SplitEvent::TrackFinished { index } = event {
+                    // receiver only exits after all workers exit, so sending never fails
+                    let _ = sender.send(SplitProgress {
+                        job,
+                        track: index,
+                        tracks,
+                    });
+                }
+            },
+        )
+    }
+}
+
+fn has_extension<P: AsRef<Path>>(path: P, extension: &str) -> bool {
+    path.as_ref()
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+fn unsupported_format<P: AsRef<Path>>(path: P) -> SplitError {
+    SplitError::UnsupportedFormat(path.as_ref().display().to_string())
+}
+
+enum JobDecoder<'a> {
+    Wav(WavDecoder<&'a Path>),
+    Flac(FlacCommandDecoder<&'a Path>),
+    Ape(ApeCommandDecoder<&'a Path>),
+    Tak(TakCommandDecoder<&'a Path>),
+    Tta(TtaCommandDecoder<&'a Path>),
+}
+
+impl<'a> JobDecoder<'a> {
+    fn new(path: &'a Path) -> Result<Self, SplitError> {
+        let extension = path.extension().unwrap_or_default().to_ascii_lowercase();
+        Ok(match extension.to_str() {
+            Some("wav") => JobDecoder::Wav(WavDecoder(path)),
+            Some("flac") => JobDecoder::Flac(FlacCommandDecoder(path)),
+            Some("ape") => JobDecoder::Ape(ApeCommandDecoder(path)),
+            Some("tak") => JobDecoder::Tak(TakCommandDecoder(path)),
+            Some("tta") => JobDecoder::Tta(TtaCommandDecoder(path)),
+            _ => return Err(unsupported_format(path)),
+        })
+    }
+}
+
+impl Decoder for JobDecoder<'_> {
+    type Output = Box<dyn Read + Send>;
+
+    fn decode(self) -> Result<Self::Output, SplitError> {
+        Ok(match self {
+            JobDecoder::Wav(decoder) => Box::new(decoder.decode()?),
+            JobDecoder::Flac(decoder) => Box::new(decoder.decode()?),
+            JobDecoder::Ape(decoder) => Box::new(decoder.decode()?),
+            JobDecoder::Tak(decoder) => Box::new(decoder.decode()?),
+            JobDecoder::Tta(decoder) => Box::new(decoder.decode()?),
+        })
+    }
+}
+
+enum JobEncoder {
+    Wav(WavEncoder<String>),
+    Flac(FlacCommandEncoder<String>),
+}
+
+impl JobEncoder {
+    fn new(path: String) -> Result<Self, SplitError> {
+        if has_extension(&path, "wav") {
+            Ok(JobEncoder::Wav(WavEncoder(path)))
+        } else if has_extension(&path, "flac") {
+            Ok(JobEncoder::Flac(FlacCommandEncoder(path)))
+        } else {
+            Err(unsupported_format(path))
+        }
+    }
+}
+
+impl Encoder for JobEncoder {
+    fn encode(self, input: impl Read) -> Result<(), SplitError> {
+        match self {
+            JobEncoder::Wav(encoder) => encoder.encode(input),
+            JobEncoder::Flac(encoder) => encoder.encode(input),
+        }
+    }
+}
+
+/// Counting semaphore to limit the number of running external processes.
+struct Permits {
+    available: Mutex<usize>,
+    released: Condvar,
+}
+
+struct Permit<'a> {
+    permits: &'a Permits,
+    count: usize,
+}
+
+impl Permits {
+    fn new(count: usize) -> Self {
+        Self {
+            available: Mutex::new(count),
+            released: Condvar::new(),
+        }
+    }
+
+    fn acquire(&self, count: usize) -> Permit<'_> {
+        let mut available = self
+            .released
+            .wait_while(self.available.lock().unwrap(), |available| {
+                *available < count
+            })
+            .unwrap();
+        *available -= count;
+        Permit {
+            permits: self,
+            count,
+        }
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        *self.permits.available.lock().unwrap() += self.count;
+        self.permits.released.notify_all();
+    }
+}
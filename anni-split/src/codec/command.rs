@@ -115,8 +115,59 @@ macro_rules! command_decoder {
     };
 }
 
+/// Define an [Encoder] which spawns `$cmd` to encode WAVE to a file.
+///
+/// With `overridable`, command and arguments are stored in the encoder,
+/// which can be replaced by `command` and `args` methods. Create it with `with_args`.
 #[macro_export]
 macro_rules! command_encoder {
+    (overridable $(#[$meta: meta])* $name: ident, $cmd: expr) => {
+        $(#[$meta])*
+        pub struct $name<P: AsRef<std::path::Path>> {
+            path: P,
+            command: String,
+            args: Vec<String>,
+        }
+
+        impl<P: AsRef<std::path::Path>> $name<P> {
+            /// Encode to `path` with `args`. Use [FILE_PLACEHOLDER](crate::codec::command::FILE_PLACEHOLDER) to indicate the output path.
+            pub fn with_args<A, S>(path: P, args: A) -> Self
+            where
+                A: IntoIterator<Item = S>,
+                S: Into<String>,
+            {
+                Self {
+                    path,
+                    command: $cmd.to_string(),
+                    args: args.into_iter().map(Into::into).collect(),
+                }
+            }
+
+            #[doc = concat!("Use another executable instead of [", stringify!($cmd), "].")]
+            pub fn command<C: Into<String>>(mut self, command: C) -> Self {
+                self.command = command.into();
+                self
+            }
+
+            /// Replace all arguments. Use [FILE_PLACEHOLDER](crate::codec::command::FILE_PLACEHOLDER) to indicate the output path.
+            pub fn args<A, S>(mut self, args: A) -> Self
+            where
+                A: IntoIterator<Item = S>,
+                S: Into<String>,
+            {
+                self.args = args.into_iter().map(Into::into).collect();
+                self
+            }
+        }
+
+        impl<P: AsRef<std::path::Path>> $crate::codec::Encoder for $name<P> {
+            fn encode(self, input: impl std::io::Read) -> Result<(), $crate::error::SplitError> {
+                let codec =
+                    $crate::codec::command::CommandCodec::new(self.command, self.args, self.path)?;
+                $crate::codec::Encoder::encode(codec, input)
+            }
+        }
+    };
     ($name: ident, $cmd: expr, $args: expr) => {
         pub struct $name<P: AsRef<std::path::Path>>(pub P);
 
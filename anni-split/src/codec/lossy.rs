@@ -0,0 +1,94 @@
+use crate::codec::command::FILE_PLACEHOLDER;
+use crate::codec::{MP3_COMMAND, OPUS_COMMAND};
+use crate::command_encoder;
+use std::path::Path;
+
+command_encoder!(
+    overridable
+    /// [Encoder](crate::codec::Encoder) which encodes WAVE to Opus with [OPUS_COMMAND].
+    OpusCommandEncoder,
+    OPUS_COMMAND
+);
+
+impl<P: AsRef<Path>> OpusCommandEncoder<P> {
+    /// Encode to `path` with target `bitrate` in kbps.
+    pub fn new(path: P, bitrate: u32) -> Self {
+        Self::with_args(
+            path,
+            [
+                "--quiet".to_string(),
+                "--bitrate".to_string(),
+                bitrate.to_string(),
+                "-".to_string(),
+                FILE_PLACEHOLDER.to_string(),
+            ],
+        )
+    }
+}
+
+/// Bitrate mode of [Mp3CommandEncoder].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mp3Quality {
+    /// Constant bitrate in kbps.
+    Cbr(u32),
+    /// Variable bitrate quality, from 0 (best) to 9 (worst).
+    Vbr(u8),
+}
+
+command_encoder!(
+    overridable
+    /// [Encoder](crate::codec::Encoder) which encodes WAVE to MP3 with [MP3_COMMAND].
+    Mp3CommandEncoder,
+    MP3_COMMAND
+);
+
+impl<P: AsRef<Path>> Mp3CommandEncoder<P> {
+    /// Encode to `path` with `quality`.
+    pub fn new(path: P, quality: Mp3Quality) -> Self {
+        let mut args = vec!["--silent".to_string()];
+        match quality {
+            Mp3Quality::Cbr(bitrate) => {
+                args.extend(["--cbr".to_string(), "-b".to_string(), bitrate.to_string()])
+            }
+            Mp3Quality::Vbr(quality) => args.push(format!("-V{quality}")),
+        }
+        args.extend(["-".to_string(), FILE_PLACEHOLDER.to_string()]);
+        Self::with_args(path, args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::codec::command::FILE_PLACEHOLDER;
+    use crate::codec::lossy::{Mp3CommandEncoder, Mp3Quality, OpusCommandEncoder};
+
+    #[test]
+    fn test_opus_args() {
+        let encoder = OpusCommandEncoder::new("a.opus", 128);
+        assert_eq!(encoder.command, "opusenc");
+        assert_eq!(
+            encoder.args,
+            ["--quiet", "--bitrate", "128", "-", FILE_PLACEHOLDER]
+        );
+
+        let encoder =
+            encoder
+                .command("/opt/opus/bin/opusenc")
+                .args(["--vbr", "-", FILE_PLACEHOLDER]);
+        assert_eq!(encoder.command, "/opt/opus/bin/opusenc");
+        assert_eq!(encoder.args, ["--vbr", "-", FILE_PLACEHOLDER]);
+    }
+
+    #[test]
+    fn test_mp3_args() {
+        let encoder = Mp3CommandEncoder::new("a.mp3", Mp3Quality::Cbr(320));
+        assert_eq!(encoder.command, "lame");
+        assert_eq!(
+            encoder.args,
+            ["--silent", "--cbr", "-b", "320", "-", FILE_PLACEHOLDER]
+        );
+
+        let encoder = Mp3CommandEncoder::new("a.mp3", Mp3Quality::Vbr(2));
+        assert_eq!(encoder.args, ["--silent", "-V2", "-", FILE_PLACEHOLDER]);
+    }
+}
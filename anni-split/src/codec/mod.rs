@@ -1,6 +1,9 @@
 pub mod command;
+pub mod lossy;
 pub mod wav;
 
+pub use lossy::{Mp3CommandEncoder, Mp3Quality, OpusCommandEncoder};
+
 /// [Decoder] trait to decode from specified format to WAVE.
 pub trait Decoder {
     type Output: std::io::Read + Send;
@@ -17,17 +20,42 @@ pub trait Encoder: Sized {
 use crate::codec::command::FILE_PLACEHOLDER;
 use crate::{command_decoder, command_encoder};
 
-command_decoder!(FlacCommandDecoder, "flac", ["-c", "-d", FILE_PLACEHOLDER]);
+/// Name of the external command used by [FlacCommandDecoder] and [FlacCommandEncoder].
+pub const FLAC_COMMAND: &str = "flac";
+/// Name of the external command used by [ApeCommandDecoder].
+pub const APE_COMMAND: &str = "mac";
+/// Name of the external command used by [TakCommandDecoder].
+pub const TAK_COMMAND: &str = "takc";
+/// Name of the external command used by [TtaCommandDecoder].
+pub const TTA_COMMAND: &str = "ttaenc";
+/// Name of the external command used by [OpusCommandEncoder].
+pub const OPUS_COMMAND: &str = "opusenc";
+/// Name of the external command used by [Mp3CommandEncoder].
+pub const MP3_COMMAND: &str = "lame";
+
+command_decoder!(
+    FlacCommandDecoder,
+    FLAC_COMMAND,
+    ["-c", "-d", FILE_PLACEHOLDER]
+);
 command_encoder!(
     FlacCommandEncoder,
-    "flac",
+    FLAC_COMMAND,
     ["--totally-silent", "-", "-o", FILE_PLACEHOLDER]
 );
-command_decoder!(ApeCommandDecoder, "mac", [FILE_PLACEHOLDER, "-", "-d"]);
-command_decoder!(TakCommandDecoder, "takc", ["-d", FILE_PLACEHOLDER, "-"]);
+command_decoder!(
+    ApeCommandDecoder,
+    APE_COMMAND,
+    [FILE_PLACEHOLDER, "-", "-d"]
+);
+command_decoder!(
+    TakCommandDecoder,
+    TAK_COMMAND,
+    ["-d", FILE_PLACEHOLDER, "-"]
+);
 command_decoder!(
     TtaCommandDecoder,
-    "ttaenc",
+    TTA_COMMAND,
     ["-d", "-o", "-", FILE_PLACEHOLDER]
 );
 
@@ -2,6 +2,7 @@ use anni_common::decode::{token, u16_le, u32_le, DecodeError};
 use anni_common::encode::{btoken_w, u16_le_w, u32_le_w};
 use anni_common::traits::{Decode, Encode};
 use log::{debug, error};
+use std::fmt;
 use std::fs::File;
 use std::io::{Read, Write};
 use std::path::Path;
@@ -18,6 +19,24 @@ pub struct WaveHeader {
     pub data_size: u32,
 }
 
+/// Format of samples in a wave file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WavFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+}
+
+impl fmt::Display for WavFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} Hz, {} bits, {} channel(s)",
+            self.sample_rate, self.bits_per_sample, self.channels
+        )
+    }
+}
+
 impl Decode for WaveHeader {
     type Err = DecodeError;
 
@@ -96,6 +115,14 @@ impl Encode for WaveHeader {
 }
 
 impl WaveHeader {
+    pub fn format(&self) -> WavFormat {
+        WavFormat {
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            bits_per_sample: self.bit_per_sample,
+        }
+    }
+
     pub fn offset_from_second_frames(&self, s: u32, f: u32) -> u32 {
         let br = self.byte_rate;
         br * s + br * f / 75
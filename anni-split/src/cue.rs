@@ -1,18 +1,84 @@
 use crate::error::SplitError;
 use crate::{codec::wav::WaveHeader, split::Breakpoint};
 use cuna::Cuna;
+use encoding_rs::Encoding;
+use std::path::Path;
 
 /// `Cue` files uses format like `mm:ss.ff` to describe time of tracks.
 /// [CueBreakpoint] reuses this value, and can be used to split wave files, depending on its byte-rate.
+///
+/// Frames are 1/75 second, so audio of other sample rates than CD-DA can be split as well,
+/// as long as positions of breakpoints are aligned to sample frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct CueBreakpoint {
     seconds: u32,
     frames: u32,
+    discard: bool,
+}
+
+impl CueBreakpoint {
+    const ZERO: CueBreakpoint = CueBreakpoint {
+        seconds: 0,
+        frames: 0,
+        discard: false,
+    };
 }
 
 impl Breakpoint for CueBreakpoint {
     fn position(&self, header: &WaveHeader) -> u32 {
         header.offset_from_second_frames(self.seconds, self.frames)
     }
+
+    fn discard(&self) -> bool {
+        self.discard
+    }
+}
+
+/// How to handle pregaps, which are audio between `INDEX 00` and `INDEX 01` of a track.
+///
+/// Audio before `INDEX 01` of the first track is treated as its pregap, even if `INDEX 00` does not exist.
+/// It's also called HTOA (hidden track one audio).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PregapMode {
+    /// Prepend pregap to its track.
+    Prepend,
+    /// Append pregap to the previous track, which is the same as `--append-gaps` of `cuebreakpoints`.
+    ///
+    /// Pregap of the first track is split to a separate track, as there's no previous track.
+    #[default]
+    Append,
+    /// Split pregap to a separate track, which is placed before its track.
+    ///
+    /// Index of output tracks would not match track numbers in cue file if any pregap exists.
+    Separate,
+    /// Drop audio of pregap.
+    Discard,
+}
+
+/// Decode content of a cue file to string.
+///
+/// Encoding is detected by BOM first. Otherwise `charset` is used if provided, or guessed from the content.
+/// `charset` can be any [encoding label](https://encoding.spec.whatwg.org/#names-and-labels), such as `shift_jis` or `gbk`.
+pub fn decode_cue(input: &[u8], charset: Option<&str>) -> Result<String, SplitError> {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(input) {
+        let (result, _) = encoding.decode_without_bom_handling(&input[bom_length..]);
+        return Ok(result.into_owned());
+    }
+
+    match charset {
+        Some(charset) => {
+            let encoding = Encoding::for_label(charset.as_bytes())
+                .ok_or_else(|| SplitError::UnsupportedCharset(charset.to_string()))?;
+            let (result, _) = encoding.decode_without_bom_handling(input);
+            Ok(result.into_owned())
+        }
+        None => Ok(anni_common::decode::raw_to_string(input)),
+    }
+}
+
+/// Read a cue file and decode it with [decode_cue].
+pub fn read_cue<P: AsRef<Path>>(path: P, charset: Option<&str>) -> Result<String, SplitError> {
+    decode_cue(&std::fs::read(path)?, charset)
 }
 
 /// Extract breakpoints from a cue file.
@@ -22,6 +88,19 @@ impl Breakpoint for CueBreakpoint {
 pub fn cue_breakpoints<C>(
     cue: C,
 ) -> Result<(impl IntoIterator<Item = CueBreakpoint>, Cuna), SplitError>
+where
+    C: AsRef<str>,
+{
+    cue_breakpoints_with(cue, PregapMode::Append)
+}
+
+/// Extract breakpoints from a cue file, handling pregaps with `mode`.
+///
+/// It returns an iterator of breakpoints, and a [Cuna] object.
+pub fn cue_breakpoints_with<C>(
+    cue: C,
+    mode: PregapMode,
+) -> Result<(impl IntoIterator<Item = CueBreakpoint>, Cuna), SplitError>
 where
     C: AsRef<str>,
 {
@@ -30,27 +109,80 @@ where
     let total_tracks = cue.files.iter().map(|f| f.tracks.len()).sum();
     let mut result = Vec::with_capacity(total_tracks);
 
-    for file in cue.files.iter() {
-        for track in file.tracks.iter() {
-            for index in track.index.iter() {
-                if index.id() == 1 {
-                    let time = index.begin_time();
-                    result.push(CueBreakpoint {
-                        seconds: time.total_seconds(),
-                        frames: time.frames(),
-                    });
-                }
+    for (i, track) in cue.files.iter().flat_map(|f| f.tracks.iter()).enumerate() {
+        let mut pregap = None;
+        let mut start = None;
+        for index in track.index.iter() {
+            let time = index.begin_time();
+            let breakpoint = CueBreakpoint {
+                seconds: time.total_seconds(),
+                frames: time.frames(),
+                discard: false,
+            };
+            match index.id() {
+                0 => pregap = Some(breakpoint),
+                1 => start = Some(breakpoint),
+                _ => {}
             }
         }
+        let Some(start) = start else {
+            continue;
+        };
+        if i == 0 && pregap.is_none() {
+            pregap = Some(CueBreakpoint::ZERO);
+        }
+
+        match (pregap.filter(|pregap| *pregap != start), mode) {
+            (None, _) => result.push(start),
+            (Some(pregap), PregapMode::Prepend) => result.push(pregap),
+            (Some(_), PregapMode::Append) => result.push(start),
+            (Some(pregap), PregapMode::Separate) => result.extend([pregap, start]),
+            (Some(pregap), PregapMode::Discard) => result.extend([
+                pregap,
+                CueBreakpoint {
+                    discard: true,
+                    ..start
+                },
+            ]),
+        }
     }
 
-    if let Some(CueBreakpoint {
-        seconds: 0,
-        frames: 0,
-    }) = result.get(0)
-    {
+    if result.first() == Some(&CueBreakpoint::ZERO) {
         result.remove(0);
     }
 
     Ok((result, cue))
 }
+
+/// Breakpoint at a sample offset, which is used by CUESHEET blocks in flac files.
+pub struct SampleBreakpoint(pub u64);
+
+impl Breakpoint for SampleBreakpoint {
+    fn position(&self, header: &WaveHeader) -> u32 {
+        (self.0 * header.block_align as u64) as u32
+    }
+}
+
+/// Extract breakpoints from a CUESHEET block embedded in flac files.
+/// Behavior is the same as [cue_breakpoints]: tracks start at `INDEX 01`, and gaps are appended to the previous track.
+#[cfg(feature = "flac")]
+pub fn flac_cue_breakpoints(cue_sheet: &anni_flac::blocks::BlockCueSheet) -> Vec<SampleBreakpoint> {
+    let mut result: Vec<_> = cue_sheet
+        .tracks
+        .iter()
+        .flat_map(|track| {
+            // lead-out track has no index point
+            track
+                .track_index
+                .iter()
+                .filter(|index| index.index_point == 1)
+                .map(|index| SampleBreakpoint(track.track_offset + index.sample_offset))
+        })
+        .collect();
+
+    if let Some(SampleBreakpoint(0)) = result.first() {
+        result.remove(0);
+    }
+
+    result
+}
@@ -1,3 +1,4 @@
+use crate::codec::wav::WavFormat;
 use std::io;
 use thiserror::Error;
 
@@ -14,4 +15,16 @@ pub enum SplitError {
 
     #[error(transparent)]
     IOError(#[from] io::Error),
+
+    #[error("unsupported charset: {0}")]
+    UnsupportedCharset(String),
+
+    #[error("unsupported audio format: {0}")]
+    UnsupportedFormat(String),
+
+    #[error("breakpoint at byte {position} is not aligned to sample frames of {format} audio")]
+    MisalignedBreakpoint { position: u32, format: WavFormat },
+
+    #[error("failed to resample: {0}")]
+    ResampleError(String),
 }
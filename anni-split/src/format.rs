@@ -0,0 +1,414 @@
+use crate::codec::wav::WaveHeader;
+use crate::codec::Encoder;
+use crate::error::SplitError;
+use anni_common::traits::{Decode, Encode};
+use rubato::{FftFixedIn, Resampler};
+use serde::Deserialize;
+use std::io::{self, Read};
+
+/// Target format of audio samples.
+///
+/// Fields set to `None` keep the value of the source audio.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AudioFormat {
+    /// Bits per sample. 8, 16, 24 and 32 are supported.
+    pub bit_depth: Option<u16>,
+    /// Samples per second.
+    pub sample_rate: Option<u32>,
+    /// Number of channels. Only down-mixing to mono and up-mixing from mono are supported.
+    pub channels: Option<u16>,
+}
+
+impl AudioFormat {
+    pub fn new(bit_depth: Option<u16>, sample_rate: Option<u32>, channels: Option<u16>) -> Self {
+        Self {
+            bit_depth,
+            sample_rate,
+            channels,
+        }
+    }
+
+    /// Whether no conversion is requested.
+    pub fn is_empty(&self) -> bool {
+        self.bit_depth.is_none() && self.sample_rate.is_none() && self.channels.is_none()
+    }
+
+    /// Header of audio converted from `source`, with `data_size` set to 0.
+    pub fn target_header(&self, source: &WaveHeader) -> WaveHeader {
+        let channels = self.channels.unwrap_or(source.channels);
+        let sample_rate = self.sample_rate.unwrap_or(source.sample_rate);
+        let bit_per_sample = self.bit_depth.unwrap_or(source.bit_per_sample);
+        let block_align = channels * bit_per_sample / 8;
+
+        WaveHeader {
+            channels,
+            sample_rate,
+            byte_rate: sample_rate * block_align as u32,
+            block_align,
+            bit_per_sample,
+            data_size: 0,
+        }
+    }
+
+    /// Whether audio described by `header` is already in this format.
+    pub fn matches(&self, header: &WaveHeader) -> bool {
+        let target = self.target_header(header);
+        target.channels == header.channels
+            && target.sample_rate == header.sample_rate
+            && target.bit_per_sample == header.bit_per_sample
+    }
+
+    /// Convert PCM `data` described by `header` to this format.
+    ///
+    /// Bit depth reduction is dithered with TPDF noise, and resampling is performed by an FFT resampler.
+    pub fn convert(
+        &self,
+        header: &WaveHeader,
+        data: &[u8],
+    ) -> Result<(WaveHeader, Vec<u8>), SplitError> {
+        let mut header = header.clone();
+        header.data_size = data.len() as u32;
+
+        let mut converter = self.pcm_converter(header, data)?;
+        let target = converter.header().clone();
+        // skip the header of output
+        converter.position = converter.buffer.len();
+
+        let mut output = Vec::with_capacity(target.data_size as usize);
+        converter.read_to_end(&mut output)?;
+        Ok((target, output))
+    }
+
+    /// Create a [FormatConverter] which reads WAVE audio from `input` and converts it to this format.
+    pub fn converter<R>(self, mut input: R) -> Result<FormatConverter<R>, SplitError>
+    where
+        R: Read,
+    {
+        let header = WaveHeader::from_reader(&mut input)?;
+        self.pcm_converter(header, input)
+    }
+
+    /// Create a [FormatConverter] which reads PCM data described by `header` from `input`.
+    pub fn pcm_converter<R>(
+        self,
+        header: WaveHeader,
+        input: R,
+    ) -> Result<FormatConverter<R>, SplitError>
+    where
+        R: Read,
+    {
+        FormatConverter::new(self, header, input)
+    }
+
+    /// Create an [Encoder] which converts audio to this format before passing it to `inner`.
+    pub fn encoder<E>(self, inner: E) -> FormatEncoder<E>
+    where
+        E: Encoder,
+    {
+        FormatEncoder {
+            format: self,
+            inner,
+        }
+    }
+}
+
+/// [Encoder] converting audio to an [AudioFormat] before passing it to the inner encoder.
+///
+/// Audio which is already in the target format is passed through.
+pub struct FormatEncoder<E> {
+    format: AudioFormat,
+    inner: E,
+}
+
+impl<E: Encoder> Encoder for FormatEncoder<E> {
+    fn encode(self, input: impl Read) -> Result<(), SplitError> {
+        self.inner.encode(self.format.converter(input)?)
+    }
+}
+
+/// Number of frames converted at a time.
+const CHUNK_SIZE: usize = 1024;
+
+/// Reader converting audio to an [AudioFormat] while reading.
+///
+/// Output of the reader is WAVE audio, starting with its header.
+/// Audio which is already in the target format is passed through.
+pub struct FormatConverter<R> {
+    input: R,
+    source: WaveHeader,
+    target: WaveHeader,
+    passthrough: bool,
+    /// Bytes of source audio data which are not read yet.
+    input_remaining: u64,
+    /// Frames of target audio data which are not converted yet.
+    output_remaining: u64,
+    resampler: Option<FftFixedIn<f64>>,
+    /// Frames of resampler output to be discarded, which are caused by its delay.
+    delay: usize,
+    dither: bool,
+    noise: TriangularNoise,
+    /// Converted data which are not read yet.
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+impl<R: Read> FormatConverter<R> {
+    fn new(format: AudioFormat, source: WaveHeader, input: R) -> Result<Self, SplitError> {
+        let mut target = format.target_header(&source);
+        let passthrough = format.matches(&source);
+        if !passthrough {
+            check_bit_depth(source.bit_per_sample)?;
+            check_bit_depth(target.bit_per_sample)?;
+            check_channels(source.channels, target.channels)?;
+        }
+
+        let frames = match source.block_align {
+            0 => 0,
+            block_align => source.data_size as u64 / block_align as u64,
+        };
+        let (resampler, delay, output_frames) = if !passthrough
+            && target.sample_rate != source.sample_rate
+        {
+            let resampler = FftFixedIn::<f64>::new(
+                source.sample_rate as usize,
+                target.sample_rate as usize,
+                CHUNK_SIZE,
+                2,
+                target.channels as usize,
+            )
+            .map_err(|e| SplitError::ResampleError(e.to_string()))?;
+            let delay = resampler.output_delay();
+            let frames = (frames * target.sample_rate as u64).div_ceil(source.sample_rate as u64);
+            (Some(resampler), delay, frames)
+        } else {
+            (None, 0, frames)
+        };
+        target.data_size = if passthrough {
+            source.data_size
+        } else {
+            (output_frames * target.block_align as u64) as u32
+        };
+
+        let mut buffer = Vec::with_capacity(44);
+        target.write_to(&mut buffer)?;
+        Ok(Self {
+            input,
+            input_remaining: source.data_size as u64,
+            dither: target.bit_per_sample < source.bit_per_sample,
+            source,
+            target,
+            passthrough,
+            output_remaining: output_frames,
+            resampler,
+            delay,
+            noise: TriangularNoise::default(),
+            buffer,
+            position: 0,
+        })
+    }
+
+    /// Header of the converted audio.
+    pub fn header(&self) -> &WaveHeader {
+        &self.target
+    }
+
+    /// Convert the next chunk of audio to `buffer`, which is left empty at the end of audio.
+    fn fill(&mut self) -> io::Result<()> {
+        let block_align = self.source.block_align as usize;
+        let mut data = vec![0u8; CHUNK_SIZE * block_align];
+
+        self.buffer.clear();
+        self.position = 0;
+        while self.buffer.is_empty() && self.output_remaining > 0 {
+            let size = (data.len() as u64).min(self.input_remaining) as usize;
+            let size = read_full(&mut self.input, &mut data[..size])?;
+            self.input_remaining -= size as u64;
+
+            // incomplete frame at the end of input is dropped
+            let samples = read_samples(&self.source, &data[..size - size % block_align]);
+            let mut samples = mix_channels(samples, self.target.channels);
+            match &mut self.resampler {
+                Some(resampler) => {
+                    // feed silence after the end of input to flush delayed samples
+                    for channel in samples.iter_mut() {
+                        channel.resize(CHUNK_SIZE, 0.0);
+                    }
+                    samples = resampler
+                        .process(&samples, None)
+                        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+
+                    let delay = self.delay.min(samples[0].len());
+                    for channel in samples.iter_mut() {
+                        channel.drain(..delay);
+                    }
+                    self.delay -= delay;
+                }
+                // input ends earlier than its header claims
+                None if size == 0 => break,
+                None => {}
+            }
+
+            let frames = samples[0].len().min(self.output_remaining as usize);
+            for channel in samples.iter_mut() {
+                channel.truncate(frames);
+            }
+            self.output_remaining -= frames as u64;
+            let noise = self.dither.then_some(&mut self.noise);
+            write_samples(&self.target, &samples, noise, &mut self.buffer);
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for FormatConverter<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.buffer.len() {
+            if self.passthrough {
+                let size = (buf.len() as u64).min(self.input_remaining) as usize;
+                let size = self.input.read(&mut buf[..size])?;
+                self.input_remaining -= size as u64;
+                return Ok(size);
+            }
+            self.fill()?;
+        }
+
+        let size = (&self.buffer[self.position..]).read(buf)?;
+        self.position += size;
+        Ok(size)
+    }
+}
+
+/// Read from `reader` until `buf` is full or the end of input is reached.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut size = 0;
+    while size < buf.len() {
+        match reader.read(&mut buf[size..]) {
+            Ok(0) => break,
+            Ok(n) => size += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(size)
+}
+
+fn check_bit_depth(bit_depth: u16) -> Result<(), SplitError> {
+    match bit_depth {
+        8 | 16 | 24 | 32 => Ok(()),
+        _ => Err(SplitError::UnsupportedFormat(format!(
+            "{bit_depth}-bit samples"
+        ))),
+    }
+}
+
+/// Read interleaved PCM data to planar samples in range `[-1, 1)`.
+fn read_samples(header: &WaveHeader, data: &[u8]) -> Vec<Vec<f64>> {
+    let channels = header.channels as usize;
+    let bytes = header.bit_per_sample as usize / 8;
+
+    let mut samples = vec![Vec::with_capacity(data.len() / bytes / channels); channels];
+    for frame in data.chunks_exact(bytes * channels) {
+        for (channel, sample) in samples.iter_mut().zip(frame.chunks_exact(bytes)) {
+            let value = if bytes == 1 {
+                // 8-bit samples are unsigned
+                (sample[0] as f64 - 128.0) / 128.0
+            } else {
+                // place the sample at the most significant bytes to keep its sign
+                let mut buf = [0u8; 4];
+                buf[4 - bytes..].copy_from_slice(sample);
+                i32::from_le_bytes(buf) as f64 / 2147483648.0
+            };
+            channel.push(value);
+        }
+    }
+    samples
+}
+
+/// Write planar samples to interleaved PCM data described by `header`, dithered with `noise` if given.
+fn write_samples(
+    header: &WaveHeader,
+    samples: &[Vec<f64>],
+    mut noise: Option<&mut TriangularNoise>,
+    data: &mut Vec<u8>,
+) {
+    let bytes = header.bit_per_sample as usize / 8;
+    let frames = samples.first().map_or(0, Vec::len);
+    let max = (1i64 << (header.bit_per_sample - 1)) as f64;
+
+    data.reserve(frames * bytes * samples.len());
+    for i in 0..frames {
+        for channel in samples {
+            let mut value = channel[i] * max;
+            if let Some(noise) = noise.as_mut() {
+                value += noise.sample();
+            }
+            let value = value.round().clamp(-max, max - 1.0) as i32;
+
+            if bytes == 1 {
+                data.push((value + 128) as u8);
+            } else {
+                let buf = (value << (32 - header.bit_per_sample)).to_le_bytes();
+                data.extend_from_slice(&buf[4 - bytes..]);
+            }
+        }
+    }
+}
+
+fn check_channels(source: u16, target: u16) -> Result<(), SplitError> {
+    if source == 0 || target == 0 {
+        Err(SplitError::UnsupportedFormat(
+            "audio without channels".to_string(),
+        ))
+    } else if source != target && source != 1 && target != 1 {
+        Err(SplitError::UnsupportedFormat(format!(
+            "mixing {source} channels to {target} channels"
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// Mix `samples` to `channels`, which must be checked by [check_channels].
+fn mix_channels(samples: Vec<Vec<f64>>, channels: u16) -> Vec<Vec<f64>> {
+    let source = samples.len();
+    let target = channels as usize;
+
+    if source == target {
+        samples
+    } else if target == 1 {
+        let frames = samples[0].len();
+        let mixed = (0..frames)
+            .map(|i| samples.iter().map(|channel| channel[i]).sum::<f64>() / source as f64)
+            .collect();
+        vec![mixed]
+    } else {
+        vec![samples[0].clone(); target]
+    }
+}
+
+/// Triangular probability density function noise in range `(-1, 1)`, used for dithering.
+///
+/// A fixed-seed xorshift generator is used, so the output is reproducible.
+struct TriangularNoise(u32);
+
+impl Default for TriangularNoise {
+    fn default() -> Self {
+        Self(0x9E3779B9)
+    }
+}
+
+impl TriangularNoise {
+    fn uniform(&mut self) -> f64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        x as f64 / u32::MAX as f64
+    }
+
+    fn sample(&mut self) -> f64 {
+        self.uniform() - self.uniform()
+    }
+}
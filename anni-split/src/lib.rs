@@ -1,9 +1,15 @@
 #![feature(impl_trait_in_assoc_type)]
 
+pub mod batch;
 pub mod codec;
 pub mod cue;
 pub mod error;
+pub mod format;
+pub mod profile;
 pub mod split;
 
-pub use cue::cue_breakpoints;
-pub use split::split;
+pub use batch::{split_many, SplitJob, SplitProgress};
+#[cfg(feature = "flac")]
+pub use cue::flac_cue_breakpoints;
+pub use cue::{cue_breakpoints, cue_breakpoints_with, decode_cue, read_cue, PregapMode};
+pub use split::{split, split_with_progress, SplitEvent};
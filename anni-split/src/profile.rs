@@ -0,0 +1,169 @@
+use crate::codec::command::{CommandCodec, FILE_PLACEHOLDER};
+use crate::codec::Encoder;
+use crate::error::SplitError;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+/// Placeholder used in [QualityProfile] arguments to indicate the output file path.
+///
+/// It would be replaced with [FILE_PLACEHOLDER] before the encoder command is spawned.
+pub const OUTPUT_PLACEHOLDER: &str = "{output}";
+
+/// A named set of encoder settings.
+///
+/// WAVE data is piped to `command` through stdin, so `args` should contain the stdin
+/// marker expected by the encoder (usually `-`) and [OUTPUT_PLACEHOLDER] for the output path.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct QualityProfile {
+    /// Encoder executable, looked up in `PATH`.
+    pub command: String,
+    /// Extension of encoded files, without the leading dot.
+    pub extension: String,
+    /// Arguments passed to the encoder.
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+impl QualityProfile {
+    pub fn new<C, E, A, S>(command: C, extension: E, args: A) -> Self
+    where
+        C: Into<String>,
+        E: Into<String>,
+        A: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            command: command.into(),
+            extension: extension.into(),
+            args: args.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Arguments with [OUTPUT_PLACEHOLDER] replaced by [FILE_PLACEHOLDER].
+    pub fn command_args(&self) -> impl Iterator<Item = &str> {
+        self.args.iter().map(|arg| {
+            if arg == OUTPUT_PLACEHOLDER {
+                FILE_PLACEHOLDER
+            } else {
+                arg.as_str()
+            }
+        })
+    }
+
+    /// Create an [Encoder] which writes encoded data to `path` with this profile.
+    pub fn encoder<P>(&self, path: P) -> ProfileEncoder<'_, P>
+    where
+        P: AsRef<Path>,
+    {
+        ProfileEncoder {
+            profile: self,
+            path,
+        }
+    }
+}
+
+/// [Encoder] spawning the command described by a [QualityProfile].
+pub struct ProfileEncoder<'a, P: AsRef<Path>> {
+    profile: &'a QualityProfile,
+    path: P,
+}
+
+impl<P: AsRef<Path>> Encoder for ProfileEncoder<'_, P> {
+    fn encode(self, input: impl Read) -> Result<(), SplitError> {
+        CommandCodec::new(
+            &self.profile.command,
+            self.profile.command_args(),
+            self.path,
+        )?
+        .encode(input)
+    }
+}
+
+/// Collection of [QualityProfile]s, indexed by name.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct QualityProfiles(HashMap<String, QualityProfile>);
+
+impl QualityProfiles {
+    /// Profiles shipped with anni-split:
+    ///
+    /// - `archive`: FLAC at compression level 8
+    /// - `phone`: Opus at 96 kbps
+    /// - `preview`: MP3 with VBR quality 2
+    pub fn builtin() -> Self {
+        let mut profiles = HashMap::new();
+        profiles.insert(
+            "archive".to_string(),
+            QualityProfile::new(
+                "flac",
+                "flac",
+                ["--totally-silent", "-8", "-", "-o", OUTPUT_PLACEHOLDER],
+            ),
+        );
+        profiles.insert(
+            "phone".to_string(),
+            QualityProfile::new(
+                "opusenc",
+                "opus",
+                ["--quiet", "--bitrate", "96", "-", OUTPUT_PLACEHOLDER],
+            ),
+        );
+        profiles.insert(
+            "preview".to_string(),
+            QualityProfile::new("lame", "mp3", ["--silent", "-V2", "-", OUTPUT_PLACEHOLDER]),
+        );
+        Self(profiles)
+    }
+
+    /// Add profiles from `other`, replacing existing profiles with the same name.
+    pub fn extend(&mut self, other: QualityProfiles) {
+        self.0.extend(other.0);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&QualityProfile> {
+        self.0.get(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::codec::command::FILE_PLACEHOLDER;
+    use crate::profile::{QualityProfile, QualityProfiles, OUTPUT_PLACEHOLDER};
+
+    #[test]
+    fn test_builtin_profile() {
+        let profiles = QualityProfiles::builtin();
+        let phone = profiles.get("phone").unwrap();
+        assert_eq!(phone.command, "opusenc");
+        assert_eq!(phone.extension, "opus");
+        assert_eq!(
+            phone.command_args().collect::<Vec<_>>(),
+            ["--quiet", "--bitrate", "96", "-", FILE_PLACEHOLDER]
+        );
+    }
+
+    #[test]
+    fn test_override_profile() {
+        let mut profiles = QualityProfiles::builtin();
+        let mut custom = QualityProfiles::default();
+        custom.0.insert(
+            "phone".to_string(),
+            QualityProfile::new(
+                "opusenc",
+                "opus",
+                ["--bitrate", "64", "-", OUTPUT_PLACEHOLDER],
+            ),
+        );
+        profiles.extend(custom);
+
+        let phone = profiles.get("phone").unwrap();
+        assert_eq!(phone.args[1], "64");
+        assert!(profiles.get("archive").is_some());
+    }
+}
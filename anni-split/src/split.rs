@@ -10,7 +10,9 @@ use crate::{
 ///
 /// `Input` must be a [Decoder], such as [crate::codec::FlacCommandDecoder], [crate::codec::wav::WavDecoder], and so on.
 /// `Output` is a [Fn] which accepts current `track index`(starting from 0) and returns an [Encoder] to accept the split data.
+/// Audio before [discarded](Breakpoint::discard) breakpoints is skipped, and does not take a track index.
 /// `Breakpoints` is an iterator of [Breakpoint], which can be generated by [crate::cue::cue_breakpoints].
+/// All breakpoints are checked to be aligned to sample frames of decoded audio before splitting.
 ///
 /// Here is an example of splitting a wave file to multiple wave files:
 /// ```no_run
@@ -35,6 +37,45 @@ use crate::{
 /// .unwrap()
 /// ```
 pub fn split<F, E, I, B>(input: impl Decoder, output: F, breakpoints: I) -> Result<(), SplitError>
+where
+    F: Fn(usize) -> Result<E, SplitError>,
+    E: Encoder,
+    I: IntoIterator<Item = B>,
+    B: Breakpoint,
+{
+    split_with_progress(input, output, breakpoints, &mut |_| {})
+}
+
+/// Events reported by [split_with_progress].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitEvent {
+    /// Track `index` started, which has `bytes` bytes(`samples` samples) of audio data.
+    TrackStarted {
+        index: usize,
+        bytes: u64,
+        samples: u64,
+    },
+    /// `bytes` bytes(`samples` samples) of track `index` have been passed to its encoder.
+    ///
+    /// It's reported about once per second of audio, and after all audio data of the track is read.
+    Progress {
+        index: usize,
+        bytes: u64,
+        samples: u64,
+    },
+    /// Track `index` has been encoded.
+    TrackFinished { index: usize },
+}
+
+/// Same as [split], but reports [SplitEvent]s of each track to `progress`.
+///
+/// `progress` is called on the current thread. Audio of discarded breakpoints does not report any event.
+pub fn split_with_progress<F, E, I, B>(
+    input: impl Decoder,
+    output: F,
+    breakpoints: I,
+    progress: &mut dyn FnMut(SplitEvent),
+) -> Result<(), SplitError>
 where
     F: Fn(usize) -> Result<E, SplitError>,
     E: Encoder,
@@ -43,17 +84,37 @@ where
 {
     let mut reader = &mut input.decode()?;
     let header = WaveHeader::from_reader(&mut reader)?;
+    let block_align = header.block_align.max(1) as u64;
 
-    let mut start = 0u32;
-
-    for (index, end) in breakpoints
+    // validate all breakpoints before writing any track
+    let breakpoints: Vec<_> = breakpoints
         .into_iter()
-        .map(|b| b.position(&header))
-        .chain([header.data_size])
-        .enumerate()
+        .map(|b| (b.position(&header), b.discard()))
+        .collect();
+    if let Some((position, _)) = breakpoints
+        .iter()
+        .find(|(position, _)| *position as u64 % block_align != 0)
     {
-        let encoder = output(index)?;
+        return Err(SplitError::MisalignedBreakpoint {
+            position: *position,
+            format: header.format(),
+        });
+    }
+
+    let mut start = 0u32;
+    let mut index = 0;
+
+    for (end, discard) in breakpoints.into_iter().chain([(header.data_size, false)]) {
         let size = end - start;
+        if discard {
+            std::io::copy(&mut reader.take(size as u64), &mut std::io::sink())?;
+            start = end;
+            continue;
+        }
+
+        let track = index;
+        let encoder = output(track)?;
+        index += 1;
 
         let mut header_buf = Cursor::new([0; 44]);
         let mut header = header.clone();
@@ -61,8 +122,22 @@ where
         header.write_to(&mut header_buf)?;
         header_buf.set_position(0);
 
-        let body = &mut reader.take(size as u64);
+        progress(SplitEvent::TrackStarted {
+            index: track,
+            bytes: size as u64,
+            samples: size as u64 / block_align,
+        });
+        let body = ProgressReader {
+            inner: reader.take(size as u64),
+            index: track,
+            block_align,
+            interval: (header.byte_rate as u64).max(1),
+            bytes: 0,
+            reported: 0,
+            progress: &mut *progress,
+        };
         encoder.encode(header_buf.chain(body))?;
+        progress(SplitEvent::TrackFinished { index: track });
 
         start = end;
     }
@@ -70,8 +145,43 @@ where
     Ok(())
 }
 
+/// [Read] wrapper which reports [SplitEvent::Progress] every `interval` bytes.
+struct ProgressReader<'a, R> {
+    inner: R,
+    index: usize,
+    block_align: u64,
+    interval: u64,
+    bytes: u64,
+    reported: u64,
+    progress: &'a mut dyn FnMut(SplitEvent),
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes += n as u64;
+        // only report when enough bytes are read, or at the end of track
+        if self.bytes - self.reported >= self.interval || (n == 0 && self.bytes > self.reported) {
+            self.reported = self.bytes;
+            (self.progress)(SplitEvent::Progress {
+                index: self.index,
+                bytes: self.bytes,
+                samples: self.bytes / self.block_align,
+            });
+        }
+        Ok(n)
+    }
+}
+
 pub trait Breakpoint {
     fn position(&self, header: &WaveHeader) -> u32;
+
+    /// Whether audio between the previous breakpoint and this one should be dropped.
+    ///
+    /// Discarded audio is not passed to any output, so it does not take a track index.
+    fn discard(&self) -> bool {
+        false
+    }
 }
 
 pub struct RawBreakpoint(pub u32);
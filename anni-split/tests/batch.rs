@@ -0,0 +1,136 @@
+use anni_common::traits::{Decode, Encode};
+use anni_split::codec::wav::WaveHeader;
+use anni_split::codec::{Encoder, FlacCommandEncoder};
+use anni_split::error::SplitError;
+use anni_split::{split_many, SplitJob, SplitProgress};
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+const BYTE_RATE: u32 = 44100 * 4;
+
+/// Write a 16-bit stereo silent wave file.
+fn write_wav(path: &Path, seconds: u32) {
+    let header = WaveHeader {
+        channels: 2,
+        sample_rate: 44100,
+        byte_rate: BYTE_RATE,
+        block_align: 4,
+        bit_per_sample: 16,
+        data_size: BYTE_RATE * seconds,
+    };
+
+    let mut file = File::create(path).unwrap();
+    header.write_to(&mut file).unwrap();
+    std::io::copy(
+        &mut std::io::repeat(0).take((BYTE_RATE * seconds) as u64),
+        &mut file,
+    )
+    .unwrap();
+}
+
+/// Generate a cue file with one track per second.
+fn cue(titles: &[&str]) -> String {
+    let mut cue = "FILE \"input.wav\" WAVE\n".to_string();
+    for (index, title) in titles.iter().enumerate() {
+        cue += &format!(
+            "  TRACK {:02} AUDIO\n    TITLE \"{title}\"\n    INDEX 01 00:{index:02}:00\n",
+            index + 1
+        );
+    }
+    cue
+}
+
+fn job(dir: &Path, name: &str, titles: &[&str]) -> SplitJob {
+    let album = dir.join(name);
+    std::fs::create_dir(&album).unwrap();
+    let input = album.join("input.wav");
+    write_wav(&input, titles.len() as u32);
+    SplitJob {
+        input,
+        cue: cue(titles),
+        output: album
+            .join("{track}. {title}.wav")
+            .to_string_lossy()
+            .to_string(),
+    }
+}
+
+#[test]
+fn test_split_many() {
+    let dir = tempfile::tempdir().unwrap();
+    let jobs = vec![
+        job(dir.path(), "album1", &["One", "Two", "Three"]),
+        job(dir.path(), "album2", &["A/B", "C"]),
+        SplitJob {
+            input: dir.path().join("input.mp3"),
+            cue: cue(&["Unsupported"]),
+            output: dir.path().join("{track}.wav").to_string_lossy().to_string(),
+        },
+    ];
+
+    let progress = RefCell::new(Vec::new());
+    let results = split_many(jobs, 2, |p| progress.borrow_mut().push(p));
+    assert_eq!(results.len(), 3);
+    assert!(results[0].is_ok());
+    assert!(results[1].is_ok());
+    assert!(matches!(results[2], Err(SplitError::UnsupportedFormat(_))));
+
+    let mut progress = progress.into_inner();
+    progress.sort_by_key(|p| (p.job, p.track));
+    let expected: Vec<_> = [(0, 3), (1, 2)]
+        .into_iter()
+        .flat_map(|(job, tracks)| {
+            (0..tracks).map(move |track| SplitProgress { job, track, tracks })
+        })
+        .collect();
+    assert_eq!(progress, expected);
+
+    for (album, files) in [
+        (
+            "album1",
+            &["01. One.wav", "02. Two.wav", "03. Three.wav"][..],
+        ),
+        ("album2", &["01. A／B.wav", "02. C.wav"][..]),
+    ] {
+        let album = dir.path().join(album);
+        // input file and output tracks
+        assert_eq!(std::fs::read_dir(&album).unwrap().count(), files.len() + 1);
+        for file in files {
+            let header =
+                WaveHeader::from_reader(&mut File::open(album.join(file)).unwrap()).unwrap();
+            assert_eq!(header.data_size, BYTE_RATE);
+        }
+    }
+}
+
+#[test]
+#[cfg_attr(
+    not(feature = "external-encoder-tests"),
+    ignore = "needs `flac` to be installed"
+)]
+fn test_split_many_single_process() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut jobs = Vec::new();
+    for (name, titles) in [("album1", &["One", "Two"][..]), ("album2", &["A"][..])] {
+        let mut job = job(dir.path(), name, titles);
+        let input = job.input.with_extension("flac");
+        FlacCommandEncoder(&input)
+            .encode(File::open(&job.input).unwrap())
+            .unwrap();
+        std::fs::remove_file(&job.input).unwrap();
+        job.input = input;
+        job.output = job.output.replace(".wav", ".flac");
+        jobs.push(job);
+    }
+
+    // flac to flac needs two processes, which is more than the bound
+    let results = split_many(jobs, 1, |_| {});
+    assert!(results.iter().all(Result::is_ok), "{results:?}");
+    for (album, tracks) in [("album1", 2), ("album2", 1)] {
+        // input file and output tracks, without temporary files
+        let files = std::fs::read_dir(dir.path().join(album)).unwrap().count();
+        assert_eq!(files, tracks + 1);
+    }
+}
@@ -0,0 +1,78 @@
+use anni_split::codec::wav::WaveHeader;
+use anni_split::error::SplitError;
+use anni_split::split::Breakpoint;
+use anni_split::{cue_breakpoints, decode_cue, read_cue};
+
+const SHIFT_JIS: &str = "tests/cue/shift-jis.cue";
+const UTF_8: &str = "tests/cue/utf-8.cue";
+
+fn header() -> WaveHeader {
+    WaveHeader {
+        channels: 2,
+        sample_rate: 44100,
+        byte_rate: 44100 * 4,
+        block_align: 4,
+        bit_per_sample: 16,
+        data_size: 0,
+    }
+}
+
+/// Track titles and breakpoint positions of a cue file.
+fn parse(cue: String) -> (Vec<String>, Vec<u32>) {
+    let (breakpoints, cue) = cue_breakpoints(cue).unwrap();
+    let header = header();
+    let positions = breakpoints
+        .into_iter()
+        .map(|b| b.position(&header))
+        .collect();
+    let titles = cue.files[0]
+        .tracks
+        .iter()
+        .map(|track| track.title[0].clone())
+        .collect();
+    (titles, positions)
+}
+
+#[test]
+fn test_detect_shift_jis() {
+    let cue = read_cue(SHIFT_JIS, None).unwrap();
+    assert_eq!(cue, read_cue(UTF_8, None).unwrap());
+
+    let (titles, positions) = parse(cue);
+    assert_eq!(
+        titles,
+        [
+            "全天候型いらっしゃいませ",
+            "ハミングsoon！",
+            "a cup of happiness"
+        ]
+    );
+    assert_eq!(positions, parse(read_cue(UTF_8, None).unwrap()).1);
+}
+
+#[test]
+fn test_cue_charset_override() {
+    let expected = read_cue(UTF_8, None).unwrap();
+    assert_eq!(read_cue(SHIFT_JIS, Some("shift_jis")).unwrap(), expected);
+    assert_eq!(read_cue(SHIFT_JIS, Some("Shift_JIS")).unwrap(), expected);
+    assert!(matches!(
+        read_cue(SHIFT_JIS, Some("not-a-charset")),
+        Err(SplitError::UnsupportedCharset(_))
+    ));
+}
+
+#[test]
+fn test_cue_bom() {
+    let utf8 = std::fs::read(UTF_8).unwrap();
+    let expected = String::from_utf8(utf8.clone()).unwrap();
+
+    // BOM is preferred over charset
+    let with_bom = [b"\xEF\xBB\xBF".as_slice(), &utf8].concat();
+    assert_eq!(decode_cue(&with_bom, Some("shift_jis")).unwrap(), expected);
+
+    let utf16: Vec<u8> = [0xFF, 0xFE]
+        .into_iter()
+        .chain(expected.encode_utf16().flat_map(u16::to_le_bytes))
+        .collect();
+    assert_eq!(decode_cue(&utf16, None).unwrap(), expected);
+}
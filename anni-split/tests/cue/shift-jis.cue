@@ -0,0 +1,17 @@
+REM DATE "2014"
+TITLE "͂łH"
+FILE "album.wav" WAVE
+  TRACK 01 AUDIO
+    TITLE "SV^Ⴂ܂"
+    PERFORMER "RRAiqjA`mîj"
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE "n~OsoonI"
+    PERFORMER "RRAiqj"
+    INDEX 00 03:44:10
+    INDEX 01 03:45:48
+  TRACK 03 AUDIO
+    TITLE "a cup of happiness"
+    PERFORMER "`mîj"
+    INDEX 00 08:22:22
+    INDEX 01 08:23:23
@@ -0,0 +1,17 @@
+REM DATE "2014"
+TITLE "ご注文はうさぎですか？"
+FILE "album.wav" WAVE
+  TRACK 01 AUDIO
+    TITLE "全天候型いらっしゃいませ"
+    PERFORMER "ココア（佐倉綾音）、チノ（水瀬いのり）"
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE "ハミングsoon！"
+    PERFORMER "ココア（佐倉綾音）"
+    INDEX 00 03:44:10
+    INDEX 01 03:45:48
+  TRACK 03 AUDIO
+    TITLE "a cup of happiness"
+    PERFORMER "チノ（水瀬いのり）"
+    INDEX 00 08:22:22
+    INDEX 01 08:23:23
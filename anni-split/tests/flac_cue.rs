@@ -0,0 +1,84 @@
+#![cfg(feature = "flac")]
+
+use anni_common::traits::{Decode, Encode};
+use anni_flac::blocks::{BlockCueSheet, CueSheetTrack, CueSheetTrackIndex};
+use anni_split::codec::wav::{WavDecoder, WavEncoder, WaveHeader};
+use anni_split::{flac_cue_breakpoints, split};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+const BLOCK_ALIGN: u64 = 4;
+
+/// Write a 16-bit stereo wave file, each sample frame contains its index.
+fn write_wav(path: &Path, samples: u64) {
+    let header = WaveHeader {
+        channels: 2,
+        sample_rate: 44100,
+        byte_rate: 44100 * BLOCK_ALIGN as u32,
+        block_align: BLOCK_ALIGN as u16,
+        bit_per_sample: 16,
+        data_size: (samples * BLOCK_ALIGN) as u32,
+    };
+
+    let mut file = File::create(path).unwrap();
+    header.write_to(&mut file).unwrap();
+    for i in 0..samples {
+        file.write_all(&(i as u32).to_le_bytes()).unwrap();
+    }
+}
+
+#[test]
+fn test_split_embedded_cue_sheet() {
+    let total = 588 * 40;
+
+    // the second track has a pre-gap of 588 samples, which belongs to the previous track
+    let mut second = CueSheetTrack::new(2, 588 * 10);
+    second.index_point_number = 2;
+    second.track_index = vec![
+        CueSheetTrackIndex {
+            sample_offset: 0,
+            index_point: 0,
+        },
+        CueSheetTrackIndex {
+            sample_offset: 588,
+            index_point: 1,
+        },
+    ];
+    let cue_sheet = BlockCueSheet::new(
+        true,
+        vec![
+            CueSheetTrack::new(1, 0),
+            second,
+            CueSheetTrack::new(3, 588 * 25),
+        ],
+        total,
+    );
+
+    let breakpoints = flac_cue_breakpoints(&cue_sheet);
+    let boundaries: Vec<_> = breakpoints.iter().map(|b| b.0).collect();
+    assert_eq!(boundaries, [588 * 11, 588 * 25]);
+
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("input.wav");
+    write_wav(&input, total);
+
+    split(
+        WavDecoder(&input),
+        |index| Ok(WavEncoder(dir.path().join(format!("{index}.wav")))),
+        breakpoints,
+    )
+    .unwrap();
+
+    let expected = [(0, 588 * 11), (588 * 11, 588 * 25), (588 * 25, total)];
+    for (index, (start, end)) in expected.into_iter().enumerate() {
+        let mut file = File::open(dir.path().join(format!("{index}.wav"))).unwrap();
+        let header = WaveHeader::from_reader(&mut file).unwrap();
+        assert_eq!(header.data_size as u64, (end - start) * BLOCK_ALIGN);
+
+        let mut first = [0u8; 4];
+        file.read_exact(&mut first).unwrap();
+        assert_eq!(u32::from_le_bytes(first) as u64, start);
+    }
+    assert!(!dir.path().join("3.wav").exists());
+}
@@ -0,0 +1,137 @@
+use anni_common::traits::Decode;
+use anni_split::codec::wav::{WavEncoder, WaveHeader};
+use anni_split::codec::Encoder;
+use anni_split::format::AudioFormat;
+use std::f64::consts::PI;
+use std::io::{Cursor, Read};
+
+fn header(bit_per_sample: u16, sample_rate: u32, channels: u16) -> WaveHeader {
+    let block_align = channels * bit_per_sample / 8;
+    WaveHeader {
+        channels,
+        sample_rate,
+        byte_rate: sample_rate * block_align as u32,
+        block_align,
+        bit_per_sample,
+        data_size: 0,
+    }
+}
+
+/// Generate a 1kHz sine wave at half amplitude, in 24-bit stereo.
+fn sine_24(sample_rate: u32, frames: usize) -> Vec<u8> {
+    let mut data = Vec::with_capacity(frames * 6);
+    for i in 0..frames {
+        let value = (2.0 * PI * 1000.0 * i as f64 / sample_rate as f64).sin() * 0.5;
+        let value = (value * 8388608.0) as i32;
+        for _ in 0..2 {
+            data.extend_from_slice(&value.to_le_bytes()[..3]);
+        }
+    }
+    data
+}
+
+#[test]
+fn test_reduce_bit_depth() {
+    let source = header(24, 48000, 2);
+    let data = sine_24(48000, 4800);
+
+    let (target, converted) = AudioFormat::new(Some(16), None, None)
+        .convert(&source, &data)
+        .unwrap();
+    assert_eq!(target.bit_per_sample, 16);
+    assert_eq!(target.block_align, 4);
+    assert_eq!(target.sample_rate, 48000);
+    assert_eq!(target.data_size as usize, 4800 * 4);
+    assert_eq!(converted.len(), 4800 * 4);
+
+    // dithered samples stay within 2 LSB of the truncated source
+    for (source, converted) in data.chunks_exact(3).zip(converted.chunks_exact(2)) {
+        let expected = i16::from_le_bytes([source[1], source[2]]) as i32;
+        let actual = i16::from_le_bytes([converted[0], converted[1]]) as i32;
+        assert!((expected - actual).abs() <= 2, "{expected} != {actual}");
+    }
+}
+
+#[test]
+fn test_resample() {
+    let source = header(24, 96000, 2);
+    let data = sine_24(96000, 9600);
+
+    let (target, converted) = AudioFormat::new(Some(16), Some(44100), None)
+        .convert(&source, &data)
+        .unwrap();
+    assert_eq!(target.sample_rate, 44100);
+    assert_eq!(target.byte_rate, 44100 * 4);
+    assert_eq!(target.data_size as usize, 4410 * 4);
+    assert_eq!(converted.len(), 4410 * 4);
+}
+
+#[test]
+fn test_same_format_is_noop() {
+    let source = header(24, 48000, 2);
+    let data = sine_24(48000, 480);
+
+    let format = AudioFormat::new(Some(24), Some(48000), Some(2));
+    assert!(format.matches(&source));
+    let (target, converted) = format.convert(&source, &data).unwrap();
+    assert_eq!(target.bit_per_sample, 24);
+    assert_eq!(converted, data);
+}
+
+#[test]
+fn test_format_encoder() {
+    let mut source = header(24, 48000, 2);
+    let data = sine_24(48000, 480);
+    source.data_size = data.len() as u32;
+
+    let mut input = Vec::new();
+    anni_common::traits::Encode::write_to(&source, &mut input).unwrap();
+    input.extend(&data);
+
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("output.wav");
+    AudioFormat::new(Some(16), None, Some(1))
+        .encoder(WavEncoder(&output))
+        .encode(Cursor::new(input))
+        .unwrap();
+
+    let output = std::fs::read(output).unwrap();
+    let header = WaveHeader::from_reader(&mut Cursor::new(&output)).unwrap();
+    assert_eq!(header.bit_per_sample, 16);
+    assert_eq!(header.channels, 1);
+    assert_eq!(header.data_size as usize, 480 * 2);
+    assert_eq!(output.len(), 44 + 480 * 2);
+}
+
+/// Reader returning at most one byte at a time.
+struct ByteReader<R>(R);
+
+impl<R: Read> Read for ByteReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let size = buf.len().min(1);
+        self.0.read(&mut buf[..size])
+    }
+}
+
+#[test]
+fn test_converter_streams() {
+    let mut source = header(24, 96000, 2);
+    let data = sine_24(96000, 9600);
+    source.data_size = data.len() as u32;
+
+    let format = AudioFormat::new(Some(16), Some(44100), Some(1));
+    let (target, converted) = format.convert(&source, &data).unwrap();
+
+    let mut converter = format
+        .pcm_converter(source, ByteReader(Cursor::new(&data)))
+        .unwrap();
+    assert_eq!(converter.header().data_size, target.data_size);
+    let mut output = Vec::new();
+    converter.read_to_end(&mut output).unwrap();
+
+    let header = WaveHeader::from_reader(&mut Cursor::new(&output)).unwrap();
+    assert_eq!(header.sample_rate, 44100);
+    assert_eq!(header.channels, 1);
+    assert_eq!(&output[output.len() - converted.len()..], converted);
+    assert_eq!(output.len(), 44 + 4410 * 2);
+}
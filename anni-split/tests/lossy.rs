@@ -0,0 +1,68 @@
+#![cfg(feature = "external-encoder-tests")]
+
+use anni_common::traits::Encode;
+use anni_split::codec::wav::WaveHeader;
+use anni_split::codec::{
+    Encoder, Mp3CommandEncoder, Mp3Quality, OpusCommandEncoder, MP3_COMMAND, OPUS_COMMAND,
+};
+use std::io::Cursor;
+use std::path::Path;
+
+/// Half a second of 16-bit stereo 440Hz sine wave.
+fn wav() -> Cursor<Vec<u8>> {
+    let samples = 22050;
+    let header = WaveHeader {
+        channels: 2,
+        sample_rate: 44100,
+        byte_rate: 44100 * 4,
+        block_align: 4,
+        bit_per_sample: 16,
+        data_size: samples * 4,
+    };
+
+    let mut data = Vec::new();
+    header.write_to(&mut data).unwrap();
+    for i in 0..samples {
+        let t = i as f64 / 44100.0;
+        let sample = ((t * 440.0 * std::f64::consts::TAU).sin() * 8000.0) as i16;
+        data.extend(sample.to_le_bytes());
+        data.extend(sample.to_le_bytes());
+    }
+    Cursor::new(data)
+}
+
+fn assert_encoded(path: &Path) {
+    let size = std::fs::metadata(path).unwrap().len();
+    assert!(size > 0, "{} is empty", path.display());
+}
+
+#[test]
+fn test_encode_opus() {
+    assert!(
+        which::which(OPUS_COMMAND).is_ok(),
+        "`{OPUS_COMMAND}` is not installed"
+    );
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("output.opus");
+    OpusCommandEncoder::new(&output, 96).encode(wav()).unwrap();
+    assert_encoded(&output);
+}
+
+#[test]
+fn test_encode_mp3() {
+    assert!(
+        which::which(MP3_COMMAND).is_ok(),
+        "`{MP3_COMMAND}` is not installed"
+    );
+    let dir = tempfile::tempdir().unwrap();
+    for (name, quality) in [
+        ("cbr.mp3", Mp3Quality::Cbr(192)),
+        ("vbr.mp3", Mp3Quality::Vbr(2)),
+    ] {
+        let output = dir.path().join(name);
+        Mp3CommandEncoder::new(&output, quality)
+            .encode(wav())
+            .unwrap();
+        assert_encoded(&output);
+    }
+}
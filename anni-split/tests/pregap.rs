@@ -0,0 +1,129 @@
+use anni_common::traits::{Decode, Encode};
+use anni_split::codec::wav::{WavDecoder, WavEncoder, WaveHeader};
+use anni_split::{cue_breakpoints_with, split, PregapMode};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+const RATE: u32 = 44100;
+const BLOCK_ALIGN: u32 = 4;
+
+/// Write a 16-bit stereo wave file, each sample frame contains its index.
+fn write_wav(path: &Path, seconds: u32) {
+    let samples = RATE * seconds;
+    let header = WaveHeader {
+        channels: 2,
+        sample_rate: RATE,
+        byte_rate: RATE * BLOCK_ALIGN,
+        block_align: BLOCK_ALIGN as u16,
+        bit_per_sample: 16,
+        data_size: samples * BLOCK_ALIGN,
+    };
+
+    let mut data = Vec::new();
+    header.write_to(&mut data).unwrap();
+    for i in 0..samples {
+        data.extend(i.to_le_bytes());
+    }
+    std::fs::write(path, data).unwrap();
+}
+
+/// Split `cue` of a 10-second wave file, and returns sample ranges of output tracks.
+fn split_ranges(cue: &str, mode: PregapMode) -> Vec<(u32, u32)> {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("input.wav");
+    write_wav(&input, 10);
+
+    let (breakpoints, _) = cue_breakpoints_with(cue, mode).unwrap();
+    split(
+        WavDecoder(&input),
+        |index| Ok(WavEncoder(dir.path().join(format!("{index}.wav")))),
+        breakpoints,
+    )
+    .unwrap();
+
+    let mut ranges = Vec::new();
+    loop {
+        let path = dir.path().join(format!("{}.wav", ranges.len()));
+        if !path.exists() {
+            break ranges;
+        }
+
+        let mut file = File::open(path).unwrap();
+        let header = WaveHeader::from_reader(&mut file).unwrap();
+        let mut first = [0u8; 4];
+        file.read_exact(&mut first).unwrap();
+        let start = u32::from_le_bytes(first);
+        ranges.push((start, start + header.data_size / BLOCK_ALIGN));
+    }
+}
+
+/// Track 2 has a 2-second pregap.
+const PREGAP_CUE: &str = r#"FILE "input.wav" WAVE
+  TRACK 01 AUDIO
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    INDEX 00 00:03:00
+    INDEX 01 00:05:00
+  TRACK 03 AUDIO
+    INDEX 01 00:08:00
+"#;
+
+#[test]
+fn test_pregap_modes() {
+    let s = |seconds: u32| seconds * RATE;
+    let expected = [
+        (
+            PregapMode::Append,
+            vec![(0, s(5)), (s(5), s(8)), (s(8), s(10))],
+        ),
+        (
+            PregapMode::Prepend,
+            vec![(0, s(3)), (s(3), s(8)), (s(8), s(10))],
+        ),
+        (
+            PregapMode::Separate,
+            vec![(0, s(3)), (s(3), s(5)), (s(5), s(8)), (s(8), s(10))],
+        ),
+        (
+            PregapMode::Discard,
+            vec![(0, s(3)), (s(5), s(8)), (s(8), s(10))],
+        ),
+    ];
+    for (mode, ranges) in expected {
+        assert_eq!(split_ranges(PREGAP_CUE, mode), ranges, "{mode:?}");
+    }
+}
+
+/// 1 second of hidden track before track 1.
+const HTOA_CUE: &str = r#"FILE "input.wav" WAVE
+  TRACK 01 AUDIO
+    INDEX 00 00:00:00
+    INDEX 01 00:01:00
+  TRACK 02 AUDIO
+    INDEX 01 00:06:00
+"#;
+
+#[test]
+fn test_hidden_track_one_audio() {
+    let s = |seconds: u32| seconds * RATE;
+    let expected = [
+        // same as cuebreakpoints, hidden track is split as there's no previous track
+        (
+            PregapMode::Append,
+            vec![(0, s(1)), (s(1), s(6)), (s(6), s(10))],
+        ),
+        (PregapMode::Prepend, vec![(0, s(6)), (s(6), s(10))]),
+        (
+            PregapMode::Separate,
+            vec![(0, s(1)), (s(1), s(6)), (s(6), s(10))],
+        ),
+        (PregapMode::Discard, vec![(s(1), s(6)), (s(6), s(10))]),
+    ];
+    for (mode, ranges) in expected {
+        assert_eq!(split_ranges(HTOA_CUE, mode), ranges, "{mode:?}");
+        // INDEX 00 of the first track is implied
+        let cue = HTOA_CUE.replace("    INDEX 00 00:00:00\n", "");
+        assert_eq!(split_ranges(&cue, mode), ranges, "{mode:?}");
+    }
+}
@@ -0,0 +1,93 @@
+use anni_common::traits::Encode;
+use anni_split::codec::wav::{WavDecoder, WavEncoder, WaveHeader};
+use anni_split::split::RawBreakpoint;
+use anni_split::{split_with_progress, SplitEvent};
+
+const RATE: u32 = 44100;
+const BLOCK_ALIGN: u32 = 4;
+
+/// Write a 16-bit stereo silent wave file.
+fn write_wav(path: &std::path::Path, samples: u32) {
+    let header = WaveHeader {
+        channels: 2,
+        sample_rate: RATE,
+        byte_rate: RATE * BLOCK_ALIGN,
+        block_align: BLOCK_ALIGN as u16,
+        bit_per_sample: 16,
+        data_size: samples * BLOCK_ALIGN,
+    };
+
+    let mut data = Vec::new();
+    header.write_to(&mut data).unwrap();
+    data.resize(data.len() + (samples * BLOCK_ALIGN) as usize, 0);
+    std::fs::write(path, data).unwrap();
+}
+
+#[test]
+fn test_split_progress_events() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("input.wav");
+    // tracks of 1.5s, 0.5s and 3s
+    let tracks = [RATE * 3 / 2, RATE / 2, RATE * 3];
+    write_wav(&input, tracks.iter().sum());
+
+    let mut events = Vec::new();
+    split_with_progress(
+        WavDecoder(&input),
+        |index| Ok(WavEncoder(dir.path().join(format!("{index}.wav")))),
+        [
+            RawBreakpoint(tracks[0] * BLOCK_ALIGN),
+            RawBreakpoint((tracks[0] + tracks[1]) * BLOCK_ALIGN),
+        ],
+        &mut |event| events.push(event),
+    )
+    .unwrap();
+
+    let finished: Vec<_> = events
+        .iter()
+        .filter_map(|event| match event {
+            SplitEvent::TrackFinished { index } => Some(*index),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(finished, [0, 1, 2]);
+
+    for (index, samples) in tracks.into_iter().enumerate() {
+        let samples = samples as u64;
+        let bytes = samples * BLOCK_ALIGN as u64;
+        let track_events: Vec<_> = events
+            .iter()
+            .filter(|event| match event {
+                SplitEvent::TrackStarted { index: i, .. }
+                | SplitEvent::Progress { index: i, .. }
+                | SplitEvent::TrackFinished { index: i } => *i == index,
+            })
+            .collect();
+
+        assert_eq!(
+            track_events.first(),
+            Some(&&SplitEvent::TrackStarted {
+                index,
+                bytes,
+                samples
+            })
+        );
+        assert_eq!(
+            track_events.last(),
+            Some(&&SplitEvent::TrackFinished { index })
+        );
+
+        // progress is monotonic, and ends with the whole track
+        let progress: Vec<_> = track_events
+            .iter()
+            .filter_map(|event| match event {
+                SplitEvent::Progress { bytes, samples, .. } => Some((*bytes, *samples)),
+                _ => None,
+            })
+            .collect();
+        assert!(progress.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(progress.last(), Some(&(bytes, samples)));
+        // reported about once per second, not on every read
+        assert!(progress.len() as u64 <= samples / RATE as u64 + 2);
+    }
+}
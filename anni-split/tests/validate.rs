@@ -0,0 +1,78 @@
+use anni_common::traits::{Decode, Encode};
+use anni_split::codec::wav::{WavDecoder, WavEncoder, WavFormat, WaveHeader};
+use anni_split::error::SplitError;
+use anni_split::{cue_breakpoints, split};
+use std::path::Path;
+
+const CUE: &str = r#"FILE "input.wav" WAVE
+  TRACK 01 AUDIO
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    INDEX 01 00:01:01
+"#;
+
+/// Write 2 seconds of 16-bit stereo silence.
+fn write_wav(path: &Path, sample_rate: u32) {
+    let header = WaveHeader {
+        channels: 2,
+        sample_rate,
+        byte_rate: sample_rate * 4,
+        block_align: 4,
+        bit_per_sample: 16,
+        data_size: sample_rate * 4 * 2,
+    };
+
+    let mut data = Vec::new();
+    header.write_to(&mut data).unwrap();
+    data.resize(data.len() + header.data_size as usize, 0);
+    std::fs::write(path, data).unwrap();
+}
+
+fn split_cue(dir: &Path, sample_rate: u32) -> Result<(), SplitError> {
+    let input = dir.join("input.wav");
+    write_wav(&input, sample_rate);
+
+    let (breakpoints, _) = cue_breakpoints(CUE).unwrap();
+    split(
+        WavDecoder(&input),
+        |index| Ok(WavEncoder(dir.join(format!("{index}.wav")))),
+        breakpoints,
+    )
+}
+
+#[test]
+fn test_cue_breakpoints_accept_any_sample_rate() {
+    // a cue frame is 588 samples at 44.1 kHz, and 640 samples at 48 kHz and 1280 samples at 96 kHz
+    for sample_rate in [44100, 48000, 96000] {
+        let dir = tempfile::tempdir().unwrap();
+        split_cue(dir.path(), sample_rate).unwrap();
+        let header =
+            WaveHeader::from_reader(&mut std::fs::File::open(dir.path().join("0.wav")).unwrap())
+                .unwrap();
+        assert_eq!(header.data_size, (sample_rate + sample_rate / 75) * 4);
+        assert!(dir.path().join("1.wav").exists());
+    }
+}
+
+#[test]
+fn test_cue_breakpoints_reject_misaligned_position() {
+    // a cue frame is 426.67 samples at 32 kHz
+    let dir = tempfile::tempdir().unwrap();
+    let error = split_cue(dir.path(), 32000).unwrap_err();
+    match error {
+        SplitError::MisalignedBreakpoint { position, format } => {
+            assert_eq!(position, 32000 * 4 + 32000 * 4 / 75);
+            assert_eq!(
+                format,
+                WavFormat {
+                    sample_rate: 32000,
+                    channels: 2,
+                    bits_per_sample: 16,
+                }
+            );
+        }
+        e => panic!("unexpected error: {e}"),
+    }
+    // nothing is written before the misaligned breakpoint is detected
+    assert!(!dir.path().join("0.wav").exists());
+}
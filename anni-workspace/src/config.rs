@@ -20,6 +20,9 @@ pub struct WorkspaceConfig {
 pub struct WorkspaceConfigInner {
     publish_to: Option<String>,
     metadata: Option<WorkspaceMetadata>,
+    use_trash: Option<bool>,
+    strict_disc_structure: Option<bool>,
+    audio_extensions: Option<Vec<String>>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -55,6 +58,33 @@ impl WorkspaceConfig {
             .unwrap_or(WorkspaceMetadata::Repo)
     }
 
+    /// Whether to move removed album directories to trash instead of deleting them permanently.
+    ///
+    /// Defaults to `true`.
+    pub fn use_trash(&self) -> bool {
+        self.inner.use_trash.unwrap_or(true)
+    }
+
+    /// Whether to reject albums with both flac files and subdirectories in album directory.
+    ///
+    /// If disabled, subdirectories with flac files are treated as discs,
+    /// and flac files in album directory of a multi-disc album are ignored with a warning.
+    /// Defaults to `false`.
+    pub fn strict_disc_structure(&self) -> bool {
+        self.inner.strict_disc_structure.unwrap_or(false)
+    }
+
+    /// Extensions of audio files recognized as tracks in untracked albums.
+    ///
+    /// Non-flac tracks must be converted to flac before committing.
+    /// Defaults to `["flac"]`.
+    pub fn audio_extensions(&self) -> Vec<&str> {
+        match &self.inner.audio_extensions {
+            Some(extensions) => extensions.iter().map(String::as_str).collect(),
+            None => vec!["flac"],
+        }
+    }
+
     pub fn publish_to(&self) -> Option<&LibraryConfig> {
         self.inner
             .publish_to
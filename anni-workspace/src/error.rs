@@ -38,6 +38,12 @@ pub enum WorkspaceError {
     #[error("Invalid album found at {0}. If there's only one disc, then subdirectories are not allowed. If there're multiple discs, then having flac files in root directory is unacceptable.")]
     InvalidAlbumDiscStructure(PathBuf),
 
+    #[error("Audio files outside of discs found, move or remove them before committing: {0:?}")]
+    StrayTracks(Vec<PathBuf>),
+
+    #[error("Track {0} is not a flac file, convert it to flac before committing")]
+    UnconvertedTrack(PathBuf),
+
     #[error("User aborted")]
     UserAborted,
 
@@ -59,9 +65,11 @@ pub enum WorkspaceError {
         error: anni_flac::error::FlacError,
     },
 
-    // TODO: print full string
-    #[error("Failed to extract album info from dir name")]
-    FailedToExtractAlbumInfo,
+    #[error("Failed to extract album info from dir name {name}: {error}")]
+    FailedToExtractAlbumInfo {
+        name: String,
+        error: Box<dyn std::error::Error + Send + Sync>,
+    },
 
     #[error("Unexpected file {0} found.")]
     UnexpectedFile(PathBuf),
@@ -69,6 +77,43 @@ pub enum WorkspaceError {
     #[error("Publish target directory {0} was not found.")]
     PublishTargetNotFound(PathBuf),
 
+    #[error("Failed to move {path} to trash: {error}")]
+    TrashError {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+
+    #[error("Disc count mismatch at {path}: expected {expected}, found {actual}")]
+    DiscMismatch {
+        path: PathBuf,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[error("Track count mismatch at {path}: expected {expected}, found {actual}")]
+    TrackMismatch {
+        path: PathBuf,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[error(
+        "Failed to get track number of {0}, neither file name nor TRACKNUMBER tag is a number"
+    )]
+    UnknownTrackNumber(PathBuf),
+
+    #[error("Track {missing} of disc {disc} is missing")]
+    TrackGap { disc: usize, missing: usize },
+
+    #[error("Disc {missing} is missing")]
+    DiscGap { missing: usize },
+
+    #[error("Track {number} of disc {disc} appears more than once")]
+    DuplicatedTrack { disc: usize, number: usize },
+
+    #[error("Disc {number} appears more than once")]
+    DuplicatedDisc { number: usize },
+
     #[error(transparent)]
     ApplyError(#[from] AlbumApplyError),
 }
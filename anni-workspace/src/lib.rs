@@ -1,13 +1,14 @@
 pub mod config;
 mod error;
+mod publish;
 mod state;
 mod utils;
 
 use crate::config::WorkspaceConfig;
 use anni_common::fs;
-use anni_metadata::model::AnniDate;
-use anni_repo::library::file_name;
-use anni_repo::models::ApplyMetadata;
+use anni_metadata::model::{Album, AlbumInfo, AnniDate, Disc, DiscInfo, UNKNOWN_ARTIST};
+use anni_repo::library::{file_name, AlbumFolderInfo, DiscFolderInfo};
+use anni_repo::models::{ApplyMetadata, RepoTrack, TrackChange};
 use anni_repo::RepositoryManager;
 use config::LibraryConfig;
 use std::borrow::Cow;
@@ -16,9 +17,12 @@ use std::num::NonZeroU8;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use utils::lock::WorkspaceAlbumLock;
+use utils::numbering::{contiguous_count, disc_number, track_number_or_tag, NumberingError};
+use utils::parallel::parallel_map;
 use uuid::Uuid;
 
 pub use error::WorkspaceError;
+pub use publish::*;
 pub use state::*;
 
 const IGNORED_LIST: [&str; 2] = [
@@ -171,9 +175,13 @@ impl AnniWorkspace {
                     if !path.join(".album").exists() {
                         // symlink is broken
                         WorkspaceAlbumState::Dangling(path)
-                    } else if fs::read_dir(controlled_path)?.next().is_some() {
+                    } else if fs::read_dir(&controlled_path)?.next().is_some() {
                         // controlled part is not empty
-                        WorkspaceAlbumState::Committed(path)
+                        if AnniWorkspace::is_inconsistent(&path, &controlled_path)? {
+                            WorkspaceAlbumState::Inconsistent(path)
+                        } else {
+                            WorkspaceAlbumState::Committed(path)
+                        }
                     } else {
                         // controlled part is empty
                         WorkspaceAlbumState::Untracked(path)
@@ -325,6 +333,7 @@ impl AnniWorkspace {
     }
 }
 
+/// Album information extracted from folder name of an album, used by [AnniWorkspace::import_tags].
 pub struct ExtractedAlbumInfo<'a> {
     pub release_date: AnniDate,
     pub catalog: Cow<'a, str>,
@@ -369,22 +378,79 @@ impl AnniWorkspace {
             return Err(WorkspaceError::CoverNotFound(album_cover));
         }
 
+        let config = self.get_config()?;
+        let extensions = config.audio_extensions();
+        let is_audio = |path: &Path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| extensions.contains(&ext))
+        };
+        let audio_files = |dir: &Path| -> Result<Vec<PathBuf>, WorkspaceError> {
+            Ok(fs::read_dir(dir)?
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|path| is_audio(path))
+                .collect())
+        };
+
         // iterate over me.path to find all discs
-        let flac_in_album_root = fs::get_ext_file(&album_path, "flac", false)?.is_some();
+        let root_tracks = audio_files(&album_path)?;
         let mut discs = fs::get_subdirectories(&album_path)?;
+        let mut stray_tracks = Vec::new();
+
+        if config.strict_disc_structure() {
+            // if there's only one disc, then there should be no sub directories, [true, true]
+            // if there are multiple discs, then there should be no flac files in the root directory, [false, false]
+            // other conditions are invalid
+            if !root_tracks.is_empty() ^ discs.is_empty() {
+                // both files and discs are empty, or both are not empty
+                return Err(WorkspaceError::InvalidAlbumDiscStructure(
+                    album_path.clone(),
+                ));
+            }
+        } else {
+            // only subdirectories named `Disc N` with audio files are treated as discs,
+            // audio files in other subdirectories are stray tracks
+            let mut disc_dirs = Vec::with_capacity(discs.len());
+            for disc in discs {
+                let tracks = audio_files(&disc)?;
+                if tracks.is_empty() {
+                    continue;
+                }
+                if disc_number(&disc).is_some() {
+                    disc_dirs.push(disc);
+                } else {
+                    stray_tracks.extend(tracks);
+                }
+            }
+            discs = disc_dirs;
 
-        // if there's only one disc, then there should be no sub directories, [true, true]
-        // if there are multiple discs, then there should be no flac files in the root directory, [false, false]
-        // other conditions are invalid
-        if flac_in_album_root ^ discs.is_empty() {
-            // both files and discs are empty, or both are not empty
-            return Err(WorkspaceError::InvalidAlbumDiscStructure(
-                album_path.clone(),
-            ));
+            if discs.is_empty() && root_tracks.is_empty() {
+                return Err(WorkspaceError::InvalidAlbumDiscStructure(
+                    album_path.clone(),
+                ));
+            }
+            contiguous_count(discs.iter().filter_map(|disc| disc_number(disc)).collect())
+                .map_err(NumberingError::into_disc_error)?;
+
+            // multiple discs take precedence over loose audio files in album root
+            if !discs.is_empty() {
+                stray_tracks.extend(root_tracks);
+            }
+            if !stray_tracks.is_empty() {
+                alphanumeric_sort::sort_path_slice(&mut stray_tracks);
+                log::warn!(
+                    "Found {} stray audio file(s) outside of discs in album {}",
+                    stray_tracks.len(),
+                    album_path.display()
+                );
+            }
         }
 
         // add album as disc if there's only one disc
-        if flac_in_album_root {
+        let simplified = discs.is_empty();
+        if simplified {
             discs.push(album_path.clone());
         }
 
@@ -395,22 +461,8 @@ impl AnniWorkspace {
             .map(|(index, disc_path)| {
                 let index = index + 1;
 
-                // iterate over all flac files
-                let mut files = fs::read_dir(&disc_path)?
-                    .filter_map(|e| {
-                        e.ok().and_then(|e| {
-                            let path = e.path();
-                            if e.file_type().ok()?.is_file() {
-                                if let Some(ext) = path.extension() {
-                                    if ext == "flac" {
-                                        return Some(path);
-                                    }
-                                }
-                            }
-                            None
-                        })
-                    })
-                    .collect::<Vec<_>>();
+                // iterate over all audio files
+                let mut files = audio_files(&disc_path)?;
                 alphanumeric_sort::sort_path_slice(&mut files);
 
                 let disc_cover = AnniWorkspace::album_disc_cover_path(&disc_path);
@@ -430,8 +482,9 @@ impl AnniWorkspace {
         Ok(UntrackedWorkspaceAlbum {
             album_id: album.album_id,
             path: album_path,
-            simplified: flac_in_album_root,
+            simplified,
             discs,
+            stray_tracks,
         })
     }
 
@@ -443,10 +496,15 @@ impl AnniWorkspace {
         P: AsRef<Path>,
         V: FnOnce(&UntrackedWorkspaceAlbum) -> bool,
     {
-        let album = self.get_untracked_album_overview(path)?;
+        let mut album = self.get_untracked_album_overview(path)?;
+
+        // stray tracks would be left in album directory after commit, and block publishing
+        if !album.stray_tracks.is_empty() {
+            return Err(WorkspaceError::StrayTracks(album.stray_tracks));
+        }
 
         // validate album lock
-        let lock = WorkspaceAlbumLock::new(&album.path)?;
+        let mut lock = WorkspaceAlbumLock::new(&album.path)?;
 
         if let Some(validator) = validator {
             let pass = validator(&album);
@@ -455,6 +513,30 @@ impl AnniWorkspace {
             }
         }
 
+        // only flac files can be committed
+        if let Some(track) = album
+            .discs
+            .iter()
+            .flat_map(|disc| disc.tracks.iter())
+            .find(|track| track.extension().map_or(true, |ext| ext != "flac"))
+        {
+            return Err(WorkspaceError::UnconvertedTrack(track.clone()));
+        }
+
+        // order tracks by numbers in file names like `01. Title.flac`, or TRACKNUMBER tags if files are not numbered,
+        // and make sure there's no gap between them
+        for disc in album.discs.iter_mut() {
+            let mut tracks = disc
+                .tracks
+                .drain(..)
+                .map(|track| Ok((track_number_or_tag(&track)?, track)))
+                .collect::<Result<Vec<_>, WorkspaceError>>()?;
+            tracks.sort_by_key(|(number, _)| *number);
+            contiguous_count(tracks.iter().map(|(number, _)| *number).collect())
+                .map_err(|e| e.into_track_error(disc.index))?;
+            disc.tracks = tracks.into_iter().map(|(_, track)| track).collect();
+        }
+
         let album_id = album.album_id;
         let album_path = album.path;
 
@@ -462,9 +544,52 @@ impl AnniWorkspace {
         // 1. lock album
         lock.lock()?;
 
-        // 2. copy or move album cover
-        let album_cover = AnniWorkspace::album_disc_cover_path(&album_path);
+        // 2. move tracks in parallel
         let album_controlled_path = self.get_album_controlled_path(&album_id)?;
+        let mut tracks = Vec::new();
+        for disc in album.discs.iter() {
+            let disc_controlled_path = album_controlled_path.join(disc.index.to_string());
+            fs::create_dir_all(&disc_controlled_path)?;
+
+            for (index, track_path) in disc.tracks.iter().enumerate() {
+                let index = index + 1;
+                let track_controlled_path = disc_controlled_path.join(format!("{index}.flac"));
+                tracks.push((track_path.as_path(), track_controlled_path));
+            }
+        }
+        let results = parallel_map(&tracks, |(track_path, track_controlled_path)| {
+            AnniWorkspace::move_and_link(track_path, track_controlled_path)
+        });
+        let mut moved = Vec::new();
+        let mut error = None;
+        for (result, track) in results.into_iter().zip(tracks.iter()) {
+            match result {
+                Ok(()) => moved.push(track),
+                Err(e) => {
+                    error.get_or_insert(e);
+                }
+            }
+        }
+        if let Some(error) = error {
+            // roll back moved tracks to keep the album untracked
+            for (track_path, track_controlled_path) in moved {
+                if let Err(e) = fs::remove_file(track_path, false)
+                    .and_then(|_| fs::rename(track_controlled_path, track_path))
+                {
+                    log::error!(
+                        "Failed to roll back {}: {e}",
+                        track_controlled_path.display()
+                    );
+                }
+            }
+            for disc in album.discs.iter() {
+                let _ = fs::remove_dir(album_controlled_path.join(disc.index.to_string()));
+            }
+            return Err(error);
+        }
+
+        // 3. copy or move album cover
+        let album_cover = AnniWorkspace::album_disc_cover_path(&album_path);
         let album_cover_controlled = AnniWorkspace::album_disc_cover_path(&album_controlled_path);
         if album.simplified {
             // cover might be used by discs, copy it
@@ -475,20 +600,9 @@ impl AnniWorkspace {
             fs::symlink_file(&album_cover_controlled, &album_cover)?;
         }
 
-        // 3. move discs
+        // 4. move disc covers
         for disc in album.discs.iter() {
             let disc_controlled_path = album_controlled_path.join(disc.index.to_string());
-            fs::create_dir_all(&disc_controlled_path)?;
-
-            // move tracks
-            for (index, track_path) in disc.tracks.iter().enumerate() {
-                let index = index + 1;
-                let track_controlled_path = disc_controlled_path.join(format!("{index}.flac"));
-                fs::rename(track_path, &track_controlled_path)?;
-                fs::symlink_file(&track_controlled_path, track_path)?;
-            }
-
-            // move disc cover
             let disc_cover_controlled_path =
                 AnniWorkspace::album_disc_cover_path(&disc_controlled_path);
             fs::rename(&disc.cover, &disc_cover_controlled_path)?;
@@ -498,14 +612,27 @@ impl AnniWorkspace {
         Ok(album_id)
     }
 
+    /// Move file at `path` to `controlled_path`, and create a symlink at `path` pointing to it.
+    ///
+    /// The file is moved back if the symlink can not be created.
+    fn move_and_link(path: &Path, controlled_path: &Path) -> Result<(), WorkspaceError> {
+        fs::rename(path, controlled_path)?;
+        if let Err(e) = fs::symlink_file(controlled_path, path) {
+            fs::rename(controlled_path, path)?;
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
     pub fn revert<P>(&self, path: P) -> Result<(), WorkspaceError>
     where
         P: AsRef<Path>,
     {
+        let use_trash = self.get_config()?.use_trash();
         let album = self.get_workspace_album(path)?;
         match album.state {
             WorkspaceAlbumState::Committed(album_path) => {
-                let lock = WorkspaceAlbumLock::new(&album_path)?;
+                let mut lock = WorkspaceAlbumLock::new(&album_path)?;
                 lock.lock()?;
 
                 let album_controlled_path = self.get_album_controlled_path(&album.album_id)?;
@@ -514,7 +641,7 @@ impl AnniWorkspace {
                 AnniWorkspace::recover_symlinks(&album_path)?;
 
                 // remove and re-create controlled album path
-                fs::remove_dir_all(&album_controlled_path, true)?;
+                AnniWorkspace::remove_dir_all(&album_controlled_path, use_trash)?;
                 fs::create_dir_all(&album_controlled_path)?;
 
                 Ok(())
@@ -523,6 +650,21 @@ impl AnniWorkspace {
         }
     }
 
+    /// Remove directory at `path`, moving it to trash if `use_trash` is `true`.
+    ///
+    /// Failures of trash are reported as [WorkspaceError::TrashError].
+    fn remove_dir_all<P: AsRef<Path>>(path: P, use_trash: bool) -> Result<(), WorkspaceError> {
+        let path = path.as_ref();
+        if use_trash {
+            fs::remove_dir_all(path, true).map_err(|error| WorkspaceError::TrashError {
+                path: path.to_path_buf(),
+                error,
+            })
+        } else {
+            Ok(fs::remove_dir_all(path, false)?)
+        }
+    }
+
     fn recover_symlinks<P: AsRef<Path>>(path: P) -> Result<(), WorkspaceError> {
         log::debug!("Recovering path: {}", path.as_ref().display());
         let metadata = fs::symlink_metadata(path.as_ref())?;
@@ -548,6 +690,327 @@ impl AnniWorkspace {
         Ok(())
     }
 
+    /// Check whether a committed album was left in an intermediate state, e.g. `commit` was interrupted.
+    ///
+    /// Covers are moved after all tracks on commit, so a missing cover in controlled part means commit did not finish.
+    fn is_inconsistent(album_path: &Path, controlled_path: &Path) -> Result<bool, WorkspaceError> {
+        if !AnniWorkspace::album_disc_cover_path(controlled_path).exists() {
+            return Ok(true);
+        }
+        for disc in fs::get_subdirectories(controlled_path)? {
+            if !AnniWorkspace::album_disc_cover_path(&disc).exists() {
+                return Ok(true);
+            }
+        }
+        AnniWorkspace::has_partial_tracks(album_path)
+    }
+
+    /// Check whether there are broken symlinks in user part, or directories with both flac files and symlinks to them.
+    fn has_partial_tracks(path: &Path) -> Result<bool, WorkspaceError> {
+        let mut linked = false;
+        let mut unlinked = false;
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            if entry.file_name() == ".album" {
+                continue;
+            }
+
+            let path = entry.path();
+            let is_flac = path.extension().is_some_and(|ext| ext == "flac");
+            let file_type = entry.file_type()?;
+            if file_type.is_symlink() {
+                if !path.exists() {
+                    // symlink is broken
+                    return Ok(true);
+                }
+                linked |= is_flac;
+            } else if !file_type.is_dir() {
+                unlinked |= is_flac;
+            } else if AnniWorkspace::has_partial_tracks(&path)? {
+                return Ok(true);
+            }
+        }
+        Ok(linked && unlinked)
+    }
+
+    /// Repair an [Inconsistent](WorkspaceAlbumState::Inconsistent) album.
+    ///
+    /// All files in controlled part are moved back to user part first.
+    /// If every track was recovered to its original path, the album is committed again.
+    /// Otherwise, it's left `Untracked` for users to check.
+    ///
+    /// `Inconsistent` -> `Committed` | `Untracked`
+    pub fn repair_album<P>(&self, path: P) -> Result<WorkspaceAlbumState, WorkspaceError>
+    where
+        P: AsRef<Path>,
+    {
+        let config = self.get_config()?;
+        let use_trash = config.use_trash();
+        let album = self.get_workspace_album(path)?;
+        let album_path = match album.state {
+            WorkspaceAlbumState::Inconsistent(album_path) => album_path,
+            state => return Err(WorkspaceError::InvalidAlbumState(state)),
+        };
+        let album_controlled_path = self.get_album_controlled_path(&album.album_id)?;
+
+        {
+            // lock file left by the interrupted commit is taken over,
+            // but albums locked by running processes are not touched
+            let mut lock = WorkspaceAlbumLock::new(&album_path)?;
+            lock.lock()?;
+
+            // 1. move linked files back
+            let mut recovered = AnniWorkspace::recover_links(&album_path)?;
+            // 2. move files without links back
+            recovered &= AnniWorkspace::recover_orphans(
+                &album_path,
+                &album_controlled_path,
+                config.strict_disc_structure(),
+            )?;
+
+            // 3. remove and re-create controlled album path
+            AnniWorkspace::remove_dir_all(&album_controlled_path, use_trash)?;
+            fs::create_dir_all(&album_controlled_path)?;
+
+            if !recovered {
+                log::warn!(
+                    "Some tracks of album at {} were not recovered to their original path, please check before committing.",
+                    album_path.display()
+                );
+                return Ok(WorkspaceAlbumState::Untracked(album_path));
+            }
+        }
+
+        match self.commit(&album_path, None::<fn(&UntrackedWorkspaceAlbum) -> bool>) {
+            Ok(_) => Ok(WorkspaceAlbumState::Committed(album_path)),
+            Err(e) => {
+                log::warn!(
+                    "Failed to commit album at {} after repair: {e}",
+                    album_path.display()
+                );
+                Ok(WorkspaceAlbumState::Untracked(album_path))
+            }
+        }
+    }
+
+    /// Move linked files in user part back, and remove broken symlinks.
+    ///
+    /// Returns `false` if any broken symlink to flac file was removed.
+    fn recover_links(path: &Path) -> Result<bool, WorkspaceError> {
+        let mut recovered = true;
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            if entry.file_name() == ".album" {
+                continue;
+            }
+
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            if file_type.is_symlink() {
+                if path.exists() {
+                    AnniWorkspace::recover_symlinks(&path)?;
+                } else {
+                    log::warn!("Removing broken symlink: {}", path.display());
+                    fs::remove_file(&path, false)?;
+                    if path.extension().is_some_and(|ext| ext == "flac") {
+                        recovered = false;
+                    }
+                }
+            } else if file_type.is_dir() {
+                recovered &= AnniWorkspace::recover_links(&path)?;
+            }
+        }
+        Ok(recovered)
+    }
+
+    /// Move files left in controlled part back to user part.
+    ///
+    /// Covers are moved back to album or disc directory, unless they already exist there.
+    /// Tracks are moved back to the corresponding disc directory with their controlled name.
+    ///
+    /// Disc directories are found in the same way as `get_untracked_album_overview` with `strict` disc structure.
+    ///
+    /// Returns `false` if any track was moved back.
+    fn recover_orphans(
+        album_path: &Path,
+        controlled_path: &Path,
+        strict: bool,
+    ) -> Result<bool, WorkspaceError> {
+        let mut recovered = true;
+
+        let album_cover = AnniWorkspace::album_disc_cover_path(controlled_path);
+        if album_cover.exists() {
+            AnniWorkspace::recover_orphan(
+                &album_cover,
+                &AnniWorkspace::album_disc_cover_path(album_path),
+            )?;
+        }
+
+        // disc directories in user part
+        let mut discs = fs::get_subdirectories(album_path)?;
+        if strict {
+            alphanumeric_sort::sort_path_slice(&mut discs);
+        } else {
+            // only `Disc N` directories are discs, and they may have no track left
+            discs.retain(|disc| disc_number(disc).is_some());
+        }
+        let user_disc = |index: usize| -> Option<&Path> {
+            if strict {
+                discs.get(index.checked_sub(1)?).map(PathBuf::as_path)
+            } else {
+                discs
+                    .iter()
+                    .find(|disc| disc_number(disc) == Some(index))
+                    .map(PathBuf::as_path)
+            }
+        };
+
+        for disc in fs::get_subdirectories(controlled_path)? {
+            let user_disc = file_name(&disc)?
+                .parse::<usize>()
+                .ok()
+                .and_then(user_disc)
+                .unwrap_or(album_path);
+
+            for entry in fs::read_dir(&disc)? {
+                let path = entry?.path();
+                let name = file_name(&path)?;
+                if name == "cover.jpg" {
+                    AnniWorkspace::recover_orphan(
+                        &path,
+                        &AnniWorkspace::album_disc_cover_path(user_disc),
+                    )?;
+                } else {
+                    let mut target = user_disc.join(&name);
+                    if fs::symlink_metadata(&target).is_ok() {
+                        target = user_disc.join(format!("recovered-{name}"));
+                    }
+                    log::warn!("Recovering {} to {}", path.display(), target.display());
+                    fs::rename(&path, &target)?;
+                    recovered = false;
+                }
+            }
+        }
+
+        Ok(recovered)
+    }
+
+    /// Move `path` to `target`, or remove it if `target` exists.
+    fn recover_orphan(path: &Path, target: &Path) -> Result<(), WorkspaceError> {
+        if target.exists() {
+            // cover was copied instead of moved
+            fs::remove_file(path, false)?;
+        } else {
+            fs::rename(path, target)?;
+        }
+        Ok(())
+    }
+
+    /// Read discs and tracks of a committed album from tags of its flac files.
+    ///
+    /// Discs and tracks must be numbered contiguously from 1, otherwise [WorkspaceError::DiscGap]
+    /// or [WorkspaceError::TrackGap] is returned. Duplicated numbers are rejected by
+    /// [WorkspaceError::DuplicatedDisc] or [WorkspaceError::DuplicatedTrack].
+    /// Catalog of discs are left empty.
+    pub fn read_discs(&self, album_id: &Uuid) -> Result<Vec<Disc>, WorkspaceError> {
+        let album_controlled_path = self.get_album_controlled_path(album_id)?;
+        let disc_numbers = fs::read_dir(&album_controlled_path)?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .filter_map(|e| e.file_name().to_str()?.parse().ok())
+            .collect();
+        let disc_count = contiguous_count(disc_numbers).map_err(NumberingError::into_disc_error)?;
+
+        let mut discs = Vec::with_capacity(disc_count);
+        for disc_id in 1..=disc_count {
+            let disc_path = album_controlled_path.join(disc_id.to_string());
+            let track_numbers = fs::get_ext_files(&disc_path, "flac", false)?
+                .iter()
+                .filter_map(|p| p.file_stem()?.to_str()?.parse().ok())
+                .collect();
+            let track_count =
+                contiguous_count(track_numbers).map_err(|e| e.into_track_error(disc_id))?;
+
+            let mut tracks = Vec::with_capacity(track_count);
+            for track_id in 1..=track_count {
+                let track_path = disc_path.join(format!("{track_id}.flac"));
+                let flac = anni_flac::FlacHeader::from_file(&track_path).map_err(|error| {
+                    WorkspaceError::FlacError {
+                        path: track_path,
+                        error,
+                    }
+                })?;
+                let track: RepoTrack = flac.into();
+                tracks.push(track.0)
+            }
+            discs.push(Disc::new(
+                DiscInfo::new(String::new(), None, None, None, None, Default::default()),
+                tracks,
+            ));
+        }
+        Ok(discs)
+    }
+
+    /// Import tags of a committed album into metadata repository.
+    ///
+    /// Album information is extracted from folder name by `extractor`, and tracks are read from tags of flac files.
+    /// If an album with the same `album_id` exists in repository, it's left untouched to keep changes made to it.
+    /// Otherwise, a new album is added, and `allow_duplicate` decides whether albums with the same catalog are allowed.
+    pub fn import_tags<P, E, R>(
+        &self,
+        album_path: P,
+        extractor: E,
+        allow_duplicate: bool,
+    ) -> Result<Uuid, WorkspaceError>
+    where
+        P: AsRef<Path>,
+        E: FnOnce(&str) -> Result<ExtractedAlbumInfo, R>,
+        R: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let album_id = self.get_album_id(album_path.as_ref())?;
+        let folder_name = file_name(album_path.as_ref())?;
+        let ExtractedAlbumInfo {
+            release_date,
+            catalog,
+            title,
+            edition,
+        } = extractor(&folder_name).map_err(|error| WorkspaceError::FailedToExtractAlbumInfo {
+            name: folder_name.clone(),
+            error: error.into(),
+        })?;
+
+        let repo = self.to_repository_manager()?;
+        if let Some(path) = repo.find_album_path(&album_id, &catalog)? {
+            // album was imported before
+            log::warn!(
+                "Album {album_id} exists at {}, skipped importing",
+                path.display()
+            );
+            return Ok(album_id);
+        }
+
+        let mut discs = self.read_discs(&album_id)?;
+        for disc in discs.iter_mut() {
+            disc.catalog += &catalog;
+        }
+
+        let album = Album::new(
+            AlbumInfo {
+                album_id,
+                title: title.to_string(),
+                edition: edition.map(|e| e.to_string()),
+                artist: UNKNOWN_ARTIST.to_string(),
+                release_date,
+                catalog: catalog.to_string(),
+                ..Default::default()
+            },
+            discs,
+        );
+        repo.add_album(album, allow_duplicate)?;
+
+        Ok(album_id)
+    }
+
     pub fn apply_tags<P>(&self, album_path: P, detailed: bool) -> Result<(), WorkspaceError>
     where
         P: AsRef<Path>,
@@ -567,7 +1030,37 @@ impl AnniWorkspace {
         Ok(())
     }
 
-    pub fn publish<P>(&self, album_path: P, soft: bool) -> Result<(), WorkspaceError>
+    /// Compute changes [AnniWorkspace::apply_tags] would make to tracks, without writing them.
+    ///
+    /// Tracks which would be left untouched are not included.
+    pub fn tag_changes<P>(
+        &self,
+        album_path: P,
+        detailed: bool,
+    ) -> Result<Vec<TrackChange>, WorkspaceError>
+    where
+        P: AsRef<Path>,
+    {
+        let album_id = self.get_album_id(album_path)?;
+        let controlled_album_path = self.get_album_controlled_path(&album_id)?;
+
+        let repo = self.to_repository_manager()?;
+        let repo = repo.into_owned_manager()?;
+
+        let album = repo
+            .album(&album_id)
+            .ok_or(WorkspaceError::AlbumNotFound(album_id))?;
+        let mut changes = album.strict_changes(controlled_album_path, detailed)?;
+        changes.retain(|change| !change.is_empty());
+
+        Ok(changes)
+    }
+
+    /// Compute operations to publish the album at `album_path` without touching the filesystem.
+    ///
+    /// Albums are published in strict layout if `layers` is set for the target library,
+    /// otherwise in convention layout.
+    pub fn publish_plan<P>(&self, album_path: P, soft: bool) -> Result<PublishPlan, WorkspaceError>
     where
         P: AsRef<Path>,
     {
@@ -603,57 +1096,242 @@ impl AnniWorkspace {
                 }
 
                 // TODO: validate whether track number matches in the repository
-                if let Some(layers) = publish_to.layers {
+                let mut plan = if let Some(layers) = publish_to.layers {
                     // publish as strict
-                    self.do_publish_strict(album_path, publish_to, layers, soft)?;
+                    self.strict_publish_plan(&album_path, publish_to, layers, soft)?
                 } else {
                     // publish as convention
-                    unimplemented!("Publishing as convention is not supported yet. Add `layers` to your library config")
-                }
-
-                Ok(())
+                    self.convention_publish_plan(&album_path, publish_to, soft)?
+                };
+
+                // clean album folder
+                plan.operations.push(PublishOperation::Remove {
+                    path: album_path,
+                    trash: config.use_trash(),
+                });
+                Ok(plan)
             }
             state => Err(WorkspaceError::InvalidAlbumState(state)),
         }
     }
 
-    fn do_publish_strict<P>(
+    pub fn publish<P>(&self, album_path: P, soft: bool) -> Result<(), WorkspaceError>
+    where
+        P: AsRef<Path>,
+    {
+        let plan = self.publish_plan(album_path, soft)?;
+        for operation in plan.operations {
+            match operation {
+                PublishOperation::Copy {
+                    source,
+                    destination,
+                } => {
+                    AnniWorkspace::create_parent_dir(&destination)?;
+                    if source.is_dir() {
+                        fs::copy_dir(source, destination)?;
+                    } else {
+                        fs::copy(source, destination)?;
+                    }
+                }
+                PublishOperation::Move {
+                    source,
+                    destination,
+                } => {
+                    AnniWorkspace::create_parent_dir(&destination)?;
+                    if source.is_dir() {
+                        fs::move_dir(source, destination)?;
+                    } else {
+                        fs::move_file(source, destination)?;
+                    }
+                }
+                PublishOperation::Mark { path } => fs::write(path, "")?,
+                PublishOperation::Remove { path, trash } => {
+                    AnniWorkspace::remove_dir_all(path, trash)?
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn create_parent_dir(path: &Path) -> Result<(), WorkspaceError> {
+        let parent = path.parent().expect("Invalid path");
+        if !parent.exists() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(())
+    }
+
+    fn strict_publish_plan(
         &self,
-        album_path: P,
+        album_path: &Path,
         publish_to: &LibraryConfig,
         layers: usize,
         soft: bool,
-    ) -> Result<(), WorkspaceError>
-    where
-        P: AsRef<Path>,
-    {
-        let album_id = self.get_album_id(album_path.as_ref())?;
+    ) -> Result<PublishPlan, WorkspaceError> {
+        let album_id = self.get_album_id(album_path)?;
         let album_controlled_path = self.get_album_controlled_path(&album_id)?;
 
         // publish as strict
         // 1. get destination path
         let result_path =
             AnniWorkspace::strict_album_path(publish_to.path.clone(), &album_id, layers);
-        let result_parent = result_path.parent().expect("Invalid path");
 
-        // 2. create parent directory
-        if !result_parent.exists() {
-            fs::create_dir_all(&result_parent)?;
+        // 2. move/copy album
+        let operations = if soft {
+            vec![
+                // copy the whole album
+                PublishOperation::Copy {
+                    source: album_controlled_path.clone(),
+                    destination: result_path.clone(),
+                },
+                // add soft published mark
+                PublishOperation::Mark {
+                    path: album_controlled_path.join(".publish"),
+                },
+            ]
+        } else {
+            // move directory
+            vec![PublishOperation::Move {
+                source: album_controlled_path,
+                destination: result_path.clone(),
+            }]
+        };
+
+        Ok(PublishPlan {
+            album_id,
+            destination: result_path,
+            operations,
+        })
+    }
+
+    fn convention_publish_plan(
+        &self,
+        album_path: &Path,
+        publish_to: &LibraryConfig,
+        soft: bool,
+    ) -> Result<PublishPlan, WorkspaceError> {
+        let album_id = self.get_album_id(album_path)?;
+        let album_controlled_path = self.get_album_controlled_path(&album_id)?;
+
+        let repo = self.to_repository_manager()?.into_owned_manager()?;
+        let album = repo
+            .album(&album_id)
+            .ok_or(WorkspaceError::AlbumNotFound(album_id))?;
+
+        // 1. validate disc and track count before moving anything
+        let disc_total = album.discs_len();
+        let discs = fs::get_subdirectories(&album_controlled_path)?;
+        if discs.len() != disc_total {
+            return Err(WorkspaceError::DiscMismatch {
+                path: album_controlled_path,
+                expected: disc_total,
+                actual: discs.len(),
+            });
+        }
+        for (index, disc) in album.iter().enumerate() {
+            let disc_controlled_path = album_controlled_path.join((index + 1).to_string());
+            let tracks = fs::get_ext_files(&disc_controlled_path, "flac", false)?.len();
+            if tracks != disc.tracks_len() {
+                return Err(WorkspaceError::TrackMismatch {
+                    path: disc_controlled_path,
+                    expected: disc.tracks_len(),
+                    actual: tracks,
+                });
+            }
+        }
+
+        // 2. get destination path
+        let folder_name = AlbumFolderInfo {
+            release_date: album.release_date().clone(),
+            catalog: album.catalog().to_string(),
+            title: album.title_raw().to_string(),
+            edition: album.edition().map(|e| e.to_string()),
+            disc_count: disc_total,
+        };
+        let result_path = publish_to.path.join(folder_name.to_string());
+        if result_path.exists() {
+            return Err(WorkspaceError::AlbumExists {
+                album_id,
+                path: result_path,
+            });
         }
 
         // 3. move/copy album
-        if soft {
-            // copy the whole album
-            fs::copy_dir(&album_controlled_path, &result_path)?;
+        let mut operations = Vec::new();
+        let mut transfer = |source: PathBuf, destination: PathBuf| {
+            operations.push(if soft {
+                PublishOperation::Copy {
+                    source,
+                    destination,
+                }
+            } else {
+                PublishOperation::Move {
+                    source,
+                    destination,
+                }
+            })
+        };
+
+        transfer(
+            AnniWorkspace::album_disc_cover_path(&album_controlled_path),
+            AnniWorkspace::album_disc_cover_path(&result_path),
+        );
+        for (index, disc) in album.iter().enumerate() {
+            let disc_id = index + 1;
+            let disc_controlled_path = album_controlled_path.join(disc_id.to_string());
+            let disc_path = if disc_total > 1 {
+                let folder_name = DiscFolderInfo {
+                    info: DiscInfo::new(
+                        disc.catalog().to_string(),
+                        Some(disc.title().to_string()),
+                        None,
+                        None,
+                        None,
+                        Default::default(),
+                    ),
+                    disc_id,
+                };
+                let disc_path = result_path.join(folder_name.to_string());
+                transfer(
+                    AnniWorkspace::album_disc_cover_path(&disc_controlled_path),
+                    AnniWorkspace::album_disc_cover_path(&disc_path),
+                );
+                disc_path
+            } else {
+                // disc cover is the same as album cover
+                result_path.clone()
+            };
+
+            for (index, track) in disc.iter().enumerate() {
+                let track_id = index + 1;
+                transfer(
+                    disc_controlled_path.join(format!("{track_id}.flac")),
+                    disc_path.join(format!(
+                        "{track_id:02}. {title}.flac",
+                        title = track.title().replace('/', "／")
+                    )),
+                );
+            }
+        }
+
+        operations.push(if soft {
             // add soft published mark
-            fs::write(album_controlled_path.join(".publish"), "")?;
+            PublishOperation::Mark {
+                path: album_controlled_path.join(".publish"),
+            }
         } else {
-            // move directory
-            fs::move_dir(&album_controlled_path, &result_path)?;
-        }
-        // 4. clean album folder
-        fs::remove_dir_all(&album_path, true)?; // TODO: add an option to disable trash feature
+            // all files have been moved
+            PublishOperation::Remove {
+                path: album_controlled_path,
+                trash: false,
+            }
+        });
 
-        Ok(())
+        Ok(PublishPlan {
+            album_id,
+            destination: result_path,
+            operations,
+        })
     }
 }
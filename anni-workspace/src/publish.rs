@@ -0,0 +1,60 @@
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// A single step of [PublishPlan].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum PublishOperation {
+    /// Copy file or directory from `source` to `destination`.
+    Copy {
+        source: PathBuf,
+        destination: PathBuf,
+    },
+    /// Move file or directory from `source` to `destination`.
+    ///
+    /// Falls back to copy and remove if `source` and `destination` are on different filesystems.
+    Move {
+        source: PathBuf,
+        destination: PathBuf,
+    },
+    /// Write soft published mark at `path`.
+    Mark { path: PathBuf },
+    /// Remove directory at `path`, or move it to trash if `trash` is `true`.
+    Remove { path: PathBuf, trash: bool },
+}
+
+impl Display for PublishOperation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PublishOperation::Copy {
+                source,
+                destination,
+            } => write!(f, "copy {} -> {}", source.display(), destination.display()),
+            PublishOperation::Move {
+                source,
+                destination,
+            } => write!(f, "move {} -> {}", source.display(), destination.display()),
+            PublishOperation::Mark { path } => write!(f, "mark {}", path.display()),
+            PublishOperation::Remove { path, trash: true } => {
+                write!(f, "trash {}", path.display())
+            }
+            PublishOperation::Remove { path, trash: false } => {
+                write!(f, "remove {}", path.display())
+            }
+        }
+    }
+}
+
+/// Operations to publish an album, computed by [crate::AnniWorkspace::publish_plan].
+///
+/// Nothing is changed on disk until the plan is executed.
+#[derive(Debug, Clone, Serialize)]
+pub struct PublishPlan {
+    pub album_id: Uuid,
+    /// Path of the published album in audio library.
+    pub destination: PathBuf,
+    /// Operations to perform, in order.
+    pub operations: Vec<PublishOperation>,
+}
@@ -17,6 +17,10 @@ pub struct UntrackedWorkspaceAlbum {
     /// This is called `simplified` album structure.
     pub simplified: bool,
     pub discs: Vec<UntrackedWorkspaceDisc>,
+    /// Audio files which are not part of any disc, in album directory of a multi-disc album,
+    /// or in subdirectories not named like `Disc N`.
+    /// Albums with stray tracks can not be committed.
+    pub stray_tracks: Vec<PathBuf>,
 }
 
 pub struct UntrackedWorkspaceDisc {
@@ -47,4 +51,7 @@ pub enum WorkspaceAlbumState {
     Dangling(PathBuf),
     /// User part of an album does not exist, and controlled part is empty.
     Garbage,
+    /// Album was partially committed, e.g. `commit` was interrupted.
+    /// User part contains both files and symlinks, or covers are missing in controlled part.
+    Inconsistent(PathBuf),
 }
@@ -1,9 +1,17 @@
+use fs4::FileExt;
+use std::fs::{File, OpenOptions};
 use std::path::{Path, PathBuf};
 
 use crate::WorkspaceError;
 
+/// Lock of an album directory, backed by `.album.lock` in album directory.
+///
+/// The lock file is locked by OS while the lock is held, so lock files left by
+/// interrupted processes are not treated as locked, and would be taken over.
 pub(crate) struct WorkspaceAlbumLock {
+    album_path: PathBuf,
     lock_path: PathBuf,
+    file: Option<File>,
 }
 
 impl WorkspaceAlbumLock {
@@ -11,24 +19,45 @@ impl WorkspaceAlbumLock {
     where
         P: AsRef<Path>,
     {
-        let lock_path = album_path.as_ref().join(".album.lock");
-        if lock_path.exists() {
-            return Err(WorkspaceError::AlbumLocked(
-                album_path.as_ref().to_path_buf(),
-            ));
-        }
+        let album_path = album_path.as_ref().to_path_buf();
+        let lock_path = album_path.join(".album.lock");
+        let mut lock = Self {
+            album_path,
+            lock_path,
+            file: None,
+        };
 
-        Ok(Self { lock_path })
+        if lock.lock_path.exists() {
+            // take over the lock if it was left by an interrupted process
+            lock.lock()?;
+        }
+        Ok(lock)
     }
 
-    pub fn lock(&self) -> std::io::Result<()> {
-        std::fs::File::create(&self.lock_path)?;
+    pub fn lock(&mut self) -> Result<(), WorkspaceError> {
+        if self.file.is_some() {
+            return Ok(());
+        }
+
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&self.lock_path)?;
+        if file.try_lock_exclusive().is_err() {
+            return Err(WorkspaceError::AlbumLocked(self.album_path.clone()));
+        }
+        self.file = Some(file);
         Ok(())
     }
 }
 
 impl Drop for WorkspaceAlbumLock {
     fn drop(&mut self) {
-        let _ = std::fs::remove_file(&self.lock_path);
+        // only remove lock file held by us
+        if let Some(file) = self.file.take() {
+            let _ = std::fs::remove_file(&self.lock_path);
+            let _ = file.unlock();
+        }
     }
 }
@@ -1 +1,3 @@
 pub mod lock;
+pub mod numbering;
+pub mod parallel;
@@ -0,0 +1,91 @@
+use std::path::Path;
+
+use crate::WorkspaceError;
+
+/// Leading number in file name of a track, e.g. `1` for `01. Title.flac`.
+pub(crate) fn track_number(path: &Path) -> Option<usize> {
+    let stem = path.file_stem()?.to_str()?;
+    leading_number(stem)
+}
+
+/// Number of a track, read from its file name, or `TRACKNUMBER` tag if file name does not start with a number.
+pub(crate) fn track_number_or_tag(path: &Path) -> Result<usize, WorkspaceError> {
+    if let Some(number) = track_number(path) {
+        return Ok(number);
+    }
+
+    let flac =
+        anni_flac::FlacHeader::from_file(path).map_err(|error| WorkspaceError::FlacError {
+            path: path.to_path_buf(),
+            error,
+        })?;
+    flac.comments()
+        .and_then(|comments| {
+            let comments = comments.to_map();
+            // TRACKNUMBER may be written as `3/12`
+            leading_number(comments.get("TRACKNUMBER")?.value())
+        })
+        .ok_or_else(|| WorkspaceError::UnknownTrackNumber(path.to_path_buf()))
+}
+
+fn leading_number(s: &str) -> Option<usize> {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    s[..end].parse().ok()
+}
+
+/// Number of a disc directory named like `Disc 1`, case insensitive.
+pub(crate) fn disc_number(path: &Path) -> Option<usize> {
+    let name = path.file_name()?.to_str()?;
+    if !name.get(..4)?.eq_ignore_ascii_case("disc") {
+        return None;
+    }
+    let number = name[4..].trim_start();
+    if !number.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    number.parse().ok().filter(|number| *number > 0)
+}
+
+/// Why disc or track numbers are not a permutation of `1..=N`.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum NumberingError {
+    /// The first missing number.
+    Missing(usize),
+    /// The first number which appears more than once.
+    Duplicated(usize),
+}
+
+impl NumberingError {
+    pub(crate) fn into_disc_error(self) -> WorkspaceError {
+        match self {
+            NumberingError::Missing(missing) => WorkspaceError::DiscGap { missing },
+            NumberingError::Duplicated(number) => WorkspaceError::DuplicatedDisc { number },
+        }
+    }
+
+    pub(crate) fn into_track_error(self, disc: usize) -> WorkspaceError {
+        match self {
+            NumberingError::Missing(missing) => WorkspaceError::TrackGap { disc, missing },
+            NumberingError::Duplicated(number) => WorkspaceError::DuplicatedTrack { disc, number },
+        }
+    }
+}
+
+/// Make sure `numbers` is a permutation of `1..=N`, and returns `N`.
+///
+/// If any number appears more than once, or is missing, the first of them is returned as error.
+pub(crate) fn contiguous_count(mut numbers: Vec<usize>) -> Result<usize, NumberingError> {
+    numbers.sort_unstable();
+    if let Some(window) = numbers.windows(2).find(|window| window[0] == window[1]) {
+        return Err(NumberingError::Duplicated(window[0]));
+    }
+
+    match numbers
+        .iter()
+        .zip(1..)
+        .find(|(number, expected)| **number != *expected)
+    {
+        Some((_, missing)) => Err(NumberingError::Missing(missing)),
+        None => Ok(numbers.len()),
+    }
+}
@@ -0,0 +1,191 @@
+use anni_common::fs;
+use anni_flac::blocks::{UserComment, UserCommentExt};
+use anni_flac::FlacHeader;
+use anni_workspace::{UntrackedWorkspaceAlbum, WorkspaceAlbumState, WorkspaceError};
+use common::TestWorkspace;
+use std::path::Path;
+
+mod common;
+
+const ALBUM_ID: &str = "6c1f0e2d-8a4b-4c3d-9e5f-7a6b5c4d3e2f";
+const TRACKS: usize = 8;
+
+/// Create a workspace with an untracked album with [TRACKS] tracks in album directory.
+fn untracked_album() -> TestWorkspace {
+    let ws = TestWorkspace::new("[workspace]\n", ALBUM_ID, "album");
+    ws.write_album("cover.jpg", "cover");
+    for i in 1..=TRACKS {
+        ws.write_album(&format!("{i:02}.flac"), &format!("track {i}"));
+    }
+    ws
+}
+
+#[test]
+fn test_commit() {
+    let TestWorkspace {
+        dir: _dir,
+        workspace,
+        album_path,
+        controlled_path,
+    } = untracked_album();
+    workspace
+        .commit(&album_path, None::<fn(&UntrackedWorkspaceAlbum) -> bool>)
+        .unwrap();
+
+    for i in 1..=TRACKS {
+        let track = album_path.join(format!("{i:02}.flac"));
+        assert!(track.is_symlink());
+        assert_eq!(
+            fs::read_to_string(controlled_path.join("1").join(format!("{i}.flac"))).unwrap(),
+            format!("track {i}")
+        );
+    }
+    assert!(matches!(
+        workspace.get_workspace_album(&album_path).unwrap().state,
+        WorkspaceAlbumState::Committed(_)
+    ));
+}
+
+#[test]
+fn test_commit_rollback() {
+    let TestWorkspace {
+        dir: _dir,
+        workspace,
+        album_path,
+        controlled_path,
+    } = untracked_album();
+
+    // block the destination of the 3rd track after validation, so that moving it fails
+    let blocked = controlled_path.join("1").join("3.flac");
+    let result = workspace.commit(
+        &album_path,
+        Some(|_: &UntrackedWorkspaceAlbum| {
+            fs::create_dir_all(&blocked).unwrap();
+            fs::write(blocked.join("blocked"), "").unwrap();
+            true
+        }),
+    );
+    assert!(result.is_err());
+
+    // all tracks are moved back
+    for i in 1..=TRACKS {
+        let track = album_path.join(format!("{i:02}.flac"));
+        assert!(!track.is_symlink());
+        assert_eq!(fs::read_to_string(track).unwrap(), format!("track {i}"));
+    }
+    assert!(!album_path.join("cover.jpg").is_symlink());
+    assert!(!album_path.join(".album.lock").exists());
+
+    // nothing but the blocking directory is left in controlled part
+    let controlled: Vec<_> =
+        fs::PathWalker::new(&controlled_path, true, false, Default::default()).collect();
+    assert_eq!(controlled, [blocked.join("blocked")]);
+}
+
+#[test]
+fn test_commit_track_gap() {
+    let TestWorkspace {
+        dir: _dir,
+        workspace,
+        album_path,
+        controlled_path,
+    } = untracked_album();
+    fs::remove_file(album_path.join("03.flac"), false).unwrap();
+
+    let result = workspace.commit(&album_path, None::<fn(&UntrackedWorkspaceAlbum) -> bool>);
+    assert!(matches!(
+        result,
+        Err(WorkspaceError::TrackGap {
+            disc: 1,
+            missing: 3
+        })
+    ));
+
+    // nothing was moved
+    assert!(!album_path.join("01.flac").is_symlink());
+    assert!(!controlled_path.join("1").exists());
+}
+
+#[test]
+fn test_commit_duplicated_track() {
+    let TestWorkspace {
+        dir: _dir,
+        workspace,
+        album_path,
+        controlled_path,
+    } = untracked_album();
+    std::fs::write(album_path.join("01 - copy.flac"), "track 1 copy").unwrap();
+
+    let result = workspace.commit(&album_path, None::<fn(&UntrackedWorkspaceAlbum) -> bool>);
+    assert!(matches!(
+        result,
+        Err(WorkspaceError::DuplicatedTrack { disc: 1, number: 1 })
+    ));
+
+    // nothing was moved
+    assert!(!album_path.join("01.flac").is_symlink());
+    assert!(!controlled_path.join("1").exists());
+}
+
+/// Copy a flac file to `path`, with its TRACKNUMBER set to `track_number` if given.
+fn write_flac(path: &Path, track_number: Option<usize>) {
+    std::fs::copy("../assets/1s-full.flac", path).unwrap();
+    let mut flac = FlacHeader::from_file(path).unwrap();
+    let comments = flac.comments_mut();
+    comments.clear();
+    if let Some(track_number) = track_number {
+        comments
+            .comments
+            .push(UserComment::track_number(track_number));
+    }
+    flac.save::<&Path>(None).unwrap();
+}
+
+#[test]
+fn test_commit_track_number_from_tag() {
+    let TestWorkspace {
+        dir: _dir,
+        workspace,
+        album_path,
+        controlled_path,
+    } = untracked_album();
+    fs::remove_file(album_path.join("03.flac"), false).unwrap();
+    // sorted after numbered tracks by file name
+    let third = album_path.join("Third.flac");
+    write_flac(&third, Some(3));
+    let content = std::fs::read(&third).unwrap();
+
+    workspace
+        .commit(&album_path, None::<fn(&UntrackedWorkspaceAlbum) -> bool>)
+        .unwrap();
+    assert!(third.is_symlink());
+    assert_eq!(
+        std::fs::read(controlled_path.join("1").join("3.flac")).unwrap(),
+        content
+    );
+    assert_eq!(
+        fs::read_to_string(controlled_path.join("1").join("8.flac")).unwrap(),
+        "track 8"
+    );
+}
+
+#[test]
+fn test_commit_unknown_track_number() {
+    let TestWorkspace {
+        dir: _dir,
+        workspace,
+        album_path,
+        controlled_path,
+    } = untracked_album();
+    fs::remove_file(album_path.join("03.flac"), false).unwrap();
+    let third = album_path.join("Third.flac");
+    write_flac(&third, None);
+
+    let result = workspace.commit(&album_path, None::<fn(&UntrackedWorkspaceAlbum) -> bool>);
+    assert!(matches!(
+        result,
+        Err(WorkspaceError::UnknownTrackNumber(path)) if path == third
+    ));
+    assert!(!third.is_symlink());
+    assert!(!controlled_path.join("1").exists());
+}
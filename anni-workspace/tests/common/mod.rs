@@ -0,0 +1,82 @@
+//! Helpers to build temporary workspaces for tests.
+#![allow(dead_code)]
+
+use anni_common::fs;
+use anni_workspace::AnniWorkspace;
+use std::path::PathBuf;
+use std::str::FromStr;
+use tempfile::TempDir;
+use uuid::Uuid;
+
+/// A temporary workspace with an album directory linked to its controlled part.
+pub struct TestWorkspace {
+    /// Root of the workspace, removed on drop.
+    pub dir: TempDir,
+    pub workspace: AnniWorkspace,
+    /// Album directory in workspace.
+    pub album_path: PathBuf,
+    /// Controlled part of the album in `.anni/objects`.
+    pub controlled_path: PathBuf,
+}
+
+impl TestWorkspace {
+    /// Create a workspace with `config` as `.anni/config.toml`, and an empty album directory `album_name`
+    /// which is linked to the controlled part of `album_id`.
+    pub fn new(config: &str, album_id: &str, album_name: &str) -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".anni")).unwrap();
+        fs::write(dir.path().join(".anni").join("config.toml"), config).unwrap();
+        let workspace = AnniWorkspace::open(dir.path()).unwrap();
+
+        let album_id = Uuid::from_str(album_id).unwrap();
+        let controlled_path = workspace.controlled_album_path(&album_id, 2);
+        fs::create_dir_all(&controlled_path).unwrap();
+
+        let album_path = dir.path().join(album_name);
+        fs::create_dir_all(&album_path).unwrap();
+        fs::symlink_dir(&controlled_path, album_path.join(".album")).unwrap();
+
+        Self {
+            dir,
+            workspace,
+            album_path,
+            controlled_path,
+        }
+    }
+
+    /// Replace `.anni/config.toml` with `config`.
+    pub fn write_config(&self, config: &str) {
+        fs::write(self.dir.path().join(".anni").join("config.toml"), config).unwrap();
+    }
+
+    /// Create an empty metadata repository, and write `albums` to it by catalog.
+    pub fn with_repo(self, albums: &[(&str, &str)]) -> Self {
+        let repo = self.workspace.repo_root();
+        fs::create_dir_all(repo.join("album")).unwrap();
+        fs::write(
+            repo.join("repo.toml"),
+            "[repo]\nname = \"Test\"\nedition = \"1.0+alpha.1.5.1\"\n",
+        )
+        .unwrap();
+        for (catalog, album) in albums {
+            fs::write(repo.join("album").join(format!("{catalog}.toml")), album).unwrap();
+        }
+        self
+    }
+
+    /// Write `content` to `file` in controlled part of the album.
+    pub fn write_controlled(&self, file: &str, content: &str) -> PathBuf {
+        let path = self.controlled_path.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    /// Write `content` to `file` in album directory.
+    pub fn write_album(&self, file: &str, content: &str) -> PathBuf {
+        let path = self.album_path.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        path
+    }
+}
@@ -0,0 +1,164 @@
+use anni_common::fs;
+use anni_workspace::{UntrackedWorkspaceAlbum, WorkspaceError};
+use common::TestWorkspace;
+use std::path::Path;
+
+mod common;
+
+const ALBUM_ID: &str = "0b5e7c1a-3d2f-4e6a-8b9c-1d2e3f4a5b6c";
+
+/// Create a workspace with an untracked album with two discs and a stray flac file in album directory.
+fn multi_disc_album(config: &str) -> TestWorkspace {
+    let ws = TestWorkspace::new(config, ALBUM_ID, "album");
+    ws.write_album("cover.jpg", "cover");
+    ws.write_album("stray.flac", "stray");
+    for disc in 1..=2 {
+        ws.write_album(&format!("Disc {disc}/cover.jpg"), "cover");
+        for track in 1..=3 {
+            ws.write_album(&format!("Disc {disc}/{track:02}.flac"), "track");
+        }
+    }
+    // directory without flac files is not a disc
+    ws.write_album("Scans/booklet.jpg", "scan");
+    // directory not named like `Disc N` is not a disc
+    ws.write_album("Bonus/bonus.flac", "bonus");
+    ws
+}
+
+fn file_name(path: &Path) -> &str {
+    path.file_name().unwrap().to_str().unwrap()
+}
+
+#[test]
+fn test_stray_track_in_multi_disc_album() {
+    let TestWorkspace {
+        dir: _dir,
+        workspace,
+        album_path,
+        ..
+    } = multi_disc_album("[workspace]\n");
+    let album = workspace.get_untracked_album_overview(&album_path).unwrap();
+
+    assert!(!album.simplified);
+    assert_eq!(album.discs.len(), 2);
+    assert_eq!(file_name(&album.discs[0].path), "Disc 1");
+    assert_eq!(file_name(&album.discs[1].path), "Disc 2");
+    assert!(album.discs.iter().all(|disc| disc.tracks.len() == 3));
+    assert_eq!(
+        album.stray_tracks,
+        [
+            album_path.join("Bonus/bonus.flac"),
+            album_path.join("stray.flac")
+        ]
+    );
+
+    // stray tracks must be handled before committing
+    assert!(matches!(
+        workspace.commit(&album_path, None::<fn(&UntrackedWorkspaceAlbum) -> bool>),
+        Err(WorkspaceError::StrayTracks(tracks)) if tracks == album.stray_tracks
+    ));
+    assert!(!album_path.join("Disc 1/01.flac").is_symlink());
+}
+
+#[test]
+fn test_disc_gap() {
+    let TestWorkspace {
+        dir: _dir,
+        workspace,
+        album_path,
+        ..
+    } = multi_disc_album("[workspace]\n");
+    fs::rename(album_path.join("Disc 2"), album_path.join("Disc 3")).unwrap();
+
+    assert!(matches!(
+        workspace.get_untracked_album_overview(&album_path),
+        Err(WorkspaceError::DiscGap { missing: 2 })
+    ));
+}
+
+#[test]
+fn test_strict_disc_structure() {
+    let TestWorkspace {
+        dir: _dir,
+        workspace,
+        album_path,
+        ..
+    } = multi_disc_album("[workspace]\nstrict-disc-structure = true\n");
+    assert!(matches!(
+        workspace.get_untracked_album_overview(&album_path),
+        Err(WorkspaceError::InvalidAlbumDiscStructure(_))
+    ));
+}
+
+#[test]
+fn test_simplified_album() {
+    let TestWorkspace {
+        dir: _dir,
+        workspace,
+        album_path,
+        ..
+    } = multi_disc_album("[workspace]\n");
+    for disc in 1..=2 {
+        fs::remove_dir_all(album_path.join(format!("Disc {disc}")), false).unwrap();
+    }
+
+    let album = workspace.get_untracked_album_overview(&album_path).unwrap();
+    assert!(album.simplified);
+    assert_eq!(album.discs.len(), 1);
+    assert_eq!(album.discs[0].tracks, [album_path.join("stray.flac")]);
+    assert_eq!(album.stray_tracks, [album_path.join("Bonus/bonus.flac")]);
+}
+
+/// Create a workspace with an untracked album with wav files only.
+fn wav_album(config: &str) -> TestWorkspace {
+    let ws = TestWorkspace::new(config, ALBUM_ID, "album");
+    ws.write_album("cover.jpg", "cover");
+    for track in 1..=3 {
+        ws.write_album(&format!("{track:02}.wav"), "track");
+    }
+    ws.write_album("log.txt", "log");
+    ws
+}
+
+#[test]
+fn test_wav_album_not_recognized_by_default() {
+    let TestWorkspace {
+        dir: _dir,
+        workspace,
+        album_path,
+        ..
+    } = wav_album("[workspace]\n");
+    assert!(matches!(
+        workspace.get_untracked_album_overview(&album_path),
+        Err(WorkspaceError::InvalidAlbumDiscStructure(_))
+    ));
+}
+
+#[test]
+fn test_wav_album_with_audio_extensions() {
+    let TestWorkspace {
+        dir: _dir,
+        workspace,
+        album_path,
+        ..
+    } = wav_album("[workspace]\naudio-extensions = [\"flac\", \"wav\"]\n");
+    let album = workspace.get_untracked_album_overview(&album_path).unwrap();
+
+    assert!(album.simplified);
+    assert_eq!(album.discs.len(), 1);
+    assert_eq!(
+        album.discs[0].tracks,
+        [
+            album_path.join("01.wav"),
+            album_path.join("02.wav"),
+            album_path.join("03.wav"),
+        ]
+    );
+
+    // wav files must be converted before committing
+    assert!(matches!(
+        workspace.commit(&album_path, None::<fn(&UntrackedWorkspaceAlbum) -> bool>),
+        Err(WorkspaceError::UnconvertedTrack(track)) if track == album_path.join("01.wav")
+    ));
+    assert!(!album_path.join("01.wav").is_symlink());
+}
@@ -0,0 +1,141 @@
+use anni_common::fs;
+use anni_metadata::model::{Album, AnniDate};
+use anni_repo::library::{AlbumFolderInfo, InfoParseError};
+use anni_workspace::{ExtractedAlbumInfo, UntrackedWorkspaceAlbum, WorkspaceError};
+use common::TestWorkspace;
+use std::borrow::Cow;
+use std::convert::Infallible;
+use std::str::FromStr;
+use uuid::Uuid;
+
+mod common;
+
+const ALBUM_ID: &str = "3a4b5c6d-7e8f-4a0b-9c1d-2e3f4a5b6c7d";
+
+/// Create a workspace with metadata repository and a committed album with one track.
+fn committed_album() -> TestWorkspace {
+    let ws = TestWorkspace::new(
+        "[workspace]\n",
+        ALBUM_ID,
+        "[2021-01-24][TEST-0001] TestAlbum",
+    )
+    .with_repo(&[]);
+    fs::copy("../assets/1s-cover.png", ws.album_path.join("cover.jpg")).unwrap();
+    fs::copy("../assets/1s-full.flac", ws.album_path.join("01.flac")).unwrap();
+    ws.workspace
+        .commit(&ws.album_path, None::<fn(&UntrackedWorkspaceAlbum) -> bool>)
+        .unwrap();
+    ws
+}
+
+fn extractor(title: &'static str) -> impl FnOnce(&str) -> Result<ExtractedAlbumInfo, Infallible> {
+    move |_| {
+        Ok(ExtractedAlbumInfo {
+            release_date: AnniDate::new(2021, 1, 24),
+            catalog: Cow::Borrowed("TEST-0001"),
+            title: Cow::Borrowed(title),
+            edition: None,
+        })
+    }
+}
+
+#[test]
+fn test_import_tags_twice() {
+    let TestWorkspace {
+        dir: _dir,
+        workspace,
+        album_path,
+        ..
+    } = committed_album();
+    let album_root = workspace.repo_root().join("album");
+
+    let album_id = workspace
+        .import_tags(&album_path, extractor("TestAlbum"), false)
+        .unwrap();
+    assert_eq!(album_id, Uuid::from_str(ALBUM_ID).unwrap());
+
+    // edit album in repository
+    let album_file = album_root.join("TEST-0001.toml");
+    let edited = fs::read_to_string(&album_file)
+        .unwrap()
+        .replace("[[discs.tracks]]", "[[discs.tracks]]\n# edited");
+    fs::write(&album_file, &edited).unwrap();
+
+    // importing again keeps the existing album instead of adding a duplicate or overwriting it
+    workspace
+        .import_tags(&album_path, extractor("Renamed"), false)
+        .unwrap();
+    workspace
+        .import_tags(&album_path, extractor("Renamed"), true)
+        .unwrap();
+
+    let files: Vec<_> = fs::read_dir(&album_root)
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    assert_eq!(files, ["TEST-0001.toml"]);
+
+    assert_eq!(fs::read_to_string(&album_file).unwrap(), edited);
+    let album = Album::from_str(&edited).unwrap();
+    assert_eq!(album.album_id(), album_id);
+    assert_eq!(album.title_raw(), "TestAlbum");
+}
+
+#[test]
+fn test_import_tags_invalid_folder_name() {
+    let TestWorkspace {
+        dir: _dir,
+        workspace,
+        album_path,
+        ..
+    } = committed_album();
+
+    // release date is missing in folder name
+    let result = workspace.import_tags(
+        &album_path,
+        |name| {
+            let info = AlbumFolderInfo::from_str(name.trim_start_matches("[2021-01-24]"))?;
+            Ok::<_, InfoParseError>(ExtractedAlbumInfo {
+                release_date: info.release_date,
+                catalog: Cow::Owned(info.catalog),
+                title: Cow::Owned(info.title),
+                edition: info.edition.map(Cow::Owned),
+            })
+        },
+        false,
+    );
+    assert!(matches!(
+        result,
+        Err(WorkspaceError::FailedToExtractAlbumInfo { name, .. }) if name == "[2021-01-24][TEST-0001] TestAlbum"
+    ));
+}
+
+#[test]
+fn test_import_tags_track_gap() {
+    let TestWorkspace {
+        dir: _dir,
+        workspace,
+        album_path,
+        ..
+    } = committed_album();
+
+    // track 2 is missing in controlled part
+    let album_id = Uuid::from_str(ALBUM_ID).unwrap();
+    let disc_path = workspace.controlled_album_path(&album_id, 2).join("1");
+    fs::copy("../assets/1s-full.flac", disc_path.join("3.flac")).unwrap();
+
+    let result = workspace.import_tags(&album_path, extractor("TestAlbum"), false);
+    assert!(matches!(
+        result,
+        Err(WorkspaceError::TrackGap {
+            disc: 1,
+            missing: 2
+        })
+    ));
+    assert_eq!(
+        fs::read_dir(workspace.repo_root().join("album"))
+            .unwrap()
+            .count(),
+        0
+    );
+}
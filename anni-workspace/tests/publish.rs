@@ -0,0 +1,179 @@
+use anni_common::fs;
+use anni_repo::library::{file_name, AlbumFolderInfo, DiscFolderInfo};
+use anni_workspace::{AnniWorkspace, PublishOperation, WorkspaceError};
+use common::TestWorkspace;
+use std::path::PathBuf;
+use std::str::FromStr;
+use uuid::Uuid;
+
+mod common;
+
+const ALBUM_ID: &str = "2d4c6e8a-1b3d-4f5a-8c7e-9a0b1c2d3e4f";
+
+/// Album with 2 tracks in metadata repository.
+fn album_toml() -> String {
+    format!(
+        r#"[album]
+album_id = "{ALBUM_ID}"
+title = "Title"
+artist = "Artist"
+date = 2999-12-31
+type = "normal"
+catalog = "TEST-0001"
+
+[[discs]]
+catalog = "TEST-0001"
+
+[[discs.tracks]]
+title = "Track 1"
+
+[[discs.tracks]]
+title = "Track/2"
+"#
+    )
+}
+
+/// Target library of the workspace.
+fn library_path(ws: &TestWorkspace) -> PathBuf {
+    ws.dir.path().join("library")
+}
+
+/// Create a workspace with a committed album with 2 tracks, which publishes to `library`.
+fn committed_album(layers: Option<usize>) -> TestWorkspace {
+    let ws = TestWorkspace::new("[workspace]\n", ALBUM_ID, "album")
+        .with_repo(&[("TEST-0001", &album_toml())]);
+    let library_path = library_path(&ws);
+    fs::create_dir_all(&library_path).unwrap();
+    let mut config = format!(
+        "[workspace]\npublish-to = \"default\"\nuse-trash = false\n\n[library.default]\npath = {:?}\n",
+        library_path
+    );
+    if let Some(layers) = layers {
+        config += &format!("layers = {layers}\n");
+    }
+    ws.write_config(&config);
+
+    for file in ["cover.jpg", "1/cover.jpg", "1/1.flac", "1/2.flac"] {
+        ws.write_controlled(file, file);
+    }
+    fs::symlink_file(
+        ws.controlled_path.join("cover.jpg"),
+        ws.album_path.join("cover.jpg"),
+    )
+    .unwrap();
+    ws
+}
+
+#[test]
+fn test_publish_plan_strict() {
+    let ws = committed_album(Some(2));
+    let plan = ws.workspace.publish_plan(&ws.album_path, false).unwrap();
+    let destination =
+        AnniWorkspace::strict_album_path(library_path(&ws), &Uuid::from_str(ALBUM_ID).unwrap(), 2);
+    assert_eq!(plan.destination, destination);
+    assert_eq!(
+        plan.operations,
+        [
+            PublishOperation::Move {
+                source: ws.controlled_path.clone(),
+                destination,
+            },
+            PublishOperation::Remove {
+                path: ws.album_path.clone(),
+                trash: false,
+            },
+        ]
+    );
+
+    // nothing is changed by planning
+    assert!(ws.controlled_path.join("1/1.flac").exists());
+    assert!(fs::read_dir(library_path(&ws)).unwrap().next().is_none());
+}
+
+#[test]
+fn test_publish_plan_convention_soft() {
+    let ws = committed_album(None);
+    let plan = ws.workspace.publish_plan(&ws.album_path, true).unwrap();
+    let destination = library_path(&ws).join("[991231][TEST-0001] Title");
+    assert_eq!(plan.destination, destination);
+
+    let copy = |source: &str, target: &str| PublishOperation::Copy {
+        source: ws.controlled_path.join(source),
+        destination: destination.join(target),
+    };
+    assert_eq!(
+        plan.operations,
+        [
+            copy("cover.jpg", "cover.jpg"),
+            copy("1/1.flac", "01. Track 1.flac"),
+            copy("1/2.flac", "02. Track／2.flac"),
+            PublishOperation::Mark {
+                path: ws.controlled_path.join(".publish"),
+            },
+            PublishOperation::Remove {
+                path: ws.album_path.clone(),
+                trash: false,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_publish_convention() {
+    let ws = committed_album(None);
+    let plan = ws.workspace.publish_plan(&ws.album_path, false).unwrap();
+    ws.workspace.publish(&ws.album_path, false).unwrap();
+
+    assert_eq!(
+        fs::read_to_string(plan.destination.join("02. Track／2.flac")).unwrap(),
+        "1/2.flac"
+    );
+    assert!(!ws.controlled_path.exists());
+    assert!(!ws.album_path.exists());
+}
+
+#[test]
+fn test_publish_convention_multi_disc() {
+    let ws = committed_album(None);
+    // add the second disc
+    let repo = ws
+        .workspace
+        .repo_root()
+        .join("album")
+        .join("TEST-0001.toml");
+    let mut album = fs::read_to_string(&repo).unwrap();
+    album += "\n[[discs]]\ncatalog = \"TEST-0002\"\ntitle = \"Disc/2\"\n\n[[discs.tracks]]\ntitle = \"Track 3\"\n";
+    fs::write(&repo, album).unwrap();
+    fs::create_dir_all(ws.controlled_path.join("2")).unwrap();
+    for file in ["2/cover.jpg", "2/1.flac"] {
+        fs::write(ws.controlled_path.join(file), file).unwrap();
+    }
+
+    let plan = ws.workspace.publish_plan(&ws.album_path, false).unwrap();
+    ws.workspace.publish(&ws.album_path, false).unwrap();
+
+    // published folders can be parsed back in convention layout
+    let album = AlbumFolderInfo::from_str(&file_name(&plan.destination).unwrap()).unwrap();
+    assert_eq!(album.catalog, "TEST-0001");
+    assert_eq!(album.disc_count, 2);
+
+    let disc_path = plan.destination.join("[TEST-0002] Disc／2 [Disc 2]");
+    let disc = DiscFolderInfo::from_str(&file_name(&disc_path).unwrap()).unwrap();
+    assert_eq!(disc.disc_id, 2);
+    assert_eq!(disc.info.catalog, "TEST-0002");
+    assert_eq!(
+        fs::read_to_string(disc_path.join("01. Track 3.flac")).unwrap(),
+        "2/1.flac"
+    );
+    assert!(!ws.controlled_path.exists());
+}
+
+#[test]
+fn test_tag_changes_of_unknown_album() {
+    let ws = TestWorkspace::new("[workspace]\n", ALBUM_ID, "album").with_repo(&[]);
+    let result = ws.workspace.tag_changes(&ws.album_path, false);
+    assert!(matches!(
+        result,
+        Err(WorkspaceError::AlbumNotFound(album_id)) if album_id.to_string() == ALBUM_ID
+    ));
+}
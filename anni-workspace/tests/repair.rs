@@ -0,0 +1,156 @@
+use anni_common::fs;
+use anni_workspace::{WorkspaceAlbumState, WorkspaceError};
+use common::TestWorkspace;
+use fs4::FileExt;
+
+mod common;
+
+const ALBUM_ID: &str = "9d8c7b6a-5f4e-4d3c-8b2a-1f0e9d8c7b6a";
+const TRACKS: usize = 6;
+
+/// Create a workspace with an album whose commit was interrupted after moving `moved` tracks.
+fn interrupted_album(moved: usize) -> TestWorkspace {
+    let ws = TestWorkspace::new("[workspace]\nuse-trash = false\n", ALBUM_ID, "album");
+    fs::create_dir_all(ws.controlled_path.join("1")).unwrap();
+    ws.write_album("cover.jpg", "cover");
+    for i in 1..=TRACKS {
+        let track = ws.write_album(&format!("{i:02}.flac"), &format!("track {i}"));
+        if i <= moved {
+            let controlled = ws.controlled_path.join("1").join(format!("{i}.flac"));
+            fs::rename(&track, &controlled).unwrap();
+            fs::symlink_file(&controlled, &track).unwrap();
+        }
+    }
+    // lock file left by the interrupted commit, which is no longer locked
+    ws.write_album(".album.lock", "");
+    ws
+}
+
+#[test]
+fn test_repair_interrupted_commit() {
+    let TestWorkspace {
+        dir: _dir,
+        workspace,
+        album_path,
+        controlled_path,
+    } = interrupted_album(TRACKS / 2);
+    assert!(matches!(
+        workspace.get_workspace_album(&album_path).unwrap().state,
+        WorkspaceAlbumState::Inconsistent(_)
+    ));
+
+    let state = workspace.repair_album(&album_path).unwrap();
+    assert!(matches!(state, WorkspaceAlbumState::Committed(_)));
+    assert!(matches!(
+        workspace.get_workspace_album(&album_path).unwrap().state,
+        WorkspaceAlbumState::Committed(_)
+    ));
+    for i in 1..=TRACKS {
+        assert!(album_path.join(format!("{i:02}.flac")).is_symlink());
+        assert_eq!(
+            fs::read_to_string(controlled_path.join("1").join(format!("{i}.flac"))).unwrap(),
+            format!("track {i}")
+        );
+    }
+    assert!(controlled_path.join("cover.jpg").exists());
+    assert!(controlled_path.join("1").join("cover.jpg").exists());
+    assert!(!album_path.join(".album.lock").exists());
+}
+
+#[test]
+fn test_repair_locked_album() {
+    let TestWorkspace {
+        dir: _dir,
+        workspace,
+        album_path,
+        ..
+    } = interrupted_album(TRACKS / 2);
+    // album is being committed by another process
+    let lock = fs::File::open(album_path.join(".album.lock")).unwrap();
+    lock.try_lock_exclusive().unwrap();
+
+    assert!(matches!(
+        workspace.repair_album(&album_path),
+        Err(WorkspaceError::AlbumLocked(_))
+    ));
+    assert!(album_path.join(".album.lock").exists());
+    for i in 1..=TRACKS / 2 {
+        assert!(album_path.join(format!("{i:02}.flac")).is_symlink());
+    }
+}
+
+#[test]
+fn test_repair_unlinked_track() {
+    let TestWorkspace {
+        dir: _dir,
+        workspace,
+        album_path,
+        controlled_path,
+    } = interrupted_album(TRACKS / 2);
+    // interrupted after moving the last track, but before linking it
+    let last = album_path.join(format!("{TRACKS:02}.flac"));
+    fs::rename(
+        &last,
+        controlled_path.join("1").join(format!("{TRACKS}.flac")),
+    )
+    .unwrap();
+
+    let state = workspace.repair_album(&album_path).unwrap();
+    assert!(matches!(state, WorkspaceAlbumState::Untracked(_)));
+    for i in 1..TRACKS {
+        let track = album_path.join(format!("{i:02}.flac"));
+        assert!(!track.is_symlink());
+        assert_eq!(fs::read_to_string(track).unwrap(), format!("track {i}"));
+    }
+    assert_eq!(
+        fs::read_to_string(album_path.join(format!("{TRACKS}.flac"))).unwrap(),
+        format!("track {TRACKS}")
+    );
+    assert_eq!(fs::read_dir(&controlled_path).unwrap().count(), 0);
+}
+
+#[test]
+fn test_repair_consistent_album() {
+    let TestWorkspace {
+        dir: _dir,
+        workspace,
+        album_path,
+        controlled_path,
+    } = interrupted_album(0);
+    fs::remove_file(album_path.join(".album.lock"), false).unwrap();
+    fs::remove_dir_all(controlled_path.join("1"), false).unwrap();
+
+    // untracked album does not need repair
+    assert!(workspace.repair_album(&album_path).is_err());
+}
+
+#[test]
+fn test_repair_orphan_to_disc_directory() {
+    let ws = TestWorkspace::new("[workspace]\nuse-trash = false\n", ALBUM_ID, "album");
+    ws.write_album("cover.jpg", "cover");
+    // sorted before `Disc N`, but not a disc
+    ws.write_album("Bonus/01.flac", "bonus");
+    ws.write_album("Disc 1/cover.jpg", "cover");
+    ws.write_album("Disc 2/cover.jpg", "cover");
+    let track = ws.write_album("Disc 1/01.flac", "disc 1");
+    let controlled = ws.write_controlled("1/1.flac", "");
+    fs::rename(&track, &controlled).unwrap();
+    fs::symlink_file(&controlled, &track).unwrap();
+    // interrupted after moving the track of disc 2, but before linking it
+    ws.write_controlled("2/1.flac", "disc 2");
+    ws.write_album(".album.lock", "");
+
+    let state = ws.workspace.repair_album(&ws.album_path).unwrap();
+    assert!(matches!(state, WorkspaceAlbumState::Untracked(_)));
+    let disc_1 = ws.album_path.join("Disc 1").join("01.flac");
+    assert!(!disc_1.is_symlink());
+    assert_eq!(fs::read_to_string(disc_1).unwrap(), "disc 1");
+    assert_eq!(
+        fs::read_to_string(ws.album_path.join("Disc 2").join("1.flac")).unwrap(),
+        "disc 2"
+    );
+    assert_eq!(
+        fs::read_to_string(ws.album_path.join("Bonus").join("01.flac")).unwrap(),
+        "bonus"
+    );
+}
@@ -0,0 +1,61 @@
+use anni_common::fs;
+use common::TestWorkspace;
+use std::path::Path;
+
+mod common;
+
+const ALBUM_ID: &str = "0f2a7c64-3d6e-4b8a-9c1d-5e2f3a4b5c6d";
+
+/// Create a workspace with a committed album, whose controlled part contains only `cover.jpg`.
+fn committed_album(use_trash: bool) -> TestWorkspace {
+    let ws = TestWorkspace::new(
+        &format!("[workspace]\nuse-trash = {use_trash}\n"),
+        ALBUM_ID,
+        "album",
+    );
+    let cover = ws.write_controlled("cover.jpg", "cover");
+    fs::symlink_file(cover, ws.album_path.join("cover.jpg")).unwrap();
+    ws
+}
+
+fn assert_reverted(album_path: &Path, controlled_path: &Path) {
+    let cover = album_path.join("cover.jpg");
+    assert!(!cover.is_symlink());
+    assert_eq!(fs::read_to_string(cover).unwrap(), "cover");
+    assert!(fs::read_dir(controlled_path).unwrap().next().is_none());
+}
+
+#[test]
+fn test_revert_without_trash() {
+    let ws = committed_album(false);
+    ws.workspace.revert(&ws.album_path).unwrap();
+    assert_reverted(&ws.album_path, &ws.controlled_path);
+}
+
+/// Moves files to the trash of current user, so it's ignored by default.
+/// Run with `cargo test -p anni-workspace --test trash -- --ignored`.
+#[test]
+#[ignore = "moves files to the trash of current user"]
+#[cfg(any(
+    target_os = "windows",
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+))]
+fn test_revert_with_trash() {
+    let ws = committed_album(true);
+    ws.workspace.revert(&ws.album_path).unwrap();
+    assert_reverted(&ws.album_path, &ws.controlled_path);
+
+    // controlled part of the album is in trash now
+    let trashed: Vec<_> = trash::os_limited::list()
+        .unwrap()
+        .into_iter()
+        .filter(|item| item.original_parent.join(&item.name) == ws.controlled_path)
+        .collect();
+    assert_eq!(trashed.len(), 1);
+    trash::os_limited::purge_all(trashed).unwrap();
+}
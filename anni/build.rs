@@ -9,8 +9,19 @@ fn get_hash() -> Result<String, Box<dyn Error>> {
     Ok(hash.trim().to_string())
 }
 
+fn get_rustc_version() -> Result<String, Box<dyn Error>> {
+    // cargo sets `RUSTC` to the compiler used to build this crate
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let output = Command::new(rustc).arg("--version").output()?;
+    let version = String::from_utf8(output.stdout)?;
+    Ok(version.trim().to_string())
+}
+
 fn main() {
     let version = env!("CARGO_PKG_VERSION");
     let hash = get_hash().unwrap_or_else(|_| "unknown".to_string());
     println!("cargo:rustc-env=ANNI_VERSION={version} ({hash})");
+
+    let rustc_version = get_rustc_version().unwrap_or_else(|_| "unknown".to_string());
+    println!("cargo:rustc-env=ANNI_RUSTC_VERSION={rustc_version}");
 }
@@ -5,8 +5,24 @@ export-to = Path for exported data.
 
 ## flac
 flac = Provide FLAC-related utilities.
+flac-cover = Normalize embedded cover of FLAC files.
+flac-cover-set = Replace all embedded pictures with the given front cover.
+flac-cover-extract = Extract the front cover to the given path.
+flac-cover-strip = Remove duplicated and non-front pictures, keeping the first front cover.
+flac-cover-max-size = Maximum width and height of covers. Larger covers and covers not in JPEG are converted to JPEG within this size.
 flac-export = Export data.
 flac-export-type = Type of data to export.
+flac-identify = Identify untagged tracks with AcoustID fingerprints.
+flac-identify-api-key = AcoustID API key. Only fingerprints are printed if not provided.
+flac-identify-limit = Maximum number of candidates to print for each file.
+flac-identify-apply = Write metadata of the best candidate into FLAC tags.
+flac-repair = Repair broken padding blocks, last block flags and seek table in header.
+flac-tags = Edit vorbis comments of FLAC files.
+flac-tags-set = Replace all values of a field with the given value.
+flac-tags-set-append = Append the value instead of replacing existing values.
+flac-tags-remove = Remove all values of a field.
+flac-verify = Verify MD5 signature of decoded audio.
+flac-verify-fix = Compute and write MD5 signature for files without one.
 
 
 ## split
@@ -16,6 +32,13 @@ split-format-output = Format of output audio file.
 split-clean = Keep split ao files clean with no metadata or cover written into.
 split-no-import-cover = Do not import cover to audio file.
 split-output-file-exist = Output file {$filename} exists. Please remove the file and try again.
+split-profile = Name of quality profile to encode output files with. Overrides output format.
+split-profile-not-found = Quality profile {$profile} was not found. Available profiles: {$available}
+split-bit-depth = Bit depth of output audio. Reduced bit depth is dithered.
+split-sample-rate = Sample rate of output audio.
+split-channels = Number of channels of output audio. Only down-mixing to mono and up-mixing from mono are supported.
+split-cue-charset = Charset of cue files, such as shift_jis. Detected automatically if not provided.
+split-pregap = How to handle pregaps in cue file. `append` appends pregaps to previous tracks, the same as cuebreakpoints. Not applied to cue sheets embedded in FLAC files.
 
 
 ## convention
@@ -63,16 +86,50 @@ repo-print-type = Print type.
 repo-print-clean = Do not print REM COMMENT "Generated by Anni" in cue mode.
 repo-print-input = Target to print. For example, tag name or album catalog. '/{"{disc_id}"}' can be appended to indicate the disc id of an album. Disc id equals to 0 or 1 both indicates the first disc.
 
+repo-commit = Commit changed metadata files if repository is a git worktree.
+repo-commit-done = Committed: {$message}
+repo-commit-skipped = Repository is not a git worktree or nothing changed, skipped commit.
+
 repo-db = Generate metadata database from repository.
 
+repo-stats = Show statistics of albums and tags in repository.
+repo-stats-json = Print statistics in JSON format.
+repo-stats-library = Library in strict layout to check album covers.
+repo-stats-layer = Layer of the library.
+
+repo-tag = Add or remove a tag of albums in bulk.
+repo-tag-add = Tag to add, in `type:name` format. The tag must be defined in repository.
+repo-tag-remove = Tag to remove, in `type:name` format.
+repo-tag-catalog-prefix = Edit albums whose catalog starts with this prefix.
+repo-tag-done = {$count} album(s) changed.
+
+repo-dedupe = Find tracks which may be imported more than once.
+repo-dedupe-report = Print each group of duplicated tracks with album ids.
+repo-dedupe-case-sensitive = Compare titles and artists case-sensitively.
+repo-dedupe-keep-whitespace = Do not trim and collapse whitespaces in titles and artists.
+repo-dedupe-done = {$count} group(s) of duplicated tracks found.
+
 repo-migrate = Migrate metadata repository to new version.
 repo-migrate-album-id = Add album_id field to album metadata.
+repo-migrate-annim = Migrate metadata repository to annim.
+repo-migrate-search-fields = Normalize album fields used by search index, such as catalog and edition.
+repo-migrate-dry-run = Report changes without writing album files.
+repo-migrate-search-fields-unresolved = {$path}: {$field} could not be normalized: {$reason}
+repo-migrate-search-fields-done = {$count} album(s) migrated, {$unresolved} field(s) need manual fix.
+repo-migrate-search-fields-dry-run-done = {$count} album(s) would be migrated, {$unresolved} field(s) need manual fix.
+repo-migrate-search-fields-failed = Some fields could not be normalized.
 
 
 ## Library
 library = Anni Audio library manager.
 library-tag = Apply metadata from repository to album.
 library-link = Link library to strict format.
+library-audit = Check integrity of a strict library against metadata repository.
+library-audit-verify = Verify MD5 signature of decoded audio files.
+library-audit-json = Print audit report in JSON format.
+library-covers = Manage album covers in a strict library.
+library-covers-export = Export album covers to `<album_id>.jpg` in output directory.
+library-covers-export-size = Resize covers to thumbnails which fit in given size.
 
 ## Workspace
 workspace = Manage audio and metadata workspace.
@@ -84,15 +141,24 @@ workspace-add-import-tags=Import metadata from audio files while adding.
 workspace-add-dry-run=Do not actually move files or make symlinks.
 workspace-add-skip-check=Skip check for album structure.
 workspace-add-open-editor=Open text editor after album metadata file is crated.
+workspace-add-convert=Convert non-flac tracks to flac before adding. Original files are removed after conversion.
 
 workspace-rm = Remove an album from workspace.
 workspace-status = Print status of workspace.
 workspace-update = Update albums in workspace.
 workspace-publish = Publish albums from workspace to audio library.
+workspace-publish-dry-run = Print files to be moved or copied without publishing.
 workspace-serve = Serve workspace as a remote service.
 workspace-fsck = Check and fix workspace.
 
 
+## Doctor
+doctor = Diagnose environment issues and print a report.
+doctor-repo = Metadata repository to check.
+doctor-library = Strict audio library to check.
+doctor-drive-token = Google Drive token file of annil to check.
+
+
 ## Completions
 completions = Generate shell completion.
 completions-shell = Shell to generate completion.
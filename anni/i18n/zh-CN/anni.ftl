@@ -5,8 +5,24 @@ export-to = 导出内容存放的路径
 
 ## flac
 flac = 提供 FLAC 处理相关的功能
+flac-cover = 规范化 FLAC 文件内嵌的封面
+flac-cover-set = 以给定的封面替换所有内嵌图片
+flac-cover-extract = 将封面导出至指定路径
+flac-cover-strip = 删除重复的图片与非正面封面，保留第一个正面封面
+flac-cover-max-size = 封面的最大宽高，超出尺寸或非 JPEG 格式的封面会被转换为此尺寸内的 JPEG 图片
 flac-export = 导出内容
 flac-export-type = 导出内容类型
+flac-identify = 使用 AcoustID 音频指纹识别无标签的音轨
+flac-identify-api-key = AcoustID API 密钥，未提供时仅输出音频指纹
+flac-identify-limit = 每个文件最多输出的候选结果数量
+flac-identify-apply = 将最佳候选结果的元数据写入 FLAC 标签
+flac-repair = 修复头部中损坏的填充块、末块标记与 SEEKTABLE
+flac-tags = 编辑 FLAC 文件的 Vorbis 注释
+flac-tags-set = 将字段的所有值替换为给定值
+flac-tags-set-append = 追加给定值而不替换已有的值
+flac-tags-remove = 删除字段的所有值
+flac-verify = 校验解码后音频的 MD5 签名
+flac-verify-fix = 为缺少 MD5 签名的文件计算并写入签名
 
 
 ## split
@@ -16,6 +32,13 @@ split-format-output = 切分后输出音频的文件类型
 split-clean = 不向切分后的音频文件中写入元数据和封面等信息
 split-no-import-cover = 不从切分目录寻找封面写入音频文件
 split-output-file-exist = 输出路径下已存在文件 {$filename}，请删除文件后重试
+split-profile = 切分后输出音频使用的编码配置名称，指定后将忽略输出文件类型
+split-profile-not-found = 未找到编码配置 {$profile}，可用的配置有：{$available}
+split-bit-depth = 输出音频的位深度，降低位深度时将进行抖动处理
+split-sample-rate = 输出音频的采样率
+split-channels = 输出音频的声道数，仅支持混合为单声道或由单声道扩展
+split-cue-charset = cue 文件的字符集，如 shift_jis。未指定时自动检测
+split-pregap = cue 文件中 pregap 的处理方式。`append` 将 pregap 附加至上一音轨，与 cuebreakpoints 相同。不适用于 FLAC 文件内嵌的 cue sheet
 
 
 ## convention
@@ -62,16 +85,50 @@ repo-print-type = 输出数据的类型
 repo-print-clean = 省略 cue 输出中的 REM COMMENT "Generated by Anni"
 repo-print-input = 需要输出的对象。可以是标签名称或专辑品番。当表示专辑品番时，可以通过get_albums_by_tag后缀 '/{"{disc_id}"}' 指定需要输出信息的碟片编号，0 和 1 均代表第一张碟片
 
+repo-commit = 若仓库位于 git 工作区中，则提交修改过的元数据文件
+repo-commit-done = 已提交：{$message}
+repo-commit-skipped = 仓库不在 git 工作区中或没有修改，已跳过提交
+
 repo-db = 生成元数据仓库对应的数据库文件
 
+repo-stats = 显示仓库中专辑与标签的统计信息
+repo-stats-json = 以 JSON 格式输出统计信息
+repo-stats-library = 检查专辑封面的严格布局音乐库
+repo-stats-layer = 音乐库的层级
+
+repo-tag = 批量添加或移除专辑标签
+repo-tag-add = 需要添加的标签，格式为 `type:name`，标签必须已在仓库中定义
+repo-tag-remove = 需要移除的标签，格式为 `type:name`
+repo-tag-catalog-prefix = 仅编辑品番以此前缀开头的专辑
+repo-tag-done = 已修改 {$count} 张专辑
+
+repo-dedupe = 查找可能被重复导入的曲目
+repo-dedupe-report = 输出每组重复曲目及其所在专辑 ID
+repo-dedupe-case-sensitive = 比较标题与艺术家时区分大小写
+repo-dedupe-keep-whitespace = 不对标题与艺术家中的空白字符进行裁剪与合并
+repo-dedupe-done = 共找到 {$count} 组重复曲目
+
 repo-migrate = 迁移旧版本元数据仓库到新版本
 repo-migrate-album-id = 为缺少 album_id 字段的专辑添加这一字段
+repo-migrate-annim = 将元数据仓库迁移至 annim
+repo-migrate-search-fields = 规范化搜索索引所需的专辑字段，如品番与版本
+repo-migrate-dry-run = 仅报告需要修改的内容，不写入专辑文件
+repo-migrate-search-fields-unresolved = {$path}：无法规范化 {$field}：{$reason}
+repo-migrate-search-fields-done = 已迁移 {$count} 张专辑，{$unresolved} 个字段需要手动修复
+repo-migrate-search-fields-dry-run-done = 将迁移 {$count} 张专辑，{$unresolved} 个字段需要手动修复
+repo-migrate-search-fields-failed = 部分字段无法规范化
 
 
 ## Library
 library = 提供音频仓库的管理功能
 library-tag = 将元数据仓库中的数据应用到专辑
 library-link = 以符号链接将约定目录格式转换为严格目录格式
+library-audit = 对照元数据仓库检查严格格式音频库的完整性
+library-audit-verify = 校验解码后音频文件的 MD5 签名
+library-audit-json = 以 JSON 格式输出检查报告
+library-covers = 管理严格格式音频库中的专辑封面
+library-covers-export = 将专辑封面以 `<album_id>.jpg` 导出到目标目录
+library-covers-export-size = 将封面缩放为不超过指定尺寸的缩略图
 
 
 ## Workspace
@@ -84,15 +141,24 @@ workspace-add-import-tags=从音频文件中导入元数据
 workspace-add-dry-run=仅展示，不实际移动文件或创建链接
 workspace-add-skip-check=跳过对专辑结构的检查
 workspace-add-open-editor=导入完成后通过文本编辑器打开元数据文件
+workspace-add-convert=添加前将非 FLAC 音轨转换为 FLAC，转换完成后删除原文件
 
 workspace-rm = 从工作空间中移除专辑
 workspace-status = 显示工作空间中所有专辑的状态
 workspace-update = 更新工作空间中的专辑
 workspace-publish = 将工作空间中的专辑发布到音频仓库
+workspace-publish-dry-run = 仅输出将要移动或复制的文件，不实际发布
 workspace-serve = 将工作空间作为 http 服务启动
 workspace-fsck = 检查并修复工作空间
 
 
+## Doctor
+doctor = 诊断运行环境问题并输出报告
+doctor-repo = 需要检查的元数据仓库
+doctor-library = 需要检查的严格格式音频仓库
+doctor-drive-token = 需要检查的 annil Google Drive 令牌文件
+
+
 ## Completions
 completions = 生成 Shell 的补全脚本
 completions-shell = 生成补全脚本的 Shell
@@ -103,6 +103,15 @@ impl InputPathOptions for FlacInputPath {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct AudioInputPath;
+
+impl InputPathOptions for AudioInputPath {
+    fn allowed_extensions() -> &'static [&'static str] {
+        &["flac", "opus", "ogg", "m4a"]
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FlacInputFile;
 
@@ -37,6 +37,7 @@ pub enum AnniSubcommand {
     Library(LibrarySubcommand),
     Completions(CompletionsSubcommand),
     Workspace(WorkspaceSubcommand),
+    Doctor(DoctorSubcommand),
 }
 
 #[tokio::main]
@@ -1,12 +1,16 @@
-use crate::args::{FlacInputPath, InputPath};
+use crate::args::{AudioInputPath, InputPath};
 use crate::config::read_config;
 use crate::ll;
 use anni_common::validator::*;
-use anni_flac::blocks::{BlockStreamInfo, BlockVorbisComment, PictureType};
+use anni_flac::blocks::{BlockStreamInfo, BlockVorbisComment, PictureType, UserComment};
 use anni_flac::{FlacHeader, MetadataBlockData};
 use anni_metadata::model::{UNKNOWN_ARTIST, VARIOUS_ARTISTS};
 use clap::{Args, Subcommand};
 use clap_handler::{handler, Context, Handler};
+use lofty::config::WriteOptions;
+use lofty::file::{AudioFile, TaggedFile, TaggedFileExt};
+use lofty::ogg::VorbisComments;
+use lofty::tag::{ItemKey, TagExt, TagType};
 use serde::de::Error;
 use serde::{Deserialize, Deserializer};
 use std::collections::{HashMap, HashSet};
@@ -50,7 +54,7 @@ pub struct ConventionCheckAction {
     fix: bool,
 
     #[clap(required = true)]
-    filename: Vec<InputPath<FlacInputPath>>,
+    filename: Vec<InputPath<AudioInputPath>>,
 }
 
 #[handler(ConventionCheckAction)]
@@ -58,14 +62,17 @@ fn convention_check(me: &ConventionCheckAction, rules: &ConventionRules) -> anyh
     info!(target: "anni", "Convention validation started...");
     for input in &me.filename {
         for file in input.iter() {
-            let flac = FlacHeader::from_file(file.as_path());
-            match flac {
-                Ok(mut flac) => {
-                    rules.validate(file, &mut flac, me.fix);
-                }
-                Err(e) => {
-                    error!(target: "convention/parse", "Failed to parse header of file {}: {:?}", file.to_string_lossy(), e)
-                }
+            let result = if file.extension().is_some_and(|ext| ext == "flac") {
+                FlacHeader::from_file(file.as_path())
+                    .map(|mut flac| rules.validate(&file, &mut flac, me.fix))
+                    .map_err(anyhow::Error::from)
+            } else {
+                lofty::read_from_path(file.as_path())
+                    .map(|mut tagged| rules.validate_tagged(&file, &mut tagged, me.fix))
+                    .map_err(anyhow::Error::from)
+            };
+            if let Err(e) = result {
+                error!(target: "convention/parse", "Failed to parse header of file {}: {:?}", file.to_string_lossy(), e)
             }
         }
     }
@@ -90,7 +97,7 @@ impl ConventionRules {
         let mut fixed = false;
 
         // validate stream info
-        self.validate_stream_info(filename.as_ref(), flac.stream_info());
+        self.validate_stream_info(filename.as_ref(), &flac.stream_info().into());
 
         // TODO: option to control whether cover validation should take effect
         // validate cover existence
@@ -128,28 +135,102 @@ impl ConventionRules {
         }
     }
 
-    fn validate_stream_info<P>(&self, filename: P, info: &BlockStreamInfo)
+    /// Validate Opus, Ogg Vorbis or MP4 files.
+    ///
+    /// Tags are converted to vorbis comments, so that the same rules apply to all formats.
+    pub(crate) fn validate_tagged<P>(&self, filename: P, file: &mut TaggedFile, fix: bool)
     where
         P: AsRef<Path>,
     {
-        let filename = filename.as_ref().to_string_lossy();
-        self.stream_info.sample_rate.iter().for_each(|expected| {
-            if !expected.contains(&info.sample_rate) {
-                error!(target: "convention/sample-rate", "Stream sample-rate mismatch in file {filename}: expected `{expected:?}`, got {}", info.sample_rate);
-            }
-        });
-        self.stream_info.bit_per_sample.iter().for_each(|expected| {
-            if info.bits_per_sample != *expected {
-                error!(target: "convention/bit-per-sample", "Stream bit-per-sample mismatch in file {filename}: expected {expected}, got {}", info.bits_per_sample);
+        let filename = filename.as_ref();
+
+        // validate stream info
+        let properties = file.properties();
+        self.validate_stream_info(
+            filename,
+            &StreamProperties {
+                sample_rate: properties.sample_rate(),
+                channels: properties.channels(),
+                bits_per_sample: properties.bit_depth(),
+                md5_signature: None,
+            },
+        );
+
+        let tag_type = file.primary_tag_type();
+        let Some(tag) = file.tag_mut(tag_type) else {
+            error!(target: "convention/comment", "No tag found in file {}!", filename.to_string_lossy());
+            return;
+        };
+
+        // validate cover existence
+        let has_cover = tag
+            .pictures()
+            .iter()
+            .any(|picture| picture.pic_type() == lofty::picture::PictureType::CoverFront);
+        if !has_cover {
+            error!(target: "convention/cover", "Cover does not exist in file {}!", filename.to_string_lossy());
+        }
+
+        // validate comments
+        let mut comment = BlockVorbisComment {
+            vendor_string: String::new(),
+            comments: VorbisComments::from(tag.clone())
+                .items()
+                .map(|(key, value)| UserComment::new(format!("{key}={value}")))
+                .collect(),
+        };
+        let keys: Vec<_> = comment
+            .comments
+            .iter()
+            .map(|c| c.key_raw().to_string())
+            .collect();
+        let (fixed, new_path) = self.validate_tags(filename, &mut comment, fix);
+
+        // apply fixes
+        if fixed {
+            // only removal of unnecessary tags is fixed
+            let retained: HashSet<_> = comment.comments.iter().map(|c| c.key_raw()).collect();
+            for key in keys.iter().filter(|key| !retained.contains(key.as_str())) {
+                tag.remove_key(&ItemKey::from_key(TagType::VorbisComments, key));
             }
-        });
-        self.stream_info.channels.iter().for_each(|expected| {
-            if info.channels != *expected {
-                error!(target: "convention/channel-num", "Stream channel num mismatch in file {filename}: expected {expected}, got {}", info.channels);
+            tag.save_to_path(filename, WriteOptions::default())
+                .expect("Failed to save audio file");
+        }
+        if let Some(new_path) = new_path {
+            std::fs::rename(filename, new_path).unwrap();
+        }
+    }
+
+    fn validate_stream_info<P>(&self, filename: P, info: &StreamProperties)
+    where
+        P: AsRef<Path>,
+    {
+        let filename = filename.as_ref().to_string_lossy();
+        if let Some(sample_rate) = info.sample_rate {
+            self.stream_info.sample_rate.iter().for_each(|expected| {
+                if !expected.contains(&sample_rate) {
+                    error!(target: "convention/sample-rate", "Stream sample-rate mismatch in file {filename}: expected `{expected:?}`, got {sample_rate}");
+                }
+            });
+        }
+        if let Some(bits_per_sample) = info.bits_per_sample {
+            self.stream_info.bit_per_sample.iter().for_each(|expected| {
+                if bits_per_sample != *expected {
+                    error!(target: "convention/bit-per-sample", "Stream bit-per-sample mismatch in file {filename}: expected {expected}, got {bits_per_sample}");
+                }
+            });
+        }
+        if let Some(channels) = info.channels {
+            self.stream_info.channels.iter().for_each(|expected| {
+                if channels != *expected {
+                    error!(target: "convention/channel-num", "Stream channel num mismatch in file {filename}: expected {expected}, got {channels}");
+                }
+            });
+        }
+        if let Some(md5_signature) = info.md5_signature {
+            if self.stream_info.require_checksum && u128::from_be_bytes(md5_signature) == 0 {
+                error!(target: "convention/checksum", "Empty checksum detected in file: {filename}");
             }
-        });
-        if self.stream_info.require_checksum && u128::from_be_bytes(info.md5_signature) == 0 {
-            error!(target: "convention/checksum", "Empty checksum detected in file: {filename}");
         }
     }
 
@@ -263,8 +344,13 @@ impl ConventionRules {
 
         // Filename check
         if let (Some(title), Some(track_number)) = (title, track_number) {
+            let extension = filename
+                .as_ref()
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or("flac");
             let filename_expected: &str =
-                &format!("{:0>2}. {}.flac", track_number, title).replace("/", "／");
+                &format!("{:0>2}. {}.{}", track_number, title, extension).replace("/", "／");
             let filename_raw = filename
                 .as_ref()
                 .file_name()
@@ -407,6 +493,27 @@ impl Default for ConventionConfig {
     }
 }
 
+/// Stream properties to validate, which may be unavailable in some formats.
+struct StreamProperties {
+    sample_rate: Option<u32>,
+    channels: Option<u8>,
+    /// Not available in lossy formats.
+    bits_per_sample: Option<u8>,
+    /// Only available in FLAC files.
+    md5_signature: Option<[u8; 16]>,
+}
+
+impl From<&BlockStreamInfo> for StreamProperties {
+    fn from(info: &BlockStreamInfo) -> Self {
+        Self {
+            sample_rate: Some(info.sample_rate),
+            channels: Some(info.channels),
+            bits_per_sample: Some(info.bits_per_sample),
+            md5_signature: Some(info.md5_signature),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 struct ConventionStreamInfo {
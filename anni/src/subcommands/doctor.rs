@@ -0,0 +1,356 @@
+use crate::ll;
+use anni_metadata::annim::AnnimClient;
+use anni_repo::RepositoryManager;
+use anni_split::codec::{APE_COMMAND, FLAC_COMMAND, TAK_COMMAND, TTA_COMMAND};
+use anni_workspace::config::WorkspaceMetadata;
+use anni_workspace::AnniWorkspace;
+use clap::Args;
+use clap_handler::handler;
+use std::ffi::OsStr;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+#[derive(Args, Debug, Clone)]
+#[clap(about = ll!("doctor"))]
+pub struct DoctorSubcommand {
+    #[clap(long = "repo", env = "ANNI_REPO")]
+    #[clap(help = ll!("doctor-repo"))]
+    repo_root: Option<PathBuf>,
+
+    #[clap(long)]
+    #[clap(help = ll!("doctor-library"))]
+    library: Option<PathBuf>,
+
+    #[clap(long, env = "ANNI_DRIVE_TOKEN")]
+    #[clap(help = ll!("doctor-drive-token"))]
+    drive_token: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CheckStatus {
+    Pass,
+    /// Something optional is missing, or a check could not be performed.
+    Warn,
+    Fail,
+}
+
+impl Display for CheckStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckStatus::Pass => write!(f, "[PASS]"),
+            CheckStatus::Warn => write!(f, "[WARN]"),
+            CheckStatus::Fail => write!(f, "[FAIL]"),
+        }
+    }
+}
+
+/// Result of a single check, with a hint to fix it if it did not pass.
+#[derive(Debug)]
+struct CheckResult {
+    name: String,
+    status: CheckStatus,
+    message: String,
+    hint: Option<&'static str>,
+}
+
+impl CheckResult {
+    fn pass<N: Into<String>, M: Into<String>>(name: N, message: M) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Pass,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    fn warn<N: Into<String>, M: Into<String>>(name: N, message: M, hint: &'static str) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Warn,
+            message: message.into(),
+            hint: Some(hint),
+        }
+    }
+
+    fn fail<N: Into<String>, M: Into<String>>(name: N, message: M, hint: &'static str) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Fail,
+            message: message.into(),
+            hint: Some(hint),
+        }
+    }
+}
+
+impl Display for CheckResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}: {}", self.status, self.name, self.message)?;
+        if let Some(hint) = self.hint {
+            write!(f, "\n       hint: {hint}")?;
+        }
+        Ok(())
+    }
+}
+
+/// External tools used by anni, and whether they are required.
+const TOOLS: [(&str, bool, &str); 4] = [
+    (
+        FLAC_COMMAND,
+        true,
+        "Install `flac` from https://xiph.org/flac/ to split and verify flac files.",
+    ),
+    (
+        APE_COMMAND,
+        false,
+        "Install `mac` (Monkey's Audio) to split ape files.",
+    ),
+    (TAK_COMMAND, false, "Install `takc` to split tak files."),
+    (TTA_COMMAND, false, "Install `ttaenc` to split tta files."),
+];
+
+/// Find executable `name` in `paths`, which has the same format as `PATH`.
+fn find_tool<P: AsRef<OsStr>>(name: &str, paths: Option<P>) -> Option<PathBuf> {
+    let cwd = std::env::current_dir().ok()?;
+    which::which_in(name, paths, cwd).ok()
+}
+
+fn check_tools<P: AsRef<OsStr>>(paths: Option<P>) -> Vec<CheckResult> {
+    let paths = paths.map(|p| p.as_ref().to_os_string());
+    TOOLS
+        .iter()
+        .map(|&(name, required, hint)| {
+            let check = format!("tool `{name}`");
+            match find_tool(name, paths.as_ref()) {
+                Some(path) => CheckResult::pass(check, path.display().to_string()),
+                None if required => CheckResult::fail(check, "not found in PATH", hint),
+                None => CheckResult::warn(check, "not found in PATH", hint),
+            }
+        })
+        .collect()
+}
+
+/// Check the toolchain anni was built with, which is recorded by build script.
+fn check_toolchain() -> CheckResult {
+    const NAME: &str = "rust toolchain";
+    const HINT: &str = "anni requires a nightly toolchain to build, see `rust-toolchain.toml`.";
+
+    let version = env!("ANNI_RUSTC_VERSION");
+    if version.contains("nightly") {
+        CheckResult::pass(NAME, version)
+    } else {
+        CheckResult::warn(NAME, version, HINT)
+    }
+}
+
+fn check_repo(repo_root: Option<&Path>) -> CheckResult {
+    const NAME: &str = "metadata repository";
+
+    let Some(repo_root) = repo_root else {
+        return CheckResult::warn(
+            NAME,
+            "not configured",
+            "Set `ANNI_REPO` or pass `--repo` to use commands which need metadata repository.",
+        );
+    };
+
+    match RepositoryManager::new(repo_root).and_then(|m| m.into_owned_manager()) {
+        Ok(manager) => CheckResult::pass(
+            NAME,
+            format!(
+                "{} ({} albums)",
+                repo_root.display(),
+                manager.albums_iter().count()
+            ),
+        ),
+        Err(e) => CheckResult::fail(
+            NAME,
+            format!("{}: {e}", repo_root.display()),
+            "Make sure `ANNI_REPO` points to a valid metadata repository with `repo.toml`.",
+        ),
+    }
+}
+
+fn check_library<P: AsRef<Path>>(name: &str, library: P) -> CheckResult {
+    let library = library.as_ref();
+    match std::fs::read_dir(library) {
+        Ok(_) => CheckResult::pass(name, library.display().to_string()),
+        Err(e) => CheckResult::fail(
+            name,
+            format!("{}: {e}", library.display()),
+            "Make sure the library directory exists and is readable.",
+        ),
+    }
+}
+
+async fn check_workspace() -> Vec<CheckResult> {
+    const NAME: &str = "workspace";
+
+    let workspace = match std::env::current_dir()
+        .map_err(anyhow::Error::from)
+        .and_then(|dir| Ok(AnniWorkspace::find(dir)?))
+    {
+        Ok(workspace) => workspace,
+        Err(_) => {
+            return vec![CheckResult::warn(
+                NAME,
+                "not found in current directory",
+                "Run `anni workspace init` to create a workspace if needed.",
+            )]
+        }
+    };
+
+    let config = match workspace.get_config() {
+        Ok(config) => config,
+        Err(e) => {
+            return vec![CheckResult::fail(
+                NAME,
+                format!("{}: {e}", workspace.config_path().display()),
+                "Fix syntax errors in workspace config.",
+            )]
+        }
+    };
+
+    let mut results = vec![CheckResult::pass(
+        NAME,
+        workspace.workspace_root().display().to_string(),
+    )];
+    if let WorkspaceMetadata::Remote { endpoint, token } = config.metadata() {
+        results.push(check_annim(&endpoint, token.as_deref()).await);
+    }
+    if let Some(library) = config.publish_to() {
+        results.push(check_library("workspace publish target", &library.path));
+    }
+    results
+}
+
+async fn check_annim(endpoint: &str, token: Option<&str>) -> CheckResult {
+    const NAME: &str = "annim endpoint";
+
+    let client = AnnimClient::new(endpoint.to_string(), token);
+    match client.album(uuid::Uuid::nil()).await {
+        Ok(_) => CheckResult::pass(NAME, endpoint),
+        Err(e) => CheckResult::fail(
+            NAME,
+            format!("{endpoint}: {e}"),
+            "Make sure annim is running and the endpoint and token in workspace config are correct.",
+        ),
+    }
+}
+
+fn check_drive_token(path: &Path) -> CheckResult {
+    const NAME: &str = "drive token";
+    const HINT: &str = "Re-authorize annil to regenerate the Google Drive token.";
+
+    let token: serde_json::Value = match std::fs::read_to_string(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|data| Ok(serde_json::from_str(&data)?))
+    {
+        Ok(token) => token,
+        Err(e) => return CheckResult::fail(NAME, format!("{}: {e}", path.display()), HINT),
+    };
+
+    // token file is a list of tokens with scopes
+    let has_refresh_token = token.as_array().is_some_and(|tokens| {
+        tokens
+            .iter()
+            .any(|t| t["token"]["refresh_token"].as_str().is_some())
+    });
+    if has_refresh_token {
+        CheckResult::pass(NAME, path.display().to_string())
+    } else {
+        CheckResult::fail(
+            NAME,
+            format!("{}: refresh token not found", path.display()),
+            HINT,
+        )
+    }
+}
+
+#[handler(DoctorSubcommand)]
+async fn handle_doctor(me: DoctorSubcommand) -> anyhow::Result<()> {
+    let mut results = check_tools(std::env::var_os("PATH"));
+    results.push(check_toolchain());
+    results.push(check_repo(me.repo_root.as_deref()));
+    if let Some(library) = &me.library {
+        results.push(check_library("library", library));
+    }
+    results.extend(check_workspace().await);
+    if let Some(token) = &me.drive_token {
+        results.push(check_drive_token(token));
+    }
+
+    let mut failed = 0;
+    for result in results.iter() {
+        if result.status == CheckStatus::Fail {
+            failed += 1;
+        }
+        println!("{result}");
+    }
+
+    if failed > 0 {
+        bail!("{failed} check(s) failed");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stub_tool(dir: &Path, name: &str) {
+        let path = dir.join(format!("{name}{}", std::env::consts::EXE_SUFFIX));
+        std::fs::write(&path, "").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_check_tools() {
+        let dir = tempfile::tempdir().unwrap();
+        stub_tool(dir.path(), FLAC_COMMAND);
+        stub_tool(dir.path(), TAK_COMMAND);
+
+        let results = check_tools(Some(dir.path()));
+        let status: Vec<_> = results.iter().map(|r| r.status).collect();
+        assert_eq!(
+            status,
+            [
+                CheckStatus::Pass,
+                CheckStatus::Warn,
+                CheckStatus::Pass,
+                CheckStatus::Warn
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_tools_missing_required() {
+        let dir = tempfile::tempdir().unwrap();
+        let results = check_tools(Some(dir.path()));
+        assert_eq!(results[0].status, CheckStatus::Fail);
+        assert!(results[0].hint.is_some());
+        assert!(results[1..].iter().all(|r| r.status == CheckStatus::Warn));
+    }
+
+    #[test]
+    fn test_check_drive_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("token.json");
+
+        std::fs::write(
+            &path,
+            r#"[{"scopes":["https://www.googleapis.com/auth/drive"],"token":{"access_token":"a","refresh_token":"r","expires_at":null}}]"#,
+        )
+        .unwrap();
+        assert_eq!(check_drive_token(&path).status, CheckStatus::Pass);
+
+        std::fs::write(&path, "[]").unwrap();
+        assert_eq!(check_drive_token(&path).status, CheckStatus::Fail);
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(check_drive_token(&path).status, CheckStatus::Fail);
+    }
+}
@@ -0,0 +1,83 @@
+use crate::args::{FlacInputFile, InputPath};
+use crate::ll;
+use anni_flac::blocks::{BlockPicture, PictureType};
+use anni_flac::FlacHeader;
+use clap::{ArgGroup, Args};
+use clap_handler::handler;
+use std::path::PathBuf;
+
+#[derive(Args, Debug, Clone)]
+#[clap(group = ArgGroup::new("operation").required(true))]
+pub struct FlacCoverAction {
+    #[clap(long, group = "operation")]
+    #[clap(help = ll!("flac-cover-set"))]
+    set: Option<PathBuf>,
+
+    #[clap(long, group = "operation")]
+    #[clap(help = ll!("flac-cover-extract"))]
+    extract: Option<PathBuf>,
+
+    #[clap(long, group = "operation")]
+    #[clap(help = ll!("flac-cover-strip"))]
+    strip: bool,
+
+    #[clap(long, default_value = "1500")]
+    #[clap(help = ll!("flac-cover-max-size"))]
+    max_size: u32,
+
+    #[clap(required = true)]
+    filename: Vec<InputPath<FlacInputFile>>,
+}
+
+#[handler(FlacCoverAction)]
+fn flac_cover(me: &FlacCoverAction) -> anyhow::Result<()> {
+    let files: Vec<_> = me.filename.iter().flat_map(|path| path.iter()).collect();
+
+    if let Some(output) = &me.extract {
+        if files.len() != 1 {
+            anyhow::bail!("Only one file can be extracted at a time");
+        }
+        let header = FlacHeader::from_file(&files[0])?;
+        match header.front_cover() {
+            Some(cover) => std::fs::write(output, &cover.data)?,
+            None => anyhow::bail!("No front cover found in {}", files[0].display()),
+        }
+        return Ok(());
+    }
+
+    let cover = match &me.set {
+        Some(path) => {
+            let cover = BlockPicture::new(path, PictureType::CoverFront, String::new())?;
+            Some(cover.to_jpeg(me.max_size)?.unwrap_or(cover))
+        }
+        None => None,
+    };
+    for path in files {
+        debug!("Opening {}", path.display());
+        let mut header = FlacHeader::from_file(&path)?;
+        match &cover {
+            Some(cover) => {
+                header.set_front_cover(cover.clone());
+                info!("Set front cover of {}", path.display());
+            }
+            None => {
+                let removed = header.retain_front_cover();
+                let converted = match header.front_cover() {
+                    Some(cover) => cover.to_jpeg(me.max_size)?,
+                    None => None,
+                };
+                if removed == 0 && converted.is_none() {
+                    info!("No picture to strip in {}", path.display());
+                    continue;
+                }
+                if let Some(cover) = converted {
+                    header.set_front_cover(cover);
+                    info!("Converted front cover of {}", path.display());
+                }
+                info!("Removed {removed} picture(s) from {}", path.display());
+            }
+        }
+        header.save(Some(&path))?;
+    }
+    Ok(())
+}
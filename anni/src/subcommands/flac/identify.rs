@@ -0,0 +1,304 @@
+use crate::args::{FlacInputFile, InputPath};
+use crate::ll;
+use anni_common::traits::Decode;
+use anni_flac::blocks::{UserComment, UserCommentExt};
+use anni_flac::FlacHeader;
+use anni_split::codec::wav::WaveHeader;
+use anni_split::codec::{Decoder, FlacCommandDecoder};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use clap::Args;
+use clap_handler::handler;
+use rusty_chromaprint::{Configuration, FingerprintCompressor, Fingerprinter};
+use serde::Deserialize;
+use std::io::Read;
+use std::path::Path;
+
+const ACOUSTID_LOOKUP_URL: &str = "https://api.acoustid.org/v2/lookup";
+
+/// AcoustID only needs the first two minutes of audio, which is the same as `fpcalc`.
+const FINGERPRINT_MAX_SECONDS: u32 = 120;
+
+#[derive(Args, Debug, Clone)]
+pub struct FlacIdentifyAction {
+    #[clap(long, env = "ANNI_ACOUSTID_KEY")]
+    #[clap(help = ll!("flac-identify-api-key"))]
+    api_key: Option<String>,
+
+    #[clap(long, default_value = "5")]
+    #[clap(help = ll!("flac-identify-limit"))]
+    limit: usize,
+
+    #[clap(long)]
+    #[clap(help = ll!("flac-identify-apply"))]
+    apply: bool,
+
+    #[clap(required = true)]
+    filename: Vec<InputPath<FlacInputFile>>,
+}
+
+#[handler(FlacIdentifyAction)]
+async fn flac_identify(me: &FlacIdentifyAction) -> anyhow::Result<()> {
+    if me.api_key.is_none() {
+        warn!(target: "flac|identify", "AcoustID API key was not provided, only fingerprints would be printed.");
+    }
+
+    let client = reqwest::Client::new();
+    for path in me.filename.iter() {
+        for file in path.iter() {
+            debug!(target: "flac|identify", "Fingerprinting {}", file.display());
+            let fingerprint = Fingerprint::from_wav(FlacCommandDecoder(&file).decode()?)?;
+            println!("{}", file.display());
+
+            let api_key = match &me.api_key {
+                Some(api_key) => api_key,
+                None => {
+                    println!("  duration: {}", fingerprint.duration);
+                    println!("  fingerprint: {}", fingerprint.encode());
+                    continue;
+                }
+            };
+
+            let candidates = lookup(&client, api_key, &fingerprint).await?;
+            if candidates.is_empty() {
+                println!("  No candidate found.");
+                continue;
+            }
+            for candidate in candidates.iter().take(me.limit) {
+                println!("  {candidate}");
+            }
+
+            if me.apply {
+                apply_candidate(&file, &candidates[0])?;
+                info!(target: "flac|identify", "Applied metadata to {}", file.display());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Chromaprint fingerprint of an audio stream.
+pub(crate) struct Fingerprint {
+    /// Duration of the whole stream in seconds.
+    pub duration: u32,
+    pub raw: Vec<u32>,
+}
+
+impl Fingerprint {
+    /// Calculate fingerprint from a WAVE stream.
+    ///
+    /// Only the first [FINGERPRINT_MAX_SECONDS] seconds are used.
+    pub fn from_wav<R: Read>(mut reader: R) -> anyhow::Result<Self> {
+        let header = WaveHeader::from_reader(&mut reader)?;
+        let bytes_per_sample = (header.bit_per_sample / 8) as usize;
+        if !(2..=4).contains(&bytes_per_sample) {
+            bail!("Unsupported bit depth: {}", header.bit_per_sample);
+        }
+
+        let limit = header
+            .data_size
+            .min(header.byte_rate * FINGERPRINT_MAX_SECONDS);
+        let mut data = Vec::with_capacity(limit as usize);
+        reader.take(limit as u64).read_to_end(&mut data)?;
+
+        // chromaprint accepts 16-bit samples only, so keep the most significant 16 bits
+        let samples: Vec<i16> = data
+            .chunks_exact(bytes_per_sample)
+            .map(|s| i16::from_le_bytes([s[bytes_per_sample - 2], s[bytes_per_sample - 1]]))
+            .collect();
+
+        let mut printer = Fingerprinter::new(&Configuration::preset_test2());
+        printer
+            .start(header.sample_rate, header.channels as u32)
+            .map_err(|e| anyhow!("Failed to start fingerprinter: {e:?}"))?;
+        printer.consume(&samples);
+        printer.finish();
+
+        Ok(Self {
+            duration: header.data_size / header.byte_rate,
+            raw: printer.fingerprint().to_vec(),
+        })
+    }
+
+    /// Compressed and base64-encoded fingerprint, which is accepted by AcoustID.
+    pub fn encode(&self) -> String {
+        let config = Configuration::preset_test2();
+        let compressed = FingerprintCompressor::from(&config).compress(&self.raw);
+        URL_SAFE_NO_PAD.encode(compressed)
+    }
+}
+
+#[derive(Deserialize)]
+struct LookupResponse {
+    status: String,
+    error: Option<LookupError>,
+    #[serde(default)]
+    results: Vec<LookupResult>,
+}
+
+#[derive(Deserialize)]
+struct LookupError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct LookupResult {
+    score: f64,
+    #[serde(default)]
+    recordings: Vec<LookupRecording>,
+}
+
+#[derive(Deserialize)]
+struct LookupRecording {
+    id: String,
+    title: Option<String>,
+    #[serde(default)]
+    artists: Vec<LookupArtist>,
+    #[serde(default)]
+    releasegroups: Vec<LookupReleaseGroup>,
+}
+
+#[derive(Deserialize)]
+struct LookupArtist {
+    name: String,
+    joinphrase: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LookupReleaseGroup {
+    title: Option<String>,
+}
+
+/// Metadata suggestion of a track.
+struct Candidate {
+    score: f64,
+    recording_id: String,
+    title: String,
+    artist: String,
+    album: Option<String>,
+}
+
+impl std::fmt::Display for Candidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{:.2}] {} - {}", self.score, self.title, self.artist)?;
+        if let Some(album) = &self.album {
+            write!(f, " ({album})")?;
+        }
+        write!(f, " <{}>", self.recording_id)
+    }
+}
+
+async fn lookup(
+    client: &reqwest::Client,
+    api_key: &str,
+    fingerprint: &Fingerprint,
+) -> anyhow::Result<Vec<Candidate>> {
+    let duration = fingerprint.duration.to_string();
+    let encoded = fingerprint.encode();
+    let response: LookupResponse = client
+        .post(ACOUSTID_LOOKUP_URL)
+        .form(&[
+            ("client", api_key),
+            ("meta", "recordings releasegroups"),
+            ("duration", duration.as_str()),
+            ("fingerprint", encoded.as_str()),
+        ])
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    if response.status != "ok" {
+        let message = response.error.map(|e| e.message).unwrap_or(response.status);
+        bail!("AcoustID lookup failed: {message}");
+    }
+
+    let mut candidates: Vec<_> = response
+        .results
+        .into_iter()
+        .flat_map(|result| {
+            let score = result.score;
+            result.recordings.into_iter().filter_map(move |recording| {
+                let artist = recording
+                    .artists
+                    .iter()
+                    .map(|a| format!("{}{}", a.name, a.joinphrase.as_deref().unwrap_or("")))
+                    .collect();
+                Some(Candidate {
+                    score,
+                    recording_id: recording.id,
+                    title: recording.title?,
+                    artist,
+                    album: recording
+                        .releasegroups
+                        .into_iter()
+                        .find_map(|group| group.title),
+                })
+            })
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(candidates)
+}
+
+fn apply_candidate(path: &Path, candidate: &Candidate) -> anyhow::Result<()> {
+    let mut header = FlacHeader::from_file(path)?;
+    let comments = header.comments_mut();
+    comments
+        .comments
+        .retain(|c| !matches!(c.key().as_str(), "TITLE" | "ARTIST" | "ALBUM"));
+    comments.push(UserComment::title(&candidate.title));
+    comments.push(UserComment::artist(&candidate.artist));
+    if let Some(album) = &candidate.album {
+        comments.push(UserComment::album(album));
+    }
+    header.save(Some(path))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Fingerprint;
+    use anni_common::traits::Encode;
+    use anni_split::codec::wav::WaveHeader;
+    use std::io::Cursor;
+
+    /// Generate a deterministic 16-bit stereo WAVE stream.
+    fn generate_wav(seconds: u32) -> Vec<u8> {
+        let sample_rate = 44100;
+        let header = WaveHeader {
+            channels: 2,
+            sample_rate,
+            byte_rate: sample_rate * 4,
+            block_align: 4,
+            bit_per_sample: 16,
+            data_size: sample_rate * 4 * seconds,
+        };
+
+        let mut wav = Vec::new();
+        header.write_to(&mut wav).unwrap();
+        for i in 0..sample_rate * seconds {
+            let t = i as f64 / sample_rate as f64;
+            // a chord whose pitch changes every half second
+            let base = 220.0 * (1.0 + (t * 2.0).floor() % 4.0 / 4.0);
+            let value = (t * base * std::f64::consts::TAU).sin() * 0.5
+                + (t * base * 1.5 * std::f64::consts::TAU).sin() * 0.3;
+            let sample = (value * i16::MAX as f64) as i16;
+            wav.extend_from_slice(&sample.to_le_bytes());
+            wav.extend_from_slice(&sample.to_le_bytes());
+        }
+        wav
+    }
+
+    #[test]
+    fn test_fingerprint_deterministic() {
+        let wav = generate_wav(10);
+        let first = Fingerprint::from_wav(Cursor::new(&wav)).unwrap();
+        let second = Fingerprint::from_wav(Cursor::new(&wav)).unwrap();
+
+        assert_eq!(first.duration, 10);
+        assert!(!first.raw.is_empty());
+        assert_eq!(first.raw, second.raw);
+        assert_eq!(first.encode(), second.encode());
+    }
+}
@@ -6,6 +6,14 @@ use clap::{Args, Subcommand, ValueEnum};
 use clap_handler::{handler, Handler};
 use std::io::Write;
 
+mod cover;
+mod identify;
+mod tags;
+
+pub use cover::FlacCoverAction;
+pub use identify::FlacIdentifyAction;
+pub use tags::FlacTagsSubcommand;
+
 #[derive(Args, Handler, Debug, Clone)]
 #[clap(about = ll!("flac"))]
 pub struct FlacSubcommand {
@@ -15,10 +23,20 @@ pub struct FlacSubcommand {
 
 #[derive(Subcommand, Handler, Debug, Clone)]
 pub enum FlacAction {
+    #[clap(about = ll!("flac-cover"))]
+    Cover(FlacCoverAction),
     #[clap(about = ll!("flac-export"))]
     Export(FlacExportAction),
     RemoveID3(FlacRemoveID3Action),
     RemoveUUID(FlacRemoveUUIDAction),
+    #[clap(about = ll!("flac-identify"))]
+    Identify(FlacIdentifyAction),
+    #[clap(about = ll!("flac-repair"))]
+    Repair(FlacRepairAction),
+    #[clap(about = ll!("flac-tags"))]
+    Tags(FlacTagsSubcommand),
+    #[clap(about = ll!("flac-verify"), long_flag = "verify")]
+    Verify(FlacVerifyAction),
 }
 
 #[derive(Args, Debug, Clone)]
@@ -172,3 +190,85 @@ fn flac_remove_uuid(me: &FlacRemoveUUIDAction) -> anyhow::Result<()> {
     }
     Ok(())
 }
+
+#[derive(Args, Debug, Clone)]
+pub struct FlacRepairAction {
+    #[clap(required = true)]
+    filename: Vec<InputPath<FlacInputFile>>,
+}
+
+#[handler(FlacRepairAction)]
+fn flac_repair(me: &FlacRepairAction) -> anyhow::Result<()> {
+    for filenames in me.filename.iter() {
+        for path in filenames.iter() {
+            debug!("Opening {}", path.display());
+            // is_last flags may be wrong in broken files, so they are not trusted here
+            let mut header = FlacHeader::from_file_lenient(&path)?;
+            let report = header.repair()?;
+            if report.is_healthy() {
+                info!("{} is already healthy", path.display());
+                continue;
+            }
+
+            if report.padding_merged {
+                info!("Merged padding blocks of {}", path.display());
+            }
+            if report.is_last_fixed {
+                info!("Fixed last block flag of {}", path.display());
+            }
+            if report.seek_table_rebuilt {
+                info!("Rebuilt seek table of {}", path.display());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct FlacVerifyAction {
+    #[clap(long)]
+    #[clap(help = ll!("flac-verify-fix"))]
+    fix: bool,
+
+    #[clap(required = true)]
+    filename: Vec<InputPath<FlacInputFile>>,
+}
+
+#[handler(FlacVerifyAction)]
+fn flac_verify(me: &FlacVerifyAction) -> anyhow::Result<()> {
+    let mut failed = 0;
+    for filenames in me.filename.iter() {
+        for path in filenames.iter() {
+            debug!("Opening {}", path.display());
+            let mut header = FlacHeader::from_file(&path)?;
+            if header.stream_info().md5_signature == [0; 16] {
+                if me.fix {
+                    header.fix_md5()?;
+                    header.save(Some(&path))?;
+                    println!("[FIXED] {}", path.display());
+                } else {
+                    failed += 1;
+                    println!("[UNSET] {}", path.display());
+                }
+                continue;
+            }
+
+            match header.verify_md5() {
+                Ok(true) => println!("[PASS] {}", path.display()),
+                Ok(false) => {
+                    failed += 1;
+                    println!("[FAIL] {}", path.display());
+                }
+                Err(e) => {
+                    failed += 1;
+                    println!("[FAIL] {}: {e}", path.display());
+                }
+            }
+        }
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{failed} file(s) failed verification");
+    }
+    Ok(())
+}
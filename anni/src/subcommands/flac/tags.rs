@@ -0,0 +1,87 @@
+use crate::args::{FlacInputFile, InputPath};
+use crate::ll;
+use anni_flac::blocks::UserComment;
+use anni_flac::FlacHeader;
+use clap::{Args, Subcommand};
+use clap_handler::{handler, Handler};
+
+#[derive(Args, Handler, Debug, Clone)]
+pub struct FlacTagsSubcommand {
+    #[clap(subcommand)]
+    action: FlacTagsAction,
+}
+
+#[derive(Subcommand, Handler, Debug, Clone)]
+pub enum FlacTagsAction {
+    #[clap(about = ll!("flac-tags-set"))]
+    Set(FlacTagsSetAction),
+    #[clap(about = ll!("flac-tags-remove"))]
+    Remove(FlacTagsRemoveAction),
+}
+
+/// Validate a vorbis comment field name, and normalize it to uppercase.
+///
+/// Field names are case-insensitive, and may only contain printable ASCII characters except `=`.
+fn normalize_key(key: &str) -> anyhow::Result<String> {
+    if key.is_empty() || !key.chars().all(|c| (' '..='}').contains(&c) && c != '=') {
+        anyhow::bail!("Invalid vorbis comment field name: {key:?}");
+    }
+    Ok(key.to_ascii_uppercase())
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct FlacTagsSetAction {
+    #[clap(long)]
+    #[clap(help = ll!("flac-tags-set-append"))]
+    append: bool,
+
+    key: String,
+    value: String,
+
+    #[clap(required = true)]
+    filename: Vec<InputPath<FlacInputFile>>,
+}
+
+#[handler(FlacTagsSetAction)]
+fn flac_tags_set(me: &FlacTagsSetAction) -> anyhow::Result<()> {
+    let key = normalize_key(&me.key)?;
+    for filenames in me.filename.iter() {
+        for path in filenames.iter() {
+            debug!("Opening {}", path.display());
+            let mut header = FlacHeader::from_file(&path)?;
+            let comments = header.comments_mut();
+            if me.append {
+                comments.push(UserComment::new(format!("{key}={}", me.value)));
+            } else {
+                comments.set(&key, &me.value);
+            }
+            header.save(Some(&path))?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct FlacTagsRemoveAction {
+    key: String,
+
+    #[clap(required = true)]
+    filename: Vec<InputPath<FlacInputFile>>,
+}
+
+#[handler(FlacTagsRemoveAction)]
+fn flac_tags_remove(me: &FlacTagsRemoveAction) -> anyhow::Result<()> {
+    let key = normalize_key(&me.key)?;
+    for filenames in me.filename.iter() {
+        for path in filenames.iter() {
+            debug!("Opening {}", path.display());
+            let mut header = FlacHeader::from_file(&path)?;
+            if header.comments_mut().remove(&key) == 0 {
+                info!("No {key} found in {}", path.display());
+                continue;
+            }
+            header.save(Some(&path))?;
+        }
+    }
+    Ok(())
+}
@@ -1,16 +1,21 @@
 use crate::{ball, ll};
 use anni_common::fs;
+use anni_flac::blocks::PictureType;
+use anni_flac::{FlacHeader, MetadataBlockData};
 use anni_provider::fs::LocalFileSystemProvider;
-use anni_provider::providers::CommonConventionProvider;
-use anni_provider::strict_album_path;
+use anni_provider::providers::{CommonConventionProvider, CommonStrictProvider};
+use anni_provider::{strict_album_path, AnniProvider};
 use anni_repo::db::RepoDatabaseRead;
 use anni_repo::library::{file_name, AlbumFolderInfo};
 use anni_repo::models::ApplyMetadata;
 use anni_repo::RepositoryManager;
 use clap::{Args, Subcommand};
 use clap_handler::{handler, Context, Handler};
+use serde::Serialize;
+use std::num::NonZeroU8;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use tokio::io::AsyncReadExt;
 use uuid::Uuid;
 
 #[derive(Args, Debug, Clone, Handler)]
@@ -39,6 +44,10 @@ pub enum LibraryAction {
     ApplyTag(LibraryApplyTagAction),
     Link(LibraryLinkAction),
     Check(LibraryCheckAction),
+    #[clap(about = ll!("library-audit"))]
+    Audit(LibraryAuditAction),
+    #[clap(about = ll!("library-covers"))]
+    Covers(LibraryCoversSubcommand),
 }
 
 #[derive(Args, Debug, Clone)]
@@ -161,7 +170,8 @@ pub async fn library_link(me: LibraryLinkAction, manager: RepositoryManager) ->
         Box::new(LocalFileSystemProvider),
     )
     .await?;
-    for (album_id, album_from) in provider.albums {
+    let all_discs = provider.discs.into_inner();
+    for (album_id, album_from) in provider.albums.into_inner() {
         // 4. create album_id folder
         let album_to = strict_album_path(&to, &album_id, me.layer);
         if me.incremental && album_to.exists() {
@@ -176,7 +186,7 @@ pub async fn library_link(me: LibraryLinkAction, manager: RepositoryManager) ->
         )?;
 
         let discs = vec![album_from];
-        let discs = provider.discs.get(&album_id).unwrap_or(&discs);
+        let discs = all_discs.get(&album_id).unwrap_or(&discs);
         for (i, disc_from) in discs.iter().enumerate() {
             // 6. create disc folder
             let disc_to = album_to.join(format!("{}", i + 1));
@@ -283,3 +293,393 @@ pub async fn library_check(
 
     Ok(())
 }
+
+#[derive(Args, Debug, Clone)]
+pub struct LibraryAuditAction {
+    #[clap(short, long, default_value = "2")]
+    layer: usize,
+
+    #[clap(long)]
+    #[clap(help = ll!("library-audit-verify"))]
+    verify: bool,
+
+    #[clap(long)]
+    #[clap(help = ll!("library-audit-json"))]
+    json: bool,
+
+    path: PathBuf,
+}
+
+/// A single problem found by `library audit`.
+#[derive(Serialize, Debug)]
+pub struct AuditEntry {
+    album_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    disc_id: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    track_id: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+impl AuditEntry {
+    fn new(album_id: &str, disc_id: Option<u8>, track_id: Option<u8>) -> Self {
+        Self {
+            album_id: album_id.to_string(),
+            disc_id,
+            track_id,
+            detail: None,
+        }
+    }
+
+    fn detail(mut self, detail: String) -> Self {
+        self.detail = Some(detail);
+        self
+    }
+}
+
+impl std::fmt::Display for AuditEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "album = {}", self.album_id)?;
+        if let Some(disc_id) = self.disc_id {
+            write!(f, ", disc = {disc_id}")?;
+        }
+        if let Some(track_id) = self.track_id {
+            write!(f, ", track = {track_id}")?;
+        }
+        if let Some(detail) = &self.detail {
+            write!(f, ": {detail}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Categorized report of `library audit`.
+#[derive(Serialize, Default, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct AuditReport {
+    /// Albums, discs or tracks which exist in repository but not in library.
+    missing_files: Vec<AuditEntry>,
+    /// Albums which exist in library but not in repository.
+    missing_metadata: Vec<AuditEntry>,
+    /// Discs or tracks whose count in library differs from repository.
+    count_mismatch: Vec<AuditEntry>,
+    /// Album or disc covers which do not exist.
+    missing_cover: Vec<AuditEntry>,
+    /// Audio files which can not be parsed or verified.
+    corrupt_audio: Vec<AuditEntry>,
+}
+
+impl AuditReport {
+    fn is_empty(&self) -> bool {
+        self.missing_files.is_empty()
+            && self.missing_metadata.is_empty()
+            && self.count_mismatch.is_empty()
+            && self.missing_cover.is_empty()
+            && self.corrupt_audio.is_empty()
+    }
+
+    fn sort(&mut self) {
+        for entries in [
+            &mut self.missing_files,
+            &mut self.missing_metadata,
+            &mut self.count_mismatch,
+            &mut self.missing_cover,
+            &mut self.corrupt_audio,
+        ] {
+            entries.sort_by(|a, b| {
+                (&a.album_id, a.disc_id, a.track_id).cmp(&(&b.album_id, b.disc_id, b.track_id))
+            });
+        }
+    }
+
+    fn print(&self) {
+        for (category, entries) in [
+            ("MISSING", &self.missing_files),
+            ("UNKNOWN", &self.missing_metadata),
+            ("COUNT", &self.count_mismatch),
+            ("COVER", &self.missing_cover),
+            ("CORRUPT", &self.corrupt_audio),
+        ] {
+            for entry in entries {
+                log::error!("[{category}] {entry}");
+            }
+        }
+    }
+}
+
+fn count_flac_files(path: &Path) -> anyhow::Result<usize> {
+    let mut count = 0;
+    for entry in fs::read_dir(path)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().map_or(false, |ext| ext == "flac") {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+#[handler(LibraryAuditAction)]
+pub async fn library_audit(
+    me: LibraryAuditAction,
+    manager: RepositoryManager,
+) -> anyhow::Result<()> {
+    let manager = manager.into_owned_manager()?;
+
+    // build a temporary database for querying
+    let repo_path = std::env::temp_dir().join(format!("anni-audit-{}.db", Uuid::new_v4()));
+    manager.to_database(&repo_path)?;
+    let database = RepoDatabaseRead::new(&repo_path)?;
+
+    let provider =
+        CommonStrictProvider::new(me.path.clone(), me.layer, Box::new(LocalFileSystemProvider))
+            .await?;
+    let library_albums = provider.albums().await?;
+
+    let mut report = AuditReport::default();
+    for album in database.get_albums()? {
+        let album_id = album.album_id.0.to_string();
+        if !library_albums.contains(album_id.as_str()) {
+            report
+                .missing_files
+                .push(AuditEntry::new(&album_id, None, None));
+            continue;
+        }
+
+        if provider.get_cover(&album_id, None).await.is_err() {
+            report
+                .missing_cover
+                .push(AuditEntry::new(&album_id, None, None));
+        }
+
+        let album_path = strict_album_path(&me.path, &album_id, me.layer);
+        let discs = database.get_discs(album.album_id.0)?;
+        let library_discs = fs::read_dir(&album_path)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .count();
+        if library_discs != discs.len() {
+            report
+                .count_mismatch
+                .push(AuditEntry::new(&album_id, None, None).detail(format!(
+                    "expected {} discs, found {library_discs}",
+                    discs.len()
+                )));
+        }
+
+        for disc in discs {
+            let disc_id = disc.disc_id;
+            let disc_path = match provider
+                .get_disc(&album_id, NonZeroU8::new(disc_id).unwrap())
+                .await
+            {
+                Ok(disc) => disc.path,
+                Err(_) => {
+                    report
+                        .missing_files
+                        .push(AuditEntry::new(&album_id, Some(disc_id), None));
+                    continue;
+                }
+            };
+
+            if !disc_path.join("cover.jpg").exists() {
+                report
+                    .missing_cover
+                    .push(AuditEntry::new(&album_id, Some(disc_id), None));
+            }
+
+            let tracks = database.get_tracks(album.album_id.0, disc_id)?;
+            let library_tracks = count_flac_files(&disc_path)?;
+            if library_tracks != tracks.len() {
+                report
+                    .count_mismatch
+                    .push(
+                        AuditEntry::new(&album_id, Some(disc_id), None).detail(format!(
+                            "expected {} tracks, found {library_tracks}",
+                            tracks.len()
+                        )),
+                    );
+            }
+
+            for track in tracks {
+                let track_id = track.track_id;
+                let track_path = disc_path.join(format!("{track_id}.flac"));
+                if !track_path.exists() {
+                    report.missing_files.push(AuditEntry::new(
+                        &album_id,
+                        Some(disc_id),
+                        Some(track_id),
+                    ));
+                    continue;
+                }
+
+                let error = match FlacHeader::from_file(&track_path) {
+                    Err(e) => Some(e.to_string()),
+                    // verify MD5 signature of decoded audio
+                    Ok(header) if me.verify => match header.verify_md5() {
+                        Ok(true) => None,
+                        Ok(false) => Some("MD5 verification failed".to_string()),
+                        Err(e) => Some(format!("MD5 verification failed: {e}")),
+                    },
+                    Ok(_) => None,
+                };
+                if let Some(error) = error {
+                    report.corrupt_audio.push(
+                        AuditEntry::new(&album_id, Some(disc_id), Some(track_id)).detail(error),
+                    );
+                }
+            }
+        }
+    }
+
+    for album_id in library_albums.iter() {
+        let known = Uuid::parse_str(album_id)
+            .map(|id| manager.album(&id).is_some())
+            .unwrap_or(false);
+        if !known {
+            report.missing_metadata.push(
+                AuditEntry::new(album_id, None, None).detail(
+                    strict_album_path(&me.path, album_id, me.layer)
+                        .display()
+                        .to_string(),
+                ),
+            );
+        }
+    }
+    drop(database);
+    let _ = std::fs::remove_file(&repo_path);
+    report.sort();
+
+    if me.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if report.is_empty() {
+        log::info!("No problem found in library.");
+    } else {
+        report.print();
+    }
+
+    Ok(())
+}
+
+#[derive(Args, Debug, Clone, Handler)]
+pub struct LibraryCoversSubcommand {
+    #[clap(subcommand)]
+    action: LibraryCoversAction,
+}
+
+#[derive(Subcommand, Debug, Clone, Handler)]
+pub enum LibraryCoversAction {
+    #[clap(about = ll!("library-covers-export"))]
+    Export(LibraryCoversExportAction),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct LibraryCoversExportAction {
+    #[clap(short, long, default_value = "2")]
+    layer: usize,
+
+    #[clap(long)]
+    #[clap(help = ll!("library-covers-export-size"))]
+    size: Option<u32>,
+
+    library: PathBuf,
+    output: PathBuf,
+}
+
+/// Extract the front cover embedded in tracks of the first disc of an album.
+///
+/// Tracks are tried in order, and broken flac files are skipped with a warning.
+async fn embedded_cover(
+    provider: &CommonStrictProvider,
+    album_id: &str,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let disc = match provider
+        .get_disc(album_id, NonZeroU8::new(1).unwrap())
+        .await
+    {
+        Ok(disc) => disc,
+        Err(_) => return Ok(None),
+    };
+    let mut tracks = fs::get_ext_files(&disc.path, "flac", false)?;
+    alphanumeric_sort::sort_path_slice(&mut tracks);
+
+    for track_path in tracks {
+        let header = match FlacHeader::from_file(&track_path) {
+            Ok(header) => header,
+            Err(e) => {
+                log::warn!("[COVER] Skipped broken file {}: {e}", track_path.display());
+                continue;
+            }
+        };
+        let cover = header
+            .blocks
+            .into_iter()
+            .find_map(|block| match block.data {
+                MetadataBlockData::Picture(picture)
+                    if picture.picture_type == PictureType::CoverFront =>
+                {
+                    Some(picture.data)
+                }
+                _ => None,
+            });
+        if cover.is_some() {
+            return Ok(cover);
+        }
+    }
+    Ok(None)
+}
+
+#[handler(LibraryCoversExportAction)]
+pub async fn library_covers_export(me: LibraryCoversExportAction) -> anyhow::Result<()> {
+    let provider = CommonStrictProvider::new(
+        me.library.clone(),
+        me.layer,
+        Box::new(LocalFileSystemProvider),
+    )
+    .await?;
+    fs::create_dir_all(&me.output)?;
+
+    let mut albums: Vec<_> = provider
+        .albums()
+        .await?
+        .into_iter()
+        .map(|album_id| album_id.to_string())
+        .collect();
+    albums.sort();
+
+    let mut exported = 0;
+    for album_id in albums {
+        let cover = match provider.get_cover(&album_id, None).await {
+            Ok(mut reader) => {
+                let mut data = Vec::new();
+                reader.read_to_end(&mut data).await?;
+                Some(data)
+            }
+            Err(_) => embedded_cover(&provider, &album_id).await?,
+        };
+        let Some(cover) = cover else {
+            log::warn!("[COVER] Album cover not found: {album_id}");
+            continue;
+        };
+
+        let output = me.output.join(format!("{album_id}.jpg"));
+        let is_jpeg = cover.starts_with(&[0xff, 0xd8, 0xff]);
+        if me.size.is_none() && is_jpeg {
+            fs::write(&output, cover)?;
+        } else {
+            // resize to thumbnail, or convert embedded covers in other formats
+            let mut image = image::load_from_memory(&cover)?;
+            if let Some(size) = me.size {
+                image = image.thumbnail(size, size);
+            }
+            image
+                .to_rgb8()
+                .save_with_format(&output, image::ImageFormat::Jpeg)?;
+        }
+        debug!("Exported cover of {album_id} to {}", output.display());
+        exported += 1;
+    }
+
+    log::info!("Exported {exported} covers to {}", me.output.display());
+    Ok(())
+}
@@ -1,5 +1,6 @@
 pub mod completions;
 pub mod convention;
+pub mod doctor;
 pub mod flac;
 pub mod library;
 pub mod repo;
@@ -8,6 +9,7 @@ pub mod workspace;
 
 pub use completions::CompletionsSubcommand;
 pub use convention::ConventionSubcommand;
+pub use doctor::DoctorSubcommand;
 pub use flac::FlacSubcommand;
 pub use library::LibrarySubcommand;
 pub use repo::RepoSubcommand;
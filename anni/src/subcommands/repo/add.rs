@@ -1,11 +1,11 @@
-use crate::repo::is_album_folder;
+use crate::repo::{commit_changes, is_album_folder};
 use crate::{ball, ll};
 use anni_common::fs;
-use anni_flac::error::FlacError;
-use anni_flac::FlacHeader;
 use anni_metadata::model::{Album, AlbumInfo, Disc, DiscInfo};
+use anni_repo::error::AudioTagsError;
 use anni_repo::library::{file_name, AlbumFolderInfo, DiscFolderInfo};
 use anni_repo::prelude::*;
+use anni_repo::tags::{audio_extensions, read_audio_tags, ReplayGain};
 use anni_repo::RepositoryManager;
 use clap::Args;
 use clap_handler::handler;
@@ -21,6 +21,10 @@ pub struct RepoAddAction {
     #[clap(short = 'D', long = "duplicate")]
     allow_duplicate: bool,
 
+    #[clap(long)]
+    #[clap(help = ll!("repo-commit"))]
+    commit: bool,
+
     #[clap(required = true)]
     directories: Vec<PathBuf>,
 }
@@ -49,12 +53,21 @@ fn repo_add(me: RepoAddAction, manager: &RepositoryManager) -> anyhow::Result<()
             bail!("Subdirectory count != disc number!")
         }
 
+        // album ReplayGain is the same in every track, take it from the first one that has it
+        let mut album_gain = ReplayGain::default();
         let discs = directories
             .iter()
             .map(|dir| {
-                let mut files = fs::get_ext_files(PathBuf::from(dir), "flac", false)?;
+                // FLAC files are preferred if there are audio files in multiple formats
+                let mut files = Vec::new();
+                for extension in audio_extensions() {
+                    files = fs::get_ext_files(PathBuf::from(dir), extension, false)?;
+                    if !files.is_empty() {
+                        break;
+                    }
+                }
                 if files.is_empty() {
-                    bail!("No FLAC files found in {}", dir.display())
+                    bail!("No audio files found in {}", dir.display())
                 }
 
                 alphanumeric_sort::sort_path_slice(&mut files);
@@ -67,11 +80,13 @@ fn repo_add(me: RepoAddAction, manager: &RepositoryManager) -> anyhow::Result<()
                 let tracks = files
                     .iter()
                     .map(|path| {
-                        let header = FlacHeader::from_file(path)?;
-                        let track: RepoTrack = header.into();
-                        Ok(track.0)
+                        let tags = read_audio_tags(path)?;
+                        if album_gain.album_gain.is_none() {
+                            album_gain = tags.replay_gain();
+                        }
+                        Ok(RepoTrack::from_tags(tags.as_ref()).0)
                     })
-                    .collect::<Result<Vec<_>, FlacError>>()?;
+                    .collect::<Result<Vec<_>, AudioTagsError>>()?;
 
                 Ok(Disc::new(disc, tracks))
             })
@@ -83,17 +98,23 @@ fn repo_add(me: RepoAddAction, manager: &RepositoryManager) -> anyhow::Result<()
                 edition,
                 release_date,
                 catalog: catalog.to_string(),
+                gain: album_gain.album_gain,
+                peak: album_gain.album_peak,
                 ..Default::default()
             },
             discs,
         );
 
+        let message = format!("Add {} {}", album.catalog(), album.full_title());
         manager.add_album(album, me.allow_duplicate)?;
         if me.open_editor {
             for file in manager.album_paths(&catalog)? {
                 edit::edit_file(&file)?;
             }
         }
+        if me.commit {
+            commit_changes(manager, &catalog, &message)?;
+        }
     }
     Ok(())
 }
@@ -0,0 +1,50 @@
+use crate::{fl, ll};
+use anni_repo::dedupe::DedupeOptions;
+use anni_repo::RepositoryManager;
+use clap::Args;
+use clap_handler::handler;
+
+#[derive(Args, Debug, Clone)]
+pub struct RepoDedupeAction {
+    #[clap(long)]
+    #[clap(help = ll!("repo-dedupe-report"))]
+    report: bool,
+
+    #[clap(long)]
+    #[clap(help = ll!("repo-dedupe-case-sensitive"))]
+    case_sensitive: bool,
+
+    #[clap(long)]
+    #[clap(help = ll!("repo-dedupe-keep-whitespace"))]
+    keep_whitespace: bool,
+}
+
+#[handler(RepoDedupeAction)]
+fn repo_dedupe(me: RepoDedupeAction, manager: RepositoryManager) -> anyhow::Result<()> {
+    let manager = manager.into_owned_manager()?;
+    let duplicates = manager.find_duplicate_tracks(&DedupeOptions {
+        ignore_case: !me.case_sensitive,
+        collapse_whitespace: !me.keep_whitespace,
+    });
+
+    if me.report {
+        for duplicate in duplicates.iter() {
+            println!("{} - {}", duplicate.title, duplicate.artist);
+            for track in duplicate.tracks.iter() {
+                let catalog = manager
+                    .album(&track.album_id)
+                    .map_or("", |album| album.catalog());
+                println!(
+                    "  {} {}/{} [{catalog}]",
+                    track.album_id, track.disc_id, track.track_id
+                );
+            }
+        }
+    }
+    info!(
+        target: "repo|dedupe",
+        "{}",
+        fl!("repo-dedupe-done", count = duplicates.len())
+    );
+    Ok(())
+}
@@ -16,7 +16,10 @@ use cuna::Cuna;
 use musicbrainz_rs::entity::artist_credit::ArtistCredit;
 use musicbrainz_rs::entity::release::Release;
 use musicbrainz_rs::Fetch;
+use serde::Deserialize;
 use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
 #[derive(Args, Handler, Debug, Clone)]
@@ -194,6 +197,29 @@ async fn repo_get_cue(
     Ok(())
 }
 
+/// MusicBrainz allows one request per second on average
+const MUSICBRAINZ_INTERVAL: Duration = Duration::from_secs(1);
+const MUSICBRAINZ_USER_AGENT: &str = concat!(
+    "anni/",
+    env!("CARGO_PKG_VERSION"),
+    " ( https://github.com/ProjectAnni/anni )"
+);
+
+/// Time when the next request to MusicBrainz is allowed
+static MUSICBRAINZ_NEXT_REQUEST: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Wait until the next request to MusicBrainz is allowed.
+async fn musicbrainz_throttle() {
+    let wait = {
+        let mut next = MUSICBRAINZ_NEXT_REQUEST.lock().unwrap();
+        let now = Instant::now();
+        let slot = next.map_or(now, |next| next.max(now));
+        *next = Some(slot + MUSICBRAINZ_INTERVAL);
+        slot - now
+    };
+    tokio::time::sleep(wait).await;
+}
+
 #[derive(Args, Debug, Clone)]
 pub struct RepoGetMusicbrainz {
     #[clap(
@@ -203,26 +229,77 @@ pub struct RepoGetMusicbrainz {
     #[clap(long)]
     base_url: String,
 
+    /// MusicBrainz release id. If not provided, release is searched by catalog, barcode and title.
     #[clap(long)]
-    id: String,
+    id: Option<String>,
+    #[clap(long)]
+    barcode: Option<String>,
+    #[clap(long)]
+    title: Option<String>,
     catalog: String,
 }
 
-#[handler(RepoGetMusicbrainz)]
-async fn repo_get_musicbrainz(
-    options: RepoGetMusicbrainz,
-    manager: &RepositoryManager,
-    get: &RepoGetAction,
-) -> anyhow::Result<()> {
-    musicbrainz_rs::config::set_base_url(options.base_url);
+#[derive(Deserialize)]
+struct ReleaseSearchResponse {
+    releases: Vec<ReleaseSearchEntry>,
+}
 
-    let release = Release::fetch()
-        .id(&options.id)
-        .with_release_groups()
-        .with_recordings()
-        .with_artist_credits()
-        .execute()
+#[derive(Deserialize)]
+struct ReleaseSearchEntry {
+    id: String,
+    title: String,
+    #[serde(default)]
+    score: u8,
+}
+
+/// Quote `value` as a phrase in lucene query syntax.
+fn lucene_phrase(value: &str) -> String {
+    format!(r#""{}""#, value.replace('\\', r"\\").replace('"', r#"\""#))
+}
+
+/// Search releases by catalog number, and optionally barcode and title.
+/// Returns id of the best matched release.
+async fn search_release(options: &RepoGetMusicbrainz) -> anyhow::Result<String> {
+    let mut query = vec![format!("catno:{}", lucene_phrase(&options.catalog))];
+    if let Some(barcode) = &options.barcode {
+        query.push(format!("barcode:{}", lucene_phrase(barcode)));
+    }
+    if let Some(title) = &options.title {
+        query.push(format!("release:{}", lucene_phrase(title)));
+    }
+
+    musicbrainz_throttle().await;
+    let response: ReleaseSearchResponse = reqwest::Client::new()
+        .get(format!(
+            "{}/release",
+            options.base_url.trim_end_matches('/')
+        ))
+        .header(reqwest::header::USER_AGENT, MUSICBRAINZ_USER_AGENT)
+        .query(&[
+            ("query", query.join(" AND ").as_str()),
+            ("limit", "5"),
+            ("fmt", "json"),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
         .await?;
+
+    // results are sorted by score
+    let release = match response.releases.into_iter().next() {
+        Some(release) => release,
+        None => bail!("No release found on MusicBrainz for {}", options.catalog),
+    };
+    info!(
+        "Found release {} <{}> with score {}",
+        release.title, release.id, release.score
+    );
+    Ok(release.id)
+}
+
+/// Convert a MusicBrainz release with recordings and artist credits to [Album].
+fn release_to_album(release: Release, catalog: String) -> Album {
     let release_date = release
         .date
         .map(|date| AnniDate::new(date.year() as u16, date.month() as u8, date.day() as u8))
@@ -248,8 +325,9 @@ async fn repo_get_musicbrainz(
         .flatten()
         .map(|media| {
             let disc = DiscInfo::new(
-                options.catalog.to_owned(),
-                media.title,
+                catalog.to_owned(),
+                // untitled media have empty title
+                media.title.filter(|title| !title.is_empty()),
                 None,
                 None,
                 None,
@@ -275,19 +353,42 @@ async fn repo_get_musicbrainz(
         })
         .collect();
 
-    let mut album = Album::new(
+    Album::new(
         AlbumInfo {
-            album_id: Uuid::parse_str(&options.id)
+            album_id: Uuid::parse_str(&release.id)
                 .ok()
                 .unwrap_or_else(Uuid::new_v4),
             title: release.title,
             artist,
             release_date,
-            catalog: options.catalog,
+            catalog,
             ..Default::default()
         },
         discs,
-    );
+    )
+}
+
+#[handler(RepoGetMusicbrainz)]
+async fn repo_get_musicbrainz(
+    options: RepoGetMusicbrainz,
+    manager: &RepositoryManager,
+    get: &RepoGetAction,
+) -> anyhow::Result<()> {
+    let id = match &options.id {
+        Some(id) => id.to_string(),
+        None => search_release(&options).await?,
+    };
+
+    musicbrainz_rs::config::set_base_url(options.base_url);
+    musicbrainz_throttle().await;
+    let release = Release::fetch()
+        .id(&id)
+        .with_release_groups()
+        .with_recordings()
+        .with_artist_credits()
+        .execute()
+        .await?;
+    let album = release_to_album(release, options.catalog);
 
     if get.print {
         println!("{}", album.format_to_string());
@@ -296,3 +397,68 @@ async fn repo_get_musicbrainz(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::release_to_album;
+    use anni_metadata::model::TrackType;
+    use musicbrainz_rs::entity::release::Release;
+
+    #[test]
+    fn test_release_to_album() {
+        let release = std::fs::read_to_string("tests/fixtures/musicbrainz/release.json").unwrap();
+        let release: Release = serde_json::from_str(&release).unwrap();
+        let album = release_to_album(release, "LTRN-0001".to_string());
+
+        assert_eq!(
+            album.album_id().to_string(),
+            "2f4e6a8c-0b1d-4e3f-a5b7-c9d1e3f5a7b9"
+        );
+        assert_eq!(album.title_raw(), "Harbour Lights");
+        assert_eq!(album.artist(), "The Lanterns");
+        assert_eq!(album.release_date().to_string(), "2021-06-18");
+        assert_eq!(album.catalog(), "LTRN-0001");
+
+        let discs: Vec<_> = album.iter().collect();
+        assert_eq!(discs.len(), 2);
+        // untitled disc inherits album title
+        assert_eq!(discs[0].title_raw(), None);
+        assert_eq!(discs[1].title(), "Bonus Disc");
+        assert!(discs.iter().all(|disc| disc.catalog() == "LTRN-0001"));
+
+        let tracks: Vec<_> = discs
+            .iter()
+            .flat_map(|disc| disc.iter())
+            .map(|track| {
+                (
+                    track.title().to_string(),
+                    track.artist().to_string(),
+                    track.track_type().clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            tracks,
+            [
+                ("Harbour Lights", "The Lanterns", TrackType::Normal),
+                ("Paper Boats", "The Lanterns、Mira Holt", TrackType::Normal),
+                (
+                    "Harbour Lights (Instrumental)",
+                    "The Lanterns",
+                    TrackType::Instrumental
+                ),
+                (
+                    "Paper Boats (Radio Edit)",
+                    "The Lanterns、Mira Holt",
+                    TrackType::Radio
+                ),
+                ("Live Drama at the Pier", "The Lanterns", TrackType::Drama),
+            ]
+            .map(|(title, artist, track_type)| (
+                title.to_string(),
+                artist.to_string(),
+                track_type
+            ))
+        );
+    }
+}
@@ -6,6 +6,7 @@ use anni_metadata::model::{Album, DiscRef, UNKNOWN_ARTIST};
 use anni_repo::RepositoryManager;
 use clap::{Args, ValueEnum};
 use clap_handler::handler;
+use regex::Regex;
 use std::collections::HashSet;
 use std::path::Path;
 
@@ -34,6 +35,7 @@ fn repo_lint(manager: RepositoryManager, me: &RepoLintAction) -> anyhow::Result<
         RepoLintFormat::Text => Box::new(AnniLinterTextFormat::default()),
         RepoLintFormat::ReviewDogJsonLines => Box::new(AnniLinterReviewDogJsonLineFormat::new()),
     };
+    let catalog_pattern = Regex::new(manager.catalog_pattern())?;
 
     if me.albums.is_empty() {
         // initialize owned manager
@@ -41,10 +43,10 @@ fn repo_lint(manager: RepositoryManager, me: &RepoLintAction) -> anyhow::Result<
         // validate all albums
         for album in manager.albums_iter() {
             let album_path = manager.album_path(&album.album_id()).unwrap();
-            validate_album(album, album_path, report.as_mut());
+            validate_album(album, album_path, &catalog_pattern, report.as_mut());
         }
-        // check tag loop
-        if let Some(path) = manager.check_tags_loop() {
+        // check tag loops
+        for path in manager.all_tag_loops() {
             report.add(Diagnostic::error(
                 DiagnosticMessage {
                     message: format!("Tag loop relation detected: {:?}", path),
@@ -64,7 +66,7 @@ fn repo_lint(manager: RepositoryManager, me: &RepoLintAction) -> anyhow::Result<
                 .iter()
                 .zip(manager.album_paths(album)?)
             {
-                validate_album(&album, &path, report.as_mut());
+                validate_album(&album, &path, &catalog_pattern, report.as_mut());
             }
         }
     }
@@ -77,8 +79,12 @@ fn repo_lint(manager: RepositoryManager, me: &RepoLintAction) -> anyhow::Result<
     Ok(())
 }
 
-fn validate_album<P>(album: &Album, path: P, report: &mut dyn AnniLinter<MetadataDiagnosticTarget>)
-where
+fn validate_album<P>(
+    album: &Album,
+    path: P,
+    catalog_pattern: &Regex,
+    report: &mut dyn AnniLinter<MetadataDiagnosticTarget>,
+) where
     P: AsRef<Path>,
 {
     let album_id = album.album_id().to_string();
@@ -125,6 +131,7 @@ where
         ));
     }
 
+    validate_catalog(album, catalog_pattern, path.as_ref(), report);
     validate_disc_catalog(album.iter().collect(), &album_id, path.as_ref(), report);
 
     for (disc_id, disc) in album.iter().enumerate() {
@@ -229,3 +236,168 @@ fn validate_disc_catalog<P>(
         }
     });
 }
+
+/// Check that album and disc catalogs match `pattern`, and disc catalogs belong to the same series as album catalog.
+fn validate_catalog<P>(
+    album: &Album,
+    pattern: &Regex,
+    path: P,
+    report: &mut dyn AnniLinter<MetadataDiagnosticTarget>,
+) where
+    P: AsRef<Path>,
+{
+    let album_id = album.album_id().to_string();
+    let location = || DiagnosticLocation::simple(path.as_ref().display().to_string());
+
+    let mut check = |target: MetadataDiagnosticTarget, catalog: &str| {
+        let message = if catalog.is_empty() {
+            "Empty catalog".to_string()
+        } else if !pattern.is_match(catalog) {
+            format!("Catalog {catalog} does not match pattern {pattern}")
+        } else {
+            return;
+        };
+        report.add(Diagnostic::error(
+            DiagnosticMessage { message, target },
+            location(),
+        ));
+    };
+
+    check(
+        MetadataDiagnosticTarget::album(album_id.clone()),
+        album.catalog(),
+    );
+    for (disc, disc_id) in album.iter().zip(1..) {
+        check(
+            MetadataDiagnosticTarget::disc(album_id.clone(), disc_id),
+            disc.catalog(),
+        );
+    }
+
+    // `LACA-15001~2` has discs `LACA-15001` and `LACA-15002`
+    let series = |catalog: &str| {
+        catalog
+            .split_once('-')
+            .map(|(series, _)| series.to_string())
+    };
+    let album_series = series(album.catalog());
+    for (disc, disc_id) in album.iter().zip(1..) {
+        let consistent = if album.discs_len() == 1 {
+            disc.catalog() == album.catalog()
+        } else {
+            series(disc.catalog()) == album_series
+        };
+        if !consistent {
+            report.add(Diagnostic::warning(
+                DiagnosticMessage {
+                    target: MetadataDiagnosticTarget::disc(album_id.clone(), disc_id),
+                    message: format!(
+                        "Disc catalog {} is inconsistent with album catalog {}",
+                        disc.catalog(),
+                        album.catalog()
+                    ),
+                },
+                location(),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate_catalog;
+    use anni_common::diagnostic::{Diagnostic, DiagnosticSeverity, MetadataDiagnosticTarget};
+    use anni_common::lint::AnniLinter;
+    use anni_metadata::model::Album;
+    use anni_repo::prelude::DEFAULT_CATALOG_PATTERN;
+    use regex::Regex;
+    use std::str::FromStr;
+
+    #[derive(Default)]
+    struct Collector(Vec<Diagnostic<MetadataDiagnosticTarget>>);
+
+    impl AnniLinter<MetadataDiagnosticTarget> for Collector {
+        fn add(&mut self, msg: Diagnostic<MetadataDiagnosticTarget>) {
+            self.0.push(msg);
+        }
+
+        fn flush(&self) -> bool {
+            true
+        }
+    }
+
+    fn lint(catalog: &str, discs: &[&str]) -> Vec<(DiagnosticSeverity, String)> {
+        let mut album = format!(
+            r#"[album]
+album_id = "7b1d3a8e-52c4-4f0e-9a0c-1a2b3c4d5e01"
+title = "Title"
+artist = "Artist"
+date = 2999-12-31
+type = "normal"
+catalog = "{catalog}"
+"#
+        );
+        for disc in discs {
+            album += &format!(
+                r#"
+[[discs]]
+catalog = "{disc}"
+
+[[discs.tracks]]
+title = "Track 1"
+"#
+            );
+        }
+        let album = Album::from_str(&album).unwrap();
+
+        let mut report = Collector::default();
+        let pattern = Regex::new(DEFAULT_CATALOG_PATTERN).unwrap();
+        validate_catalog(&album, &pattern, "album.toml", &mut report);
+        report
+            .0
+            .into_iter()
+            .map(|d| (d.severity, d.message.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_catalog() {
+        assert!(lint("LACA-15001", &["LACA-15001"]).is_empty());
+        assert!(lint("LACA-15001~2", &["LACA-15001", "LACA-15002"]).is_empty());
+    }
+
+    #[test]
+    fn test_empty_catalog() {
+        let result = lint("", &[""]);
+        assert_eq!(result.len(), 2);
+        assert!(result.iter().all(|(severity, message)| matches!(
+            severity,
+            DiagnosticSeverity::Error
+        ) && message == "Empty catalog"));
+    }
+
+    #[test]
+    fn test_invalid_catalog() {
+        let result = lint("laca15001", &["laca15001"]);
+        assert_eq!(result.len(), 2);
+        assert!(result
+            .iter()
+            .all(|(severity, _)| matches!(severity, DiagnosticSeverity::Error)));
+    }
+
+    #[test]
+    fn test_mismatched_disc_catalog() {
+        let result = lint("LACA-15001~2", &["LACA-15001", "KICA-1234"]);
+        assert_eq!(result.len(), 1);
+        let (severity, message) = &result[0];
+        assert!(matches!(severity, DiagnosticSeverity::Warning));
+        assert_eq!(
+            message,
+            "Disc catalog KICA-1234 is inconsistent with album catalog LACA-15001~2"
+        );
+
+        let result = lint("LACA-15001", &["LACA-15002"]);
+        assert_eq!(result.len(), 1);
+        assert!(matches!(result[0].0, DiagnosticSeverity::Warning));
+    }
+}
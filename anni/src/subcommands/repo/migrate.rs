@@ -1,21 +1,95 @@
+use crate::{ball, fl, ll};
 use anni_metadata::annim::query::album::{AlbumFragment, TagTypeInput};
 use anni_metadata::annim::AnnimClient;
 use anni_metadata::model::Album;
 use anni_repo::RepositoryManager;
-use clap::Args;
-use clap_handler::handler;
+use clap::{Args, Subcommand};
+use clap_handler::{handler, Handler};
 use std::collections::HashMap;
 
+#[derive(Subcommand, Handler, Debug, Clone)]
+pub enum RepoMigrateAction {
+    #[clap(about = ll!("repo-migrate-annim"))]
+    Annim(RepoMigrateAnnimAction),
+    #[clap(about = ll!("repo-migrate-search-fields"))]
+    SearchFields(RepoMigrateSearchFieldsAction),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct RepoMigrateSearchFieldsAction {
+    #[clap(long)]
+    #[clap(help = ll!("repo-migrate-dry-run"))]
+    dry_run: bool,
+}
+
+#[handler(RepoMigrateSearchFieldsAction)]
+fn repo_migrate_search_fields(
+    me: &RepoMigrateSearchFieldsAction,
+    manager: &RepositoryManager,
+) -> anyhow::Result<()> {
+    let report = manager.migrate_search_fields(me.dry_run)?;
+    for path in report.migrated.iter() {
+        info!(target: "repo|migrate", "{}", path.display());
+    }
+    for unresolved in report.unresolved.iter() {
+        let field = unresolved
+            .field
+            .map(|f| f.to_string())
+            .unwrap_or_else(|| "*".to_string());
+        warn!(
+            target: "repo|migrate",
+            "{}",
+            fl!(
+                "repo-migrate-search-fields-unresolved",
+                path = unresolved.path.display().to_string(),
+                field = field,
+                reason = unresolved.reason.to_string()
+            )
+        );
+    }
+
+    let count = report.migrated.len();
+    let unresolved = report.unresolved.len();
+    if me.dry_run {
+        info!(
+            target: "repo|migrate",
+            "{}",
+            fl!(
+                "repo-migrate-search-fields-dry-run-done",
+                count = count,
+                unresolved = unresolved
+            )
+        );
+    } else {
+        info!(
+            target: "repo|migrate",
+            "{}",
+            fl!(
+                "repo-migrate-search-fields-done",
+                count = count,
+                unresolved = unresolved
+            )
+        );
+    }
+    if unresolved > 0 {
+        ball!("repo-migrate-search-fields-failed");
+    }
+    Ok(())
+}
+
 #[derive(Args, Debug, Clone)]
-pub struct RepoMigrateAction {
+pub struct RepoMigrateAnnimAction {
     #[clap(long)]
     auth: String,
 
     endpoint: String,
 }
 
-#[handler(RepoMigrateAction)]
-async fn repo_migrate(me: RepoMigrateAction, manager: RepositoryManager) -> anyhow::Result<()> {
+#[handler(RepoMigrateAnnimAction)]
+async fn repo_migrate_annim(
+    me: RepoMigrateAnnimAction,
+    manager: RepositoryManager,
+) -> anyhow::Result<()> {
     let repo = manager.into_owned_manager()?;
     let client = AnnimClient::new(me.endpoint, Some(&me.auth));
 
@@ -1,8 +1,11 @@
 mod add;
+mod dedupe;
 mod get;
 mod lint;
 mod migrate;
 mod print;
+mod stats;
+mod tag;
 mod watch;
 
 use crate::args::ActionFile;
@@ -15,13 +18,16 @@ use anni_repo::RepositoryManager;
 use anni_workspace::AnniWorkspace;
 use clap::{Args, Subcommand, ValueEnum};
 use clap_handler::{handler, Context, Handler};
+use dedupe::RepoDedupeAction;
 use get::RepoGetAction;
 use lint::*;
 use migrate::RepoMigrateAction;
 use print::*;
+use stats::RepoStatsAction;
 use std::io::Read;
 use std::path::PathBuf;
 use std::str::FromStr;
+use tag::RepoTagAction;
 use watch::*;
 
 #[derive(Args, Debug, Clone, Handler)]
@@ -73,7 +79,15 @@ pub enum RepoAction {
     #[clap(name = "db")]
     #[clap(about = ll!("repo-db"))]
     Database(RepoDatabaseAction),
+    #[clap(about = ll!("repo-stats"))]
+    Stats(RepoStatsAction),
+    #[clap(about = ll!("repo-tag"))]
+    Tag(RepoTagAction),
+    #[clap(about = ll!("repo-dedupe"))]
+    Dedupe(RepoDedupeAction),
     Watch(RepoWatchAction),
+    #[clap(subcommand)]
+    #[clap(about = ll!("repo-migrate"))]
     Migrate(RepoMigrateAction),
 }
 
@@ -135,13 +149,21 @@ fn repo_import(me: &RepoImportAction, manager: &RepositoryManager) -> anyhow::Re
 
 #[derive(Args, Debug, Clone)]
 pub struct RepoEditAction {
+    #[clap(long)]
+    #[clap(help = ll!("repo-commit"))]
+    commit: bool,
+
     #[clap(required = true)]
     directories: Vec<PathBuf>,
 }
 
 #[handler(RepoEditAction)]
 fn repo_edit(me: &RepoEditAction, manager: &RepositoryManager) -> anyhow::Result<()> {
-    fn do_edit(directory: &PathBuf, manager: &RepositoryManager) -> anyhow::Result<()> {
+    fn do_edit(
+        directory: &PathBuf,
+        manager: &RepositoryManager,
+        commit: bool,
+    ) -> anyhow::Result<()> {
         let last = file_name(directory)?;
         debug!(target: "repo|edit", "Directory: {}", last);
         if !is_album_folder(&last) {
@@ -153,17 +175,36 @@ fn repo_edit(me: &RepoEditAction, manager: &RepositoryManager) -> anyhow::Result
         for file in manager.album_paths(&catalog)? {
             edit::edit_file(&file)?;
         }
+        if commit {
+            let titles: Vec<_> = manager
+                .load_albums(&catalog)?
+                .iter()
+                .map(|album| album.full_title().to_string())
+                .collect();
+            let message = format!("Edit {catalog} {}", titles.join(", "));
+            commit_changes(manager, &catalog, &message)?;
+        }
         Ok(())
     }
 
     for directory in me.directories.iter() {
-        if let Err(e) = do_edit(directory, manager) {
+        if let Err(e) = do_edit(directory, manager, me.commit) {
             error!("{}", e);
         }
     }
     Ok(())
 }
 
+/// Commit metadata files of albums with `catalog`, if repository is in a git worktree.
+fn commit_changes(manager: &RepositoryManager, catalog: &str, message: &str) -> anyhow::Result<()> {
+    if manager.commit_changes(&manager.album_paths(catalog)?, message)? {
+        info!(target: "repo|git", "{}", fl!("repo-commit-done", message = message));
+    } else {
+        warn!(target: "repo|git", "{}", fl!("repo-commit-skipped"));
+    }
+    Ok(())
+}
+
 fn is_album_folder(input: &str) -> bool {
     let bytes = input.as_bytes();
     let second_last_byte = bytes[bytes.len() - 2];
@@ -0,0 +1,71 @@
+use crate::ll;
+use anni_provider::strict_album_path;
+use anni_repo::RepositoryManager;
+use clap::Args;
+use clap_handler::handler;
+use std::path::PathBuf;
+
+#[derive(Args, Debug, Clone)]
+pub struct RepoStatsAction {
+    #[clap(long)]
+    #[clap(help = ll!("repo-stats-json"))]
+    json: bool,
+
+    #[clap(long)]
+    #[clap(help = ll!("repo-stats-library"))]
+    library: Option<PathBuf>,
+
+    #[clap(long, default_value = "2")]
+    #[clap(help = ll!("repo-stats-layer"))]
+    layer: usize,
+}
+
+#[handler(RepoStatsAction)]
+fn repo_stats(me: RepoStatsAction, manager: RepositoryManager) -> anyhow::Result<()> {
+    let manager = manager.into_owned_manager()?;
+    let mut stats = manager.stats();
+    if let Some(library) = &me.library {
+        stats.check_covers(&manager, |album_id| {
+            strict_album_path(library, &album_id.to_string(), me.layer)
+                .join("cover.jpg")
+                .exists()
+        });
+    }
+    if me.json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+
+    println!("{:<16}{}", "Albums", stats.albums);
+    println!("{:<16}{}", "Discs", stats.discs);
+    println!("{:<16}{}", "Tracks", stats.tracks);
+    println!("{:<16}{}", "Artists", stats.artists);
+    println!("{:<16}{}", "Tags", stats.tags);
+    if let (Some(earliest), Some(latest)) = (&stats.earliest_release, &stats.latest_release) {
+        println!("{:<16}{earliest} ~ {latest}", "Release dates");
+    }
+
+    println!();
+    println!("{:<16}{}", "Untagged albums", stats.untagged_albums.len());
+    for album_id in stats.untagged_albums.iter() {
+        println!("  {album_id}");
+    }
+    if let Some(albums) = &stats.albums_missing_cover {
+        println!("{:<16}{}", "Missing cover", albums.len());
+        for album_id in albums.iter() {
+            println!("  {album_id}");
+        }
+    }
+
+    println!();
+    let width = stats
+        .tag_usage
+        .iter()
+        .map(|usage| usage.tag.chars().count())
+        .max()
+        .unwrap_or(0);
+    for usage in stats.tag_usage.iter() {
+        println!("{:<width$}  {}", usage.tag, usage.albums);
+    }
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use crate::{fl, ll};
+use anni_metadata::model::TagRef;
+use anni_repo::RepositoryManager;
+use clap::{ArgGroup, Args};
+use clap_handler::handler;
+
+#[derive(Args, Debug, Clone)]
+#[clap(group = ArgGroup::new("operation").required(true))]
+pub struct RepoTagAction {
+    #[clap(long, group = "operation")]
+    #[clap(help = ll!("repo-tag-add"))]
+    add: Option<String>,
+
+    #[clap(long, group = "operation")]
+    #[clap(help = ll!("repo-tag-remove"))]
+    remove: Option<String>,
+
+    #[clap(long)]
+    #[clap(help = ll!("repo-tag-catalog-prefix"))]
+    catalog_prefix: String,
+}
+
+#[handler(RepoTagAction)]
+fn repo_tag(me: RepoTagAction, manager: RepositoryManager) -> anyhow::Result<()> {
+    let manager = manager.into_owned_manager()?;
+    let changed = match (me.add, me.remove) {
+        (Some(tag), _) => manager.add_album_tag(&TagRef::from_cow_str(tag), &me.catalog_prefix)?,
+        (_, Some(tag)) => {
+            manager.remove_album_tag(&TagRef::from_cow_str(tag), &me.catalog_prefix)?
+        }
+        _ => unreachable!(),
+    };
+
+    for path in changed.iter() {
+        info!(target: "repo|tag", "{}", path.display());
+    }
+    info!(
+        target: "repo|tag",
+        "{}",
+        fl!("repo-tag-done", count = changed.len())
+    );
+    Ok(())
+}
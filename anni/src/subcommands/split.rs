@@ -5,18 +5,26 @@ use clap::{ArgAction, Args, ValueEnum};
 
 use anni_common::fs;
 
+use crate::config::read_config;
 use crate::{ball, ll};
-use anni_flac::blocks::{BlockPicture, PictureType, UserComment, UserCommentExt};
+use anni_flac::blocks::{BlockCueSheet, BlockPicture, PictureType, UserComment, UserCommentExt};
 use anni_flac::{FlacHeader, MetadataBlock, MetadataBlockData};
 use anni_split::codec::wav::{WavDecoder, WavEncoder};
 use anni_split::codec::{
     ApeCommandDecoder, Decoder, Encoder, FlacCommandDecoder, FlacCommandEncoder, TakCommandDecoder,
     TtaCommandDecoder,
 };
+use anni_split::cue::{CueBreakpoint, SampleBreakpoint};
 use anni_split::error::SplitError;
-use anni_split::{cue_breakpoints, split};
+use anni_split::format::AudioFormat;
+use anni_split::profile::{ProfileEncoder, QualityProfile, QualityProfiles};
+use anni_split::split::Breakpoint;
+use anni_split::{
+    cue_breakpoints, cue_breakpoints_with, flac_cue_breakpoints, read_cue, split, PregapMode,
+};
 use clap_handler::handler;
 use cuna::Cuna;
+use serde::Deserialize;
 use std::fmt::{Display, Formatter};
 
 #[derive(Args, Debug, Clone)]
@@ -32,6 +40,31 @@ pub struct SplitSubcommand {
     #[clap(help = ll!("split-format-output"))]
     output_format: SplitOutputFormat,
 
+    #[clap(short, long)]
+    #[clap(help = ll!("split-profile"))]
+    profile: Option<String>,
+
+    #[clap(long)]
+    #[clap(help = ll!("split-bit-depth"))]
+    bit_depth: Option<u16>,
+
+    #[clap(long)]
+    #[clap(help = ll!("split-sample-rate"))]
+    sample_rate: Option<u32>,
+
+    #[clap(long)]
+    #[clap(help = ll!("split-channels"))]
+    channels: Option<u16>,
+
+    #[clap(long)]
+    #[clap(help = ll!("split-cue-charset"))]
+    cue_charset: Option<String>,
+
+    #[clap(value_enum)]
+    #[clap(long, default_value = "append")]
+    #[clap(help = ll!("split-pregap"))]
+    pregap: SplitPregapMode,
+
     #[clap(long = "clean")]
     #[clap(help = ll!("split-clean"))]
     clean: bool,
@@ -57,7 +90,13 @@ impl SplitSubcommand {
         !self.dry_run && self.remove_after_success
     }
 
-    fn split<P>(&self, audio_path: P, cue_path: P, cover: Option<P>) -> anyhow::Result<()>
+    fn split<P>(
+        &self,
+        audio_path: P,
+        cue_path: Option<P>,
+        cover: Option<P>,
+        profile: Option<&QualityProfile>,
+    ) -> anyhow::Result<()>
     where
         P: AsRef<Path>,
     {
@@ -66,18 +105,68 @@ impl SplitSubcommand {
         let input = self
             .input_format
             .get_decoder(audio_path.as_ref().to_path_buf());
-        let (breakpoints, cue) = cue_breakpoints(fs::read_to_string(cue_path.as_ref())?)?;
-        let tracks = cue_tracks(cue);
+        let embedded = match self.input_format {
+            SplitFormat::Flac => embedded_cue_sheet(audio_path.as_ref())?,
+            _ => None,
+        };
+        let (breakpoints, tracks) = match (embedded, &cue_path) {
+            // prefer breakpoints in embedded CUESHEET block, and use external cue for track titles only
+            (Some((cue_sheet, info)), cue_path) => {
+                let breakpoints: Vec<_> = flac_cue_breakpoints(&cue_sheet)
+                    .into_iter()
+                    .map(SplitBreakpoint::Sample)
+                    .collect();
+                let track_total = breakpoints.len() + 1;
+                let tracks = match cue_path {
+                    Some(cue_path) => {
+                        let cue = read_cue(cue_path.as_ref(), self.cue_charset.as_deref())?;
+                        let (_, cue) = cue_breakpoints(cue)?;
+                        Some(cue_tracks(cue)).filter(|tracks| tracks.len() == track_total)
+                    }
+                    None => None,
+                };
+                let tracks = tracks.unwrap_or_else(|| {
+                    debug!(target: "split", "Using track information from embedded cue sheet");
+                    embedded_tracks(&info, track_total)
+                });
+                (breakpoints, tracks)
+            }
+            (None, Some(cue_path)) => {
+                let cue = read_cue(cue_path.as_ref(), self.cue_charset.as_deref())?;
+                let (breakpoints, cue) = cue_breakpoints_with(cue, self.pregap.into())?;
+                let breakpoints: Vec<_> =
+                    breakpoints.into_iter().map(SplitBreakpoint::Cue).collect();
+                let tracks = cue_tracks(cue);
+                // pregaps split to separate tracks have no title in cue file
+                if breakpoints.len() + 1 != tracks.len() {
+                    bail!(
+                        "{} tracks would be split from {}, but there are {} tracks in cue file. Try another pregap mode.",
+                        breakpoints.len() + 1,
+                        audio_path.as_ref().display(),
+                        tracks.len()
+                    );
+                }
+                (breakpoints, tracks)
+            }
+            (None, None) => bail!(
+                "Failed to find cue file or embedded cue sheet for {}",
+                audio_path.as_ref().display()
+            ),
+        };
+        // file output path is relative to cue path, or audio path if cue file does not exist
+        let output_base = cue_path.as_ref().unwrap_or(&audio_path).as_ref();
+        let extension = match profile {
+            Some(profile) => profile.extension.as_str(),
+            None => self.output_format.as_str(),
+        };
 
         // generate file names & check whether file exists before split
         let files = tracks
             .iter()
             .map(|track| {
                 let filename =
-                    format!("{:02}. {}.{}", track.index, track.title, self.output_format)
-                        .replace("/", "／");
-                // file output path is relative to cue path
-                let output = cue_path.as_ref().with_file_name(&filename);
+                    format!("{:02}. {}.{}", track.index, track.title, extension).replace("/", "／");
+                let output = output_base.with_file_name(&filename);
                 // check if file exists
                 if output.exists()
                 /* TODO: && !override_file */
@@ -92,17 +181,23 @@ impl SplitSubcommand {
 
         // do split & write tags
         if !self.dry_run {
+            let format = AudioFormat::new(self.bit_depth, self.sample_rate, self.channels);
             split(
                 input,
                 |index| {
                     let file = files[index].as_path();
                     info!(target: "split", "{}...", file.file_name().unwrap().to_string_lossy());
-                    Ok(self.output_format.get_encoder(file))
+                    let encoder = match profile {
+                        Some(profile) => SplitOutputFormats::Profile(profile.encoder(file)),
+                        None => self.output_format.get_encoder(file),
+                    };
+                    // audio already in target format is passed through
+                    Ok(format.encoder(encoder))
                 },
                 breakpoints,
             )?;
 
-            if !self.clean && matches!(self.output_format, SplitOutputFormat::Flac) {
+            if !self.clean && extension == "flac" {
                 for (path, mut track) in files.into_iter().zip(tracks) {
                     let mut flac = FlacHeader::from_file(&path)?;
 
@@ -128,8 +223,10 @@ impl SplitSubcommand {
             if self.need_remove_after_success() {
                 debug!(target: "split", "Removing audio file: {}", audio_path.as_ref().display());
                 fs::remove_file(audio_path, self.trashcan)?;
-                debug!(target: "split", "Removing cue file: {}", cue_path.as_ref().display());
-                fs::remove_file(cue_path, self.trashcan)?;
+                if let Some(cue_path) = cue_path {
+                    debug!(target: "split", "Removing cue file: {}", cue_path.as_ref().display());
+                    fs::remove_file(cue_path, self.trashcan)?;
+                }
             }
         }
 
@@ -185,7 +282,18 @@ impl SplitFormat {
         }
     }
 
-    fn get_decoder<P>(&self, path: P) -> SplitFormats<P>
+    pub(crate) fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "wav" => Some(SplitFormat::Wav),
+            "flac" => Some(SplitFormat::Flac),
+            "ape" => Some(SplitFormat::Ape),
+            "tak" => Some(SplitFormat::Tak),
+            "tta" => Some(SplitFormat::Tta),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn get_decoder<P>(&self, path: P) -> SplitFormats<P>
     where
         P: AsRef<Path>,
     {
@@ -205,6 +313,25 @@ impl Display for SplitFormat {
     }
 }
 
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum SplitPregapMode {
+    Prepend,
+    Append,
+    Separate,
+    Discard,
+}
+
+impl From<SplitPregapMode> for PregapMode {
+    fn from(mode: SplitPregapMode) -> Self {
+        match mode {
+            SplitPregapMode::Prepend => PregapMode::Prepend,
+            SplitPregapMode::Append => PregapMode::Append,
+            SplitPregapMode::Separate => PregapMode::Separate,
+            SplitPregapMode::Discard => PregapMode::Discard,
+        }
+    }
+}
+
 #[derive(ValueEnum, Debug, Clone)]
 enum SplitOutputFormat {
     Flac,
@@ -219,7 +346,7 @@ impl SplitOutputFormat {
         }
     }
 
-    fn get_encoder<P>(&self, path: P) -> SplitOutputFormats<P>
+    fn get_encoder<P>(&self, path: P) -> SplitOutputFormats<'static, P>
     where
         P: AsRef<Path>,
     {
@@ -230,15 +357,16 @@ impl SplitOutputFormat {
     }
 }
 
-pub enum SplitOutputFormats<P>
+pub enum SplitOutputFormats<'a, P>
 where
     P: AsRef<Path>,
 {
     Wav(WavEncoder<P>),
     Flac(FlacCommandEncoder<P>),
+    Profile(ProfileEncoder<'a, P>),
 }
 
-impl<P> Encoder for SplitOutputFormats<P>
+impl<P> Encoder for SplitOutputFormats<'_, P>
 where
     P: AsRef<Path>,
 {
@@ -246,6 +374,7 @@ where
         match self {
             SplitOutputFormats::Wav(encoder) => encoder.encode(input),
             SplitOutputFormats::Flac(encoder) => encoder.encode(input),
+            SplitOutputFormats::Profile(encoder) => encoder.encode(input),
         }
     }
 }
@@ -276,8 +405,50 @@ fn get_cover(root: &PathBuf) -> anyhow::Result<Option<PathBuf>> {
     Ok(None)
 }
 
+/// Content of `split.toml` in anni config root.
+#[derive(Deserialize, Default)]
+struct SplitConfig {
+    #[serde(default)]
+    profiles: QualityProfiles,
+}
+
+fn load_profiles() -> anyhow::Result<QualityProfiles> {
+    let config: SplitConfig = match read_config("split") {
+        Ok(config) => config,
+        // split.toml is optional
+        Err(e)
+            if e.downcast_ref::<std::io::Error>()
+                .is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound) =>
+        {
+            SplitConfig::default()
+        }
+        Err(e) => return Err(e.context("Failed to parse split.toml")),
+    };
+
+    let mut profiles = QualityProfiles::builtin();
+    profiles.extend(config.profiles);
+    Ok(profiles)
+}
+
 #[handler(SplitSubcommand)]
 fn handle_split(me: &SplitSubcommand) -> anyhow::Result<()> {
+    let profiles = load_profiles()?;
+    let profile = match &me.profile {
+        Some(name) => match profiles.get(name) {
+            Some(profile) => Some(profile),
+            None => {
+                let mut available: Vec<_> = profiles.names().collect();
+                available.sort();
+                ball!(
+                    "split-profile-not-found",
+                    profile = name.as_str(),
+                    available = available.join(", ")
+                );
+            }
+        },
+        None => None,
+    };
+
     for directory in me.directories.iter() {
         if !directory.is_dir() {
             warn!(target: "split", "Ignoring non-dir file {}", directory.display());
@@ -291,17 +462,13 @@ fn handle_split(me: &SplitSubcommand) -> anyhow::Result<()> {
                     directory.display()
                 )
             })?;
+        // cue file is optional if the audio file has an embedded cue sheet
         let cue = {
             let audio_cue = audio.with_extension("cue");
             if audio_cue.is_file() {
-                audio_cue
+                Some(audio_cue)
             } else {
-                fs::get_ext_file(directory.as_path(), "cue", false)?.ok_or_else(|| {
-                    anyhow!(
-                        "Failed to find cue file from directory {}",
-                        directory.display()
-                    )
-                })?
+                fs::get_ext_file(directory.as_path(), "cue", false)?
             }
         };
 
@@ -315,7 +482,7 @@ fn handle_split(me: &SplitSubcommand) -> anyhow::Result<()> {
             warn!(target: "split", "Cover not found in directory {}", directory.display());
         }
 
-        me.split(audio, cue, cover)?;
+        me.split(audio, cue, cover, profile)?;
     }
 
     // log 'Finished' after all tracks were split
@@ -323,6 +490,53 @@ fn handle_split(me: &SplitSubcommand) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Breakpoints from either an external cue file or an embedded CUESHEET block.
+enum SplitBreakpoint {
+    Cue(CueBreakpoint),
+    Sample(SampleBreakpoint),
+}
+
+impl Breakpoint for SplitBreakpoint {
+    fn position(&self, header: &anni_split::codec::wav::WaveHeader) -> u32 {
+        match self {
+            SplitBreakpoint::Cue(breakpoint) => breakpoint.position(header),
+            SplitBreakpoint::Sample(breakpoint) => breakpoint.position(header),
+        }
+    }
+
+    fn discard(&self) -> bool {
+        match self {
+            SplitBreakpoint::Cue(breakpoint) => breakpoint.discard(),
+            SplitBreakpoint::Sample(breakpoint) => breakpoint.discard(),
+        }
+    }
+}
+
+/// Album and artist of a flac file, used to generate tags for tracks in embedded cue sheet.
+struct EmbeddedInfo {
+    album: String,
+    artist: String,
+}
+
+/// Read CUESHEET block and album information from a flac file.
+fn embedded_cue_sheet(path: &Path) -> anyhow::Result<Option<(BlockCueSheet, EmbeddedInfo)>> {
+    let flac = FlacHeader::from_file(path)?;
+    let value = |key: &str| {
+        flac.comments()
+            .and_then(|c| c.to_map().get(key).map(|c| c.value().to_string()))
+            .unwrap_or_default()
+    };
+    let info = EmbeddedInfo {
+        album: value("ALBUM"),
+        artist: value("ARTIST"),
+    };
+
+    Ok(flac.blocks.into_iter().find_map(|block| match block.data {
+        MetadataBlockData::CueSheet(cue_sheet) => Some((cue_sheet, info)),
+        _ => None,
+    }))
+}
+
 struct CueTrack {
     pub index: u8,
     pub title: String,
@@ -365,3 +579,22 @@ fn cue_tracks(cue: Cuna) -> Vec<CueTrack> {
     }
     result
 }
+
+fn embedded_tracks(info: &EmbeddedInfo, track_total: usize) -> Vec<CueTrack> {
+    (1..=track_total)
+        .map(|track_number| {
+            let title = format!("Track {}", track_number);
+            CueTrack {
+                index: track_number as u8,
+                title: title.clone(),
+                tags: vec![
+                    UserComment::title(title),
+                    UserComment::album(&info.album),
+                    UserComment::artist(&info.artist),
+                    UserComment::track_number(track_number),
+                    UserComment::track_total(track_total),
+                ],
+            }
+        })
+        .collect()
+}
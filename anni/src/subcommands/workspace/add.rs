@@ -1,17 +1,20 @@
 use crate::ll;
+use crate::subcommands::split::SplitFormat;
+use anni_common::fs;
 use anni_metadata::annim::mutation::add_album::AddAlbumInput;
 use anni_metadata::annim::AnnimClient;
-use anni_metadata::model::{Album, AlbumInfo, Disc, DiscInfo, UNKNOWN_ARTIST};
-use anni_repo::library::{file_name, AlbumFolderInfo};
-use anni_repo::models::RepoTrack;
-use anni_workspace::{AnniWorkspace, UntrackedWorkspaceAlbum, WorkspaceError};
+use anni_metadata::model::UNKNOWN_ARTIST;
+use anni_repo::library::{file_name, AlbumFolderInfo, InfoParseError};
+use anni_split::codec::{Decoder, Encoder, FlacCommandEncoder};
+use anni_workspace::{AnniWorkspace, ExtractedAlbumInfo, UntrackedWorkspaceAlbum};
 use clap::Args;
 use clap_handler::handler;
 use colored::Colorize;
 use inquire::Confirm;
 use ptree::TreeBuilder;
 use serde::Deserialize;
-use std::path::PathBuf;
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 #[derive(Args, Debug, Clone)]
@@ -30,6 +33,10 @@ pub struct WorkspaceAddAction {
     #[clap(help = ll!("workspace-add-open-editor"))]
     open_editor: bool,
 
+    #[clap(short = 'c', long)]
+    #[clap(help = ll!("workspace-add-convert"))]
+    convert: bool,
+
     path: PathBuf,
 }
 
@@ -39,6 +46,10 @@ fn handle_workspace_add(me: WorkspaceAddAction) -> anyhow::Result<()> {
     let workspace = AnniWorkspace::new()?;
     let album_path = me.path;
 
+    if me.convert {
+        convert_tracks(&workspace, &album_path)?;
+    }
+
     let validator = |album: &UntrackedWorkspaceAlbum| -> bool {
         // print album tree
         let album_name = album_path
@@ -86,75 +97,37 @@ fn handle_workspace_add(me: WorkspaceAddAction) -> anyhow::Result<()> {
     if me.import_tags {
         let config = workspace.get_config()?;
 
-        let album_id = workspace.get_album_id(&album_path)?;
-        let folder_name = file_name(&album_path)?;
-
-        let album_path = workspace.get_album_controlled_path(&album_id)?;
-        let mut discs = Vec::new();
-        loop {
-            let disc_id = discs.len() + 1;
-            let disc_path = album_path.join(disc_id.to_string());
-            if !disc_path.exists() {
-                break;
-            }
-
-            let mut tracks = Vec::new();
-            loop {
-                let track_id = tracks.len() + 1;
-                let track_path = disc_path.join(format!("{track_id}.flac"));
-                if !track_path.exists() {
-                    break;
-                }
-
-                let flac = anni_flac::FlacHeader::from_file(&track_path).map_err(|error| {
-                    WorkspaceError::FlacError {
-                        path: track_path,
-                        error,
-                    }
-                })?;
-                let track: RepoTrack = flac.into();
-                tracks.push(track.0)
-            }
-            discs.push(Disc::new(
-                DiscInfo::new(String::new(), None, None, None, None, Default::default()),
-                tracks,
-            ));
-        }
-
         match config.metadata() {
             anni_workspace::config::WorkspaceMetadata::Repo => {
-                let AlbumFolderInfo {
-                    release_date,
-                    catalog,
-                    title,
-                    edition,
-                    ..
-                } = AlbumFolderInfo::from_str(&folder_name)?;
-
-                for disc in discs.iter_mut() {
-                    disc.catalog += &catalog;
-                }
-
-                let repo = workspace.to_repository_manager()?;
-                let album = Album::new(
-                    AlbumInfo {
-                        album_id,
-                        title: title.to_string(),
-                        edition: edition.map(|c| c.to_string()),
-                        artist: UNKNOWN_ARTIST.to_string(),
-                        release_date,
-                        catalog: catalog.to_string(),
-                        ..Default::default()
+                let album_id = workspace.import_tags(
+                    &album_path,
+                    |folder_name| {
+                        let AlbumFolderInfo {
+                            release_date,
+                            catalog,
+                            title,
+                            edition,
+                            ..
+                        } = AlbumFolderInfo::from_str(folder_name)?;
+                        Ok::<_, InfoParseError>(ExtractedAlbumInfo {
+                            release_date,
+                            catalog: Cow::Owned(catalog),
+                            title: Cow::Owned(title),
+                            edition: edition.map(Cow::Owned),
+                        })
                     },
-                    discs,
-                );
-                repo.add_album(album, false)?;
+                    false,
+                )?;
 
                 if me.open_editor {
-                    edit::edit_file(&album_path)?;
+                    edit::edit_file(workspace.get_album_controlled_path(&album_id)?)?;
                 }
             }
             anni_workspace::config::WorkspaceMetadata::Remote { endpoint, token } => {
+                let album_id = workspace.get_album_id(&album_path)?;
+                let folder_name = file_name(&album_path)?;
+                let discs = workspace.read_discs(&album_id)?;
+
                 #[derive(Deserialize)]
                 struct StarryDirnameResponse {
                     #[serde(rename = "t")]
@@ -206,3 +179,32 @@ fn handle_workspace_add(me: WorkspaceAddAction) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Convert tracks of an untracked album to flac, and remove the original files.
+fn convert_tracks(workspace: &AnniWorkspace, album_path: &Path) -> anyhow::Result<()> {
+    let album = workspace.get_untracked_album_overview(album_path)?;
+    let use_trash = workspace.get_config()?.use_trash();
+
+    for track in album.discs.iter().flat_map(|disc| disc.tracks.iter()) {
+        let format = track
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(SplitFormat::from_extension)
+            .ok_or_else(|| anyhow!("Unsupported audio file: {}", track.display()))?;
+        if matches!(format, SplitFormat::Flac) {
+            continue;
+        }
+
+        let output = track.with_extension("flac");
+        if output.exists() {
+            bail!("Converted file {} already exists", output.display());
+        }
+
+        log::info!("Converting {} to flac", track.display());
+        let decoded = format.get_decoder(track.clone()).decode()?;
+        FlacCommandEncoder(&output).encode(decoded)?;
+        fs::remove_file(track, use_trash)?;
+    }
+
+    Ok(())
+}
@@ -9,6 +9,8 @@ pub struct WorkspaceFsckAction {
     fix_dangling: bool,
     #[clap(long)]
     gc: bool,
+    #[clap(long)]
+    repair: bool,
 }
 
 #[handler(WorkspaceFsckAction)]
@@ -40,6 +42,33 @@ fn handle_workspace_fsck(me: WorkspaceFsckAction) -> anyhow::Result<()> {
         }
     }
 
+    let albums = workspace.scan()?;
+    for album in albums {
+        if let WorkspaceAlbumState::Inconsistent(album_path) = album.state {
+            if !me.repair {
+                log::warn!(
+                    "Album {} at {} is inconsistent, run with --repair to fix it",
+                    album.album_id,
+                    album_path.display()
+                );
+                continue;
+            }
+
+            match workspace.repair_album(&album_path) {
+                Ok(state) => log::info!(
+                    "Repaired album {} at {}: {state:?}",
+                    album.album_id,
+                    album_path.display()
+                ),
+                Err(e) => log::error!(
+                    "Error while repairing album at {}: {}",
+                    album_path.display(),
+                    e
+                ),
+            }
+        }
+    }
+
     if me.gc {
         let albums = workspace.scan()?;
         for album in albums {
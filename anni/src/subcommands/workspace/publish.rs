@@ -1,3 +1,4 @@
+use crate::ll;
 use anni_workspace::{AnniWorkspace, WorkspaceAlbumState};
 use clap::Args;
 use clap_handler::handler;
@@ -17,6 +18,10 @@ pub struct WorkspacePublishAction {
     #[clap(long)]
     soft: bool,
 
+    #[clap(long = "dry-run")]
+    #[clap(help = ll!("workspace-publish-dry-run"))]
+    dry_run: bool,
+
     // publish_to: Option<PathBuf>,
     path: Vec<PathBuf>,
 }
@@ -58,6 +63,26 @@ pub async fn handle_workspace_publish(mut me: WorkspacePublishAction) -> anyhow:
         .collect();
 
     for path in me.path {
+        if me.dry_run {
+            let changes = workspace.tag_changes(&path, me.detailed)?;
+            let plan = workspace.publish_plan(&path, me.soft)?;
+            println!("{} => {}", plan.album_id, plan.destination.display());
+            for change in changes {
+                println!("  tag {}", change.path.display());
+                for comment in change.comments.unwrap_or_default() {
+                    println!("    {comment}");
+                }
+                if change.picture {
+                    let action = if me.detailed { "embed" } else { "remove" };
+                    println!("    ({action} cover)");
+                }
+            }
+            for operation in plan.operations {
+                println!("  {operation}");
+            }
+            continue;
+        }
+
         workspace.apply_tags(&path, me.detailed)?;
         workspace.publish(path, me.soft)?;
     }
@@ -50,6 +50,7 @@ pub async fn handle_workspace_status(me: WorkspaceStatusAction) -> anyhow::Resul
         let mut untracked: Vec<(&Path, DisplayUuid)> = vec![];
         let mut committed: Vec<(&Path, DisplayUuid)> = vec![];
         let mut dangling: Vec<(&Path, DisplayUuid)> = vec![];
+        let mut inconsistent: Vec<(&Path, DisplayUuid)> = vec![];
         let mut published: Vec<DisplayUuid> = vec![];
         let mut garbage: Vec<DisplayUuid> = vec![];
         for album in albums.iter() {
@@ -66,6 +67,10 @@ pub async fn handle_workspace_status(me: WorkspaceStatusAction) -> anyhow::Resul
                     p.strip_prefix(&root)?,
                     DisplayUuid::new(&album.album_id, me.album_id),
                 )),
+                WorkspaceAlbumState::Inconsistent(ref p) => inconsistent.push((
+                    p.strip_prefix(&root)?,
+                    DisplayUuid::new(&album.album_id, me.album_id),
+                )),
                 WorkspaceAlbumState::Published => {
                     published.push(DisplayUuid::new(&album.album_id, me.album_id))
                 }
@@ -105,6 +110,16 @@ pub async fn handle_workspace_status(me: WorkspaceStatusAction) -> anyhow::Resul
             println!();
         }
 
+        if !inconsistent.is_empty() {
+            println!("Inconsistent albums (run `anni workspace fsck --repair` to fix):");
+            for (path, album_id) in inconsistent.iter() {
+                let album_id = format!("[{album_id}]").bold();
+                let output = format!("{album_id}: {}", path.display()).yellow();
+                println!("\t{output}");
+            }
+            println!();
+        }
+
         if !published.is_empty() {
             println!("Published albums:");
             for album_id in published.iter() {
@@ -127,6 +142,7 @@ pub async fn handle_workspace_status(me: WorkspaceStatusAction) -> anyhow::Resul
         if untracked.is_empty()
             && committed.is_empty()
             && dangling.is_empty()
+            && inconsistent.is_empty()
             && published.is_empty()
             && garbage.is_empty()
         {
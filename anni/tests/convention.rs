@@ -0,0 +1,19 @@
+mod common;
+
+const OPUS_PATH: &str = "../anni-repo/tests/fixtures/tags/test.opus";
+
+#[test]
+fn convention_check_opus() {
+    let cmd = common::run(&["convention", "check", OPUS_PATH])
+        .output()
+        .unwrap();
+    assert!(cmd.status.success());
+
+    let stderr = String::from_utf8_lossy(&cmd.stderr);
+    assert!(!stderr.contains("Failed to parse"), "{stderr}");
+    // title and artist are read from OpusTags
+    assert!(!stderr.contains("Missing tag TITLE"), "{stderr}");
+    assert!(!stderr.contains("Missing tag ARTIST"), "{stderr}");
+    assert!(stderr.contains("Missing tag ALBUM"), "{stderr}");
+    assert!(stderr.contains("Cover does not exist"), "{stderr}");
+}
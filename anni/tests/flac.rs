@@ -57,3 +57,84 @@ fn flac_export_cover() {
     file.read_to_end(&mut data).expect("Failed to read cover.");
     assert_eq!(cmd.stdout, data);
 }
+
+#[test]
+fn flac_repair_healthy() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("1s-full.flac");
+    std::fs::copy(FLAC_PATH, &path).unwrap();
+
+    let cmd = common::run(&["flac", "repair", path.to_str().unwrap()])
+        .output()
+        .unwrap();
+    assert!(cmd.status.success());
+    assert_eq!(
+        std::fs::read(&path).unwrap(),
+        std::fs::read(FLAC_PATH).unwrap()
+    );
+}
+
+#[test]
+fn flac_verify() {
+    let cmd = common::run(&["flac", "--verify", FLAC_PATH, "../assets/1s.flac"])
+        .output()
+        .unwrap();
+    assert!(cmd.status.success());
+    let stdout = String::from_utf8(cmd.stdout).expect("Invalid UTF-8 output.");
+    assert_eq!(
+        stdout.lines().filter(|l| l.starts_with("[PASS]")).count(),
+        2
+    );
+}
+
+/// Copy [FLAC_PATH] to a temporary directory, run `anni flac tags` with `args` on it and export its tags.
+fn flac_tags(args: &[&[&str]]) -> String {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("1s-full.flac");
+    std::fs::copy(FLAC_PATH, &path).unwrap();
+    let path = path.to_str().unwrap();
+
+    for args in args {
+        let cmd = common::run(&[&["flac", "tags"][..], *args, &[path][..]].concat())
+            .output()
+            .unwrap();
+        assert!(cmd.status.success());
+    }
+
+    let cmd = common::run(&["flac", "export", path]).output().unwrap();
+    String::from_utf8(cmd.stdout).expect("Invalid UTF-8 output.")
+}
+
+#[test]
+fn flac_tags_set() {
+    assert_eq!(
+        flac_tags(&[&["set", "title", "New Title"]]),
+        TEST_TAGS.replace("TITLE=TRACK ONE", "TITLE=New Title")
+    );
+}
+
+#[test]
+fn flac_tags_set_append() {
+    assert_eq!(
+        flac_tags(&[
+            &["set", "--append", "Artist", "AnotherArtist"],
+            &["set", "--append", "ARTIST", "ThirdArtist"]
+        ]),
+        format!("{TEST_TAGS}ARTIST=AnotherArtist\nARTIST=ThirdArtist\n")
+    );
+    assert_eq!(
+        flac_tags(&[
+            &["set", "--append", "ARTIST", "AnotherArtist"],
+            &["set", "artist", "OnlyArtist"]
+        ]),
+        TEST_TAGS.replace("ARTIST=TestArtist", "ARTIST=OnlyArtist")
+    );
+}
+
+#[test]
+fn flac_tags_remove() {
+    assert_eq!(
+        flac_tags(&[&["remove", "artist"]]),
+        TEST_TAGS.replace("ARTIST=TestArtist\n", "")
+    );
+}
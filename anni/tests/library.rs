@@ -0,0 +1,157 @@
+use serde_json::{json, Value};
+use std::fs;
+
+mod common;
+
+const FLAC_PATH: &str = "../assets/1s.flac";
+const COVER_PATH: &str = "../assets/1s-cover.png";
+const REPO_PATH: &str = "tests/fixtures/audit/repo";
+
+#[test]
+fn library_audit_report() {
+    let library = tempfile::tempdir().expect("Failed to create library dir.");
+    let root = library.path();
+
+    // track 2 is missing
+    let album = root.join("11/11/11111111-1111-4111-8111-111111111111");
+    let disc = album.join("1");
+    fs::create_dir_all(&disc).unwrap();
+    fs::copy(COVER_PATH, album.join("cover.jpg")).unwrap();
+    fs::copy(COVER_PATH, disc.join("cover.jpg")).unwrap();
+    fs::copy(FLAC_PATH, disc.join("1.flac")).unwrap();
+
+    // audio is corrupted, and covers are missing
+    let disc = root.join("33/33/33333333-3333-4333-8333-333333333333/1");
+    fs::create_dir_all(&disc).unwrap();
+    fs::write(disc.join("1.flac"), b"not a flac file").unwrap();
+
+    // album does not exist in repo
+    fs::create_dir_all(root.join("44/44/44444444-4444-4444-8444-444444444444/1")).unwrap();
+
+    let cmd = common::run(&[
+        "library",
+        "--repo",
+        REPO_PATH,
+        "audit",
+        "--json",
+        root.to_str().unwrap(),
+    ])
+    .output()
+    .unwrap();
+    assert!(cmd.status.success());
+
+    let report: Value = serde_json::from_slice(&cmd.stdout).expect("Invalid JSON output.");
+    assert_eq!(
+        report["MissingFiles"],
+        json!([
+            {
+                "album_id": "11111111-1111-4111-8111-111111111111",
+                "disc_id": 1,
+                "track_id": 2,
+            },
+            {
+                "album_id": "22222222-2222-4222-8222-222222222222",
+            },
+        ])
+    );
+    assert_eq!(
+        report["CountMismatch"],
+        json!([
+            {
+                "album_id": "11111111-1111-4111-8111-111111111111",
+                "disc_id": 1,
+                "detail": "expected 2 tracks, found 1",
+            },
+        ])
+    );
+    assert_eq!(
+        report["MissingCover"],
+        json!([
+            {
+                "album_id": "33333333-3333-4333-8333-333333333333",
+            },
+            {
+                "album_id": "33333333-3333-4333-8333-333333333333",
+                "disc_id": 1,
+            },
+        ])
+    );
+
+    let corrupted = report["CorruptAudio"].as_array().unwrap();
+    assert_eq!(corrupted.len(), 1);
+    assert_eq!(
+        corrupted[0]["album_id"],
+        "33333333-3333-4333-8333-333333333333"
+    );
+    assert_eq!(corrupted[0]["track_id"], 1);
+
+    let unknown = report["MissingMetadata"].as_array().unwrap();
+    assert_eq!(unknown.len(), 1);
+    assert_eq!(
+        unknown[0]["album_id"],
+        "44444444-4444-4444-8444-444444444444"
+    );
+}
+
+#[test]
+fn library_covers_export() {
+    let library = tempfile::tempdir().expect("Failed to create library dir.");
+    let output = tempfile::tempdir().expect("Failed to create output dir.");
+    let root = library.path();
+
+    // standalone cover
+    let album = root.join("11/11/11111111-1111-4111-8111-111111111111");
+    fs::create_dir_all(album.join("1")).unwrap();
+    fs::copy(COVER_PATH, album.join("cover.jpg")).unwrap();
+    fs::copy(FLAC_PATH, album.join("1/1.flac")).unwrap();
+
+    // embedded cover only
+    let album = root.join("22/22/22222222-2222-4222-8222-222222222222");
+    fs::create_dir_all(album.join("1")).unwrap();
+    fs::copy("../assets/1s-full.flac", album.join("1/1.flac")).unwrap();
+
+    // no cover at all
+    let album = root.join("33/33/33333333-3333-4333-8333-333333333333");
+    fs::create_dir_all(album.join("1")).unwrap();
+    fs::copy(FLAC_PATH, album.join("1/1.flac")).unwrap();
+
+    // first track is broken, embedded cover in the next track is used
+    let album = root.join("44/44/44444444-4444-4444-8444-444444444444");
+    fs::create_dir_all(album.join("1")).unwrap();
+    fs::write(album.join("1/1.flac"), b"not a flac file").unwrap();
+    fs::copy("../assets/1s-full.flac", album.join("1/2.flac")).unwrap();
+
+    let cmd = common::run(&[
+        "library",
+        "--repo",
+        REPO_PATH,
+        "covers",
+        "export",
+        "--size",
+        "16",
+        root.to_str().unwrap(),
+        output.path().to_str().unwrap(),
+    ])
+    .output()
+    .unwrap();
+    assert!(cmd.status.success());
+
+    let mut exported: Vec<_> = fs::read_dir(output.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    exported.sort();
+    assert_eq!(
+        exported,
+        [
+            "11111111-1111-4111-8111-111111111111.jpg",
+            "22222222-2222-4222-8222-222222222222.jpg",
+            "44444444-4444-4444-8444-444444444444.jpg",
+        ]
+    );
+    for file in exported {
+        let data = fs::read(output.path().join(file)).unwrap();
+        // covers are converted to JPEG
+        assert!(data.starts_with(&[0xff, 0xd8, 0xff]));
+    }
+}
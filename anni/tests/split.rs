@@ -0,0 +1,111 @@
+use anni_flac::FlacHeader;
+use std::fs;
+use std::path::Path;
+
+mod common;
+
+const FLAC_PATH: &str = "tests/fixtures/split/embedded-cue.flac";
+
+/// Copy flac file with embedded cue sheet to a temporary album directory, without cue file.
+fn album_dir() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    fs::copy(FLAC_PATH, dir.path().join("album.flac")).unwrap();
+    dir
+}
+
+fn split(dir: &Path, args: &[&str]) -> std::process::Output {
+    let mut subcommands = vec!["split", "-i", "flac", "--no-import-cover", "--keep"];
+    subcommands.extend_from_slice(args);
+    subcommands.push(dir.to_str().unwrap());
+    common::run(&subcommands).output().unwrap()
+}
+
+#[test]
+fn split_embedded_cue_dry_run() {
+    let dir = album_dir();
+    let cmd = split(dir.path(), &["--dry-run"]);
+    assert!(
+        cmd.status.success(),
+        "{}",
+        String::from_utf8_lossy(&cmd.stderr)
+    );
+    assert!(!dir.path().join("01. Track 1.flac").exists());
+    assert!(dir.path().join("album.flac").exists());
+}
+
+#[test]
+#[cfg_attr(
+    not(feature = "external-decoder-tests"),
+    ignore = "needs `flac` to be installed"
+)]
+fn split_embedded_cue() {
+    assert!(which::which("flac").is_ok(), "`flac` is not installed");
+
+    let dir = album_dir();
+    let cmd = split(dir.path(), &[]);
+    assert!(
+        cmd.status.success(),
+        "{}",
+        String::from_utf8_lossy(&cmd.stderr)
+    );
+
+    // the fixture has 44100 samples, and the second track starts at 23520
+    for (track_number, samples) in [(1, 23520), (2, 44100 - 23520)] {
+        let path = dir
+            .path()
+            .join(format!("{track_number:02}. Track {track_number}.flac"));
+        let flac = FlacHeader::from_file(&path).unwrap();
+        assert_eq!(flac.stream_info().total_samples, samples);
+
+        let comments = flac.comments().unwrap().to_map();
+        let value = |key: &str| comments.get(key).unwrap().value().to_string();
+        assert_eq!(value("TITLE"), format!("Track {track_number}"));
+        assert_eq!(value("ALBUM"), "TestAlbum");
+        assert_eq!(value("ARTIST"), "TestArtist");
+        assert_eq!(value("TRACKNUMBER"), track_number.to_string());
+        assert_eq!(value("TRACKTOTAL"), "2");
+    }
+}
+
+/// 1 second of hidden track before track 1.
+const HTOA_CUE: &str = r#"FILE "album.wav" WAVE
+  TRACK 01 AUDIO
+    TITLE "One"
+    INDEX 00 00:00:00
+    INDEX 01 00:01:00
+  TRACK 02 AUDIO
+    TITLE "Two"
+    INDEX 01 00:06:00
+"#;
+
+#[test]
+fn split_pregap_mode() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("album.wav"), b"").unwrap();
+    fs::write(dir.path().join("album.cue"), HTOA_CUE).unwrap();
+    let split = |pregap| {
+        common::run(&[
+            "split",
+            "--no-import-cover",
+            "--dry-run",
+            "--pregap",
+            pregap,
+            dir.path().to_str().unwrap(),
+        ])
+        .output()
+        .unwrap()
+    };
+
+    // hidden track is split to a separate track by default, which has no title in cue file
+    let cmd = split("append");
+    assert!(!cmd.status.success());
+    assert!(String::from_utf8_lossy(&cmd.stderr).contains("pregap mode"));
+    for pregap in ["prepend", "discard"] {
+        let cmd = split(pregap);
+        assert!(
+            cmd.status.success(),
+            "{pregap}: {}",
+            String::from_utf8_lossy(&cmd.stderr)
+        );
+    }
+}
@@ -1,10 +1,26 @@
 use crate::extractor::auth::AuthExtractor;
+use crate::state::AdminScope;
 use crate::{error::AnnilError, state::AnnilKeys};
 use async_trait::async_trait;
 use axum::{extract::FromRequestParts, http::request::Parts, Extension};
+use std::collections::HashSet;
 use std::sync::Arc;
 
-pub struct AnnilAdmin;
+/// Request authorized by one of the admin tokens.
+pub struct AnnilAdmin {
+    scopes: HashSet<AdminScope>,
+}
+
+impl AnnilAdmin {
+    /// Check whether the admin token is allowed to perform actions in `scope`.
+    pub fn require(&self, scope: AdminScope) -> Result<(), AnnilError> {
+        if self.scopes.contains(&scope) {
+            Ok(())
+        } else {
+            Err(AnnilError::Forbidden)
+        }
+    }
+}
 
 #[async_trait]
 impl<S> FromRequestParts<S> for AnnilAdmin
@@ -19,10 +35,11 @@ where
             .await
             .expect("Failed to extract keys from extension. Please re-check your code first.");
 
-        if auth != keys.admin_token {
-            return Err(AnnilError::Unauthorized);
-        }
-
-        Ok(AnnilAdmin)
+        let scopes = keys
+            .admin_tokens
+            .get(&auth)
+            .ok_or(AnnilError::Unauthorized)?
+            .clone();
+        Ok(AnnilAdmin { scopes })
     }
 }
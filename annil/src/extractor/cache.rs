@@ -0,0 +1,38 @@
+use async_trait::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::header::CACHE_CONTROL;
+use axum::http::request::Parts;
+use std::convert::Infallible;
+
+/// Whether the client asks to skip server side cache.
+///
+/// Cache is skipped if `Cache-Control: no-cache` header is present, or `nocache` query is set to `1` or `true`.
+pub struct NoCache(pub bool);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for NoCache
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let header = parts
+            .headers
+            .get_all(CACHE_CONTROL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|directive| directive.trim().eq_ignore_ascii_case("no-cache"));
+        let query = parts.uri.query().map_or(false, |query| {
+            query.split('&').any(|pair| {
+                matches!(
+                    pair.split_once('=').unwrap_or((pair, "")),
+                    ("nocache", "" | "1" | "true")
+                )
+            })
+        });
+
+        Ok(NoCache(header || query))
+    }
+}
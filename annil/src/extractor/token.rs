@@ -47,7 +47,26 @@ pub struct ShareToken {
 /// `Share Token` body
 #[derive(Serialize, Deserialize, Clone)]
 pub struct ShareClaim {
+    /// Shared tracks, indexed by album id and disc id
+    #[serde(default)]
     pub(crate) audios: HashMap<String, HashMap<String, Vec<NonZeroU8>>>,
+    /// Albums which are shared as a whole
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) albums: Option<Vec<Uuid>>,
+    /// Audio quality of shared tracks, `low` is used if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) quality: Option<String>,
+}
+
+impl ShareClaim {
+    /// Whether any track of album with `album_id` is shared
+    pub(crate) fn can_access_album(&self, album_id: &Uuid) -> bool {
+        self.audios.contains_key(&album_id.to_string())
+            || self
+                .albums
+                .as_ref()
+                .map_or(false, |albums| albums.contains(album_id))
+    }
 }
 
 #[async_trait]
@@ -101,6 +120,14 @@ impl AnnilClaim {
         match &self {
             AnnilClaim::User(_) => true,
             AnnilClaim::Share(s) => {
+                // the whole album is shared
+                if s.albums
+                    .as_ref()
+                    .map_or(false, |albums| albums.contains(&track.album_id))
+                {
+                    return true;
+                }
+
                 match s.audios.get(&track.album_id.to_string()) {
                     // album_id exist
                     Some(album) => match album.get(&format!("{}", track.disc_id)) {
@@ -119,6 +146,13 @@ impl AnnilClaim {
         }
     }
 
+    pub(crate) fn can_access_album(&self, album_id: &Uuid) -> bool {
+        match &self {
+            AnnilClaim::User(_) => true,
+            AnnilClaim::Share(s) => s.can_access_album(album_id),
+        }
+    }
+
     #[inline]
     pub(crate) fn is_guest(&self) -> bool {
         matches!(self, AnnilClaim::Share(_))
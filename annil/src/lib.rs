@@ -2,34 +2,56 @@
 
 pub mod extractor;
 pub mod provider;
+pub mod ratelimit;
 pub mod route;
+pub mod shutdown;
 pub mod state;
 pub mod utils;
 
 pub mod metadata;
-mod transcode;
+pub mod transcode;
 
 pub mod error {
     use axum::http::StatusCode;
     use axum::response::{IntoResponse, Response};
+    use axum::Json;
+    use jwt_simple::reexports::serde_json::json;
     use thiserror::Error;
 
     #[derive(Error, Debug)]
     pub enum AnnilError {
         #[error("unauthorized")]
         Unauthorized,
+        #[error("forbidden")]
+        Forbidden,
         #[error("unknown path")]
         UnknownPath,
         #[error("not found")]
         NotFound,
+        #[error("failed to reload: {0}")]
+        ReloadFailed(String),
+        #[error("failed to transcode")]
+        TranscodeFailed,
     }
 
     impl IntoResponse for AnnilError {
         fn into_response(self) -> Response {
             match self {
+                AnnilError::ReloadFailed(message) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(json!({
+                            "error": "reload_failed",
+                            "message": message,
+                        })),
+                    )
+                        .into_response()
+                }
                 AnnilError::Unauthorized => StatusCode::UNAUTHORIZED,
+                AnnilError::Forbidden => StatusCode::FORBIDDEN,
                 AnnilError::UnknownPath => StatusCode::FORBIDDEN,
                 AnnilError::NotFound => StatusCode::NOT_FOUND,
+                AnnilError::TranscodeFailed => StatusCode::INTERNAL_SERVER_ERROR,
             }
             .into_response()
         }
@@ -1,150 +1,279 @@
-use config::{Config, ProviderItem};
+use config::{Config, ProviderConfig, ProviderItem};
 
+use anni_provider::cache::{CacheConfig, CachePool, CacheProvider};
 use anni_provider::fs::LocalFileSystemProvider;
 use anni_provider::providers::drive::DriveProviderSettings;
+#[cfg(feature = "webdav")]
+use anni_provider::providers::webdav::{WebDavAuth, WebDavProviderSettings};
+#[cfg(feature = "webdav")]
+use anni_provider::providers::WebDavProvider;
 use anni_provider::providers::{
     CommonConventionProvider, CommonStrictProvider, DriveProvider, MultipleProviders,
 };
 use anni_provider::AnniProvider;
-use annil::metadata::MetadataConfig;
-use annil::provider::AnnilProvider;
+use annil::metadata::{LazyDb, MetadataConfig};
+use annil::provider::{AnnilProvider, ProviderHealth, ProviderStatus};
+use annil::ratelimit::{self, RateLimiter};
 use annil::route::admin;
+use annil::route::compression_layer;
+use annil::route::health;
 use annil::route::user;
-use annil::state::{AnnilKeys, AnnilState};
+use annil::route::webui;
+use annil::shutdown;
+use annil::state::{AdminScope, AnnilKeys, AnnilState};
+use annil::transcode::TranscodeCache;
 use axum::http::Method;
+use axum::middleware;
 use axum::routing::{get, post};
 use axum::{Extension, Router};
 use jwt_simple::prelude::HS256Key;
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::net::TcpListener;
 use tokio::sync::RwLock;
 use tower_http::cors;
 use tower_http::cors::CorsLayer;
 
-async fn init_state(
-    config: Config,
-) -> anyhow::Result<(AnnilState, AnnilProvider<MultipleProviders>, AnnilKeys)> {
+/// Initialize all configured providers, and returns them with status of each provider.
+///
+/// Providers failed to initialize are skipped, so that others can still be served.
+///
+/// Providers with a cache pool in `pools` are wrapped by [CacheProvider].
+async fn init_providers(
+    providers_config: &HashMap<String, ProviderConfig>,
+    pools: &HashMap<String, Arc<CachePool>>,
+    metadata: Option<MetadataConfig>,
+) -> (MultipleProviders, Vec<ProviderStatus>) {
     #[cfg(feature = "metadata")]
-    let mut db = config.metadata.clone().map(MetadataConfig::into_db);
+    let mut db = metadata.map(MetadataConfig::into_db);
 
     log::info!("Start initializing providers...");
     let now = SystemTime::now();
-    let mut providers = Vec::with_capacity(config.providers.len());
+    let mut providers = Vec::with_capacity(providers_config.len());
+    let mut statuses = Vec::with_capacity(providers_config.len());
 
-    for (provider_name, provider_config) in config.providers.iter() {
+    for (provider_name, provider_config) in providers_config.iter() {
         log::debug!("Initializing provider: {}", provider_name);
-        let provider: Box<dyn AnniProvider + Send + Sync> = match (&provider_config.item, &mut db) {
-            (
-                ProviderItem::File {
-                    root,
-                    strict: false,
-                    ..
-                },
-                Some(db),
-            ) => Box::new(
-                CommonConventionProvider::new(
-                    PathBuf::from(root),
-                    db.open()?,
-                    Box::new(LocalFileSystemProvider),
-                )
-                .await?,
-            ),
-            (
-                ProviderItem::File {
-                    root,
-                    strict: true,
-                    layer,
-                },
-                _,
-            ) => Box::new(
-                CommonStrictProvider::new(
-                    PathBuf::from(root),
-                    *layer,
-                    Box::new(LocalFileSystemProvider),
-                )
-                .await?,
-            ),
-            (
-                ProviderItem::Drive {
-                    drive_id,
-                    corpora,
-                    initial_token_path,
-                    token_path,
-                    strict: false,
-                },
-                Some(db),
-            ) => {
-                if let Some(initial_token_path) = initial_token_path {
-                    if initial_token_path.exists() && !token_path.exists() {
-                        let _ = std::fs::copy(initial_token_path, token_path.clone());
-                    }
-                }
-                Box::new(
-                    DriveProvider::new(
-                        Default::default(),
-                        DriveProviderSettings {
-                            corpora: corpora.to_string(),
-                            drive_id: drive_id.clone(),
-                        },
-                        Some(db.open()?),
-                        token_path.clone(),
-                    )
-                    .await?,
-                )
-            }
-            (
-                ProviderItem::Drive {
-                    drive_id,
-                    corpora,
-                    initial_token_path,
-                    token_path,
-                    strict: true,
-                },
-                _,
-            ) => {
-                if let Some(initial_token_path) = initial_token_path {
-                    if initial_token_path.exists() && !token_path.exists() {
-                        let _ = std::fs::copy(initial_token_path, token_path.clone());
-                    }
-                }
-                Box::new(
-                    DriveProvider::new(
-                        Default::default(),
-                        DriveProviderSettings {
-                            corpora: corpora.to_string(),
-                            drive_id: drive_id.clone(),
-                        },
-                        None,
-                        token_path.clone(),
-                    )
-                    .await?,
-                )
+        match init_provider(provider_name, provider_config, &mut db).await {
+            Ok(provider) => {
+                let provider: Box<dyn AnniProvider + Send + Sync> = match pools.get(provider_name) {
+                    Some(pool) => Box::new(CacheProvider::new(provider, pool.clone())),
+                    None => provider,
+                };
+                statuses.push(ProviderStatus::ready(provider_name));
+                providers.push(provider);
             }
-            (_, None) => {
-                log::error!(
-                    "Metadata is not configured, but provider {} requires it.",
-                    provider_name
-                );
-                continue;
+            Err(e) => {
+                log::error!("Failed to initialize provider {provider_name}: {e:#}");
+                statuses.push(ProviderStatus::failed(provider_name, format!("{e:#}")));
             }
-        };
-        providers.push(provider);
+        }
     }
     log::info!(
         "Provider initialization finished, used {:?}",
         now.elapsed().unwrap()
     );
 
-    let providers = AnnilProvider::new(MultipleProviders::new(providers));
+    (MultipleProviders::new(providers), statuses)
+}
+
+async fn init_provider(
+    provider_name: &str,
+    provider_config: &ProviderConfig,
+    db: &mut Option<LazyDb>,
+) -> anyhow::Result<Box<dyn AnniProvider + Send + Sync>> {
+    let provider: Box<dyn AnniProvider + Send + Sync> = match (&provider_config.item, db) {
+        (
+            ProviderItem::File {
+                root,
+                strict: false,
+                ..
+            },
+            Some(db),
+        ) => Box::new(
+            CommonConventionProvider::new(
+                PathBuf::from(root),
+                db.open()?,
+                Box::new(LocalFileSystemProvider),
+            )
+            .await?,
+        ),
+        (
+            ProviderItem::File {
+                root,
+                strict: true,
+                layer,
+            },
+            _,
+        ) => Box::new(
+            CommonStrictProvider::new(
+                PathBuf::from(root),
+                *layer,
+                Box::new(LocalFileSystemProvider),
+            )
+            .await?,
+        ),
+        (
+            ProviderItem::Drive {
+                drive_id,
+                corpora,
+                initial_token_path,
+                token_path,
+                strict: false,
+            },
+            Some(db),
+        ) => {
+            if let Some(initial_token_path) = initial_token_path {
+                if initial_token_path.exists() && !token_path.exists() {
+                    let _ = std::fs::copy(initial_token_path, token_path.clone());
+                }
+            }
+            Box::new(
+                DriveProvider::new(
+                    Default::default(),
+                    DriveProviderSettings::new(corpora.to_string(), drive_id.clone()),
+                    Some(db.open()?),
+                    token_path.clone(),
+                )
+                .await?,
+            )
+        }
+        (
+            ProviderItem::Drive {
+                drive_id,
+                corpora,
+                initial_token_path,
+                token_path,
+                strict: true,
+            },
+            _,
+        ) => {
+            if let Some(initial_token_path) = initial_token_path {
+                if initial_token_path.exists() && !token_path.exists() {
+                    let _ = std::fs::copy(initial_token_path, token_path.clone());
+                }
+            }
+            Box::new(
+                DriveProvider::new(
+                    Default::default(),
+                    DriveProviderSettings::new(corpora.to_string(), drive_id.clone()),
+                    None,
+                    token_path.clone(),
+                )
+                .await?,
+            )
+        }
+        #[cfg(feature = "webdav")]
+        (
+            ProviderItem::WebDav {
+                url,
+                username,
+                password,
+                token,
+                layer,
+            },
+            _,
+        ) => {
+            let auth = match (username, password, token) {
+                (_, _, Some(token)) => WebDavAuth::Bearer(token.clone()),
+                (Some(username), password, None) => WebDavAuth::Basic {
+                    username: username.clone(),
+                    password: password.clone().unwrap_or_default(),
+                },
+                _ => WebDavAuth::None,
+            };
+            Box::new(
+                WebDavProvider::new(WebDavProviderSettings {
+                    url: url.clone(),
+                    auth,
+                    layer: *layer,
+                })
+                .await?,
+            )
+        }
+        (_, None) => {
+            anyhow::bail!("metadata is not configured, but provider {provider_name} requires it")
+        }
+    };
+    Ok(provider)
+}
+
+async fn init_state(
+    config: Config,
+) -> anyhow::Result<(AnnilState, AnnilProvider<MultipleProviders>, AnnilKeys)> {
+    let providers_config = Arc::new(config.providers);
+    // cache pools are kept across reloads, so that cached files are still tracked
+    let pools: Arc<HashMap<_, _>> = Arc::new(
+        providers_config
+            .iter()
+            .filter_map(|(name, provider)| {
+                let cache = provider.cache.as_ref()?;
+                let pool = CachePool::with_config(
+                    &cache.root,
+                    CacheConfig {
+                        max_size: cache.max_size,
+                        dedup: cache.dedup,
+                    },
+                );
+                Some((name.clone(), Arc::new(pool)))
+            })
+            .collect(),
+    );
+    let (providers, statuses) =
+        init_providers(&providers_config, &pools, config.metadata.clone()).await;
+    let health = ProviderHealth::default();
+    health.set(statuses);
+    let metadata = config.metadata.clone();
+    let factory_health = health.clone();
+    let providers = AnnilProvider::new(providers)
+        .with_health(health)
+        .with_factory(move || {
+            let providers_config = providers_config.clone();
+            let pools = pools.clone();
+            // metadata repository has already been pulled by admin reload
+            let metadata = metadata.clone().map(|metadata| MetadataConfig {
+                pull: false,
+                ..metadata
+            });
+            let health = factory_health.clone();
+            async move {
+                let (providers, statuses) =
+                    init_providers(&providers_config, &pools, metadata).await;
+                // keep serving old providers if any of them failed to reload
+                if let Some(failed) = statuses.iter().find(|status| !status.ready) {
+                    anyhow::bail!(
+                        "provider {} failed to initialize: {}",
+                        failed.name,
+                        failed.error.as_deref().unwrap_or_default()
+                    );
+                }
+                health.set(statuses);
+                // cached audio and covers might be outdated
+                for pool in pools.values() {
+                    pool.purge().await;
+                }
+                Ok(providers)
+            }
+        });
     let etag = providers.compute_etag().await?;
 
     // key
     let sign_key = HS256Key::from_bytes(config.server.sign_key.as_ref());
     let share_key = HS256Key::from_bytes(config.server.share_key.as_ref())
         .with_key_id(&config.server.share_key_id);
+    let mut admin_tokens: HashMap<_, _> = config
+        .server
+        .admin_tokens
+        .into_iter()
+        .map(|admin| (admin.token, admin.scopes))
+        .collect();
+    if let Some(token) = config.server.admin_token {
+        // legacy admin token can perform all actions
+        admin_tokens.insert(token, AdminScope::all());
+    }
     let version = format!("Annil v{}", env!("CARGO_PKG_VERSION"));
     let last_update = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -161,7 +290,7 @@ async fn init_state(
         AnnilKeys {
             sign_key,
             share_key,
-            admin_token: config.server.admin_token,
+            admin_tokens,
         },
     ))
 }
@@ -179,18 +308,38 @@ async fn main() -> anyhow::Result<()> {
             .unwrap_or_else(|| "config.toml".to_owned()),
     )?;
     let listen: SocketAddr = config.server.listen.parse()?;
+    let rate_limit = config.server.rate_limit;
+    let drain_timeout = config.server.drain_timeout;
+    let transcode_cache = config.server.transcode_cache.clone();
     let (state, provider, keys) = init_state(config).await?;
 
     type Provider = MultipleProviders;
     let app = Router::new()
+        .route("/", get(webui::index))
         .route("/info", get(user::info))
-        .route("/albums", get(user::albums::<Provider>))
+        .route("/albums", get(user::albums::<Provider>));
+    #[cfg(feature = "metadata")]
+    let app = app
+        .route("/albums.ndjson", get(user::albums_ndjson))
+        .route("/playlist/:album_id", get(user::playlist::<Provider>));
+    let resources = Router::new()
         .route(
             "/:album_id/:disc_id/:track_id",
             get(user::audio::<Provider>).head(user::audio_head::<Provider>),
         )
         .route("/:album_id/cover", get(user::cover::<Provider>))
-        .route("/:album_id/:disc_id/cover", get(user::cover::<Provider>))
+        .route("/:album_id/:disc_id/cover", get(user::cover::<Provider>));
+    let resources = match rate_limit {
+        Some(config) => resources.route_layer(middleware::from_fn_with_state(
+            RateLimiter::new(config),
+            ratelimit::rate_limit,
+        )),
+        None => resources,
+    };
+    let app = app
+        // only compress json and text responses, audio and covers are already compressed
+        .layer(compression_layer())
+        .merge(resources)
         .layer(
             CorsLayer::new()
                 .allow_methods([Method::GET])
@@ -198,22 +347,41 @@ async fn main() -> anyhow::Result<()> {
                 .allow_headers(cors::Any),
         )
         .route("/admin/sign", post(admin::sign))
+        .route("/admin/share", post(admin::share))
         .route("/admin/reload", post(admin::reload::<Provider>))
+        .route("/healthz", get(health::healthz))
+        .route("/readyz", get(health::readyz::<Provider>));
+    #[cfg(feature = "webui")]
+    let app = app.route("/webui/*path", get(webui::asset));
+    let app = match transcode_cache {
+        Some(config) => app.layer(Extension(Arc::new(TranscodeCache::new(config)))),
+        None => app,
+    };
+    let app = app
         .layer(Extension(Arc::new(state)))
         .layer(Extension(Arc::new(provider)))
         .layer(Extension(Arc::new(keys)));
 
     let listener = TcpListener::bind(&listen).await?;
-    axum::serve(listener, app).await?;
+    shutdown::serve(
+        listener,
+        app,
+        shutdown::shutdown_signal(),
+        Duration::from_secs(drain_timeout),
+    )
+    .await?;
 
     Ok(())
 }
 
 mod config {
     use annil::metadata::MetadataConfig;
+    use annil::ratelimit::RateLimitConfig;
+    use annil::state::AdminScope;
+    use annil::transcode::TranscodeCacheConfig;
     use anyhow::Context;
     use serde::Deserialize;
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
     use std::fs;
     use std::path::{Path, PathBuf};
 
@@ -246,14 +414,48 @@ mod config {
         pub sign_key: String,
         pub share_key: String,
         pub share_key_id: String,
-        /// Password to reload data
-        pub admin_token: String,
+        /// Admin token allowed to perform all admin actions
+        pub admin_token: Option<String>,
+        /// Admin tokens with limited scopes
+        #[serde(default)]
+        pub admin_tokens: Vec<AdminTokenConfig>,
+        /// Limit requests to audio and covers per token
+        pub rate_limit: Option<RateLimitConfig>,
+        /// Seconds to wait for in-flight requests to finish on shutdown
+        #[serde(default = "default_drain_timeout")]
+        pub drain_timeout: u64,
+        /// Cache transcoded audio if set
+        pub transcode_cache: Option<TranscodeCacheConfig>,
+    }
+
+    fn default_drain_timeout() -> u64 {
+        10
+    }
+
+    #[derive(Deserialize)]
+    pub struct AdminTokenConfig {
+        pub token: String,
+        pub scopes: HashSet<AdminScope>,
     }
 
     #[derive(Deserialize)]
     pub struct ProviderConfig {
         #[serde(flatten)]
         pub item: ProviderItem,
+        /// Cache audio and covers read from the provider if set
+        pub cache: Option<ProviderCacheConfig>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct ProviderCacheConfig {
+        /// Directory to store cached audio and covers
+        pub root: PathBuf,
+        /// Maximum space used by cache
+        pub max_size: Option<usize>,
+        /// Store identical audio and covers only once
+        #[serde(default)]
+        pub dedup: bool,
     }
 
     #[derive(Deserialize)]
@@ -277,6 +479,17 @@ mod config {
             #[serde(default)]
             strict: bool,
         },
+        #[cfg(feature = "webdav")]
+        #[serde(rename = "webdav")]
+        #[serde(rename_all = "kebab-case")]
+        WebDav {
+            url: String,
+            username: Option<String>,
+            password: Option<String>,
+            token: Option<String>,
+            #[serde(default = "default_layer")]
+            layer: usize,
+        },
     }
 
     const fn default_layer() -> usize {
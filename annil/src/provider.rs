@@ -1,19 +1,217 @@
-use anni_provider::{AnniProvider, ProviderError};
+use anni_provider::{AnniProvider, ProviderError, ReloadReport};
+use async_trait::async_trait;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::num::NonZeroU8;
 use std::ops::{Deref, DerefMut};
-use tokio::sync::RwLock;
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
 
-pub struct AnnilProvider<T: AnniProvider + Send + Sync>(RwLock<T>);
+/// Builds a new provider to replace the serving one on reload.
+#[async_trait]
+pub trait ProviderFactory<T>: Send + Sync {
+    async fn build(&self) -> anyhow::Result<T>;
+}
+
+#[async_trait]
+impl<T, F, Fut> ProviderFactory<T> for F
+where
+    T: 'static,
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = anyhow::Result<T>> + Send,
+{
+    async fn build(&self) -> anyhow::Result<T> {
+        self().await
+    }
+}
+
+/// Initialization status of a configured provider.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProviderStatus {
+    pub name: String,
+    pub ready: bool,
+    /// Reason of initialization failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ProviderStatus {
+    pub fn ready<N: Into<String>>(name: N) -> Self {
+        Self {
+            name: name.into(),
+            ready: true,
+            error: None,
+        }
+    }
+
+    pub fn failed<N: Into<String>, E: ToString>(name: N, error: E) -> Self {
+        Self {
+            name: name.into(),
+            ready: false,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// Statuses of providers from the latest initialization.
+///
+/// It's cheap to clone, and clones share the same statuses,
+/// so that a [ProviderFactory] can update statuses of the [AnnilProvider] it belongs to.
+#[derive(Clone, Default)]
+pub struct ProviderHealth(Arc<std::sync::RwLock<Vec<ProviderStatus>>>);
+
+impl ProviderHealth {
+    pub fn set(&self, statuses: Vec<ProviderStatus>) {
+        *self.0.write().unwrap() = statuses;
+    }
+
+    pub fn statuses(&self) -> Vec<ProviderStatus> {
+        self.0.read().unwrap().clone()
+    }
+
+    /// Whether all providers are initialized successfully.
+    pub fn is_ready(&self) -> bool {
+        self.0.read().unwrap().iter().all(|status| status.ready)
+    }
+}
+
+pub struct AnnilProvider<T: AnniProvider + Send + Sync> {
+    provider: RwLock<T>,
+    factory: Option<Box<dyn ProviderFactory<T>>>,
+    health: ProviderHealth,
+    /// Held during reload, so that only one reload runs at a time
+    reloading: Mutex<()>,
+    /// Track durations of albums, which are cleared on reload
+    durations: std::sync::RwLock<HashMap<String, AlbumDurations>>,
+}
+
+/// Durations of tracks in an album in milliseconds, grouped by disc.
+///
+/// Tracks which can not be read are `None`.
+pub type AlbumDurations = Vec<Vec<Option<u64>>>;
 
 impl<T: AnniProvider + Send + Sync> AnnilProvider<T> {
     pub fn new(provider: T) -> Self {
-        Self(RwLock::new(provider))
+        Self {
+            provider: RwLock::new(provider),
+            factory: None,
+            health: ProviderHealth::default(),
+            reloading: Mutex::new(()),
+            durations: Default::default(),
+        }
+    }
+
+    /// Report provider statuses in `health`, which is updated by whoever initializes providers.
+    pub fn with_health(mut self, health: ProviderHealth) -> Self {
+        self.health = health;
+        self
+    }
+
+    pub fn health(&self) -> &ProviderHealth {
+        &self.health
+    }
+
+    /// Build a new provider with `factory` on reload, instead of reloading the provider in place.
+    pub fn with_factory<F>(mut self, factory: F) -> Self
+    where
+        F: ProviderFactory<T> + 'static,
+    {
+        self.factory = Some(Box::new(factory));
+        self
+    }
+
+    /// Reload albums of the provider.
+    ///
+    /// If a factory is set, the new provider is swapped in only after it's initialized successfully,
+    /// so the old one keeps serving if anything fails. Otherwise, the provider is reloaded in place,
+    /// and keeps serving requests while reloading.
+    pub async fn reload(&self) -> anyhow::Result<ReloadReport> {
+        let _reloading = self.reloading.lock().await;
+        let Some(factory) = &self.factory else {
+            let report = self.provider.read().await.reload_report().await?;
+            self.durations.write().unwrap().clear();
+            return Ok(report);
+        };
+
+        let staging = factory.build().await?;
+        let new_albums = album_set(&staging).await?;
+
+        let mut provider = self.provider.write().await;
+        let old_albums = album_set(&*provider).await?;
+        *provider = staging;
+        self.durations.write().unwrap().clear();
+        Ok(ReloadReport::diff(&old_albums, &new_albums))
+    }
+
+    /// Whether durations of `album_id` are cached since the last reload.
+    pub fn has_durations(&self, album_id: &str) -> bool {
+        self.durations.read().unwrap().contains_key(album_id)
+    }
+
+    /// Get durations of tracks in `album_id`, which are cached until the next reload.
+    ///
+    /// If `track_counts` of discs are known, tracks which are missing or can not be read are `None`.
+    /// Otherwise, as providers do not list tracks, tracks are probed until the first missing one in each disc.
+    pub async fn album_durations(
+        &self,
+        album_id: &str,
+        track_counts: Option<&[usize]>,
+    ) -> AlbumDurations {
+        if let Some(durations) = self.durations.read().unwrap().get(album_id) {
+            return durations.clone();
+        }
+
+        let provider = self.provider.read().await;
+        let durations = match track_counts {
+            Some(track_counts) => {
+                let mut discs = Vec::with_capacity(track_counts.len());
+                let disc_ids = (1..=u8::MAX).filter_map(NonZeroU8::new);
+                for (disc_id, &track_count) in disc_ids.zip(track_counts) {
+                    let mut tracks = Vec::with_capacity(track_count);
+                    for track_id in (1..=u8::MAX).filter_map(NonZeroU8::new).take(track_count) {
+                        let duration =
+                            track_duration(&*provider, album_id, disc_id, track_id).await;
+                        tracks.push(duration.ok());
+                    }
+                    discs.push(tracks);
+                }
+                discs
+            }
+            None => {
+                let mut discs = Vec::new();
+                for disc_id in (1..=u8::MAX).filter_map(NonZeroU8::new) {
+                    let mut tracks = Vec::new();
+                    for track_id in (1..=u8::MAX).filter_map(NonZeroU8::new) {
+                        match track_duration(&*provider, album_id, disc_id, track_id).await {
+                            Ok(duration) => tracks.push(Some(duration)),
+                            Err(ProviderError::FileNotFound) => break,
+                            Err(_) => tracks.push(None),
+                        }
+                    }
+                    if tracks.is_empty() {
+                        break;
+                    }
+                    discs.push(tracks);
+                }
+                discs
+            }
+        };
+        drop(provider);
+
+        self.durations
+            .write()
+            .unwrap()
+            .insert(album_id.to_string(), durations.clone());
+        durations
     }
 
     pub async fn compute_etag(&self) -> Result<String, ProviderError> {
         use base64::engine::general_purpose::STANDARD;
         use base64::Engine;
 
-        let provider = self.0.read().await;
+        let provider = self.provider.read().await;
 
         let mut etag = 0;
         for album in provider.albums().await? {
@@ -28,16 +226,44 @@ impl<T: AnniProvider + Send + Sync> AnnilProvider<T> {
     }
 }
 
+async fn album_set<T: AnniProvider>(provider: &T) -> Result<HashSet<String>, ProviderError> {
+    Ok(provider
+        .albums()
+        .await?
+        .into_iter()
+        .map(Cow::into_owned)
+        .collect())
+}
+
+/// Duration of a track in milliseconds, errors other than a missing track are logged.
+async fn track_duration<T: AnniProvider>(
+    provider: &T,
+    album_id: &str,
+    disc_id: NonZeroU8,
+    track_id: NonZeroU8,
+) -> Result<u64, ProviderError> {
+    provider
+        .get_audio_info(album_id, disc_id, track_id)
+        .await
+        .map(|info| info.duration)
+        .map_err(|e| {
+            if !matches!(e, ProviderError::FileNotFound) {
+                log::warn!("Failed to read duration of {album_id}/{disc_id}/{track_id}: {e}");
+            }
+            e
+        })
+}
+
 impl<T: AnniProvider + Send + Sync> Deref for AnnilProvider<T> {
     type Target = RwLock<T>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.provider
     }
 }
 
 impl<T: AnniProvider + Send + Sync> DerefMut for AnnilProvider<T> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.provider
     }
 }
@@ -0,0 +1,136 @@
+use crate::extractor::auth::AuthExtractor;
+use crate::extractor::token::AnnilClaim;
+use axum::extract::{Request, State};
+use axum::http::header::RETRY_AFTER;
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use dashmap::DashMap;
+use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Interval between evictions of idle buckets.
+const EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Deserialize, Clone, Copy)]
+pub struct RateLimitConfig {
+    /// Number of requests refilled per minute
+    pub requests_per_minute: u32,
+    /// Maximum number of requests allowed in a burst
+    pub burst: u32,
+}
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Token bucket rate limiter, keyed by token subject.
+///
+/// Buckets are shared between all clones of the limiter.
+/// Buckets refilled to full are evicted periodically, as they are the same as new ones.
+#[derive(Clone)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Arc<DashMap<String, Bucket>>,
+    last_eviction: Arc<Mutex<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Default::default(),
+            last_eviction: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Number of buckets kept by the limiter.
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Remove buckets which have been refilled to full.
+    pub fn evict_idle(&self) {
+        let now = Instant::now();
+        let burst = self.config.burst as f64;
+        self.buckets
+            .retain(|_, bucket| self.refilled(bucket, now) < burst);
+    }
+
+    /// Tokens in `bucket` after refilling it at `now`.
+    fn refilled(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let rate = self.config.requests_per_minute as f64 / 60.0;
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        (bucket.tokens + elapsed * rate).min(self.config.burst as f64)
+    }
+
+    /// Take a token from the bucket of `key`.
+    ///
+    /// Returns the duration to wait before the next request is allowed if the bucket is empty.
+    pub fn check(&self, key: &str) -> Result<(), Duration> {
+        let burst = self.config.burst as f64;
+        let rate = self.config.requests_per_minute as f64 / 60.0;
+        let now = Instant::now();
+
+        // evict before taking the entry, which holds a lock on the map
+        let evict = match self.last_eviction.try_lock() {
+            Ok(mut last_eviction) if now.duration_since(*last_eviction) >= EVICTION_INTERVAL => {
+                *last_eviction = now;
+                true
+            }
+            _ => false,
+        };
+        if evict {
+            self.evict_idle();
+        }
+
+        let mut bucket = self.buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: burst,
+            last_refill: now,
+        });
+        bucket.tokens = self.refilled(&bucket, now);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else if rate > 0.0 {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        } else {
+            Err(Duration::MAX)
+        }
+    }
+}
+
+/// Middleware limiting requests per token.
+///
+/// User tokens are limited by `user_id`, share tokens are limited by the token itself.
+/// Requests without a valid token are passed to the inner handler, which decides whether to reject them.
+pub async fn rate_limit(
+    State(limiter): State<RateLimiter>,
+    auth: Option<AuthExtractor>,
+    claim: Option<AnnilClaim>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let key = match (claim, auth) {
+        (Some(AnnilClaim::User(user)), _) => Some(format!("user:{}", user.user_id)),
+        (Some(AnnilClaim::Share(_)), Some(AuthExtractor(token))) => Some(format!("share:{token}")),
+        _ => None,
+    };
+
+    if let Some(key) = key {
+        if let Err(retry_after) = limiter.check(&key) {
+            let retry_after = retry_after.as_secs_f64().ceil().min(u32::MAX as f64) as u64;
+            return (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(RETRY_AFTER, retry_after.to_string())],
+            )
+                .into_response();
+        }
+    }
+
+    next.run(request).await
+}
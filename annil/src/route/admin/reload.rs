@@ -1,18 +1,25 @@
+use crate::error::AnnilError;
 use crate::extractor::admin::AnnilAdmin;
 use crate::provider::AnnilProvider;
-use crate::state::AnnilState;
+use crate::state::{AdminScope, AnnilState};
+use crate::transcode::TranscodeCache;
 use anni_provider::AnniProvider;
-use axum::Extension;
+use axum::{Extension, Json};
+use jwt_simple::reexports::serde_json::{json, Value};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 pub async fn reload<P>(
-    _: AnnilAdmin,
+    admin: AnnilAdmin,
     Extension(data): Extension<Arc<AnnilState>>,
     Extension(provider): Extension<Arc<AnnilProvider<P>>>,
-) where
+    transcode_cache: Option<Extension<Arc<TranscodeCache>>>,
+) -> Result<Json<Value>, AnnilError>
+where
     P: AnniProvider + Send + Sync,
 {
+    admin.require(AdminScope::Reload)?;
+
     #[cfg(feature = "metadata")]
     if let Some(metadata) = &data.metadata {
         use anni_repo::RepositoryManager;
@@ -27,13 +34,29 @@ pub async fn reload<P>(
         }
     }
 
-    if let Err(e) = provider.write().await.reload().await {
+    // old providers keep serving if reload failed, so etag and last_update are left untouched
+    let report = provider.reload().await.map_err(|e| {
         log::error!("Failed to reload provider: {:?}", e);
+        AnnilError::ReloadFailed(e.to_string())
+    })?;
+
+    // source audio might have changed
+    if let Some(Extension(cache)) = transcode_cache {
+        cache.purge().await;
     }
 
-    *data.etag.write().await = provider.compute_etag().await.unwrap();
+    let etag = provider
+        .compute_etag()
+        .await
+        .map_err(|e| AnnilError::ReloadFailed(e.to_string()))?;
+    *data.etag.write().await = etag;
     *data.last_update.write().await = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
+
+    Ok(Json(json!({
+        "added": report.added,
+        "removed": report.removed,
+    })))
 }
@@ -1,29 +1,34 @@
+use crate::error::AnnilError;
 use crate::extractor::admin::AnnilAdmin;
-use crate::extractor::token::{AnnilClaim, ShareToken, UserClaim};
-use crate::state::AnnilKeys;
+use crate::extractor::token::{AnnilClaim, ShareClaim, ShareToken, UserClaim};
+use crate::state::{AdminScope, AnnilKeys};
 use axum::{Extension, Json};
 use jwt_simple::prelude::*;
 use std::sync::Arc;
+use uuid::Uuid;
 
 #[derive(Deserialize, Clone)]
 pub struct SignPayload {
     user_id: String,
     #[serde(default)]
     share: bool,
+    /// Albums allowed to share, all albums are allowed if not set
+    allowed: Option<Vec<Uuid>>,
 }
 
 pub async fn sign(
-    _: AnnilAdmin,
+    admin: AnnilAdmin,
     Extension(keys): Extension<Arc<AnnilKeys>>,
     Json(info): Json<SignPayload>,
-) -> String {
+) -> Result<String, AnnilError> {
+    admin.require(AdminScope::Sign)?;
     let custom = AnnilClaim::User(UserClaim {
         user_id: info.user_id,
         share: if info.share {
             Some(ShareToken {
                 key_id: keys.share_key.key_id().as_deref().unwrap().to_string(),
                 secret: unsafe { String::from_utf8_unchecked(keys.share_key.to_bytes().to_vec()) },
-                allowed: None,
+                allowed: info.allowed,
             })
         } else {
             None
@@ -42,7 +47,45 @@ pub async fn sign(
         nonce: None,
         custom,
     };
-    keys.sign_key
+    Ok(keys
+        .sign_key
         .authenticate(claim)
-        .expect("Failed to sign user token")
+        .expect("Failed to sign user token"))
+}
+
+/// Default lifetime of share tokens signed by `/admin/share`
+const SHARE_TOKEN_HOURS: u64 = 7 * 24;
+
+fn default_share_hours() -> u64 {
+    SHARE_TOKEN_HOURS
+}
+
+#[derive(Deserialize, Clone)]
+pub struct SharePayload {
+    /// Albums shared by the token
+    albums: Vec<Uuid>,
+    /// Lifetime of the token in hours, 7 days if not set
+    #[serde(default = "default_share_hours")]
+    expires_in: u64,
+}
+
+/// Sign a share token which can only access albums in `albums`
+pub async fn share(
+    admin: AnnilAdmin,
+    Extension(keys): Extension<Arc<AnnilKeys>>,
+    Json(info): Json<SharePayload>,
+) -> Result<String, AnnilError> {
+    admin.require(AdminScope::Sign)?;
+    let custom = AnnilClaim::Share(ShareClaim {
+        audios: Default::default(),
+        albums: Some(info.albums),
+        quality: None,
+    });
+
+    // share tokens are handed out to guests, never sign one that lives forever
+    let claim = Claims::with_custom_claims(custom, Duration::from_hours(info.expires_in.max(1)));
+    Ok(keys
+        .share_key
+        .authenticate(claim)
+        .expect("Failed to sign share token"))
 }
@@ -0,0 +1,84 @@
+use crate::provider::{AnnilProvider, ProviderStatus};
+use crate::state::AnnilState;
+use anni_provider::AnniProvider;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use jwt_simple::reexports::serde_json::json;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Serialize)]
+struct Readiness {
+    ready: bool,
+    providers: Vec<ProviderStatus>,
+    /// Whether metadata database can be opened, `None` if metadata is not configured
+    metadata: Option<bool>,
+}
+
+/// Liveness probe, which responds as long as the server is running
+pub async fn healthz() -> Response {
+    Json(json!({ "status": "ok" })).into_response()
+}
+
+/// Readiness probe, which responds `503` if any provider failed to initialize or metadata database is unreachable
+pub async fn readyz<P>(
+    Extension(provider): Extension<Arc<AnnilProvider<P>>>,
+    Extension(data): Extension<Arc<AnnilState>>,
+) -> Response
+where
+    P: AnniProvider + Send + Sync,
+{
+    let health = provider.health();
+    let metadata = match &data.metadata {
+        Some(metadata) => Some(metadata_reachable(metadata.base.join("repo.db")).await),
+        None => None,
+    };
+
+    let ready = health.is_ready() && metadata != Some(false);
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let readiness = Readiness {
+        ready,
+        providers: health.statuses(),
+        metadata,
+    };
+    (status, Json(readiness)).into_response()
+}
+
+/// How long the result of opening metadata database is reused, so frequent probes don't open it each time
+const METADATA_PROBE_TTL: Duration = Duration::from_secs(5);
+
+/// Last result of opening metadata database, with its path and when it was opened
+static METADATA_PROBE: Mutex<Option<(PathBuf, Instant, bool)>> = Mutex::new(None);
+
+async fn metadata_reachable(db_path: PathBuf) -> bool {
+    let probe = METADATA_PROBE.lock().unwrap().clone();
+    if let Some((path, probed_at, reachable)) = probe {
+        if path == db_path && probed_at.elapsed() < METADATA_PROBE_TTL {
+            return reachable;
+        }
+    }
+
+    let reachable = open_metadata(db_path.clone()).await;
+    *METADATA_PROBE.lock().unwrap() = Some((db_path, Instant::now(), reachable));
+    reachable
+}
+
+#[cfg(feature = "metadata")]
+async fn open_metadata(db_path: PathBuf) -> bool {
+    tokio::task::spawn_blocking(move || anni_repo::db::RepoDatabaseRead::new(db_path).is_ok())
+        .await
+        .unwrap_or(false)
+}
+
+#[cfg(not(feature = "metadata"))]
+async fn open_metadata(_db_path: PathBuf) -> bool {
+    // metadata database is never used without `metadata` feature
+    true
+}
@@ -1,2 +1,144 @@
 pub mod admin;
+pub mod health;
 pub mod user;
+pub mod webui;
+
+use tower_http::compression::predicate::{And, DefaultPredicate, NotForContentType, Predicate};
+use tower_http::compression::CompressionLayer;
+
+/// Compress responses with `gzip` or `br` according to `Accept-Encoding` of the request.
+///
+/// Audio and cover responses are already compressed and would never be compressed again.
+pub fn compression_layer() -> CompressionLayer<And<DefaultPredicate, NotForContentType>> {
+    CompressionLayer::new()
+        .compress_when(DefaultPredicate::new().and(NotForContentType::const_new("audio/")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compression_layer;
+    use crate::extractor::token::{AnnilClaim, UserClaim};
+    use crate::provider::AnnilProvider;
+    use crate::route::user;
+    use crate::state::{AnnilKeys, AnnilState};
+    use anni_provider::{
+        AnniProvider, AudioInfo, AudioResourceReader, ProviderError, Range, ResourceReader,
+    };
+    use axum::body::Body;
+    use axum::http::header::{ACCEPT_ENCODING, AUTHORIZATION, CONTENT_ENCODING};
+    use axum::http::{Request, StatusCode};
+    use axum::routing::get;
+    use axum::{Extension, Router};
+    use jwt_simple::prelude::*;
+    use std::borrow::Cow;
+    use std::collections::HashSet;
+    use std::num::NonZeroU8;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    struct TestProvider;
+
+    #[async_trait::async_trait]
+    impl AnniProvider for TestProvider {
+        async fn albums(&self) -> anni_provider::Result<HashSet<Cow<str>>> {
+            Ok((0..64)
+                .map(|i| Cow::Owned(Uuid::from_u128(i).to_string()))
+                .collect())
+        }
+
+        async fn get_audio(
+            &self,
+            _album_id: &str,
+            _disc_id: NonZeroU8,
+            _track_id: NonZeroU8,
+            range: Range,
+        ) -> anni_provider::Result<AudioResourceReader> {
+            Ok(AudioResourceReader {
+                info: AudioInfo {
+                    extension: "flac".to_string(),
+                    size: 4096,
+                    duration: 1000,
+                },
+                range,
+                reader: Box::pin(std::io::Cursor::new(vec![0; 4096])),
+            })
+        }
+
+        async fn get_cover(
+            &self,
+            _album_id: &str,
+            _disc_id: Option<NonZeroU8>,
+        ) -> anni_provider::Result<ResourceReader> {
+            Err(ProviderError::FileNotFound)
+        }
+
+        async fn reload(&self) -> anni_provider::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn app(keys: AnnilKeys) -> Router {
+        let state = AnnilState {
+            version: String::new(),
+            last_update: RwLock::new(0),
+            etag: RwLock::new(String::new()),
+            metadata: None,
+        };
+        // audio route is also covered by compression layer here to make sure audio is excluded
+        Router::new()
+            .route("/albums", get(user::albums::<TestProvider>))
+            .route(
+                "/:album_id/:disc_id/:track_id",
+                get(user::audio::<TestProvider>),
+            )
+            .layer(compression_layer())
+            .layer(Extension(Arc::new(state)))
+            .layer(Extension(Arc::new(AnnilProvider::new(TestProvider))))
+            .layer(Extension(Arc::new(keys)))
+    }
+
+    fn user_token(keys: &AnnilKeys) -> String {
+        let claim = AnnilClaim::User(UserClaim {
+            user_id: "test".to_string(),
+            share: None,
+        });
+        keys.sign_key
+            .authenticate(Claims::with_custom_claims(claim, Duration::from_hours(1)))
+            .unwrap()
+    }
+
+    async fn request(app: Router, token: &str, uri: &str) -> axum::response::Response {
+        app.oneshot(
+            Request::get(uri)
+                .header(AUTHORIZATION, token)
+                .header(ACCEPT_ENCODING, "gzip")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_compress_albums() {
+        let keys = AnnilKeys::new(b"sign", b"share", String::new());
+        let token = user_token(&keys);
+
+        let response = request(app(keys), &token, "/albums").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_ENCODING], "gzip");
+    }
+
+    #[tokio::test]
+    async fn test_do_not_compress_audio() {
+        let keys = AnnilKeys::new(b"sign", b"share", String::new());
+        let token = user_token(&keys);
+
+        let uri = format!("/{}/1/1?quality=lossless", Uuid::from_u128(0));
+        let response = request(app(keys), &token, &uri).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(CONTENT_ENCODING).is_none());
+    }
+}
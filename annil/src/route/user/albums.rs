@@ -2,18 +2,33 @@ use crate::extractor::token::AnnilClaim;
 use crate::provider::AnnilProvider;
 use crate::state::AnnilState;
 use anni_provider::AnniProvider;
+use axum::extract::Query;
 use axum::http::header::{ETAG, IF_NONE_MATCH};
 use axum::http::{HeaderMap, StatusCode};
 use axum::response::{IntoResponse, Response};
 use axum::{Extension, Json};
-use std::collections::HashSet;
+use futures::StreamExt;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
+#[derive(Deserialize)]
+pub struct AlbumsQuery {
+    /// Include durations of tracks if set to non-zero.
+    #[serde(default)]
+    duration: u8,
+}
+
 /// Get available albums of current annil server
+///
+/// With `?duration=1`, users get durations of all tracks in milliseconds, grouped by album and disc.
+/// Tracks which can not be read are `null`.
 pub async fn albums<P>(
     claims: AnnilClaim,
     Extension(provider): Extension<Arc<AnnilProvider<P>>>,
     Extension(data): Extension<Arc<AnnilState>>,
+    Query(query): Query<AlbumsQuery>,
     headers: HeaderMap,
 ) -> Response
 where
@@ -21,7 +36,10 @@ where
 {
     match claims {
         AnnilClaim::User(_) => {
-            let etag_now = data.etag.read().await.to_string();
+            let mut etag_now = data.etag.read().await.to_string();
+            if query.duration != 0 {
+                etag_now = duration_etag(&etag_now);
+            }
 
             if let Some(Ok(mut etag)) = headers.get(IF_NONE_MATCH).map(|v| v.to_str()) {
                 if etag.starts_with("W/") {
@@ -33,13 +51,158 @@ where
             }
 
             // users can get real album list
-            let provider = provider.read().await;
-            let albums = provider.albums().await.unwrap_or(HashSet::new());
-            ([(ETAG, etag_now)], Json(albums)).into_response()
+            let albums: HashSet<String> = provider
+                .read()
+                .await
+                .albums()
+                .await
+                .map(|albums| albums.into_iter().map(Cow::into_owned).collect())
+                .unwrap_or(HashSet::new());
+            if query.duration == 0 {
+                return ([(ETAG, etag_now)], Json(albums)).into_response();
+            }
+
+            let uncached = albums
+                .iter()
+                .filter(|album_id| !provider.has_durations(album_id))
+                .cloned()
+                .collect();
+            let track_counts = &track_counts(&data, uncached).await;
+            let provider = &provider;
+            let durations: HashMap<_, _> = futures::stream::iter(albums)
+                .map(|album_id| async move {
+                    let track_counts = track_counts.get(&album_id).map(Vec::as_slice);
+                    let durations = provider.album_durations(&album_id, track_counts).await;
+                    (album_id, durations)
+                })
+                .buffer_unordered(16)
+                .collect()
+                .await;
+            ([(ETAG, etag_now)], Json(durations)).into_response()
         }
         AnnilClaim::Share(share) => {
             // guests can only get album list defined in jwt
-            Json(share.audios.keys().collect::<Vec<_>>()).into_response()
+            let mut albums: HashSet<String> = share.audios.into_keys().collect();
+            if let Some(shared) = share.albums {
+                albums.extend(shared.iter().map(ToString::to_string));
+            }
+            Json(albums).into_response()
         }
     }
 }
+
+/// ETag of album list with durations, which differs from the one of plain album list.
+fn duration_etag(etag: &str) -> String {
+    match etag.strip_suffix('"') {
+        Some(etag) => format!(r#"{etag}-duration""#),
+        None => format!("{etag}-duration"),
+    }
+}
+
+/// Number of tracks in each disc of `albums`, read from metadata repository.
+///
+/// Albums which are not found in metadata repository are omitted.
+#[cfg(feature = "metadata")]
+async fn track_counts(data: &AnnilState, albums: Vec<String>) -> HashMap<String, Vec<usize>> {
+    use anni_repo::db::RepoDatabaseRead;
+
+    let Some(metadata) = &data.metadata else {
+        return HashMap::new();
+    };
+    let db_path = metadata.base.join("repo.db");
+    if albums.is_empty() || !db_path.exists() {
+        return HashMap::new();
+    }
+
+    let counts = tokio::task::spawn_blocking(move || {
+        let db = RepoDatabaseRead::new(db_path)?;
+        let mut counts = HashMap::new();
+        for album_id in albums {
+            let Ok(uuid) = uuid::Uuid::parse_str(&album_id) else {
+                continue;
+            };
+            let discs = db.get_discs(uuid)?;
+            if discs.is_empty() {
+                continue;
+            }
+            let tracks = discs
+                .iter()
+                .map(|disc| db.get_tracks(uuid, disc.disc_id).map(|tracks| tracks.len()))
+                .collect::<Result<_, _>>()?;
+            counts.insert(album_id, tracks);
+        }
+        Ok::<_, anni_repo::error::Error>(counts)
+    })
+    .await;
+    match counts {
+        Ok(Ok(counts)) => counts,
+        Ok(Err(e)) => {
+            log::warn!("Failed to read track counts from metadata repository: {e}");
+            HashMap::new()
+        }
+        Err(e) => {
+            log::warn!("Failed to read track counts from metadata repository: {e}");
+            HashMap::new()
+        }
+    }
+}
+
+#[cfg(not(feature = "metadata"))]
+async fn track_counts(_: &AnnilState, _: Vec<String>) -> HashMap<String, Vec<usize>> {
+    HashMap::new()
+}
+
+/// Export all albums in metadata repository, one JSON object per line
+#[cfg(feature = "metadata")]
+pub async fn albums_ndjson(
+    claims: AnnilClaim,
+    Extension(data): Extension<Arc<AnnilState>>,
+) -> Response {
+    use crate::error::AnnilError;
+    use anni_repo::db::RepoDatabaseRead;
+    use anni_repo::models::JsonAlbum;
+    use axum::body::{Body, Bytes};
+    use axum::http::header::CONTENT_TYPE;
+
+    if !matches!(claims, AnnilClaim::User(_)) {
+        return AnnilError::Forbidden.into_response();
+    }
+    let Some(metadata) = &data.metadata else {
+        return AnnilError::NotFound.into_response();
+    };
+    let db_path = metadata.base.join("repo.db");
+    if !db_path.exists() {
+        return AnnilError::NotFound.into_response();
+    }
+
+    // albums are read on a blocking thread and sent to the response body one by one
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Bytes, anni_repo::error::Error>>(16);
+    tokio::task::spawn_blocking(move || {
+        let db = match RepoDatabaseRead::new(db_path) {
+            Ok(db) => db,
+            Err(e) => return tx.blocking_send(Err(e)),
+        };
+        let albums = match db.albums_iter() {
+            Ok(albums) => albums,
+            Err(e) => return tx.blocking_send(Err(e)),
+        };
+        for album in albums {
+            let line = album.map(|album| {
+                let mut line = JsonAlbum::from(album).to_string();
+                line.push('\n');
+                Bytes::from(line)
+            });
+            tx.blocking_send(line)?;
+        }
+        Ok(())
+    });
+
+    let stream = futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|line| (line, rx))
+    });
+    (
+        [(CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(stream),
+    )
+        .into_response()
+}
@@ -1,14 +1,17 @@
 use crate::error::AnnilError;
+use crate::extractor::cache::NoCache;
 use crate::extractor::token::AnnilClaim;
 use crate::extractor::track::TrackIdentifier;
 use crate::provider::AnnilProvider;
 use crate::transcode::*;
-use anni_provider::{AnniProvider, Range};
+use anni_provider::{AnniProvider, AudioInfo, ProviderError, Range};
+use anni_split::codec::wav::WaveHeader;
+use anni_split::format::AudioFormat;
 use axum::body::Body;
 use axum::extract::Query;
 use axum::http::header::{
-    ACCEPT_RANGES, ACCESS_CONTROL_EXPOSE_HEADERS, CACHE_CONTROL, CONTENT_LENGTH, CONTENT_RANGE,
-    CONTENT_TYPE,
+    ACCEPT, ACCEPT_RANGES, ACCESS_CONTROL_EXPOSE_HEADERS, CACHE_CONTROL, CONTENT_LENGTH,
+    CONTENT_RANGE, CONTENT_TYPE, RANGE,
 };
 use axum::http::{HeaderMap, StatusCode};
 use axum::response::{IntoResponse, Response};
@@ -65,43 +68,162 @@ pub struct AudioQuery {
     #[serde(rename = "quality")]
     quality_requested: Option<String>,
 
-    #[serde(default)]
-    opus: bool,
+    /// Transcode to opus instead of aac. Negotiated by `Accept` header if not set.
+    opus: Option<bool>,
+
+    /// Target bit depth of lossless audio.
+    bit_depth: Option<u16>,
+    /// Target sample rate. Ignored by opus.
+    sample_rate: Option<u32>,
+    /// Target number of channels. Ignored by opus.
+    channels: Option<u16>,
 }
 
 impl AudioQuery {
-    pub fn get_transcoder(&self, is_guest: bool) -> Box<dyn Transcode + Send + Sync> {
-        let quality = self.quality(is_guest);
+    /// `source` is the format of the requested audio, which is required to convert its format.
+    pub fn get_transcoder(
+        &self,
+        claim: &AnnilClaim,
+        headers: &HeaderMap,
+        source: Option<WaveHeader>,
+    ) -> Box<dyn Transcode + Send + Sync> {
+        let quality = self.quality(claim);
         if quality.need_transcode() {
-            if self.opus {
+            let accept = headers.get(ACCEPT).and_then(|v| v.to_str().ok());
+            if self
+                .opus
+                .unwrap_or_else(|| accept.is_some_and(prefers_opus))
+            {
                 Box::new(OpusTranscoder::new(quality))
             } else {
-                Box::new(AacTranscoder::new(quality))
+                Box::new(AacTranscoder::new(quality, self.format(), source))
             }
         } else {
-            Box::new(FlacTranscoder::new(quality))
+            Box::new(FlacTranscoder::new(quality, self.format(), source))
+        }
+    }
+
+    fn format(&self) -> AudioFormat {
+        AudioFormat::new(self.bit_depth, self.sample_rate, self.channels)
+    }
+
+    /// Read format of the requested audio, which is only needed if a target format is requested.
+    async fn source_format<P>(&self, provider: &P, track: &TrackIdentifier) -> Option<WaveHeader>
+    where
+        P: AnniProvider,
+    {
+        if self.format().is_empty() {
+            return None;
+        }
+
+        let audio = provider
+            .get_audio(
+                &track.album_id.to_string(),
+                track.disc_id,
+                track.track_id,
+                Range::FLAC_HEADER,
+            )
+            .await
+            .ok()?;
+        source_format(audio).await
+    }
+
+    fn quality(&self, claim: &AnnilClaim) -> AudioQuality {
+        let quality = match claim {
+            AnnilClaim::User(_) => self.quality_requested.as_deref(),
+            // guests get low quality unless a higher one is signed in share token
+            AnnilClaim::Share(share) => Some(share.quality.as_deref().unwrap_or("low")),
+        };
+        AudioQuality::from_str(quality.unwrap_or("medium")).unwrap()
+    }
+}
+
+/// Whether `audio/ogg` or `audio/opus` appears before `audio/aac` in `Accept` header.
+///
+/// Quality values are ignored, media types are preferred in the order they appear.
+fn prefers_opus(accept: &str) -> bool {
+    accept
+        .split(',')
+        .map(|media| media.split(';').next().unwrap_or_default().trim())
+        .find_map(|media| match media {
+            "audio/ogg" | "audio/opus" => Some(true),
+            "audio/aac" => Some(false),
+            _ => None,
+        })
+        .unwrap_or(false)
+}
+
+/// Single byte range requested by `Range` header.
+#[derive(Debug, PartialEq)]
+enum RequestedRange {
+    /// `bytes=start-end`, or `bytes=start-` if end is omitted
+    FromStart(u64, Option<u64>),
+    /// `bytes=-length`, the last `length` bytes
+    Suffix(u64),
+}
+
+impl RequestedRange {
+    /// Parse `Range` header. Returns `None` for invalid or multiple ranges, which should be ignored.
+    fn parse(header: &str) -> Option<Self> {
+        let range = header.trim().strip_prefix("bytes=")?;
+        if range.contains(',') {
+            return None;
         }
+
+        let (start, end) = range.split_once('-')?;
+        let (start, end) = (start.trim(), end.trim());
+        if start.is_empty() {
+            return Some(RequestedRange::Suffix(end.parse().ok()?));
+        }
+
+        let start = start.parse().ok()?;
+        let end = match end {
+            "" => None,
+            end => Some(end.parse().ok()?),
+        };
+        if end.is_some_and(|end| end < start) {
+            return None;
+        }
+        Some(RequestedRange::FromStart(start, end))
     }
 
-    fn quality(&self, is_guest: bool) -> AudioQuality {
-        if is_guest {
-            return AudioQuality::Low;
+    /// Resolve range in a file of `size` bytes. Returns `None` if the range is not satisfiable.
+    fn resolve(&self, size: u64) -> Option<Range> {
+        if size == 0 {
+            return None;
         }
-        AudioQuality::from_str(self.quality_requested.as_deref().unwrap_or("medium")).unwrap()
+
+        let (start, end) = match *self {
+            RequestedRange::FromStart(start, _) if start >= size => return None,
+            RequestedRange::FromStart(start, end) => (start, end.unwrap_or(size - 1).min(size - 1)),
+            RequestedRange::Suffix(0) => return None,
+            RequestedRange::Suffix(length) => (size.saturating_sub(length), size - 1),
+        };
+        Some(Range {
+            start,
+            end: Some(end),
+            total: Some(size),
+        })
     }
 }
 
+/// `X-Duration-Seconds` header, which is omitted if duration of audio is unknown.
+fn duration_header(info: &AudioInfo) -> Option<[(&'static str, String); 1]> {
+    (info.duration > 0).then(|| [("X-Duration-Seconds", format!("{}", info.duration / 1000))])
+}
+
 pub async fn audio_head<P>(
     claim: AnnilClaim,
     track: TrackIdentifier,
     Extension(provider): Extension<Arc<AnnilProvider<P>>>,
     query: Query<AudioQuery>,
+    request_headers: HeaderMap,
 ) -> Response
 where
     P: AnniProvider + Send + Sync,
 {
     if !claim.can_fetch(&track) {
-        return AnnilError::Unauthorized.into_response();
+        return AnnilError::Forbidden.into_response();
     }
 
     let provider = provider.read().await;
@@ -115,7 +237,8 @@ where
         .await
         .map_err(|_| AnnilError::NotFound);
 
-    let transcoder = query.get_transcoder(claim.is_guest());
+    let source = query.source_format(&*provider, &track).await;
+    let transcoder = query.get_transcoder(&claim, &request_headers, source);
     let need_transcode = transcoder.need_transcode();
 
     return match audio {
@@ -137,10 +260,9 @@ where
             let custom_headers = [
                 ("X-Origin-Type", format!("audio/{}", info.extension)),
                 ("X-Origin-Size", format!("{}", info.size)),
-                ("X-Duration-Seconds", format!("{}", info.duration / 1000)),
                 (
                     "X-Audio-Quality",
-                    query.quality(claim.is_guest()).as_str().to_string(),
+                    query.quality(&claim).as_str().to_string(),
                 ),
             ];
 
@@ -155,7 +277,13 @@ where
                 transcode_headers.insert(ACCEPT_RANGES, "bytes".parse().unwrap());
             }
 
-            (headers, custom_headers, transcode_headers).into_response()
+            (
+                headers,
+                custom_headers,
+                duration_header(&info),
+                transcode_headers,
+            )
+                .into_response()
         }
         Err(e) => e.into_response(),
     };
@@ -167,114 +295,160 @@ pub async fn audio<P>(
     track: TrackIdentifier,
     Extension(provider): Extension<Arc<AnnilProvider<P>>>,
     query: Query<AudioQuery>,
+    transcode_cache: Option<Extension<Arc<TranscodeCache>>>,
+    NoCache(no_cache): NoCache,
     headers: HeaderMap,
 ) -> Response
 where
     P: AnniProvider + Send + Sync,
 {
     if !claim.can_fetch(&track) {
-        return AnnilError::Unauthorized.into_response();
+        return AnnilError::Forbidden.into_response();
     }
 
     let provider = provider.read().await;
     let album_id = track.album_id.to_string();
 
-    let range = headers.get("Range").and_then(|r| {
-        let range = r.to_str().ok()?;
-        let (_, right) = range.split_once('=')?;
-        let (from, to) = right.split_once('-')?;
-        let range = Range::new(from.parse().ok()?, to.parse().ok());
-        Some(if range.is_full() {
-            Range::new(0, Some(1023))
-        } else {
-            range
-        })
-    });
-    let need_range = range.is_some();
-    let range = range.unwrap_or(Range::FULL);
-
     if !provider.has_album(&album_id).await {
         return (StatusCode::NOT_FOUND, [(CACHE_CONTROL, "private")]).into_response();
     }
 
-    let transcoder = query.get_transcoder(claim.is_guest());
-    let need_range = need_range && !transcoder.need_transcode(); // Only support range if transcode is not performed
-
-    // range is only supported on lossless
-    #[cfg(feature = "transcode")]
-    let range = if transcoder.need_transcode() {
-        Range::FULL
-    } else {
-        range
+    let source = query.source_format(&*provider, &track).await;
+    let transcoder = query.get_transcoder(&claim, &headers, source);
+    // range is only supported if transcode is not performed
+    let accept_ranges = !transcoder.need_transcode();
+    let requested_range = headers
+        .get(RANGE)
+        .and_then(|r| r.to_str().ok())
+        .and_then(RequestedRange::parse)
+        .filter(|_| accept_ranges);
+
+    let range = match requested_range {
+        Some(requested_range) => {
+            let size = match provider
+                .get_audio_info(&album_id, track.disc_id, track.track_id)
+                .await
+            {
+                Ok(info) => info.size as u64,
+                Err(_) => return AnnilError::NotFound.into_response(),
+            };
+            match requested_range.resolve(size) {
+                Some(range) => Some(range),
+                None => {
+                    return (
+                        StatusCode::RANGE_NOT_SATISFIABLE,
+                        [
+                            (CONTENT_RANGE, format!("bytes */{size}")),
+                            (ACCEPT_RANGES, "bytes".to_string()),
+                        ],
+                    )
+                        .into_response()
+                }
+            }
+        }
+        None => None,
     };
+    let need_range = range.is_some();
+    let range = range.unwrap_or(Range::FULL);
 
-    let audio = provider
-        .get_audio(&album_id, track.disc_id, track.track_id, range)
-        .await
-        .map_err(|_| AnnilError::NotFound);
+    // transcoded audio is cached if transcode cache is configured
+    let transcode_cache = transcode_cache
+        .filter(|_| cfg!(feature = "transcode") && transcoder.need_transcode() && !no_cache);
+    let audio = match &transcode_cache {
+        // info of original audio is read separately, as cached audio is already transcoded
+        Some(Extension(cache)) => {
+            match provider
+                .get_audio_info(&album_id, track.disc_id, track.track_id)
+                .await
+            {
+                Ok(origin) => cache
+                    .transcode(
+                        &album_id,
+                        track.disc_id,
+                        track.track_id,
+                        transcoder.as_ref(),
+                        provider.get_audio(&album_id, track.disc_id, track.track_id, range),
+                    )
+                    .await
+                    .map(|audio| (origin, audio))
+                    .map_err(|e| match e {
+                        ProviderError::FileNotFound => AnnilError::NotFound,
+                        e => {
+                            log::error!("Failed to transcode audio: {e}");
+                            AnnilError::TranscodeFailed
+                        }
+                    }),
+                Err(_) => Err(AnnilError::NotFound),
+            }
+        }
+        None => if no_cache {
+            provider
+                .get_audio_no_cache(&album_id, track.disc_id, track.track_id, range)
+                .await
+        } else {
+            provider
+                .get_audio(&album_id, track.disc_id, track.track_id, range)
+                .await
+        }
+        .map(|audio| (audio.info.clone(), audio))
+        .map_err(|_| AnnilError::NotFound),
+    };
 
     return match audio {
-        Ok(audio) => {
-            let (status, range) = if need_range && !audio.range.is_full() {
+        Ok((origin, audio)) => {
+            let (status, range) = if need_range {
                 (
                     StatusCode::PARTIAL_CONTENT,
-                    Some([
-                        (CONTENT_RANGE, audio.range.to_content_range_header()),
-                        (ACCEPT_RANGES, "bytes".to_string()),
-                    ]),
+                    Some([(CONTENT_RANGE, audio.range.to_content_range_header())]),
                 )
             } else {
                 (StatusCode::OK, None)
             };
+            let accept_ranges = accept_ranges.then(|| [(ACCEPT_RANGES, "bytes")]);
 
             let header = [(
                 ACCESS_CONTROL_EXPOSE_HEADERS,
-                "X-Origin-Type, X-Origin-Size, X-Duration-Seconds, X-Audio-Quality".to_string(),
+                "X-Origin-Type, X-Origin-Size, X-Duration-Seconds, X-Audio-Quality, Accept-Ranges, Content-Range".to_string(),
             )];
 
+            let duration = duration_header(&origin);
             let headers = [
-                ("X-Origin-Type", format!("audio/{}", audio.info.extension)),
-                ("X-Origin-Size", format!("{}", audio.info.size)),
-                (
-                    "X-Duration-Seconds",
-                    format!("{}", audio.info.duration / 1000),
-                ),
+                ("X-Origin-Type", format!("audio/{}", origin.extension)),
+                ("X-Origin-Size", format!("{}", origin.size)),
                 (
                     "X-Audio-Quality",
-                    query.quality(claim.is_guest()).as_str().to_string(),
+                    query.quality(&claim).as_str().to_string(),
                 ),
             ];
 
             #[cfg(feature = "transcode")]
             use crate::utils::Either;
             #[cfg(feature = "transcode")]
-            let body = if transcoder.quality().need_transcode() {
+            let body = if transcoder.need_transcode() {
+                let audio = match transcode_cache {
+                    Some(_) => audio,
+                    None => transcode(transcoder.as_ref(), audio),
+                };
                 let mut transcode_headers = HeaderMap::new();
-                let info = audio.info.clone();
-                let mut process = transcoder.spawn();
-                let stdout = process.stdout.take().unwrap();
-                tokio::spawn(async move {
-                    let mut stdin = process.stdin.as_mut().unwrap();
-                    let mut audio = audio;
-                    let _ = tokio::io::copy(&mut audio.reader, &mut stdin).await;
-                });
                 transcode_headers.insert(
                     CONTENT_TYPE,
                     transcoder.content_type().to_string().parse().unwrap(),
                 );
-                if let Some(length) = transcoder.content_length(&info) {
+                if let Some(length) = transcoder.content_length(&origin) {
                     transcode_headers.insert(CONTENT_LENGTH, length.to_string().parse().unwrap());
                 }
 
-                if let Some(length) = transcoder.content_length(&info) {
+                if let Some(length) = transcoder.content_length(&origin) {
                     Either::Left((
                         transcode_headers,
-                        Either::Left(Body::from_stream(ReaderStream::new(stdout).take(length))),
+                        Either::Left(Body::from_stream(
+                            ReaderStream::new(audio.reader).take(length),
+                        )),
                     ))
                 } else {
                     Either::Left((
                         transcode_headers,
-                        Either::Right(Body::from_stream(ReaderStream::new(stdout))),
+                        Either::Right(Body::from_stream(ReaderStream::new(audio.reader))),
                     ))
                 }
             } else {
@@ -300,7 +474,16 @@ where
                 )
             };
 
-            (status, range, header, headers, body).into_response()
+            (
+                status,
+                range,
+                accept_ranges,
+                header,
+                headers,
+                duration,
+                body,
+            )
+                .into_response()
         }
         Err(e) => e.into_response(),
     };
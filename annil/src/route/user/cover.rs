@@ -7,6 +7,9 @@ use axum::Extension;
 use std::num::NonZeroU8;
 use std::sync::Arc;
 
+use crate::error::AnnilError;
+use crate::extractor::cache::NoCache;
+use crate::extractor::token::AnnilClaim;
 use crate::provider::AnnilProvider;
 use anni_provider::AnniProvider;
 use serde::Deserialize;
@@ -20,13 +23,23 @@ pub struct CoverPath {
 }
 
 /// Get audio cover of an album with {album_id} and optional {disc_id}
+///
+/// Covers are public, but requests with share token can only access albums shared.
 pub async fn cover<P>(
+    claim: Option<AnnilClaim>,
     Path(CoverPath { album_id, disc_id }): Path<CoverPath>,
     Extension(provider): Extension<Arc<AnnilProvider<P>>>,
+    NoCache(no_cache): NoCache,
 ) -> Response
 where
     P: AnniProvider + Send + Sync,
 {
+    if let Some(claim) = claim {
+        if !claim.can_access_album(&album_id) {
+            return AnnilError::Forbidden.into_response();
+        }
+    }
+
     let provider = provider.read().await;
     let album_id = album_id.to_string();
 
@@ -38,7 +51,14 @@ where
         Ok(cover) => (
             ([
                 (CONTENT_TYPE, "image/jpeg"),
-                (CACHE_CONTROL, "public, max-age=31536000"),
+                (
+                    CACHE_CONTROL,
+                    if no_cache {
+                        "no-cache"
+                    } else {
+                        "public, max-age=31536000"
+                    },
+                ),
             ]),
             Body::from_stream(ReaderStream::new(cover)),
         )
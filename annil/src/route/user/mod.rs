@@ -2,8 +2,12 @@ mod albums;
 mod audio;
 mod cover;
 mod info;
+#[cfg(feature = "metadata")]
+mod playlist;
 
 pub use albums::*;
 pub use audio::*;
 pub use cover::*;
 pub use info::*;
+#[cfg(feature = "metadata")]
+pub use playlist::*;
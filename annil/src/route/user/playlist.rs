@@ -0,0 +1,133 @@
+use crate::error::AnnilError;
+use crate::extractor::token::{AnnilClaim, ShareClaim};
+use crate::extractor::track::TrackIdentifier;
+use crate::provider::AnnilProvider;
+use crate::route::user::AudioQuality;
+use crate::state::{AnnilKeys, AnnilState};
+use anni_provider::AnniProvider;
+use anni_repo::db::RepoDatabaseRead;
+use axum::extract::{Path, Query};
+use axum::http::header::CACHE_CONTROL;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use jwt_simple::prelude::*;
+use std::collections::HashMap;
+use std::num::NonZeroU8;
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Lifetime of tokens signed in playlist
+const PLAYLIST_TOKEN_HOURS: u64 = 6;
+
+#[derive(Deserialize)]
+pub struct PlaylistQuery {
+    quality: Option<String>,
+    #[serde(default)]
+    opus: bool,
+}
+
+#[derive(Serialize)]
+pub struct PlaylistTrack {
+    disc_id: u8,
+    track_id: u8,
+    /// Path of the track relative to annil root, with quality parameters
+    path: String,
+    /// Share token which can only access this track
+    token: String,
+}
+
+/// Get signed urls of all tracks in an album with `album_id`
+pub async fn playlist<P>(
+    claim: AnnilClaim,
+    Path(album_id): Path<Uuid>,
+    Query(query): Query<PlaylistQuery>,
+    Extension(provider): Extension<Arc<AnnilProvider<P>>>,
+    Extension(data): Extension<Arc<AnnilState>>,
+    Extension(keys): Extension<Arc<AnnilKeys>>,
+) -> Response
+where
+    P: AnniProvider + Send + Sync,
+{
+    if !claim.can_access_album(&album_id) {
+        return AnnilError::Forbidden.into_response();
+    }
+
+    let provider = provider.read().await;
+    if !provider.has_album(&album_id.to_string()).await {
+        return (StatusCode::NOT_FOUND, [(CACHE_CONTROL, "private")]).into_response();
+    }
+
+    let Some(metadata) = &data.metadata else {
+        return AnnilError::NotFound.into_response();
+    };
+    let tracks = match RepoDatabaseRead::new(metadata.base.join("repo.db")).and_then(|db| {
+        let mut tracks = Vec::new();
+        for disc in db.get_discs(album_id)? {
+            tracks.extend(db.get_tracks(album_id, disc.disc_id)?);
+        }
+        Ok(tracks)
+    }) {
+        Ok(tracks) => tracks,
+        Err(e) => {
+            log::error!("Failed to read album {album_id} from metadata: {e:?}");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    if tracks.is_empty() {
+        return AnnilError::NotFound.into_response();
+    }
+
+    // guests always get low quality audio
+    let quality = if claim.is_guest() {
+        AudioQuality::Low
+    } else {
+        AudioQuality::from_str(query.quality.as_deref().unwrap_or("medium")).unwrap()
+    };
+    let mut parameters = format!("quality={}", quality.as_str());
+    if query.opus {
+        parameters.push_str("&opus=true");
+    }
+
+    let playlist: Vec<_> = tracks
+        .into_iter()
+        .filter_map(|track| {
+            let disc_id = NonZeroU8::new(track.disc_id)?;
+            let track_id = NonZeroU8::new(track.track_id)?;
+            let identifier = TrackIdentifier {
+                album_id,
+                disc_id,
+                track_id,
+            };
+            // guests can only get tracks shared to them
+            if !claim.can_fetch(&identifier) {
+                return None;
+            }
+
+            let share = Claims::with_custom_claims(
+                AnnilClaim::Share(ShareClaim {
+                    audios: HashMap::from([(
+                        album_id.to_string(),
+                        HashMap::from([(disc_id.to_string(), vec![track_id])]),
+                    )]),
+                    albums: None,
+                    quality: (!claim.is_guest()).then(|| quality.as_str().to_string()),
+                }),
+                Duration::from_hours(PLAYLIST_TOKEN_HOURS),
+            );
+            let token = keys
+                .share_key
+                .authenticate(share)
+                .expect("Failed to sign share token");
+            Some(PlaylistTrack {
+                disc_id: track.disc_id,
+                track_id: track.track_id,
+                path: format!("/{album_id}/{disc_id}/{track_id}?{parameters}"),
+                token,
+            })
+        })
+        .collect();
+
+    Json(playlist).into_response()
+}
@@ -0,0 +1,48 @@
+//! Minimal web player bundled with annil.
+//!
+//! Assets are embedded into the binary when the `webui` feature is enabled.
+//! Otherwise [index] responds with `404 Not Found`.
+
+use crate::error::AnnilError;
+use axum::response::{IntoResponse, Response};
+
+#[cfg(feature = "webui")]
+#[derive(rust_embed::RustEmbed)]
+#[folder = "webui"]
+struct WebUiAssets;
+
+/// Serve the web player at `/`
+#[cfg(feature = "webui")]
+pub async fn index() -> Response {
+    serve("index.html")
+}
+
+/// Web player is not bundled, always responds with `404 Not Found`
+#[cfg(not(feature = "webui"))]
+pub async fn index() -> Response {
+    AnnilError::NotFound.into_response()
+}
+
+/// Serve assets of the web player at `/webui/{path}`
+#[cfg(feature = "webui")]
+pub async fn asset(axum::extract::Path(path): axum::extract::Path<String>) -> Response {
+    serve(&path)
+}
+
+#[cfg(feature = "webui")]
+fn serve(path: &str) -> Response {
+    use axum::http::header::CONTENT_TYPE;
+
+    match WebUiAssets::get(path) {
+        Some(file) => {
+            let content_type = match path.rsplit_once('.').map(|(_, ext)| ext) {
+                Some("html") => "text/html; charset=utf-8",
+                Some("js") => "text/javascript; charset=utf-8",
+                Some("css") => "text/css; charset=utf-8",
+                _ => "application/octet-stream",
+            };
+            ([(CONTENT_TYPE, content_type)], file.data).into_response()
+        }
+        None => AnnilError::NotFound.into_response(),
+    }
+}
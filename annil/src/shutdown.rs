@@ -0,0 +1,115 @@
+use axum::extract::{Request, State};
+use axum::http::StatusCode;
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use std::future::{Future, IntoFuture};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+
+/// Draining state shared between the server and [reject_when_draining] middleware.
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    draining: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+}
+
+impl Shutdown {
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// Stop accepting new requests. Requests in flight are not affected.
+    pub fn start_draining(&self) {
+        self.draining.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    async fn drained(&self) {
+        let notified = self.notify.notified();
+        if !self.is_draining() {
+            notified.await;
+        }
+    }
+}
+
+/// Middleware rejecting new requests with `503 Service Unavailable` while the server is draining.
+pub async fn reject_when_draining(
+    State(shutdown): State<Shutdown>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if shutdown.is_draining() {
+        return StatusCode::SERVICE_UNAVAILABLE.into_response();
+    }
+    next.run(request).await
+}
+
+/// Completes when `SIGINT` or `SIGTERM` is received.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install SIGINT handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+/// Serve `app` until `signal` completes.
+///
+/// After `signal`, the listener is closed and new requests are rejected,
+/// while in-flight requests (e.g. audio streams) have `drain_timeout` to finish.
+pub async fn serve<F>(
+    listener: TcpListener,
+    app: Router,
+    signal: F,
+    drain_timeout: Duration,
+) -> std::io::Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let shutdown = Shutdown::default();
+    let app = app.layer(middleware::from_fn_with_state(
+        shutdown.clone(),
+        reject_when_draining,
+    ));
+
+    let server = axum::serve(listener, app).with_graceful_shutdown({
+        let shutdown = shutdown.clone();
+        async move {
+            signal.await;
+            log::info!(
+                "Shutting down, waiting at most {drain_timeout:?} for requests to finish..."
+            );
+            shutdown.start_draining();
+        }
+    });
+
+    tokio::select! {
+        result = server.into_future() => result,
+        _ = async {
+            shutdown.drained().await;
+            tokio::time::sleep(drain_timeout).await;
+        } => {
+            log::warn!("Drain timeout exceeded, closing remaining connections.");
+            Ok(())
+        }
+    }
+}
@@ -1,19 +1,39 @@
 use jwt_simple::prelude::HS256Key;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
 use tokio::sync::RwLock;
 
+/// Actions an admin token is allowed to perform
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum AdminScope {
+    /// Reload providers and metadata repository
+    Reload,
+    /// Sign user tokens and share tokens
+    Sign,
+}
+
+impl AdminScope {
+    pub fn all() -> HashSet<AdminScope> {
+        HashSet::from([AdminScope::Reload, AdminScope::Sign])
+    }
+}
+
 /// Readonly keys
 pub struct AnnilKeys {
     pub sign_key: HS256Key,
     pub share_key: HS256Key,
-    pub admin_token: String,
+    /// Admin tokens with their allowed scopes
+    pub admin_tokens: HashMap<String, HashSet<AdminScope>>,
 }
 
 impl AnnilKeys {
+    /// Create keys with a single admin token, which is allowed to perform all admin actions.
     pub fn new(sign_key: &[u8], share_key: &[u8], admin_token: String) -> Self {
         Self {
             sign_key: HS256Key::from_bytes(sign_key),
             share_key: HS256Key::from_bytes(share_key),
-            admin_token,
+            admin_tokens: HashMap::from([(admin_token, AdminScope::all())]),
         }
     }
 }
@@ -1,31 +1,80 @@
 use crate::{route::user::AudioQuality, utils::opus_file_size};
-use anni_provider::AudioInfo;
-use std::process::Stdio;
+use anni_flac::blocks::BlockStreamInfo;
+use anni_flac::prelude::Decode;
+use anni_provider::cache::{CacheConfig, CachePool};
+use anni_provider::{AudioInfo, AudioResourceReader, ProviderError, Range};
+use anni_split::codec::wav::WaveHeader;
+use anni_split::format::AudioFormat;
+use dashmap::DashMap;
+use serde::Deserialize;
+use std::future::Future;
+use std::io;
+use std::num::NonZeroU8;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::process::{ExitStatus, Stdio};
+use std::sync::Arc;
+use tokio::fs::File;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
 use tokio::process::Child;
+use tokio::task::JoinHandle;
+use tokio_util::io::SyncIoBridge;
 
 pub trait Transcode {
     fn content_type(&self) -> &'static str;
 
+    /// Identifier of transcoded output, which differs by codec, quality and target format.
+    ///
+    /// It's used to cache output of different transcoders separately.
+    fn variant(&self) -> String;
+
     fn quality(&self) -> AudioQuality;
 
     fn need_transcode(&self) -> bool {
         self.quality().need_transcode()
     }
 
+    /// Target format and format of source audio, if samples should be converted before encoding.
+    fn conversion(&self) -> Option<(AudioFormat, &WaveHeader)> {
+        None
+    }
+
     fn content_length(&self, info: &AudioInfo) -> Option<usize>;
 
+    /// Spawn the encoder, which reads source audio, or WAVE audio if [Transcode::conversion] is set, from stdin.
     fn spawn(&self) -> Child;
 }
 
-pub struct AacTranscoder(AudioQuality);
+/// Target format of samples, which is only kept if `source` is known and not in that format.
+fn conversion(
+    format: AudioFormat,
+    source: &Option<WaveHeader>,
+) -> Option<(AudioFormat, &WaveHeader)> {
+    match source {
+        Some(source) if !format.matches(source) => Some((format, source)),
+        _ => None,
+    }
+}
+
+pub struct AacTranscoder(AudioQuality, AudioFormat, Option<WaveHeader>);
 
 impl AacTranscoder {
-    pub fn new(quality: AudioQuality) -> Self {
+    /// Bit depth of `format` is ignored, as AAC does not store samples in integer.
+    ///
+    /// `format` is ignored if format of `source` is unknown.
+    pub fn new(quality: AudioQuality, format: AudioFormat, source: Option<WaveHeader>) -> Self {
         if let AudioQuality::Lossless = quality {
             panic!("AacTranscoder cannot be lossless");
         }
 
-        Self(quality)
+        Self(
+            quality,
+            AudioFormat {
+                bit_depth: None,
+                ..format
+            },
+            source,
+        )
     }
 }
 
@@ -34,10 +83,22 @@ impl Transcode for AacTranscoder {
         "audio/aac"
     }
 
+    fn variant(&self) -> String {
+        format!(
+            "aac-{}{}",
+            self.0.as_str(),
+            format_variant(self.conversion())
+        )
+    }
+
     fn quality(&self) -> AudioQuality {
         self.0
     }
 
+    fn conversion(&self) -> Option<(AudioFormat, &WaveHeader)> {
+        conversion(self.1, &self.2)
+    }
+
     fn spawn(&self) -> Child {
         let bitrate = match self.quality() {
             AudioQuality::Low => "128k",
@@ -47,9 +108,8 @@ impl Transcode for AacTranscoder {
         };
 
         tokio::process::Command::new("ffmpeg")
-            .args(&[
-                "-i", "pipe:0", "-map", "0:0", "-b:a", bitrate, "-f", "adts", "-",
-            ])
+            .args(&["-i", "pipe:0", "-map", "0:0", "-b:a", bitrate])
+            .args(&["-f", "adts", "-"])
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::null())
@@ -88,6 +148,10 @@ impl Transcode for OpusTranscoder {
         "audio/ogg"
     }
 
+    fn variant(&self) -> String {
+        format!("opus-{}", self.0.as_str())
+    }
+
     fn quality(&self) -> AudioQuality {
         self.0
     }
@@ -120,12 +184,17 @@ impl Transcode for OpusTranscoder {
     }
 }
 
-pub struct FlacTranscoder;
+/// Transcoder for lossless audio.
+///
+/// Audio is passed through unless it differs from the target [AudioFormat],
+/// in which case it is converted and re-encoded to FLAC by `ffmpeg`.
+pub struct FlacTranscoder(AudioFormat, Option<WaveHeader>);
 
 impl FlacTranscoder {
-    pub fn new(quality: AudioQuality) -> Self {
+    /// `format` is ignored if format of `source` is unknown.
+    pub fn new(quality: AudioQuality, format: AudioFormat, source: Option<WaveHeader>) -> Self {
         if let AudioQuality::Lossless = quality {
-            Self
+            Self(format, source)
         } else {
             panic!("FlacTranscoder can only be lossless");
         }
@@ -137,15 +206,319 @@ impl Transcode for FlacTranscoder {
         "audio/flac"
     }
 
+    fn variant(&self) -> String {
+        format!("flac{}", format_variant(self.conversion()))
+    }
+
     fn quality(&self) -> AudioQuality {
         AudioQuality::Lossless
     }
 
+    fn need_transcode(&self) -> bool {
+        self.conversion().is_some()
+    }
+
+    fn conversion(&self) -> Option<(AudioFormat, &WaveHeader)> {
+        conversion(self.0, &self.1)
+    }
+
     fn spawn(&self) -> Child {
-        panic!("FlacTranscoder cannot transcode")
+        if !self.need_transcode() {
+            panic!("FlacTranscoder cannot transcode without format conversion")
+        }
+
+        tokio::process::Command::new("ffmpeg")
+            .args(&["-i", "pipe:0", "-map", "0:0"])
+            .args(&["-c:a", "flac", "-f", "flac", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap()
     }
 
     fn content_length(&self, info: &AudioInfo) -> Option<usize> {
-        Some(info.size)
+        if self.need_transcode() {
+            None
+        } else {
+            Some(info.size)
+        }
+    }
+}
+
+/// Suffix of [Transcode::variant] for target format, which is empty if format is not changed.
+fn format_variant(conversion: Option<(AudioFormat, &WaveHeader)>) -> String {
+    let mut variant = String::new();
+    let Some((format, _)) = conversion else {
+        return variant;
+    };
+    if let Some(bit_depth) = format.bit_depth {
+        variant += &format!("-{bit_depth}bit");
+    }
+    if let Some(sample_rate) = format.sample_rate {
+        variant += &format!("-{sample_rate}hz");
+    }
+    if let Some(channels) = format.channels {
+        variant += &format!("-{channels}ch");
+    }
+    variant
+}
+
+/// Info of audio transcoded by `transcoder` from `source`, whose size is estimated by [Transcode::content_length].
+fn transcoded_info(transcoder: &dyn Transcode, source: &AudioInfo) -> AudioInfo {
+    AudioInfo {
+        extension: transcoder
+            .content_type()
+            .trim_start_matches("audio/")
+            .to_string(),
+        size: transcoder.content_length(source).unwrap_or(0),
+        duration: source.duration,
+    }
+}
+
+/// Spawn `transcoder`, and feed source `audio` to it in background.
+///
+/// The returned task fails if source audio can not be read or decoded completely.
+fn spawn(
+    transcoder: &dyn Transcode,
+    audio: AudioResourceReader,
+) -> (Child, JoinHandle<io::Result<()>>) {
+    let mut process = transcoder.spawn();
+    let mut stdin = process.stdin.take().unwrap();
+    let (mut source, decoder) = match transcoder.conversion() {
+        Some((format, header)) => {
+            let (source, decoder) = convert(format, header.clone(), audio.reader);
+            (source, Some(decoder))
+        }
+        None => (audio.reader, None),
+    };
+    let feed = tokio::spawn(async move {
+        let copied = tokio::io::copy(&mut source, &mut stdin).await;
+        // close stdin so that the transcoder can finish
+        drop(stdin);
+        copied?;
+        match decoder {
+            Some(decoder) => decoder.await?,
+            None => Ok(()),
+        }
+    });
+    (process, feed)
+}
+
+/// Transcode `audio` with `transcoder`.
+///
+/// Source audio is fed to the transcoder in background, and the returned reader reads the transcoded output.
+/// Size of the output is estimated by [Transcode::content_length], or 0 if it's unknown.
+///
+/// Failure of transcoding can not be reported once the output is being read, so it's only logged.
+pub fn transcode(transcoder: &dyn Transcode, audio: AudioResourceReader) -> AudioResourceReader {
+    let info = transcoded_info(transcoder, &audio.info);
+    let (mut process, feed) = spawn(transcoder, audio);
+    let stdout = process.stdout.take().unwrap();
+    tokio::spawn(async move {
+        if let Err(e) = wait(process, feed).await {
+            log::error!("Failed to transcode audio: {e}");
+        }
+    });
+
+    AudioResourceReader {
+        info,
+        range: Range::FULL,
+        reader: Box::pin(stdout),
+    }
+}
+
+/// Transcode `audio` with `transcoder`, and write the output to `path`.
+///
+/// Fails if the transcoder or the decoder exits unsuccessfully, in which case `path` is removed.
+pub async fn transcode_to_file(
+    transcoder: &dyn Transcode,
+    audio: AudioResourceReader,
+    path: &Path,
+) -> Result<AudioInfo, ProviderError> {
+    let mut info = transcoded_info(transcoder, &audio.info);
+    let (mut process, feed) = spawn(transcoder, audio);
+    let mut stdout = process.stdout.take().unwrap();
+    let result = async {
+        let mut file = File::create(path).await?;
+        let size = tokio::io::copy(&mut stdout, &mut file).await?;
+        file.flush().await?;
+        wait(process, feed).await?;
+        Ok::<_, io::Error>(size as usize)
+    }
+    .await;
+
+    match result {
+        Ok(size) => {
+            info.size = size;
+            Ok(info)
+        }
+        Err(e) => {
+            let _ = tokio::fs::remove_file(path).await;
+            Err(ProviderError::IOError(e))
+        }
+    }
+}
+
+/// Wait for `process` and the task `feed`ing it, and fail if either of them failed.
+async fn wait(mut process: Child, feed: JoinHandle<io::Result<()>>) -> io::Result<()> {
+    let status = process.wait().await?;
+    feed.await??;
+    exit_status(status, "transcoder")
+}
+
+fn exit_status(status: ExitStatus, name: &str) -> io::Result<()> {
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("{name} exited with {status}"),
+        ))
+    }
+}
+
+/// Read format of FLAC audio from the beginning of `audio`.
+///
+/// Returns `None` if `audio` is not a FLAC file, or its number of samples is unknown.
+pub async fn source_format(mut audio: AudioResourceReader) -> Option<WaveHeader> {
+    // magic number, metadata block header and STREAMINFO block
+    let mut header = [0u8; 42];
+    audio.reader.read_exact(&mut header).await.ok()?;
+    if &header[..4] != b"fLaC" || header[4] & 0x7f != 0 {
+        return None;
+    }
+
+    let info = BlockStreamInfo::from_reader(&mut &header[8..]).ok()?;
+    let block_align = info.channels as u16 * info.bits_per_sample.div_ceil(8) as u16;
+    Some(WaveHeader {
+        channels: info.channels as u16,
+        sample_rate: info.sample_rate,
+        byte_rate: info.sample_rate * block_align as u32,
+        block_align,
+        bit_per_sample: info.bits_per_sample as u16,
+        data_size: u32::try_from(info.total_samples * block_align as u64)
+            .ok()
+            .filter(|size| *size > 0)?,
+    })
+}
+
+/// Decode FLAC audio `source` to PCM by `ffmpeg`, and convert it to `format` by [AudioFormat::pcm_converter].
+///
+/// Returns a reader of the converted WAVE audio, and a task which fails if decoding failed.
+fn convert(
+    format: AudioFormat,
+    header: WaveHeader,
+    mut source: Pin<Box<dyn AsyncRead + Send>>,
+) -> (Pin<Box<dyn AsyncRead + Send>>, JoinHandle<io::Result<()>>) {
+    let sample_format = match header.bit_per_sample {
+        8 => "u8",
+        16 => "s16le",
+        24 => "s24le",
+        _ => "s32le",
+    };
+    let mut decoder = tokio::process::Command::new("ffmpeg")
+        .args(&["-i", "pipe:0", "-map", "0:0", "-f", sample_format, "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let decoded = SyncIoBridge::new(decoder.stdout.take().unwrap());
+    let mut stdin = decoder.stdin.take().unwrap();
+    let decoded_all = tokio::spawn(async move {
+        let copied = tokio::io::copy(&mut source, &mut stdin).await;
+        drop(stdin);
+        let status = decoder.wait().await?;
+        copied?;
+        exit_status(status, "decoder")
+    });
+
+    let (writer, reader) = tokio::io::duplex(64 * 1024);
+    let mut writer = SyncIoBridge::new(writer);
+    tokio::task::spawn_blocking(move || match format.pcm_converter(header, decoded) {
+        Ok(mut converter) => {
+            let _ = std::io::copy(&mut converter, &mut writer);
+        }
+        Err(e) => log::error!("Failed to convert audio: {e}"),
+    });
+    (Box::pin(reader), decoded_all)
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct TranscodeCacheConfig {
+    /// Directory to store transcoded audio
+    pub root: PathBuf,
+    /// Maximum space used by each variant of transcoded audio
+    pub max_size: Option<usize>,
+    /// Store identical transcoded audio only once
+    #[serde(default)]
+    pub dedup: bool,
+}
+
+/// Cache of transcoded audio, keyed by track and [Transcode::variant].
+///
+/// Each variant is stored in a separate [CachePool] under `root`.
+pub struct TranscodeCache {
+    config: TranscodeCacheConfig,
+    pools: DashMap<String, Arc<CachePool>>,
+}
+
+impl TranscodeCache {
+    pub fn new(config: TranscodeCacheConfig) -> Self {
+        Self {
+            config,
+            pools: Default::default(),
+        }
+    }
+
+    fn pool(&self, variant: String) -> Arc<CachePool> {
+        self.pools
+            .entry(variant)
+            .or_insert_with_key(|variant| {
+                Arc::new(CachePool::with_config(
+                    self.config.root.join(variant),
+                    CacheConfig {
+                        max_size: self.config.max_size,
+                        dedup: self.config.dedup,
+                    },
+                ))
+            })
+            .clone()
+    }
+
+    /// Remove all cached audio, which is called on reload as source audio might have changed.
+    pub async fn purge(&self) {
+        let pools: Vec<_> = self.pools.iter().map(|pool| pool.value().clone()).collect();
+        for pool in pools {
+            pool.purge().await;
+        }
+    }
+
+    /// Get audio transcoded by `transcoder`, which is transcoded and cached on the first request.
+    ///
+    /// `audio` is only awaited if transcoded output is not cached. It should resolve to the full source audio.
+    /// Output is written to a temporary file first, which is moved into cache only if transcoding succeeded.
+    pub async fn transcode(
+        &self,
+        album_id: &str,
+        disc_id: NonZeroU8,
+        track_id: NonZeroU8,
+        transcoder: &(dyn Transcode + Send + Sync),
+        audio: impl Future<Output = Result<AudioResourceReader, ProviderError>>,
+    ) -> Result<AudioResourceReader, ProviderError> {
+        let variant = transcoder.variant();
+        let pool = self.pool(variant.clone());
+        pool.fetch_audio_file(album_id, disc_id, track_id, Range::FULL, async {
+            // the cache pool prevents concurrent transcoding of the same track
+            let root = self.config.root.join(&variant);
+            tokio::fs::create_dir_all(&root).await?;
+            let path = root.join(format!("{album_id}_{disc_id}_{track_id}.tmp"));
+            let info = transcode_to_file(transcoder, audio.await?, &path).await?;
+            Ok((path, info))
+        })
+        .await
     }
 }
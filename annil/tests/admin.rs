@@ -0,0 +1,86 @@
+use anni_provider::providers::MultipleProviders;
+use annil::provider::AnnilProvider;
+use annil::route::admin;
+use annil::state::{AdminScope, AnnilKeys};
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use axum::routing::post;
+use axum::{Extension, Router};
+use jwt_simple::prelude::HS256Key;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+mod common;
+
+fn app(keys: AnnilKeys) -> Router {
+    type Provider = MultipleProviders;
+    let state = common::state(None);
+    let provider = AnnilProvider::new(MultipleProviders::new(vec![]));
+
+    Router::new()
+        .route("/admin/sign", post(admin::sign))
+        .route("/admin/share", post(admin::share))
+        .route("/admin/reload", post(admin::reload::<Provider>))
+        .layer(Extension(Arc::new(state)))
+        .layer(Extension(Arc::new(provider)))
+        .layer(Extension(Arc::new(keys)))
+}
+
+async fn post_status(app: &Router, uri: &str, token: &str, body: &'static str) -> StatusCode {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header(header::AUTHORIZATION, token)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap().status()
+}
+
+async fn sign(app: &Router, token: &str) -> StatusCode {
+    post_status(app, "/admin/sign", token, r#"{"user_id":"test"}"#).await
+}
+
+async fn share(app: &Router, token: &str) -> StatusCode {
+    post_status(app, "/admin/share", token, r#"{"albums":[]}"#).await
+}
+
+async fn reload(app: &Router, token: &str) -> StatusCode {
+    post_status(app, "/admin/reload", token, "").await
+}
+
+#[tokio::test]
+async fn test_admin_scopes() {
+    let app = app(AnnilKeys {
+        sign_key: HS256Key::from_bytes(b"sign key"),
+        share_key: HS256Key::from_bytes(b"share key").with_key_id("share"),
+        admin_tokens: HashMap::from([
+            ("signer".to_string(), HashSet::from([AdminScope::Sign])),
+            ("reloader".to_string(), HashSet::from([AdminScope::Reload])),
+        ]),
+    });
+
+    assert_eq!(sign(&app, "signer").await, StatusCode::OK);
+    assert_eq!(share(&app, "signer").await, StatusCode::OK);
+    assert_eq!(reload(&app, "signer").await, StatusCode::FORBIDDEN);
+
+    assert_eq!(sign(&app, "reloader").await, StatusCode::FORBIDDEN);
+    assert_eq!(share(&app, "reloader").await, StatusCode::FORBIDDEN);
+    assert_eq!(reload(&app, "reloader").await, StatusCode::OK);
+
+    assert_eq!(sign(&app, "unknown").await, StatusCode::UNAUTHORIZED);
+    assert_eq!(reload(&app, "unknown").await, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_single_admin_token_has_all_scopes() {
+    let app = app(AnnilKeys::new(
+        b"sign key",
+        b"share key",
+        "admin".to_string(),
+    ));
+
+    assert_eq!(sign(&app, "admin").await, StatusCode::OK);
+    assert_eq!(reload(&app, "admin").await, StatusCode::OK);
+}
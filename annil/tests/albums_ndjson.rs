@@ -0,0 +1,75 @@
+#![cfg(feature = "metadata")]
+
+use anni_repo::RepositoryManager;
+use annil::metadata::MetadataConfig;
+use annil::route::user;
+use axum::body::{to_bytes, Body};
+use axum::http::{header, Request, StatusCode};
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use jwt_simple::reexports::serde_json::{self, Value};
+use tower::ServiceExt;
+
+mod common;
+
+fn app(metadata: Option<MetadataConfig>) -> (Router, String) {
+    let router = Router::new().route("/albums.ndjson", get(user::albums_ndjson));
+    common::with_state(router, metadata)
+}
+
+async fn get_albums(app: Router, token: Option<&str>) -> Response {
+    let mut request = Request::builder().uri("/albums.ndjson");
+    if let Some(token) = token {
+        request = request.header(header::AUTHORIZATION, token);
+    }
+    app.oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_albums_ndjson() {
+    let base = tempfile::tempdir().unwrap();
+    let repo = RepositoryManager::new("../anni-repo/tests/repos/editions")
+        .unwrap()
+        .into_owned_manager()
+        .unwrap();
+    let album_count = repo.albums().len();
+    repo.to_database(&base.path().join("repo.db")).unwrap();
+
+    let (app, token) = app(Some(MetadataConfig {
+        repo: String::new(),
+        branch: "master".to_string(),
+        base: base.path().to_path_buf(),
+        pull: false,
+        proxy: None,
+    }));
+    let response = get_albums(app, Some(&token)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "application/x-ndjson"
+    );
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+    let lines: Vec<_> = body.lines().collect();
+    assert_eq!(lines.len(), album_count);
+    for line in lines {
+        let album: Value = serde_json::from_str(line).unwrap();
+        assert!(album.is_object());
+        assert!(album["album_id"].is_string());
+        assert!(album["discs"].is_array());
+    }
+}
+
+#[tokio::test]
+async fn test_albums_ndjson_requires_auth_and_metadata() {
+    let (app, token) = app(None);
+    let response = get_albums(app.clone(), None).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = get_albums(app, Some(&token)).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
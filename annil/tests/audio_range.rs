@@ -0,0 +1,129 @@
+use anni_provider::providers::NoCacheStrictLocalProvider;
+use annil::route::user;
+use axum::body::{to_bytes, Body};
+use axum::http::{header, Request, StatusCode};
+use axum::response::Response;
+use axum::routing::get;
+use axum::{Extension, Router};
+use std::sync::Arc;
+use tempfile::TempDir;
+use tower::ServiceExt;
+
+mod common;
+
+const ALBUM_ID: &str = "33333333-3333-4333-8333-333333333333";
+
+/// Create an app serving `1s.flac` as the first track of [ALBUM_ID], with a user token.
+fn app() -> (TempDir, Router, String, Vec<u8>) {
+    type Provider = NoCacheStrictLocalProvider;
+
+    let root = tempfile::tempdir().unwrap();
+    common::write_album(root.path(), ALBUM_ID, 1);
+    let audio = std::fs::read(root.path().join(ALBUM_ID).join("1/1.flac")).unwrap();
+
+    let router = Router::new()
+        .route(
+            "/:album_id/:disc_id/:track_id",
+            get(user::audio::<Provider>),
+        )
+        .layer(Extension(Arc::new(common::local_provider(root.path()))));
+    let (router, token) = common::with_state(router, None);
+    (root, router, token, audio)
+}
+
+async fn get_audio(app: &Router, token: &str, range: Option<&str>) -> Response {
+    let mut request = Request::builder()
+        .uri(format!("/{ALBUM_ID}/1/1?quality=lossless"))
+        .header(header::AUTHORIZATION, token);
+    if let Some(range) = range {
+        request = request.header(header::RANGE, range);
+    }
+    app.clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+fn header_value<'a>(response: &'a Response, name: header::HeaderName) -> Option<&'a str> {
+    response.headers().get(name).map(|v| v.to_str().unwrap())
+}
+
+async fn body(response: Response) -> Vec<u8> {
+    to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap()
+        .to_vec()
+}
+
+#[tokio::test]
+async fn test_audio_without_range() {
+    let (_root, app, token, audio) = app();
+
+    let response = get_audio(&app, &token, None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        header_value(&response, header::ACCEPT_RANGES),
+        Some("bytes")
+    );
+    assert_eq!(header_value(&response, header::CONTENT_RANGE), None);
+    assert_eq!(body(response).await, audio);
+}
+
+#[tokio::test]
+async fn test_audio_range() {
+    let (_root, app, token, audio) = app();
+    let size = audio.len();
+
+    // closed range
+    let response = get_audio(&app, &token, Some("bytes=100-199")).await;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(
+        header_value(&response, header::CONTENT_RANGE),
+        Some(format!("bytes 100-199/{size}").as_str())
+    );
+    assert_eq!(header_value(&response, header::CONTENT_LENGTH), Some("100"));
+    assert_eq!(body(response).await, audio[100..200]);
+
+    // open-ended range
+    let response = get_audio(&app, &token, Some("bytes=100-")).await;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(
+        header_value(&response, header::CONTENT_RANGE),
+        Some(format!("bytes 100-{}/{size}", size - 1).as_str())
+    );
+    assert_eq!(body(response).await, audio[100..]);
+
+    // suffix range
+    let response = get_audio(&app, &token, Some("bytes=-50")).await;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(
+        header_value(&response, header::CONTENT_RANGE),
+        Some(format!("bytes {}-{}/{size}", size - 50, size - 1).as_str())
+    );
+    assert_eq!(body(response).await, audio[size - 50..]);
+
+    // end of range exceeds file size
+    let response = get_audio(&app, &token, Some(&format!("bytes=10-{}", size + 100))).await;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(body(response).await, audio[10..]);
+}
+
+#[tokio::test]
+async fn test_audio_range_not_satisfiable() {
+    let (_root, app, token, audio) = app();
+    let size = audio.len();
+
+    for range in [format!("bytes={size}-"), "bytes=-0".to_string()] {
+        let response = get_audio(&app, &token, Some(&range)).await;
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(
+            header_value(&response, header::CONTENT_RANGE),
+            Some(format!("bytes */{size}").as_str())
+        );
+    }
+
+    // invalid range header is ignored
+    let response = get_audio(&app, &token, Some("bytes=200-100")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body(response).await, audio);
+}
@@ -0,0 +1,87 @@
+//! Shared fixture of annil tests.
+#![allow(dead_code)]
+
+use anni_provider::providers::NoCacheStrictLocalProvider;
+use annil::metadata::MetadataConfig;
+use annil::provider::AnnilProvider;
+use annil::state::{AnnilKeys, AnnilState};
+use axum::{Extension, Router};
+use jwt_simple::prelude::*;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// User id of the token returned by [with_state].
+pub const USER_ID: &str = "test";
+
+#[derive(Serialize)]
+pub struct UserClaim {
+    pub r#type: &'static str,
+    pub user_id: &'static str,
+}
+
+/// Keys of a server without admin tokens.
+pub fn keys() -> AnnilKeys {
+    AnnilKeys {
+        sign_key: HS256Key::from_bytes(b"sign key"),
+        share_key: HS256Key::from_bytes(b"share key").with_key_id("share"),
+        admin_tokens: HashMap::new(),
+    }
+}
+
+/// Sign a user token of `user_id` with `keys`, which expires in an hour.
+pub fn user_token(keys: &AnnilKeys, user_id: &'static str) -> String {
+    keys.sign_key
+        .authenticate(Claims::with_custom_claims(
+            UserClaim {
+                r#type: "user",
+                user_id,
+            },
+            Duration::from_hours(1),
+        ))
+        .unwrap()
+}
+
+/// State of a server which has never been reloaded.
+pub fn state(metadata: Option<MetadataConfig>) -> AnnilState {
+    AnnilState {
+        version: "test".to_string(),
+        last_update: RwLock::new(0),
+        etag: RwLock::new(String::new()),
+        metadata,
+    }
+}
+
+/// Add [state] with `metadata` and [keys] to `router`.
+///
+/// Returns the router, and a user token of [USER_ID].
+pub fn with_state(router: Router, metadata: Option<MetadataConfig>) -> (Router, String) {
+    let keys = keys();
+    let token = user_token(&keys, USER_ID);
+    let router = router
+        .layer(Extension(Arc::new(state(metadata))))
+        .layer(Extension(Arc::new(keys)));
+    (router, token)
+}
+
+/// Copy `1s.flac` to `root` as the first `tracks` tracks of the first disc of `album_id`.
+///
+/// Returns the size of each track.
+pub fn write_album(root: &Path, album_id: &str, tracks: u8) -> u64 {
+    let disc = root.join(album_id).join("1");
+    std::fs::create_dir_all(&disc).unwrap();
+    let mut size = 0;
+    for track in 1..=tracks {
+        size = std::fs::copy("../assets/1s.flac", disc.join(format!("{track}.flac"))).unwrap();
+    }
+    size
+}
+
+/// Provider serving audio files in `root`.
+pub fn local_provider(root: &Path) -> AnnilProvider<NoCacheStrictLocalProvider> {
+    AnnilProvider::new(NoCacheStrictLocalProvider {
+        root: root.to_path_buf(),
+        layer: 0,
+    })
+}
@@ -0,0 +1,206 @@
+use anni_provider::providers::NoCacheStrictLocalProvider;
+use annil::route::user;
+use axum::body::{to_bytes, Body};
+use axum::http::{header, Method, Request, StatusCode};
+use axum::response::Response;
+use axum::routing::get;
+use axum::{Extension, Router};
+use jwt_simple::reexports::serde_json::{self, json, Value};
+use std::sync::Arc;
+use tempfile::TempDir;
+use tower::ServiceExt;
+
+mod common;
+
+const ALBUM_ID: &str = "44444444-4444-4444-8444-444444444444";
+
+/// Create an app serving `1s.flac` as the first two tracks of [ALBUM_ID], with a user token.
+fn app() -> (TempDir, Router, String, u64) {
+    type Provider = NoCacheStrictLocalProvider;
+
+    let root = tempfile::tempdir().unwrap();
+    let size = common::write_album(root.path(), ALBUM_ID, 2);
+
+    let router = Router::new()
+        .route("/albums", get(user::albums::<Provider>))
+        .route(
+            "/:album_id/:disc_id/:track_id",
+            get(user::audio::<Provider>).head(user::audio_head::<Provider>),
+        )
+        .layer(Extension(Arc::new(common::local_provider(root.path()))));
+    let (router, token) = common::with_state(router, None);
+    (root, router, token, size)
+}
+
+fn request(token: &str, method: Method, uri: &str) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::AUTHORIZATION, token)
+        .body(Body::empty())
+        .unwrap()
+}
+
+async fn send(app: &Router, request: Request<Body>) -> Response {
+    app.clone().oneshot(request).await.unwrap()
+}
+
+fn header_value<'a>(response: &'a Response, name: &str) -> Option<&'a str> {
+    response.headers().get(name).map(|v| v.to_str().unwrap())
+}
+
+#[tokio::test]
+async fn test_audio_head_duration_and_length() {
+    let (_root, app, token, size) = app();
+
+    let uri = format!("/{ALBUM_ID}/1/1?quality=lossless");
+    let response = send(&app, request(&token, Method::HEAD, &uri)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header_value(&response, "X-Duration-Seconds"), Some("1"));
+    assert_eq!(
+        header_value(&response, "Content-Length"),
+        Some(size.to_string().as_str())
+    );
+}
+
+#[tokio::test]
+async fn test_audio_range_duration() {
+    let (_root, app, token, _) = app();
+
+    let uri = format!("/{ALBUM_ID}/1/1?quality=lossless");
+    let mut request = request(&token, Method::GET, &uri);
+    request
+        .headers_mut()
+        .insert(header::RANGE, "bytes=100-199".parse().unwrap());
+    let response = send(&app, request).await;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    // STREAMINFO is not in range, but duration is still known
+    assert_eq!(header_value(&response, "X-Duration-Seconds"), Some("1"));
+}
+
+async fn get_json(app: &Router, token: &str, uri: &str) -> Value {
+    let response = send(app, request(token, Method::GET, uri)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn test_albums_with_duration() {
+    let (_root, app, token, _) = app();
+
+    let albums = get_json(&app, &token, "/albums").await;
+    assert_eq!(albums, json!([ALBUM_ID]));
+
+    let albums = get_json(&app, &token, "/albums?duration=1").await;
+    assert_eq!(albums, json!({ ALBUM_ID: [[1000, 1000]] }));
+}
+
+#[tokio::test]
+async fn test_albums_with_duration_etag() {
+    let (_root, app, token, _) = app();
+
+    let response = send(&app, request(&token, Method::GET, "/albums")).await;
+    let plain = header_value(&response, "ETag").unwrap().to_string();
+    let response = send(&app, request(&token, Method::GET, "/albums?duration=1")).await;
+    let duration = header_value(&response, "ETag").unwrap().to_string();
+    assert_ne!(plain, duration);
+
+    // ETag of plain list does not match the list with durations
+    let mut request = request(&token, Method::GET, "/albums?duration=1");
+    request
+        .headers_mut()
+        .insert(header::IF_NONE_MATCH, plain.parse().unwrap());
+    assert_eq!(send(&app, request).await.status(), StatusCode::OK);
+
+    let mut request = request(&token, Method::GET, "/albums?duration=1");
+    request
+        .headers_mut()
+        .insert(header::IF_NONE_MATCH, duration.parse().unwrap());
+    assert_eq!(send(&app, request).await.status(), StatusCode::NOT_MODIFIED);
+}
+
+#[tokio::test]
+async fn test_albums_with_duration_cached() {
+    let (root, app, token, _) = app();
+
+    let albums = get_json(&app, &token, "/albums?duration=1").await;
+    assert_eq!(albums, json!({ ALBUM_ID: [[1000, 1000]] }));
+
+    // durations are not probed again until reload
+    std::fs::remove_file(root.path().join(ALBUM_ID).join("1").join("2.flac")).unwrap();
+    let albums = get_json(&app, &token, "/albums?duration=1").await;
+    assert_eq!(albums, json!({ ALBUM_ID: [[1000, 1000]] }));
+}
+
+#[cfg(feature = "metadata")]
+#[tokio::test]
+async fn test_albums_with_duration_gap() {
+    use annil::metadata::MetadataConfig;
+
+    type Provider = NoCacheStrictLocalProvider;
+
+    // metadata repository has 3 tracks, while track 2 is missing in provider
+    let repo = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(repo.path().join("album")).unwrap();
+    std::fs::create_dir_all(repo.path().join("tag")).unwrap();
+    std::fs::write(
+        repo.path().join("repo.toml"),
+        "[repo]\nname = \"Test\"\nedition = \"1.0+alpha.1.5.1\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        repo.path().join("album").join("album.toml"),
+        format!(
+            r#"[album]
+album_id = "{ALBUM_ID}"
+title = "Title"
+artist = "Artist"
+date = 2999-12-31
+type = "normal"
+catalog = "TEST-0001"
+
+[[discs]]
+catalog = "TEST-0001"
+
+[[discs.tracks]]
+title = "Track 1"
+
+[[discs.tracks]]
+title = "Track 2"
+
+[[discs.tracks]]
+title = "Track 3"
+"#
+        ),
+    )
+    .unwrap();
+    let base = tempfile::tempdir().unwrap();
+    anni_repo::RepositoryManager::new(repo.path())
+        .unwrap()
+        .into_owned_manager()
+        .unwrap()
+        .to_database(&base.path().join("repo.db"))
+        .unwrap();
+
+    let root = tempfile::tempdir().unwrap();
+    common::write_album(root.path(), ALBUM_ID, 3);
+    std::fs::remove_file(root.path().join(ALBUM_ID).join("1").join("2.flac")).unwrap();
+
+    let router = Router::new()
+        .route("/albums", get(user::albums::<Provider>))
+        .layer(Extension(Arc::new(common::local_provider(root.path()))));
+    let (app, token) = common::with_state(
+        router,
+        Some(MetadataConfig {
+            repo: String::new(),
+            branch: "master".to_string(),
+            base: base.path().to_path_buf(),
+            pull: false,
+            proxy: None,
+        }),
+    );
+
+    let albums = get_json(&app, &token, "/albums?duration=1").await;
+    assert_eq!(albums, json!({ ALBUM_ID: [[1000, null, 1000]] }));
+}
@@ -0,0 +1,145 @@
+use anni_provider::{AnniProvider, AudioResourceReader, ProviderError, Range, ResourceReader};
+use annil::metadata::MetadataConfig;
+use annil::provider::{AnnilProvider, ProviderHealth, ProviderStatus};
+use annil::route::health;
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use axum::routing::get;
+use axum::{Extension, Router};
+use jwt_simple::reexports::serde_json::{self, json, Value};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::num::NonZeroU8;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+mod common;
+
+struct EmptyProvider;
+
+#[async_trait::async_trait]
+impl AnniProvider for EmptyProvider {
+    async fn albums(&self) -> anni_provider::Result<HashSet<Cow<str>>> {
+        Ok(HashSet::new())
+    }
+
+    async fn get_audio(
+        &self,
+        _album_id: &str,
+        _disc_id: NonZeroU8,
+        _track_id: NonZeroU8,
+        _range: Range,
+    ) -> anni_provider::Result<AudioResourceReader> {
+        Err(ProviderError::FileNotFound)
+    }
+
+    async fn get_cover(
+        &self,
+        _album_id: &str,
+        _disc_id: Option<NonZeroU8>,
+    ) -> anni_provider::Result<ResourceReader> {
+        Err(ProviderError::FileNotFound)
+    }
+
+    async fn reload(&self) -> anni_provider::Result<()> {
+        Ok(())
+    }
+}
+
+fn app(statuses: Vec<ProviderStatus>, metadata: Option<MetadataConfig>) -> Router {
+    let provider_health = ProviderHealth::default();
+    provider_health.set(statuses);
+    let provider = AnnilProvider::new(EmptyProvider).with_health(provider_health);
+    let state = common::state(metadata);
+
+    Router::new()
+        .route("/healthz", get(health::healthz))
+        .route("/readyz", get(health::readyz::<EmptyProvider>))
+        .layer(Extension(Arc::new(state)))
+        .layer(Extension(Arc::new(provider)))
+}
+
+/// Request `uri` without any token, and returns status and body.
+async fn get_json(app: Router, uri: &str) -> (StatusCode, Value) {
+    let response = app
+        .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn test_ready() {
+    let app = app(vec![ProviderStatus::ready("local")], None);
+
+    let (status, _) = get_json(app.clone(), "/healthz").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = get_json(app, "/readyz").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body,
+        json!({
+            "ready": true,
+            "providers": [{ "name": "local", "ready": true }],
+            "metadata": null,
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_not_ready_if_provider_failed() {
+    let app = app(
+        vec![
+            ProviderStatus::ready("local"),
+            ProviderStatus::failed("drive", "invalid token"),
+        ],
+        None,
+    );
+
+    // process is still alive
+    let (status, _) = get_json(app.clone(), "/healthz").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = get_json(app, "/readyz").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["ready"], false);
+    assert_eq!(
+        body["providers"][1],
+        json!({ "name": "drive", "ready": false, "error": "invalid token" })
+    );
+}
+
+#[cfg(feature = "metadata")]
+#[tokio::test]
+async fn test_not_ready_if_metadata_unreachable() {
+    let base = tempfile::tempdir().unwrap();
+    let app = app(
+        vec![ProviderStatus::ready("local")],
+        Some(MetadataConfig {
+            repo: String::new(),
+            branch: "master".to_string(),
+            // repo.db does not exist
+            base: base.path().to_path_buf(),
+            pull: false,
+            proxy: None,
+        }),
+    );
+
+    let (status, body) = get_json(app.clone(), "/readyz").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["metadata"], false);
+
+    // result is reused by probes shortly after, without opening the database again
+    anni_repo::RepositoryManager::new("../anni-repo/tests/repos/editions")
+        .unwrap()
+        .into_owned_manager()
+        .unwrap()
+        .to_database(&base.path().join("repo.db"))
+        .unwrap();
+    let (status, body) = get_json(app, "/readyz").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["metadata"], false);
+}
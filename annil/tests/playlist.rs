@@ -0,0 +1,140 @@
+#![cfg(feature = "metadata")]
+
+use anni_provider::providers::NoCacheStrictLocalProvider;
+use anni_repo::RepositoryManager;
+use annil::metadata::MetadataConfig;
+use annil::route::user;
+use axum::body::{to_bytes, Body};
+use axum::http::{header, Request, StatusCode};
+use axum::response::Response;
+use axum::routing::get;
+use axum::{Extension, Router};
+use jwt_simple::reexports::serde_json::{self, Value};
+use std::sync::Arc;
+use tempfile::TempDir;
+use tower::ServiceExt;
+
+mod common;
+
+const ALBUM_ID: &str = "44444444-4444-4444-8444-444444444444";
+const MISSING_ALBUM_ID: &str = "55555555-5555-4555-8555-555555555555";
+
+const REPO: &str = r#"[repo]
+name = "Playlist test"
+edition = "1.0+alpha.1.5.1"
+"#;
+
+const ALBUM: &str = r#"[album]
+album_id = "44444444-4444-4444-8444-444444444444"
+title = "Playlist"
+artist = "Artist"
+date = 2999-12-31
+type = "normal"
+catalog = "TEST-0002"
+
+[[discs]]
+catalog = "TEST-0002"
+
+[[discs.tracks]]
+title = "Track 1"
+
+[[discs.tracks]]
+title = "Track 2"
+
+[[discs]]
+catalog = "TEST-0003"
+
+[[discs.tracks]]
+title = "Track 3"
+"#;
+
+/// Create an app with metadata of [ALBUM_ID] and the first track of it, with a user token.
+fn app() -> (TempDir, Router, String) {
+    type Provider = NoCacheStrictLocalProvider;
+
+    let root = tempfile::tempdir().unwrap();
+    let repo = root.path().join("metadata/repo");
+    std::fs::create_dir_all(repo.join("album")).unwrap();
+    std::fs::create_dir_all(repo.join("tag")).unwrap();
+    std::fs::write(repo.join("repo.toml"), REPO).unwrap();
+    std::fs::write(repo.join("album/playlist.toml"), ALBUM).unwrap();
+    RepositoryManager::new(&repo)
+        .unwrap()
+        .into_owned_manager()
+        .unwrap()
+        .to_database(root.path().join("metadata/repo.db"))
+        .unwrap();
+
+    let audio = root.path().join("audio");
+    common::write_album(&audio, ALBUM_ID, 1);
+
+    let router = Router::new()
+        .route("/playlist/:album_id", get(user::playlist::<Provider>))
+        .route(
+            "/:album_id/:disc_id/:track_id",
+            get(user::audio::<Provider>),
+        )
+        .layer(Extension(Arc::new(common::local_provider(&audio))));
+    let (router, token) = common::with_state(
+        router,
+        Some(MetadataConfig {
+            repo: String::new(),
+            branch: "master".to_string(),
+            base: root.path().join("metadata"),
+            pull: false,
+            proxy: None,
+        }),
+    );
+    (root, router, token)
+}
+
+async fn request(app: &Router, uri: &str, token: &str) -> Response {
+    let request = Request::builder()
+        .uri(uri)
+        .header(header::AUTHORIZATION, token)
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_playlist() {
+    let (_root, app, token) = app();
+
+    let response = request(
+        &app,
+        &format!("/playlist/{ALBUM_ID}?quality=lossless"),
+        &token,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let playlist: Vec<Value> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(playlist.len(), 3);
+
+    let first = &playlist[0];
+    assert_eq!(first["disc_id"], 1);
+    assert_eq!(first["track_id"], 1);
+    assert_eq!(
+        first["path"],
+        format!("/{ALBUM_ID}/1/1?quality=lossless").as_str()
+    );
+
+    // signed token can fetch the track with requested quality
+    let path = first["path"].as_str().unwrap();
+    let response = request(&app, path, first["token"].as_str().unwrap()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["X-Audio-Quality"], "lossless");
+
+    // but not other tracks
+    let response = request(&app, path, playlist[2]["token"].as_str().unwrap()).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_playlist_album_not_found() {
+    let (_root, app, token) = app();
+
+    let response = request(&app, &format!("/playlist/{MISSING_ALBUM_ID}"), &token).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
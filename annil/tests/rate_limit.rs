@@ -0,0 +1,101 @@
+use annil::ratelimit::{rate_limit, RateLimitConfig, RateLimiter};
+use annil::state::AnnilKeys;
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use axum::middleware;
+use axum::response::Response;
+use axum::routing::get;
+use axum::{Extension, Router};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+mod common;
+
+fn app(keys: AnnilKeys, config: RateLimitConfig) -> Router {
+    Router::new()
+        .route("/resource", get(|| async { "ok" }))
+        .route_layer(middleware::from_fn_with_state(
+            RateLimiter::new(config),
+            rate_limit,
+        ))
+        .layer(Extension(Arc::new(keys)))
+}
+
+async fn request(app: &Router, token: &str) -> Response {
+    app.clone()
+        .oneshot(
+            Request::get("/resource")
+                .header(header::AUTHORIZATION, token)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_rate_limit_burst() {
+    let keys = common::keys();
+    let alice = common::user_token(&keys, "alice");
+    let bob = common::user_token(&keys, "bob");
+    let app = app(
+        keys,
+        RateLimitConfig {
+            requests_per_minute: 1,
+            burst: 3,
+        },
+    );
+
+    for _ in 0..3 {
+        assert_eq!(request(&app, &alice).await.status(), StatusCode::OK);
+    }
+
+    let response = request(&app, &alice).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = response.headers()[header::RETRY_AFTER]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(retry_after > 0 && retry_after <= 60);
+
+    // buckets are separated by user
+    assert_eq!(request(&app, &bob).await.status(), StatusCode::OK);
+}
+
+#[test]
+fn test_rate_limiter_refill() {
+    let limiter = RateLimiter::new(RateLimitConfig {
+        requests_per_minute: 60_000,
+        burst: 1,
+    });
+    assert!(limiter.check("user").is_ok());
+    assert!(limiter.check("user").is_err());
+
+    // 1000 requests per second, a token is refilled in 1ms
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    assert!(limiter.check("user").is_ok());
+}
+
+#[test]
+fn test_rate_limiter_evict_idle() {
+    let limiter = RateLimiter::new(RateLimitConfig {
+        requests_per_minute: 60_000,
+        burst: 1,
+    });
+    let slow = RateLimiter::new(RateLimitConfig {
+        requests_per_minute: 1,
+        burst: 1,
+    });
+    assert!(limiter.check("user").is_ok());
+    assert!(slow.check("user").is_ok());
+    assert_eq!(limiter.bucket_count(), 1);
+
+    // bucket of `limiter` is refilled to full, while bucket of `slow` is not
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    limiter.evict_idle();
+    slow.evict_idle();
+    assert_eq!(limiter.bucket_count(), 0);
+    assert_eq!(slow.bucket_count(), 1);
+    assert!(slow.check("user").is_err());
+}
@@ -0,0 +1,136 @@
+use anni_provider::{AnniProvider, AudioResourceReader, ProviderError, Range, ResourceReader};
+use annil::provider::AnnilProvider;
+use annil::route::admin;
+use annil::state::{AnnilKeys, AnnilState};
+use axum::body::{to_bytes, Body};
+use axum::http::{header, Method, Request, StatusCode};
+use axum::response::Response;
+use axum::routing::post;
+use axum::{Extension, Router};
+use jwt_simple::reexports::serde_json::{self, Value};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::num::NonZeroU8;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+struct AlbumsProvider(Vec<String>);
+
+impl AlbumsProvider {
+    fn new(ids: &[u128]) -> Self {
+        Self(ids.iter().map(|id| album_id(*id)).collect())
+    }
+}
+
+fn album_id(id: u128) -> String {
+    Uuid::from_u128(id).to_string()
+}
+
+#[async_trait::async_trait]
+impl AnniProvider for AlbumsProvider {
+    async fn albums(&self) -> anni_provider::Result<HashSet<Cow<str>>> {
+        Ok(self.0.iter().map(|id| Cow::Borrowed(id.as_str())).collect())
+    }
+
+    async fn get_audio(
+        &self,
+        _album_id: &str,
+        _disc_id: NonZeroU8,
+        _track_id: NonZeroU8,
+        _range: Range,
+    ) -> anni_provider::Result<AudioResourceReader> {
+        Err(ProviderError::FileNotFound)
+    }
+
+    async fn get_cover(
+        &self,
+        _album_id: &str,
+        _disc_id: Option<NonZeroU8>,
+    ) -> anni_provider::Result<ResourceReader> {
+        Err(ProviderError::FileNotFound)
+    }
+
+    async fn reload(&self) -> anni_provider::Result<()> {
+        Ok(())
+    }
+}
+
+fn app(provider: Arc<AnnilProvider<AlbumsProvider>>, state: Arc<AnnilState>) -> Router {
+    Router::new()
+        .route("/admin/reload", post(admin::reload::<AlbumsProvider>))
+        .layer(Extension(state))
+        .layer(Extension(provider))
+        .layer(Extension(Arc::new(AnnilKeys::new(
+            b"sign key",
+            b"share key",
+            "admin".to_string(),
+        ))))
+}
+
+async fn init(
+    provider: AnnilProvider<AlbumsProvider>,
+) -> (Router, Arc<AnnilProvider<AlbumsProvider>>, Arc<AnnilState>) {
+    let provider = Arc::new(provider);
+    let state = Arc::new(AnnilState {
+        version: "test".to_string(),
+        last_update: RwLock::new(0),
+        etag: RwLock::new(provider.compute_etag().await.unwrap()),
+        metadata: None,
+    });
+    (app(provider.clone(), state.clone()), provider, state)
+}
+
+async fn reload(app: Router) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/admin/reload")
+        .header(header::AUTHORIZATION, "admin")
+        .body(Body::empty())
+        .unwrap();
+    let response: Response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn test_reload_swaps_provider() {
+    let provider = AnnilProvider::new(AlbumsProvider::new(&[1, 2]))
+        .with_factory(|| async { anyhow::Ok(AlbumsProvider::new(&[2, 3, 4])) });
+    let (app, provider, state) = init(provider).await;
+    let old_etag = state.etag.read().await.clone();
+
+    let (status, body) = reload(app).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["added"], 2);
+    assert_eq!(body["removed"], 1);
+
+    assert_eq!(provider.read().await.albums().await.unwrap().len(), 3);
+    assert_ne!(*state.etag.read().await, old_etag);
+    assert_ne!(*state.last_update.read().await, 0);
+}
+
+#[tokio::test]
+async fn test_failed_reload_keeps_serving() {
+    let provider = AnnilProvider::new(AlbumsProvider::new(&[1, 2])).with_factory(|| async {
+        Err::<AlbumsProvider, _>(anyhow::anyhow!("failed to initialize provider"))
+    });
+    let (app, provider, state) = init(provider).await;
+    let old_etag = state.etag.read().await.clone();
+
+    let (status, body) = reload(app).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(body["error"], "reload_failed");
+    assert!(body["message"]
+        .as_str()
+        .unwrap()
+        .contains("failed to initialize provider"));
+
+    // old provider is still serving, and nothing was updated
+    let albums = provider.read().await.albums().await.unwrap();
+    assert!(albums.contains(album_id(1).as_str()) && albums.contains(album_id(2).as_str()));
+    assert_eq!(*state.etag.read().await, old_etag);
+    assert_eq!(*state.last_update.read().await, 0);
+}
@@ -0,0 +1,107 @@
+use anni_provider::providers::NoCacheStrictLocalProvider;
+use anni_provider::{AnniProvider, AudioResourceReader, Range, ResourceReader};
+use annil::provider::AnnilProvider;
+use annil::route::user;
+use axum::body::{to_bytes, Body};
+use axum::http::{header, Request, StatusCode};
+use axum::routing::get;
+use axum::{Extension, Router};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::num::NonZeroU8;
+use std::sync::Arc;
+use tokio::sync::Notify;
+use tower::ServiceExt;
+
+mod common;
+
+const ALBUM_ID: &str = "66666666-6666-4666-8666-666666666666";
+
+/// Provider whose reload does not finish until `finish` is notified.
+struct SlowReloadProvider {
+    inner: NoCacheStrictLocalProvider,
+    started: Arc<Notify>,
+    finish: Arc<Notify>,
+}
+
+#[async_trait::async_trait]
+impl AnniProvider for SlowReloadProvider {
+    async fn albums(&self) -> anni_provider::Result<HashSet<Cow<str>>> {
+        self.inner.albums().await
+    }
+
+    async fn get_audio(
+        &self,
+        album_id: &str,
+        disc_id: NonZeroU8,
+        track_id: NonZeroU8,
+        range: Range,
+    ) -> anni_provider::Result<AudioResourceReader> {
+        self.inner
+            .get_audio(album_id, disc_id, track_id, range)
+            .await
+    }
+
+    async fn get_cover(
+        &self,
+        album_id: &str,
+        disc_id: Option<NonZeroU8>,
+    ) -> anni_provider::Result<ResourceReader> {
+        self.inner.get_cover(album_id, disc_id).await
+    }
+
+    async fn reload(&self) -> anni_provider::Result<()> {
+        self.started.notify_one();
+        self.finish.notified().await;
+        self.inner.reload().await
+    }
+}
+
+#[tokio::test]
+async fn test_serve_audio_during_reload() {
+    let root = tempfile::tempdir().unwrap();
+    common::write_album(root.path(), ALBUM_ID, 1);
+    let audio = std::fs::read(root.path().join(ALBUM_ID).join("1/1.flac")).unwrap();
+
+    let started = Arc::new(Notify::new());
+    let finish = Arc::new(Notify::new());
+    let provider = Arc::new(AnnilProvider::new(SlowReloadProvider {
+        inner: NoCacheStrictLocalProvider {
+            root: root.path().to_path_buf(),
+            layer: 0,
+        },
+        started: started.clone(),
+        finish: finish.clone(),
+    }));
+    let app = Router::new()
+        .route(
+            "/:album_id/:disc_id/:track_id",
+            get(user::audio::<SlowReloadProvider>),
+        )
+        .layer(Extension(provider.clone()));
+    let (app, token) = common::with_state(app, None);
+
+    let reload = tokio::spawn({
+        let provider = provider.clone();
+        async move { provider.reload().await }
+    });
+    started.notified().await;
+
+    // reload is still in progress
+    let request = Request::builder()
+        .uri(format!("/{ALBUM_ID}/1/1?quality=lossless"))
+        .header(header::AUTHORIZATION, token)
+        .body(Body::empty())
+        .unwrap();
+    let response = tokio::time::timeout(std::time::Duration::from_secs(5), app.oneshot(request))
+        .await
+        .expect("audio request blocked by reload")
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(body, audio);
+    assert!(!reload.is_finished());
+
+    finish.notify_one();
+    reload.await.unwrap().unwrap();
+}
@@ -0,0 +1,152 @@
+use anni_provider::providers::MultipleProviders;
+use annil::provider::AnnilProvider;
+use annil::route::{admin, user};
+use annil::state::{AdminScope, AnnilKeys};
+use axum::body::{to_bytes, Body};
+use axum::http::{header, Method, Request, StatusCode};
+use axum::routing::{get, post};
+use axum::{Extension, Router};
+use jwt_simple::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+mod common;
+
+const ADMIN_TOKEN: &str = "admin";
+const SHARED_ALBUM: &str = "11111111-1111-4111-8111-111111111111";
+const OTHER_ALBUM: &str = "22222222-2222-4222-8222-222222222222";
+
+fn app() -> Router {
+    type Provider = MultipleProviders;
+    let keys = AnnilKeys {
+        sign_key: HS256Key::from_bytes(b"sign key"),
+        share_key: HS256Key::from_bytes(b"share key").with_key_id("share"),
+        admin_tokens: HashMap::from([(ADMIN_TOKEN.to_string(), AdminScope::all())]),
+    };
+    let state = common::state(None);
+    let provider = AnnilProvider::new(MultipleProviders::new(vec![]));
+
+    Router::new()
+        .route("/albums", get(user::albums::<Provider>))
+        .route(
+            "/:album_id/:disc_id/:track_id",
+            get(user::audio::<Provider>),
+        )
+        .route("/:album_id/cover", get(user::cover::<Provider>))
+        .route("/admin/share", post(admin::share))
+        .layer(Extension(Arc::new(state)))
+        .layer(Extension(Arc::new(provider)))
+        .layer(Extension(Arc::new(keys)))
+}
+
+async fn sign_share_token(app: &Router, albums: &[&str]) -> String {
+    sign_share_token_with(app, &format!(r#"{{"albums":{albums:?}}}"#)).await
+}
+
+async fn sign_share_token_with(app: &Router, payload: &str) -> String {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/admin/share")
+        .header(header::AUTHORIZATION, ADMIN_TOKEN)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(payload.to_string()))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+async fn get_status(app: &Router, uri: &str, token: &str) -> StatusCode {
+    let request = Request::builder()
+        .uri(uri)
+        .header(header::AUTHORIZATION, token)
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn test_share_token_scoped_to_album() {
+    let app = app();
+    let token = sign_share_token(&app, &[SHARED_ALBUM]).await;
+
+    // album is shared, but does not exist in provider
+    assert_eq!(
+        get_status(&app, &format!("/{SHARED_ALBUM}/1/1"), &token).await,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        get_status(&app, &format!("/{SHARED_ALBUM}/cover"), &token).await,
+        StatusCode::NOT_FOUND
+    );
+
+    // album is not shared
+    assert_eq!(
+        get_status(&app, &format!("/{OTHER_ALBUM}/1/1"), &token).await,
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        get_status(&app, &format!("/{OTHER_ALBUM}/cover"), &token).await,
+        StatusCode::FORBIDDEN
+    );
+}
+
+#[tokio::test]
+async fn test_share_token_album_list() {
+    let app = app();
+    let token = sign_share_token(&app, &[SHARED_ALBUM]).await;
+
+    let request = Request::builder()
+        .uri("/albums")
+        .header(header::AUTHORIZATION, &token)
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(body, format!(r#"["{SHARED_ALBUM}"]"#));
+}
+
+#[tokio::test]
+async fn test_share_requires_admin() {
+    let app = app();
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/admin/share")
+        .header(header::AUTHORIZATION, "not admin")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(format!(r#"{{"albums":["{SHARED_ALBUM}"]}}"#)))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+fn share_token_lifetime(token: &str) -> u64 {
+    let claims = HS256Key::from_bytes(b"share key")
+        .verify_token::<NoCustomClaims>(token, None)
+        .unwrap();
+    let issued_at = claims.issued_at.unwrap().as_secs();
+    claims
+        .expires_at
+        .expect("share token must expire")
+        .as_secs()
+        - issued_at
+}
+
+#[tokio::test]
+async fn test_share_token_expires() {
+    let app = app();
+    let token = sign_share_token(&app, &[SHARED_ALBUM]).await;
+    assert_eq!(share_token_lifetime(&token), 7 * 24 * 3600);
+
+    let token = sign_share_token_with(
+        &app,
+        &format!(r#"{{"albums":["{SHARED_ALBUM}"],"expires_in":2}}"#),
+    )
+    .await;
+    assert_eq!(share_token_lifetime(&token), 2 * 3600);
+}
@@ -0,0 +1,67 @@
+use annil::shutdown::{reject_when_draining, serve, Shutdown};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::middleware;
+use axum::routing::get;
+use axum::Router;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tower::ServiceExt;
+
+fn app() -> Router {
+    Router::new().route("/", get(|| async { "ok" }))
+}
+
+async fn get_status_line(addr: SocketAddr) -> std::io::Result<String> {
+    let mut stream = TcpStream::connect(addr).await?;
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+    Ok(response.lines().next().unwrap_or_default().to_string())
+}
+
+#[tokio::test]
+async fn test_stop_accepting_after_signal() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+    let server = tokio::spawn(serve(
+        listener,
+        app(),
+        async move {
+            let _ = rx.await;
+        },
+        Duration::from_secs(5),
+    ));
+
+    assert_eq!(get_status_line(addr).await.unwrap(), "HTTP/1.1 200 OK");
+
+    tx.send(()).unwrap();
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("server did not stop after signal")
+        .unwrap()
+        .unwrap();
+    assert!(get_status_line(addr).await.is_err());
+}
+
+#[tokio::test]
+async fn test_reject_when_draining() {
+    let shutdown = Shutdown::default();
+    let app = app().layer(middleware::from_fn_with_state(
+        shutdown.clone(),
+        reject_when_draining,
+    ));
+    let request = || Request::get("/").body(Body::empty()).unwrap();
+
+    let response = app.clone().oneshot(request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    shutdown.start_draining();
+    let response = app.oneshot(request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
@@ -0,0 +1,108 @@
+use anni_provider::{AudioInfo, AudioResourceReader, Range};
+use annil::route::user::AudioQuality;
+use annil::transcode::{Transcode, TranscodeCache, TranscodeCacheConfig};
+use std::num::NonZeroU8;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::io::AsyncReadExt;
+use tokio::process::Child;
+
+const ALBUM_ID: &str = "55555555-5555-4555-8555-555555555555";
+const AUDIO: &[u8] = b"fLaC source audio";
+
+/// Transcoder which outputs its input as is, and counts how many times it's spawned.
+struct CatTranscoder {
+    variant: &'static str,
+    spawned: AtomicUsize,
+}
+
+impl CatTranscoder {
+    fn new(variant: &'static str) -> Self {
+        Self {
+            variant,
+            spawned: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl Transcode for CatTranscoder {
+    fn content_type(&self) -> &'static str {
+        "audio/ogg"
+    }
+
+    fn variant(&self) -> String {
+        self.variant.to_string()
+    }
+
+    fn quality(&self) -> AudioQuality {
+        AudioQuality::Low
+    }
+
+    fn content_length(&self, _: &AudioInfo) -> Option<usize> {
+        None
+    }
+
+    fn spawn(&self) -> Child {
+        self.spawned.fetch_add(1, Ordering::SeqCst);
+        tokio::process::Command::new("cat")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap()
+    }
+}
+
+/// Transcode track 1 with `transcoder`, and returns the output.
+/// `fetched` counts how many times source audio is read.
+async fn transcode(
+    cache: &TranscodeCache,
+    transcoder: &CatTranscoder,
+    fetched: &AtomicUsize,
+) -> Vec<u8> {
+    let one = NonZeroU8::new(1).unwrap();
+    let audio = cache
+        .transcode(ALBUM_ID, one, one, transcoder, async {
+            fetched.fetch_add(1, Ordering::SeqCst);
+            Ok(AudioResourceReader {
+                info: AudioInfo {
+                    extension: "flac".to_string(),
+                    size: AUDIO.len(),
+                    duration: 1000,
+                },
+                range: Range::FULL,
+                reader: Box::pin(AUDIO),
+            })
+        })
+        .await
+        .unwrap();
+    assert_eq!(audio.info.duration, 1000);
+
+    let mut output = Vec::new();
+    let mut reader = audio.reader;
+    reader.read_to_end(&mut output).await.unwrap();
+    output
+}
+
+#[tokio::test]
+async fn test_transcode_cache_hit() {
+    let root = tempfile::tempdir().unwrap();
+    let cache = TranscodeCache::new(TranscodeCacheConfig {
+        root: root.path().to_path_buf(),
+        max_size: None,
+        dedup: false,
+    });
+    let transcoder = CatTranscoder::new("opus-low");
+    let fetched = AtomicUsize::new(0);
+
+    assert_eq!(transcode(&cache, &transcoder, &fetched).await, AUDIO);
+    assert_eq!(transcode(&cache, &transcoder, &fetched).await, AUDIO);
+    // the second request is served from cache
+    assert_eq!(transcoder.spawned.load(Ordering::SeqCst), 1);
+    assert_eq!(fetched.load(Ordering::SeqCst), 1);
+
+    // other variants of the same track are cached separately
+    let other = CatTranscoder::new("opus-high");
+    assert_eq!(transcode(&cache, &other, &fetched).await, AUDIO);
+    assert_eq!(other.spawned.load(Ordering::SeqCst), 1);
+    assert_eq!(fetched.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,97 @@
+//! Transcoders are replaced by a fake `ffmpeg` in `PATH`, so tests in this file must not run with others.
+#![cfg(all(unix, feature = "transcode"))]
+
+use anni_provider::providers::NoCacheStrictLocalProvider;
+use annil::route::user;
+use annil::transcode::{TranscodeCache, TranscodeCacheConfig};
+use axum::body::{to_bytes, Body};
+use axum::http::{header, Request, StatusCode};
+use axum::response::Response;
+use axum::routing::get;
+use axum::{Extension, Router};
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+mod common;
+
+const ALBUM_ID: &str = "66666666-6666-4666-8666-666666666666";
+
+/// Fake `ffmpeg` which outputs its input as is.
+const SUCCEED: &str = "#!/bin/sh\nexec cat\n";
+/// Fake `ffmpeg` which outputs part of its input, and exits unsuccessfully.
+const FAIL: &str = "#!/bin/sh\nhead -c 16\ncat > /dev/null\nexit 1\n";
+
+fn install_ffmpeg(bin: &Path, script: &str) {
+    let path = bin.join("ffmpeg");
+    std::fs::write(&path, script).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+async fn get_audio(app: &Router, token: &str) -> Response {
+    let request = Request::builder()
+        .uri(format!("/{ALBUM_ID}/1/1?quality=low"))
+        .header(header::AUTHORIZATION, token)
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_transcode_cache_route() {
+    type Provider = NoCacheStrictLocalProvider;
+
+    let bin = tempfile::tempdir().unwrap();
+    let path = std::env::var("PATH").unwrap_or_default();
+    std::env::set_var("PATH", format!("{}:{path}", bin.path().display()));
+
+    let root = tempfile::tempdir().unwrap();
+    common::write_album(root.path(), ALBUM_ID, 1);
+    let source = std::fs::read("../assets/1s.flac").unwrap();
+
+    let cache_root = tempfile::tempdir().unwrap();
+    let cache = Arc::new(TranscodeCache::new(TranscodeCacheConfig {
+        root: cache_root.path().to_path_buf(),
+        max_size: None,
+        dedup: false,
+    }));
+    let router = Router::new()
+        .route(
+            "/:album_id/:disc_id/:track_id",
+            get(user::audio::<Provider>),
+        )
+        .layer(Extension(cache.clone()))
+        .layer(Extension(Arc::new(common::local_provider(root.path()))));
+    let (app, token) = common::with_state(router, None);
+    let variant = cache_root.path().join("aac-low");
+
+    // failed output is neither served nor cached
+    install_ffmpeg(bin.path(), FAIL);
+    for _ in 0..2 {
+        let response = get_audio(&app, &token).await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    assert!(!variant.join(ALBUM_ID).exists());
+    assert_eq!(std::fs::read_dir(&variant).unwrap().count(), 0);
+
+    install_ffmpeg(bin.path(), SUCCEED);
+    let response = get_audio(&app, &token).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "audio/aac");
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(body, source);
+    assert!(variant.join(ALBUM_ID).join("1_1").exists());
+
+    // cached output is served without transcoding
+    install_ffmpeg(bin.path(), FAIL);
+    let response = get_audio(&app, &token).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(body, source);
+
+    // purged on reload
+    cache.purge().await;
+    let response = get_audio(&app, &token).await;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
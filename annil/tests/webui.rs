@@ -0,0 +1,31 @@
+use annil::route::webui;
+use axum::body::to_bytes;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::StatusCode;
+
+#[cfg(feature = "webui")]
+#[tokio::test]
+async fn test_index_serves_player() {
+    let response = webui::index().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()[CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .starts_with("text/html"));
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+    assert!(body.contains("<title>Annil Player</title>"));
+    assert!(body.contains("webui/player.js"));
+}
+
+#[cfg(not(feature = "webui"))]
+#[tokio::test]
+async fn test_index_not_found() {
+    let response = webui::index().await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(response.headers().get(CONTENT_TYPE).is_none());
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert!(body.is_empty());
+}
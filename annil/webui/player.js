@@ -0,0 +1,130 @@
+'use strict';
+
+// Token is read from `?auth=` first, so share links like `https://annil.example/?auth=<token>` work directly.
+const TOKEN_KEY = 'annil-token';
+
+const $ = (id) => document.getElementById(id);
+const audio = $('audio');
+
+let token = new URLSearchParams(location.search).get('auth') || localStorage.getItem(TOKEN_KEY);
+// playlist of current album, in `[disc_id, track_id]` pairs, or null if the track list is unknown
+let playlist = null;
+let current = null;
+
+function setStatus(text) {
+  $('status').textContent = text;
+}
+
+// Decode claims of a JWT token without verification, which is done by annil.
+function decodeClaims(token) {
+  try {
+    const payload = token.split('.')[1].replace(/-/g, '+').replace(/_/g, '/');
+    return JSON.parse(atob(payload));
+  } catch (e) {
+    return null;
+  }
+}
+
+// Share tokens carry their track list, while user tokens do not.
+function sharedTracks(albumId) {
+  const claims = decodeClaims(token);
+  if (!claims || claims.type !== 'share' || !claims.audios[albumId]) {
+    return null;
+  }
+  const discs = claims.audios[albumId];
+  return Object.keys(discs)
+    .map(Number)
+    .sort((a, b) => a - b)
+    .flatMap((disc) => discs[disc].slice().sort((a, b) => a - b).map((track) => [disc, track]));
+}
+
+function play(albumId, discId, trackId) {
+  current = { albumId, discId, trackId };
+  // <audio> can not send headers, so token is passed by query
+  // range requests are made by browser and handled by annil
+  audio.src = `${albumId}/${discId}/${trackId}?auth=${encodeURIComponent(token)}`;
+  audio.play();
+  $('now-playing').textContent = `${albumId} - Disc ${discId} - Track ${trackId}`;
+  for (const li of $('albums').children) {
+    li.classList.toggle('playing', li.dataset.album === albumId);
+  }
+}
+
+function playAlbum(albumId) {
+  playlist = sharedTracks(albumId);
+  if (playlist) {
+    if (playlist.length > 0) {
+      play(albumId, ...playlist[0]);
+    }
+  } else {
+    play(albumId, 1, 1);
+  }
+}
+
+function next(failed) {
+  if (!current) {
+    return;
+  }
+  const { albumId, discId, trackId } = current;
+  if (playlist) {
+    const index = playlist.findIndex(([d, t]) => d === discId && t === trackId);
+    if (index + 1 < playlist.length) {
+      play(albumId, ...playlist[index + 1]);
+    }
+  } else if (!failed) {
+    play(albumId, discId, trackId + 1);
+  } else if (trackId > 1) {
+    // track does not exist, try next disc
+    play(albumId, discId + 1, 1);
+  } else {
+    current = null;
+  }
+}
+
+audio.addEventListener('ended', () => next(false));
+audio.addEventListener('error', () => next(true));
+
+async function loadAlbums() {
+  $('albums').replaceChildren();
+  if (!token) {
+    setStatus('Please login with your annil token.');
+    return;
+  }
+
+  setStatus('Loading...');
+  const response = await fetch('albums', { headers: { Authorization: token } });
+  if (response.status === 401) {
+    setStatus('Invalid token.');
+    return;
+  }
+  if (!response.ok) {
+    setStatus(`Failed to load albums: ${response.status}`);
+    return;
+  }
+
+  const albums = (await response.json()).sort();
+  for (const album of albums) {
+    const li = document.createElement('li');
+    li.dataset.album = album;
+    li.textContent = album;
+    li.addEventListener('click', () => playAlbum(album));
+    $('albums').appendChild(li);
+  }
+  setStatus(`${albums.length} albums available.`);
+}
+
+$('login').addEventListener('submit', (e) => {
+  e.preventDefault();
+  token = $('token').value.trim();
+  localStorage.setItem(TOKEN_KEY, token);
+  loadAlbums();
+});
+
+$('logout').addEventListener('click', () => {
+  token = null;
+  localStorage.removeItem(TOKEN_KEY);
+  audio.removeAttribute('src');
+  loadAlbums();
+});
+
+loadAlbums();
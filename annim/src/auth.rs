@@ -2,11 +2,23 @@ use std::sync::LazyLock;
 
 /// https://github.com/async-graphql/examples/blob/0c4e5e29e97a41c8877c126cbcefb82721ae81af/models/token/src/lib.rs
 use async_graphql::{Data, Result};
+use axum::http::{header::AUTHORIZATION, HeaderMap};
 use serde::Deserialize;
 
 static TOKEN: LazyLock<String> =
     LazyLock::new(|| std::env::var("ANNIM_AUTH_TOKEN").unwrap_or_else(|_| "114514".to_string()));
 
+/// Maximum length of a token, longer tokens are rejected without comparison.
+const MAX_TOKEN_LENGTH: usize = 1024;
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum AuthError {
+    #[error("Token is required")]
+    Missing,
+    #[error("Malformed token: {0}")]
+    Malformed(&'static str),
+}
+
 pub struct AuthToken(String);
 
 impl AuthToken {
@@ -14,6 +26,40 @@ impl AuthToken {
         Self(token)
     }
 
+    /// Parse a token from HTTP header or WebSocket connection params.
+    ///
+    /// An optional `Bearer ` prefix is stripped. The token itself must be non-empty
+    /// printable ASCII without whitespaces.
+    pub fn parse(value: &str) -> Result<Self, AuthError> {
+        let token = value.strip_prefix("Bearer ").unwrap_or(value);
+        if token.is_empty() {
+            return Err(AuthError::Missing);
+        }
+        if token.len() > MAX_TOKEN_LENGTH {
+            return Err(AuthError::Malformed("token is too long"));
+        }
+        if !token.bytes().all(|b| b.is_ascii_graphic()) {
+            return Err(AuthError::Malformed(
+                "token must be printable ASCII without whitespaces",
+            ));
+        }
+
+        Ok(Self(token.to_string()))
+    }
+
+    /// Parse token from `Authorization` header.
+    ///
+    /// Returns `Ok(None)` if the header does not exist, as anonymous queries are allowed.
+    pub fn from_headers(headers: &HeaderMap) -> Result<Option<Self>, AuthError> {
+        let Some(value) = headers.get(AUTHORIZATION) else {
+            return Ok(None);
+        };
+        let value = value
+            .to_str()
+            .map_err(|_| AuthError::Malformed("token must be printable ASCII"))?;
+        Self::parse(value).map(Some)
+    }
+
     pub fn is_valid(&self) -> bool {
         self.0 == TOKEN.as_str()
     }
@@ -27,13 +73,12 @@ pub async fn on_connection_init(value: serde_json::Value) -> Result<Data> {
 
     // Coerce the connection params into our `Payload` struct so we can
     // validate the token exists in the headers.
-    if let Ok(payload) = serde_json::from_value::<Payload>(value) {
-        let mut data = Data::default();
-        data.insert(AuthToken(payload.token));
-        Ok(data)
-    } else {
-        Err("Token is required".into())
-    }
+    let Ok(payload) = serde_json::from_value::<Payload>(value) else {
+        return Err(AuthError::Missing.into());
+    };
+    let mut data = Data::default();
+    data.insert(AuthToken::parse(&payload.token)?);
+    Ok(data)
 }
 
 pub(crate) struct AdminGuard;
@@ -14,6 +14,7 @@ pub struct Model {
     pub artist: Option<String>,
     pub created_at: DateTime,
     pub updated_at: DateTime,
+    pub cover_url: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
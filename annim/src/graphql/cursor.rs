@@ -27,7 +27,7 @@ impl Cursor {
         ValueTuple::Many(self.into_inner())
     }
 
-    fn into_inner(self) -> Vec<sea_orm::Value> {
+    pub(crate) fn into_inner(self) -> Vec<sea_orm::Value> {
         self.0.into_iter().map(Into::into).collect()
     }
 }
@@ -54,6 +54,9 @@ pub(crate) enum CursorValue {
     Unsigned(Option<u32>),
     #[serde(rename = "B")]
     BigUnsigned(Option<u64>),
+    /// f -> float
+    #[serde(rename = "f")]
+    Float(Option<f32>),
     /// s -> string
     #[serde(rename = "s")]
     String(Option<Box<String>>),
@@ -70,6 +73,7 @@ impl From<sea_orm::Value> for CursorValue {
             sea_orm::Value::SmallUnsigned(value) => CursorValue::SmallUnsigned(value),
             sea_orm::Value::Unsigned(value) => CursorValue::Unsigned(value),
             sea_orm::Value::BigUnsigned(value) => CursorValue::BigUnsigned(value),
+            sea_orm::Value::Float(value) => CursorValue::Float(value),
             sea_orm::Value::String(value) => CursorValue::String(value),
             _ => panic!("Unsupported value type"),
         }
@@ -87,6 +91,7 @@ impl From<CursorValue> for sea_orm::Value {
             CursorValue::SmallUnsigned(value) => sea_orm::Value::SmallUnsigned(value),
             CursorValue::Unsigned(value) => sea_orm::Value::Unsigned(value),
             CursorValue::BigUnsigned(value) => sea_orm::Value::BigUnsigned(value),
+            CursorValue::Float(value) => sea_orm::Value::Float(value),
             CursorValue::String(value) => sea_orm::Value::String(value),
         }
     }
@@ -1,7 +1,5 @@
 use async_graphql::{InputObject, InputType, OneofObject, ID};
-use sea_orm::{
-    prelude::Uuid, ActiveModelTrait, ActiveValue, ConnectionTrait, DatabaseConnection, DbErr,
-};
+use sea_orm::{prelude::Uuid, ActiveModelTrait, ActiveValue, ConnectionTrait, DbErr};
 
 use super::types::{MetadataOrganizeLevel, TrackType};
 use crate::{
@@ -85,6 +83,9 @@ pub struct CreateAlbumTrackInput {
     pub title: String,
     pub artist: String,
     pub r#type: TrackType,
+    /// Duration of the track in milliseconds.
+    #[graphql(name = "duration")]
+    pub duration_ms: Option<i32>,
 }
 
 impl CreateAlbumTrackInput {
@@ -103,6 +104,7 @@ impl CreateAlbumTrackInput {
             title: ActiveValue::set(self.title),
             artist: ActiveValue::set(self.artist),
             r#type: ActiveValue::set(self.r#type.into()),
+            duration_ms: ActiveValue::set(self.duration_ms),
             ..Default::default()
         };
         let track = track.insert(txn).await?;
@@ -130,13 +132,16 @@ pub struct UpdateAlbumInfoInput {
 }
 
 impl UpdateAlbumInfoInput {
-    pub(crate) async fn update(
+    pub(crate) async fn update<C: ConnectionTrait>(
         self,
         mut model: album::ActiveModel,
-        db: &DatabaseConnection,
+        db: &C,
     ) -> Result<album::Model, DbErr> {
         may_update_required!(self, model, title);
-        may_update_optional!(self, model, edition);
+        // empty edition is the same as no edition
+        if let Some(edition) = self.edition {
+            model.edition = ActiveValue::set(edition.value.filter(|e| !e.is_empty()));
+        }
         may_update_optional!(self, model, catalog);
         may_update_required!(self, model, artist);
         may_update_required!(self, model, release_year);
@@ -159,10 +164,10 @@ pub struct UpdateDiscInfoInput {
 }
 
 impl UpdateDiscInfoInput {
-    pub(crate) async fn update(
+    pub(crate) async fn update<C: ConnectionTrait>(
         self,
         mut model: disc::ActiveModel,
-        db: &DatabaseConnection,
+        db: &C,
     ) -> Result<disc::Model, DbErr> {
         may_update_optional!(self, model, title);
         may_update_optional!(self, model, catalog);
@@ -180,19 +185,23 @@ pub struct UpdateTrackInfoInput {
     pub title: Option<String>,
     pub artist: Option<String>,
     pub r#type: Option<TrackType>,
+    /// Duration of the track in milliseconds.
+    #[graphql(name = "duration")]
+    pub duration_ms: Option<UpdateI32>,
 }
 
 impl UpdateTrackInfoInput {
-    pub(crate) async fn update(
+    pub(crate) async fn update<C: ConnectionTrait>(
         self,
         mut model: track::ActiveModel,
-        db: &DatabaseConnection,
+        db: &C,
     ) -> Result<track::Model, DbErr> {
         may_update_required!(self, model, title);
         may_update_required!(self, model, artist);
         if let Some(r#type) = self.r#type {
             model.r#type = sea_orm::ActiveValue::set(r#type.into());
         }
+        may_update_optional!(self, model, duration_ms);
 
         model.updated_at = ActiveValue::set(now());
         model.update(db).await
@@ -215,6 +224,9 @@ pub struct ReplaceDiscTracksInput {
 pub struct UpdateAlbumOrganizeLevelInput {
     pub id: ID,
     pub level: MetadataOrganizeLevel,
+    /// Whether to allow decreasing the organize level. Defaults to `false`.
+    #[graphql(default)]
+    pub allow_downgrade: bool,
 }
 
 /// List albums by conditions.
@@ -237,6 +249,7 @@ pub enum AlbumsBy {
 }
 
 pub type UpdateString = UpdateValue<String>;
+pub type UpdateI32 = UpdateValue<i32>;
 pub type UpdateI16 = UpdateValue<i16>;
 pub type UpdateJson = UpdateValue<serde_json::Value>;
 
@@ -2,41 +2,114 @@ mod cursor;
 mod input;
 pub mod types;
 
-use std::{i64, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    i64,
+    str::FromStr,
+};
 
 use anyhow::Ok;
 use async_graphql::{
-    connection::{Connection, Edge},
+    connection::{Connection, Edge, EmptyFields},
     Context, EmptySubscription, Object, Schema, ID,
 };
 use cursor::Cursor;
 use input::{AlbumsBy, MetadataIDInput};
 use sea_orm::{
     prelude::Uuid, sea_query::IntoIden, ActiveModelTrait, ActiveValue, ColumnTrait,
-    DatabaseConnection, EntityTrait, Identity, ModelTrait, QueryFilter, QueryOrder, QuerySelect,
-    Related, TransactionTrait,
+    ConnectionTrait, DatabaseConnection, EntityTrait, Identity, ModelTrait, QueryFilter,
+    QueryOrder, QuerySelect, Related, TransactionTrait,
 };
 use tantivy::{
-    collector::TopDocs,
-    query::{BooleanQuery, Occur, PhraseQuery, QueryClone, TermQuery},
-    Term,
+    collector::{Count, TopDocs},
+    query::{BooleanQuery, ConstScoreQuery, Occur, PhraseQuery, QueryClone, TermQuery},
+    snippet::SnippetGenerator,
+    TantivyDocument, Term,
 };
 use types::{
-    AlbumInfo, DiscInfo, MetadataOrganizeLevel, TagInfo, TagRelation, TagType, TrackInfo,
-    TrackSearchResult,
+    AlbumEdgeFields, AlbumInfo, DiscInfo, MetadataOrganizeLevel, TagInfo, TagRelation,
+    TagTreeDirection, TagTreeNode, TagType, TrackInfo, TrackSearchResult,
 };
 
 use crate::{
     auth::AdminGuard,
     entities::{album, album_tag_relation, disc, helper::now, tag_info, tag_relation, track},
-    search::RepositorySearchManager,
+    search::{mark_pending, RepositorySearchManager},
 };
 
 pub type MetadataSchema = Schema<MetadataQuery, MetadataMutation, EmptySubscription>;
 
-pub fn build_schema(db: DatabaseConnection) -> MetadataSchema {
+type AlbumConnection = Connection<String, AlbumInfo, EmptyFields, AlbumEdgeFields>;
+
+/// Limits of incoming queries, protecting the database from expensive queries.
+#[derive(Debug, Clone)]
+pub struct QueryLimits {
+    /// Maximum nesting depth of a query.
+    pub depth: usize,
+    /// Maximum complexity of a query, which is the number of fields by default.
+    pub complexity: usize,
+    /// Maximum value of `first`, `last`, `count` and other page size arguments.
+    pub page_size: u64,
+}
+
+impl Default for QueryLimits {
+    fn default() -> Self {
+        Self {
+            depth: 16,
+            complexity: 1000,
+            page_size: 100,
+        }
+    }
+}
+
+impl QueryLimits {
+    /// Read limits from `ANNIM_QUERY_DEPTH_LIMIT`, `ANNIM_QUERY_COMPLEXITY_LIMIT` and `ANNIM_QUERY_PAGE_SIZE_LIMIT`.
+    ///
+    /// Limits not set in environment use default values.
+    pub fn from_env() -> anyhow::Result<Self> {
+        fn read<T: FromStr>(key: &str, default: T) -> anyhow::Result<T> {
+            match std::env::var(key) {
+                std::result::Result::Ok(value) => value
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid value of {key}: {value}")),
+                Err(_) => Ok(default),
+            }
+        }
+
+        let default = Self::default();
+        Ok(Self {
+            depth: read("ANNIM_QUERY_DEPTH_LIMIT", default.depth)?,
+            complexity: read("ANNIM_QUERY_COMPLEXITY_LIMIT", default.complexity)?,
+            page_size: read("ANNIM_QUERY_PAGE_SIZE_LIMIT", default.page_size)?,
+        })
+    }
+
+    /// Reject page size arguments larger than [QueryLimits::page_size].
+    fn check_page_size(ctx: &Context<'_>, name: &str, size: Option<u64>) -> anyhow::Result<()> {
+        let limit = match ctx.data_opt::<QueryLimits>() {
+            Some(limits) => limits.page_size,
+            None => Self::default().page_size,
+        };
+        match size {
+            Some(size) if size > limit => {
+                anyhow::bail!("`{name}` must not be larger than {limit}, got {size}")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+pub fn build_schema(
+    db: DatabaseConnection,
+    searcher: RepositorySearchManager,
+    limits: QueryLimits,
+) -> MetadataSchema {
     Schema::build(MetadataQuery, MetadataMutation, EmptySubscription)
+        .limit_depth(limits.depth)
+        .limit_complexity(limits.complexity)
         .data(db)
+        .data(searcher)
+        .data(limits)
         .finish()
 }
 
@@ -62,11 +135,26 @@ impl MetadataQuery {
         ctx: &Context<'ctx>,
         by: AlbumsBy,
         after: Option<String>,
-        // before: Option<String>,
+        before: Option<String>,
         first: Option<u64>,
-        // last: Option<i32>,
-    ) -> anyhow::Result<Connection<String, AlbumInfo>> {
+        last: Option<u64>,
+    ) -> anyhow::Result<AlbumConnection> {
         let db = ctx.data::<DatabaseConnection>().unwrap();
+        if first.is_some() && last.is_some() {
+            anyhow::bail!("Passing both `first` and `last` is not supported");
+        }
+        QueryLimits::check_page_size(ctx, "first", first)?;
+        QueryLimits::check_page_size(ctx, "last", last)?;
+        match &by {
+            AlbumsBy::RecentlyCreated(limit)
+            | AlbumsBy::RecentlyUpdated(limit)
+            | AlbumsBy::RecentlyReleased(limit) => {
+                QueryLimits::check_page_size(ctx, "by", Some(*limit))?
+            }
+            _ => {}
+        }
+        // paginate backward if `last` is given, or only `before` is given
+        let backward = last.is_some() || (before.is_some() && first.is_none());
 
         let (query, columns, desc) = match by {
             AlbumsBy::AlbumIds(album_ids) => (
@@ -98,7 +186,14 @@ impl MetadataQuery {
                 ],
                 true,
             ),
-            AlbumsBy::Keyword(_) => unimplemented!(),
+            AlbumsBy::Keyword(keyword) => {
+                if backward {
+                    anyhow::bail!("Backward pagination is not supported for keyword search");
+                }
+                let search_manager = ctx.data::<RepositorySearchManager>().unwrap();
+                return search_albums(db, search_manager, &keyword, after, first.unwrap_or(20))
+                    .await;
+            }
             AlbumsBy::OrganizeLevel(level) => (
                 album::Entity::find().filter(album::Column::Level.eq(level.to_string())),
                 vec![album::Column::Id],
@@ -114,7 +209,7 @@ impl MetadataQuery {
             ),
         };
 
-        let limit = first.unwrap_or(20);
+        let limit = first.or(last).unwrap_or(20);
         let mut query = query.cursor_by(Identity::Many(
             columns.iter().map(|r| r.into_iden()).collect(),
         ));
@@ -126,14 +221,29 @@ impl MetadataQuery {
             let cursor = Cursor::from_str(&cursor)?;
             query.after(cursor.into_value_tuple());
         }
+        if let Some(cursor) = before {
+            let cursor = Cursor::from_str(&cursor)?;
+            query.before(cursor.into_value_tuple());
+        }
 
-        let mut data = query.first(limit + 1).all(db).await.unwrap();
+        let mut has_previous_page = false;
         let mut has_next_page = false;
-
-        if data.len() == limit as usize + 1 {
-            data.pop();
-            has_next_page = true;
-        }
+        let data = if backward {
+            // `last` queries in reversed order, and reverses the result back to the original order
+            let mut data = query.last(limit + 1).all(db).await?;
+            if data.len() == limit as usize + 1 {
+                data.remove(0);
+                has_previous_page = true;
+            }
+            data
+        } else {
+            let mut data = query.first(limit + 1).all(db).await?;
+            if data.len() == limit as usize + 1 {
+                data.pop();
+                has_next_page = true;
+            }
+            data
+        };
 
         let edges: Vec<_> = data
             .into_iter()
@@ -144,25 +254,39 @@ impl MetadataQuery {
                     .collect();
 
                 let cursor = Cursor::new(values);
-                Edge::new(cursor.to_string(), AlbumInfo(model))
+                Edge::with_additional_fields(
+                    cursor.to_string(),
+                    AlbumInfo(model),
+                    AlbumEdgeFields { score: None },
+                )
             })
             .collect();
 
-        let mut connection = Connection::new(false, has_next_page);
+        let mut connection = Connection::new(has_previous_page, has_next_page);
         connection.edges.extend(edges);
         Ok(connection)
     }
 
+    /// Search tracks by title and artist.
+    ///
+    /// If `fuzzy` is set, each word in `keyword` matches words within that edit distance, which is capped at 2.
+    /// If `highlight` is true, snippets of matched title and artist are returned. Fuzzy matches are not highlighted.
     async fn tracks<'ctx>(
         &self,
         ctx: &Context<'ctx>,
         keyword: String,
         #[graphql(default = 20)] count: usize,
         #[graphql(default = 0)] offset: usize,
+        fuzzy: Option<u8>,
+        #[graphql(default = false)] highlight: bool,
     ) -> anyhow::Result<Vec<TrackSearchResult>> {
+        QueryLimits::check_page_size(ctx, "count", Some(count as u64))?;
         let search_manager = ctx.data::<RepositorySearchManager>().unwrap();
 
-        let query = search_manager.query_parser().parse_query(&keyword)?;
+        let query = match fuzzy {
+            Some(distance) if distance > 0 => search_manager.fuzzy_query(&keyword, distance)?,
+            _ => search_manager.query_parser().parse_query(&keyword)?,
+        };
         let query_not = TermQuery::new(
             Term::from_field_i64(search_manager.fields.track_db_id, i64::MAX),
             Default::default(),
@@ -175,11 +299,30 @@ impl MetadataQuery {
 
         let searcher = search_manager.searcher();
         let top_docs = searcher.search(&query, &TopDocs::with_limit(count).and_offset(offset))?;
+        let snippet_generators = if highlight {
+            let fields = &search_manager.fields;
+            Some((
+                SnippetGenerator::create(&searcher, &query, fields.title)?,
+                SnippetGenerator::create(&searcher, &query, fields.artist)?,
+            ))
+        } else {
+            None
+        };
 
         let result: Vec<_> = top_docs
             .into_iter()
             .filter_map(|(score, addr)| {
-                let doc = searcher.doc(addr).ok()?;
+                let doc: TantivyDocument = searcher.doc(addr).ok()?;
+                // fields without matched terms have no snippet
+                let snippet = |generator: &SnippetGenerator| {
+                    let snippet = generator.snippet_from_doc(&doc);
+                    (!snippet.highlighted().is_empty()).then(|| snippet.to_html())
+                };
+                let (title_snippet, artist_snippet) = match &snippet_generators {
+                    Some((title, artist)) => (snippet(title), snippet(artist)),
+                    None => (None, None),
+                };
+
                 let (album_db_id, disc_db_id, track_db_id) =
                     search_manager.deserialize_document(doc);
                 if disc_db_id.is_none() || track_db_id.is_none() {
@@ -191,6 +334,8 @@ impl MetadataQuery {
                     album_db_id,
                     disc_db_id: disc_db_id.unwrap(),
                     track_db_id: track_db_id.unwrap(),
+                    title_snippet,
+                    artist_snippet,
                 })
             })
             .collect();
@@ -217,6 +362,297 @@ impl MetadataQuery {
             .await?;
         Ok(model.into_iter().map(|model| TagInfo(model)).collect())
     }
+
+    /// Walk the hierarchy of tag `tag_id`, returning its transitive parents and children
+    /// up to `depth` levels (default 1, at most 16) in breadth-first order.
+    ///
+    /// Each tag is visited at most once in each direction, so cyclic relations are cut off.
+    async fn tag_tree<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        tag_id: ID,
+        depth: Option<i32>,
+    ) -> anyhow::Result<Vec<TagTreeNode>> {
+        const MAX_DEPTH: i32 = 16;
+
+        let db = ctx.data::<DatabaseConnection>().unwrap();
+        let root_db_id = tag_id.parse::<i32>()?;
+        if tag_info::Entity::find_by_id(root_db_id)
+            .one(db)
+            .await?
+            .is_none()
+        {
+            anyhow::bail!("Tag {root_db_id} not found");
+        }
+
+        let depth = depth.unwrap_or(1).clamp(0, MAX_DEPTH);
+        let mut nodes = Vec::new();
+        for direction in [TagTreeDirection::Parent, TagTreeDirection::Child] {
+            let from_column = match direction {
+                TagTreeDirection::Parent => tag_relation::Column::TagDbId,
+                TagTreeDirection::Child => tag_relation::Column::ParentTagDbId,
+            };
+
+            let mut visited = HashSet::from([root_db_id]);
+            let mut frontier = vec![root_db_id];
+            for level in 1..=depth {
+                let relations = tag_relation::Entity::find()
+                    .filter(from_column.is_in(frontier))
+                    .order_by_asc(tag_relation::Column::Id)
+                    .all(db)
+                    .await?;
+
+                // (from, to) pairs of tags reached for the first time
+                let edges: Vec<_> = relations
+                    .into_iter()
+                    .map(|relation| match direction {
+                        TagTreeDirection::Parent => (relation.tag_db_id, relation.parent_tag_db_id),
+                        TagTreeDirection::Child => (relation.parent_tag_db_id, relation.tag_db_id),
+                    })
+                    .filter(|(_, to)| visited.insert(*to))
+                    .collect();
+                if edges.is_empty() {
+                    break;
+                }
+
+                let mut tags: HashMap<_, _> = tag_info::Entity::find()
+                    .filter(tag_info::Column::Id.is_in(edges.iter().map(|(_, to)| *to)))
+                    .all(db)
+                    .await?
+                    .into_iter()
+                    .map(|tag| (tag.id, tag))
+                    .collect();
+                frontier = edges.iter().map(|(_, to)| *to).collect();
+                for (from_db_id, to_db_id) in edges {
+                    if let Some(tag) = tags.remove(&to_db_id) {
+                        nodes.push(TagTreeNode {
+                            tag,
+                            from_db_id,
+                            direction,
+                            depth: level,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(nodes)
+    }
+}
+
+/// Order of album search results: higher score first, then smaller album db id.
+fn search_order(a: &(f32, i32), b: &(f32, i32)) -> std::cmp::Ordering {
+    b.0.total_cmp(&a.0).then(a.1.cmp(&b.1))
+}
+
+/// Search albums by `keyword` in search index.
+///
+/// Cursor of each album is `(score, album_db_id)`, so pagination is stable as long as the index is unchanged.
+async fn search_albums(
+    db: &DatabaseConnection,
+    search_manager: &RepositorySearchManager,
+    keyword: &str,
+    after: Option<String>,
+    limit: u64,
+) -> anyhow::Result<AlbumConnection> {
+    let query = search_manager.query_parser().parse_query(keyword)?;
+    let query_album = TermQuery::new(
+        Term::from_field_i64(search_manager.fields.disc_db_id, i64::MAX),
+        Default::default(),
+    );
+    let query = BooleanQuery::new(vec![
+        (Occur::Must, query),
+        // AND disc_db_id:9223372036854775807, which does not affect score
+        (
+            Occur::Must,
+            Box::new(ConstScoreQuery::new(Box::new(query_album), 0.0)),
+        ),
+    ]);
+
+    let searcher = search_manager.searcher();
+    let count = searcher.search(&query, &Count)?;
+    let mut hits = Vec::with_capacity(count);
+    if count > 0 {
+        for (score, addr) in searcher.search(&query, &TopDocs::with_limit(count))? {
+            let (album_db_id, _, _) = search_manager.deserialize_document(searcher.doc(addr)?);
+            hits.push((score, album_db_id as i32));
+        }
+    }
+    hits.sort_by(search_order);
+    let mut seen = HashSet::new();
+    hits.retain(|(_, album_db_id)| seen.insert(*album_db_id));
+
+    if let Some(cursor) = after {
+        let cursor = match Cursor::from_str(&cursor)?.into_inner().as_slice() {
+            [sea_orm::Value::Float(Some(score)), sea_orm::Value::Int(Some(album_db_id))] => {
+                (*score, *album_db_id)
+            }
+            _ => anyhow::bail!("Invalid cursor for keyword search"),
+        };
+        hits.retain(|hit| search_order(hit, &cursor).is_gt());
+    }
+
+    let has_next_page = hits.len() > limit as usize;
+    hits.truncate(limit as usize);
+
+    let mut albums: HashMap<_, _> = album::Entity::find()
+        .filter(album::Column::Id.is_in(hits.iter().map(|(_, album_db_id)| *album_db_id)))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|model| (model.id, model))
+        .collect();
+    // albums removed from database might still exist in search index
+    let edges: Vec<_> = hits
+        .into_iter()
+        .filter_map(|(score, album_db_id)| {
+            let model = albums.remove(&album_db_id)?;
+            let cursor = Cursor::new(vec![
+                sea_orm::Value::Float(Some(score)),
+                sea_orm::Value::Int(Some(album_db_id)),
+            ]);
+            Some(Edge::with_additional_fields(
+                cursor.to_string(),
+                AlbumInfo(model),
+                AlbumEdgeFields { score: Some(score) },
+            ))
+        })
+        .collect();
+
+    let mut connection = Connection::new(false, has_next_page);
+    connection.edges.extend(edges);
+    Ok(connection)
+}
+
+/// Parse tag ids, with duplicated ids removed.
+fn parse_tags_id(tags: &[ID]) -> anyhow::Result<Vec<i32>> {
+    let mut seen = HashSet::new();
+    let tags_id = tags
+        .iter()
+        .map(|id| {
+            id.parse::<i32>()
+                .map_err(|_| anyhow::anyhow!("Invalid tag id `{}`", id.as_str()))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(tags_id.into_iter().filter(|id| seen.insert(*id)).collect())
+}
+
+/// Make sure all tags in `tags_id` exist.
+async fn ensure_tags_exist<C>(db: &C, tags_id: &[i32]) -> anyhow::Result<()>
+where
+    C: ConnectionTrait,
+{
+    if tags_id.is_empty() {
+        return Ok(());
+    }
+
+    let found: HashSet<i32> = tag_info::Entity::find()
+        .select_only()
+        .column(tag_info::Column::Id)
+        .filter(tag_info::Column::Id.is_in(tags_id.to_vec()))
+        .into_tuple()
+        .all(db)
+        .await?
+        .into_iter()
+        .collect();
+    if let Some(missing) = tags_id.iter().find(|id| !found.contains(id)) {
+        anyhow::bail!("Tag {missing} not found");
+    }
+    Ok(())
+}
+
+/// Parse new order of discs or tracks.
+///
+/// `order` must contain each of `existing` exactly once.
+fn parse_order(order: &[ID], existing: &[i32]) -> anyhow::Result<Vec<i32>> {
+    let order = order
+        .iter()
+        .map(|id| id.parse::<i32>())
+        .collect::<Result<Vec<_>, _>>()?;
+    let ids: HashSet<_> = order.iter().collect();
+    if ids.len() != order.len() {
+        anyhow::bail!("Duplicated ids in order");
+    }
+    if order.len() != existing.len() || existing.iter().any(|id| !ids.contains(id)) {
+        anyhow::bail!("Ids in order do not match existing ones");
+    }
+    Ok(order)
+}
+
+/// Replace tags of an album, disc or track with `tags_id`.
+///
+/// Returns db id of the album which the target belongs to.
+async fn replace_metadata_tags<C>(
+    db: &C,
+    input: MetadataIDInput,
+    tags_id: &[i32],
+) -> anyhow::Result<i32>
+where
+    C: ConnectionTrait,
+{
+    let (album_db_id, disc_db_id, track_db_id) = match input {
+        MetadataIDInput::Album(album_db_id) => {
+            let album_db_id = album_db_id.parse::<i32>()?;
+            album_tag_relation::Entity::delete_many()
+                .filter(
+                    album_tag_relation::Column::AlbumDbId
+                        .eq(album_db_id)
+                        .and(album_tag_relation::Column::DiscDbId.is_null())
+                        .and(album_tag_relation::Column::TrackDbId.is_null()),
+                )
+                .exec(db)
+                .await?;
+
+            (album_db_id, None, None)
+        }
+        MetadataIDInput::Disc(disc_db_id) => {
+            let disc_db_id = disc_db_id.parse::<i32>()?;
+            album_tag_relation::Entity::delete_many()
+                .filter(
+                    album_tag_relation::Column::DiscDbId
+                        .eq(disc_db_id)
+                        .and(album_tag_relation::Column::TrackDbId.is_null()),
+                )
+                .exec(db)
+                .await?;
+
+            let disc = disc::Entity::find_by_id(disc_db_id)
+                .one(db)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("Disc {disc_db_id} not found"))?;
+            (disc.album_db_id, Some(disc_db_id), None)
+        }
+        MetadataIDInput::Track(track_db_id) => {
+            let track_db_id = track_db_id.parse::<i32>()?;
+            album_tag_relation::Entity::delete_many()
+                .filter(album_tag_relation::Column::TrackDbId.eq(track_db_id))
+                .exec(db)
+                .await?;
+
+            let track = track::Entity::find_by_id(track_db_id)
+                .one(db)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("Track {track_db_id} not found"))?;
+            (track.album_db_id, Some(track.disc_db_id), Some(track_db_id))
+        }
+    };
+
+    ensure_tags_exist(db, tags_id).await?;
+    if !tags_id.is_empty() {
+        album_tag_relation::Entity::insert_many(tags_id.iter().map(|&tag_id| {
+            album_tag_relation::ActiveModel {
+                album_db_id: ActiveValue::set(album_db_id),
+                disc_db_id: ActiveValue::set(disc_db_id),
+                track_db_id: ActiveValue::set(track_db_id),
+                tag_db_id: ActiveValue::set(tag_id),
+                ..Default::default()
+            }
+        }))
+        .exec(db)
+        .await?;
+    }
+
+    Ok(album_db_id)
 }
 
 pub struct MetadataMutation;
@@ -238,7 +674,8 @@ impl MetadataMutation {
         let album = album::ActiveModel {
             album_id: ActiveValue::set(input.album_id.unwrap_or_else(|| Uuid::new_v4())),
             title: ActiveValue::set(input.title),
-            edition: ActiveValue::set(input.edition),
+            // empty edition is the same as no edition
+            edition: ActiveValue::set(input.edition.filter(|e| !e.is_empty())),
             catalog: ActiveValue::set(input.catalog),
             artist: ActiveValue::set(input.artist),
             release_year: ActiveValue::set(input.release_year),
@@ -259,9 +696,14 @@ impl MetadataMutation {
                 .await?;
         }
 
+        let pending = mark_pending(&txn, album_db_id).await?;
         txn.commit().await?;
         if commit {
             index_writer.commit().await?;
+            searcher.clear_pending(db, pending).await?;
+        } else {
+            // cleared after any later commit, or recovered on next startup if none
+            index_writer.defer_pending(pending);
         }
 
         Ok(AlbumInfo(album))
@@ -287,9 +729,16 @@ impl MetadataMutation {
 
         let need_update_search_index = input.title.is_some() || input.artist.is_some();
         let album: album::ActiveModel = model.into();
-        let album = input.update(album, db).await?;
+        let txn = db.begin().await?;
+        let album = input.update(album, &txn).await?;
+        let pending = if need_update_search_index {
+            Some(mark_pending(&txn, album.id).await?)
+        } else {
+            None
+        };
+        txn.commit().await?;
 
-        if need_update_search_index {
+        if let Some(pending) = pending {
             let searcher = ctx.data::<RepositorySearchManager>().unwrap();
             let index_writer = searcher.writer().await;
             let query = PhraseQuery::new(vec![
@@ -306,6 +755,7 @@ impl MetadataMutation {
                 None,
             ))?;
             index_writer.commit().await?;
+            searcher.clear_pending(db, pending).await?;
         }
 
         Ok(Some(AlbumInfo(album)))
@@ -327,9 +777,16 @@ impl MetadataMutation {
 
         let need_update_search_index = input.title.is_some() || input.artist.is_some();
         let disc: disc::ActiveModel = model.into();
-        let disc = input.update(disc, db).await?;
+        let txn = db.begin().await?;
+        let disc = input.update(disc, &txn).await?;
+        let pending = if need_update_search_index {
+            Some(mark_pending(&txn, disc.album_db_id).await?)
+        } else {
+            None
+        };
+        txn.commit().await?;
 
-        if need_update_search_index {
+        if let Some(pending) = pending {
             let searcher = ctx.data::<RepositorySearchManager>().unwrap();
             let index_writer = searcher.writer().await;
             let query = PhraseQuery::new(vec![
@@ -346,6 +803,7 @@ impl MetadataMutation {
                 None,
             ))?;
             index_writer.commit().await?;
+            searcher.clear_pending(db, pending).await?;
         }
         Ok(Some(DiscInfo(disc)))
     }
@@ -366,9 +824,16 @@ impl MetadataMutation {
 
         let need_update_search_index = input.title.is_some() || input.artist.is_some();
         let track: track::ActiveModel = model.into();
-        let track = input.update(track, db).await?;
+        let txn = db.begin().await?;
+        let track = input.update(track, &txn).await?;
+        let pending = if need_update_search_index {
+            Some(mark_pending(&txn, track.album_db_id).await?)
+        } else {
+            None
+        };
+        txn.commit().await?;
 
-        if need_update_search_index {
+        if let Some(pending) = pending {
             let searcher = ctx.data::<RepositorySearchManager>().unwrap();
             let index_writer = searcher.writer().await;
             let query = PhraseQuery::new(vec![
@@ -385,10 +850,69 @@ impl MetadataMutation {
                 Some(track.id as i64),
             ))?;
             index_writer.commit().await?;
+            searcher.clear_pending(db, pending).await?;
         }
         Ok(Some(TrackInfo(track)))
     }
 
+    /// Delete an album, including its discs, tracks and tags.
+    ///
+    /// Returns the deleted album, or `null` if the album does not exist.
+    async fn delete_album<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        id: ID,
+    ) -> anyhow::Result<Option<AlbumInfo>> {
+        let db = ctx.data::<DatabaseConnection>().unwrap();
+        let searcher = ctx.data::<RepositorySearchManager>().unwrap();
+        let index_writer = searcher.writer().await;
+
+        let Some(album) = album::Entity::find_by_id(id.parse::<i32>()?)
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let album_db_id = album.id;
+        let txn = db.begin().await?;
+
+        // 1. remove tag relations
+        album_tag_relation::Entity::delete_many()
+            .filter(album_tag_relation::Column::AlbumDbId.eq(album_db_id))
+            .exec(&txn)
+            .await?;
+
+        // 2. remove tracks
+        track::Entity::delete_many()
+            .filter(track::Column::AlbumDbId.eq(album_db_id))
+            .exec(&txn)
+            .await?;
+
+        // 3. remove discs
+        disc::Entity::delete_many()
+            .filter(disc::Column::AlbumDbId.eq(album_db_id))
+            .exec(&txn)
+            .await?;
+
+        // 4. remove album
+        album::Entity::delete_by_id(album_db_id).exec(&txn).await?;
+
+        // 5. delete indexes of album, discs and tracks
+        let query = TermQuery::new(
+            Term::from_field_i64(searcher.fields.album_db_id, album_db_id as i64),
+            Default::default(),
+        );
+        index_writer.delete_query(Box::new(query))?;
+
+        let pending = mark_pending(&txn, album_db_id).await?;
+        txn.commit().await?;
+        index_writer.commit().await?;
+        searcher.clear_pending(db, pending).await?;
+
+        Ok(Some(AlbumInfo(album)))
+    }
+
     /// Replace discs of an album.
     ///
     /// This method only works if the organize level of the album is INITIAL.
@@ -449,8 +973,10 @@ impl MetadataMutation {
                 .await?;
         }
 
+        let pending = mark_pending(&txn, album_db_id).await?;
         txn.commit().await?;
         index_writer.commit().await?;
+        searcher.clear_pending(db, pending).await?;
 
         Ok(Some(AlbumInfo(album)))
     }
@@ -519,15 +1045,165 @@ impl MetadataMutation {
                 .await?;
         }
 
+        let pending = mark_pending(&txn, album_db_id).await?;
         txn.commit().await?;
         index_writer.commit().await?;
+        searcher.clear_pending(db, pending).await?;
+
+        Ok(Some(DiscInfo(disc)))
+    }
+
+    /// Reorder discs of an album.
+    ///
+    /// `order` must contain ids of all discs in the album exactly once.
+    /// This method only works if the organize level of the album is INITIAL.
+    async fn reorder_discs<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        album_id: ID,
+        order: Vec<ID>,
+    ) -> anyhow::Result<Option<AlbumInfo>> {
+        let db = ctx.data::<DatabaseConnection>().unwrap();
+
+        let Some(album) = album::Entity::find_by_id(album_id.parse::<i32>()?)
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let level = MetadataOrganizeLevel::from_str(&album.level.to_string())?;
+        if level != MetadataOrganizeLevel::Initial {
+            anyhow::bail!("Cannot reorder discs of an album with organize level {level:?}",);
+        }
+
+        let existing: Vec<i32> = disc::Entity::find()
+            .filter(disc::Column::AlbumDbId.eq(album.id))
+            .select_only()
+            .column(disc::Column::Id)
+            .into_tuple()
+            .all(db)
+            .await?;
+        let order = parse_order(&order, &existing)?;
+
+        let txn = db.begin().await?;
+        for (index, disc_db_id) in order.into_iter().enumerate() {
+            disc::ActiveModel {
+                id: ActiveValue::unchanged(disc_db_id),
+                index: ActiveValue::set(index as i32),
+                updated_at: ActiveValue::set(now()),
+                ..Default::default()
+            }
+            .update(&txn)
+            .await?;
+        }
+        txn.commit().await?;
+
+        Ok(Some(AlbumInfo(album)))
+    }
+
+    /// Reorder tracks of a disc.
+    ///
+    /// `order` must contain ids of all tracks in the disc exactly once.
+    /// This method only works if the organize level of the album is INITIAL.
+    async fn reorder_tracks<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        disc_id: ID,
+        order: Vec<ID>,
+    ) -> anyhow::Result<Option<DiscInfo>> {
+        let db = ctx.data::<DatabaseConnection>().unwrap();
+
+        let Some(disc) = disc::Entity::find_by_id(disc_id.parse::<i32>()?)
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let level: String = album::Entity::find_by_id(disc.album_db_id)
+            .select_only()
+            .column(album::Column::Level)
+            .into_tuple()
+            .one(db)
+            .await?
+            .unwrap();
+        let level = MetadataOrganizeLevel::from_str(&level)?;
+        if level != MetadataOrganizeLevel::Initial {
+            anyhow::bail!("Cannot reorder tracks of an album with organize level {level:?}",);
+        }
+
+        let existing: Vec<i32> = track::Entity::find()
+            .filter(track::Column::DiscDbId.eq(disc.id))
+            .select_only()
+            .column(track::Column::Id)
+            .into_tuple()
+            .all(db)
+            .await?;
+        let order = parse_order(&order, &existing)?;
+
+        let txn = db.begin().await?;
+        for (index, track_db_id) in order.into_iter().enumerate() {
+            track::ActiveModel {
+                id: ActiveValue::unchanged(track_db_id),
+                index: ActiveValue::set(index as i32),
+                updated_at: ActiveValue::set(now()),
+                ..Default::default()
+            }
+            .update(&txn)
+            .await?;
+        }
+        txn.commit().await?;
 
         Ok(Some(DiscInfo(disc)))
     }
 
+    /// Set cover of an album or disc to an object key or URL.
+    ///
+    /// Passing `null` or an empty string as `url` removes the cover.
+    /// Returns the album which the target belongs to.
+    async fn set_cover(
+        &self,
+        ctx: &Context<'_>,
+        id: MetadataIDInput,
+        url: Option<String>,
+    ) -> anyhow::Result<Option<AlbumInfo>> {
+        let db = ctx.data::<DatabaseConnection>().unwrap();
+        let url = url.filter(|url| !url.is_empty());
+
+        let album_db_id = match id {
+            MetadataIDInput::Album(id) => {
+                let Some(album) = album::Entity::find_by_id(id.parse::<i32>()?)
+                    .one(db)
+                    .await?
+                else {
+                    return Ok(None);
+                };
+                let mut album: album::ActiveModel = album.into();
+                album.cover_url = ActiveValue::set(url);
+                album.updated_at = ActiveValue::set(now());
+                let album = album.update(db).await?;
+                return Ok(Some(AlbumInfo(album)));
+            }
+            MetadataIDInput::Disc(id) => {
+                let Some(disc) = disc::Entity::find_by_id(id.parse::<i32>()?).one(db).await? else {
+                    return Ok(None);
+                };
+                let mut disc: disc::ActiveModel = disc.into();
+                disc.cover_url = ActiveValue::set(url);
+                disc.updated_at = ActiveValue::set(now());
+                disc.update(db).await?.album_db_id
+            }
+            MetadataIDInput::Track(_) => anyhow::bail!("Cover of tracks is not supported"),
+        };
+
+        let album = album::Entity::find_by_id(album_db_id).one(db).await?;
+        Ok(album.map(AlbumInfo))
+    }
+
     /// Update organize level of an album.
     ///
-    /// The organize level should only increase. However, it is not enforced by the server.
+    /// The organize level should only increase. Decreasing it is rejected unless `allowDowngrade` is set.
     async fn update_organize_level<'ctx>(
         &self,
         ctx: &Context<'ctx>,
@@ -566,6 +1242,13 @@ impl MetadataMutation {
                 input.level
             ),
         }
+        if input.level < original_level && !input.allow_downgrade {
+            anyhow::bail!(
+                "Cannot decrease organize level from {:?} to {:?} without allowDowngrade",
+                original_level,
+                input.level
+            );
+        }
 
         // make Disc::Index and Track::Index sequential when organize level increases
         if original_level == MetadataOrganizeLevel::Initial {
@@ -612,15 +1295,34 @@ impl MetadataMutation {
     }
 
     /// Add a new tag `type:name` to the database.
+    ///
+    /// If the tag already exists, it is returned when `upsert` is `true` (default),
+    /// otherwise an error is returned.
     async fn add_tag<'ctx>(
         &self,
         ctx: &Context<'ctx>,
         name: String,
         r#type: TagType,
+        upsert: Option<bool>,
     ) -> anyhow::Result<TagInfo> {
         let db = ctx.data::<DatabaseConnection>().unwrap();
 
-        // TODO: return if already exists
+        let existing = tag_info::Entity::find()
+            .filter(
+                tag_info::Column::Name
+                    .eq(name.as_str())
+                    .and(tag_info::Column::Type.eq(r#type.to_string())),
+            )
+            .one(db)
+            .await?;
+        if let Some(tag) = existing {
+            if !upsert.unwrap_or(true) {
+                anyhow::bail!("Tag {}:{name} already exists", r#type.to_string());
+            }
+            return Ok(TagInfo(tag));
+        }
+
+        // concurrent insertions of the same tag are rejected by unique index `idx-tag-name-type`
         let tag = tag_info::ActiveModel {
             name: ActiveValue::set(name),
             r#type: ActiveValue::set(r#type.into()),
@@ -664,6 +1366,8 @@ impl MetadataMutation {
     }
 
     /// Update tags of an album, disc or track.
+    ///
+    /// Existing tags of the target are replaced by `tags`.
     async fn update_metadata_tags(
         &self,
         ctx: &Context<'_>,
@@ -671,86 +1375,11 @@ impl MetadataMutation {
         tags: Vec<ID>,
     ) -> anyhow::Result<AlbumInfo> {
         let db = ctx.data::<DatabaseConnection>().unwrap();
-        let tags_id = tags
-            .iter()
-            .map(|id| id.parse::<i32>())
-            .collect::<Result<Vec<_>, _>>()?;
-
-        let album_db_id = match input {
-            MetadataIDInput::Album(album_db_id) => {
-                let album_db_id = album_db_id.parse::<i32>()?;
-                album_tag_relation::Entity::delete_many()
-                    .filter(
-                        album_tag_relation::Column::AlbumDbId
-                            .eq(album_db_id)
-                            .and(album_tag_relation::Column::DiscDbId.is_null())
-                            .and(album_tag_relation::Column::TrackDbId.is_null()),
-                    )
-                    .exec(db)
-                    .await?;
-                album_tag_relation::Entity::insert_many(tags_id.into_iter().map(|tag_id| {
-                    album_tag_relation::ActiveModel {
-                        album_db_id: ActiveValue::set(album_db_id),
-                        tag_db_id: ActiveValue::set(tag_id),
-                        ..Default::default()
-                    }
-                }))
-                .exec(db)
-                .await?;
-
-                album_db_id
-            }
-            MetadataIDInput::Disc(disc_db_id) => {
-                let disc_db_id = disc_db_id.parse::<i32>()?;
-                album_tag_relation::Entity::delete_many()
-                    .filter(
-                        album_tag_relation::Column::DiscDbId
-                            .eq(disc_db_id)
-                            .and(album_tag_relation::Column::TrackDbId.is_null()),
-                    )
-                    .exec(db)
-                    .await?;
-
-                let disc = disc::Entity::find_by_id(disc_db_id).one(db).await?.unwrap();
-                album_tag_relation::Entity::insert_many(tags_id.into_iter().map(|tag_id| {
-                    album_tag_relation::ActiveModel {
-                        album_db_id: ActiveValue::set(disc.album_db_id),
-                        disc_db_id: ActiveValue::set(Some(disc_db_id)),
-                        tag_db_id: ActiveValue::set(tag_id),
-                        ..Default::default()
-                    }
-                }))
-                .exec(db)
-                .await?;
-
-                disc.album_db_id
-            }
-            MetadataIDInput::Track(track_db_id) => {
-                let track_db_id = track_db_id.parse::<i32>()?;
-                album_tag_relation::Entity::delete_many()
-                    .filter(album_tag_relation::Column::TrackDbId.eq(track_db_id))
-                    .exec(db)
-                    .await?;
+        let tags_id = parse_tags_id(&tags)?;
 
-                let track = track::Entity::find_by_id(track_db_id)
-                    .one(db)
-                    .await?
-                    .unwrap();
-                album_tag_relation::Entity::insert_many(tags_id.into_iter().map(|tag_id| {
-                    album_tag_relation::ActiveModel {
-                        album_db_id: ActiveValue::set(track.album_db_id),
-                        disc_db_id: ActiveValue::set(Some(track.disc_db_id)),
-                        track_db_id: ActiveValue::set(Some(track_db_id)),
-                        tag_db_id: ActiveValue::set(tag_id),
-                        ..Default::default()
-                    }
-                }))
-                .exec(db)
-                .await?;
-
-                track.album_db_id
-            }
-        };
+        let txn = db.begin().await?;
+        let album_db_id = replace_metadata_tags(&txn, input, &tags_id).await?;
+        txn.commit().await?;
 
         let album = album::Entity::find_by_id(album_db_id)
             .one(db)
@@ -759,77 +1388,110 @@ impl MetadataMutation {
         Ok(AlbumInfo(album))
     }
 
-    async fn rebuild_search_index(&self, ctx: &Context<'_>) -> anyhow::Result<bool> {
+    /// Update tags of multiple albums, discs or tracks in a single transaction.
+    ///
+    /// Existing tags of each target are replaced by `tags`.
+    /// If any of the targets fails to update, tags of all targets are left unchanged.
+    /// Returns affected albums without duplicates.
+    async fn update_metadata_tags_batch(
+        &self,
+        ctx: &Context<'_>,
+        inputs: Vec<MetadataIDInput>,
+        tags: Vec<ID>,
+    ) -> anyhow::Result<Vec<AlbumInfo>> {
         let db = ctx.data::<DatabaseConnection>().unwrap();
-        let searcher = ctx.data::<RepositorySearchManager>().unwrap();
+        let tags_id = parse_tags_id(&tags)?;
 
-        // 1. clear search index
-        let writer = searcher.writer().await;
-        writer.delete_all()?;
+        let txn = db.begin().await?;
+        let mut albums_db_id = Vec::new();
+        for input in inputs {
+            let album_db_id = replace_metadata_tags(&txn, input, &tags_id).await?;
+            if !albums_db_id.contains(&album_db_id) {
+                albums_db_id.push(album_db_id);
+            }
+        }
+        txn.commit().await?;
 
-        // 2. insert albums
-        let albums: Vec<(i32, String, String)> = album::Entity::find()
-            .select_only()
-            .column(album::Column::Id)
-            .column(album::Column::Title)
-            .column(album::Column::Artist)
-            .into_tuple()
+        let albums = album::Entity::find()
+            .filter(album::Column::Id.is_in(albums_db_id))
             .all(db)
             .await?;
-        for (album_db_id, title, artist) in albums {
-            writer.add_document(searcher.build_track_document(
-                &title,
-                &artist,
-                album_db_id as i64,
-                None,
-                None,
-            ))?;
-        }
+        Ok(albums.into_iter().map(AlbumInfo).collect())
+    }
 
-        // 3. insert discs
-        let discs: Vec<(i32, i32, Option<String>, Option<String>)> = disc::Entity::find()
-            .select_only()
-            .column(disc::Column::AlbumDbId)
-            .column(disc::Column::Id)
-            .column(disc::Column::Title)
-            .column(disc::Column::Artist)
-            .into_tuple()
-            .all(db)
+    /// Add tags to multiple albums in a single transaction.
+    ///
+    /// Unlike `updateMetadataTags`, existing tags of albums are kept.
+    /// Tags which are already attached to an album are skipped.
+    async fn add_tags_to_albums(
+        &self,
+        ctx: &Context<'_>,
+        album_ids: Vec<ID>,
+        tags: Vec<ID>,
+    ) -> anyhow::Result<Vec<AlbumInfo>> {
+        let db = ctx.data::<DatabaseConnection>().unwrap();
+        let albums_db_id = album_ids
+            .iter()
+            .map(|id| id.parse::<i32>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let tags_id = parse_tags_id(&tags)?;
+
+        let txn = db.begin().await?;
+
+        // 1. make sure all albums and tags exist
+        let albums = album::Entity::find()
+            .filter(album::Column::Id.is_in(albums_db_id.clone()))
+            .all(&txn)
             .await?;
-        for (album_db_id, disc_db_id, title, artist) in discs {
-            writer.add_document(searcher.build_track_document(
-                &title.unwrap_or_default(),
-                &artist.unwrap_or_default(),
-                album_db_id as i64,
-                Some(disc_db_id as i64),
-                None,
-            ))?;
+        let found: HashSet<_> = albums.iter().map(|album| album.id).collect();
+        if let Some(missing) = albums_db_id.iter().find(|id| !found.contains(id)) {
+            anyhow::bail!("Album {missing} not found");
         }
+        ensure_tags_exist(&txn, &tags_id).await?;
 
-        // 4. insert tracks
-        let tracks: Vec<(i32, i32, i32, String, String)> = track::Entity::find()
+        // 2. find existing album-level relations
+        let mut existing: HashSet<(i32, i32)> = album_tag_relation::Entity::find()
             .select_only()
-            .column(track::Column::AlbumDbId)
-            .column(track::Column::DiscDbId)
-            .column(track::Column::Id)
-            .column(track::Column::Title)
-            .column(track::Column::Artist)
+            .column(album_tag_relation::Column::AlbumDbId)
+            .column(album_tag_relation::Column::TagDbId)
+            .filter(
+                album_tag_relation::Column::AlbumDbId
+                    .is_in(albums_db_id.clone())
+                    .and(album_tag_relation::Column::DiscDbId.is_null())
+                    .and(album_tag_relation::Column::TrackDbId.is_null()),
+            )
             .into_tuple()
-            .all(db)
-            .await?;
-        for (album_db_id, disc_db_id, track_db_id, title, artist) in tracks {
-            writer.add_document(searcher.build_track_document(
-                &title,
-                &artist,
-                album_db_id as i64,
-                Some(disc_db_id as i64),
-                Some(track_db_id as i64),
-            ))?;
+            .all(&txn)
+            .await?
+            .into_iter()
+            .collect();
+
+        // 3. insert missing relations
+        let relations: Vec<_> = albums_db_id
+            .iter()
+            .flat_map(|&album_db_id| tags_id.iter().map(move |&tag_id| (album_db_id, tag_id)))
+            .filter(|relation| existing.insert(*relation))
+            .map(|(album_db_id, tag_id)| album_tag_relation::ActiveModel {
+                album_db_id: ActiveValue::set(album_db_id),
+                tag_db_id: ActiveValue::set(tag_id),
+                ..Default::default()
+            })
+            .collect();
+        if !relations.is_empty() {
+            album_tag_relation::Entity::insert_many(relations)
+                .exec(&txn)
+                .await?;
         }
 
-        // 5. commit
-        writer.commit().await?;
+        txn.commit().await?;
+        Ok(albums.into_iter().map(AlbumInfo).collect())
+    }
+
+    async fn rebuild_search_index(&self, ctx: &Context<'_>) -> anyhow::Result<bool> {
+        let db = ctx.data::<DatabaseConnection>().unwrap();
+        let searcher = ctx.data::<RepositorySearchManager>().unwrap();
 
+        searcher.rebuild(db).await?;
         Ok(true)
     }
 }
@@ -1,6 +1,6 @@
 use std::str::FromStr;
 
-use async_graphql::{Context, Enum, Object, ID};
+use async_graphql::{Context, Enum, Object, SimpleObject, ID};
 use sea_orm::{
     prelude::{DateTimeUtc, Uuid},
     ColumnTrait, DatabaseConnection, EntityTrait, FromQueryResult, JoinType, QueryFilter,
@@ -31,7 +31,7 @@ impl AlbumInfo {
 
     /// Optional edition of the album.
     async fn edition(&self) -> Option<&str> {
-        self.0.edition.as_deref()
+        self.0.edition.as_deref().filter(|e| !e.is_empty())
     }
 
     /// Optional catalog number of the album.
@@ -101,6 +101,11 @@ impl AlbumInfo {
         self.0.extra.as_ref()
     }
 
+    /// Object key or URL of the album cover, set by `setCover`.
+    async fn cover_url(&self) -> Option<&str> {
+        self.0.cover_url.as_deref()
+    }
+
     /// Discs of the album.
     async fn discs<'ctx>(&self, ctx: &Context<'ctx>) -> anyhow::Result<Vec<DiscInfo>> {
         let db = ctx.data::<DatabaseConnection>().unwrap();
@@ -137,6 +142,12 @@ impl DiscInfo {
         self.0.artist.as_deref()
     }
 
+    /// Object key or URL of the disc cover, set by `setCover`.
+    /// Clients may fall back to the album cover if it is null.
+    async fn cover_url(&self) -> Option<&str> {
+        self.0.cover_url.as_deref()
+    }
+
     async fn tags<'ctx>(&self, ctx: &Context<'ctx>) -> anyhow::Result<Vec<TagInfo>> {
         let db = ctx.data::<DatabaseConnection>().unwrap();
         let models = album_tag_relation::Entity::find()
@@ -202,6 +213,11 @@ impl TrackInfo {
         (&self.0.r#type).into()
     }
 
+    /// Duration of the track in milliseconds, or null if unknown.
+    async fn duration(&self) -> Option<i32> {
+        self.0.duration_ms
+    }
+
     async fn artists(&self) -> Option<&serde_json::Value> {
         self.0.artists.as_ref()
     }
@@ -270,7 +286,7 @@ impl FromStr for TrackType {
     }
 }
 
-#[derive(Enum, Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Enum, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
 pub enum MetadataOrganizeLevel {
     /// Level 1: Initial organization. Principal errors may exist, such as mismatches in the number of album tracks.
     ///
@@ -448,11 +464,60 @@ impl TagRelation {
     }
 }
 
+/// Direction of an edge in a tag tree.
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum TagTreeDirection {
+    /// The node is a parent of the tag it is reached from.
+    Parent,
+    /// The node is a child of the tag it is reached from.
+    Child,
+}
+
+pub struct TagTreeNode {
+    pub(crate) tag: tag_info::Model,
+    pub(crate) from_db_id: i32,
+    pub(crate) direction: TagTreeDirection,
+    pub(crate) depth: i32,
+}
+
+#[Object]
+impl TagTreeNode {
+    async fn tag(&self) -> TagInfo {
+        TagInfo(self.tag.clone())
+    }
+
+    /// ID of the tag this node is reached from.
+    async fn from_id(&self) -> ID {
+        ID(self.from_db_id.to_string())
+    }
+
+    /// Direction of the edge from `fromId` to this node.
+    async fn direction(&self) -> TagTreeDirection {
+        self.direction
+    }
+
+    /// Distance from the root tag, starting from 1.
+    async fn depth(&self) -> i32 {
+        self.depth
+    }
+}
+
+/// Additional fields on edges of album connections.
+#[derive(SimpleObject)]
+pub struct AlbumEdgeFields {
+    /// Search score of the album, only available when albums are searched by keyword.
+    pub score: Option<f32>,
+}
+
 pub struct TrackSearchResult {
     pub score: f32,
     pub album_db_id: i64,
     pub disc_db_id: i64,
     pub track_db_id: i64,
+    /// Highlighted title, `None` if not requested or title does not match
+    pub title_snippet: Option<String>,
+    /// Highlighted artist, `None` if not requested or artist does not match
+    pub artist_snippet: Option<String>,
 }
 
 #[Object]
@@ -462,6 +527,18 @@ impl TrackSearchResult {
         self.score
     }
 
+    /// Return the track title in HTML with matched terms wrapped in `<b>`,
+    /// or null if highlight is not requested or the title does not match.
+    async fn title_snippet(&self) -> Option<&str> {
+        self.title_snippet.as_deref()
+    }
+
+    /// Return the track artist in HTML with matched terms wrapped in `<b>`,
+    /// or null if highlight is not requested or the artist does not match.
+    async fn artist_snippet(&self) -> Option<&str> {
+        self.artist_snippet.as_deref()
+    }
+
     /// Return a `TrackIdentifier` string which represents the track.
     async fn identifier(&self, ctx: &Context<'_>) -> anyhow::Result<String> {
         let db = ctx.data::<DatabaseConnection>().unwrap();
@@ -2,4 +2,5 @@ pub mod auth;
 pub mod entities;
 pub mod graphql;
 pub mod migrator;
+pub mod route;
 pub mod search;
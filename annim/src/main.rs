@@ -1,17 +1,12 @@
 use annim::{
-    auth::{on_connection_init, AuthToken},
-    graphql::{MetadataMutation, MetadataQuery, MetadataSchema},
-    search::RepositorySearchManager,
+    graphql::{build_schema, QueryLimits},
+    route::{graphql_handler, graphql_ws_handler},
+    search::{RepositorySearchManager, TokenizerConfig},
 };
-use async_graphql::{
-    http::{graphiql_source, ALL_WEBSOCKET_PROTOCOLS},
-    EmptySubscription,
-};
-use async_graphql_axum::{GraphQLProtocol, GraphQLRequest, GraphQLResponse, GraphQLWebSocket};
+use async_graphql::http::graphiql_source;
 use axum::{
-    extract::{State, WebSocketUpgrade},
-    http::{HeaderMap, Method},
-    response::{self, IntoResponse, Response},
+    http::Method,
+    response::{self, IntoResponse},
     routing::get,
     Router,
 };
@@ -25,38 +20,6 @@ async fn graphql_playground() -> impl IntoResponse {
     response::Html(graphiql_source("/", None))
 }
 
-fn get_token_from_headers(headers: &HeaderMap) -> Option<AuthToken> {
-    headers
-        .get("Authorization")
-        .and_then(|value| value.to_str().map(|s| AuthToken::new(s.to_string())).ok())
-}
-
-async fn graphql_handler(
-    State(schema): State<MetadataSchema>,
-    headers: HeaderMap,
-    req: GraphQLRequest,
-) -> GraphQLResponse {
-    let mut req = req.into_inner();
-    if let Some(token) = get_token_from_headers(&headers) {
-        req = req.data(token);
-    }
-    schema.execute(req).await.into()
-}
-
-async fn graphql_ws_handler(
-    State(schema): State<MetadataSchema>,
-    protocol: GraphQLProtocol,
-    websocket: WebSocketUpgrade,
-) -> Response {
-    websocket
-        .protocols(ALL_WEBSOCKET_PROTOCOLS)
-        .on_upgrade(move |stream| {
-            GraphQLWebSocket::new(stream, schema.clone(), protocol)
-                .on_connection_init(on_connection_init)
-                .serve()
-        })
-}
-
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt()
@@ -73,12 +36,22 @@ async fn main() -> anyhow::Result<()> {
 
     let searcher_directory = std::env::var("ANNIM_SEARCH_DIRECTORY")?;
     std::fs::create_dir_all(&searcher_directory)?;
-    let searcher = RepositorySearchManager::open_or_create(searcher_directory)?;
+    let tokenizer = TokenizerConfig {
+        user_dictionary: std::env::var_os("ANNIM_SEARCH_USER_DICTIONARY").map(Into::into),
+    };
+    let searcher = RepositorySearchManager::open_or_create(searcher_directory, &tokenizer)?;
+    if searcher.needs_rebuild() {
+        tracing::info!("Building search index from database");
+        searcher.rebuild(&database).await?;
+    } else {
+        // replay search index changes interrupted by last shutdown
+        let recovered = searcher.recover(&database).await?;
+        if recovered > 0 {
+            tracing::info!("Recovered search index of {recovered} album(s)");
+        }
+    }
 
-    let schema = MetadataSchema::build(MetadataQuery, MetadataMutation, EmptySubscription)
-        .data(database)
-        .data(searcher)
-        .finish();
+    let schema = build_schema(database, searcher, QueryLimits::from_env()?);
 
     let app = Router::new()
         .route("/", get(graphql_playground).post(graphql_handler))
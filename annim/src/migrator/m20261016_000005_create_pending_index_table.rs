@@ -0,0 +1,52 @@
+use sea_orm::DbErr;
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::helper::pk_foreign;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20261016_000005_create_pending_index_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create the `PendingIndex` table.
+        // No foreign key to album here, as deleted albums also need to be removed from search index.
+        manager
+            .create_table(
+                Table::create()
+                    .table(PendingIndex::Table)
+                    .col(pk_auto(PendingIndex::Id))
+                    .col(pk_foreign(PendingIndex::AlbumDbId))
+                    .col(timestamp(PendingIndex::CreatedAt).default(Expr::current_timestamp()))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PendingIndex::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+pub enum PendingIndex {
+    Table,
+    /// Pending Index Table ID
+    Id,
+    /// Album Table ID, whose search index is not committed yet
+    AlbumDbId,
+
+    // Metadata
+    CreatedAt,
+}
@@ -0,0 +1,63 @@
+use sea_orm::DbErr;
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::m20240817_000001_create_basic_tables::{Album, Disc};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20261016_000006_add_cover_url"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Album::Table)
+                    .add_column(string_null(Cover::CoverUrl))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Disc::Table)
+                    .add_column(string_null(Cover::CoverUrl))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Album::Table)
+                    .drop_column(Cover::CoverUrl)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Disc::Table)
+                    .drop_column(Cover::CoverUrl)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+pub enum Cover {
+    /// Object key or URL of the cover image, shared by `Album` and `Disc`
+    CoverUrl,
+}
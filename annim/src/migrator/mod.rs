@@ -5,6 +5,9 @@ mod m20240817_000001_create_basic_tables;
 mod m20240824_000002_create_tag_tables;
 mod m20240905_000003_add_tag_type_category;
 mod m20240905_000004_album_extra_jsonb;
+mod m20261016_000005_create_pending_index_table;
+mod m20261016_000006_add_cover_url;
+mod m20261016_000007_add_track_duration;
 
 pub struct Migrator;
 
@@ -16,6 +19,9 @@ impl MigratorTrait for Migrator {
             Box::new(m20240824_000002_create_tag_tables::Migration),
             Box::new(m20240905_000003_add_tag_type_category::Migration),
             Box::new(m20240905_000004_album_extra_jsonb::Migration),
+            Box::new(m20261016_000005_create_pending_index_table::Migration),
+            Box::new(m20261016_000006_add_cover_url::Migration),
+            Box::new(m20261016_000007_add_track_duration::Migration),
         ]
     }
 }
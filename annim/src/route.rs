@@ -0,0 +1,47 @@
+use async_graphql::{http::ALL_WEBSOCKET_PROTOCOLS, ServerError};
+use async_graphql_axum::{GraphQLProtocol, GraphQLRequest, GraphQLResponse, GraphQLWebSocket};
+use axum::{
+    extract::{State, WebSocketUpgrade},
+    http::HeaderMap,
+    response::Response,
+};
+
+use crate::{
+    auth::{on_connection_init, AuthToken},
+    graphql::MetadataSchema,
+};
+
+pub async fn graphql_handler(
+    State(schema): State<MetadataSchema>,
+    headers: HeaderMap,
+    req: GraphQLRequest,
+) -> GraphQLResponse {
+    let mut req = req.into_inner();
+    match AuthToken::from_headers(&headers) {
+        Ok(Some(token)) => req = req.data(token),
+        Ok(None) => {}
+        // reject malformed tokens before executing anything
+        Err(e) => {
+            return async_graphql::Response::from_errors(vec![ServerError::new(
+                e.to_string(),
+                None,
+            )])
+            .into()
+        }
+    }
+    schema.execute(req).await.into()
+}
+
+pub async fn graphql_ws_handler(
+    State(schema): State<MetadataSchema>,
+    protocol: GraphQLProtocol,
+    websocket: WebSocketUpgrade,
+) -> Response {
+    websocket
+        .protocols(ALL_WEBSOCKET_PROTOCOLS)
+        .on_upgrade(move |stream| {
+            GraphQLWebSocket::new(stream, schema.clone(), protocol)
+                .on_connection_init(on_connection_init)
+                .serve()
+        })
+}
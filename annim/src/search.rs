@@ -1,36 +1,69 @@
-use std::{path::Path, sync::Arc};
+use std::{
+    collections::BTreeSet,
+    path::Path,
+    sync::{Arc, Mutex},
+};
 
 use lindera_core::mode::Mode;
-use lindera_dictionary::{DictionaryConfig, DictionaryKind, DictionaryLoader};
+use lindera_dictionary::{
+    DictionaryConfig, DictionaryKind, DictionaryLoader, UserDictionaryConfig,
+};
 use lindera_tantivy::tokenizer::LinderaTokenizer;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr,
+    EntityTrait, QueryFilter, QuerySelect,
+};
 use tantivy::{
     directory::MmapDirectory,
     doc,
-    query::{Query, QueryParser},
+    query::{BooleanQuery, FuzzyTermQuery, Occur, Query, QueryParser, TermQuery},
     schema::{
         Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, Value, INDEXED, STORED,
     },
-    Index, IndexReader, IndexWriter, Opstamp, Searcher, TantivyDocument, TantivyError,
+    tokenizer::{Token, TokenStream},
+    Index, IndexReader, IndexWriter, Opstamp, Searcher, TantivyDocument, TantivyError, Term,
 };
 use tokio::sync::{RwLock, RwLockReadGuard};
 
-use crate::entities::{album, disc, track};
+use crate::entities::{album, disc, pending_index, track};
+
+/// Maximum edit distance of fuzzy search, which is also the largest distance supported by tantivy.
+pub const MAX_FUZZY_DISTANCE: u8 = 2;
+
+/// Dictionary used by the tokenizer of search index, shared with `anni-repo`.
+pub use anni_repo::search::TokenizerConfig;
 
 pub struct RepositorySearchManager {
     index: Index,
     index_reader: IndexReader,
     index_writer: Arc<RwLock<IndexWriter>>,
     query_parser: QueryParser,
+    /// Whether the index is created by [Self::open_or_create], so it does not contain albums in database.
+    created: bool,
+    /// Records created by [mark_pending] whose changes are written to, but not committed by the index.
+    deferred: Mutex<DeferredRecords>,
 
     pub fields: SearchFields,
 }
 
 impl RepositorySearchManager {
-    pub fn open_or_create<P>(directory_path: P) -> Result<Self, TantivyError>
+    pub fn open_or_create<P>(
+        directory_path: P,
+        tokenizer: &TokenizerConfig,
+    ) -> Result<Self, TantivyError>
     where
         P: AsRef<Path>,
     {
         let (schema, fields) = SearchFields::new();
+        let directory = MmapDirectory::open(directory_path.as_ref())?;
+        let mut created = !Index::exists(&directory)?;
+        if !created && Index::open(directory.clone())?.schema() != schema {
+            // search index is derived from database, so it is safe to recreate
+            tracing::warn!("Schema of search index changed, recreating");
+            std::fs::remove_dir_all(directory_path.as_ref())?;
+            std::fs::create_dir_all(directory_path.as_ref())?;
+            created = true;
+        }
         let directory = MmapDirectory::open(directory_path)?;
         let index = Index::open_or_create(directory, schema)?;
 
@@ -43,12 +76,19 @@ impl RepositorySearchManager {
             index_reader: reader,
             index_writer: Arc::new(RwLock::new(writer)),
             query_parser,
+            created,
+            deferred: Default::default(),
             fields,
         };
-        me.register_tokenizers();
+        me.register_tokenizers(tokenizer)?;
         Ok(me)
     }
 
+    /// Whether the index is newly created or recreated, and should be filled by [Self::rebuild].
+    pub fn needs_rebuild(&self) -> bool {
+        self.created
+    }
+
     pub async fn writer(&self) -> SearchWriter<'_> {
         SearchWriter {
             lock: self.index_writer.read().await,
@@ -60,21 +100,40 @@ impl RepositorySearchManager {
         let mut writer = self.index_writer.write().await;
         writer.commit()?;
         writer.garbage_collect_files().await?;
+        // make committed documents searchable immediately
+        self.index_reader.reload()?;
+
+        // still holding the writer lock, so no changes are deferred during commit
+        let mut deferred = self.deferred.lock().unwrap();
+        let uncommitted = std::mem::take(&mut deferred.uncommitted);
+        deferred.committed.extend(uncommitted);
 
         Ok(())
     }
 
-    fn register_tokenizers(&self) {
+    fn register_tokenizers(&self, config: &TokenizerConfig) -> Result<(), TantivyError> {
         let dictionary_config = DictionaryConfig {
             kind: Some(DictionaryKind::IPADIC),
             path: None,
         };
-        let dictionary = DictionaryLoader::load_dictionary_from_config(dictionary_config).unwrap();
+        let dictionary = DictionaryLoader::load_dictionary_from_config(dictionary_config)
+            .map_err(|e| TantivyError::InvalidArgument(e.to_string()))?;
+        let user_dictionary = match &config.user_dictionary {
+            Some(path) => Some(
+                DictionaryLoader::load_user_dictionary_from_config(UserDictionaryConfig {
+                    kind: Some(DictionaryKind::IPADIC),
+                    path: path.clone(),
+                })
+                .map_err(|e| TantivyError::InvalidArgument(e.to_string()))?,
+            ),
+            None => None,
+        };
 
         self.index.tokenizers().register(
             "lang_ja",
-            LinderaTokenizer::new(dictionary, None, Mode::Normal),
+            LinderaTokenizer::new(dictionary, user_dictionary, Mode::Normal),
         );
+        Ok(())
     }
 
     pub fn build_track_document(
@@ -102,6 +161,194 @@ impl RepositorySearchManager {
         &self.query_parser
     }
 
+    /// Build a query matching documents whose title or artist contains every token of `keyword`,
+    /// allowing at most `distance` edits for each token.
+    ///
+    /// `distance` is capped at [MAX_FUZZY_DISTANCE].
+    pub fn fuzzy_query(&self, keyword: &str, distance: u8) -> Result<Box<dyn Query>, TantivyError> {
+        let distance = distance.min(MAX_FUZZY_DISTANCE);
+
+        // title and artist share the same tokenizer
+        let mut tokenizer = self.index.tokenizer_for_field(self.fields.title)?;
+        let mut tokens = Vec::new();
+        tokenizer
+            .token_stream(keyword)
+            .process(&mut |token: &Token| {
+                if !token.text.trim().is_empty() {
+                    tokens.push(token.text.clone());
+                }
+            });
+
+        let fuzzy = |field, token: &str| -> Box<dyn Query> {
+            Box::new(FuzzyTermQuery::new(
+                Term::from_field_text(field, token),
+                distance,
+                true,
+            ))
+        };
+        let clauses = tokens
+            .iter()
+            .map(|token| -> (Occur, Box<dyn Query>) {
+                let fields = BooleanQuery::new(vec![
+                    (Occur::Should, fuzzy(self.fields.title, token)),
+                    (Occur::Should, fuzzy(self.fields.artist, token)),
+                ]);
+                (Occur::Must, Box::new(fields))
+            })
+            .collect();
+        Ok(Box::new(BooleanQuery::new(clauses)))
+    }
+
+    /// Reindex albums recorded by [mark_pending] from database, and clear the records.
+    ///
+    /// Albums which no longer exist are removed from search index.
+    /// Returns the number of reindexed albums.
+    pub async fn recover(&self, db: &DatabaseConnection) -> anyhow::Result<usize> {
+        let pending = pending_index::Entity::find().all(db).await?;
+        if pending.is_empty() {
+            return Ok(0);
+        }
+
+        let albums_db_id: BTreeSet<_> = pending.iter().map(|p| p.album_db_id).collect();
+        let writer = self.writer().await;
+        for &album_db_id in albums_db_id.iter() {
+            let query = TermQuery::new(
+                Term::from_field_i64(self.fields.album_db_id, album_db_id as i64),
+                Default::default(),
+            );
+            writer.delete_query(Box::new(query))?;
+
+            let Some(album) = album::Entity::find_by_id(album_db_id).one(db).await? else {
+                continue;
+            };
+            writer.add_album_info(&album)?;
+            let discs = disc::Entity::find()
+                .filter(disc::Column::AlbumDbId.eq(album_db_id))
+                .all(db)
+                .await?;
+            for disc in discs.iter() {
+                writer.add_disc_info(disc)?;
+            }
+            let tracks = track::Entity::find()
+                .filter(track::Column::AlbumDbId.eq(album_db_id))
+                .all(db)
+                .await?;
+            for track in tracks.iter() {
+                writer.add_track_info(track)?;
+            }
+        }
+        writer.commit().await?;
+
+        // records added during recovery are kept for their own mutations
+        pending_index::Entity::delete_many()
+            .filter(pending_index::Column::Id.is_in(pending.iter().map(|p| p.id)))
+            .exec(db)
+            .await?;
+        Ok(albums_db_id.len())
+    }
+
+    /// Rebuild the whole search index from database.
+    ///
+    /// Records created by [mark_pending] before rebuilding are cleared.
+    pub async fn rebuild(&self, db: &DatabaseConnection) -> anyhow::Result<()> {
+        // 0. pending records before rebuilding are covered by the rebuild
+        let pending: Vec<i32> = pending_index::Entity::find()
+            .select_only()
+            .column(pending_index::Column::Id)
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        // 1. clear search index
+        let writer = self.writer().await;
+        writer.delete_all()?;
+
+        // 2. insert albums
+        let albums: Vec<(i32, String, String)> = album::Entity::find()
+            .select_only()
+            .column(album::Column::Id)
+            .column(album::Column::Title)
+            .column(album::Column::Artist)
+            .into_tuple()
+            .all(db)
+            .await?;
+        for (album_db_id, title, artist) in albums {
+            writer.add_document(self.build_track_document(
+                &title,
+                &artist,
+                album_db_id as i64,
+                None,
+                None,
+            ))?;
+        }
+
+        // 3. insert discs
+        let discs: Vec<(i32, i32, Option<String>, Option<String>)> = disc::Entity::find()
+            .select_only()
+            .column(disc::Column::AlbumDbId)
+            .column(disc::Column::Id)
+            .column(disc::Column::Title)
+            .column(disc::Column::Artist)
+            .into_tuple()
+            .all(db)
+            .await?;
+        for (album_db_id, disc_db_id, title, artist) in discs {
+            writer.add_document(self.build_track_document(
+                &title.unwrap_or_default(),
+                &artist.unwrap_or_default(),
+                album_db_id as i64,
+                Some(disc_db_id as i64),
+                None,
+            ))?;
+        }
+
+        // 4. insert tracks
+        let tracks: Vec<(i32, i32, i32, String, String)> = track::Entity::find()
+            .select_only()
+            .column(track::Column::AlbumDbId)
+            .column(track::Column::DiscDbId)
+            .column(track::Column::Id)
+            .column(track::Column::Title)
+            .column(track::Column::Artist)
+            .into_tuple()
+            .all(db)
+            .await?;
+        for (album_db_id, disc_db_id, track_db_id, title, artist) in tracks {
+            writer.add_document(self.build_track_document(
+                &title,
+                &artist,
+                album_db_id as i64,
+                Some(disc_db_id as i64),
+                Some(track_db_id as i64),
+            ))?;
+        }
+
+        // 5. commit
+        writer.commit().await?;
+        pending_index::Entity::delete_many()
+            .filter(pending_index::Column::Id.is_in(pending))
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Remove a record created by [mark_pending] once search index is committed.
+    ///
+    /// Records deferred by [SearchWriter::defer_pending] and committed since are removed as well.
+    pub async fn clear_pending<C>(&self, db: &C, pending_id: i32) -> Result<(), DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let mut pending = std::mem::take(&mut self.deferred.lock().unwrap().committed);
+        pending.push(pending_id);
+        pending_index::Entity::delete_many()
+            .filter(pending_index::Column::Id.is_in(pending))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
     pub fn deserialize_document(&self, doc: TantivyDocument) -> (i64, Option<i64>, Option<i64>) {
         let album_db_id = doc.get_first(self.fields.album_db_id).unwrap();
         let disc_db_id = doc.get_first(self.fields.disc_db_id).unwrap();
@@ -119,6 +366,23 @@ impl RepositorySearchManager {
     }
 }
 
+/// Record that search index of an album is about to change, and returns id of the record.
+///
+/// Call this in the same transaction which modifies the album, and call
+/// [RepositorySearchManager::clear_pending] after
+/// search index is committed. Records left by a crash between the two commits are replayed by
+/// [RepositorySearchManager::recover].
+pub async fn mark_pending<C>(db: &C, album_db_id: i32) -> Result<i32, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let pending = pending_index::ActiveModel {
+        album_db_id: ActiveValue::set(album_db_id),
+        ..Default::default()
+    };
+    Ok(pending.insert(db).await?.id)
+}
+
 pub struct SearchWriter<'a> {
     lock: RwLockReadGuard<'a, IndexWriter>,
     manager: &'a RepositorySearchManager,
@@ -167,6 +431,15 @@ impl SearchWriter<'_> {
         self.lock.delete_all_documents()
     }
 
+    /// Keep a record created by [mark_pending] until changes written by this writer are committed,
+    /// possibly by another writer.
+    ///
+    /// The record is removed by the next [RepositorySearchManager::clear_pending] after commit.
+    pub fn defer_pending(&self, pending_id: i32) {
+        let mut deferred = self.manager.deferred.lock().unwrap();
+        deferred.uncommitted.push(pending_id);
+    }
+
     pub async fn commit(self) -> tantivy::Result<()> {
         let manager = self.manager;
         drop(self);
@@ -174,6 +447,12 @@ impl SearchWriter<'_> {
     }
 }
 
+#[derive(Default)]
+struct DeferredRecords {
+    uncommitted: Vec<i32>,
+    committed: Vec<i32>,
+}
+
 pub struct SearchFields {
     pub album_db_id: Field,
     pub disc_db_id: Field,
@@ -192,19 +471,25 @@ impl SearchFields {
 
         let title = schema_builder.add_text_field(
             "title",
-            TextOptions::default().set_indexing_options(
-                TextFieldIndexing::default()
-                    .set_tokenizer("lang_ja")
-                    .set_index_option(IndexRecordOption::WithFreqsAndPositions),
-            ),
+            TextOptions::default()
+                .set_indexing_options(
+                    TextFieldIndexing::default()
+                        .set_tokenizer("lang_ja")
+                        .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+                )
+                // stored for snippets of search results
+                .set_stored(),
         );
         let artist = schema_builder.add_text_field(
             "artist",
-            TextOptions::default().set_indexing_options(
-                TextFieldIndexing::default()
-                    .set_tokenizer("lang_ja")
-                    .set_index_option(IndexRecordOption::WithFreqsAndPositions),
-            ),
+            TextOptions::default()
+                .set_indexing_options(
+                    TextFieldIndexing::default()
+                        .set_tokenizer("lang_ja")
+                        .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+                )
+                // stored for snippets of search results
+                .set_stored(),
         );
 
         (
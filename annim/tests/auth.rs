@@ -0,0 +1,141 @@
+use annim::auth::{on_connection_init, AuthError, AuthToken};
+use annim::route::graphql_handler;
+use async_graphql::Request as GraphQLRequest;
+use axum::body::{to_bytes, Body};
+use axum::http::{
+    header::{self, AUTHORIZATION},
+    HeaderMap, HeaderValue, Request,
+};
+use axum::routing::post;
+use axum::Router;
+use common::execute;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+mod common;
+
+fn headers(value: &[u8]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(AUTHORIZATION, HeaderValue::from_bytes(value).unwrap());
+    headers
+}
+
+#[test]
+fn test_parse_token_from_headers() {
+    assert!(AuthToken::from_headers(&HeaderMap::new())
+        .unwrap()
+        .is_none());
+
+    let token = AuthToken::from_headers(&headers(b"114514"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        token.is_valid(),
+        AuthToken::new("114514".to_string()).is_valid()
+    );
+    let token = AuthToken::from_headers(&headers(b"Bearer 114514"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        token.is_valid(),
+        AuthToken::new("114514".to_string()).is_valid()
+    );
+
+    // malformed tokens
+    assert_eq!(
+        AuthToken::from_headers(&headers(b"")).err(),
+        Some(AuthError::Missing)
+    );
+    assert_eq!(
+        AuthToken::from_headers(&headers(b"Bearer ")).err(),
+        Some(AuthError::Missing)
+    );
+    for value in [
+        b"114 514".as_slice(),
+        b"114514\t",
+        "トークン".as_bytes(),
+        "a".repeat(2048).as_bytes(),
+    ] {
+        assert!(matches!(
+            AuthToken::from_headers(&headers(value)),
+            Err(AuthError::Malformed(_))
+        ));
+    }
+}
+
+#[tokio::test]
+async fn test_parse_token_on_connection_init() {
+    assert!(on_connection_init(json!({ "token": "114514" }))
+        .await
+        .is_ok());
+    assert!(on_connection_init(json!({ "token": "Bearer 114514" }))
+        .await
+        .is_ok());
+
+    let error = on_connection_init(json!({})).await.unwrap_err();
+    assert_eq!(error.message, "Token is required");
+    let error = on_connection_init(json!({ "token": "" }))
+        .await
+        .unwrap_err();
+    assert_eq!(error.message, "Token is required");
+
+    for token in ["114 514", "114514\n", "トークン"] {
+        let error = on_connection_init(json!({ "token": token }))
+            .await
+            .unwrap_err();
+        assert!(
+            error.message.starts_with("Malformed token"),
+            "{}",
+            error.message
+        );
+    }
+}
+
+/// Post `query` to [graphql_handler] with `authorization` header.
+async fn post_query(app: &Router, authorization: &[u8], query: &str) -> Value {
+    let request = Request::builder()
+        .method("POST")
+        .uri("/")
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(
+            AUTHORIZATION,
+            HeaderValue::from_bytes(authorization).unwrap(),
+        )
+        .body(Body::from(json!({ "query": query }).to_string()))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn test_reject_malformed_token_over_http() -> anyhow::Result<()> {
+    let (schema, _directory) = common::schema().await?;
+    let app = Router::new()
+        .route("/", post(graphql_handler))
+        .with_state(schema.clone());
+    let mutation = r#"mutation {
+        addAlbum(input: { title: "Rejected", artist: "Artist", year: 2024, discs: [] }) { id }
+    }"#;
+
+    for value in [b"114 514".as_slice(), "トークン".as_bytes()] {
+        let response = post_query(&app, value, mutation).await;
+        let message = response["errors"][0]["message"].as_str().unwrap();
+        assert!(message.starts_with("Malformed token"), "{message}");
+        assert!(response.get("data").map_or(true, Value::is_null));
+    }
+
+    // mutation is not executed
+    let request =
+        GraphQLRequest::new(r#"query { albums(by: { recentlyCreated: 10 }) { nodes { id } } }"#);
+    let albums = execute(&schema, request).await;
+    assert_eq!(albums["albums"]["nodes"], json!([]));
+
+    // well-formed token is accepted
+    let token = std::env::var("ANNIM_AUTH_TOKEN").unwrap_or_else(|_| "114514".to_string());
+    let response = post_query(&app, format!("Bearer {token}").as_bytes(), mutation).await;
+    assert!(response.get("errors").is_none(), "{response}");
+    assert!(response["data"]["addAlbum"]["id"].is_string());
+
+    Ok(())
+}
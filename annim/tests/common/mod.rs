@@ -0,0 +1,96 @@
+//! Shared fixture of annim tests.
+//!
+//! Tests run against an in-memory SQLite database by default.
+//! Set `ANNIM_TEST_DATABASE_URL` to run them against another database, e.g. PostgreSQL.
+//! All tables in that database are dropped before testing.
+#![allow(dead_code)]
+
+use annim::{
+    auth::AuthToken,
+    graphql::{build_schema, MetadataSchema, QueryLimits},
+    migrator::Migrator,
+    search::{RepositorySearchManager, TokenizerConfig},
+};
+use async_graphql::{Request, Response, Variables};
+use sea_orm::{Database, DatabaseConnection};
+use sea_orm_migration::MigratorTrait;
+use serde_json::{json, Value};
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Connect to a fresh database with all migrations applied.
+pub async fn database() -> anyhow::Result<DatabaseConnection> {
+    let database_url =
+        std::env::var("ANNIM_TEST_DATABASE_URL").unwrap_or_else(|_| "sqlite::memory:".to_string());
+    let database = Database::connect(database_url).await?;
+    Migrator::fresh(&database).await?;
+    Ok(database)
+}
+
+/// Open search index in `directory`.
+pub fn searcher(directory: &Path) -> anyhow::Result<RepositorySearchManager> {
+    Ok(RepositorySearchManager::open_or_create(
+        directory,
+        &TokenizerConfig::default(),
+    )?)
+}
+
+/// Build schema with `database`, search index in `directory` and default query limits.
+pub fn schema_with(
+    database: DatabaseConnection,
+    directory: &Path,
+) -> anyhow::Result<MetadataSchema> {
+    Ok(build_schema(
+        database,
+        searcher(directory)?,
+        QueryLimits::default(),
+    ))
+}
+
+/// Build schema with a fresh database.
+///
+/// Search index is stored in the returned directory, which must be kept until the test ends.
+pub async fn schema() -> anyhow::Result<(MetadataSchema, TempDir)> {
+    let search_directory = tempfile::tempdir()?;
+    let schema = schema_with(database().await?, search_directory.path())?;
+    Ok((schema, search_directory))
+}
+
+/// Execute `request` with auth token from `ANNIM_AUTH_TOKEN`.
+pub async fn execute_raw(schema: &MetadataSchema, request: Request) -> Response {
+    let token = std::env::var("ANNIM_AUTH_TOKEN").unwrap_or_else(|_| "114514".to_string());
+    schema.execute(request.data(AuthToken::new(token))).await
+}
+
+/// Execute `request` and returns its data, panics if any error occurred.
+pub async fn execute(schema: &MetadataSchema, request: Request) -> Value {
+    let response = execute_raw(schema, request).await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    response.data.into_json().unwrap()
+}
+
+/// Add an album with `title`, which has one disc with `tracks` if `tracks` is not empty.
+///
+/// Returns `id`, `albumId` and ids of discs and tracks of the added album.
+pub async fn add_album(schema: &MetadataSchema, title: &str, tracks: &[&str]) -> Value {
+    let discs = if tracks.is_empty() {
+        json!([])
+    } else {
+        let tracks: Vec<_> = tracks
+            .iter()
+            .map(|track| json!({ "title": track, "artist": "Artist", "type": "NORMAL" }))
+            .collect();
+        json!([{ "tracks": tracks }])
+    };
+    let request = Request::new(
+        r#"mutation($title: String!, $discs: [CreateAlbumDiscInput!]!) {
+            addAlbum(input: { title: $title, artist: "Artist", year: 2024, discs: $discs }) {
+                id albumId discs { id tracks { id } }
+            }
+        }"#,
+    )
+    .variables(Variables::from_json(
+        json!({ "title": title, "discs": discs }),
+    ));
+    execute(schema, request).await["addAlbum"].clone()
+}
@@ -0,0 +1,86 @@
+use async_graphql::{Request, Variables};
+use common::{execute, execute_raw};
+use serde_json::{json, Value};
+
+mod common;
+
+fn set_cover(id: Value, url: Option<&str>) -> Request {
+    Request::new(
+        r#"mutation($id: MetadataIDInput!, $url: String) {
+            setCover(id: $id, url: $url) { coverUrl discs { coverUrl } }
+        }"#,
+    )
+    .variables(Variables::from_json(json!({ "id": id, "url": url })))
+}
+
+#[tokio::test]
+async fn test_set_cover() -> anyhow::Result<()> {
+    let (schema, _search_directory) = common::schema().await?;
+
+    let request = Request::new(
+        r#"mutation {
+            addAlbum(input: {
+                title: "Title",
+                artist: "Artist",
+                year: 2024,
+                discs: [{ tracks: [{ title: "Track", artist: "Artist", type: NORMAL }] }]
+            }) { id coverUrl discs { id coverUrl tracks { id } } }
+        }"#,
+    );
+    let album = execute(&schema, request).await["addAlbum"].clone();
+    // covers are optional
+    assert_eq!(album["coverUrl"], Value::Null);
+    assert_eq!(album["discs"][0]["coverUrl"], Value::Null);
+
+    let album_id = album["id"].clone();
+    let disc_id = album["discs"][0]["id"].clone();
+    let track_id = album["discs"][0]["tracks"][0]["id"].clone();
+
+    let result = execute(
+        &schema,
+        set_cover(json!({ "album": album_id }), Some("covers/album.jpg")),
+    )
+    .await;
+    assert_eq!(
+        result["setCover"],
+        json!({ "coverUrl": "covers/album.jpg", "discs": [{ "coverUrl": null }] })
+    );
+
+    let result = execute(
+        &schema,
+        set_cover(
+            json!({ "disc": disc_id }),
+            Some("https://example.com/disc.jpg"),
+        ),
+    )
+    .await;
+    assert_eq!(
+        result["setCover"],
+        json!({
+            "coverUrl": "covers/album.jpg",
+            "discs": [{ "coverUrl": "https://example.com/disc.jpg" }]
+        })
+    );
+
+    // empty url removes the cover
+    let result = execute(&schema, set_cover(json!({ "album": album_id }), Some(""))).await;
+    assert_eq!(result["setCover"]["coverUrl"], Value::Null);
+    let result = execute(&schema, set_cover(json!({ "disc": disc_id }), None)).await;
+    assert_eq!(result["setCover"]["discs"][0]["coverUrl"], Value::Null);
+
+    let response = execute_raw(
+        &schema,
+        set_cover(json!({ "track": track_id }), Some("covers/track.jpg")),
+    )
+    .await;
+    assert!(!response.errors.is_empty());
+
+    let result = execute(
+        &schema,
+        set_cover(json!({ "album": "0" }), Some("cover.jpg")),
+    )
+    .await;
+    assert_eq!(result["setCover"], Value::Null);
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use annim::graphql::MetadataSchema;
+use async_graphql::{Request, Variables};
+use common::{add_album, execute};
+use serde_json::{json, Value};
+
+mod common;
+
+async fn delete_album(schema: &MetadataSchema, id: &str) -> Value {
+    let request = Request::new(
+        r#"mutation($id: ID!) {
+            deleteAlbum(id: $id) { title }
+        }"#,
+    )
+    .variables(Variables::from_json(json!({ "id": id })));
+    execute(schema, request).await["deleteAlbum"].clone()
+}
+
+async fn search_tracks(schema: &MetadataSchema, keyword: &str) -> Vec<String> {
+    let request = Request::new(
+        r#"query($keyword: String!) {
+            tracks(keyword: $keyword) { album { title } }
+        }"#,
+    )
+    .variables(Variables::from_json(json!({ "keyword": keyword })));
+    execute(schema, request).await["tracks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|track| track["album"]["title"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_delete_album() -> anyhow::Result<()> {
+    let (schema, _search_directory) = common::schema().await?;
+
+    let deleted = add_album(&schema, "Deleted", &["Melody"]).await;
+    let deleted = deleted["id"].as_str().unwrap();
+    add_album(&schema, "Kept", &["Melody"]).await;
+
+    let mut titles = search_tracks(&schema, "Melody").await;
+    titles.sort();
+    assert_eq!(titles, ["Deleted", "Kept"]);
+
+    let album = delete_album(&schema, deleted).await;
+    assert_eq!(album["title"], "Deleted");
+    assert_eq!(search_tracks(&schema, "Melody").await, ["Kept"]);
+
+    // deleting again returns null
+    assert_eq!(delete_album(&schema, deleted).await, Value::Null);
+    Ok(())
+}
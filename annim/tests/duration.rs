@@ -0,0 +1,74 @@
+use async_graphql::{Request, Variables};
+use common::execute;
+use serde_json::{json, Value};
+
+mod common;
+
+fn update_duration(id: &Value, duration: Value) -> Request {
+    Request::new(
+        r#"mutation($input: UpdateTrackInfoInput!) {
+            updateTrackInfo(input: $input) { title duration }
+        }"#,
+    )
+    .variables(Variables::from_json(json!({
+        "input": { "id": id, "duration": duration }
+    })))
+}
+
+#[tokio::test]
+async fn test_track_duration() -> anyhow::Result<()> {
+    let (schema, _search_directory) = common::schema().await?;
+
+    let request = Request::new(
+        r#"mutation {
+            addAlbum(input: {
+                title: "Title",
+                artist: "Artist",
+                year: 2024,
+                discs: [{
+                    tracks: [
+                        { title: "Track 1", artist: "Artist", type: NORMAL, duration: 245013 },
+                        { title: "Track 2", artist: "Artist", type: NORMAL },
+                    ]
+                }]
+            }) { discs { tracks { id duration } } }
+        }"#,
+    );
+    let result = execute(&schema, request).await;
+    let tracks = &result["addAlbum"]["discs"][0]["tracks"];
+    assert_eq!(tracks[0]["duration"], 245013);
+    assert_eq!(tracks[1]["duration"], Value::Null);
+
+    let result = execute(
+        &schema,
+        update_duration(&tracks[1]["id"], json!({ "value": 180000 })),
+    )
+    .await;
+    assert_eq!(
+        result["updateTrackInfo"],
+        json!({ "title": "Track 2", "duration": 180000 })
+    );
+
+    // duration is kept if not given
+    let request = Request::new(
+        r#"mutation($id: ID!) {
+            updateTrackInfo(input: { id: $id, title: "Renamed" }) { title duration }
+        }"#,
+    )
+    .variables(Variables::from_json(json!({ "id": tracks[1]["id"] })));
+    let result = execute(&schema, request).await;
+    assert_eq!(
+        result["updateTrackInfo"],
+        json!({ "title": "Renamed", "duration": 180000 })
+    );
+
+    // and removed by null value
+    let result = execute(
+        &schema,
+        update_duration(&tracks[0]["id"], json!({ "value": null })),
+    )
+    .await;
+    assert_eq!(result["updateTrackInfo"]["duration"], Value::Null);
+
+    Ok(())
+}
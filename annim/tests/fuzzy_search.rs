@@ -0,0 +1,68 @@
+use annim::graphql::MetadataSchema;
+use async_graphql::{Request, Variables};
+use common::execute;
+use serde_json::json;
+
+mod common;
+
+async fn add_album(schema: &MetadataSchema) {
+    let request = Request::new(
+        r#"mutation {
+            addAlbum(input: {
+                title: "Harbour Lights",
+                artist: "Kalafina",
+                year: 2024,
+                discs: [{
+                    tracks: [
+                        { title: "Lumina", artist: "Kalafina", type: NORMAL },
+                        { title: "Sunrise", artist: "Someone", type: NORMAL },
+                    ]
+                }]
+            }) { id }
+        }"#,
+    );
+    execute(schema, request).await;
+}
+
+/// Search tracks, and returns their identifiers.
+async fn search(schema: &MetadataSchema, keyword: &str, fuzzy: Option<u8>) -> Vec<String> {
+    let request = Request::new(
+        r#"query($keyword: String!, $fuzzy: Int) {
+            tracks(keyword: $keyword, fuzzy: $fuzzy) { identifier }
+        }"#,
+    )
+    .variables(Variables::from_json(
+        json!({ "keyword": keyword, "fuzzy": fuzzy }),
+    ));
+    execute(schema, request).await["tracks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|track| track["identifier"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_fuzzy_search_tracks() -> anyhow::Result<()> {
+    let (schema, _search_directory) = common::schema().await?;
+
+    add_album(&schema).await;
+
+    let exact = search(&schema, "Kalafina", None).await;
+    assert_eq!(exact.len(), 1);
+    assert!(exact[0].ends_with("/1/1"));
+
+    // one character typo
+    assert!(search(&schema, "Kalafima", None).await.is_empty());
+    assert_eq!(search(&schema, "Kalafima", Some(1)).await, exact);
+    // edit distance is capped instead of rejected
+    assert_eq!(search(&schema, "Kalafima", Some(100)).await, exact);
+    // every word must match
+    assert_eq!(search(&schema, "Lumima Kalafima", Some(1)).await, exact);
+    assert!(search(&schema, "Sunrise Kalafima", Some(1))
+        .await
+        .is_empty());
+
+    assert!(search(&schema, "Qwzxvbj", Some(2)).await.is_empty());
+    Ok(())
+}
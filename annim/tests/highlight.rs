@@ -0,0 +1,65 @@
+use annim::graphql::MetadataSchema;
+use async_graphql::{Request, Variables};
+use common::execute;
+use serde_json::{json, Value};
+
+mod common;
+
+async fn add_album(schema: &MetadataSchema) {
+    let request = Request::new(
+        r#"mutation {
+            addAlbum(input: {
+                title: "Harbour Lights",
+                artist: "Kalafina",
+                year: 2024,
+                discs: [{
+                    tracks: [
+                        { title: "Lumina", artist: "Kalafina", type: NORMAL },
+                        { title: "Sunrise", artist: "Kalafina", type: NORMAL },
+                    ]
+                }]
+            }) { id }
+        }"#,
+    );
+    execute(schema, request).await;
+}
+
+async fn search(schema: &MetadataSchema, keyword: &str, highlight: bool) -> Value {
+    let request = Request::new(
+        r#"query($keyword: String!, $highlight: Boolean!) {
+            tracks(keyword: $keyword, highlight: $highlight) { identifier titleSnippet artistSnippet }
+        }"#,
+    )
+    .variables(Variables::from_json(
+        json!({ "keyword": keyword, "highlight": highlight }),
+    ));
+    execute(schema, request).await["tracks"].clone()
+}
+
+#[tokio::test]
+async fn test_highlight_tracks() -> anyhow::Result<()> {
+    let (schema, _search_directory) = common::schema().await?;
+
+    add_album(&schema).await;
+
+    let tracks = search(&schema, "Lumina", true).await;
+    assert_eq!(tracks.as_array().unwrap().len(), 1);
+    assert_eq!(tracks[0]["titleSnippet"], "<b>Lumina</b>");
+    // artist does not match
+    assert_eq!(tracks[0]["artistSnippet"], Value::Null);
+
+    let tracks = search(&schema, "Kalafina", true).await;
+    assert_eq!(tracks.as_array().unwrap().len(), 2);
+    for track in tracks.as_array().unwrap() {
+        assert_eq!(track["titleSnippet"], Value::Null);
+        assert!(track["artistSnippet"]
+            .as_str()
+            .unwrap()
+            .contains("<b>Kalafina</b>"));
+    }
+
+    // snippets are only generated on request
+    let tracks = search(&schema, "Lumina", false).await;
+    assert_eq!(tracks[0]["titleSnippet"], Value::Null);
+    Ok(())
+}
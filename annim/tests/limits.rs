@@ -0,0 +1,86 @@
+use annim::graphql::{build_schema, MetadataSchema, QueryLimits};
+use async_graphql::Request;
+use sea_orm::DatabaseConnection;
+
+mod common;
+
+/// Build a schema without database, queries in this test are rejected or answered before reaching it.
+fn schema(directory: &tempfile::TempDir, limits: QueryLimits) -> anyhow::Result<MetadataSchema> {
+    Ok(build_schema(
+        DatabaseConnection::Disconnected,
+        common::searcher(directory.path())?,
+        limits,
+    ))
+}
+
+#[tokio::test]
+async fn test_query_limits() -> anyhow::Result<()> {
+    let search_directory = tempfile::tempdir()?;
+    let schema = schema(
+        &search_directory,
+        QueryLimits {
+            depth: 3,
+            complexity: 5,
+            ..Default::default()
+        },
+    )?;
+
+    let response = schema
+        .execute(Request::new(
+            r#"query { tracks(keyword: "Title") { identifier score } }"#,
+        ))
+        .await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+    // too deep
+    let response = schema
+        .execute(Request::new(
+            r#"query {
+                tracks(keyword: "Title") { album { discs { title } } }
+            }"#,
+        ))
+        .await;
+    assert_eq!(response.errors.len(), 1);
+    assert!(response.errors[0].message.contains("too deep"));
+
+    // too complex
+    let response = schema
+        .execute(Request::new(
+            r#"query {
+                a: tracks(keyword: "A") { identifier score }
+                b: tracks(keyword: "B") { identifier score }
+            }"#,
+        ))
+        .await;
+    assert_eq!(response.errors.len(), 1);
+    assert!(response.errors[0].message.contains("too complex"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_page_size_limit() -> anyhow::Result<()> {
+    let search_directory = tempfile::tempdir()?;
+    let schema = schema(&search_directory, QueryLimits::default())?;
+
+    let response = schema
+        .execute(Request::new(
+            r#"query { tracks(keyword: "Title", count: 100) { identifier } }"#,
+        ))
+        .await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+    for query in [
+        r#"query { tracks(keyword: "Title", count: 101) { identifier } }"#,
+        r#"query { albums(by: { keyword: "Title" }, first: 1000) { nodes { title } } }"#,
+        r#"query { albums(by: { recentlyCreated: 1000 }) { nodes { title } } }"#,
+    ] {
+        let response = schema.execute(Request::new(query)).await;
+        assert_eq!(response.errors.len(), 1, "{query}");
+        assert!(response.errors[0]
+            .message
+            .contains("must not be larger than 100"));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use annim::{
+    entities::{album, disc},
+    migrator::Migrator,
+};
+use sea_orm::{ConnectionTrait, EntityTrait};
+use sea_orm_migration::MigratorTrait;
+
+mod common;
+
+#[tokio::test]
+async fn test_add_cover_url_to_existing_data() -> anyhow::Result<()> {
+    let database = common::database().await?;
+
+    // insert data before cover columns exist, which are added by the last but one migration
+    Migrator::down(&database, Some(2)).await?;
+    database
+        .execute_unprepared(
+            r#"INSERT INTO album (album_id, title, artist, release_year)
+                VALUES ('2b5e2f3a-6c1d-4e8f-9a2b-3c4d5e6f7a01', 'Title', 'Artist', 2024)"#,
+        )
+        .await?;
+    database
+        .execute_unprepared(r#"INSERT INTO disc (album_db_id, "index") SELECT id, 0 FROM album"#)
+        .await?;
+
+    Migrator::up(&database, None).await?;
+    let album = album::Entity::find().one(&database).await?.unwrap();
+    assert_eq!(album.title, "Title");
+    assert_eq!(album.cover_url, None);
+    let disc = disc::Entity::find().one(&database).await?.unwrap();
+    assert_eq!(disc.album_db_id, album.id);
+    assert_eq!(disc.cover_url, None);
+
+    // migration can be reverted without losing other data
+    Migrator::down(&database, Some(2)).await?;
+    let titles: Vec<String> = database
+        .query_all(sea_orm::Statement::from_string(
+            database.get_database_backend(),
+            "SELECT title FROM album",
+        ))
+        .await?
+        .into_iter()
+        .map(|row| row.try_get("", "title"))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(titles, vec!["Title".to_string()]);
+
+    Migrator::up(&database, None).await?;
+    Ok(())
+}
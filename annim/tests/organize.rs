@@ -0,0 +1,53 @@
+use async_graphql::{Request, Variables};
+use common::{add_album, execute, execute_raw};
+use serde_json::json;
+
+mod common;
+
+fn update_level(id: &str, level: &str, allow_downgrade: Option<bool>) -> Request {
+    let mut input = json!({ "id": id, "level": level });
+    if let Some(allow_downgrade) = allow_downgrade {
+        input["allowDowngrade"] = json!(allow_downgrade);
+    }
+    Request::new(
+        r#"mutation($input: UpdateAlbumOrganizeLevelInput!) {
+            updateOrganizeLevel(input: $input) { level }
+        }"#,
+    )
+    .variables(Variables::from_json(json!({ "input": input })))
+}
+
+#[tokio::test]
+async fn test_update_organize_level() -> anyhow::Result<()> {
+    let (schema, _search_directory) = common::schema().await?;
+
+    let album = add_album(&schema, "Title", &["Track"]).await;
+    let album = album["id"].as_str().unwrap();
+
+    // upgrades are allowed
+    for level in ["PARTIAL", "REVIEWED"] {
+        let result = execute(&schema, update_level(album, level, None)).await;
+        assert_eq!(result["updateOrganizeLevel"]["level"], level);
+    }
+
+    // downgrades are rejected by default
+    let response = execute_raw(&schema, update_level(album, "PARTIAL", None)).await;
+    assert!(!response.errors.is_empty());
+    let response = execute_raw(&schema, update_level(album, "PARTIAL", Some(false))).await;
+    assert!(!response.errors.is_empty());
+
+    // level is not changed by rejected downgrades
+    let request = Request::new(
+        r#"query {
+            albums(by: { organizeLevel: REVIEWED }) { nodes { id } }
+        }"#,
+    );
+    let result = execute(&schema, request).await;
+    assert_eq!(result["albums"]["nodes"][0]["id"], album);
+
+    // downgrades are allowed with allowDowngrade
+    let result = execute(&schema, update_level(album, "PARTIAL", Some(true))).await;
+    assert_eq!(result["updateOrganizeLevel"]["level"], "PARTIAL");
+
+    Ok(())
+}
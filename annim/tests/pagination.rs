@@ -0,0 +1,72 @@
+use annim::graphql::MetadataSchema;
+use async_graphql::{Request, Variables};
+use common::{add_album, execute, execute_raw};
+use serde_json::{json, Value};
+
+mod common;
+
+fn albums_request(variables: Value) -> Request {
+    Request::new(
+        r#"query($after: String, $before: String, $first: Int, $last: Int) {
+            albums(by: { organizeLevel: INITIAL }, after: $after, before: $before, first: $first, last: $last) {
+                edges { cursor node { title } }
+                pageInfo { hasPreviousPage hasNextPage }
+            }
+        }"#,
+    )
+    .variables(Variables::from_json(variables))
+}
+
+/// Returns titles, cursors and page info of a page.
+async fn albums(schema: &MetadataSchema, variables: Value) -> (Vec<String>, Vec<String>, Value) {
+    let page = execute(schema, albums_request(variables)).await["albums"].clone();
+    let edges = page["edges"].as_array().unwrap();
+    let titles = edges
+        .iter()
+        .map(|edge| edge["node"]["title"].as_str().unwrap().to_string())
+        .collect();
+    let cursors = edges
+        .iter()
+        .map(|edge| edge["cursor"].as_str().unwrap().to_string())
+        .collect();
+    (titles, cursors, page["pageInfo"].clone())
+}
+
+#[tokio::test]
+async fn test_albums_pagination() -> anyhow::Result<()> {
+    let (schema, _search_directory) = common::schema().await?;
+
+    for title in ["A", "B", "C", "D", "E"] {
+        add_album(&schema, title, &[]).await;
+    }
+
+    // forward
+    let (titles, cursors, page_info) = albums(&schema, json!({ "first": 2 })).await;
+    assert_eq!(titles, ["A", "B"]);
+    assert_eq!(page_info["hasNextPage"], true);
+    let (titles, cursors, page_info) =
+        albums(&schema, json!({ "first": 2, "after": cursors[1] })).await;
+    assert_eq!(titles, ["C", "D"]);
+    assert_eq!(page_info["hasNextPage"], true);
+    let (titles, _, page_info) = albums(&schema, json!({ "first": 2, "after": cursors[1] })).await;
+    assert_eq!(titles, ["E"]);
+    assert_eq!(page_info["hasNextPage"], false);
+
+    // backward
+    let (titles, cursors, page_info) = albums(&schema, json!({ "last": 2 })).await;
+    assert_eq!(titles, ["D", "E"]);
+    assert_eq!(page_info["hasPreviousPage"], true);
+    assert_eq!(page_info["hasNextPage"], false);
+    let (titles, cursors, page_info) =
+        albums(&schema, json!({ "last": 2, "before": cursors[0] })).await;
+    assert_eq!(titles, ["B", "C"]);
+    assert_eq!(page_info["hasPreviousPage"], true);
+    let (titles, _, page_info) = albums(&schema, json!({ "last": 2, "before": cursors[0] })).await;
+    assert_eq!(titles, ["A"]);
+    assert_eq!(page_info["hasPreviousPage"], false);
+
+    // first + last is ambiguous
+    let response = execute_raw(&schema, albums_request(json!({ "first": 2, "last": 2 }))).await;
+    assert_eq!(response.errors.len(), 1);
+    Ok(())
+}
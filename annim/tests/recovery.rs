@@ -0,0 +1,134 @@
+use annim::{
+    entities::{album, pending_index},
+    search::{mark_pending, RepositorySearchManager},
+};
+use async_graphql::Request;
+use common::execute;
+use sea_orm::{DatabaseConnection, EntityTrait, PaginatorTrait, TransactionTrait};
+use tantivy::collector::Count;
+
+mod common;
+
+/// Count committed documents matching `keyword`.
+fn count(searcher: &RepositorySearchManager, keyword: &str) -> anyhow::Result<usize> {
+    let query = searcher.query_parser().parse_query(keyword)?;
+    Ok(searcher.searcher().search(&query, &Count)?)
+}
+
+async fn count_pending(database: &DatabaseConnection) -> anyhow::Result<u64> {
+    Ok(pending_index::Entity::find().count(database).await?)
+}
+
+#[tokio::test]
+async fn test_recover_search_index() -> anyhow::Result<()> {
+    let database = common::database().await?;
+    let search_directory = tempfile::tempdir()?;
+    let schema = common::schema_with(database.clone(), search_directory.path())?;
+
+    // database is committed, but search index is not
+    let request = Request::new(
+        r#"mutation {
+            addAlbum(input: {
+                title: "Lost Album",
+                artist: "Artist",
+                year: 2024,
+                discs: [{ tracks: [{ title: "Lost Track", artist: "Artist", type: NORMAL }] }]
+            }, commit: false) { id }
+        }"#,
+    );
+    execute(&schema, request).await;
+    assert_eq!(count_pending(&database).await?, 1);
+
+    // crash, uncommitted documents are lost
+    drop(schema);
+    let searcher = common::searcher(search_directory.path())?;
+    assert_eq!(count(&searcher, "Lost")?, 0);
+
+    assert_eq!(searcher.recover(&database).await?, 1);
+    assert_eq!(count(&searcher, "Lost")?, 2);
+    assert_eq!(count_pending(&database).await?, 0);
+
+    // album deleted from database, but not from search index
+    let album = album::Entity::find().one(&database).await?.unwrap();
+    let txn = database.begin().await?;
+    mark_pending(&txn, album.id).await?;
+    album::Entity::delete_by_id(album.id).exec(&txn).await?;
+    txn.commit().await?;
+    drop(searcher);
+
+    let searcher = common::searcher(search_directory.path())?;
+    assert_eq!(count(&searcher, "Lost")?, 2);
+    assert_eq!(searcher.recover(&database).await?, 1);
+    assert_eq!(count(&searcher, "Lost")?, 0);
+
+    // recovery converges
+    assert_eq!(searcher.recover(&database).await?, 0);
+    assert_eq!(count(&searcher, "Lost")?, 0);
+    assert_eq!(count_pending(&database).await?, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rebuild_recreated_index() -> anyhow::Result<()> {
+    let database = common::database().await?;
+    let schema_directory = tempfile::tempdir()?;
+    let schema = common::schema_with(database.clone(), schema_directory.path())?;
+    common::add_album(&schema, "Indexed Album", &["Indexed Track"]).await;
+
+    // index created by an older schema
+    let search_directory = tempfile::tempdir()?;
+    let mut builder = tantivy::schema::Schema::builder();
+    builder.add_text_field("title", tantivy::schema::TEXT);
+    tantivy::Index::create_in_dir(search_directory.path(), builder.build())?;
+
+    let searcher = common::searcher(search_directory.path())?;
+    assert!(searcher.needs_rebuild());
+    assert_eq!(count(&searcher, "Indexed")?, 0);
+    searcher.rebuild(&database).await?;
+    assert_eq!(count(&searcher, "Indexed")?, 2);
+    drop(searcher);
+
+    let searcher = common::searcher(search_directory.path())?;
+    assert!(!searcher.needs_rebuild());
+    assert_eq!(count(&searcher, "Indexed")?, 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_clear_deferred_pending() -> anyhow::Result<()> {
+    let database = common::database().await?;
+    let search_directory = tempfile::tempdir()?;
+    let schema = common::schema_with(database.clone(), search_directory.path())?;
+    let add_uncommitted = |title: &str| {
+        Request::new(format!(
+            r#"mutation {{
+                addAlbum(input: {{ title: "{title}", artist: "Artist", year: 2024, discs: [] }}, commit: false) {{ id }}
+            }}"#
+        ))
+    };
+
+    // cleared when committed by a later mutation
+    execute(&schema, add_uncommitted("Deferred")).await;
+    assert_eq!(count_pending(&database).await?, 1);
+    common::add_album(&schema, "Committed", &[]).await;
+    assert_eq!(count_pending(&database).await?, 0);
+
+    // cleared when deleted
+    let data = execute(&schema, add_uncommitted("Deleted")).await;
+    assert_eq!(count_pending(&database).await?, 1);
+    let id = data["addAlbum"]["id"].as_str().unwrap();
+    let request = Request::new(format!(
+        r#"mutation {{ deleteAlbum(id: "{id}") {{ id }} }}"#
+    ));
+    execute(&schema, request).await;
+    assert_eq!(count_pending(&database).await?, 0);
+
+    drop(schema);
+    let searcher = common::searcher(search_directory.path())?;
+    assert_eq!(count(&searcher, "Deferred")?, 1);
+    assert_eq!(count(&searcher, "Deleted")?, 0);
+
+    Ok(())
+}
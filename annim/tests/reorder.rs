@@ -0,0 +1,145 @@
+use annim::graphql::MetadataSchema;
+use async_graphql::{Request, Variables};
+use common::{execute, execute_raw};
+use serde_json::{json, Value};
+
+mod common;
+
+/// Add an album, and returns it with ids of discs and tracks.
+async fn add_album(schema: &MetadataSchema) -> Value {
+    let request = Request::new(
+        r#"mutation {
+            addAlbum(input: {
+                title: "Title",
+                artist: "Artist",
+                year: 2024,
+                discs: [
+                    {
+                        title: "Disc 1",
+                        tracks: [
+                            { title: "Track 1", artist: "Artist", type: NORMAL },
+                            { title: "Track 2", artist: "Artist", type: NORMAL },
+                            { title: "Track 3", artist: "Artist", type: NORMAL },
+                        ]
+                    },
+                    { title: "Disc 2", tracks: [] },
+                ]
+            }) { id albumId discs { id tracks { id } } }
+        }"#,
+    );
+    execute(schema, request).await["addAlbum"].clone()
+}
+
+fn ids(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["id"].as_str().unwrap().to_string())
+        .collect()
+}
+
+fn reorder_discs(album_id: &str, order: &[String]) -> Request {
+    Request::new(
+        r#"mutation($albumId: ID!, $order: [ID!]!) {
+            reorderDiscs(albumId: $albumId, order: $order) { discs { index title } }
+        }"#,
+    )
+    .variables(Variables::from_json(
+        json!({ "albumId": album_id, "order": order }),
+    ))
+}
+
+fn reorder_tracks(disc_id: &str, order: &[String]) -> Request {
+    Request::new(
+        r#"mutation($discId: ID!, $order: [ID!]!) {
+            reorderTracks(discId: $discId, order: $order) { tracks { index title } }
+        }"#,
+    )
+    .variables(Variables::from_json(
+        json!({ "discId": disc_id, "order": order }),
+    ))
+}
+
+#[tokio::test]
+async fn test_reorder_discs_and_tracks() -> anyhow::Result<()> {
+    let (schema, _search_directory) = common::schema().await?;
+
+    let album = add_album(&schema).await;
+    let album_id = album["id"].as_str().unwrap();
+    let discs = ids(&album["discs"]);
+    let tracks = ids(&album["discs"][0]["tracks"]);
+
+    let order = vec![discs[1].clone(), discs[0].clone()];
+    let result = execute(&schema, reorder_discs(album_id, &order)).await;
+    assert_eq!(
+        result["reorderDiscs"]["discs"],
+        json!([
+            { "index": 0, "title": "Disc 2" },
+            { "index": 1, "title": "Disc 1" },
+        ])
+    );
+
+    let order = vec![tracks[2].clone(), tracks[0].clone(), tracks[1].clone()];
+    let result = execute(&schema, reorder_tracks(&discs[0], &order)).await;
+    assert_eq!(
+        result["reorderTracks"]["tracks"],
+        json!([
+            { "index": 0, "title": "Track 3" },
+            { "index": 1, "title": "Track 1" },
+            { "index": 2, "title": "Track 2" },
+        ])
+    );
+
+    // id set must match existing tracks exactly
+    for order in [
+        vec![tracks[0].clone(), tracks[1].clone()],
+        vec![tracks[0].clone(), tracks[1].clone(), tracks[1].clone()],
+        vec![tracks[0].clone(), tracks[1].clone(), discs[1].clone()],
+        vec![
+            tracks[0].clone(),
+            tracks[1].clone(),
+            tracks[2].clone(),
+            discs[1].clone(),
+        ],
+    ] {
+        let response = execute_raw(&schema, reorder_tracks(&discs[0], &order)).await;
+        assert!(!response.errors.is_empty());
+    }
+    let response = execute_raw(&schema, reorder_discs(album_id, &discs[..1])).await;
+    assert!(!response.errors.is_empty());
+
+    // rejected reorders do not change anything
+    let request = Request::new(
+        r#"query($albumId: UUID!) {
+            album(albumId: $albumId) { discs { title tracks { title } } }
+        }"#,
+    )
+    .variables(Variables::from_json(json!({ "albumId": album["albumId"] })));
+    let result = execute(&schema, request).await;
+    assert_eq!(
+        result["album"]["discs"],
+        json!([
+            { "title": "Disc 2", "tracks": [] },
+            {
+                "title": "Disc 1",
+                "tracks": [{ "title": "Track 3" }, { "title": "Track 1" }, { "title": "Track 2" }]
+            },
+        ])
+    );
+
+    // only albums with INITIAL organize level can be reordered
+    let request = Request::new(
+        r#"mutation($id: ID!) {
+            updateOrganizeLevel(input: { id: $id, level: PARTIAL }) { level }
+        }"#,
+    )
+    .variables(Variables::from_json(json!({ "id": album_id })));
+    execute(&schema, request).await;
+    let response = execute_raw(&schema, reorder_discs(album_id, &discs)).await;
+    assert!(!response.errors.is_empty());
+    let response = execute_raw(&schema, reorder_tracks(&discs[0], &tracks)).await;
+    assert!(!response.errors.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use annim::graphql::MetadataSchema;
+use async_graphql::{Request, Variables};
+use common::{add_album, execute};
+use serde_json::{json, Value};
+
+mod common;
+
+async fn search(schema: &MetadataSchema, keyword: &str, after: Option<&str>) -> Value {
+    let request = Request::new(
+        r#"query($keyword: String!, $after: String) {
+            albums(by: { keyword: $keyword }, after: $after, first: 1) {
+                edges { cursor score node { title } }
+                pageInfo { hasNextPage }
+            }
+        }"#,
+    )
+    .variables(Variables::from_json(
+        json!({ "keyword": keyword, "after": after }),
+    ));
+    execute(schema, request).await["albums"].clone()
+}
+
+#[tokio::test]
+async fn test_search_albums_by_keyword() -> anyhow::Result<()> {
+    let (schema, _search_directory) = common::schema().await?;
+
+    add_album(&schema, "Blue Sky", &[]).await;
+    add_album(&schema, "Unrelated", &[]).await;
+    add_album(&schema, "Sky Sky Sky", &[]).await;
+
+    // album with more matching terms ranks first
+    let page = search(&schema, "Sky", None).await;
+    assert_eq!(page["edges"][0]["node"]["title"], "Sky Sky Sky");
+    assert_eq!(page["pageInfo"]["hasNextPage"], true);
+
+    let first_score = page["edges"][0]["score"].as_f64().unwrap();
+
+    let cursor = page["edges"][0]["cursor"].as_str().unwrap();
+    let page = search(&schema, "Sky", Some(cursor)).await;
+    assert_eq!(page["edges"][0]["node"]["title"], "Blue Sky");
+    assert!(page["edges"][0]["score"].as_f64().unwrap() < first_score);
+    assert_eq!(page["pageInfo"]["hasNextPage"], false);
+
+    let cursor = page["edges"][0]["cursor"].as_str().unwrap();
+    let page = search(&schema, "Sky", Some(cursor)).await;
+    assert_eq!(page["edges"], json!([]));
+
+    let page = search(&schema, "Nothing", None).await;
+    assert_eq!(page["edges"], json!([]));
+
+    // albums with the same score are paginated by their ids
+    add_album(&schema, "Sea One", &[]).await;
+    add_album(&schema, "Sea Two", &[]).await;
+    let page = search(&schema, "Sea", None).await;
+    assert_eq!(page["edges"][0]["node"]["title"], "Sea One");
+    let score = page["edges"][0]["score"].clone();
+    let cursor = page["edges"][0]["cursor"].as_str().unwrap();
+    let page = search(&schema, "Sea", Some(cursor)).await;
+    assert_eq!(page["edges"][0]["node"]["title"], "Sea Two");
+    assert_eq!(page["edges"][0]["score"], score);
+    assert_eq!(page["pageInfo"]["hasNextPage"], false);
+
+    // score is only available for keyword search
+    let request = Request::new(
+        r#"query {
+            albums(by: { recentlyCreated: 10 }) { edges { score } }
+        }"#,
+    );
+    let edges = execute(&schema, request).await["albums"]["edges"].clone();
+    let edges = edges.as_array().unwrap();
+    assert_eq!(edges.len(), 5);
+    assert!(edges.iter().all(|edge| edge["score"].is_null()));
+    Ok(())
+}
@@ -0,0 +1,336 @@
+use annim::graphql::MetadataSchema;
+use async_graphql::{Request, Variables};
+use common::{add_album, execute, execute_raw};
+use serde_json::json;
+
+mod common;
+
+async fn add_tag(schema: &MetadataSchema, name: &str) -> String {
+    let request = Request::new(
+        r#"mutation($name: String!) {
+            addTag(name: $name, type: SERIES) { id }
+        }"#,
+    )
+    .variables(Variables::from_json(json!({ "name": name })));
+    execute(schema, request).await["addTag"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+async fn album_tags(schema: &MetadataSchema, album_id: &str) -> Vec<String> {
+    let request = Request::new(
+        r#"query($albumId: UUID!) {
+            album(albumId: $albumId) { tags { name } }
+        }"#,
+    )
+    .variables(Variables::from_json(json!({ "albumId": album_id })));
+    let mut tags: Vec<_> = execute(schema, request).await["album"]["tags"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tag| tag["name"].as_str().unwrap().to_string())
+        .collect();
+    tags.sort();
+    tags
+}
+
+#[tokio::test]
+async fn test_add_tags_to_albums() -> anyhow::Result<()> {
+    let (schema, _search_directory) = common::schema().await?;
+
+    let albums = [
+        add_album(&schema, "First", &[]).await,
+        add_album(&schema, "Second", &[]).await,
+        add_album(&schema, "Third", &[]).await,
+    ];
+    let existing = add_tag(&schema, "Existing").await;
+    let series = add_tag(&schema, "X").await;
+
+    // the first album already has both tags
+    let request = Request::new(
+        r#"mutation($id: ID!, $tags: [ID!]!) {
+            updateMetadataTags(input: { album: $id }, tags: $tags) { id }
+        }"#,
+    )
+    .variables(Variables::from_json(
+        json!({ "id": albums[0]["id"], "tags": [existing, series] }),
+    ));
+    execute(&schema, request).await;
+    // the second album has an existing tag
+    let request = Request::new(
+        r#"mutation($id: ID!, $tags: [ID!]!) {
+            updateMetadataTags(input: { album: $id }, tags: $tags) { id }
+        }"#,
+    )
+    .variables(Variables::from_json(
+        json!({ "id": albums[1]["id"], "tags": [existing] }),
+    ));
+    execute(&schema, request).await;
+
+    let request = Request::new(
+        r#"mutation($albums: [ID!]!, $tags: [ID!]!) {
+            addTagsToAlbums(albumIds: $albums, tags: $tags) { id }
+        }"#,
+    )
+    .variables(Variables::from_json(
+        json!({ "albums": albums.iter().map(|album| &album["id"]).collect::<Vec<_>>(), "tags": [series] }),
+    ));
+    let result = execute(&schema, request).await;
+    assert_eq!(result["addTagsToAlbums"].as_array().unwrap().len(), 3);
+
+    assert_eq!(
+        album_tags(&schema, albums[0]["albumId"].as_str().unwrap()).await,
+        ["Existing", "X"]
+    );
+    assert_eq!(
+        album_tags(&schema, albums[1]["albumId"].as_str().unwrap()).await,
+        ["Existing", "X"]
+    );
+    assert_eq!(
+        album_tags(&schema, albums[2]["albumId"].as_str().unwrap()).await,
+        ["X"]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_add_tags_to_albums_invalid_tag() -> anyhow::Result<()> {
+    let (schema, _search_directory) = common::schema().await?;
+
+    let album = add_album(&schema, "Album", &[]).await;
+    let existing = add_tag(&schema, "Existing").await;
+
+    for (tag, message) in [
+        ("not-a-number", "Invalid tag id `not-a-number`"),
+        ("2147483647", "Tag 2147483647 not found"),
+    ] {
+        let request = Request::new(
+            r#"mutation($albums: [ID!]!, $tags: [ID!]!) {
+                addTagsToAlbums(albumIds: $albums, tags: $tags) { id }
+            }"#,
+        )
+        .variables(Variables::from_json(
+            json!({ "albums": [album["id"]], "tags": [existing, tag] }),
+        ));
+        let response = execute_raw(&schema, request).await;
+        assert_eq!(response.errors.len(), 1);
+        assert_eq!(response.errors[0].message, message);
+
+        let request = Request::new(
+            r#"mutation($id: ID!, $tags: [ID!]!) {
+                updateMetadataTags(input: { album: $id }, tags: $tags) { id }
+            }"#,
+        )
+        .variables(Variables::from_json(
+            json!({ "id": album["id"], "tags": [existing, tag] }),
+        ));
+        let response = execute_raw(&schema, request).await;
+        assert_eq!(response.errors.len(), 1);
+        assert_eq!(response.errors[0].message, message);
+    }
+
+    // nothing should be added
+    assert!(album_tags(&schema, album["albumId"].as_str().unwrap())
+        .await
+        .is_empty());
+    Ok(())
+}
+
+async fn track_tags(schema: &MetadataSchema, album_id: &str) -> Vec<Vec<String>> {
+    let request = Request::new(
+        r#"query($albumId: UUID!) {
+            album(albumId: $albumId) { discs { tracks { tags { name } } } }
+        }"#,
+    )
+    .variables(Variables::from_json(json!({ "albumId": album_id })));
+    execute(schema, request).await["album"]["discs"][0]["tracks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|track| {
+            track["tags"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|tag| tag["name"].as_str().unwrap().to_string())
+                .collect()
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_update_metadata_tags_batch() -> anyhow::Result<()> {
+    let (schema, _search_directory) = common::schema().await?;
+
+    let album = add_album(&schema, "Album", &["Track 1", "Track 2"]).await;
+    let album_id = album["albumId"].as_str().unwrap();
+    let tracks: Vec<_> = album["discs"][0]["tracks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|track| track["id"].as_str().unwrap().to_string())
+        .collect();
+    let first = add_tag(&schema, "First").await;
+    let second = add_tag(&schema, "Second").await;
+
+    let request = Request::new(
+        r#"mutation($inputs: [MetadataIDInput!]!, $tags: [ID!]!) {
+            updateMetadataTagsBatch(inputs: $inputs, tags: $tags) { id }
+        }"#,
+    )
+    .variables(Variables::from_json(json!({
+        "inputs": [{ "track": tracks[0] }, { "track": tracks[1] }],
+        "tags": [first, first],
+    })));
+    let result = execute(&schema, request).await;
+    // both tracks belong to the same album
+    assert_eq!(
+        result["updateMetadataTagsBatch"],
+        json!([{ "id": album["id"] }])
+    );
+    assert_eq!(track_tags(&schema, album_id).await, [["First"], ["First"]]);
+
+    // the second target does not exist, so nothing should be changed
+    let request = Request::new(
+        r#"mutation($inputs: [MetadataIDInput!]!, $tags: [ID!]!) {
+            updateMetadataTagsBatch(inputs: $inputs, tags: $tags) { id }
+        }"#,
+    )
+    .variables(Variables::from_json(json!({
+        "inputs": [{ "track": tracks[0] }, { "track": "2147483647" }],
+        "tags": [second],
+    })));
+    let response = execute_raw(&schema, request).await;
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(track_tags(&schema, album_id).await, [["First"], ["First"]]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_add_tag_twice() -> anyhow::Result<()> {
+    let (schema, _search_directory) = common::schema().await?;
+
+    let first = add_tag(&schema, "Duplicated").await;
+    let second = add_tag(&schema, "Duplicated").await;
+    assert_eq!(first, second);
+
+    let request = Request::new(
+        r#"query {
+            tag(tagName: "Duplicated", tagType: SERIES) { id }
+        }"#,
+    );
+    let result = execute(&schema, request).await;
+    assert_eq!(result["tag"].as_array().unwrap().len(), 1);
+
+    // adding an existing tag fails without upsert
+    let request = Request::new(
+        r#"mutation {
+            addTag(name: "Duplicated", type: SERIES, upsert: false) { id }
+        }"#,
+    );
+    let response = execute_raw(&schema, request).await;
+    assert!(!response.errors.is_empty());
+
+    Ok(())
+}
+
+async fn add_tag_relation(schema: &MetadataSchema, tag: &str, parent: &str) {
+    let request = Request::new(
+        r#"mutation($tag: ID!, $parent: ID!) {
+            updateTagRelation(tagId: $tag, parentId: $parent) { id }
+        }"#,
+    )
+    .variables(Variables::from_json(
+        json!({ "tag": tag, "parent": parent }),
+    ));
+    execute(schema, request).await;
+}
+
+/// Returns (name, direction, depth, fromId) of nodes in the tag tree.
+async fn tag_tree(
+    schema: &MetadataSchema,
+    tag: &str,
+    depth: i32,
+) -> Vec<(String, String, i64, String)> {
+    let request = Request::new(
+        r#"query($tag: ID!, $depth: Int) {
+            tagTree(tagId: $tag, depth: $depth) { tag { name } direction depth fromId }
+        }"#,
+    )
+    .variables(Variables::from_json(json!({ "tag": tag, "depth": depth })));
+    execute(schema, request).await["tagTree"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|node| {
+            (
+                node["tag"]["name"].as_str().unwrap().to_string(),
+                node["direction"].as_str().unwrap().to_string(),
+                node["depth"].as_i64().unwrap(),
+                node["fromId"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_tag_tree() -> anyhow::Result<()> {
+    let (schema, _search_directory) = common::schema().await?;
+
+    // parent -> child -> grandchild
+    let parent = add_tag(&schema, "Parent").await;
+    let child = add_tag(&schema, "Child").await;
+    let grandchild = add_tag(&schema, "Grandchild").await;
+    add_tag_relation(&schema, &child, &parent).await;
+    add_tag_relation(&schema, &grandchild, &child).await;
+
+    let node = |name: &str, direction: &str, depth: i64, from: &str| {
+        (
+            name.to_string(),
+            direction.to_string(),
+            depth,
+            from.to_string(),
+        )
+    };
+
+    assert_eq!(
+        tag_tree(&schema, &parent, 1).await,
+        vec![node("Child", "CHILD", 1, &parent)]
+    );
+    assert_eq!(
+        tag_tree(&schema, &parent, 2).await,
+        vec![
+            node("Child", "CHILD", 1, &parent),
+            node("Grandchild", "CHILD", 2, &child),
+        ]
+    );
+    assert_eq!(
+        tag_tree(&schema, &child, 2).await,
+        vec![
+            node("Parent", "PARENT", 1, &child),
+            node("Grandchild", "CHILD", 1, &child),
+        ]
+    );
+    assert_eq!(
+        tag_tree(&schema, &grandchild, 2).await,
+        vec![
+            node("Child", "PARENT", 1, &grandchild),
+            node("Parent", "PARENT", 2, &child),
+        ]
+    );
+
+    // grandchild -> parent makes a cycle, which is walked only once
+    add_tag_relation(&schema, &parent, &grandchild).await;
+    assert_eq!(
+        tag_tree(&schema, &parent, 10).await,
+        vec![
+            node("Grandchild", "PARENT", 1, &parent),
+            node("Child", "PARENT", 2, &grandchild),
+            node("Child", "CHILD", 1, &parent),
+            node("Grandchild", "CHILD", 2, &child),
+        ]
+    );
+
+    Ok(())
+}